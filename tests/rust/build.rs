@@ -0,0 +1,45 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Dependencies whose resolved versions are reported by the /version endpoint
+const REPORTED_DEPENDENCIES: &[&str] = &["tokio", "axum", "sqlx", "redis", "rdkafka", "lettre", "reqwest"];
+
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=BUILD_GIT_COMMIT={}", commit);
+
+    let built_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={}", built_at);
+
+    println!("cargo:rustc-env=BUILD_DEPENDENCIES={}", locked_versions());
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+}
+
+/// `name=version` pairs for the reported dependencies, read from Cargo.lock
+fn locked_versions() -> String {
+    let lock = std::fs::read_to_string("Cargo.lock").unwrap_or_default();
+    let mut versions = Vec::new();
+    let mut name = None;
+
+    for line in lock.lines() {
+        if let Some(value) = line.strip_prefix("name = ") {
+            name = Some(value.trim_matches('"').to_string());
+        } else if let Some(value) = line.strip_prefix("version = ") {
+            if let Some(name) = name.take().filter(|n| REPORTED_DEPENDENCIES.contains(&n.as_str())) {
+                versions.push(format!("{}={}", name, value.trim_matches('"')));
+            }
+        }
+    }
+    versions.join(",")
+}
@@ -0,0 +1,5 @@
+-- Track which encryption key version protects each user's PII columns.
+-- Existing ciphertexts predate versioning and were written under key 1.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS pii_key_version INTEGER NOT NULL DEFAULT 1;
+
+CREATE INDEX IF NOT EXISTS idx_users_pii_key_version ON users (pii_key_version);
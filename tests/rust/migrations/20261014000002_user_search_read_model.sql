@@ -0,0 +1,22 @@
+-- Denormalized read model for user search, maintained by the projection worker
+CREATE TABLE IF NOT EXISTS user_search (
+    user_id         UUID PRIMARY KEY,
+    email           TEXT NOT NULL,
+    username        TEXT NOT NULL,
+    full_name       TEXT NOT NULL DEFAULT '',
+    role            TEXT NOT NULL,
+    status          TEXT NOT NULL,
+    email_verified  BOOLEAN NOT NULL DEFAULT FALSE,
+    last_login      TIMESTAMPTZ,
+    login_count     BIGINT NOT NULL DEFAULT 0,
+    created_at      TIMESTAMPTZ NOT NULL,
+    projected_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    search_document TSVECTOR GENERATED ALWAYS AS (
+        to_tsvector('simple', username || ' ' || email || ' ' || full_name)
+    ) STORED
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_search_document ON user_search USING GIN (search_document);
+CREATE INDEX IF NOT EXISTS idx_user_search_role_status ON user_search (role, status);
+CREATE INDEX IF NOT EXISTS idx_user_search_created_at ON user_search (created_at DESC, user_id);
+CREATE INDEX IF NOT EXISTS idx_user_search_last_login ON user_search (last_login);
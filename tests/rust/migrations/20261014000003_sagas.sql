@@ -0,0 +1,14 @@
+-- Persisted progress of multi-step sagas so they can resume or compensate after restarts
+CREATE TABLE IF NOT EXISTS sagas (
+    id              UUID PRIMARY KEY,
+    name            TEXT NOT NULL,
+    status          TEXT NOT NULL,
+    completed_steps INTEGER NOT NULL DEFAULT 0,
+    context         JSONB NOT NULL DEFAULT '{}',
+    error           TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_sagas_unfinished ON sagas (status)
+    WHERE status IN ('running', 'compensating');
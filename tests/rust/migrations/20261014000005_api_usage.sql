@@ -0,0 +1,9 @@
+-- Metered API calls per subject and billing period, flushed from Redis counters
+CREATE TABLE IF NOT EXISTS api_usage (
+    subject_type TEXT NOT NULL,
+    subject_id   TEXT NOT NULL,
+    period_start DATE NOT NULL,
+    call_count   BIGINT NOT NULL DEFAULT 0,
+    updated_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (subject_type, subject_id, period_start)
+);
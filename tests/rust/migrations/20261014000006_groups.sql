@@ -0,0 +1,23 @@
+-- User groups used for bulk targeting such as notification broadcasts.
+CREATE TABLE IF NOT EXISTS groups (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    created_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_groups_name ON groups (LOWER(name));
+
+CREATE TABLE IF NOT EXISTS group_members (
+    group_id UUID NOT NULL REFERENCES groups (id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    added_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (group_id, user_id)
+);
+
+-- Primary key order serves keyset pagination of a group's members;
+-- this index serves "which groups is this user in".
+CREATE INDEX IF NOT EXISTS idx_group_members_user ON group_members (user_id);
@@ -0,0 +1,14 @@
+-- Pending and completed account deletion requests. Rows outlive the user
+-- they refer to as a record of when erasure happened, so there is no
+-- foreign key to users.
+CREATE TABLE IF NOT EXISTS account_deletions (
+    user_id UUID PRIMARY KEY,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    erase_after TIMESTAMPTZ NOT NULL,
+    previous_status TEXT NOT NULL,
+    cancelled_at TIMESTAMPTZ,
+    erased_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_deletions_due ON account_deletions (erase_after)
+    WHERE cancelled_at IS NULL AND erased_at IS NULL;
@@ -0,0 +1,29 @@
+-- Admin bulk operations over a user selection, with the per-user prior
+-- state needed to undo them. Only the fields an operation can change are
+-- kept so the audit trail holds no decrypted PII.
+CREATE TABLE IF NOT EXISTS bulk_operations (
+    id UUID PRIMARY KEY,
+    action JSONB NOT NULL,
+    filters JSONB NOT NULL,
+    requested_by UUID NOT NULL,
+    status TEXT NOT NULL,
+    total INTEGER NOT NULL DEFAULT 0,
+    processed INTEGER NOT NULL DEFAULT 0,
+    skipped INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    undo_until TIMESTAMPTZ,
+    undone_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS bulk_operation_items (
+    operation_id UUID NOT NULL REFERENCES bulk_operations (id) ON DELETE CASCADE,
+    user_id UUID NOT NULL,
+    previous_role TEXT NOT NULL,
+    previous_status TEXT NOT NULL,
+    previous_deleted_at TIMESTAMPTZ,
+    applied_at TIMESTAMPTZ,
+    skipped_reason TEXT,
+    PRIMARY KEY (operation_id, user_id)
+);
@@ -0,0 +1,23 @@
+-- Editable notification content keyed by template, one column pair per channel.
+CREATE TABLE IF NOT EXISTS notification_templates (
+    key TEXT PRIMARY KEY,
+    notification_type TEXT NOT NULL,
+    email_subject TEXT NOT NULL,
+    email_body TEXT NOT NULL,
+    email_html TEXT,
+    in_app_title TEXT NOT NULL,
+    in_app_body TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO notification_templates
+    (key, notification_type, email_subject, email_body, in_app_title, in_app_body)
+VALUES (
+    'welcome',
+    'welcome',
+    'Welcome, {{first_name}}!',
+    E'Hi {{first_name}},\n\nYour account {{username}} has been created. We''re glad to have you.',
+    'Welcome, {{first_name}}!',
+    'Your account has been created.'
+)
+ON CONFLICT (key) DO NOTHING;
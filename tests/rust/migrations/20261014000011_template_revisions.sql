@@ -0,0 +1,34 @@
+-- Immutable history of template edits. notification_templates keeps the
+-- current content for fast lookup; every change also lands here.
+ALTER TABLE notification_templates ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE notification_templates ADD COLUMN IF NOT EXISTS updated_by UUID;
+
+CREATE TABLE IF NOT EXISTS notification_template_revisions (
+    template_key TEXT NOT NULL REFERENCES notification_templates (key) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    notification_type TEXT NOT NULL,
+    email_subject TEXT NOT NULL,
+    email_body TEXT NOT NULL,
+    email_html TEXT,
+    in_app_title TEXT NOT NULL,
+    in_app_body TEXT NOT NULL,
+    author_id UUID,
+    rolled_back_from INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (template_key, version)
+);
+
+-- Existing templates become their own first revision
+INSERT INTO notification_template_revisions
+    (template_key, version, notification_type, email_subject, email_body, email_html,
+     in_app_title, in_app_body, created_at)
+SELECT key, version, notification_type, email_subject, email_body, email_html,
+       in_app_title, in_app_body, updated_at
+FROM notification_templates
+ON CONFLICT DO NOTHING;
+
+-- Which template revision produced each notification
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS template_key TEXT;
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS template_version INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_template ON notifications (template_key, template_version);
@@ -0,0 +1,18 @@
+-- Per-tenant overrides for notification branding. NULL columns fall back
+-- to the deployment-wide defaults.
+CREATE TABLE IF NOT EXISTS tenant_branding (
+    tenant_id TEXT PRIMARY KEY,
+    sender_address TEXT,
+    sender_name TEXT,
+    logo_url TEXT,
+    primary_color TEXT,
+    accent_color TEXT,
+    footer TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Notifications keep the branded sender and HTML body they were rendered
+-- with so queued deliveries survive restarts unchanged
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS tenant_id TEXT;
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS sender TEXT;
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS html_message TEXT;
@@ -0,0 +1,6 @@
+-- Email engagement recorded from tracking pixels and redirect links.
+-- Timestamps hold the first occurrence; counts include repeats.
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS opened_at TIMESTAMPTZ;
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS clicked_at TIMESTAMPTZ;
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS open_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS click_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,27 @@
+-- Progress of long-running work that graceful shutdown can interrupt, so
+-- it resumes where it stopped instead of starting over or being lost.
+
+-- One row per projection rebuild in progress, removed once it completes
+CREATE TABLE IF NOT EXISTS projection_checkpoints (
+    projection TEXT PRIMARY KEY,
+    resume_after UUID,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS broadcasts (
+    id UUID PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    target JSONB NOT NULL,
+    message JSONB NOT NULL,
+    cursor UUID,
+    targeted INTEGER NOT NULL DEFAULT 0,
+    queued INTEGER NOT NULL DEFAULT 0,
+    skipped INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_broadcasts_interrupted ON broadcasts (created_at)
+    WHERE status = 'interrupted';
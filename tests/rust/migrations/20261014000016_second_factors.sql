@@ -0,0 +1,21 @@
+-- Second factors: WebAuthn authenticators and single-use backup codes.
+CREATE TABLE IF NOT EXISTS authenticators (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    credential_id TEXT NOT NULL UNIQUE,
+    credential JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_authenticators_user ON authenticators (user_id);
+
+-- Only SHA-256 hashes of the codes are kept; they are shown to the user once
+CREATE TABLE IF NOT EXISTS backup_codes (
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    code_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    used_at TIMESTAMPTZ,
+    PRIMARY KEY (user_id, code_hash)
+);
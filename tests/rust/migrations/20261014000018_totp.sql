@@ -0,0 +1,10 @@
+-- TOTP authenticator apps. The shared secret is encrypted with the PII key ring.
+CREATE TABLE IF NOT EXISTS totp_credentials (
+    user_id UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    secret TEXT NOT NULL,
+    -- NULL until the user proves their app produces matching codes
+    confirmed_at TIMESTAMPTZ,
+    -- Time step of the last accepted code, so a code cannot be replayed
+    last_used_step BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,33 @@
+-- Single-use password reset tokens. Only a hash of each token is kept;
+-- the token itself exists in the emailed link alone.
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    token_hash TEXT PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    -- Set when the token is redeemed or superseded by a newer one
+    used_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_password_reset_tokens_user ON password_reset_tokens (user_id) WHERE used_at IS NULL;
+
+INSERT INTO notification_templates
+    (key, notification_type, email_subject, email_body, in_app_title, in_app_body)
+VALUES (
+    'password_reset',
+    'security',
+    'Reset your password',
+    E'Hi {{first_name}},\n\nUse this link within {{expires_minutes}} minutes to choose a new password:\n\n{{reset_link}}\n\nIf you did not ask for a reset, you can ignore this email; your password stays the same.',
+    'Password reset requested',
+    'A link to reset your password was sent to {{email}}.'
+)
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO notification_template_revisions
+    (template_key, version, notification_type, email_subject, email_body, email_html,
+     in_app_title, in_app_body, created_at)
+SELECT key, version, notification_type, email_subject, email_body, email_html,
+       in_app_title, in_app_body, updated_at
+FROM notification_templates
+WHERE key = 'password_reset'
+ON CONFLICT DO NOTHING;
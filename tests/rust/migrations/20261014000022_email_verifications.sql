@@ -0,0 +1,35 @@
+-- Single-use email verification tokens, stored by hash. Each token names
+-- the address it was sent to, so it cannot verify an email changed since.
+CREATE TABLE IF NOT EXISTS email_verification_tokens (
+    token_hash TEXT PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    -- Set when the token is redeemed or superseded by a newer one
+    used_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_verification_tokens_user
+    ON email_verification_tokens (user_id) WHERE used_at IS NULL;
+
+INSERT INTO notification_templates
+    (key, notification_type, email_subject, email_body, in_app_title, in_app_body)
+VALUES (
+    'email_verification',
+    'security',
+    'Confirm your email address',
+    E'Hi {{first_name}},\n\nPlease confirm that {{email}} is your address by opening this link within {{expires_minutes}} minutes:\n\n{{verification_link}}',
+    'Confirm your email address',
+    'A confirmation link was sent to {{email}}.'
+)
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO notification_template_revisions
+    (template_key, version, notification_type, email_subject, email_body, email_html,
+     in_app_title, in_app_body, created_at)
+SELECT key, version, notification_type, email_subject, email_body, email_html,
+       in_app_title, in_app_body, updated_at
+FROM notification_templates
+WHERE key = 'email_verification'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,14 @@
+-- Account events per user: sign-ins, profile and security changes, and
+-- actions administrators took against the account. Details hold field
+-- names and enum values only, never decrypted PII.
+CREATE TABLE IF NOT EXISTS user_audit_events (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    -- Who made the change; NULL for events the system recorded on its own
+    actor_id UUID,
+    action TEXT NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_audit_events_user ON user_audit_events (user_id, created_at DESC);
@@ -0,0 +1,20 @@
+-- Users and addresses that must never be contacted, checked at dispatch
+CREATE TABLE IF NOT EXISTS notification_suppressions (
+    id UUID PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    user_id UUID,
+    email TEXT,
+    reason TEXT NOT NULL,
+    created_by UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (user_id IS NOT NULL OR email IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_suppressions_user
+    ON notification_suppressions (tenant_id, user_id) WHERE user_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_notification_suppressions_email
+    ON notification_suppressions (tenant_id, email) WHERE email IS NOT NULL;
+
+-- Cancelling a broadcast looks up its still-pending notifications
+CREATE INDEX IF NOT EXISTS idx_notifications_pending_broadcast
+    ON notifications ((metadata->>'broadcast_id')) WHERE status = 'pending';
@@ -0,0 +1,5 @@
+-- Lockouts now expire. failed_login_attempts alone no longer locks an
+-- account, so accounts locked under the old fixed threshold are unlocked.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_users_locked_until ON users (locked_until) WHERE locked_until IS NOT NULL;
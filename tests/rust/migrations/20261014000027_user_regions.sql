@@ -0,0 +1,5 @@
+-- Accounts can be stored in another region's database. The users table of
+-- the home database keeps an anchor row for each of them, holding the
+-- region and no personal data, so rows referencing users stay valid.
+-- NULL marks an account stored in this database.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS region TEXT;
@@ -0,0 +1,20 @@
+-- Security-sensitive actions for compliance reviews: who did what to whom,
+-- with the target's role and status before and after. There is no foreign
+-- key to users so entries outlive the accounts they mention. Snapshots hold
+-- ids and enum values only, never decrypted PII.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id UUID PRIMARY KEY,
+    -- NULL for actions the system took on its own or anonymous callers
+    actor_id UUID,
+    action TEXT NOT NULL,
+    target_id UUID,
+    before JSONB,
+    after JSONB,
+    context JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created ON audit_log (created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log (actor_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_target ON audit_log (target_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log (action, created_at DESC);
@@ -0,0 +1,21 @@
+-- Requests to raise a user's role, decided by someone who manages both the
+-- user's role and the role asked for. Decided requests are kept as the
+-- record of why a role was granted or refused.
+CREATE TABLE IF NOT EXISTS role_requests (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    requested_by UUID NOT NULL,
+    current_role TEXT NOT NULL,
+    requested_role TEXT NOT NULL,
+    rationale TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    decided_by UUID,
+    decision_note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    decided_at TIMESTAMPTZ
+);
+
+-- A user has at most one request waiting at a time
+CREATE UNIQUE INDEX IF NOT EXISTS idx_role_requests_pending_user ON role_requests (user_id) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_role_requests_pending ON role_requests (created_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_role_requests_user ON role_requests (user_id, created_at DESC);
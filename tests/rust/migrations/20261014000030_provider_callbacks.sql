@@ -0,0 +1,22 @@
+-- Delivery status callbacks from email providers, stored as received so
+-- integrations can be debugged and callbacks replayed. Callbacks whose
+-- signature did not verify are kept too, flagged, but never applied on
+-- arrival.
+CREATE TABLE IF NOT EXISTS provider_callbacks (
+    id UUID PRIMARY KEY,
+    provider TEXT NOT NULL,
+    headers JSONB NOT NULL DEFAULT '{}',
+    payload TEXT NOT NULL,
+    signature_valid BOOLEAN NOT NULL,
+    notification_id UUID,
+    outcome TEXT NOT NULL DEFAULT 'pending',
+    error TEXT,
+    replay_count INTEGER NOT NULL DEFAULT 0,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    processed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_provider_callbacks_received ON provider_callbacks (provider, received_at DESC);
+CREATE INDEX IF NOT EXISTS idx_provider_callbacks_notification ON provider_callbacks (notification_id);
+CREATE INDEX IF NOT EXISTS idx_provider_callbacks_unverified ON provider_callbacks (received_at DESC)
+    WHERE NOT signature_valid;
@@ -0,0 +1,14 @@
+-- Role permissions and resource rules managed at runtime, on top of the
+-- built-in ones and any policy file. Deny rules win over allow rules.
+CREATE TABLE IF NOT EXISTS authz_policies (
+    id UUID PRIMARY KEY,
+    role TEXT NOT NULL,
+    permission TEXT NOT NULL,
+    resource TEXT,
+    condition TEXT,
+    effect TEXT NOT NULL DEFAULT 'allow',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_authz_policies_rule
+    ON authz_policies (role, permission, COALESCE(resource, ''), COALESCE(condition, ''), effect);
@@ -0,0 +1,45 @@
+-- Exports generated in the background. The file lives in storage under
+-- artifact_key until expires_at, after which it is deleted and the job
+-- is kept as a record with status 'expired'.
+CREATE TABLE IF NOT EXISTS export_jobs (
+    id UUID PRIMARY KEY,
+    requested_by UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    tenant_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    artifact_key TEXT,
+    filename TEXT,
+    content_type TEXT,
+    size_bytes BIGINT,
+    error TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_export_jobs_user ON export_jobs (requested_by, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_export_jobs_queue ON export_jobs (created_at) WHERE status IN ('pending', 'running');
+CREATE INDEX IF NOT EXISTS idx_export_jobs_expiry ON export_jobs (expires_at) WHERE status = 'completed';
+
+INSERT INTO notification_templates
+    (key, notification_type, email_subject, email_body, in_app_title, in_app_body)
+VALUES (
+    'export_ready',
+    'system',
+    'Your export is ready',
+    E'Hi {{first_name}},\n\nThe {{export_kind}} export you asked for is ready. Download it within {{expires_hours}} hours:\n\n{{download_link}}\n\nAfter that the file is deleted and you can request a new export.',
+    'Export ready',
+    'Your {{export_kind}} export is ready to download.'
+)
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO notification_template_revisions
+    (template_key, version, notification_type, email_subject, email_body, email_html,
+     in_app_title, in_app_body, created_at)
+SELECT key, version, notification_type, email_subject, email_body, email_html,
+       in_app_title, in_app_body, updated_at
+FROM notification_templates
+WHERE key = 'export_ready'
+ON CONFLICT DO NOTHING;
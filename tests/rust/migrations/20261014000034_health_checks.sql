@@ -0,0 +1,14 @@
+-- Results of periodic component health probes, from every instance. The
+-- status page derives uptime and incident windows from them; checks older
+-- than the reported history are pruned.
+CREATE TABLE IF NOT EXISTS health_checks (
+    id BIGSERIAL PRIMARY KEY,
+    component TEXT NOT NULL,
+    healthy BOOLEAN NOT NULL,
+    latency_ms BIGINT NOT NULL,
+    error TEXT,
+    checked_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_health_checks_component ON health_checks (component, checked_at);
+CREATE INDEX IF NOT EXISTS idx_health_checks_checked_at ON health_checks (checked_at);
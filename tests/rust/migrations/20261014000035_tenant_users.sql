@@ -0,0 +1,14 @@
+-- The tenant each account was onboarded into, so tenants can be held to a
+-- maximum number of users. Accounts from before tenants existed belong to
+-- the default tenant.
+CREATE TABLE IF NOT EXISTS tenant_users (
+    user_id UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    tenant_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_tenant_users_tenant ON tenant_users (tenant_id);
+
+INSERT INTO tenant_users (user_id, tenant_id)
+SELECT id, 'default' FROM users
+ON CONFLICT (user_id) DO NOTHING;
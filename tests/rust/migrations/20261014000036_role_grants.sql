@@ -0,0 +1,16 @@
+-- Time-boxed role elevations. A grant raises the user's effective role
+-- until it expires; the stored role is left alone, so nothing has to be
+-- restored. Grants are closed by setting ended_at, when they expire or
+-- are revoked early.
+CREATE TABLE IF NOT EXISTS role_grants (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role TEXT NOT NULL,
+    granted_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    ended_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_role_grants_open_user ON role_grants (user_id) WHERE ended_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_role_grants_open_expiry ON role_grants (expires_at) WHERE ended_at IS NULL;
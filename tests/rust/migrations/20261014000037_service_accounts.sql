@@ -0,0 +1,16 @@
+-- Machine identities for internal callers. An account trades its client
+-- secret for short-lived access tokens carrying a subset of its scopes;
+-- only a hash of the secret is kept, and it is shown once, at creation.
+CREATE TABLE IF NOT EXISTS service_accounts (
+    id UUID PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    scopes TEXT[] NOT NULL,
+    secret_hash TEXT NOT NULL UNIQUE,
+    created_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    disabled_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_service_accounts_tenant ON service_accounts (tenant_id, created_at DESC);
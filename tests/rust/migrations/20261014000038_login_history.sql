@@ -0,0 +1,39 @@
+-- Every sign-in attempt, successful or not, with the client it came from.
+-- Attempts on unknown emails have no user_id; rows of a user go with the
+-- account.
+CREATE TABLE IF NOT EXISTS login_history (
+    id UUID PRIMARY KEY,
+    user_id UUID REFERENCES users (id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    succeeded BOOLEAN NOT NULL,
+    failure_reason TEXT,
+    ip_address TEXT,
+    user_agent TEXT NOT NULL DEFAULT '',
+    country TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_history_user ON login_history (user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_login_history_failed_address
+    ON login_history (ip_address, created_at DESC) WHERE NOT succeeded;
+
+INSERT INTO notification_templates
+    (key, notification_type, email_subject, email_body, in_app_title, in_app_body)
+VALUES (
+    'new_sign_in',
+    'security',
+    'New sign-in to your account',
+    E'Hi {{first_name}},\n\nYour account was just signed in to from {{browser}} at {{ip_address}} ({{country}}).\n\nIf this was you, there is nothing to do. If not, reset your password and sign out of all devices.',
+    'New sign-in',
+    'Your account was signed in to from {{browser}} at {{ip_address}}.'
+)
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO notification_template_revisions
+    (template_key, version, notification_type, email_subject, email_body, email_html,
+     in_app_title, in_app_body, created_at)
+SELECT key, version, notification_type, email_subject, email_body, email_html,
+       in_app_title, in_app_body, updated_at
+FROM notification_templates
+WHERE key = 'new_sign_in'
+ON CONFLICT DO NOTHING;
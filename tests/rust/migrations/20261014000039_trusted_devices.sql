@@ -0,0 +1,18 @@
+-- Browsers a user chose to trust after completing two-factor sign-in on
+-- them. The browser keeps a signed cookie naming its row; password
+-- sign-ins presenting it skip the second factor until the trust expires
+-- or is revoked.
+CREATE TABLE IF NOT EXISTS trusted_devices (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    browser TEXT NOT NULL,
+    os TEXT NOT NULL,
+    ip_address TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_trusted_devices_user ON trusted_devices (user_id) WHERE revoked_at IS NULL;
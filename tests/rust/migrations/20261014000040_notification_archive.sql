@@ -0,0 +1,13 @@
+-- Where each archived notification went. Archival writes settled
+-- notifications past their retention to compressed files in storage and
+-- moves their rows here, so a notification can still be found by id or in
+-- its user's history without scanning the files.
+CREATE TABLE IF NOT EXISTS notification_archive (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL,
+    archive_key TEXT NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_archive_user ON notification_archive (user_id, created_at DESC);
@@ -0,0 +1,4 @@
+-- Users are listed newest first, ties broken by id. Cursor pages seek to
+-- the last (created_at, id) seen instead of counting past an offset, and
+-- this index lets them start there.
+CREATE INDEX IF NOT EXISTS idx_users_created_at_id ON users (created_at DESC, id) WHERE region IS NULL;
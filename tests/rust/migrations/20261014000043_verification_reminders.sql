@@ -0,0 +1,50 @@
+-- How far along the email verification reminder campaign each unverified
+-- user is. Rows of users who have since verified are left in place; the
+-- campaign no longer selects them.
+CREATE TABLE IF NOT EXISTS verification_reminders (
+    user_id UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    reminders_sent INT NOT NULL DEFAULT 0,
+    last_sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Serves the campaign's segment of unverified users, oldest sign-ups last
+CREATE INDEX IF NOT EXISTS idx_users_unverified
+    ON users (created_at DESC, id) WHERE email_verified = FALSE AND region IS NULL;
+
+INSERT INTO notification_templates
+    (key, notification_type, email_subject, email_body, in_app_title, in_app_body)
+VALUES
+    (
+        'verification_reminder',
+        'security',
+        'Please confirm your email address',
+        E'Hi {{first_name}},\n\nYou have not confirmed {{email}} yet. Please open this link within {{expires_minutes}} minutes:\n\n{{verification_link}}',
+        'Please confirm your email address',
+        'A new confirmation link was sent to {{email}}.'
+    ),
+    (
+        'verification_reminder_second',
+        'security',
+        'Reminder: confirm your email address',
+        E'Hi {{first_name}},\n\n{{email}} is still unconfirmed. Unconfirmed accounts are suspended on {{suspends_at}}. Confirm it here:\n\n{{verification_link}}',
+        'Confirm your email address',
+        'Your account is suspended on {{suspends_at}} unless {{email}} is confirmed.'
+    ),
+    (
+        'verification_reminder_final',
+        'security',
+        'Last reminder: your account will be suspended',
+        E'Hi {{first_name}},\n\nThis is the last reminder. Unless you confirm {{email}} by {{suspends_at}}, your account will be suspended:\n\n{{verification_link}}',
+        'Your account will be suspended',
+        'Confirm {{email}} by {{suspends_at}} to keep your account.'
+    )
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO notification_template_revisions
+    (template_key, version, notification_type, email_subject, email_body, email_html,
+     in_app_title, in_app_body, created_at)
+SELECT key, version, notification_type, email_subject, email_body, email_html,
+       in_app_title, in_app_body, updated_at
+FROM notification_templates
+WHERE key IN ('verification_reminder', 'verification_reminder_second', 'verification_reminder_final')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,3 @@
+-- Serves the purge of soft-deleted users, longest deleted first
+CREATE INDEX IF NOT EXISTS idx_users_deleted
+    ON users (deleted_at) WHERE deleted_at IS NOT NULL AND region IS NULL;
@@ -0,0 +1,7 @@
+-- Organizations belong to the tenant they were created in, and names only
+-- need to be unique within it. Existing organizations belong to the
+-- default tenant.
+ALTER TABLE organizations ADD COLUMN IF NOT EXISTS tenant_id TEXT NOT NULL DEFAULT 'default';
+
+DROP INDEX IF EXISTS idx_organizations_name;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_organizations_tenant_name ON organizations (tenant_id, LOWER(name));
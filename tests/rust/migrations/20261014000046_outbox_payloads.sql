@@ -0,0 +1,17 @@
+-- Outbox rows carried the whole user, names and addresses included. They
+-- now carry the user's id and fields that identify no one; rows already
+-- written are rewritten the same way. Published rows are deleted once past
+-- their retention.
+UPDATE outbox
+SET payload = jsonb_build_object(
+    'type', payload->>'type',
+    'user_id', aggregate_id,
+    'version', payload->'user'->'version',
+    'role', payload->'user'->'role',
+    'status', payload->'user'->'status',
+    'region', payload->'user'->'region',
+    'at', payload->'user'->'updated_at'
+)
+WHERE payload ? 'user';
+
+CREATE INDEX IF NOT EXISTS idx_outbox_published ON outbox (published_at) WHERE published_at IS NOT NULL;
@@ -0,0 +1,186 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::require_admin;
+use crate::models::{
+    ActivityPage, AppResult, AuditLogAction, AuditLogFilters, AuditLogPage, AuthContext, LoginAttempt,
+    LoginHistoryFilters,
+};
+use crate::services::{
+    AuditService, CacheService, HotKeyReport, LoginAnalytics, LoginThrottlingReport, UserService,
+};
+
+const DEFAULT_HOT_KEYS: usize = 20;
+const MAX_HOT_KEYS: usize = 50;
+const DEFAULT_ACTIVITY_PAGE: i64 = 50;
+const MAX_ACTIVITY_PAGE: i64 = 200;
+const DEFAULT_LOGIN_FAILURE_HOURS: u32 = 24;
+const DEFAULT_LOGIN_FAILURE_SOURCES: usize = 20;
+const MAX_LOGIN_FAILURE_SOURCES: usize = 100;
+
+#[derive(Clone)]
+struct AdminState {
+    users: Arc<UserService>,
+    cache: Arc<CacheService>,
+    audit_log: Arc<AuditService>,
+    login_analytics: Arc<LoginAnalytics>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HotKeysQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ActivityQuery {
+    before: Option<DateTime<Utc>>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginFailuresQuery {
+    hours: Option<u32>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuditLogQuery {
+    actor_id: Option<Uuid>,
+    target_id: Option<Uuid>,
+    action: Option<AuditLogAction>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+/// Operational inspection routes for administrators
+pub fn router(
+    users: Arc<UserService>,
+    cache: Arc<CacheService>,
+    audit_log: Arc<AuditService>,
+    login_analytics: Arc<LoginAnalytics>,
+) -> Router {
+    Router::new()
+        .route("/admin/cache/hot-keys", get(hot_keys))
+        .route("/admin/audit-log", get(audit_log_entries))
+        .route("/admin/security/login-failures", get(login_failures))
+        .route("/admin/users/:id/activity", get(activity_timeline))
+        .route("/admin/users/:id/login-history", get(login_history))
+        .route("/admin/users/:id/unlock", post(unlock_account))
+        .route("/admin/users/:id/restore", post(restore_user))
+        .route("/admin/users/:id", delete(purge_user))
+        .with_state(AdminState {
+            users,
+            cache,
+            audit_log,
+            login_analytics,
+        })
+}
+
+/// Hottest cache keys read through the instance that serves the request
+async fn hot_keys(
+    State(state): State<AdminState>,
+    context: Option<Extension<AuthContext>>,
+    Query(query): Query<HotKeysQuery>,
+) -> AppResult<Json<HotKeyReport>> {
+    require_admin(&state.users, context).await?;
+    let limit = query.limit.unwrap_or(DEFAULT_HOT_KEYS).clamp(1, MAX_HOT_KEYS);
+    Ok(Json(state.cache.hot_keys(limit)))
+}
+
+/// A user's activity feed for support; follow `next_before` for older pages
+async fn activity_timeline(
+    State(state): State<AdminState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ActivityQuery>,
+) -> AppResult<Json<ActivityPage>> {
+    require_admin(&state.users, context).await?;
+    let limit = query.limit.unwrap_or(DEFAULT_ACTIVITY_PAGE).clamp(1, MAX_ACTIVITY_PAGE);
+    Ok(Json(state.users.activity_timeline(id, query.before, limit).await?))
+}
+
+/// A user's sign-in attempts with the address, browser and country each came from, newest first
+async fn login_history(
+    State(state): State<AdminState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+    Query(filters): Query<LoginHistoryFilters>,
+) -> AppResult<Json<Vec<LoginAttempt>>> {
+    require_admin(&state.users, context).await?;
+    Ok(Json(state.users.get_login_history(id, &filters).await?))
+}
+
+/// Security-sensitive actions for compliance reviews, newest first
+async fn audit_log_entries(
+    State(state): State<AdminState>,
+    context: Option<Extension<AuthContext>>,
+    Query(query): Query<AuditLogQuery>,
+) -> AppResult<Json<AuditLogPage>> {
+    require_admin(&state.users, context).await?;
+    let filters = AuditLogFilters {
+        actor_id: query.actor_id,
+        target_id: query.target_id,
+        action: query.action,
+        from: query.from,
+        to: query.to,
+        limit: query.limit,
+        offset: query.offset,
+    };
+    Ok(Json(state.audit_log.search(&filters).await?))
+}
+
+/// Failed sign-ins by client address and targeted account, lockouts per hour and the latest failures
+async fn login_failures(
+    State(state): State<AdminState>,
+    context: Option<Extension<AuthContext>>,
+    Query(query): Query<LoginFailuresQuery>,
+) -> AppResult<Json<LoginThrottlingReport>> {
+    require_admin(&state.users, context).await?;
+    let hours = query.hours.unwrap_or(DEFAULT_LOGIN_FAILURE_HOURS);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_LOGIN_FAILURE_SOURCES)
+        .clamp(1, MAX_LOGIN_FAILURE_SOURCES);
+    Ok(Json(state.login_analytics.report(hours, limit).await?))
+}
+
+/// Lift a failed sign-in lockout before it expires
+async fn unlock_account(
+    State(state): State<AdminState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let admin = require_admin(&state.users, context).await?;
+    state.users.unlock_account(id, &admin).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Undo a soft delete
+async fn restore_user(
+    State(state): State<AdminState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let admin = require_admin(&state.users, context).await?;
+    state.users.restore_user(id, &admin).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Permanently remove a soft-deleted user without waiting for the purge job
+async fn purge_user(
+    State(state): State<AdminState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let admin = require_admin(&state.users, context).await?;
+    state.users.purge_user(id, Some(&admin)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
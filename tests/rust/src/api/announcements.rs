@@ -0,0 +1,69 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::{require_admin, signed_in_user};
+use crate::models::{AppResult, Announcement, AuthContext, CreateAnnouncementRequest, TenantContext};
+use crate::services::{AnnouncementService, UserService};
+
+#[derive(Clone)]
+struct AnnouncementState {
+    announcements: Arc<AnnouncementService>,
+    users: Arc<UserService>,
+}
+
+/// Banners for signed-in users and their management by administrators
+pub fn router(announcements: Arc<AnnouncementService>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/announcements", get(list))
+        .route("/announcements/:id/dismiss", post(dismiss))
+        .route("/admin/announcements", post(publish))
+        .route("/admin/announcements/:id", delete(withdraw))
+        .with_state(AnnouncementState { announcements, users })
+}
+
+/// Live announcements the caller has not dismissed, newest first
+async fn list(
+    State(state): State<AnnouncementState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<Vec<Announcement>>> {
+    let user = signed_in_user(&state.users, context).await?;
+    Ok(Json(state.announcements.for_user(&tenant, &user).await?))
+}
+
+async fn dismiss(
+    State(state): State<AnnouncementState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let user = signed_in_user(&state.users, context).await?;
+    state.announcements.dismiss(&tenant, &user, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn publish(
+    State(state): State<AnnouncementState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Json(request): Json<CreateAnnouncementRequest>,
+) -> AppResult<(StatusCode, Json<Announcement>)> {
+    let admin = require_admin(&state.users, context).await?;
+    let announcement = state.announcements.publish(&tenant, request, &admin).await?;
+    Ok((StatusCode::CREATED, Json(announcement)))
+}
+
+async fn withdraw(
+    State(state): State<AnnouncementState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    require_admin(&state.users, context).await?;
+    state.announcements.withdraw(&tenant, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
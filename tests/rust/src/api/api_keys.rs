@@ -0,0 +1,69 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::require_admin;
+use crate::models::{ApiKey, AppResult, AuthContext, CreateApiKeyRequest, IssuedApiKey, TenantContext};
+use crate::services::{ApiKeyService, UserService};
+
+#[derive(Clone)]
+struct ApiKeyState {
+    api_keys: Arc<ApiKeyService>,
+    users: Arc<UserService>,
+}
+
+/// Management of the tenant's API keys by administrators
+pub fn router(api_keys: Arc<ApiKeyService>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/admin/api-keys", get(list).post(create))
+        .route("/admin/api-keys/:id", delete(revoke))
+        .route("/admin/api-keys/:id/rotate", post(rotate))
+        .with_state(ApiKeyState { api_keys, users })
+}
+
+async fn list(
+    State(state): State<ApiKeyState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<Vec<ApiKey>>> {
+    require_admin(&state.users, context).await?;
+    Ok(Json(state.api_keys.list(&tenant).await?))
+}
+
+/// The response is the only time the key itself is shown
+async fn create(
+    State(state): State<ApiKeyState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> AppResult<(StatusCode, Json<IssuedApiKey>)> {
+    let admin = require_admin(&state.users, context).await?;
+    let issued = state.api_keys.create(&tenant, request, &admin).await?;
+    Ok((StatusCode::CREATED, Json(issued)))
+}
+
+/// Issue a replacement key; the old one keeps working for the rotation grace period
+async fn rotate(
+    State(state): State<ApiKeyState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<(StatusCode, Json<IssuedApiKey>)> {
+    let admin = require_admin(&state.users, context).await?;
+    let issued = state.api_keys.rotate(&tenant, id, &admin).await?;
+    Ok((StatusCode::CREATED, Json(issued)))
+}
+
+async fn revoke(
+    State(state): State<ApiKeyState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let admin = require_admin(&state.users, context).await?;
+    state.api_keys.revoke(&tenant, id, &admin).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
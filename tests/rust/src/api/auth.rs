@@ -0,0 +1,260 @@
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use webauthn_rs::prelude::RequestChallengeResponse;
+
+use super::{client_info, signed_in_user};
+use crate::middleware::{AuthMiddleware, ClientAddress, RefreshRedemption};
+use crate::models::{
+    AppError, AppResult, AuthContext, SecondFactorProof, Session, TenantContext, TokenPair, WebAuthnChallenge,
+};
+use crate::services::{TrustedDevices, UserService};
+
+#[derive(Clone)]
+struct AuthState {
+    auth: Arc<AuthMiddleware>,
+    users: Arc<UserService>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginRequest {
+    email: String,
+    password: String,
+    second_factor: Option<SecondFactorProof>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginAssertionRequest {
+    email: String,
+    password: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExternalSecondFactorRequest {
+    ticket: String,
+    second_factor: SecondFactorProof,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExternalAssertionRequest {
+    ticket: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PasswordResetRequest {
+    email: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletePasswordReset {
+    token: String,
+    new_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyEmailRequest {
+    token: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SignedOut {
+    sessions: usize,
+}
+
+/// Password sign-in, the second factor of OAuth and SAML sign-ins, and
+/// token refresh, where short-lived access tokens are traded in; the
+/// signed-in user's sessions, password resets and email verification
+pub fn router(auth: Arc<AuthMiddleware>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/auth/login", post(login))
+        .route("/auth/login/assertion", post(begin_login_assertion))
+        .route("/auth/login/second-factor", post(finish_external_login))
+        .route("/auth/login/second-factor/assertion", post(begin_external_assertion))
+        .route("/auth/refresh", post(refresh))
+        .route("/auth/sessions", get(list_sessions))
+        .route("/auth/sessions/:id", delete(revoke_session))
+        .route("/auth/sessions/revoke-all", post(sign_out_everywhere))
+        .route("/auth/password-reset", post(request_password_reset))
+        .route("/auth/password-reset/complete", post(complete_password_reset))
+        .route("/auth/verify-email", post(verify_email))
+        .route("/auth/verify-email/resend", post(resend_email_verification))
+        .with_state(AuthState { auth, users })
+}
+
+/// Sign in with an email and password, opening a session for the calling device.
+///
+/// Lockouts, the second factor and the trusted device cookie are all
+/// checked by `UserService::login`, which also keeps the attempt in the
+/// sign-in history.
+async fn login(
+    State(state): State<AuthState>,
+    Extension(tenant): Extension<TenantContext>,
+    headers: HeaderMap,
+    address: Option<Extension<ClientAddress>>,
+    Json(request): Json<LoginRequest>,
+) -> AppResult<Json<TokenPair>> {
+    let client = client_info(&headers, address.as_deref());
+    let user = state
+        .users
+        .login(
+            &tenant,
+            &request.email,
+            &request.password,
+            request.second_factor.as_ref(),
+            TrustedDevices::presented(&headers),
+            &client,
+        )
+        .await?;
+    let session = state.users.start_session(&user, &client).await?;
+    Ok(Json(state.auth.issue(&user, Some(session.id))?))
+}
+
+/// Challenge one of the user's authenticators, whose answer is then sent to `/auth/login` as the second factor
+async fn begin_login_assertion(
+    State(state): State<AuthState>,
+    headers: HeaderMap,
+    address: Option<Extension<ClientAddress>>,
+    Json(request): Json<LoginAssertionRequest>,
+) -> AppResult<Json<WebAuthnChallenge<RequestChallengeResponse>>> {
+    let client = client_info(&headers, address.as_deref());
+    Ok(Json(
+        state
+            .users
+            .begin_login_assertion(&request.email, &request.password, &client)
+            .await?,
+    ))
+}
+
+/// Answer an OAuth or SAML sign-in that asked for the second factor with its ticket
+async fn finish_external_login(
+    State(state): State<AuthState>,
+    Extension(tenant): Extension<TenantContext>,
+    headers: HeaderMap,
+    address: Option<Extension<ClientAddress>>,
+    Json(request): Json<ExternalSecondFactorRequest>,
+) -> AppResult<Json<TokenPair>> {
+    let client = client_info(&headers, address.as_deref());
+    let user = state
+        .users
+        .finish_external_login(&tenant, &request.ticket, &request.second_factor, &client)
+        .await?;
+    let session = state.users.start_session(&user, &client).await?;
+    Ok(Json(state.auth.issue(&user, Some(session.id))?))
+}
+
+/// Challenge an authenticator of the account an external sign-in ticket waits on
+async fn begin_external_assertion(
+    State(state): State<AuthState>,
+    Json(request): Json<ExternalAssertionRequest>,
+) -> AppResult<Json<WebAuthnChallenge<RequestChallengeResponse>>> {
+    Ok(Json(state.users.begin_external_assertion(&request.ticket).await?))
+}
+
+/// Trade a refresh token in for a new pair; the old refresh token stops working.
+///
+/// The user is re-read so role changes, suspensions and revoked sessions
+/// take effect. Presenting a refresh token a second time ends its session.
+async fn refresh(
+    State(state): State<AuthState>,
+    headers: HeaderMap,
+    address: Option<Extension<ClientAddress>>,
+    Json(request): Json<RefreshRequest>,
+) -> AppResult<Json<TokenPair>> {
+    let claims = match state.auth.redeem_refresh(&request.refresh_token).await? {
+        RefreshRedemption::Fresh(claims) => claims,
+        RefreshRedemption::Reused(claims) => {
+            if let Some(session_id) = claims.sid {
+                match state.users.revoke_session(claims.sub, session_id).await {
+                    Ok(()) | Err(AppError::NotFound(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            return Err(AppError::Unauthorized("Refresh token has already been used".to_string()));
+        }
+    };
+    let user = state.users.signed_in_user(claims.sub, claims.sid).await?;
+    if let Some(session_id) = claims.sid {
+        let client = client_info(&headers, address.as_deref());
+        state
+            .users
+            .refresh_session(session_id, client.ip_address.as_deref())
+            .await?;
+    }
+    Ok(Json(state.auth.issue_rotated(&user, &claims)?))
+}
+
+async fn list_sessions(
+    State(state): State<AuthState>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<Vec<Session>>> {
+    let user = signed_in_user(&state.users, context).await?.id;
+    Ok(Json(state.users.list_sessions(user).await?))
+}
+
+async fn revoke_session(
+    State(state): State<AuthState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let user = signed_in_user(&state.users, context).await?.id;
+    state.users.revoke_session(user, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Log out all devices, including the one making the request
+async fn sign_out_everywhere(
+    State(state): State<AuthState>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<SignedOut>> {
+    let user = signed_in_user(&state.users, context).await?.id;
+    let sessions = state.users.sign_out_everywhere(user).await?;
+    Ok(Json(SignedOut { sessions }))
+}
+
+/// Always accepted, so the response does not reveal whether the email is registered
+async fn request_password_reset(
+    State(state): State<AuthState>,
+    Extension(tenant): Extension<TenantContext>,
+    Json(request): Json<PasswordResetRequest>,
+) -> AppResult<StatusCode> {
+    state.users.request_password_reset(&tenant, &request.email).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn complete_password_reset(
+    State(state): State<AuthState>,
+    Json(request): Json<CompletePasswordReset>,
+) -> AppResult<StatusCode> {
+    state
+        .users
+        .complete_password_reset(&request.token, &request.new_password)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn verify_email(
+    State(state): State<AuthState>,
+    Json(request): Json<VerifyEmailRequest>,
+) -> AppResult<StatusCode> {
+    state.users.verify_email(&request.token).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn resend_email_verification(
+    State(state): State<AuthState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<StatusCode> {
+    let user = signed_in_user(&state.users, context).await?.id;
+    state.users.resend_email_verification(&tenant, user).await?;
+    Ok(StatusCode::ACCEPTED)
+}
@@ -0,0 +1,27 @@
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+
+use crate::middleware::CsrfProtection;
+use crate::models::{AppError, AppResult, AuthContext};
+
+/// A CSRF token for the signed-in session, for browser clients to send with mutating requests
+pub fn router(csrf: Arc<CsrfProtection>) -> Router {
+    Router::new().route("/auth/csrf", get(issue_token)).with_state(csrf)
+}
+
+/// Issue a fresh token, returned in the body as well as the cookie and header
+async fn issue_token(
+    State(csrf): State<Arc<CsrfProtection>>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Response> {
+    let Some(session_id) = context.and_then(|Extension(context)| context.session_id) else {
+        return Err(AppError::Unauthorized("A signed-in session is required".to_string()));
+    };
+    let token = csrf.issue(session_id).await?;
+    let mut response = Json(&token).into_response();
+    csrf.attach(&mut response, &token)?;
+    Ok(response)
+}
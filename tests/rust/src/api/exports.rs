@@ -0,0 +1,79 @@
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{middleware, Extension, Json, Router};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::signed_in_user;
+use crate::middleware::require_signed_url;
+use crate::models::{AppResult, AuthContext, CreateExportRequest, ExportJob, ExportJobView, TenantContext};
+use crate::services::{ExportService, UserService};
+use crate::utils::UrlSigner;
+
+#[derive(Clone)]
+struct ExportState {
+    exports: Arc<ExportService>,
+    users: Arc<UserService>,
+}
+
+/// Requesting exports and following their progress, plus the download
+/// route, which takes no session since its signed link is the credential
+pub fn router(exports: Arc<ExportService>, users: Arc<UserService>, signer: Arc<UrlSigner>) -> Router {
+    let download = Router::new()
+        .route("/exports/:id/download", get(download_export))
+        .route_layer(middleware::from_fn_with_state(signer, require_signed_url));
+
+    Router::new()
+        .route("/exports", post(request_export).get(list_exports))
+        .route("/exports/:id", get(get_export))
+        .merge(download)
+        .with_state(ExportState { exports, users })
+}
+
+/// Queue an export; the requester is emailed a download link when it is ready
+async fn request_export(
+    State(state): State<ExportState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Json(request): Json<CreateExportRequest>,
+) -> AppResult<(StatusCode, Json<ExportJob>)> {
+    let user = signed_in_user(&state.users, context).await?;
+    let job = state.exports.request(&tenant, &user, request.kind).await?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn list_exports(
+    State(state): State<ExportState>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<Vec<ExportJobView>>> {
+    let user = signed_in_user(&state.users, context).await?;
+    Ok(Json(state.exports.list(&user).await?))
+}
+
+async fn get_export(
+    State(state): State<ExportState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ExportJobView>> {
+    let user = signed_in_user(&state.users, context).await?;
+    Ok(Json(state.exports.get(&user, id).await?))
+}
+
+async fn download_export(State(state): State<ExportState>, Path(id): Path<Uuid>) -> AppResult<Response> {
+    let (job, content) = state.exports.download(id).await?;
+    let filename = job.filename.unwrap_or_else(|| format!("export_{}", job.id));
+    let content_type = job
+        .content_type
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        content,
+    )
+        .into_response())
+}
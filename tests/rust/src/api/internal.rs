@@ -0,0 +1,62 @@
+use axum::extract::{Path, Query, State};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::{AppError, AppResult, ServiceContext, UserPublic};
+use crate::services::UserService;
+
+#[derive(Debug, Deserialize)]
+struct UserLookup {
+    email: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RevokedSessions {
+    revoked: usize,
+}
+
+/// Routes for internal services signed in with a service token; each checks the scope it needs
+pub fn router(users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/internal/users", get(find_user))
+        .route("/internal/users/:id", get(get_user))
+        .route("/internal/users/:id/sessions/revoke", post(revoke_sessions))
+        .with_state(users)
+}
+
+async fn get_user(
+    State(users): State<Arc<UserService>>,
+    caller: Option<Extension<ServiceContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<UserPublic>> {
+    let caller = require_service(caller)?;
+    Ok(Json(users.user_for_service(&caller, id).await?.into()))
+}
+
+async fn find_user(
+    State(users): State<Arc<UserService>>,
+    caller: Option<Extension<ServiceContext>>,
+    Query(lookup): Query<UserLookup>,
+) -> AppResult<Json<UserPublic>> {
+    let caller = require_service(caller)?;
+    Ok(Json(users.user_by_email_for_service(&caller, &lookup.email).await?.into()))
+}
+
+async fn revoke_sessions(
+    State(users): State<Arc<UserService>>,
+    caller: Option<Extension<ServiceContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<RevokedSessions>> {
+    let caller = require_service(caller)?;
+    let revoked = users.sign_out_everywhere_for_service(&caller, id).await?;
+    Ok(Json(RevokedSessions { revoked }))
+}
+
+fn require_service(caller: Option<Extension<ServiceContext>>) -> AppResult<ServiceContext> {
+    caller
+        .map(|Extension(caller)| caller)
+        .ok_or_else(|| AppError::Unauthorized("A service token is required".to_string()))
+}
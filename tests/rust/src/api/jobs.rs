@@ -0,0 +1,88 @@
+use axum::extract::{Path, Query, State};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+
+use super::require_admin;
+use crate::models::{
+    AppError, AppResult, AuthContext, JobFilters, JobQueue, JobQueueSummary, PauseQueueRequest, QueuedJob,
+};
+use crate::services::{JobQueues, UserService};
+
+#[derive(Clone)]
+struct JobsState {
+    queues: Arc<JobQueues>,
+    users: Arc<UserService>,
+}
+
+/// Looking into the background work queues and managing them without database access
+pub fn router(queues: Arc<JobQueues>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/admin/jobs", get(list_queues))
+        .route("/admin/jobs/:queue", get(list_jobs))
+        .route("/admin/jobs/:queue/pause", post(pause_queue).delete(resume_queue))
+        .route("/admin/jobs/:queue/:id/retry", post(retry_job))
+        .route("/admin/jobs/:queue/:id/cancel", post(cancel_job))
+        .with_state(JobsState { queues, users })
+}
+
+async fn list_queues(
+    State(state): State<JobsState>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<Vec<JobQueueSummary>>> {
+    require_admin(&state.users, context).await?;
+    Ok(Json(state.queues.summaries().await?))
+}
+
+async fn list_jobs(
+    State(state): State<JobsState>,
+    context: Option<Extension<AuthContext>>,
+    Path(queue): Path<String>,
+    Query(filters): Query<JobFilters>,
+) -> AppResult<Json<Vec<QueuedJob>>> {
+    require_admin(&state.users, context).await?;
+    Ok(Json(state.queues.list(job_queue(&queue)?, &filters).await?))
+}
+
+/// Hold the queue's runners on every instance until resumed
+async fn pause_queue(
+    State(state): State<JobsState>,
+    context: Option<Extension<AuthContext>>,
+    Path(queue): Path<String>,
+    Json(request): Json<PauseQueueRequest>,
+) -> AppResult<Json<JobQueueSummary>> {
+    let admin = require_admin(&state.users, context).await?;
+    let reason = request.reason.unwrap_or_else(|| "Paused by an operator".to_string());
+    Ok(Json(state.queues.pause_queue(job_queue(&queue)?, &reason, admin.id).await?))
+}
+
+async fn resume_queue(
+    State(state): State<JobsState>,
+    context: Option<Extension<AuthContext>>,
+    Path(queue): Path<String>,
+) -> AppResult<Json<JobQueueSummary>> {
+    let admin = require_admin(&state.users, context).await?;
+    Ok(Json(state.queues.resume_queue(job_queue(&queue)?, admin.id).await?))
+}
+
+async fn retry_job(
+    State(state): State<JobsState>,
+    context: Option<Extension<AuthContext>>,
+    Path((queue, id)): Path<(String, String)>,
+) -> AppResult<Json<QueuedJob>> {
+    let admin = require_admin(&state.users, context).await?;
+    Ok(Json(state.queues.retry(job_queue(&queue)?, &id, admin.id).await?))
+}
+
+async fn cancel_job(
+    State(state): State<JobsState>,
+    context: Option<Extension<AuthContext>>,
+    Path((queue, id)): Path<(String, String)>,
+) -> AppResult<Json<QueuedJob>> {
+    let admin = require_admin(&state.users, context).await?;
+    Ok(Json(state.queues.cancel(job_queue(&queue)?, &id, admin.id).await?))
+}
+
+fn job_queue(name: &str) -> AppResult<JobQueue> {
+    name.parse().map_err(|e: String| AppError::NotFound(e))
+}
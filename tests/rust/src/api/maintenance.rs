@@ -0,0 +1,138 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::require_admin;
+use crate::models::{AppResult, AuthContext};
+use crate::services::{MaintenanceMode, MaintenanceState, ReadOnlyMode, ReadOnlyState, UserService};
+
+#[derive(Clone)]
+struct MaintenanceRoutes {
+    read_only: Arc<ReadOnlyMode>,
+    maintenance: Arc<MaintenanceMode>,
+    users: Arc<UserService>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnableReadOnly {
+    reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReadOnlyStatus {
+    read_only: bool,
+    #[serde(flatten)]
+    state: Option<ReadOnlyState>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnableMaintenance {
+    reason: Option<String>,
+    /// Whether reads keep being served to everyone; they are unless refused here
+    allow_reads: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct MaintenanceStatus {
+    maintenance: bool,
+    #[serde(flatten)]
+    state: Option<MaintenanceState>,
+}
+
+/// Switching read-only mode on and off for database maintenance, and maintenance mode for everything else
+pub fn router(read_only: Arc<ReadOnlyMode>, maintenance: Arc<MaintenanceMode>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route(
+            "/admin/read-only",
+            get(read_only_status).put(enable_read_only).delete(disable_read_only),
+        )
+        .route(
+            "/admin/maintenance",
+            get(maintenance_status).put(enable_maintenance).delete(disable_maintenance),
+        )
+        .with_state(MaintenanceRoutes {
+            read_only,
+            maintenance,
+            users,
+        })
+}
+
+async fn read_only_status(
+    State(state): State<MaintenanceRoutes>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<ReadOnlyStatus>> {
+    require_admin(&state.users, context).await?;
+    Ok(Json(status(&state.read_only)))
+}
+
+/// Refuse writes on every instance until switched off; reads and sign-in keep working
+async fn enable_read_only(
+    State(state): State<MaintenanceRoutes>,
+    context: Option<Extension<AuthContext>>,
+    Json(request): Json<EnableReadOnly>,
+) -> AppResult<Json<ReadOnlyStatus>> {
+    let admin = require_admin(&state.users, context).await?;
+    let reason = request.reason.unwrap_or_else(|| "Scheduled maintenance".to_string());
+    state.read_only.enable(&reason, Some(admin.id)).await?;
+    Ok(Json(status(&state.read_only)))
+}
+
+async fn disable_read_only(
+    State(state): State<MaintenanceRoutes>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<StatusCode> {
+    let admin = require_admin(&state.users, context).await?;
+    state.read_only.disable(Some(admin.id)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn status(read_only: &ReadOnlyMode) -> ReadOnlyStatus {
+    let state = read_only.status();
+    ReadOnlyStatus {
+        read_only: state.is_some(),
+        state,
+    }
+}
+
+async fn maintenance_status(
+    State(state): State<MaintenanceRoutes>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<MaintenanceStatus>> {
+    require_admin(&state.users, context).await?;
+    Ok(Json(maintenance_of(&state.maintenance)))
+}
+
+/// Turn everyone but admins away on every instance until switched off, or change whether reads are served
+async fn enable_maintenance(
+    State(state): State<MaintenanceRoutes>,
+    context: Option<Extension<AuthContext>>,
+    Json(request): Json<EnableMaintenance>,
+) -> AppResult<Json<MaintenanceStatus>> {
+    let admin = require_admin(&state.users, context).await?;
+    let reason = request.reason.unwrap_or_else(|| "Down for maintenance".to_string());
+    state
+        .maintenance
+        .enable(&reason, request.allow_reads.unwrap_or(true), Some(admin.id))
+        .await?;
+    Ok(Json(maintenance_of(&state.maintenance)))
+}
+
+async fn disable_maintenance(
+    State(state): State<MaintenanceRoutes>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<StatusCode> {
+    let admin = require_admin(&state.users, context).await?;
+    state.maintenance.disable(Some(admin.id)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn maintenance_of(maintenance: &MaintenanceMode) -> MaintenanceStatus {
+    let state = maintenance.status();
+    MaintenanceStatus {
+        maintenance: state.is_some(),
+        state,
+    }
+}
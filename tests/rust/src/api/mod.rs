@@ -0,0 +1,157 @@
+pub mod admin;
+pub mod api_keys;
+pub mod announcements;
+pub mod auth;
+pub mod csrf;
+pub mod exports;
+pub mod internal;
+pub mod jobs;
+pub mod maintenance;
+pub mod notifications;
+pub mod oauth;
+pub mod organizations;
+pub mod saml;
+pub mod passkeys;
+pub mod presence;
+pub mod role_requests;
+pub mod role_grants;
+pub mod service_accounts;
+pub mod status;
+pub mod tracking;
+pub mod trusted_devices;
+pub mod usage;
+pub mod users;
+pub mod version;
+pub mod webhooks;
+
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::middleware::{AuthMiddleware, ClientAddress};
+use crate::models::{AppError, AppResult, AuthContext, ClientInfo, ExternalLogin, TenantContext, TokenPair, User};
+use crate::services::{TrustedDevices, UserService};
+
+/// The client making a request, as recorded on sessions and devices; `address` is as the middleware resolved it
+pub(crate) fn client_info(headers: &HeaderMap, address: Option<&ClientAddress>) -> ClientInfo {
+    let text = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    ClientInfo {
+        user_agent: text(header::USER_AGENT.as_str()).unwrap_or_default().to_string(),
+        ip_address: address.map(|ClientAddress(address)| address.to_string()),
+        country: text("x-client-country").map(|country| country.trim().to_ascii_uppercase()),
+    }
+}
+
+/// The signed-in caller with their effective role; suspended accounts and
+/// revoked sessions are refused like anonymous callers
+pub(crate) async fn signed_in_user(users: &UserService, context: Option<Extension<AuthContext>>) -> AppResult<User> {
+    let Some(Extension(context)) = context else {
+        return Err(AppError::Unauthorized("Authentication required".to_string()));
+    };
+    users.authenticated_user(&context).await
+}
+
+/// Only signed-in users with the admin permission may inspect internals
+#[tracing::instrument(name = "auth", skip_all)]
+pub(crate) async fn require_admin(users: &UserService, context: Option<Extension<AuthContext>>) -> AppResult<User> {
+    let user = signed_in_user(users, context).await?;
+    if !users.policies().allows(&user, "admin", None) {
+        return Err(AppError::Forbidden("Admin permission required".to_string()));
+    }
+    Ok(user)
+}
+
+/// Answer to a sign-in through an external identity provider
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub(crate) enum ExternalSignIn {
+    Tokens(TokenPair),
+    /// Sent back with the second factor to `/auth/login/second-factor`
+    SecondFactorRequired { second_factor_ticket: String },
+}
+
+/// Finish a sign-in an external provider vouched for the way `UserService::external_login`
+/// decides, opening a session unless the account still owes its second factor
+pub(crate) async fn external_sign_in(
+    users: &UserService,
+    auth: &AuthMiddleware,
+    tenant: &TenantContext,
+    user: User,
+    headers: &HeaderMap,
+    address: Option<&ClientAddress>,
+) -> AppResult<ExternalSignIn> {
+    let client = client_info(headers, address);
+    match users
+        .external_login(tenant, user, TrustedDevices::presented(headers), &client)
+        .await?
+    {
+        ExternalLogin::SignedIn(user) => {
+            let session = users.start_session(&user, &client).await?;
+            Ok(ExternalSignIn::Tokens(auth.issue(&user, Some(session.id))?))
+        }
+        ExternalLogin::SecondFactorRequired { ticket } => Ok(ExternalSignIn::SecondFactorRequired {
+            second_factor_ticket: ticket,
+        }),
+    }
+}
+
+impl AppError {
+    /// HTTP status this error maps to
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::PreconditionRequired(_) => StatusCode::PRECONDITION_REQUIRED,
+            AppError::PreconditionFailed { .. } => StatusCode::PRECONDITION_FAILED,
+            AppError::QuotaExceeded { .. } | AppError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::ReadOnly(_) | AppError::Maintenance(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::DeadlineExceeded(_) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::Database(_)
+            | AppError::Cache(_)
+            | AppError::Encryption(_)
+            | AppError::Config(_)
+            | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+
+        // Never leak internal details such as SQL errors to clients
+        let message = if status.is_server_error() && !matches!(self, AppError::ReadOnly(_) | AppError::Maintenance(_)) {
+            tracing::error!("Request failed: {}", self);
+            "Internal server error".to_string()
+        } else {
+            self.to_string()
+        };
+
+        let mut response = (status, Json(json!({ "error": message }))).into_response();
+
+        let retry_after = match &self {
+            AppError::QuotaExceeded { resets_at, .. } => Some((*resets_at - Utc::now()).num_seconds().max(0) as u64),
+            // Rounded up so a client retrying on time finds a token waiting
+            AppError::RateLimited { retry_after } => Some(retry_after.as_millis().div_ceil(1000).max(1) as u64),
+            _ => None,
+        };
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        if let AppError::PreconditionFailed { etag, .. } = &self {
+            if let Ok(etag) = HeaderValue::from_str(etag) {
+                response.headers_mut().insert(header::ETAG, etag);
+            }
+        }
+
+        response
+    }
+}
@@ -0,0 +1,93 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::require_admin;
+use crate::models::{AppResult, AuthContext, CreateSuppressionRequest, NotificationFilters, Suppression, TenantContext};
+use crate::services::{ChannelBudgetUsage, NotificationBudget, NotificationService, UserService};
+
+#[derive(Clone)]
+struct NotificationAdminState {
+    notifications: Arc<NotificationService>,
+    budget: Arc<NotificationBudget>,
+    users: Arc<UserService>,
+}
+
+#[derive(Debug, Serialize)]
+struct CancelResponse {
+    cancelled: u64,
+}
+
+/// Incident controls over outgoing notifications for administrators
+pub fn router(
+    notifications: Arc<NotificationService>,
+    budget: Arc<NotificationBudget>,
+    users: Arc<UserService>,
+) -> Router {
+    Router::new()
+        .route("/admin/notifications/cancel", post(cancel_pending))
+        .route("/admin/notifications/budget", get(budget_usage))
+        .route("/admin/suppressions", get(list_suppressions).post(suppress))
+        .route("/admin/suppressions/:id", delete(unsuppress))
+        .with_state(NotificationAdminState {
+            notifications,
+            budget,
+            users,
+        })
+}
+
+/// Cancel matching notifications that have not been delivered yet
+async fn cancel_pending(
+    State(state): State<NotificationAdminState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Json(filters): Json<NotificationFilters>,
+) -> AppResult<Json<CancelResponse>> {
+    let admin = require_admin(&state.users, context).await?;
+    let cancelled = state.notifications.cancel_pending(&tenant, filters, &admin).await?;
+    Ok(Json(CancelResponse { cancelled }))
+}
+
+/// This month's sends and spend per channel against its budget
+async fn budget_usage(
+    State(state): State<NotificationAdminState>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<Vec<ChannelBudgetUsage>>> {
+    require_admin(&state.users, context).await?;
+    Ok(Json(state.budget.usage().await?))
+}
+
+async fn list_suppressions(
+    State(state): State<NotificationAdminState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<Vec<Suppression>>> {
+    require_admin(&state.users, context).await?;
+    Ok(Json(state.notifications.suppressions(&tenant).await?))
+}
+
+async fn suppress(
+    State(state): State<NotificationAdminState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Json(request): Json<CreateSuppressionRequest>,
+) -> AppResult<(StatusCode, Json<Suppression>)> {
+    let admin = require_admin(&state.users, context).await?;
+    let suppression = state.notifications.suppress(&tenant, request, &admin).await?;
+    Ok((StatusCode::CREATED, Json(suppression)))
+}
+
+async fn unsuppress(
+    State(state): State<NotificationAdminState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    require_admin(&state.users, context).await?;
+    state.notifications.unsuppress(&tenant, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
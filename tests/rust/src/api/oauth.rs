@@ -0,0 +1,66 @@
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::Redirect;
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use super::{external_sign_in, ExternalSignIn};
+use crate::middleware::{AuthMiddleware, ClientAddress};
+use crate::models::{AppError, AppResult, TenantContext};
+use crate::services::{OAuthService, UserService};
+
+#[derive(Clone)]
+struct OAuthState {
+    oauth: Arc<OAuthService>,
+    auth: Arc<AuthMiddleware>,
+    users: Arc<UserService>,
+}
+
+/// Parameters of the provider's redirect back; `error` is set when the user declined
+#[derive(Debug, Deserialize)]
+struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+/// Sign-in through external identity providers, ending in the same tokens as any other sign-in
+pub fn router(oauth: Arc<OAuthService>, auth: Arc<AuthMiddleware>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/auth/oauth/providers", get(providers))
+        .route("/auth/oauth/:provider", get(start))
+        .route("/auth/oauth/:provider/callback", get(callback))
+        .with_state(OAuthState { oauth, auth, users })
+}
+
+async fn providers(State(state): State<OAuthState>) -> Json<Vec<String>> {
+    Json(state.oauth.providers().into_iter().map(str::to_string).collect())
+}
+
+/// Send the browser to the provider's consent page
+async fn start(State(state): State<OAuthState>, Path(provider): Path<String>) -> AppResult<Redirect> {
+    Ok(Redirect::to(&state.oauth.authorization_url(&provider).await?))
+}
+
+async fn callback(
+    State(state): State<OAuthState>,
+    Path(provider): Path<String>,
+    Query(query): Query<CallbackQuery>,
+    Extension(tenant): Extension<TenantContext>,
+    headers: HeaderMap,
+    address: Option<Extension<ClientAddress>>,
+) -> AppResult<Json<ExternalSignIn>> {
+    if let Some(error) = query.error {
+        return Err(AppError::Unauthorized(format!("Sign-in was not completed: {}", error)));
+    }
+    let (Some(code), Some(oauth_state)) = (query.code, query.state) else {
+        return Err(AppError::Validation(vec!["code and state are required".to_string()]));
+    };
+
+    let user = state.oauth.complete(&provider, &code, &oauth_state).await?;
+    Ok(Json(
+        external_sign_in(&state.users, &state.auth, &tenant, user, &headers, address.as_deref()).await?,
+    ))
+}
@@ -0,0 +1,88 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, put};
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::signed_in_user;
+use crate::models::{
+    AppResult, AuthContext, CreateOrganizationRequest, Organization, OrganizationMember, SetOrganizationMemberRequest,
+    TenantContext,
+};
+use crate::services::{OrganizationService, UserService};
+
+#[derive(Clone)]
+struct OrganizationState {
+    organizations: Arc<OrganizationService>,
+    users: Arc<UserService>,
+}
+
+/// The tenant's organizations and their members' roles; org admins manage
+/// their own organization's members here without the global admin permission
+pub fn router(organizations: Arc<OrganizationService>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/admin/organizations", get(list).post(create_organization))
+        .route("/admin/organizations/:id/members", get(members))
+        .route(
+            "/admin/organizations/:id/members/:user_id",
+            put(set_member).delete(remove_member),
+        )
+        .with_state(OrganizationState { organizations, users })
+}
+
+async fn list(
+    State(state): State<OrganizationState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<Vec<Organization>>> {
+    let actor = signed_in_user(&state.users, context).await?;
+    Ok(Json(state.organizations.list(&tenant, &actor).await?))
+}
+
+async fn create_organization(
+    State(state): State<OrganizationState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Json(request): Json<CreateOrganizationRequest>,
+) -> AppResult<(StatusCode, Json<Organization>)> {
+    let actor = signed_in_user(&state.users, context).await?;
+    let organization = state.organizations.create(&tenant, &actor, request).await?;
+    Ok((StatusCode::CREATED, Json(organization)))
+}
+
+async fn members(
+    State(state): State<OrganizationState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Vec<OrganizationMember>>> {
+    let actor = signed_in_user(&state.users, context).await?;
+    Ok(Json(state.organizations.members(&tenant, &actor, id).await?))
+}
+
+async fn set_member(
+    State(state): State<OrganizationState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<SetOrganizationMemberRequest>,
+) -> AppResult<Json<OrganizationMember>> {
+    let actor = signed_in_user(&state.users, context).await?;
+    let member = state
+        .organizations
+        .set_member(&tenant, &actor, id, user_id, request.role)
+        .await?;
+    Ok(Json(member))
+}
+
+async fn remove_member(
+    State(state): State<OrganizationState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<StatusCode> {
+    let actor = signed_in_user(&state.users, context).await?;
+    state.organizations.remove_member(&tenant, &actor, id, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,112 @@
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse,
+};
+
+use super::{client_info, signed_in_user};
+use crate::middleware::{AuthMiddleware, ClientAddress};
+use crate::models::{
+    AppResult, AuthContext, PasskeyCredential, RegisterPasskeyRequest, TenantContext, TokenPair, WebAuthnChallenge,
+};
+use crate::services::{Passkeys, UserService};
+
+#[derive(Clone)]
+struct PasskeyState {
+    passkeys: Arc<Passkeys>,
+    auth: Arc<AuthMiddleware>,
+    users: Arc<UserService>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompleteRegistration {
+    ceremony_id: Uuid,
+    credential: RegisterPublicKeyCredential,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompleteSignIn {
+    ceremony_id: Uuid,
+    credential: PublicKeyCredential,
+}
+
+/// Passwordless sign-in with passkeys, ending in the same tokens as any
+/// other sign-in, and management of the signed-in user's passkeys
+pub fn router(passkeys: Arc<Passkeys>, auth: Arc<AuthMiddleware>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/auth/passkeys", get(list_passkeys).post(begin_registration))
+        .route("/auth/passkeys/complete", post(complete_registration))
+        .route("/auth/passkeys/:id", delete(remove_passkey))
+        .route("/auth/passkeys/sign-in", post(begin_sign_in))
+        .route("/auth/passkeys/sign-in/complete", post(complete_sign_in))
+        .with_state(PasskeyState { passkeys, auth, users })
+}
+
+async fn list_passkeys(
+    State(state): State<PasskeyState>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<Vec<PasskeyCredential>>> {
+    let user = signed_in_user(&state.users, context).await?;
+    Ok(Json(state.passkeys.list(user.id).await?))
+}
+
+async fn begin_registration(
+    State(state): State<PasskeyState>,
+    context: Option<Extension<AuthContext>>,
+    Json(request): Json<RegisterPasskeyRequest>,
+) -> AppResult<Json<WebAuthnChallenge<CreationChallengeResponse>>> {
+    let user = signed_in_user(&state.users, context).await?;
+    Ok(Json(state.passkeys.begin_registration(&user, &request.name).await?))
+}
+
+async fn complete_registration(
+    State(state): State<PasskeyState>,
+    context: Option<Extension<AuthContext>>,
+    Json(request): Json<CompleteRegistration>,
+) -> AppResult<(StatusCode, Json<PasskeyCredential>)> {
+    let user = signed_in_user(&state.users, context).await?;
+    let passkey = state
+        .passkeys
+        .finish_registration(user.id, request.ceremony_id, &request.credential)
+        .await?;
+    Ok((StatusCode::CREATED, Json(passkey)))
+}
+
+async fn remove_passkey(
+    State(state): State<PasskeyState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let user = signed_in_user(&state.users, context).await?;
+    state.passkeys.remove(user.id, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn begin_sign_in(
+    State(state): State<PasskeyState>,
+) -> AppResult<Json<WebAuthnChallenge<RequestChallengeResponse>>> {
+    Ok(Json(state.passkeys.begin_sign_in().await?))
+}
+
+/// Verify the browser's assertion and sign its passkey's owner in
+async fn complete_sign_in(
+    State(state): State<PasskeyState>,
+    Extension(tenant): Extension<TenantContext>,
+    headers: HeaderMap,
+    address: Option<Extension<ClientAddress>>,
+    Json(request): Json<CompleteSignIn>,
+) -> AppResult<Json<TokenPair>> {
+    let passkey = state
+        .passkeys
+        .finish_sign_in(request.ceremony_id, &request.credential)
+        .await?;
+    let client = client_info(&headers, address.as_deref());
+    let user = state.users.passkey_login(&tenant, passkey.user_id, &client).await?;
+    let session = state.users.start_session(&user, &client).await?;
+    Ok(Json(state.auth.issue(&user, Some(session.id))?))
+}
@@ -0,0 +1,42 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::{delete, post};
+use axum::{Extension, Router};
+use std::sync::Arc;
+
+use super::signed_in_user;
+use crate::models::{AppResult, AuthContext};
+use crate::services::{PresenceService, UserService};
+
+#[derive(Clone)]
+struct PresenceState {
+    presence: Arc<PresenceService>,
+    users: Arc<UserService>,
+}
+
+/// Heartbeats from signed-in clients
+pub fn router(presence: Arc<PresenceService>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/presence/heartbeat", post(heartbeat))
+        .route("/presence", delete(go_offline))
+        .with_state(PresenceState { presence, users })
+}
+
+/// Clients call this at an interval comfortably below the presence TTL
+async fn heartbeat(
+    State(state): State<PresenceState>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<StatusCode> {
+    let user_id = signed_in_user(&state.users, context).await?.id;
+    state.presence.heartbeat(user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn go_offline(
+    State(state): State<PresenceState>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<StatusCode> {
+    let user_id = signed_in_user(&state.users, context).await?.id;
+    state.presence.go_offline(user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
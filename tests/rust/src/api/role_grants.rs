@@ -0,0 +1,50 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{delete, get};
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::signed_in_user;
+use crate::models::{AppResult, AuthContext, GrantTemporaryRoleRequest, RoleGrant};
+use crate::services::UserService;
+
+/// Granting roles for a limited time, and ending them early
+pub fn router(users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/users/:id/role-grants", get(user_grants).post(grant_role))
+        .route("/users/:id/role-grants/:grant_id", delete(revoke_grant))
+        .with_state(users)
+}
+
+async fn grant_role(
+    State(users): State<Arc<UserService>>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<GrantTemporaryRoleRequest>,
+) -> AppResult<(StatusCode, Json<RoleGrant>)> {
+    let actor = signed_in_user(&users, context).await?;
+    let grant = users
+        .grant_temporary_role(&actor, id, request.role, Duration::from_secs(request.duration_secs))
+        .await?;
+    Ok((StatusCode::CREATED, Json(grant)))
+}
+
+async fn user_grants(
+    State(users): State<Arc<UserService>>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Vec<RoleGrant>>> {
+    let actor = signed_in_user(&users, context).await?;
+    Ok(Json(users.role_grants_for(&actor, id).await?))
+}
+
+async fn revoke_grant(
+    State(users): State<Arc<UserService>>,
+    context: Option<Extension<AuthContext>>,
+    Path((id, grant_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<RoleGrant>> {
+    let actor = signed_in_user(&users, context).await?;
+    Ok(Json(users.revoke_role_grant(&actor, id, grant_id).await?))
+}
@@ -0,0 +1,69 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::signed_in_user;
+use crate::models::{AppResult, AuthContext, CreateRoleRequest, RoleRequest, RoleRequestDecision};
+use crate::services::UserService;
+
+const DEFAULT_PENDING_PAGE: i64 = 50;
+const MAX_PENDING_PAGE: i64 = 200;
+
+#[derive(Debug, Deserialize)]
+struct PendingQuery {
+    limit: Option<i64>,
+}
+
+/// Asking for a higher role and deciding those requests
+pub fn router(users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/role-requests", post(create_request))
+        .route("/role-requests/pending", get(pending_requests))
+        .route("/role-requests/:id/decision", post(decide_request))
+        .route("/users/:id/role-requests", get(user_requests))
+        .with_state(users)
+}
+
+async fn create_request(
+    State(users): State<Arc<UserService>>,
+    context: Option<Extension<AuthContext>>,
+    Json(request): Json<CreateRoleRequest>,
+) -> AppResult<(StatusCode, Json<RoleRequest>)> {
+    let actor = signed_in_user(&users, context).await?;
+    let created = users.request_role(&actor, request).await?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// Requests the caller may approve or deny, oldest first
+async fn pending_requests(
+    State(users): State<Arc<UserService>>,
+    context: Option<Extension<AuthContext>>,
+    Query(query): Query<PendingQuery>,
+) -> AppResult<Json<Vec<RoleRequest>>> {
+    let actor = signed_in_user(&users, context).await?;
+    let limit = query.limit.unwrap_or(DEFAULT_PENDING_PAGE).clamp(1, MAX_PENDING_PAGE);
+    Ok(Json(users.pending_role_requests(&actor, limit).await?))
+}
+
+async fn decide_request(
+    State(users): State<Arc<UserService>>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+    Json(decision): Json<RoleRequestDecision>,
+) -> AppResult<Json<RoleRequest>> {
+    let actor = signed_in_user(&users, context).await?;
+    Ok(Json(users.decide_role_request(&actor, id, decision).await?))
+}
+
+async fn user_requests(
+    State(users): State<Arc<UserService>>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Vec<RoleRequest>>> {
+    let actor = signed_in_user(&users, context).await?;
+    Ok(Json(users.role_requests_for(&actor, id).await?))
+}
@@ -0,0 +1,59 @@
+use axum::extract::State;
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Redirect};
+use axum::routing::{get, post};
+use axum::{Extension, Form, Json, Router};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use super::{external_sign_in, ExternalSignIn};
+use crate::middleware::{AuthMiddleware, ClientAddress};
+use crate::models::{AppResult, TenantContext};
+use crate::services::{SamlService, UserService};
+
+#[derive(Clone)]
+struct SamlState {
+    saml: Arc<SamlService>,
+    auth: Arc<AuthMiddleware>,
+    users: Arc<UserService>,
+}
+
+/// Form the identity provider posts back with the HTTP-POST binding
+#[derive(Deserialize)]
+struct AssertionForm {
+    #[serde(rename = "SAMLResponse")]
+    saml_response: String,
+}
+
+/// Single sign-on through the SAML identity provider, ending in the same tokens as any other sign-in
+pub fn router(saml: Arc<SamlService>, auth: Arc<AuthMiddleware>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/auth/saml/login", get(login))
+        .route("/auth/saml/acs", post(consume))
+        .route("/auth/saml/metadata", get(metadata))
+        .with_state(SamlState { saml, auth, users })
+}
+
+/// Send the browser to the identity provider with a new authentication request
+async fn login(State(state): State<SamlState>) -> AppResult<Redirect> {
+    Ok(Redirect::to(&state.saml.login_url().await?))
+}
+
+/// Assertion consumer service
+async fn consume(
+    State(state): State<SamlState>,
+    Extension(tenant): Extension<TenantContext>,
+    headers: HeaderMap,
+    address: Option<Extension<ClientAddress>>,
+    Form(form): Form<AssertionForm>,
+) -> AppResult<Json<ExternalSignIn>> {
+    let user = state.saml.complete(&form.saml_response).await?;
+    Ok(Json(
+        external_sign_in(&state.users, &state.auth, &tenant, user, &headers, address.as_deref()).await?,
+    ))
+}
+
+/// Service provider metadata to register with the identity provider
+async fn metadata(State(state): State<SamlState>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/samlmetadata+xml")], state.saml.metadata())
+}
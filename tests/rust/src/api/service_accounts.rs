@@ -0,0 +1,77 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::require_admin;
+use crate::middleware::AuthMiddleware;
+use crate::models::{
+    AppResult, AuthContext, CreateServiceAccountRequest, IssuedServiceAccount, ServiceAccount, ServiceToken,
+    ServiceTokenRequest, TenantContext,
+};
+use crate::services::{ServiceAccountService, UserService};
+
+#[derive(Clone)]
+struct ServiceAccountState {
+    accounts: Arc<ServiceAccountService>,
+    auth: Arc<AuthMiddleware>,
+    users: Arc<UserService>,
+}
+
+/// Management of the tenant's service accounts, and the token exchange internal services sign in with
+pub fn router(accounts: Arc<ServiceAccountService>, auth: Arc<AuthMiddleware>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/admin/service-accounts", get(list).post(create))
+        .route("/admin/service-accounts/:id", delete(disable))
+        .route("/auth/service-token", post(issue_token))
+        .with_state(ServiceAccountState { accounts, auth, users })
+}
+
+async fn list(
+    State(state): State<ServiceAccountState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<Vec<ServiceAccount>>> {
+    require_admin(&state.users, context).await?;
+    Ok(Json(state.accounts.list(&tenant).await?))
+}
+
+/// The response is the only time the client secret is shown
+async fn create(
+    State(state): State<ServiceAccountState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Json(request): Json<CreateServiceAccountRequest>,
+) -> AppResult<(StatusCode, Json<IssuedServiceAccount>)> {
+    let admin = require_admin(&state.users, context).await?;
+    let issued = state.accounts.create(&tenant, request, &admin).await?;
+    Ok((StatusCode::CREATED, Json(issued)))
+}
+
+/// Tokens already issued to the account stop working when they expire
+async fn disable(
+    State(state): State<ServiceAccountState>,
+    Extension(tenant): Extension<TenantContext>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let admin = require_admin(&state.users, context).await?;
+    state.accounts.disable(&tenant, id, &admin).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Exchange an account's client credentials for a short-lived token, narrowed to the scopes asked for
+async fn issue_token(
+    State(state): State<ServiceAccountState>,
+    Extension(tenant): Extension<TenantContext>,
+    Json(request): Json<ServiceTokenRequest>,
+) -> AppResult<Json<ServiceToken>> {
+    let account = state
+        .accounts
+        .authenticate(&tenant, request.client_id, &request.client_secret)
+        .await?;
+    let scopes = account.token_scopes(request.scopes.as_deref())?;
+    Ok(Json(state.auth.issue_service_token(&account, scopes)?))
+}
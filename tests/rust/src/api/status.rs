@@ -0,0 +1,23 @@
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+use crate::services::StatusService;
+
+/// Unauthenticated component health and uptime for a public status page
+pub fn router(status: Arc<StatusService>) -> Router {
+    Router::new()
+        .route("/status", get(status_page))
+        .with_state(status)
+}
+
+async fn status_page(State(status): State<Arc<StatusService>>) -> impl IntoResponse {
+    let page = status.page().await;
+    (
+        [(header::CACHE_CONTROL, "public, max-age=30")],
+        Json(page.as_ref().clone()),
+    )
+}
@@ -0,0 +1,101 @@
+use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::require_admin;
+use crate::models::{AppError, AppResult, AuthContext, EngagementEvent, TemplateEngagement};
+use crate::services::{EmailTracker, NotificationService, UserService};
+
+/// Transparent 1x1 GIF served as the open pixel
+const PIXEL: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+const DEFAULT_ENGAGEMENT_DAYS: i64 = 30;
+
+#[derive(Clone)]
+struct TrackingState {
+    notifications: Arc<NotificationService>,
+    tracker: Arc<EmailTracker>,
+    users: Arc<UserService>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EngagementQuery {
+    days: Option<i64>,
+}
+
+/// Ingest endpoints for tracked emails plus per-template engagement stats for admins
+pub fn router(notifications: Arc<NotificationService>, tracker: Arc<EmailTracker>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/t/open/:notification_id/:signature", get(track_open))
+        .route("/t/click/:notification_id/:target/:signature", get(track_click))
+        .route("/notifications/engagement", get(template_engagement))
+        .with_state(TrackingState {
+            notifications,
+            tracker,
+            users,
+        })
+}
+
+/// Always answers with the pixel so mail clients never show a broken image
+async fn track_open(
+    State(state): State<TrackingState>,
+    Path((notification_id, signature)): Path<(Uuid, String)>,
+) -> Response {
+    if state.tracker.verify_open(notification_id, &signature) {
+        if let Err(e) = state
+            .notifications
+            .record_engagement(notification_id, EngagementEvent::Open)
+            .await
+        {
+            tracing::warn!("Failed to record open of notification {}: {}", notification_id, e);
+        }
+    }
+
+    (
+        [(header::CONTENT_TYPE, "image/gif"), (header::CACHE_CONTROL, "no-store")],
+        PIXEL,
+    )
+        .into_response()
+}
+
+/// Redirect to the original link; unsigned targets are refused so this is not an open redirect
+async fn track_click(
+    State(state): State<TrackingState>,
+    Path((notification_id, target, signature)): Path<(Uuid, String, String)>,
+) -> AppResult<Redirect> {
+    let target = state
+        .tracker
+        .verify_click(notification_id, &target, &signature)
+        .ok_or_else(|| AppError::Validation(vec!["Invalid tracking link".to_string()]))?;
+
+    // A recording failure must not break the link for the reader
+    if let Err(e) = state
+        .notifications
+        .record_engagement(notification_id, EngagementEvent::Click)
+        .await
+    {
+        tracing::warn!("Failed to record click of notification {}: {}", notification_id, e);
+    }
+    Ok(Redirect::to(&target))
+}
+
+async fn template_engagement(
+    State(state): State<TrackingState>,
+    context: Option<Extension<AuthContext>>,
+    Query(query): Query<EngagementQuery>,
+) -> AppResult<Json<Vec<TemplateEngagement>>> {
+    require_admin(&state.users, context).await?;
+    let days = query.days.unwrap_or(DEFAULT_ENGAGEMENT_DAYS).clamp(1, 365);
+    let since = Utc::now() - Duration::days(days);
+    Ok(Json(state.notifications.template_engagement(since).await?))
+}
@@ -0,0 +1,74 @@
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, patch};
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::{client_info, signed_in_user};
+use crate::middleware::ClientAddress;
+use crate::models::{AppError, AppResult, AuthContext, RenameDeviceRequest, TrustDeviceRequest, TrustedDevice};
+use crate::services::{TrustedDevices, UserService};
+
+#[derive(Clone)]
+struct TrustedDeviceState {
+    devices: Arc<TrustedDevices>,
+    users: Arc<UserService>,
+}
+
+/// The signed-in user's trusted devices, whose sign-ins skip the second factor
+pub fn router(devices: Arc<TrustedDevices>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/auth/trusted-devices", get(list_devices).post(trust_device))
+        .route("/auth/trusted-devices/:id", patch(rename_device).delete(revoke_device))
+        .with_state(TrustedDeviceState { devices, users })
+}
+
+async fn list_devices(
+    State(state): State<TrustedDeviceState>,
+    context: Option<Extension<AuthContext>>,
+) -> AppResult<Json<Vec<TrustedDevice>>> {
+    let user = signed_in_user(&state.users, context).await?.id;
+    Ok(Json(state.users.list_trusted_devices(user).await?))
+}
+
+/// Trust the calling browser, handing it the cookie later sign-ins present
+async fn trust_device(
+    State(state): State<TrustedDeviceState>,
+    context: Option<Extension<AuthContext>>,
+    headers: HeaderMap,
+    address: Option<Extension<ClientAddress>>,
+    Json(request): Json<TrustDeviceRequest>,
+) -> AppResult<Response> {
+    let user = signed_in_user(&state.users, context).await?.id;
+    let issued = state
+        .users
+        .trust_device(user, &request, &client_info(&headers, address.as_deref()))
+        .await?;
+    let cookie = HeaderValue::from_str(&state.devices.cookie(&issued))
+        .map_err(|e| AppError::Internal(format!("Invalid trusted device cookie: {}", e)))?;
+    let mut response = (StatusCode::CREATED, Json(issued)).into_response();
+    response.headers_mut().append(header::SET_COOKIE, cookie);
+    Ok(response)
+}
+
+async fn rename_device(
+    State(state): State<TrustedDeviceState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<RenameDeviceRequest>,
+) -> AppResult<Json<TrustedDevice>> {
+    let user = signed_in_user(&state.users, context).await?.id;
+    Ok(Json(state.users.rename_trusted_device(user, id, &request.name).await?))
+}
+
+async fn revoke_device(
+    State(state): State<TrustedDeviceState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let user = signed_in_user(&state.users, context).await?.id;
+    state.users.revoke_trusted_device(user, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
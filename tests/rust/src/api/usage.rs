@@ -0,0 +1,42 @@
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+
+use crate::models::{AppError, AppResult, TenantContext};
+use crate::services::{QuotaService, QuotaSubject, TenantLimitService, TenantUsage, UsageReport};
+
+#[derive(Clone)]
+struct UsageState {
+    quota: Arc<QuotaService>,
+    tenants: Arc<TenantLimitService>,
+}
+
+/// Routes exposing API usage for the authenticated caller and their tenant
+pub fn router(quota: Arc<QuotaService>, tenants: Arc<TenantLimitService>) -> Router {
+    Router::new()
+        .route("/usage", get(current_usage))
+        .route("/usage/tenant", get(tenant_usage))
+        .with_state(UsageState { quota, tenants })
+}
+
+async fn current_usage(
+    State(state): State<UsageState>,
+    subject: Option<Extension<QuotaSubject>>,
+) -> AppResult<Json<UsageReport>> {
+    let Extension(subject) =
+        subject.ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+    Ok(Json(state.quota.usage(&subject).await?))
+}
+
+async fn tenant_usage(
+    State(state): State<UsageState>,
+    subject: Option<Extension<QuotaSubject>>,
+    tenant: Option<Extension<TenantContext>>,
+) -> AppResult<Json<TenantUsage>> {
+    if subject.is_none() {
+        return Err(AppError::Unauthorized("Authentication required".to_string()));
+    }
+    let tenant = tenant.map(|Extension(tenant)| tenant).unwrap_or_default();
+    Ok(Json(state.tenants.usage(&tenant).await?))
+}
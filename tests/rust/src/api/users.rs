@@ -0,0 +1,125 @@
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::signed_in_user;
+use crate::models::{
+    AppError, AppResult, AuthContext, EffectivePermissions, PolicyResource, UpdateUserRequest, User, UserPublic,
+};
+use crate::services::UserService;
+
+/// Reading and editing user accounts.
+///
+/// Responses carry the user's version as a strong `ETag`, and edits must
+/// send it back in `If-Match`: an edit based on an outdated read fails with
+/// 412 instead of silently overwriting the change it did not see.
+pub fn router(users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/users/:id", get(get_user).patch(update_user))
+        .route("/users/:id/permissions", get(get_permissions))
+        .with_state(users)
+}
+
+async fn get_user(
+    State(users): State<Arc<UserService>>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Response> {
+    let actor = signed_in_user(&users, context).await?;
+    let user = users.get_user_by_id(id).await?;
+    // Checked before reporting a missing user, so callers cannot probe which ids exist
+    let resource = match &user {
+        Some(user) => users.user_resource(&actor, user).await?,
+        None => PolicyResource::owned("user", id),
+    };
+    if !users.policies().allows(&actor, "users:read", Some(&resource)) {
+        return Err(AppError::Forbidden("Cannot view another user".to_string()));
+    }
+    let user = user.ok_or_else(|| AppError::NotFound(format!("User {} not found", id)))?;
+    Ok(with_etag(user))
+}
+
+/// What the user may do outright; readable by whoever may read the user
+async fn get_permissions(
+    State(users): State<Arc<UserService>>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<EffectivePermissions>> {
+    let actor = signed_in_user(&users, context).await?;
+    let user = users.get_user_by_id(id).await?;
+    let resource = match &user {
+        Some(user) => users.user_resource(&actor, user).await?,
+        None => PolicyResource::owned("user", id),
+    };
+    if !users.policies().allows(&actor, "users:read", Some(&resource)) {
+        return Err(AppError::Forbidden("Cannot view another user".to_string()));
+    }
+    Ok(Json(users.effective_permissions(id).await?))
+}
+
+async fn update_user(
+    State(users): State<Arc<UserService>>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(request): Json<UpdateUserRequest>,
+) -> AppResult<Response> {
+    let actor = signed_in_user(&users, context).await?;
+    let Some(if_match) = headers.get(header::IF_MATCH).and_then(|value| value.to_str().ok()) else {
+        return Err(AppError::PreconditionRequired(
+            "If-Match with the user's ETag is required".to_string(),
+        ));
+    };
+    let current = users
+        .get_user_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("User {} not found", id)))?;
+    let Some(expected_version) = matching_version(if_match, current.version) else {
+        return Err(stale(&current));
+    };
+
+    match users.update_user(&actor, id, request, expected_version).await {
+        Ok(user) => Ok(with_etag(user)),
+        // Conflicts are also raised for taken emails and usernames; only a moved version is a failed precondition
+        Err(AppError::Conflict(message)) => match users.get_user_by_id(id).await? {
+            Some(latest) if latest.version != expected_version => Err(stale(&latest)),
+            _ => Err(AppError::Conflict(message)),
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// The version an `If-Match` value names if it matches `current`; `*` matches any version.
+///
+/// `If-Match` uses strong comparison, so weak `W/` tags never match.
+fn matching_version(if_match: &str, current: i64) -> Option<i64> {
+    let matches = if_match.split(',').map(str::trim).any(|tag| {
+        tag == "*"
+            || tag
+                .strip_prefix('"')
+                .and_then(|tag| tag.strip_suffix('"'))
+                .is_some_and(|version| version.parse::<i64>() == Ok(current))
+    });
+    matches.then_some(current)
+}
+
+fn etag(user: &User) -> String {
+    format!("\"{}\"", user.version)
+}
+
+fn with_etag(user: User) -> Response {
+    let tag = etag(&user);
+    ([(header::ETAG, tag)], Json(UserPublic::from(user))).into_response()
+}
+
+/// 412 carrying the current ETag, so the client can re-read and retry
+fn stale(current: &User) -> AppError {
+    AppError::PreconditionFailed {
+        message: "User has been modified since it was read".to_string(),
+        etag: etag(current),
+    }
+}
@@ -0,0 +1,17 @@
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+use crate::utils::AppInfo;
+
+/// Routes describing the running build
+pub fn router(info: Arc<AppInfo>) -> Router {
+    Router::new()
+        .route("/version", get(version))
+        .with_state(info)
+}
+
+async fn version(State(info): State<Arc<AppInfo>>) -> Json<AppInfo> {
+    Json(info.as_ref().clone())
+}
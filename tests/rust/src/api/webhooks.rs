@@ -0,0 +1,74 @@
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::{AppResult, AuthContext, CallbackFilters, ProviderCallback};
+use crate::services::{ProviderCallbacks, UserService};
+
+#[derive(Clone)]
+struct WebhookState {
+    webhooks: Arc<dyn ProviderCallbacks>,
+    users: Arc<UserService>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplayQuery {
+    #[serde(default)]
+    force: bool,
+}
+
+/// Delivery status callbacks from providers, and admin tooling to inspect and replay them
+pub fn router(webhooks: Arc<dyn ProviderCallbacks>, users: Arc<UserService>) -> Router {
+    Router::new()
+        .route("/webhooks/delivery/:provider", post(receive_callback))
+        .route("/admin/webhooks/callbacks", get(list_callbacks))
+        .route("/admin/webhooks/callbacks/:id", get(get_callback))
+        .route("/admin/webhooks/callbacks/:id/replay", post(replay_callback))
+        .with_state(WebhookState { webhooks, users })
+}
+
+/// Acknowledged once stored; processing failures are kept for replay rather than retried by the provider
+async fn receive_callback(
+    State(state): State<WebhookState>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<StatusCode> {
+    state.webhooks.receive(&provider, &headers, &body).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Stored callbacks, newest first; `signature_valid=false` lists the ones that were flagged
+async fn list_callbacks(
+    State(state): State<WebhookState>,
+    context: Option<Extension<AuthContext>>,
+    Query(filters): Query<CallbackFilters>,
+) -> AppResult<Json<Vec<ProviderCallback>>> {
+    super::require_admin(&state.users, context).await?;
+    Ok(Json(state.webhooks.list(&filters).await?))
+}
+
+async fn get_callback(
+    State(state): State<WebhookState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ProviderCallback>> {
+    super::require_admin(&state.users, context).await?;
+    Ok(Json(state.webhooks.get(id).await?))
+}
+
+async fn replay_callback(
+    State(state): State<WebhookState>,
+    context: Option<Extension<AuthContext>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ReplayQuery>,
+) -> AppResult<Json<ProviderCallback>> {
+    let admin = super::require_admin(&state.users, context).await?;
+    tracing::info!(callback_id = %id, admin_id = %admin.id, force = query.force, "Replaying provider callback");
+    Ok(Json(state.webhooks.replay(id, query.force).await?))
+}
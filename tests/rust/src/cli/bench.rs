@@ -0,0 +1,286 @@
+use clap::Args;
+use rand::seq::SliceRandom;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::models::{AppError, AppResult, Notification, NotificationChannel, NotificationType, User};
+use crate::repositories::{
+    CachingUserRepository, NotificationRepository, OutboxRepository, PostgresNotificationRepository,
+    PostgresUserRepository, UserRepository,
+};
+use crate::services::{CacheEntity, CachePolicies, CacheService};
+use crate::utils::KeyRing;
+
+/// Password hash of benchmark users; it never verifies
+const UNUSABLE_PASSWORD_HASH: &str = "!bench";
+
+/// Options for the load generator
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// How long load is generated for
+    #[arg(long, default_value_t = 30)]
+    pub duration_secs: u64,
+
+    /// User creations started per second; 0 disables the workload
+    #[arg(long, default_value_t = 10.0)]
+    pub create_rate: f64,
+
+    /// User lookups by id started per second; 0 disables the workload
+    #[arg(long, default_value_t = 200.0)]
+    pub lookup_rate: f64,
+
+    /// In-app notification sends started per second; 0 disables the workload
+    #[arg(long, default_value_t = 20.0)]
+    pub notify_rate: f64,
+
+    /// Operations in flight per workload; arrivals beyond it are dropped and counted
+    #[arg(long, default_value_t = 64)]
+    pub concurrency: usize,
+
+    /// Users created before measuring so lookups and sends have targets
+    #[arg(long, default_value_t = 100)]
+    pub seed_users: usize,
+
+    /// Leave the benchmark's users and notifications in place afterwards
+    #[arg(long)]
+    pub keep_data: bool,
+}
+
+/// Outcome of one workload
+#[derive(Debug)]
+pub struct WorkloadReport {
+    pub name: &'static str,
+    pub completed: u64,
+    pub errors: u64,
+    /// Arrivals skipped because `concurrency` operations were already in flight
+    pub dropped: u64,
+    /// Successful operations per second of wall time
+    pub throughput: f64,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl fmt::Display for WorkloadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<8} {:>8} ok {:>6} err {:>6} dropped {:>9.1}/s  p50 {:>8.2?}  p90 {:>8.2?}  p99 {:>8.2?}  max {:>8.2?}",
+            self.name,
+            self.completed,
+            self.errors,
+            self.dropped,
+            self.throughput,
+            self.p50,
+            self.p90,
+            self.p99,
+            self.max
+        )
+    }
+}
+
+/// Results of a benchmark run
+#[derive(Debug)]
+pub struct BenchReport {
+    pub elapsed: Duration,
+    pub workloads: Vec<WorkloadReport>,
+}
+
+/// Drive user creation, lookups and notification sends at fixed rates.
+///
+/// Load goes through the same repository and cache layers the service
+/// uses, against the database and Redis of the current configuration, so
+/// point it at a staging deployment rather than production. Arrivals are
+/// open-loop: a slow backend shows up as latency and dropped operations
+/// instead of quietly lowering the offered rate. Created users write
+/// outbox events like any other, and sends are stored in-app
+/// notifications, so no email goes out.
+pub async fn run(config: &AppConfig, args: BenchArgs) -> AppResult<BenchReport> {
+    let database = Arc::new(Database::connect(&config.database_url).await?);
+    let cache = Arc::new(CacheService::new(&config.redis_url).await?);
+    let key_ring = Arc::new(KeyRing::from_config(&config.encryption)?);
+    let outbox = Arc::new(OutboxRepository::new(database.clone()));
+    let users: Arc<dyn UserRepository> = Arc::new(CachingUserRepository::new(
+        Arc::new(PostgresUserRepository::new(database.clone(), key_ring, outbox)),
+        cache,
+        CachePolicies::from_config(&config.cache).get(CacheEntity::User),
+    ));
+    let notifications: Arc<dyn NotificationRepository> = Arc::new(PostgresNotificationRepository::new(database.clone()));
+
+    let run_id = Uuid::new_v4().simple().to_string()[..8].to_string();
+    let pool = Arc::new(RwLock::new(Vec::new()));
+    for _ in 0..args.seed_users {
+        pool_write(&pool).push(users.create(&bench_user(&run_id)).await?);
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(args.duration_secs);
+    let started = Instant::now();
+    let creates = {
+        let (users, pool, run_id) = (users.clone(), pool.clone(), run_id.clone());
+        drive("create", args.create_rate, deadline, args.concurrency, move || {
+            let (users, pool, user) = (users.clone(), pool.clone(), bench_user(&run_id));
+            async move {
+                let created = users.create(&user).await?;
+                pool_write(&pool).push(created);
+                Ok(())
+            }
+        })
+    };
+    let lookups = {
+        let (users, pool) = (users.clone(), pool.clone());
+        drive("lookup", args.lookup_rate, deadline, args.concurrency, move || {
+            let (users, target) = (users.clone(), random_user(&pool));
+            async move {
+                let target = target?;
+                users
+                    .find_by_id(target.id)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound(format!("Benchmark user {} vanished", target.id)))?;
+                Ok(())
+            }
+        })
+    };
+    let sends = {
+        let (notifications, pool) = (notifications.clone(), pool.clone());
+        drive("notify", args.notify_rate, deadline, args.concurrency, move || {
+            let (notifications, target) = (notifications.clone(), random_user(&pool));
+            async move {
+                let target = target?;
+                let notification = Notification::new(
+                    target.id,
+                    NotificationType::System,
+                    NotificationChannel::InApp,
+                    target.email.clone(),
+                    "Benchmark".to_string(),
+                    "Load generated by the bench command".to_string(),
+                );
+                notifications.create(&notification).await
+            }
+        })
+    };
+    let (creates, lookups, sends) = tokio::join!(creates, lookups, sends);
+    let elapsed = started.elapsed();
+
+    if !args.keep_data {
+        let ids: Vec<Uuid> = pool_read(&pool).iter().map(|user| user.id).collect();
+        sqlx::query("DELETE FROM notifications WHERE user_id = ANY($1)")
+            .bind(&ids)
+            .execute(database.pool())
+            .await?;
+        for id in ids {
+            users.delete(id).await?;
+        }
+    }
+
+    Ok(BenchReport {
+        elapsed,
+        workloads: [creates, lookups, sends].into_iter().flatten().collect(),
+    })
+}
+
+/// Start `operation` `rate` times a second until `deadline`, then wait for stragglers
+async fn drive<F, Fut>(
+    name: &'static str,
+    rate: f64,
+    deadline: Instant,
+    concurrency: usize,
+    operation: F,
+) -> Option<WorkloadReport>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = AppResult<()>> + Send + 'static,
+{
+    if rate <= 0.0 {
+        return None;
+    }
+
+    let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+    let latencies = Arc::new(Mutex::new(Vec::new()));
+    let errors = Arc::new(AtomicU64::new(0));
+    let mut dropped = 0;
+    let mut in_flight = JoinSet::new();
+    let mut ticks = tokio::time::interval(Duration::from_secs_f64(1.0 / rate));
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Burst);
+    let started = Instant::now();
+
+    while Instant::now() < deadline {
+        ticks.tick().await;
+        let Ok(permit) = permits.clone().try_acquire_owned() else {
+            dropped += 1;
+            continue;
+        };
+        let (latencies, errors, pending) = (latencies.clone(), errors.clone(), operation());
+        in_flight.spawn(async move {
+            let began = Instant::now();
+            match pending.await {
+                Ok(()) => latencies.lock().unwrap_or_else(|e| e.into_inner()).push(began.elapsed()),
+                Err(e) => {
+                    if errors.fetch_add(1, Ordering::Relaxed) == 0 {
+                        tracing::warn!("First {} failure: {}", name, e);
+                    }
+                }
+            }
+            drop(permit);
+        });
+        while in_flight.try_join_next().is_some() {}
+    }
+    while in_flight.join_next().await.is_some() {}
+
+    let mut latencies = std::mem::take(&mut *latencies.lock().unwrap_or_else(|e| e.into_inner()));
+    latencies.sort_unstable();
+    Some(WorkloadReport {
+        name,
+        completed: latencies.len() as u64,
+        errors: errors.load(Ordering::Relaxed),
+        dropped,
+        throughput: latencies.len() as f64 / started.elapsed().as_secs_f64(),
+        p50: percentile(&latencies, 50.0),
+        p90: percentile(&latencies, 90.0),
+        p99: percentile(&latencies, 99.0),
+        max: latencies.last().copied().unwrap_or_default(),
+    })
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn bench_user(run_id: &str) -> User {
+    let suffix = Uuid::new_v4().simple().to_string();
+    User::new(
+        format!("bench.{}.{}@example.com", run_id, &suffix[..12]),
+        format!("bench_{}_{}", run_id, &suffix[..12]),
+        "Bench".to_string(),
+        "User".to_string(),
+        UNUSABLE_PASSWORD_HASH.to_string(),
+    )
+}
+
+fn random_user(pool: &RwLock<Vec<User>>) -> AppResult<User> {
+    pool_read(pool)
+        .choose(&mut rand::thread_rng())
+        .cloned()
+        .ok_or_else(|| AppError::Validation(vec!["Lookups and sends need --seed-users or --create-rate".to_string()]))
+}
+
+fn pool_read(pool: &RwLock<Vec<User>>) -> std::sync::RwLockReadGuard<'_, Vec<User>> {
+    pool.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn pool_write(pool: &RwLock<Vec<User>>) -> std::sync::RwLockWriteGuard<'_, Vec<User>> {
+    pool.write().unwrap_or_else(|e| e.into_inner())
+}
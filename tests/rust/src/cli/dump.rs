@@ -0,0 +1,142 @@
+use clap::Args;
+use futures::TryStreamExt;
+use serde_json::{json, Map, Value};
+use std::path::PathBuf;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::models::{AppError, AppResult};
+use crate::utils::{Anonymizer, KeyRing};
+
+/// Password hash written for every exported user; it never verifies
+const UNUSABLE_PASSWORD_HASH: &str = "!anonymized";
+
+/// Options for the anonymized staging dump
+#[derive(Debug, Args)]
+pub struct DumpArgs {
+    /// File the SQL dump is written to
+    #[arg(short, long, default_value = "staging_dump.sql")]
+    pub output: PathBuf,
+
+    /// Secret salt; reusing it reproduces the same fake data
+    #[arg(long, env = "DUMP_ANONYMIZE_SALT")]
+    pub salt: String,
+}
+
+/// Row counts written by a dump run
+#[derive(Debug, Default)]
+pub struct DumpSummary {
+    pub users: u64,
+    pub notifications: u64,
+}
+
+/// Export the users and notifications tables with PII replaced.
+///
+/// Encrypted name columns are re-encrypted with the key ring from the
+/// current configuration, so run the dump with the staging environment's
+/// `PII_ENCRYPTION_KEYS` rather than production's.
+pub async fn run(config: &AppConfig, args: DumpArgs) -> AppResult<DumpSummary> {
+    let database = Database::connect(&config.database_url).await?;
+    let key_ring = KeyRing::from_config(&config.encryption)?;
+    let mut anonymizer = Anonymizer::new(args.salt);
+    let mut summary = DumpSummary::default();
+
+    let file = File::create(&args.output)
+        .await
+        .map_err(|e| AppError::Internal(format!("Cannot create {}: {}", args.output.display(), e)))?;
+    let mut out = BufWriter::new(file);
+
+    write(&mut out, "BEGIN;\n").await?;
+
+    let mut users = sqlx::query_scalar::<_, Value>("SELECT row_to_json(u) FROM users u ORDER BY u.id")
+        .fetch(database.pool());
+    while let Some(mut row) = users.try_next().await? {
+        if let Some(fields) = row.as_object_mut() {
+            anonymize_user(fields, &mut anonymizer, &key_ring)?;
+        }
+        write(&mut out, &insert_statement("users", &row)).await?;
+        summary.users += 1;
+    }
+    drop(users);
+
+    let mut notifications = sqlx::query_scalar::<_, Value>(
+        "SELECT row_to_json(n) FROM notifications n ORDER BY n.id",
+    )
+    .fetch(database.pool());
+    while let Some(mut row) = notifications.try_next().await? {
+        if let Some(fields) = row.as_object_mut() {
+            anonymize_notification(fields, &mut anonymizer);
+        }
+        write(&mut out, &insert_statement("notifications", &row)).await?;
+        summary.notifications += 1;
+    }
+    drop(notifications);
+
+    write(&mut out, "COMMIT;\n").await?;
+    out.flush()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to flush dump: {}", e)))?;
+
+    Ok(summary)
+}
+
+fn anonymize_user(
+    fields: &mut Map<String, Value>,
+    anonymizer: &mut Anonymizer,
+    key_ring: &KeyRing,
+) -> AppResult<()> {
+    let original = |fields: &Map<String, Value>, key: &str| {
+        fields.get(key).and_then(Value::as_str).unwrap_or_default().to_string()
+    };
+    // Names are stored encrypted with random nonces, so seed them from the id
+    let id = original(fields, "id");
+
+    let email = anonymizer.email(&original(fields, "email"));
+    let username = anonymizer.username(&original(fields, "username"));
+    let first_name = key_ring.encrypt(&anonymizer.first_name(&id))?.encode();
+    let last_name = key_ring.encrypt(&anonymizer.last_name(&id))?.encode();
+
+    fields.insert("email".to_string(), json!(email));
+    fields.insert("username".to_string(), json!(username));
+    fields.insert("first_name".to_string(), json!(first_name));
+    fields.insert("last_name".to_string(), json!(last_name));
+    fields.insert("pii_key_version".to_string(), json!(key_ring.active_version()));
+    fields.insert("password_hash".to_string(), json!(UNUSABLE_PASSWORD_HASH));
+    fields.insert("metadata".to_string(), json!({}));
+
+    Ok(())
+}
+
+fn anonymize_notification(fields: &mut Map<String, Value>, anonymizer: &mut Anonymizer) {
+    if let Some(recipient) = fields.get("recipient").and_then(Value::as_str) {
+        let recipient = anonymizer.email(recipient);
+        fields.insert("recipient".to_string(), json!(recipient));
+    }
+
+    for key in ["title", "message"] {
+        if let Some(text) = fields.get(key).and_then(Value::as_str) {
+            let text = anonymizer.sentence(text);
+            fields.insert(key.to_string(), json!(text));
+        }
+    }
+
+    if fields.contains_key("metadata") {
+        fields.insert("metadata".to_string(), json!({}));
+    }
+}
+
+/// Insert statement that maps a JSON row back onto the table's columns
+fn insert_statement(table: &str, row: &Value) -> String {
+    let literal = row.to_string().replace('\'', "''");
+    format!(
+        "INSERT INTO {table} SELECT * FROM json_populate_record(NULL::{table}, '{literal}');\n"
+    )
+}
+
+async fn write(out: &mut BufWriter<File>, statement: &str) -> AppResult<()> {
+    out.write_all(statement.as_bytes())
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to write dump: {}", e)))
+}
@@ -0,0 +1,236 @@
+use clap::{Args, Subcommand};
+use sqlx::migrate::Migrator;
+use sqlx::Row;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::models::AppResult;
+use crate::repositories::DatabaseRouter;
+use crate::utils::{describe_constraint, ColumnSpec, LintFinding, Schema, SchemaDrift};
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Options for managing the schema
+#[derive(Debug, Args)]
+pub struct MigrateArgs {
+    #[command(subcommand)]
+    pub action: MigrateAction,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateAction {
+    /// Apply pending migrations to the home database and every region's
+    Run,
+    /// Compare each database's schema with the one the migrations build and fail on any difference
+    Check(CheckArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct CheckArgs {
+    /// Fail on lint findings in the migrations too, not only on drift
+    #[arg(long)]
+    pub strict: bool,
+}
+
+/// What `migrate check` found in one database
+#[derive(Debug)]
+pub struct SchemaCheck {
+    /// `home` or the region's name
+    pub database: String,
+    /// Migrations not applied yet
+    pub pending: Vec<i64>,
+    /// Applied migrations this build does not have, or that failed
+    pub unknown: Vec<i64>,
+    /// Applied migrations whose file has changed since
+    pub modified: Vec<i64>,
+    pub drift: Vec<SchemaDrift>,
+}
+
+impl SchemaCheck {
+    pub fn is_clean(&self) -> bool {
+        self.pending.is_empty() && self.unknown.is_empty() && self.modified.is_empty() && self.drift.is_empty()
+    }
+}
+
+/// Outcome of `migrate check` across every database
+#[derive(Debug)]
+pub struct CheckReport {
+    pub lint: Vec<LintFinding>,
+    pub databases: Vec<SchemaCheck>,
+    pub strict: bool,
+}
+
+impl CheckReport {
+    pub fn passed(&self) -> bool {
+        self.databases.iter().all(SchemaCheck::is_clean) && !(self.strict && !self.lint.is_empty())
+    }
+}
+
+/// Apply pending migrations everywhere, as startup does; returns how many databases were migrated
+pub async fn run(config: &AppConfig) -> AppResult<usize> {
+    let router = connect(config).await?;
+    router.home().migrate().await?;
+    let mut migrated = 1;
+    for (_, database) in router.regions() {
+        database.migrate().await?;
+        migrated += 1;
+    }
+    Ok(migrated)
+}
+
+/// Lint the migrations and compare every database with the schema they build
+pub async fn check(config: &AppConfig, args: CheckArgs) -> AppResult<CheckReport> {
+    let migrations: Vec<(String, &str)> = MIGRATOR
+        .iter()
+        .map(|migration| (format!("{}_{}", migration.version, migration.description), migration.sql.as_ref()))
+        .collect();
+    let (expected, lint) = Schema::from_migrations(migrations.iter().map(|(name, sql)| (name.as_str(), *sql)));
+
+    let router = connect(config).await?;
+    let mut databases = vec![check_database("home", router.home(), &expected).await?];
+    for (region, database) in router.regions() {
+        databases.push(check_database(region, database, &expected).await?);
+    }
+    Ok(CheckReport {
+        lint,
+        databases,
+        strict: args.strict,
+    })
+}
+
+async fn connect(config: &AppConfig) -> AppResult<DatabaseRouter> {
+    let home = Arc::new(Database::connect(&config.database_url).await?);
+    DatabaseRouter::connect(home, &config.residency).await
+}
+
+async fn check_database(name: &str, database: &Database, expected: &Schema) -> AppResult<SchemaCheck> {
+    let applied = applied_migrations(database).await?;
+    let pending = MIGRATOR
+        .iter()
+        .filter(|migration| !applied.contains_key(&migration.version))
+        .map(|migration| migration.version)
+        .collect();
+    let unknown = applied
+        .iter()
+        .filter(|(version, (_, success))| !success || !MIGRATOR.iter().any(|m| m.version == **version))
+        .map(|(version, _)| *version)
+        .collect();
+    let modified = MIGRATOR
+        .iter()
+        .filter(|migration| {
+            applied
+                .get(&migration.version)
+                .is_some_and(|(checksum, _)| checksum.as_slice() != migration.checksum.as_ref())
+        })
+        .map(|migration| migration.version)
+        .collect();
+
+    Ok(SchemaCheck {
+        database: name.to_string(),
+        pending,
+        unknown,
+        modified,
+        drift: expected.diff(&live_schema(database).await?),
+    })
+}
+
+/// Checksum and outcome of each applied migration, by version
+async fn applied_migrations(database: &Database) -> AppResult<BTreeMap<i64, (Vec<u8>, bool)>> {
+    let tracked: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(database.pool())
+        .await?;
+    if !tracked {
+        return Ok(BTreeMap::new());
+    }
+    let rows = sqlx::query("SELECT version, checksum, success FROM _sqlx_migrations")
+        .fetch_all(database.pool())
+        .await?;
+    rows.iter()
+        .map(|row| Ok((row.try_get("version")?, (row.try_get("checksum")?, row.try_get("success")?))))
+        .collect()
+}
+
+/// Tables, columns, constraints and indexes of the connection's current schema
+async fn live_schema(database: &Database) -> AppResult<Schema> {
+    let mut schema = Schema::default();
+
+    let columns = sqlx::query(
+        "SELECT c.relname::text AS table_name, a.attname::text AS column_name, \
+                format_type(a.atttypid, a.atttypmod) AS data_type, a.attnotnull AS not_null \
+         FROM pg_attribute a \
+         JOIN pg_class c ON c.oid = a.attrelid \
+         JOIN pg_namespace n ON n.oid = c.relnamespace \
+         WHERE n.nspname = current_schema() AND c.relkind IN ('r', 'p') \
+           AND a.attnum > 0 AND NOT a.attisdropped AND c.relname <> '_sqlx_migrations'",
+    )
+    .fetch_all(database.pool())
+    .await?;
+    for row in &columns {
+        let table: String = row.try_get("table_name")?;
+        let spec = ColumnSpec {
+            data_type: row.try_get("data_type")?,
+            nullable: !row.try_get::<bool, _>("not_null")?,
+        };
+        schema
+            .tables
+            .entry(table)
+            .or_default()
+            .columns
+            .insert(row.try_get("column_name")?, spec);
+    }
+
+    let constraints = sqlx::query(
+        "SELECT c.relname::text AS table_name, con.contype::text AS kind, \
+                ARRAY(SELECT a.attname::text FROM unnest(con.conkey) WITH ORDINALITY AS k (attnum, ord) \
+                      JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum \
+                      ORDER BY k.ord) AS columns, \
+                f.relname::text AS referenced_table, \
+                ARRAY(SELECT a.attname::text FROM unnest(con.confkey) WITH ORDINALITY AS k (attnum, ord) \
+                      JOIN pg_attribute a ON a.attrelid = con.confrelid AND a.attnum = k.attnum \
+                      ORDER BY k.ord) AS referenced_columns \
+         FROM pg_constraint con \
+         JOIN pg_class c ON c.oid = con.conrelid \
+         JOIN pg_namespace n ON n.oid = c.relnamespace \
+         LEFT JOIN pg_class f ON f.oid = con.confrelid \
+         WHERE n.nspname = current_schema() AND con.contype IN ('p', 'u', 'f', 'c') \
+           AND c.relname <> '_sqlx_migrations'",
+    )
+    .fetch_all(database.pool())
+    .await?;
+    for row in &constraints {
+        let table: String = row.try_get("table_name")?;
+        let kind: String = row.try_get("kind")?;
+        let columns: Vec<String> = row.try_get("columns")?;
+        let referenced_table: Option<String> = row.try_get("referenced_table")?;
+        let referenced_columns: Vec<String> = row.try_get("referenced_columns")?;
+        let references = referenced_table
+            .as_deref()
+            .map(|referenced| (referenced, referenced_columns.as_slice()));
+        let kind = kind.chars().next().unwrap_or('c');
+        schema
+            .tables
+            .entry(table)
+            .or_default()
+            .constraints
+            .push(describe_constraint(kind, &columns, references));
+    }
+
+    // Indexes backing primary keys and unique constraints are compared as constraints
+    let definitions: Vec<String> = sqlx::query_scalar(
+        "SELECT pg_get_indexdef(i.indexrelid) \
+         FROM pg_index i \
+         JOIN pg_class c ON c.oid = i.indrelid \
+         JOIN pg_namespace n ON n.oid = c.relnamespace \
+         WHERE n.nspname = current_schema() AND c.relname <> '_sqlx_migrations' \
+           AND NOT EXISTS (SELECT 1 FROM pg_constraint con WHERE con.conindid = i.indexrelid)",
+    )
+    .fetch_all(database.pool())
+    .await?;
+    schema
+        .indexes
+        .extend(definitions.iter().filter_map(|definition| Schema::index_from_definition(definition)));
+
+    Ok(schema)
+}
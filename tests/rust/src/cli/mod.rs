@@ -0,0 +1,32 @@
+pub mod bench;
+pub mod dump;
+pub mod seed;
+pub mod migrate;
+pub mod replay;
+
+use clap::{Parser, Subcommand};
+
+/// Command line interface for the application binary
+#[derive(Debug, Parser)]
+#[command(name = "crawler-test-rust", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Available subcommands; running without one starts the application
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the application
+    Serve,
+    /// Export users and notifications with PII replaced by deterministic fake data
+    DumpAnonymized(dump::DumpArgs),
+    /// Generate load against the configured database and cache and report latency percentiles
+    Bench(bench::BenchArgs),
+    /// Fill the database with deterministic fake users and notification history
+    Seed(seed::SeedArgs),
+    /// Apply migrations, or check every database's schema against them
+    Migrate(migrate::MigrateArgs),
+    /// Re-send captured requests to an instance and compare its answers with the captured ones
+    Replay(replay::ReplayArgs),
+}
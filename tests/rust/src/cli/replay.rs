@@ -0,0 +1,188 @@
+use clap::Args;
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, HOST};
+use reqwest::{Client, Method};
+use std::fmt;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::middleware::capture::redact_json;
+use crate::models::{AppError, AppResult, CapturedBody, CapturedExchange, REDACTED};
+use crate::services::StorageService;
+
+/// Options for re-running captured requests
+#[derive(Debug, Args)]
+pub struct ReplayArgs {
+    /// Ids of the captures to replay, in order
+    #[arg(required = true)]
+    pub ids: Vec<Uuid>,
+
+    /// Instance the requests are sent to
+    #[arg(long, default_value = "http://localhost:8080")]
+    pub base_url: String,
+
+    /// Bearer token sent in place of the redacted credentials
+    #[arg(long)]
+    pub token: Option<String>,
+
+    /// How long each request may take
+    #[arg(long, default_value_t = 30)]
+    pub timeout_secs: u64,
+}
+
+/// How one replayed request compared with its capture
+#[derive(Debug)]
+pub struct ReplayOutcome {
+    pub id: Uuid,
+    pub method: String,
+    pub uri: String,
+    pub captured_status: u16,
+    /// None when the request could not be sent
+    pub status: Option<u16>,
+    /// Whether the JSON body matched, when both sides had one
+    pub body_matches: Option<bool>,
+    pub elapsed: Duration,
+    pub note: Option<String>,
+}
+
+impl ReplayOutcome {
+    pub fn matches(&self) -> bool {
+        self.status == Some(self.captured_status) && self.body_matches != Some(false)
+    }
+}
+
+impl fmt::Display for ReplayOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = self.status.map_or("---".to_string(), |status| status.to_string());
+        let body = match self.body_matches {
+            Some(true) => "body matches",
+            Some(false) => "body differs",
+            None => "body not compared",
+        };
+        write!(
+            f,
+            "{} {} {} captured {} now {} ({}) in {:.1?}",
+            self.id, self.method, self.uri, self.captured_status, status, body, self.elapsed
+        )?;
+        if let Some(note) = &self.note {
+            write!(f, ": {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a replay run
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    pub outcomes: Vec<ReplayOutcome>,
+}
+
+impl ReplayReport {
+    pub fn mismatches(&self) -> usize {
+        self.outcomes.iter().filter(|outcome| !outcome.matches()).count()
+    }
+}
+
+/// Re-send captured requests to an instance and compare what it answers with what was captured.
+///
+/// Captures are read from the configured storage. Redacted headers are not
+/// sent, so requests that needed credentials only authenticate when a token
+/// is given; redacted body fields are sent as recorded. A request whose body
+/// was not captured is reported rather than sent without it.
+pub async fn run(config: &AppConfig, args: ReplayArgs) -> AppResult<ReplayReport> {
+    let storage = StorageService::new(&config.storage);
+    let client = Client::builder()
+        .timeout(Duration::from_secs(args.timeout_secs))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to build HTTP client: {}", e)))?;
+    let base_url = args.base_url.trim_end_matches('/');
+
+    let mut report = ReplayReport::default();
+    for id in &args.ids {
+        let content = storage
+            .get(&CapturedExchange::storage_key(*id))
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Capture {} not found", id)))?;
+        let exchange: CapturedExchange = serde_json::from_slice(&content)
+            .map_err(|e| AppError::Internal(format!("Capture {} is unreadable: {}", id, e)))?;
+        report
+            .outcomes
+            .push(replay(&client, base_url, args.token.as_deref(), &exchange).await?);
+    }
+    Ok(report)
+}
+
+async fn replay(
+    client: &Client,
+    base_url: &str,
+    token: Option<&str>,
+    exchange: &CapturedExchange,
+) -> AppResult<ReplayOutcome> {
+    let captured = &exchange.request;
+    let mut outcome = ReplayOutcome {
+        id: exchange.id,
+        method: captured.method.clone(),
+        uri: captured.uri.clone(),
+        captured_status: exchange.response.status,
+        status: None,
+        body_matches: None,
+        elapsed: Duration::ZERO,
+        note: None,
+    };
+    if let CapturedBody::Omitted { bytes, .. } = &captured.body {
+        outcome.note = Some(format!("request body of {} bytes was not captured", bytes));
+        return Ok(outcome);
+    }
+
+    let method = Method::from_bytes(captured.method.as_bytes())
+        .map_err(|_| AppError::Validation(vec![format!("Capture {} has an invalid method", exchange.id)]))?;
+    let mut request = client.request(method, format!("{}{}", base_url, captured.uri));
+    for (name, value) in &captured.headers {
+        if value == REDACTED {
+            continue;
+        }
+        let (Ok(name), Ok(value)) = (HeaderName::try_from(name.as_str()), HeaderValue::from_str(value)) else {
+            continue;
+        };
+        // The client sets these for the instance and body it sends
+        if name == HOST || name == CONTENT_LENGTH {
+            continue;
+        }
+        request = request.header(name, value);
+    }
+    if let Some(token) = token {
+        request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+    }
+    request = match &captured.body {
+        CapturedBody::Json { content } => request.json(content),
+        CapturedBody::Form { content } => request
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(content.clone()),
+        CapturedBody::Empty | CapturedBody::Omitted { .. } => request,
+    };
+
+    let started = Instant::now();
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            outcome.elapsed = started.elapsed();
+            outcome.note = Some(format!("request failed: {}", e));
+            return Ok(outcome);
+        }
+    };
+    outcome.status = Some(response.status().as_u16());
+    let body = response.bytes().await;
+    outcome.elapsed = started.elapsed();
+
+    if let (CapturedBody::Json { content }, Ok(body)) = (&exchange.response.body, body) {
+        outcome.body_matches = Some(match serde_json::from_slice(&body) {
+            Ok(mut live) => {
+                redact_json(&mut live);
+                live == *content
+            }
+            Err(_) => false,
+        });
+    }
+    Ok(outcome)
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use super::{env_or, env_parse};
+use super::email::EmailPolicy;
+use super::lockout::LockoutPolicy;
+use super::password::PasswordPolicy;
+use super::session::SessionPolicies;
+use super::username::UsernamePolicy;
+use super::verification_reminder::VerificationReminderPolicy;
+use crate::models::AppResult;
+
+/// Account lifecycle settings
+#[derive(Debug, Clone)]
+pub struct AccountConfig {
+    /// How long a deletion request can be cancelled before data is erased
+    pub deletion_grace_period: Duration,
+    /// How often due deletions are erased
+    pub erasure_interval: Duration,
+    /// How long soft-deleted users can be restored before they are purged; None, the default, keeps them
+    pub purge_deleted_after: Option<Duration>,
+    /// How often soft-deleted users past that age are purged
+    pub purge_interval: Duration,
+    /// Lifetimes, idle timeouts and concurrency limits of sign-in sessions, per role
+    pub sessions: SessionPolicies,
+    /// How long an emailed password reset link can be used
+    pub password_reset_ttl: Duration,
+    /// Page reset links point at; the token is appended as the `token` query parameter
+    pub password_reset_url: String,
+    /// How long an emailed verification link can be used
+    pub email_verification_ttl: Duration,
+    /// Page verification links point at; the token is appended as the `token` query parameter
+    pub email_verification_url: String,
+    /// Verification emails a user may have re-sent per window
+    pub email_verification_resend_limit: u64,
+    pub email_verification_resend_window: Duration,
+    /// Reminders to users who leave their address unverified, and the deadline for it
+    pub verification_reminders: VerificationReminderPolicy,
+    /// How long a completed bulk operation can be undone
+    pub bulk_undo_window: Duration,
+    /// Longest a temporary role may be granted for
+    pub role_grant_max_duration: Duration,
+    /// How often expired temporary roles are closed and audited
+    pub role_grant_sweep_interval: Duration,
+    /// How long a user's effective permissions are cached; zero turns caching off
+    pub permissions_cache_ttl: Duration,
+    /// Sizing of the bloom filters backing email and username existence checks
+    pub identity_filter_capacity: u64,
+    pub identity_filter_error_rate: f64,
+    pub password_policy: PasswordPolicy,
+    pub lockout_policy: LockoutPolicy,
+    pub email_policy: EmailPolicy,
+    pub username_policy: UsernamePolicy,
+    /// WebAuthn relying party: the domain credentials are scoped to and the origin browsers report
+    pub webauthn_rp_id: String,
+    pub webauthn_rp_name: String,
+    pub webauthn_origin: String,
+    /// How long a WebAuthn challenge, or an external sign-in waiting for its second factor, can be answered
+    pub second_factor_challenge_ttl: Duration,
+    /// Backup codes issued per generation
+    pub backup_code_count: usize,
+    /// Issuer name authenticator apps show next to TOTP codes
+    pub totp_issuer: String,
+    /// Time steps before and after the current one whose codes are still accepted
+    pub totp_skew_steps: u8,
+    /// Email users when their account is signed in to from a client it was not signed in to from before
+    pub new_sign_in_alerts: bool,
+}
+
+impl AccountConfig {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            deletion_grace_period: Duration::from_secs(
+                env_parse("ACCOUNT_DELETION_GRACE_DAYS", 30u64)? * 86_400,
+            ),
+            erasure_interval: Duration::from_secs(env_parse("ACCOUNT_ERASURE_INTERVAL_SECS", 3600)?),
+            purge_deleted_after: Some(env_parse("ACCOUNT_PURGE_DELETED_AFTER_DAYS", 0u64)?)
+                .filter(|days| *days > 0)
+                .map(|days| Duration::from_secs(days * 86_400)),
+            purge_interval: Duration::from_secs(env_parse("ACCOUNT_PURGE_INTERVAL_SECS", 3600)?),
+            sessions: SessionPolicies::from_env()?,
+            password_reset_ttl: Duration::from_secs(env_parse("PASSWORD_RESET_TTL_MINUTES", 60u64)? * 60),
+            password_reset_url: env_or("PASSWORD_RESET_URL", "http://localhost:8080/reset-password"),
+            email_verification_ttl: Duration::from_secs(env_parse("EMAIL_VERIFICATION_TTL_HOURS", 48u64)? * 3600),
+            email_verification_url: env_or("EMAIL_VERIFICATION_URL", "http://localhost:8080/verify-email"),
+            email_verification_resend_limit: env_parse("EMAIL_VERIFICATION_RESEND_LIMIT", 3)?,
+            email_verification_resend_window: Duration::from_secs(
+                env_parse("EMAIL_VERIFICATION_RESEND_WINDOW_MINUTES", 60u64)? * 60,
+            ),
+            verification_reminders: VerificationReminderPolicy::from_env()?,
+            bulk_undo_window: Duration::from_secs(env_parse("BULK_UNDO_WINDOW_MINUTES", 60u64)? * 60),
+            role_grant_max_duration: Duration::from_secs(env_parse("ROLE_GRANT_MAX_HOURS", 72u64)? * 3600),
+            role_grant_sweep_interval: Duration::from_secs(env_parse("ROLE_GRANT_SWEEP_INTERVAL_SECS", 60)?),
+            permissions_cache_ttl: Duration::from_secs(env_parse("PERMISSIONS_CACHE_TTL_SECS", 300)?),
+            identity_filter_capacity: env_parse("IDENTITY_FILTER_CAPACITY", 1_000_000)?,
+            identity_filter_error_rate: env_parse("IDENTITY_FILTER_ERROR_RATE", 0.01)?,
+            password_policy: PasswordPolicy::from_env()?,
+            lockout_policy: LockoutPolicy::from_env()?,
+            email_policy: EmailPolicy::from_env()?,
+            username_policy: UsernamePolicy::from_env()?,
+            webauthn_rp_id: env_or("WEBAUTHN_RP_ID", "localhost"),
+            webauthn_rp_name: env_or("WEBAUTHN_RP_NAME", "Crawler"),
+            webauthn_origin: env_or("WEBAUTHN_ORIGIN", "http://localhost:8080"),
+            second_factor_challenge_ttl: Duration::from_secs(env_parse("SECOND_FACTOR_CHALLENGE_SECS", 300)?),
+            backup_code_count: env_parse("BACKUP_CODE_COUNT", 10)?,
+            totp_issuer: env_or("TOTP_ISSUER", "Crawler"),
+            totp_skew_steps: env_parse("TOTP_SKEW_STEPS", 1)?,
+            new_sign_in_alerts: env_parse("NEW_SIGN_IN_ALERTS", true)?,
+        })
+    }
+}
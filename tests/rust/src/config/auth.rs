@@ -0,0 +1,76 @@
+use std::fmt;
+use std::time::Duration;
+
+use super::{env_or, env_parse};
+use crate::models::{AppError, AppResult};
+
+/// Token signing keys and lifetimes for API authentication
+#[derive(Clone)]
+pub struct AuthConfig {
+    /// HMAC secrets indexed by key id; tokens name the key that signed them
+    pub signing_keys: Vec<(String, String)>,
+    /// Key id new tokens are signed with
+    pub active_key_id: Option<String>,
+    pub issuer: String,
+    pub audience: String,
+    pub access_token_ttl: Duration,
+    pub refresh_token_ttl: Duration,
+    /// How long a rotated API key keeps working alongside its replacement
+    pub api_key_rotation_grace: Duration,
+    /// Lifetime of service account tokens, kept short since they cannot be refreshed or revoked
+    pub service_token_ttl: Duration,
+    /// HMAC secret signing trusted device cookies; trusting devices is off without one
+    pub trusted_device_secret: Option<String>,
+    /// How long a trusted device skips the second factor before it must be trusted again
+    pub trusted_device_ttl: Duration,
+}
+
+impl AuthConfig {
+    /// Load keys from `JWT_SIGNING_KEYS` formatted as `kid:secret,...`
+    pub fn from_env() -> AppResult<Self> {
+        let raw_keys = env_or("JWT_SIGNING_KEYS", "");
+        let mut signing_keys = Vec::new();
+
+        for entry in raw_keys.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (key_id, secret) = entry
+                .split_once(':')
+                .filter(|(key_id, secret)| !key_id.is_empty() && !secret.is_empty())
+                .ok_or_else(|| AppError::Config("JWT_SIGNING_KEYS entries must be kid:secret".to_string()))?;
+            signing_keys.push((key_id.to_string(), secret.to_string()));
+        }
+
+        let newest = signing_keys.last().map(|(key_id, _)| key_id.clone());
+        let active_key_id = Some(env_or("JWT_ACTIVE_KEY_ID", "")).filter(|v| !v.is_empty()).or(newest);
+
+        Ok(Self {
+            signing_keys,
+            active_key_id,
+            issuer: env_or("JWT_ISSUER", "crawler"),
+            audience: env_or("JWT_AUDIENCE", "crawler-api"),
+            access_token_ttl: Duration::from_secs(env_parse("JWT_ACCESS_TTL_SECS", 900)?),
+            refresh_token_ttl: Duration::from_secs(env_parse("JWT_REFRESH_TTL_DAYS", 30u64)? * 86_400),
+            api_key_rotation_grace: Duration::from_secs(env_parse("API_KEY_ROTATION_GRACE_SECS", 86_400)?),
+            service_token_ttl: Duration::from_secs(env_parse("SERVICE_TOKEN_TTL_SECS", 300)?),
+            trusted_device_secret: Some(env_or("TRUSTED_DEVICE_SECRET", "")).filter(|v| !v.is_empty()),
+            trusted_device_ttl: Duration::from_secs(env_parse("TRUSTED_DEVICE_TTL_DAYS", 30u64)? * 86_400),
+        })
+    }
+}
+
+impl fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key_ids: Vec<&str> = self.signing_keys.iter().map(|(key_id, _)| key_id.as_str()).collect();
+        f.debug_struct("AuthConfig")
+            .field("key_ids", &key_ids)
+            .field("active_key_id", &self.active_key_id)
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .field("access_token_ttl", &self.access_token_ttl)
+            .field("refresh_token_ttl", &self.refresh_token_ttl)
+            .field("api_key_rotation_grace", &self.api_key_rotation_grace)
+            .field("service_token_ttl", &self.service_token_ttl)
+            .field("trusted_devices", &self.trusted_device_secret.is_some())
+            .field("trusted_device_ttl", &self.trusted_device_ttl)
+            .finish()
+    }
+}
@@ -0,0 +1,26 @@
+use std::env;
+use std::time::Duration;
+
+use super::env_parse;
+use crate::models::AppResult;
+
+/// Where role permissions and resource rules come from
+#[derive(Debug, Clone)]
+pub struct AuthorizationConfig {
+    /// JSON file holding an array of policy rules, added to the built-in ones
+    pub policy_file: Option<String>,
+    /// Start from the built-in role permissions; off means only configured rules grant anything
+    pub builtin_policies: bool,
+    /// How often rules are re-read from the file and the database
+    pub reload_interval: Duration,
+}
+
+impl AuthorizationConfig {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            policy_file: env::var("AUTHZ_POLICY_FILE").ok().filter(|path| !path.is_empty()),
+            builtin_policies: env_parse("AUTHZ_BUILTIN_POLICIES", true)?,
+            reload_interval: Duration::from_secs(env_parse("AUTHZ_RELOAD_INTERVAL_SECS", 60)?),
+        })
+    }
+}
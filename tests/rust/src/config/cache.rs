@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use super::env_parse;
+use crate::models::AppResult;
+
+/// How long each kind of entity stays cached; zero disables caching it
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    pub user_ttl: Duration,
+    pub template_ttl: Duration,
+    pub segment_ttl: Duration,
+    /// How often cached users are checked against the database; zero turns the check off
+    pub verify_interval: Duration,
+    /// Cached users compared per check
+    pub verify_sample_size: usize,
+    /// Evict entries found to differ from the database instead of only reporting them
+    pub verify_auto_evict: bool,
+}
+
+impl CacheConfig {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            user_ttl: Duration::from_secs(env_parse("CACHE_USER_TTL_SECS", 300)?),
+            template_ttl: Duration::from_secs(env_parse("CACHE_TEMPLATE_TTL_SECS", 3600)?),
+            segment_ttl: Duration::from_secs(env_parse("CACHE_SEGMENT_TTL_SECS", 60)?),
+            verify_interval: Duration::from_secs(env_parse("CACHE_VERIFY_INTERVAL_SECS", 600)?),
+            verify_sample_size: env_parse::<usize>("CACHE_VERIFY_SAMPLE_SIZE", 100)?.max(1),
+            verify_auto_evict: env_parse("CACHE_VERIFY_AUTO_EVICT", false)?,
+        })
+    }
+}
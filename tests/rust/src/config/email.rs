@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+
+use super::{env_or, env_parse};
+use crate::models::AppResult;
+
+/// Throwaway-mailbox providers refused unless `EMAIL_BLOCKED_DOMAINS` says otherwise
+const DEFAULT_BLOCKED_DOMAINS: &str = "mailinator.com,guerrillamail.com,10minutemail.com,tempmail.com,\
+    temp-mail.org,yopmail.com,trashmail.com,sharklasers.com,getnada.com,dispostable.com";
+
+/// Domains whose mailboxes ignore dots and `+tag` suffixes in the local part
+const GMAIL_DOMAINS: [&str; 2] = ["gmail.com", "googlemail.com"];
+
+/// How email addresses are canonicalized and which domains may register
+#[derive(Debug, Clone)]
+pub struct EmailPolicy {
+    /// Strip dots and `+tag` suffixes from Gmail addresses, so one inbox is one account
+    pub canonicalize_gmail: bool,
+    pub blocked_domains: HashSet<String>,
+}
+
+impl EmailPolicy {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            canonicalize_gmail: env_parse("EMAIL_CANONICALIZE_GMAIL", false)?,
+            blocked_domains: env_or("EMAIL_BLOCKED_DOMAINS", DEFAULT_BLOCKED_DOMAINS)
+                .split(',')
+                .map(|domain| domain.trim().trim_start_matches('@').to_lowercase())
+                .filter(|domain| !domain.is_empty())
+                .collect(),
+        })
+    }
+
+    /// The form an address is stored and compared in: trimmed and lowercased,
+    /// with Gmail aliases folded onto their inbox when enabled
+    pub fn normalize(&self, email: &str) -> String {
+        let email = email.trim().to_lowercase();
+        let Some((local, domain)) = email.rsplit_once('@') else {
+            return email;
+        };
+        if !self.canonicalize_gmail || !GMAIL_DOMAINS.contains(&domain) {
+            return email;
+        }
+        let local = local.split('+').next().unwrap_or(local).replace('.', "");
+        format!("{}@gmail.com", local)
+    }
+
+    /// Problems with a normalized address, empty if it may register
+    pub fn validate(&self, email: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        let Some((_, domain)) = email.rsplit_once('@') else {
+            return errors;
+        };
+        // Subdomains of a blocked domain are blocked too
+        let blocked = domain
+            .match_indices('.')
+            .map(|(dot, _)| &domain[dot + 1..])
+            .chain(std::iter::once(domain))
+            .any(|candidate| self.blocked_domains.contains(candidate));
+        if blocked {
+            errors.push(format!("Email addresses at {} are not accepted", domain));
+        }
+        errors
+    }
+}
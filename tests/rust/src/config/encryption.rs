@@ -0,0 +1,57 @@
+use std::fmt;
+use std::time::Duration;
+
+use super::{env_or, env_parse};
+use crate::models::{AppError, AppResult};
+
+/// Key material and rotation settings for field-level PII encryption
+#[derive(Clone)]
+pub struct EncryptionConfig {
+    /// Base64-encoded 256-bit keys indexed by version
+    pub keys: Vec<(u32, String)>,
+    /// Version used for all new ciphertexts
+    pub active_key_version: u32,
+    /// Number of rows re-encrypted per rotation batch
+    pub rotation_batch_size: i64,
+    /// Delay between rotation passes
+    pub rotation_interval: Duration,
+}
+
+impl EncryptionConfig {
+    /// Load keys from `PII_ENCRYPTION_KEYS` formatted as `version:base64key,...`
+    pub fn from_env() -> AppResult<Self> {
+        let raw_keys = env_or("PII_ENCRYPTION_KEYS", "");
+        let mut keys = Vec::new();
+
+        for entry in raw_keys.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (version, key) = entry.split_once(':').ok_or_else(|| {
+                AppError::Config("PII_ENCRYPTION_KEYS entries must be version:key".to_string())
+            })?;
+            let version = version.parse::<u32>().map_err(|_| {
+                AppError::Config(format!("Invalid PII key version: {}", version))
+            })?;
+            keys.push((version, key.to_string()));
+        }
+
+        let newest = keys.iter().map(|(version, _)| *version).max().unwrap_or(1);
+
+        Ok(Self {
+            keys,
+            active_key_version: env_parse("PII_ACTIVE_KEY_VERSION", newest)?,
+            rotation_batch_size: env_parse("PII_ROTATION_BATCH_SIZE", 500)?,
+            rotation_interval: Duration::from_secs(env_parse("PII_ROTATION_INTERVAL_SECS", 300)?),
+        })
+    }
+}
+
+impl fmt::Debug for EncryptionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let versions: Vec<u32> = self.keys.iter().map(|(version, _)| *version).collect();
+        f.debug_struct("EncryptionConfig")
+            .field("key_versions", &versions)
+            .field("active_key_version", &self.active_key_version)
+            .field("rotation_batch_size", &self.rotation_batch_size)
+            .field("rotation_interval", &self.rotation_interval)
+            .finish()
+    }
+}
@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use super::env_parse;
+use crate::models::AppResult;
+
+/// Background exports and how long their files are kept
+#[derive(Debug, Clone)]
+pub struct ExportConfig {
+    /// How long a finished export can be downloaded before its file is deleted
+    pub retention: Duration,
+    /// How often the runner looks for queued exports and expired files
+    pub poll_interval: Duration,
+    /// A running export not finished after this long is taken to have died with its instance
+    pub stale_after: Duration,
+    /// Tries before a repeatedly interrupted export is given up
+    pub max_attempts: i32,
+    /// Exports a user may have queued or running at once
+    pub max_pending_per_user: i64,
+}
+
+impl ExportConfig {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            retention: Duration::from_secs(env_parse("EXPORT_RETENTION_HOURS", 72u64)? * 3600),
+            poll_interval: Duration::from_secs(env_parse("EXPORT_POLL_INTERVAL_SECS", 5)?),
+            stale_after: Duration::from_secs(env_parse("EXPORT_STALE_AFTER_SECS", 900)?),
+            max_attempts: env_parse("EXPORT_MAX_ATTEMPTS", 3)?,
+            max_pending_per_user: env_parse("EXPORT_MAX_PENDING_PER_USER", 3)?,
+        })
+    }
+}
@@ -0,0 +1,47 @@
+use super::{env_or, env_parse};
+use crate::models::{AppError, AppResult};
+
+/// How ids of new entities are generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdStrategy {
+    /// Random UUIDv4, as every id was before strategies could be chosen
+    UuidV4,
+    /// UUIDv7: millisecond timestamp first, so new rows land at the end of their indexes
+    UuidV7,
+    /// Snowflake-style UUIDv8: timestamp, per-millisecond sequence and node id
+    Snowflake,
+}
+
+impl IdStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdStrategy::UuidV4 => "uuid_v4",
+            IdStrategy::UuidV7 => "uuid_v7",
+            IdStrategy::Snowflake => "snowflake",
+        }
+    }
+}
+
+/// Id generation settings
+#[derive(Debug, Clone)]
+pub struct IdConfig {
+    pub strategy: IdStrategy,
+    /// Tells apart Snowflake ids minted in the same millisecond on different instances; 0 to 1023
+    pub node_id: u16,
+}
+
+impl IdConfig {
+    pub fn from_env() -> AppResult<Self> {
+        let strategy = match env_or("ID_STRATEGY", "uuid_v7").as_str() {
+            "uuid_v4" => IdStrategy::UuidV4,
+            "uuid_v7" => IdStrategy::UuidV7,
+            "snowflake" => IdStrategy::Snowflake,
+            other => return Err(AppError::Config(format!("Unknown ID_STRATEGY: {}", other))),
+        };
+        let node_id: u16 = env_parse("ID_NODE_ID", 0)?;
+        if node_id > 1023 {
+            return Err(AppError::Config(format!("ID_NODE_ID must be at most 1023: {}", node_id)));
+        }
+        Ok(Self { strategy, node_id })
+    }
+}
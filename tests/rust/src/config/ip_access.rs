@@ -0,0 +1,173 @@
+use ipnet::IpNet;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use super::{env_or, env_pairs, env_parse};
+use crate::models::{AppError, AppResult, UserRole};
+
+/// Why an address was refused, used as the label of the blocked requests counter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpBlock {
+    Denied,
+    NotAllowed,
+}
+
+impl IpBlock {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IpBlock::Denied => "denylist",
+            IpBlock::NotAllowed => "allowlist",
+        }
+    }
+}
+
+/// Address ranges let in and kept out; a bare address is a range of one
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IpAccessList {
+    /// When non-empty, only addresses in these ranges are let in
+    #[serde(default, deserialize_with = "deserialize_ranges")]
+    pub allow: Vec<IpNet>,
+    /// Refused even when also allowed
+    #[serde(default, deserialize_with = "deserialize_ranges")]
+    pub deny: Vec<IpNet>,
+}
+
+impl IpAccessList {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// An unknown address is in no range, so only allowlists refuse it
+    pub fn check(&self, address: Option<IpAddr>) -> Result<(), IpBlock> {
+        let contains = |ranges: &[IpNet]| {
+            address.is_some_and(|address| ranges.iter().any(|range| range.contains(&address)))
+        };
+        if contains(&self.deny) {
+            return Err(IpBlock::Denied);
+        }
+        if !self.allow.is_empty() && !contains(&self.allow) {
+            return Err(IpBlock::NotAllowed);
+        }
+        Ok(())
+    }
+
+    fn extend(&mut self, other: IpAccessList) {
+        self.allow.extend(other.allow);
+        self.deny.extend(other.deny);
+    }
+}
+
+/// Lists every request is checked against, plus lists for callers signed in with a role.
+///
+/// A rules file has the same shape: `{"allow": [...], "deny": [...],
+/// "roles": {"admin": {"allow": ["10.0.0.0/8"]}}}`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IpAccessRules {
+    #[serde(flatten)]
+    pub global: IpAccessList,
+    #[serde(default)]
+    pub roles: HashMap<String, IpAccessList>,
+}
+
+impl IpAccessRules {
+    pub fn is_empty(&self) -> bool {
+        self.global.is_empty() && self.roles.values().all(IpAccessList::is_empty)
+    }
+
+    /// A caller must pass the global lists and, when signed in, their role's
+    pub fn check(&self, address: Option<IpAddr>, role: Option<&UserRole>) -> Result<(), IpBlock> {
+        self.global.check(address)?;
+        match role.and_then(|role| self.roles.get(role.as_str())) {
+            Some(list) => list.check(address),
+            None => Ok(()),
+        }
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        self.roles
+            .keys()
+            .filter(|role| role.parse::<UserRole>().is_err())
+            .map(|role| format!("Unknown role in IP access rules: {}", role))
+            .collect()
+    }
+
+    pub fn merge(&mut self, other: IpAccessRules) {
+        self.global.extend(other.global);
+        for (role, list) in other.roles {
+            self.roles.entry(role).or_default().extend(list);
+        }
+    }
+}
+
+/// Which client addresses may reach the API
+#[derive(Debug, Clone)]
+pub struct IpAccessConfig {
+    /// Rules from the environment, always in force
+    pub rules: IpAccessRules,
+    /// JSON file with further rules, re-read while running
+    pub rules_file: Option<String>,
+    pub reload_interval: Duration,
+}
+
+impl IpAccessConfig {
+    /// Global lists are comma-separated ranges in `IP_ALLOWLIST` and
+    /// `IP_DENYLIST`; per-role lists are `role=range range,...` pairs in
+    /// `IP_ROLE_ALLOWLISTS` and `IP_ROLE_DENYLISTS`
+    pub fn from_env() -> AppResult<Self> {
+        let mut rules = IpAccessRules {
+            global: IpAccessList {
+                allow: parse_ranges("IP_ALLOWLIST", env_or("IP_ALLOWLIST", "").split(','))?,
+                deny: parse_ranges("IP_DENYLIST", env_or("IP_DENYLIST", "").split(','))?,
+            },
+            roles: HashMap::new(),
+        };
+        for (role, ranges) in env_pairs("IP_ROLE_ALLOWLISTS")? {
+            let allow = parse_ranges("IP_ROLE_ALLOWLISTS", ranges.split_whitespace())?;
+            rules.roles.entry(role).or_default().allow.extend(allow);
+        }
+        for (role, ranges) in env_pairs("IP_ROLE_DENYLISTS")? {
+            let deny = parse_ranges("IP_ROLE_DENYLISTS", ranges.split_whitespace())?;
+            rules.roles.entry(role).or_default().deny.extend(deny);
+        }
+        let errors = rules.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Config(errors.join("; ")));
+        }
+
+        Ok(Self {
+            rules,
+            rules_file: std::env::var("IP_RULES_FILE").ok().filter(|path| !path.is_empty()),
+            reload_interval: Duration::from_secs(env_parse("IP_RULES_RELOAD_INTERVAL_SECS", 30)?),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.rules.is_empty() || self.rules_file.is_some()
+    }
+}
+
+fn parse_range(raw: &str) -> Option<IpNet> {
+    raw.parse::<IpNet>()
+        .ok()
+        .or_else(|| raw.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+pub(super) fn parse_ranges<'a>(key: &str, raw: impl Iterator<Item = &'a str>) -> AppResult<Vec<IpNet>> {
+    raw.map(str::trim)
+        .filter(|range| !range.is_empty())
+        .map(|range| {
+            parse_range(range).ok_or_else(|| AppError::Config(format!("{} has an invalid range: {}", key, range)))
+        })
+        .collect()
+}
+
+fn deserialize_ranges<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<IpNet>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|range| {
+            parse_range(range).ok_or_else(|| serde::de::Error::custom(format!("invalid range: {}", range)))
+        })
+        .collect()
+}
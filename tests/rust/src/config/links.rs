@@ -0,0 +1,31 @@
+use std::fmt;
+
+use super::{env_or, env_var};
+use crate::models::AppResult;
+
+/// Settings for links sent to users outside a session
+#[derive(Clone)]
+pub struct LinkConfig {
+    /// Externally reachable base URL links point at
+    pub public_base_url: String,
+    /// Key for time-limited signed links; signed links are unavailable without it
+    pub signing_secret: Option<String>,
+}
+
+impl LinkConfig {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            public_base_url: env_or("PUBLIC_BASE_URL", "http://localhost:8080"),
+            signing_secret: env_var("URL_SIGNING_SECRET").filter(|secret| !secret.is_empty()),
+        })
+    }
+}
+
+impl fmt::Debug for LinkConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LinkConfig")
+            .field("public_base_url", &self.public_base_url)
+            .field("signing_secret", &self.signing_secret.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
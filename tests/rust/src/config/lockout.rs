@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use super::env_parse;
+use crate::models::AppResult;
+
+/// When repeated failed sign-ins lock an account, and for how long
+#[derive(Debug, Clone)]
+pub struct LockoutPolicy {
+    /// Consecutive failed sign-ins, wrong passwords and wrong second factors alike, that lock the account
+    pub threshold: i32,
+    /// How long a lockout lasts before the account unlocks by itself
+    pub duration: Duration,
+    /// How often expired lockouts are cleared
+    pub sweep_interval: Duration,
+    /// How far back failed sign-ins are aggregated for the throttling dashboard
+    pub analytics_retention: Duration,
+    /// Recent failed sign-ins kept in the security event stream
+    pub failure_stream_length: usize,
+    /// Failed sign-ins from one client address, across accounts, after which it is refused; 0 never refuses
+    pub address_threshold: i64,
+    /// How far back failed sign-ins from an address are counted, and how long it is refused
+    pub address_window: Duration,
+}
+
+impl LockoutPolicy {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            threshold: env_parse::<i32>("LOCKOUT_THRESHOLD", 5)?.max(1),
+            duration: Duration::from_secs(env_parse("LOCKOUT_DURATION_MINUTES", 15u64)? * 60),
+            sweep_interval: Duration::from_secs(env_parse("LOCKOUT_SWEEP_INTERVAL_SECS", 60)?),
+            analytics_retention: Duration::from_secs(
+                env_parse("LOGIN_ANALYTICS_RETENTION_HOURS", 168u64)?.max(1) * 3600,
+            ),
+            failure_stream_length: env_parse("LOGIN_FAILURE_STREAM_LENGTH", 10_000)?,
+            address_threshold: env_parse("LOCKOUT_ADDRESS_THRESHOLD", 50)?,
+            address_window: Duration::from_secs(env_parse("LOCKOUT_ADDRESS_WINDOW_MINUTES", 15u64)?.max(1) * 60),
+        })
+    }
+}
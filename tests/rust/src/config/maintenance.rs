@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use super::{env_or, env_parse};
+use crate::models::AppResult;
+
+/// Read-only mode, for database maintenance while reads keep being served,
+/// and maintenance mode, closing the API to everyone but admins
+#[derive(Debug, Clone)]
+pub struct MaintenanceConfig {
+    /// Start read-only, as though an administrator had switched the mode on
+    pub read_only: bool,
+    /// Told to callers whose writes are refused
+    pub read_only_reason: String,
+    /// Start in maintenance mode, as though an administrator had switched it on
+    pub maintenance_mode: bool,
+    /// Told to callers turned away by maintenance mode
+    pub maintenance_reason: String,
+    /// Whether reads keep being served in maintenance mode when it is switched on by configuration
+    pub maintenance_allow_reads: bool,
+    /// How often each instance picks up either mode switched on or off elsewhere
+    pub sync_interval: Duration,
+}
+
+impl MaintenanceConfig {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            read_only: env_parse("READ_ONLY_MODE", false)?,
+            read_only_reason: env_or("READ_ONLY_REASON", "Scheduled maintenance"),
+            maintenance_mode: env_parse("MAINTENANCE_MODE", false)?,
+            maintenance_reason: env_or("MAINTENANCE_REASON", "Down for maintenance"),
+            maintenance_allow_reads: env_parse("MAINTENANCE_ALLOW_READS", true)?,
+            sync_interval: Duration::from_secs(env_parse("READ_ONLY_SYNC_INTERVAL_SECS", 5)?),
+        })
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::{env_or, env_parse, env_var, RouteGroup};
+use uuid::Uuid;
+
+use crate::models::{AppError, AppResult};
+
+/// A layer of the HTTP middleware pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MiddlewareLayer {
+    /// Request deadline propagation
+    Deadline,
+    Cors,
+    /// Per-phase latency breakdown and histograms
+    Latency,
+    /// A log line per request
+    Logging,
+    Tenant,
+    Auth,
+    IpAccess,
+    RateLimit,
+    /// Double-submit token checks on mutating requests from signed-in sessions
+    Csrf,
+    /// Replaying the response to a repeated `Idempotency-Key`
+    Idempotency,
+    Quota,
+    Deprecation,
+    /// Deciding which requests' spans are kept
+    Sampling,
+    /// Recording chosen requests and their responses for replay
+    Capture,
+    /// Turning away all but admins while maintenance mode is on
+    Maintenance,
+}
+
+impl MiddlewareLayer {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MiddlewareLayer::Deadline => "deadline",
+            MiddlewareLayer::Cors => "cors",
+            MiddlewareLayer::Latency => "latency",
+            MiddlewareLayer::Logging => "logging",
+            MiddlewareLayer::Tenant => "tenant",
+            MiddlewareLayer::Auth => "auth",
+            MiddlewareLayer::IpAccess => "ip_access",
+            MiddlewareLayer::RateLimit => "rate_limit",
+            MiddlewareLayer::Csrf => "csrf",
+            MiddlewareLayer::Idempotency => "idempotency",
+            MiddlewareLayer::Quota => "quota",
+            MiddlewareLayer::Deprecation => "deprecation",
+            MiddlewareLayer::Sampling => "sampling",
+            MiddlewareLayer::Capture => "capture",
+            MiddlewareLayer::Maintenance => "maintenance",
+        }
+    }
+
+    /// Layers that must run before this one whenever both are in a stack
+    fn runs_after(&self) -> &'static [MiddlewareLayer] {
+        match self {
+            MiddlewareLayer::Auth => &[MiddlewareLayer::Tenant],
+            MiddlewareLayer::IpAccess
+            | MiddlewareLayer::RateLimit
+            | MiddlewareLayer::Quota
+            | MiddlewareLayer::Csrf
+            | MiddlewareLayer::Sampling
+            | MiddlewareLayer::Capture
+            | MiddlewareLayer::Maintenance => &[MiddlewareLayer::Auth],
+            MiddlewareLayer::Idempotency => &[MiddlewareLayer::Tenant, MiddlewareLayer::Auth],
+            _ => &[],
+        }
+    }
+
+    /// Layers that make no sense without this one
+    fn requires(&self) -> &'static [MiddlewareLayer] {
+        match self {
+            MiddlewareLayer::Auth => &[MiddlewareLayer::Tenant],
+            MiddlewareLayer::Idempotency | MiddlewareLayer::Csrf => &[MiddlewareLayer::Auth],
+            _ => &[],
+        }
+    }
+}
+
+impl FromStr for MiddlewareLayer {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "deadline" => Ok(MiddlewareLayer::Deadline),
+            "cors" => Ok(MiddlewareLayer::Cors),
+            "latency" => Ok(MiddlewareLayer::Latency),
+            "logging" => Ok(MiddlewareLayer::Logging),
+            "tenant" => Ok(MiddlewareLayer::Tenant),
+            "auth" => Ok(MiddlewareLayer::Auth),
+            "ip_access" => Ok(MiddlewareLayer::IpAccess),
+            "rate_limit" => Ok(MiddlewareLayer::RateLimit),
+            "csrf" => Ok(MiddlewareLayer::Csrf),
+            "idempotency" => Ok(MiddlewareLayer::Idempotency),
+            "quota" => Ok(MiddlewareLayer::Quota),
+            "deprecation" => Ok(MiddlewareLayer::Deprecation),
+            "sampling" => Ok(MiddlewareLayer::Sampling),
+            "capture" => Ok(MiddlewareLayer::Capture),
+            "maintenance" => Ok(MiddlewareLayer::Maintenance),
+            other => Err(format!("Unknown middleware layer: {}", other)),
+        }
+    }
+}
+
+/// The layers before this setting existed, in the order they ran, with CSRF checks ahead of replay
+/// and trace sampling, capture and maintenance mode as soon as the caller is known
+const DEFAULT_STACK: &str = concat!(
+    "deadline,cors,latency,tenant,auth,sampling,capture,maintenance,",
+    "ip_access,rate_limit,csrf,idempotency,quota,deprecation"
+);
+
+/// Health checks are polled constantly and rarely interesting; admin actions are rare and always are
+const DEFAULT_ROUTE_SAMPLE_RATES: &str = "/admin/=1,/status=0.01,/version=0.01";
+
+/// Which origins browsers may call the API from
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// Exact origins, or `*` for any; CORS headers are never sent when empty
+    pub allowed_origins: Vec<String>,
+    pub allow_credentials: bool,
+    /// How long browsers may cache a preflight answer
+    pub max_age: Duration,
+}
+
+/// Double-submit CSRF tokens for browser clients
+#[derive(Debug, Clone)]
+pub struct CsrfConfig {
+    /// Mutating requests from a signed-in session are refused without a valid token
+    pub enabled: bool,
+    /// How long an issued token stays valid for its session
+    pub token_ttl: Duration,
+    /// Mark the token cookie `Secure`, so browsers only send it over HTTPS
+    pub secure_cookie: bool,
+}
+
+/// Which requests' spans are kept
+#[derive(Debug, Clone)]
+pub struct TraceSamplingConfig {
+    /// Share of requests sampled when no route rule matches, from 0 to 1
+    pub default_rate: f64,
+    /// Path prefixes and their rates, longest prefix first so it wins
+    pub routes: Vec<(String, f64)>,
+    /// Keep failed requests whatever their rate
+    pub sample_errors: bool,
+    /// Header forcing a request to be sampled, honoured for `force_users` only
+    pub force_header: String,
+    pub force_users: HashSet<Uuid>,
+}
+
+/// Recording requests and responses of chosen callers or routes, to reproduce bugs
+#[derive(Debug, Clone)]
+pub struct RequestCaptureConfig {
+    /// Callers whose requests are recorded
+    pub users: HashSet<Uuid>,
+    /// Path prefixes whose requests are recorded, whoever makes them
+    pub routes: Vec<String>,
+    /// Larger bodies are recorded by size only
+    pub max_body_bytes: usize,
+}
+
+impl RequestCaptureConfig {
+    /// Capture is opt-in: nothing is recorded until a user or route is chosen
+    pub fn is_enabled(&self) -> bool {
+        !self.users.is_empty() || !self.routes.is_empty()
+    }
+}
+
+/// The middleware pipeline of each route group
+#[derive(Clone)]
+pub struct MiddlewareConfig {
+    /// Layers in the order a request passes through them
+    pub stacks: HashMap<RouteGroup, Vec<MiddlewareLayer>>,
+    pub cors: CorsConfig,
+    pub csrf: CsrfConfig,
+    pub sampling: TraceSamplingConfig,
+    pub capture: RequestCaptureConfig,
+    /// How long a response is kept for replay to a repeated idempotency key
+    pub idempotency_ttl: Duration,
+}
+
+impl MiddlewareConfig {
+    /// Every group uses `MIDDLEWARE_STACK`, a comma-separated list of layers
+    /// in the order requests pass through them, unless
+    /// `MIDDLEWARE_STACK_<GROUP>` gives it one of its own. Layers whose
+    /// component is not configured, such as auth without signing keys, are
+    /// skipped.
+    pub fn from_env() -> AppResult<Self> {
+        let default = parse_stack("MIDDLEWARE_STACK", &env_or("MIDDLEWARE_STACK", DEFAULT_STACK))?;
+        let mut stacks = HashMap::new();
+        for group in RouteGroup::ALL {
+            let key = format!("MIDDLEWARE_STACK_{}", group.as_str().to_uppercase());
+            let stack = match env_var(&key) {
+                Some(raw) => parse_stack(&key, &raw)?,
+                None => default.clone(),
+            };
+            stacks.insert(group, stack);
+        }
+
+        let allowed_origins = env_or("CORS_ALLOWED_ORIGINS", "")
+            .split(',')
+            .map(|origin| origin.trim().trim_end_matches('/').to_string())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        let cors = CorsConfig {
+            allowed_origins,
+            allow_credentials: env_parse("CORS_ALLOW_CREDENTIALS", false)?,
+            max_age: Duration::from_secs(env_parse("CORS_MAX_AGE_SECS", 600)?),
+        };
+        if cors.allow_credentials && cors.allowed_origins.iter().any(|origin| origin == "*") {
+            return Err(AppError::Config(
+                "CORS_ALLOW_CREDENTIALS cannot be combined with a * origin".to_string(),
+            ));
+        }
+
+        let csrf = CsrfConfig {
+            enabled: env_parse("CSRF_ENABLED", false)?,
+            token_ttl: Duration::from_secs(env_parse("CSRF_TOKEN_TTL_SECS", 12 * 3600)?),
+            secure_cookie: env_parse("CSRF_COOKIE_SECURE", true)?,
+        };
+
+        let mut routes = parse_rates(
+            "TRACE_ROUTE_SAMPLE_RATES",
+            &env_or("TRACE_ROUTE_SAMPLE_RATES", DEFAULT_ROUTE_SAMPLE_RATES),
+        )?;
+        routes.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+        let sampling = TraceSamplingConfig {
+            default_rate: parse_rate("TRACE_SAMPLE_RATE", &env_or("TRACE_SAMPLE_RATE", "0.1"))?,
+            routes,
+            sample_errors: env_parse("TRACE_SAMPLE_ERRORS", true)?,
+            force_header: env_or("TRACE_FORCE_HEADER", "x-trace-sample").to_ascii_lowercase(),
+            force_users: parse_users("TRACE_FORCE_USERS")?,
+        };
+
+        let capture = RequestCaptureConfig {
+            users: parse_users("REQUEST_CAPTURE_USERS")?,
+            routes: env_or("REQUEST_CAPTURE_ROUTES", "")
+                .split(',')
+                .map(str::trim)
+                .filter(|prefix| !prefix.is_empty())
+                .map(String::from)
+                .collect(),
+            max_body_bytes: env_parse("REQUEST_CAPTURE_MAX_BODY_BYTES", 64 * 1024)?,
+        };
+
+        Ok(Self {
+            stacks,
+            cors,
+            csrf,
+            sampling,
+            capture,
+            idempotency_ttl: Duration::from_secs(env_parse("IDEMPOTENCY_TTL_HOURS", 24u64)? * 3600),
+        })
+    }
+
+    pub fn stack(&self, group: RouteGroup) -> &[MiddlewareLayer] {
+        self.stacks.get(&group).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl fmt::Debug for MiddlewareConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut stacks: Vec<(&str, Vec<&str>)> = self
+            .stacks
+            .iter()
+            .map(|(group, layers)| (group.as_str(), layers.iter().map(MiddlewareLayer::as_str).collect()))
+            .collect();
+        stacks.sort();
+        f.debug_struct("MiddlewareConfig")
+            .field("stacks", &stacks)
+            .field("cors", &self.cors)
+            .field("csrf", &self.csrf)
+            .field("sampling", &self.sampling)
+            .field("capture", &self.capture)
+            .field("idempotency_ttl", &self.idempotency_ttl)
+            .finish()
+    }
+}
+
+/// Comma-separated user ids
+fn parse_users(key: &str) -> AppResult<HashSet<Uuid>> {
+    env_or(key, "")
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse()
+                .map_err(|_| AppError::Config(format!("{} has an invalid user id: {}", key, id)))
+        })
+        .collect()
+}
+
+/// `prefix=rate,...` pairs in the order given
+fn parse_rates(key: &str, raw: &str) -> AppResult<Vec<(String, f64)>> {
+    let mut rates = Vec::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (prefix, rate) = entry
+            .split_once('=')
+            .ok_or_else(|| AppError::Config(format!("{} entries must be prefix=rate", key)))?;
+        rates.push((prefix.trim().to_string(), parse_rate(key, rate.trim())?));
+    }
+    Ok(rates)
+}
+
+fn parse_rate(key: &str, raw: &str) -> AppResult<f64> {
+    raw.parse::<f64>()
+        .ok()
+        .filter(|rate| (0.0..=1.0).contains(rate))
+        .ok_or_else(|| AppError::Config(format!("{} rates must be between 0 and 1: {}", key, raw)))
+}
+
+/// Parse a stack and check each layer has what it depends on, in front of it
+fn parse_stack(key: &str, raw: &str) -> AppResult<Vec<MiddlewareLayer>> {
+    let mut stack: Vec<MiddlewareLayer> = Vec::new();
+    for name in raw.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let layer: MiddlewareLayer = name.parse().map_err(|e| AppError::Config(format!("{}: {}", key, e)))?;
+        if stack.contains(&layer) {
+            return Err(AppError::Config(format!("{} lists {} twice", key, name)));
+        }
+        stack.push(layer);
+    }
+
+    let mut errors = Vec::new();
+    for (position, layer) in stack.iter().enumerate() {
+        for required in layer.requires() {
+            if !stack.contains(required) {
+                errors.push(format!("{} needs {}", layer.as_str(), required.as_str()));
+            }
+        }
+        for before in layer.runs_after() {
+            if stack[position..].contains(before) {
+                errors.push(format!("{} must come after {}", layer.as_str(), before.as_str()));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(AppError::Config(format!("{}: {}", key, errors.join("; "))));
+    }
+    Ok(stack)
+}
@@ -0,0 +1,229 @@
+pub mod notification;
+pub mod encryption;
+pub mod search;
+pub mod outbox;
+pub mod quota;
+pub mod report;
+pub mod account;
+pub mod cache;
+pub mod password;
+pub mod lockout;
+pub mod verification_reminder;
+pub mod links;
+pub mod email;
+pub mod username;
+pub mod auth;
+pub mod oauth;
+pub mod saml;
+pub mod residency;
+pub mod rate_limit;
+pub mod authorization;
+pub mod ip_access;
+pub mod proxy;
+pub mod session;
+pub mod storage;
+pub mod export;
+pub mod status;
+pub mod secrets;
+pub mod middleware;
+pub mod tenant_limits;
+pub mod maintenance;
+pub mod subsystems;
+pub mod ids;
+
+pub use notification::{ChannelBudget, ChannelRouting, NotificationBudgets, NotificationConfig};
+pub use encryption::EncryptionConfig;
+pub use search::SearchConfig;
+pub use outbox::{OutboxConfig, OutboxPublisherKind};
+pub use quota::QuotaConfig;
+pub use report::{ReportConfig, ReportFormat};
+pub use account::AccountConfig;
+pub use cache::CacheConfig;
+pub use password::PasswordPolicy;
+pub use lockout::LockoutPolicy;
+pub use verification_reminder::VerificationReminderPolicy;
+pub use email::EmailPolicy;
+pub use username::{UsernamePolicy, UsernameViolation};
+pub use links::LinkConfig;
+pub use auth::AuthConfig;
+pub use oauth::{OAuthConfig, OAuthProviderConfig, OAuthProviderKind};
+pub use saml::{SamlAttributeMap, SamlConfig, SamlIdentityProvider};
+pub use residency::ResidencyConfig;
+pub use rate_limit::{RateLimit, RateLimitConfig, RouteGroup};
+pub use authorization::AuthorizationConfig;
+pub use session::{SessionPolicies, SessionPolicy};
+pub use storage::StorageConfig;
+pub use export::ExportConfig;
+pub use status::StatusConfig;
+pub use secrets::{publish_secrets, SecretReference, SecretsConfig};
+pub use middleware::{
+    CorsConfig, CsrfConfig, MiddlewareConfig, MiddlewareLayer, RequestCaptureConfig, TraceSamplingConfig,
+};
+pub use tenant_limits::{TenantLimits, TenantLimitsConfig};
+pub use maintenance::MaintenanceConfig;
+pub use subsystems::SubsystemsConfig;
+pub use ids::{IdConfig, IdStrategy};
+pub use ip_access::{IpAccessConfig, IpAccessList, IpAccessRules, IpBlock};
+pub use proxy::ProxyConfig;
+
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::models::{AppError, AppResult};
+
+/// Top-level application configuration loaded from the environment
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    /// Deployment profile such as development, staging or production
+    pub profile: String,
+    pub log_level: String,
+    pub database_url: String,
+    pub redis_url: String,
+    pub http_addr: String,
+    /// Longest a request may run before its work is abandoned
+    pub http_request_timeout: Duration,
+    /// How long background work gets to reach a checkpoint after shutdown is signalled
+    pub shutdown_grace_period: Duration,
+    /// How long a user counts as online after their last heartbeat
+    pub presence_ttl: Duration,
+    /// Distinct label combinations kept per metric before new ones collapse into `other`
+    pub metrics_max_label_sets: usize,
+    /// How often the alerting gauges derived from counters are recomputed
+    pub metrics_derived_interval: Duration,
+    pub notification_config: NotificationConfig,
+    pub encryption: EncryptionConfig,
+    pub search: SearchConfig,
+    pub outbox: OutboxConfig,
+    pub quota: QuotaConfig,
+    pub reports: ReportConfig,
+    pub accounts: AccountConfig,
+    pub cache: CacheConfig,
+    pub links: LinkConfig,
+    pub auth: AuthConfig,
+    pub oauth: OAuthConfig,
+    pub saml: SamlConfig,
+    pub residency: ResidencyConfig,
+    pub rate_limits: RateLimitConfig,
+    pub authorization: AuthorizationConfig,
+    pub ip_access: IpAccessConfig,
+    /// Where client addresses are read from behind proxies
+    pub proxy: ProxyConfig,
+    pub storage: StorageConfig,
+    pub exports: ExportConfig,
+    pub status: StatusConfig,
+    pub middleware: MiddlewareConfig,
+    pub tenant_limits: TenantLimitsConfig,
+    pub maintenance: MaintenanceConfig,
+    pub subsystems: SubsystemsConfig,
+    pub ids: IdConfig,
+}
+
+impl AppConfig {
+    /// Build the configuration from environment variables
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            profile: env_or("APP_PROFILE", "development"),
+            log_level: env_or("LOG_LEVEL", "info"),
+            database_url: env_required("DATABASE_URL")?,
+            redis_url: env_or("REDIS_URL", "redis://127.0.0.1:6379"),
+            http_addr: env_or("HTTP_ADDR", "0.0.0.0:8080"),
+            http_request_timeout: Duration::from_millis(env_parse("HTTP_REQUEST_TIMEOUT_MS", 30_000)?),
+            shutdown_grace_period: Duration::from_secs(env_parse("SHUTDOWN_GRACE_SECS", 30)?),
+            presence_ttl: Duration::from_secs(env_parse("PRESENCE_TTL_SECS", 90)?),
+            metrics_max_label_sets: env_parse("METRICS_MAX_LABEL_SETS", 200)?,
+            metrics_derived_interval: Duration::from_secs(env_parse("METRICS_DERIVED_INTERVAL_SECS", 60)?),
+            notification_config: NotificationConfig::from_env()?,
+            encryption: EncryptionConfig::from_env()?,
+            search: SearchConfig::from_env()?,
+            outbox: OutboxConfig::from_env()?,
+            quota: QuotaConfig::from_env()?,
+            reports: ReportConfig::from_env()?,
+            accounts: AccountConfig::from_env()?,
+            cache: CacheConfig::from_env()?,
+            links: LinkConfig::from_env()?,
+            auth: AuthConfig::from_env()?,
+            oauth: OAuthConfig::from_env()?,
+            saml: SamlConfig::from_env()?,
+            residency: ResidencyConfig::from_env()?,
+            rate_limits: RateLimitConfig::from_env()?,
+            authorization: AuthorizationConfig::from_env()?,
+            ip_access: IpAccessConfig::from_env()?,
+            proxy: ProxyConfig::from_env()?,
+            storage: StorageConfig::from_env()?,
+            exports: ExportConfig::from_env()?,
+            status: StatusConfig::from_env()?,
+            middleware: MiddlewareConfig::from_env()?,
+            tenant_limits: TenantLimitsConfig::from_env()?,
+            maintenance: MaintenanceConfig::from_env()?,
+            subsystems: SubsystemsConfig::from_env()?,
+            ids: IdConfig::from_env()?,
+        })
+    }
+
+    /// Optional subsystems this configuration turns on
+    pub fn enabled_features(&self) -> Vec<&'static str> {
+        let mut features = Vec::new();
+        if self.search.url.is_some() {
+            features.push("search_cluster");
+        }
+        if self.outbox.publisher == OutboxPublisherKind::Kafka {
+            features.push("kafka_outbox");
+        }
+        if self.reports.format == ReportFormat::Pdf {
+            features.push("pdf_reports");
+        }
+        if !self.oauth.providers.is_empty() {
+            features.push("oauth_login");
+        }
+        if self.saml.idp.is_some() {
+            features.push("saml_login");
+        }
+        if !self.residency.region_databases.is_empty() {
+            features.push("data_residency");
+        }
+        if self.ip_access.is_enabled() {
+            features.push("ip_access_lists");
+        }
+        features
+    }
+}
+
+/// Read an environment variable, or the secret it refers to once fetched
+pub(crate) fn env_var(key: &str) -> Option<String> {
+    secrets::resolved_secret(key).or_else(|| env::var(key).ok())
+}
+
+/// Read an environment variable, falling back to a default
+pub(crate) fn env_or(key: &str, default: &str) -> String {
+    env_var(key).unwrap_or_else(|| default.to_string())
+}
+
+/// Read an environment variable that must be present
+pub(crate) fn env_required(key: &str) -> AppResult<String> {
+    env_var(key).ok_or_else(|| AppError::Config(format!("{} must be set", key)))
+}
+
+/// Read `name=value,...` pairs from an environment variable; empty when unset
+pub(crate) fn env_pairs(key: &str) -> AppResult<HashMap<String, String>> {
+    let raw = env_or(key, "");
+    let mut pairs = HashMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, value) = entry
+            .split_once('=')
+            .ok_or_else(|| AppError::Config(format!("{} entries must be name=value", key)))?;
+        pairs.insert(name.trim().to_string(), value.trim().to_string());
+    }
+    Ok(pairs)
+}
+
+/// Read and parse an environment variable, falling back to a default when unset
+pub(crate) fn env_parse<T: FromStr>(key: &str, default: T) -> AppResult<T> {
+    match env_var(key) {
+        Some(raw) => raw
+            .parse()
+            .map_err(|_| AppError::Config(format!("{} has an invalid value: {}", key, raw))),
+        None => Ok(default),
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Duration;
+
+use super::{env_or, env_pairs, env_parse};
+use crate::models::{AppError, AppResult, NotificationChannel, NotificationPriority, NotificationType};
+
+/// Delivery settings for notification channels
+#[derive(Clone)]
+pub struct NotificationConfig {
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub from_address: String,
+    pub from_name: String,
+    pub broadcast_batch_size: i64,
+    /// Worker tasks draining the dispatch queue
+    pub dispatch_workers: usize,
+    /// Notifications that can wait for a worker before enqueueing blocks
+    pub dispatch_queue_capacity: usize,
+    /// Concurrent sends allowed per channel
+    pub email_concurrency: usize,
+    pub in_app_concurrency: usize,
+    /// Branding used for tenants without their own overrides
+    pub brand_logo_url: Option<String>,
+    pub brand_primary_color: Option<String>,
+    pub brand_accent_color: Option<String>,
+    pub brand_footer: Option<String>,
+    /// Public URL the tracking endpoints are served under; tracking is off when unset
+    pub tracking_base_url: Option<String>,
+    /// Key signing tracking links
+    pub tracking_secret: Option<String>,
+    /// Which channels each notification type and priority goes out on
+    pub routing: ChannelRouting,
+    /// Secret each delivery provider signs its status callbacks with, by provider name
+    pub webhook_secrets: HashMap<String, String>,
+    /// Monthly message and cost allowances per channel
+    pub budgets: NotificationBudgets,
+    /// Age past which delivered notifications move to cold storage; None keeps them in Postgres
+    pub archive_after: Option<Duration>,
+    pub archive_batch_size: i64,
+    pub archive_interval: Duration,
+    /// Register the channels with the health registry so the status page probes them
+    pub channel_probes: bool,
+    /// Provider APIs and webhook endpoints probed with `HEAD`, by component name
+    pub probe_urls: HashMap<String, String>,
+}
+
+impl NotificationConfig {
+    pub fn from_env() -> AppResult<Self> {
+        let optional = |key: &str| Some(env_or(key, "")).filter(|v| !v.is_empty());
+
+        Ok(Self {
+            smtp_host: env_or("SMTP_HOST", "localhost"),
+            smtp_port: env_parse("SMTP_PORT", 587)?,
+            smtp_username: optional("SMTP_USERNAME"),
+            smtp_password: optional("SMTP_PASSWORD"),
+            from_address: env_or("NOTIFICATION_FROM_ADDRESS", "no-reply@example.com"),
+            from_name: env_or("NOTIFICATION_FROM_NAME", "Crawler Test"),
+            broadcast_batch_size: env_parse("NOTIFICATION_BROADCAST_BATCH_SIZE", 500)?,
+            dispatch_workers: env_parse("NOTIFICATION_WORKERS", 4)?,
+            dispatch_queue_capacity: env_parse("NOTIFICATION_QUEUE_CAPACITY", 1000)?,
+            email_concurrency: env_parse("NOTIFICATION_EMAIL_CONCURRENCY", 8)?,
+            in_app_concurrency: env_parse("NOTIFICATION_IN_APP_CONCURRENCY", 32)?,
+            brand_logo_url: optional("NOTIFICATION_BRAND_LOGO_URL"),
+            brand_primary_color: optional("NOTIFICATION_BRAND_PRIMARY_COLOR"),
+            brand_accent_color: optional("NOTIFICATION_BRAND_ACCENT_COLOR"),
+            brand_footer: optional("NOTIFICATION_BRAND_FOOTER"),
+            tracking_base_url: optional("NOTIFICATION_TRACKING_BASE_URL"),
+            tracking_secret: optional("NOTIFICATION_TRACKING_SECRET"),
+            routing: ChannelRouting::from_env()?,
+            webhook_secrets: env_pairs("NOTIFICATION_WEBHOOK_SECRETS")?,
+            budgets: NotificationBudgets::from_env()?,
+            archive_after: Some(env_parse("NOTIFICATION_ARCHIVE_AFTER_DAYS", 180u64)?)
+                .filter(|days| *days > 0)
+                .map(|days| Duration::from_secs(days * 86_400)),
+            archive_batch_size: env_parse("NOTIFICATION_ARCHIVE_BATCH_SIZE", 1000)?,
+            archive_interval: Duration::from_secs(env_parse("NOTIFICATION_ARCHIVE_INTERVAL_SECS", 3600)?),
+            channel_probes: env_parse("NOTIFICATION_CHANNEL_PROBES", true)?,
+            probe_urls: env_pairs("NOTIFICATION_PROBE_URLS")?,
+        })
+    }
+}
+
+impl fmt::Debug for NotificationConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NotificationConfig")
+            .field("smtp_host", &self.smtp_host)
+            .field("smtp_port", &self.smtp_port)
+            .field("smtp_username", &self.smtp_username)
+            .field("smtp_password", &self.smtp_password.as_ref().map(|_| "<redacted>"))
+            .field("from_address", &self.from_address)
+            .field("from_name", &self.from_name)
+            .field("broadcast_batch_size", &self.broadcast_batch_size)
+            .field("dispatch_workers", &self.dispatch_workers)
+            .field("dispatch_queue_capacity", &self.dispatch_queue_capacity)
+            .field("email_concurrency", &self.email_concurrency)
+            .field("in_app_concurrency", &self.in_app_concurrency)
+            .field("brand_logo_url", &self.brand_logo_url)
+            .field("brand_primary_color", &self.brand_primary_color)
+            .field("brand_accent_color", &self.brand_accent_color)
+            .field("brand_footer", &self.brand_footer)
+            .field("tracking_base_url", &self.tracking_base_url)
+            .field("tracking_secret", &self.tracking_secret.as_ref().map(|_| "<redacted>"))
+            .field("routing", &self.routing)
+            .field("webhook_providers", &self.webhook_secrets.keys().collect::<Vec<_>>())
+            .field("budgets", &self.budgets)
+            .field("archive_after", &self.archive_after)
+            .field("archive_batch_size", &self.archive_batch_size)
+            .field("archive_interval", &self.archive_interval)
+            .field("channel_probes", &self.channel_probes)
+            .field("probe_urls", &self.probe_urls)
+            .finish()
+    }
+}
+
+/// Ordered delivery channels per notification type and priority.
+///
+/// A route for a type at a given priority wins over the type's route for
+/// any priority, which wins over the fallback. Earlier channels are
+/// preferred; later ones are used when the recipient cannot be reached on
+/// the earlier ones.
+#[derive(Debug, Clone)]
+pub struct ChannelRouting {
+    routes: HashMap<(NotificationType, Option<NotificationPriority>), Vec<NotificationChannel>>,
+    fallback: Vec<NotificationChannel>,
+}
+
+impl Default for ChannelRouting {
+    fn default() -> Self {
+        Self {
+            routes: HashMap::from([((NotificationType::Welcome, None), vec![NotificationChannel::Email])]),
+            fallback: vec![NotificationChannel::Email, NotificationChannel::InApp],
+        }
+    }
+}
+
+impl ChannelRouting {
+    /// Defaults overlaid with `NOTIFICATION_ROUTING`, e.g.
+    /// `marketing=in_app;security.urgent=email,in_app;*=email,in_app`
+    pub fn from_env() -> AppResult<Self> {
+        let mut routing = Self::default();
+        let raw = env_or("NOTIFICATION_ROUTING", "");
+        for rule in raw.split(';').map(str::trim).filter(|rule| !rule.is_empty()) {
+            let invalid = |reason: String| AppError::Config(format!("NOTIFICATION_ROUTING rule '{}': {}", rule, reason));
+            let (selector, channels) = rule.split_once('=').ok_or_else(|| invalid("expected type=channels".into()))?;
+            let channels = channels
+                .split(',')
+                .map(|channel| channel.trim().parse::<NotificationChannel>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(invalid)?;
+            if channels.is_empty() {
+                return Err(invalid("no channels given".into()));
+            }
+
+            let selector = selector.trim();
+            if selector == "*" {
+                routing.fallback = channels;
+                continue;
+            }
+            let (notification_type, priority) = match selector.split_once('.') {
+                Some((notification_type, priority)) => (notification_type, Some(priority.parse().map_err(invalid)?)),
+                None => (selector, None),
+            };
+            routing
+                .routes
+                .insert((notification_type.parse().map_err(invalid)?, priority), channels);
+        }
+        Ok(routing)
+    }
+
+    pub fn route(&self, notification_type: NotificationType, priority: NotificationPriority) -> &[NotificationChannel] {
+        self.routes
+            .get(&(notification_type, Some(priority)))
+            .or_else(|| self.routes.get(&(notification_type, None)))
+            .unwrap_or(&self.fallback)
+    }
+}
+
+/// A channel's allowance for a calendar month (UTC); spent once either limit is reached
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChannelBudget {
+    pub max_messages: Option<u64>,
+    /// In the budgets' currency, at `cost_per_message` for every message sent
+    pub max_cost: Option<f64>,
+    pub cost_per_message: f64,
+}
+
+/// Monthly notification budgets per channel.
+///
+/// `NOTIFICATION_BUDGETS` caps messages (`email=100000`) and
+/// `NOTIFICATION_COST_BUDGETS` caps spend (`email=250`), priced by
+/// `NOTIFICATION_MESSAGE_COSTS` (`email=0.0009`) in
+/// `NOTIFICATION_BUDGET_CURRENCY`. Admins are warned as a budget passes each
+/// percentage in `NOTIFICATION_BUDGET_WARNINGS`; the notification types in
+/// `NOTIFICATION_BUDGET_HARD_STOP` are no longer sent on a channel whose
+/// budget is spent, and every other type still is.
+#[derive(Debug, Clone, Default)]
+pub struct NotificationBudgets {
+    pub channels: HashMap<NotificationChannel, ChannelBudget>,
+    pub currency: String,
+    /// Percentages of a budget, ascending
+    pub warning_thresholds: Vec<u32>,
+    pub hard_stop: HashSet<NotificationType>,
+}
+
+impl NotificationBudgets {
+    pub fn from_env() -> AppResult<Self> {
+        let mut channels: HashMap<NotificationChannel, ChannelBudget> = HashMap::new();
+        for (channel, value) in channel_pairs("NOTIFICATION_BUDGETS")? {
+            let max_messages = parse_amount::<u64>("NOTIFICATION_BUDGETS", &value)?;
+            channels.entry(channel).or_default().max_messages = Some(max_messages);
+        }
+        for (channel, value) in channel_pairs("NOTIFICATION_COST_BUDGETS")? {
+            let max_cost = parse_amount::<f64>("NOTIFICATION_COST_BUDGETS", &value)?;
+            channels.entry(channel).or_default().max_cost = Some(max_cost);
+        }
+        for (channel, value) in channel_pairs("NOTIFICATION_MESSAGE_COSTS")? {
+            let cost = parse_amount::<f64>("NOTIFICATION_MESSAGE_COSTS", &value)?;
+            channels.entry(channel).or_default().cost_per_message = cost;
+        }
+        for (channel, budget) in &channels {
+            if budget.max_cost.is_some() && budget.cost_per_message <= 0.0 {
+                return Err(AppError::Config(format!(
+                    "NOTIFICATION_COST_BUDGETS caps {} but NOTIFICATION_MESSAGE_COSTS gives it no price",
+                    channel.as_str()
+                )));
+            }
+        }
+
+        let mut warning_thresholds = env_or("NOTIFICATION_BUDGET_WARNINGS", "80,100")
+            .split(',')
+            .map(str::trim)
+            .filter(|percent| !percent.is_empty())
+            .map(|percent| parse_amount::<u32>("NOTIFICATION_BUDGET_WARNINGS", percent))
+            .collect::<AppResult<Vec<_>>>()?;
+        warning_thresholds.sort_unstable();
+        warning_thresholds.dedup();
+
+        let mut hard_stop = HashSet::new();
+        for name in env_or("NOTIFICATION_BUDGET_HARD_STOP", "").split(',').map(str::trim) {
+            if name.is_empty() {
+                continue;
+            }
+            let notification_type: NotificationType = name
+                .parse()
+                .map_err(|e| AppError::Config(format!("NOTIFICATION_BUDGET_HARD_STOP: {}", e)))?;
+            if notification_type == NotificationType::Security {
+                return Err(AppError::Config(
+                    "NOTIFICATION_BUDGET_HARD_STOP cannot stop security notifications".to_string(),
+                ));
+            }
+            hard_stop.insert(notification_type);
+        }
+
+        Ok(Self {
+            channels,
+            currency: env_or("NOTIFICATION_BUDGET_CURRENCY", "USD"),
+            warning_thresholds,
+            hard_stop,
+        })
+    }
+
+    pub fn for_channel(&self, channel: NotificationChannel) -> Option<&ChannelBudget> {
+        self.channels.get(&channel)
+    }
+}
+
+fn channel_pairs(key: &str) -> AppResult<Vec<(NotificationChannel, String)>> {
+    env_pairs(key)?
+        .into_iter()
+        .map(|(channel, value)| {
+            let channel = channel
+                .parse()
+                .map_err(|e| AppError::Config(format!("{}: {}", key, e)))?;
+            Ok((channel, value))
+        })
+        .collect()
+}
+
+fn parse_amount<T: std::str::FromStr + PartialOrd + Default>(key: &str, raw: &str) -> AppResult<T> {
+    raw.parse::<T>()
+        .ok()
+        .filter(|amount| *amount >= T::default())
+        .ok_or_else(|| AppError::Config(format!("{} has an invalid value: {}", key, raw)))
+}
@@ -0,0 +1,109 @@
+use std::env;
+use std::fmt;
+use std::time::Duration;
+
+use super::{env_or, env_parse};
+use crate::models::{AppError, AppResult, UserRole};
+
+const GOOGLE_ISSUER: &str = "https://accounts.google.com";
+
+/// How a provider's endpoints and identities are obtained
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OAuthProviderKind {
+    /// OpenID Connect provider whose endpoints come from its discovery document
+    Oidc { issuer: String },
+    /// GitHub, which speaks plain OAuth2 and exposes identities through its REST API
+    GitHub,
+}
+
+/// An external identity provider users can sign in with
+#[derive(Clone)]
+pub struct OAuthProviderConfig {
+    /// Path segment of the provider's sign-in routes, e.g. `google`
+    pub name: String,
+    pub kind: OAuthProviderKind,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+impl fmt::Debug for OAuthProviderConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuthProviderConfig")
+            .field("name", &self.name)
+            .field("kind", &self.kind)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Sign-in through Google, GitHub and a generic OIDC provider
+#[derive(Debug, Clone)]
+pub struct OAuthConfig {
+    /// Providers with credentials configured; sign-in through others is unavailable
+    pub providers: Vec<OAuthProviderConfig>,
+    /// Create an account for verified emails nobody has registered yet
+    pub auto_provision: bool,
+    /// Role of provisioned accounts
+    pub default_role: UserRole,
+    /// How long a started sign-in can be completed
+    pub state_ttl: Duration,
+}
+
+impl OAuthConfig {
+    pub fn from_env() -> AppResult<Self> {
+        let mut providers = Vec::new();
+        if let Some((client_id, client_secret)) = credentials("OAUTH_GOOGLE")? {
+            providers.push(OAuthProviderConfig {
+                name: "google".to_string(),
+                kind: OAuthProviderKind::Oidc {
+                    issuer: GOOGLE_ISSUER.to_string(),
+                },
+                client_id,
+                client_secret,
+            });
+        }
+        if let Some((client_id, client_secret)) = credentials("OAUTH_GITHUB")? {
+            providers.push(OAuthProviderConfig {
+                name: "github".to_string(),
+                kind: OAuthProviderKind::GitHub,
+                client_id,
+                client_secret,
+            });
+        }
+        if let Some((client_id, client_secret)) = credentials("OAUTH_OIDC")? {
+            let issuer = env_or("OAUTH_OIDC_ISSUER", "");
+            if issuer.is_empty() {
+                return Err(AppError::Config("OAUTH_OIDC_ISSUER must be set with OAUTH_OIDC_CLIENT_ID".to_string()));
+            }
+            providers.push(OAuthProviderConfig {
+                name: env_or("OAUTH_OIDC_NAME", "oidc"),
+                kind: OAuthProviderKind::Oidc {
+                    issuer: issuer.trim_end_matches('/').to_string(),
+                },
+                client_id,
+                client_secret,
+            });
+        }
+
+        Ok(Self {
+            providers,
+            auto_provision: env_parse("OAUTH_AUTO_PROVISION", true)?,
+            default_role: env_parse("OAUTH_DEFAULT_ROLE", UserRole::User)?,
+            state_ttl: Duration::from_secs(env_parse("OAUTH_STATE_TTL_SECS", 600)?),
+        })
+    }
+}
+
+/// `<PREFIX>_CLIENT_ID` and `<PREFIX>_CLIENT_SECRET`, which must be set together
+fn credentials(prefix: &str) -> AppResult<Option<(String, String)>> {
+    let read = |suffix: &str| env::var(format!("{}_{}", prefix, suffix)).ok().filter(|v| !v.is_empty());
+    match (read("CLIENT_ID"), read("CLIENT_SECRET")) {
+        (Some(client_id), Some(client_secret)) => Ok(Some((client_id, client_secret))),
+        (None, None) => Ok(None),
+        _ => Err(AppError::Config(format!(
+            "{}_CLIENT_ID and {}_CLIENT_SECRET must be set together",
+            prefix, prefix
+        ))),
+    }
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use super::{env_or, env_parse};
+use crate::models::{AppError, AppResult};
+
+/// Destination the outbox relay publishes to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutboxPublisherKind {
+    EventBus,
+    Kafka,
+}
+
+/// Outbox relay polling and broker settings
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    pub publisher: OutboxPublisherKind,
+    pub kafka_brokers: String,
+    pub kafka_topic: String,
+    pub batch_size: i64,
+    pub poll_interval: Duration,
+    pub publish_timeout: Duration,
+    /// How long published rows are kept before they are deleted; None keeps them
+    pub retention: Option<Duration>,
+    /// How often published rows past that age are deleted
+    pub retention_interval: Duration,
+}
+
+impl OutboxConfig {
+    pub fn from_env() -> AppResult<Self> {
+        let publisher = match env_or("OUTBOX_PUBLISHER", "event_bus").as_str() {
+            "event_bus" => OutboxPublisherKind::EventBus,
+            "kafka" => OutboxPublisherKind::Kafka,
+            other => {
+                return Err(AppError::Config(format!("Unknown OUTBOX_PUBLISHER: {}", other)))
+            }
+        };
+
+        Ok(Self {
+            publisher,
+            kafka_brokers: env_or("KAFKA_BROKERS", "localhost:9092"),
+            kafka_topic: env_or("OUTBOX_KAFKA_TOPIC", "user-events"),
+            batch_size: env_parse("OUTBOX_BATCH_SIZE", 100)?,
+            poll_interval: Duration::from_millis(env_parse("OUTBOX_POLL_INTERVAL_MS", 500)?),
+            publish_timeout: Duration::from_millis(env_parse("OUTBOX_PUBLISH_TIMEOUT_MS", 5000)?),
+            retention: Some(env_parse("OUTBOX_RETENTION_DAYS", 7u64)?)
+                .filter(|days| *days > 0)
+                .map(|days| Duration::from_secs(days * 86_400)),
+            retention_interval: Duration::from_secs(env_parse("OUTBOX_RETENTION_INTERVAL_SECS", 3600)?),
+        })
+    }
+}
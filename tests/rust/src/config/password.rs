@@ -0,0 +1,80 @@
+use super::env_parse;
+use crate::models::AppResult;
+use crate::utils::PasswordAlgorithm;
+
+/// Rules a new password must satisfy
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    /// Least estimated strength, in bits, from the length and the kinds of characters used
+    pub min_entropy_bits: f64,
+    /// How many previous passwords, including the current one, may not be reused;
+    /// older history is pruned
+    pub history_depth: usize,
+    /// Scheme new hashes use; hashes in other schemes are replaced on the owner's next sign-in
+    pub algorithm: PasswordAlgorithm,
+    /// Breached passwords in haveibeenpwned's download format, one `SHA1:count` line each;
+    /// None skips the breach check
+    pub breached_list: Option<String>,
+    /// Sizing of the bloom filter the breached list is loaded into
+    pub breached_filter_capacity: u64,
+    pub breached_filter_error_rate: f64,
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            min_length: env_parse("PASSWORD_MIN_LENGTH", 12)?,
+            min_entropy_bits: env_parse("PASSWORD_MIN_ENTROPY_BITS", 50.0)?,
+            history_depth: env_parse("PASSWORD_HISTORY_DEPTH", 5)?,
+            algorithm: env_parse("PASSWORD_HASH_ALGORITHM", PasswordAlgorithm::Argon2)?,
+            breached_list: std::env::var("PASSWORD_BREACHED_LIST").ok().filter(|path| !path.is_empty()),
+            breached_filter_capacity: env_parse("PASSWORD_BREACHED_FILTER_CAPACITY", 10_000_000)?,
+            breached_filter_error_rate: env_parse("PASSWORD_BREACHED_FILTER_ERROR_RATE", 0.001)?,
+        })
+    }
+
+    /// Problems with a candidate password, empty if it is acceptable; the breach check is made separately
+    pub fn validate(&self, password: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        if password.chars().count() < self.min_length {
+            errors.push(format!("Password must be at least {} characters", self.min_length));
+        }
+        if entropy_bits(password) < self.min_entropy_bits {
+            errors.push("Password is too easy to guess; use a longer or more varied password".to_string());
+        }
+        errors
+    }
+}
+
+/// Length times the bits per character of the alphabets drawn from.
+///
+/// A character repeating the one before it adds nothing, so padding a
+/// short password with one key does not pass for strength.
+fn entropy_bits(password: &str) -> f64 {
+    let (mut lower, mut upper, mut digit, mut symbol, mut other) = (false, false, false, false, false);
+    let mut length = 0;
+    let mut previous = None;
+    for c in password.chars() {
+        match c {
+            'a'..='z' => lower = true,
+            'A'..='Z' => upper = true,
+            '0'..='9' => digit = true,
+            c if c.is_ascii() => symbol = true,
+            _ => other = true,
+        }
+        if previous != Some(c) {
+            length += 1;
+        }
+        previous = Some(c);
+    }
+    let alphabet: u32 = [(lower, 26), (upper, 26), (digit, 10), (symbol, 33), (other, 100)]
+        .iter()
+        .filter(|(used, _)| *used)
+        .map(|(_, size)| size)
+        .sum();
+    if alphabet == 0 {
+        return 0.0;
+    }
+    length as f64 * (alphabet as f64).log2()
+}
@@ -0,0 +1,119 @@
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+use super::ip_access::parse_ranges;
+use super::{env_or, env_parse};
+use crate::models::AppResult;
+
+/// Which proxies in front of the service are believed about the client's address
+#[derive(Debug, Clone, Default)]
+pub struct ProxyConfig {
+    /// Judge the client by `X-Forwarded-For` rather than the peer; only safe behind a proxy that sets it
+    pub trust_forwarded_for: bool,
+    /// Proxies whose hops are skipped when reading `X-Forwarded-For`; when
+    /// set, a peer outside them is taken as the client whatever it forwards
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+impl ProxyConfig {
+    /// `TRUST_FORWARDED_FOR` turns forwarded addresses on, and
+    /// `TRUSTED_PROXIES` lists the proxy ranges, comma-separated
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            trust_forwarded_for: env_parse("TRUST_FORWARDED_FOR", false)?,
+            trusted_proxies: parse_ranges("TRUSTED_PROXIES", env_or("TRUSTED_PROXIES", "").split(','))?,
+        })
+    }
+
+    /// The client behind a request from `peer` carrying `forwarded_for`.
+    ///
+    /// Every proxy appends the address it was connected from, so only the
+    /// hops on the right were written by proxies; anything further left is
+    /// whatever the client sent. The client is therefore the rightmost hop
+    /// that is not one of the trusted proxies.
+    pub fn client_address(&self, peer: Option<IpAddr>, forwarded_for: Option<&str>) -> Option<IpAddr> {
+        let peer = peer.map(|peer| peer.to_canonical());
+        let Some(forwarded_for) = forwarded_for.filter(|_| self.trust_forwarded_for) else {
+            return peer;
+        };
+        if !self.trusted_proxies.is_empty() && !peer.is_some_and(|peer| self.is_trusted(peer)) {
+            return peer;
+        }
+        let mut client = peer;
+        for hop in forwarded_for.rsplit(',').map(str::trim) {
+            let Ok(hop) = hop.parse::<IpAddr>().map(|hop| hop.to_canonical()) else {
+                return None;
+            };
+            client = Some(hop);
+            if !self.is_trusted(hop) {
+                break;
+            }
+        }
+        client
+    }
+
+    fn is_trusted(&self, address: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|range| range.contains(&address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(text: &str) -> Option<IpAddr> {
+        Some(text.parse().unwrap())
+    }
+
+    fn behind(trusted: &[&str]) -> ProxyConfig {
+        ProxyConfig {
+            trust_forwarded_for: true,
+            trusted_proxies: trusted.iter().map(|range| range.parse().unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn forwarded_addresses_are_ignored_by_default() {
+        let proxy = ProxyConfig::default();
+        assert_eq!(proxy.client_address(address("203.0.113.7"), Some("198.51.100.1")), address("203.0.113.7"));
+    }
+
+    #[test]
+    fn the_rightmost_hop_outside_the_trusted_proxies_is_the_client() {
+        let proxy = behind(&["10.0.0.0/8"]);
+        assert_eq!(
+            proxy.client_address(address("10.0.0.2"), Some("1.2.3.4, 198.51.100.9, 10.0.0.1")),
+            address("198.51.100.9")
+        );
+    }
+
+    #[test]
+    fn hops_a_client_prepends_are_not_believed() {
+        let proxy = behind(&[]);
+        assert_eq!(
+            proxy.client_address(address("10.0.0.2"), Some("6.6.6.6, 198.51.100.9")),
+            address("198.51.100.9")
+        );
+    }
+
+    #[test]
+    fn peers_outside_the_trusted_proxies_are_the_client() {
+        let proxy = behind(&["10.0.0.0/8"]);
+        assert_eq!(proxy.client_address(address("203.0.113.7"), Some("198.51.100.9")), address("203.0.113.7"));
+    }
+
+    #[test]
+    fn an_unreadable_hop_leaves_the_client_unknown() {
+        let proxy = behind(&["10.0.0.0/8"]);
+        assert_eq!(proxy.client_address(address("10.0.0.2"), Some("198.51.100.9, garbage")), None);
+    }
+
+    #[test]
+    fn mapped_addresses_compare_as_ipv4() {
+        let proxy = behind(&["10.0.0.0/8"]);
+        assert_eq!(
+            proxy.client_address(address("::ffff:10.0.0.2"), Some("::ffff:198.51.100.9")),
+            address("198.51.100.9")
+        );
+    }
+}
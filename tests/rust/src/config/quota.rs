@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use super::env_parse;
+use crate::models::AppResult;
+
+/// Monthly API call allowances and metering flush cadence
+#[derive(Debug, Clone)]
+pub struct QuotaConfig {
+    /// Calls allowed per billing period for an authenticated user
+    pub user_monthly_limit: u64,
+    /// Calls allowed per billing period for an API key
+    pub api_key_monthly_limit: u64,
+    /// How often Redis counters are persisted to Postgres
+    pub flush_interval: Duration,
+}
+
+impl QuotaConfig {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            user_monthly_limit: env_parse("QUOTA_USER_MONTHLY_LIMIT", 100_000)?,
+            api_key_monthly_limit: env_parse("QUOTA_API_KEY_MONTHLY_LIMIT", 1_000_000)?,
+            flush_interval: Duration::from_secs(env_parse("QUOTA_FLUSH_INTERVAL_SECS", 60)?),
+        })
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use super::{env_or, env_parse};
+use crate::models::{AppError, AppResult};
+
+/// Routes sharing one set of rate limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    /// Sign-in, token refresh and account recovery
+    Auth,
+    Admin,
+    Api,
+}
+
+impl RouteGroup {
+    pub const ALL: [RouteGroup; 3] = [RouteGroup::Auth, RouteGroup::Admin, RouteGroup::Api];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteGroup::Auth => "auth",
+            RouteGroup::Admin => "admin",
+            RouteGroup::Api => "api",
+        }
+    }
+
+    pub fn of_path(path: &str) -> Self {
+        if path.starts_with("/auth/") {
+            RouteGroup::Auth
+        } else if path.starts_with("/admin/") {
+            RouteGroup::Admin
+        } else {
+            RouteGroup::Api
+        }
+    }
+
+    fn default_limits(&self) -> (&'static str, &'static str) {
+        match self {
+            RouteGroup::Auth => ("20/60", "20/60"),
+            RouteGroup::Admin => ("120/60", "300/60"),
+            RouteGroup::Api => ("300/60", "600/60"),
+        }
+    }
+}
+
+/// A token bucket holding up to `burst` requests and refilling `burst`
+/// tokens every `window_secs`
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub window_secs: u32,
+}
+
+impl RateLimit {
+    /// Tokens added back per second
+    pub fn refill_rate(&self) -> f64 {
+        self.burst as f64 / self.window_secs as f64
+    }
+
+    /// Parse `requests/seconds`, e.g. `20/60`
+    pub(super) fn parse(key: &str, raw: &str) -> AppResult<Self> {
+        let invalid = || AppError::Config(format!("{} must be requests/seconds, got {}", key, raw));
+        let (burst, window) = raw.split_once('/').ok_or_else(invalid)?;
+        let burst: u32 = burst.trim().parse().map_err(|_| invalid())?;
+        let window_secs: u32 = window.trim().parse().map_err(|_| invalid())?;
+        if burst == 0 || window_secs == 0 {
+            return Err(invalid());
+        }
+        Ok(Self { burst, window_secs })
+    }
+}
+
+/// Request rate limits, per client address and per signed-in caller
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub per_ip: HashMap<RouteGroup, RateLimit>,
+    /// Applied to users and API keys on top of their address's limit
+    pub per_subject: HashMap<RouteGroup, RateLimit>,
+}
+
+impl RateLimitConfig {
+    /// Limits are read from `RATE_LIMIT_IP_<GROUP>` and `RATE_LIMIT_SUBJECT_<GROUP>`
+    pub fn from_env() -> AppResult<Self> {
+        let mut per_ip = HashMap::new();
+        let mut per_subject = HashMap::new();
+        for group in RouteGroup::ALL {
+            let (ip_default, subject_default) = group.default_limits();
+            let name = group.as_str().to_uppercase();
+            let ip_key = format!("RATE_LIMIT_IP_{}", name);
+            let subject_key = format!("RATE_LIMIT_SUBJECT_{}", name);
+            per_ip.insert(group, RateLimit::parse(&ip_key, &env_or(&ip_key, ip_default))?);
+            per_subject.insert(group, RateLimit::parse(&subject_key, &env_or(&subject_key, subject_default))?);
+        }
+        Ok(Self {
+            enabled: env_parse("RATE_LIMIT_ENABLED", true)?,
+            per_ip,
+            per_subject,
+        })
+    }
+}
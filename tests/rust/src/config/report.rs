@@ -0,0 +1,35 @@
+use super::{env_or, env_parse};
+use crate::models::{AppError, AppResult};
+
+/// File format reports are rendered to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Pdf,
+}
+
+/// Schedule and contents of the periodic admin reports
+#[derive(Debug, Clone)]
+pub struct ReportConfig {
+    /// Cron expression (with seconds) for when reports are sent, evaluated in UTC
+    pub schedule: String,
+    pub format: ReportFormat,
+    /// Number of days each report covers, ending at generation time
+    pub window_days: i64,
+}
+
+impl ReportConfig {
+    pub fn from_env() -> AppResult<Self> {
+        let format = match env_or("REPORT_FORMAT", "csv").as_str() {
+            "csv" => ReportFormat::Csv,
+            "pdf" => ReportFormat::Pdf,
+            other => return Err(AppError::Config(format!("Unknown REPORT_FORMAT: {}", other))),
+        };
+
+        Ok(Self {
+            schedule: env_or("REPORT_SCHEDULE", "0 0 7 * * Mon *"),
+            format,
+            window_days: env_parse("REPORT_WINDOW_DAYS", 7)?,
+        })
+    }
+}
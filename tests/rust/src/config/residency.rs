@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{env_or, env_pairs};
+use crate::models::{AppError, AppResult, TenantContext};
+
+/// Which database each tenant's accounts are stored in
+#[derive(Clone)]
+pub struct ResidencyConfig {
+    /// Region of `DATABASE_URL`; accounts without a region are stored there
+    pub home_region: String,
+    /// Connection URLs of the other regions' databases
+    pub region_databases: HashMap<String, String>,
+    /// Region new accounts of a tenant are created in; unlisted tenants use the home region
+    pub tenant_regions: HashMap<String, String>,
+}
+
+impl ResidencyConfig {
+    /// Load `REGION_DATABASE_URLS` as `region=url,...` and `TENANT_REGIONS` as `tenant=region,...`
+    pub fn from_env() -> AppResult<Self> {
+        let home_region = env_or("DATA_HOME_REGION", "default");
+        let region_databases = env_pairs("REGION_DATABASE_URLS")?;
+        let tenant_regions = env_pairs("TENANT_REGIONS")?;
+
+        if region_databases.contains_key(&home_region) {
+            return Err(AppError::Config(format!(
+                "REGION_DATABASE_URLS must not list the home region {}",
+                home_region
+            )));
+        }
+        if let Some((tenant, region)) = tenant_regions
+            .iter()
+            .find(|(_, region)| **region != home_region && !region_databases.contains_key(*region))
+        {
+            return Err(AppError::Config(format!(
+                "Tenant {} is assigned to region {} which has no database",
+                tenant, region
+            )));
+        }
+
+        Ok(Self {
+            home_region,
+            region_databases,
+            tenant_regions,
+        })
+    }
+
+    /// Region new accounts of `tenant` belong in, None for the home region
+    pub fn tenant_region(&self, tenant: &TenantContext) -> Option<&str> {
+        self.tenant_regions
+            .get(&tenant.tenant_id)
+            .map(String::as_str)
+            .filter(|region| *region != self.home_region)
+    }
+}
+
+impl fmt::Debug for ResidencyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut regions: Vec<&String> = self.region_databases.keys().collect();
+        regions.sort();
+        f.debug_struct("ResidencyConfig")
+            .field("home_region", &self.home_region)
+            .field("regions", &regions)
+            .field("tenant_regions", &self.tenant_regions)
+            .finish()
+    }
+}
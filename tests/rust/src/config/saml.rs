@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use super::{env_or, env_pairs, env_parse, env_var};
+use crate::models::{AppError, AppResult, UserRole};
+
+/// The identity provider assertions are accepted from
+#[derive(Debug, Clone)]
+pub struct SamlIdentityProvider {
+    /// Issuer the provider's assertions carry
+    pub entity_id: String,
+    /// Where authentication requests are sent, with the HTTP-Redirect binding
+    pub sso_url: String,
+    /// PEM file of the certificate the provider signs with
+    pub certificate_file: String,
+}
+
+/// SAML attribute names user fields are read from
+#[derive(Debug, Clone)]
+pub struct SamlAttributeMap {
+    /// Falls back to the subject's NameID when it is an email address
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub username: String,
+}
+
+/// Single sign-on through a SAML 2.0 identity provider
+#[derive(Debug, Clone)]
+pub struct SamlConfig {
+    /// None leaves SAML sign-in off
+    pub idp: Option<SamlIdentityProvider>,
+    /// Our entity id; the metadata URL when unset
+    pub sp_entity_id: Option<String>,
+    /// Email domains allowed to sign in through the provider, lowercase
+    pub allowed_domains: Vec<String>,
+    /// Create accounts for allowed emails nobody has registered yet
+    pub jit_provisioning: bool,
+    /// Role of provisioned accounts
+    pub default_role: UserRole,
+    pub attributes: SamlAttributeMap,
+    /// How long a sent authentication request can be answered
+    pub request_ttl: Duration,
+    /// Clock difference with the provider tolerated on assertion validity
+    pub clock_skew: Duration,
+}
+
+impl SamlConfig {
+    pub fn from_env() -> AppResult<Self> {
+        let read = |key: &str| env_var(key).filter(|v| !v.is_empty());
+        let idp = match (
+            read("SAML_IDP_ENTITY_ID"),
+            read("SAML_IDP_SSO_URL"),
+            read("SAML_IDP_CERTIFICATE_FILE"),
+        ) {
+            (Some(entity_id), Some(sso_url), Some(certificate_file)) => Some(SamlIdentityProvider {
+                entity_id,
+                sso_url,
+                certificate_file,
+            }),
+            (None, None, None) => None,
+            _ => {
+                return Err(AppError::Config(
+                    "SAML_IDP_ENTITY_ID, SAML_IDP_SSO_URL and SAML_IDP_CERTIFICATE_FILE must be set together"
+                        .to_string(),
+                ))
+            }
+        };
+
+        let allowed_domains: Vec<String> = env_or("SAML_ALLOWED_DOMAINS", "")
+            .split(',')
+            .map(|domain| domain.trim().trim_start_matches('@').to_lowercase())
+            .filter(|domain| !domain.is_empty())
+            .collect();
+        // Without a domain list any account at the provider could sign in as anyone
+        if idp.is_some() && allowed_domains.is_empty() {
+            return Err(AppError::Config(
+                "SAML_ALLOWED_DOMAINS must list the email domains allowed to sign in with SAML".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            idp,
+            sp_entity_id: read("SAML_SP_ENTITY_ID"),
+            allowed_domains,
+            jit_provisioning: env_parse("SAML_JIT_PROVISIONING", true)?,
+            default_role: env_parse("SAML_DEFAULT_ROLE", UserRole::User)?,
+            attributes: attribute_map()?,
+            request_ttl: Duration::from_secs(env_parse("SAML_REQUEST_TTL_SECS", 600)?),
+            clock_skew: Duration::from_secs(env_parse("SAML_CLOCK_SKEW_SECS", 120)?),
+        })
+    }
+}
+
+/// `SAML_ATTRIBUTES` as `field=attribute` pairs over the defaults
+fn attribute_map() -> AppResult<SamlAttributeMap> {
+    let mut map = SamlAttributeMap {
+        email: "email".to_string(),
+        first_name: "firstName".to_string(),
+        last_name: "lastName".to_string(),
+        username: "username".to_string(),
+    };
+    for (field, attribute) in env_pairs("SAML_ATTRIBUTES")? {
+        let target = match field.as_str() {
+            "email" => &mut map.email,
+            "first_name" => &mut map.first_name,
+            "last_name" => &mut map.last_name,
+            "username" => &mut map.username,
+            _ => {
+                return Err(AppError::Config(format!(
+                    "SAML_ATTRIBUTES maps unknown field {}; use email, first_name, last_name or username",
+                    field
+                )))
+            }
+        };
+        *target = attribute;
+    }
+    Ok(map)
+}
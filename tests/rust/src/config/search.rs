@@ -0,0 +1,42 @@
+use std::fmt;
+use std::time::Duration;
+
+use super::{env_or, env_parse};
+use crate::models::AppResult;
+
+/// Connection settings for the Elasticsearch/OpenSearch cluster
+#[derive(Clone)]
+pub struct SearchConfig {
+    /// Base URL of the cluster; search falls back to Postgres when unset
+    pub url: Option<String>,
+    pub users_index: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub request_timeout: Duration,
+}
+
+impl SearchConfig {
+    pub fn from_env() -> AppResult<Self> {
+        let optional = |key: &str| Some(env_or(key, "")).filter(|v| !v.is_empty());
+
+        Ok(Self {
+            url: optional("SEARCH_URL").map(|url| url.trim_end_matches('/').to_string()),
+            users_index: env_or("SEARCH_USERS_INDEX", "users"),
+            username: optional("SEARCH_USERNAME"),
+            password: optional("SEARCH_PASSWORD"),
+            request_timeout: Duration::from_millis(env_parse("SEARCH_TIMEOUT_MS", 2000)?),
+        })
+    }
+}
+
+impl fmt::Debug for SearchConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SearchConfig")
+            .field("url", &self.url)
+            .field("users_index", &self.users_index)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("request_timeout", &self.request_timeout)
+            .finish()
+    }
+}
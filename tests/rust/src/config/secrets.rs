@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use super::env_parse;
+use crate::models::{AppError, AppResult};
+
+/// Variables that may hold a secret reference instead of the secret itself
+const SECRET_VARS: [&str; 5] = [
+    "DATABASE_URL",
+    "REDIS_URL",
+    "JWT_SIGNING_KEYS",
+    "URL_SIGNING_SECRET",
+    "PII_ENCRYPTION_KEYS",
+];
+
+/// Where a secret is fetched from.
+///
+/// Written as the variable's value: `vault:<path>#<field>` for a KV v2
+/// secret, `aws-sm:<secret id>` or `aws-sm:<secret id>#<key>` for AWS
+/// Secrets Manager, the key picking a field of a JSON secret, and
+/// `file:<path>` for a file such as a Docker secret. Setting `<VAR>_FILE`
+/// is the same as `file:`, as Docker images conventionally allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretReference {
+    Vault { path: String, field: String },
+    AwsSecretsManager { secret_id: String, field: Option<String> },
+    File { path: PathBuf },
+}
+
+impl SecretReference {
+    /// None when the value is a plain secret rather than a reference
+    pub fn parse(value: &str) -> Option<AppResult<Self>> {
+        let (scheme, target) = value.split_once(':')?;
+        let invalid = || AppError::Config(format!("Invalid {} secret reference: {}", scheme, target));
+        let reference = match scheme {
+            "vault" => target
+                .split_once('#')
+                .filter(|(path, field)| !path.is_empty() && !field.is_empty())
+                .map(|(path, field)| SecretReference::Vault {
+                    path: path.trim_matches('/').to_string(),
+                    field: field.to_string(),
+                })
+                .ok_or_else(invalid),
+            "aws-sm" => {
+                let (secret_id, field) = match target.split_once('#') {
+                    Some((secret_id, field)) => (secret_id, Some(field.to_string()).filter(|f| !f.is_empty())),
+                    None => (target, None),
+                };
+                Some(secret_id)
+                    .filter(|id| !id.is_empty())
+                    .map(|secret_id| SecretReference::AwsSecretsManager {
+                        secret_id: secret_id.to_string(),
+                        field,
+                    })
+                    .ok_or_else(invalid)
+            }
+            "file" => Some(target)
+                .filter(|path| !path.is_empty())
+                .map(|path| SecretReference::File { path: PathBuf::from(path) })
+                .ok_or_else(invalid),
+            _ => return None,
+        };
+        Some(reference)
+    }
+
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            SecretReference::Vault { .. } => "vault",
+            SecretReference::AwsSecretsManager { .. } => "aws-sm",
+            SecretReference::File { .. } => "file",
+        }
+    }
+}
+
+/// Secret references found in the environment and how to reach their backends
+#[derive(Clone)]
+pub struct SecretsConfig {
+    /// Variable names and the secrets they refer to
+    pub references: Vec<(String, SecretReference)>,
+    pub vault_addr: Option<String>,
+    /// From `VAULT_TOKEN` or the file named by `VAULT_TOKEN_FILE`
+    pub vault_token: Option<String>,
+    pub vault_namespace: Option<String>,
+    /// Relative `file:` paths are read from here; Docker mounts secrets at `/run/secrets`
+    pub secrets_dir: PathBuf,
+    /// How often secrets are fetched again so rotations are picked up
+    pub refresh_interval: Duration,
+}
+
+impl SecretsConfig {
+    /// Variables listed in `SECRET_EXTRA_VARS` may hold references as well
+    pub fn from_env() -> AppResult<Self> {
+        let extra = env::var("SECRET_EXTRA_VARS").unwrap_or_default();
+        let vars = SECRET_VARS
+            .iter()
+            .map(|var| var.to_string())
+            .chain(extra.split(',').map(str::trim).filter(|v| !v.is_empty()).map(String::from));
+
+        let mut references = Vec::new();
+        for var in vars {
+            let file = env::var(format!("{}_FILE", var)).ok().filter(|path| !path.is_empty());
+            let reference = match (file, env::var(&var)) {
+                (Some(path), _) => Some(SecretReference::File { path: PathBuf::from(path) }),
+                (None, Ok(value)) => SecretReference::parse(&value).transpose()?,
+                (None, Err(_)) => None,
+            };
+            if let Some(reference) = reference {
+                references.push((var, reference));
+            }
+        }
+
+        let vault_token = match env::var("VAULT_TOKEN_FILE").ok().filter(|path| !path.is_empty()) {
+            Some(path) => Some(
+                std::fs::read_to_string(&path)
+                    .map_err(|e| AppError::Config(format!("Cannot read VAULT_TOKEN_FILE {}: {}", path, e)))?
+                    .trim()
+                    .to_string(),
+            ),
+            None => env::var("VAULT_TOKEN").ok().filter(|token| !token.is_empty()),
+        };
+
+        Ok(Self {
+            references,
+            vault_addr: env::var("VAULT_ADDR").ok().filter(|addr| !addr.is_empty()),
+            vault_token,
+            vault_namespace: env::var("VAULT_NAMESPACE").ok().filter(|ns| !ns.is_empty()),
+            secrets_dir: PathBuf::from(env::var("SECRETS_DIR").unwrap_or_else(|_| "/run/secrets".to_string())),
+            refresh_interval: Duration::from_secs(env_parse("SECRETS_REFRESH_INTERVAL_SECS", 300)?),
+        })
+    }
+
+    pub fn uses(&self, scheme: &str) -> bool {
+        self.references.iter().any(|(_, reference)| reference.scheme() == scheme)
+    }
+}
+
+impl fmt::Debug for SecretsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretsConfig")
+            .field("references", &self.references)
+            .field("vault_addr", &self.vault_addr)
+            .field("vault_token", &self.vault_token.as_ref().map(|_| "<redacted>"))
+            .field("vault_namespace", &self.vault_namespace)
+            .field("secrets_dir", &self.secrets_dir)
+            .field("refresh_interval", &self.refresh_interval)
+            .finish()
+    }
+}
+
+fn resolved_secrets() -> &'static RwLock<HashMap<String, String>> {
+    static RESOLVED: OnceLock<RwLock<HashMap<String, String>>> = OnceLock::new();
+    RESOLVED.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Make fetched secrets visible to configuration loading in place of the
+/// references their variables hold
+pub fn publish_secrets(values: &HashMap<String, String>) {
+    let mut resolved = resolved_secrets().write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    resolved.extend(values.iter().map(|(var, value)| (var.clone(), value.clone())));
+}
+
+/// The fetched secret for a variable, if it held a reference
+pub(crate) fn resolved_secret(var: &str) -> Option<String> {
+    resolved_secrets()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(var)
+        .cloned()
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::env;
+use std::time::Duration;
+
+use super::env_parse;
+use crate::models::{AppResult, UserRole};
+
+/// How long sessions last and how many a user may keep open
+#[derive(Debug, Clone)]
+pub struct SessionPolicy {
+    pub lifetime: Duration,
+    /// Sessions unused for this long end before their lifetime is up
+    pub idle_timeout: Option<Duration>,
+    /// Live sessions a user may hold; opening another ends the least recently active
+    pub max_concurrent: Option<usize>,
+    /// Token lifetimes in place of the ones in `AuthConfig`
+    pub access_token_ttl: Option<Duration>,
+    pub refresh_token_ttl: Option<Duration>,
+}
+
+/// Session policy for each role, falling back to the default one
+#[derive(Debug, Clone)]
+pub struct SessionPolicies {
+    pub default: SessionPolicy,
+    pub roles: HashMap<&'static str, SessionPolicy>,
+}
+
+impl SessionPolicies {
+    /// The default policy comes from `SESSION_LIFETIME_HOURS`,
+    /// `SESSION_IDLE_TIMEOUT_SECS` and `SESSION_MAX_CONCURRENT`; a role
+    /// overrides any of it with `SESSION_<ROLE>_LIFETIME_SECS`,
+    /// `_IDLE_TIMEOUT_SECS`, `_MAX_CONCURRENT`, `_ACCESS_TTL_SECS` and
+    /// `_REFRESH_TTL_SECS`. Zero turns an idle timeout or limit off.
+    pub fn from_env() -> AppResult<Self> {
+        let default = SessionPolicy {
+            lifetime: Duration::from_secs(env_parse("SESSION_LIFETIME_HOURS", 720u64)? * 3600),
+            idle_timeout: nonzero_secs(env_parse("SESSION_IDLE_TIMEOUT_SECS", 0)?),
+            max_concurrent: Some(env_parse("SESSION_MAX_CONCURRENT", 0usize)?).filter(|max| *max > 0),
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+        };
+
+        let mut roles = HashMap::new();
+        for role in UserRole::ALL {
+            let prefix = format!("SESSION_{}", role.as_str().to_uppercase());
+            let key = |setting: &str| format!("{}_{}", prefix, setting);
+            let max_concurrent = match env::var(key("MAX_CONCURRENT")) {
+                Ok(_) => Some(env_parse(&key("MAX_CONCURRENT"), 0usize)?).filter(|max| *max > 0),
+                Err(_) => default.max_concurrent,
+            };
+            let policy = SessionPolicy {
+                lifetime: override_secs(&key("LIFETIME_SECS"), None)?.unwrap_or(default.lifetime),
+                idle_timeout: override_secs(&key("IDLE_TIMEOUT_SECS"), default.idle_timeout)?,
+                max_concurrent,
+                access_token_ttl: override_secs(&key("ACCESS_TTL_SECS"), None)?,
+                refresh_token_ttl: override_secs(&key("REFRESH_TTL_SECS"), None)?,
+            };
+            roles.insert(role.as_str(), policy);
+        }
+        Ok(Self { default, roles })
+    }
+
+    pub fn for_role(&self, role: &UserRole) -> &SessionPolicy {
+        self.roles.get(role.as_str()).unwrap_or(&self.default)
+    }
+
+    /// The longest any refresh token may live, falling back to `fallback` for roles without an override
+    pub fn longest_refresh_token_ttl(&self, fallback: Duration) -> Duration {
+        std::iter::once(&self.default)
+            .chain(self.roles.values())
+            .map(|policy| policy.refresh_token_ttl.unwrap_or(fallback))
+            .max()
+            .unwrap_or(fallback)
+    }
+}
+
+fn nonzero_secs(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// `inherited` when the variable is unset, otherwise its value with zero meaning none
+fn override_secs(key: &str, inherited: Option<Duration>) -> AppResult<Option<Duration>> {
+    match env::var(key) {
+        Ok(_) => Ok(nonzero_secs(env_parse(key, 0)?)),
+        Err(_) => Ok(inherited),
+    }
+}
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use super::env_parse;
+use crate::models::AppResult;
+
+/// Component health probes behind the public status page
+#[derive(Debug, Clone)]
+pub struct StatusConfig {
+    pub probe_interval: Duration,
+    /// A probe taking longer than this counts as a failure
+    pub probe_timeout: Duration,
+    /// How far back uptime and incidents are reported; older checks are pruned
+    pub history_days: i64,
+    /// How long a built status page is served before it is rebuilt
+    pub page_ttl: Duration,
+}
+
+impl StatusConfig {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            probe_interval: Duration::from_secs(env_parse("STATUS_PROBE_INTERVAL_SECS", 60)?),
+            probe_timeout: Duration::from_millis(env_parse("STATUS_PROBE_TIMEOUT_MS", 2000)?),
+            history_days: env_parse("STATUS_HISTORY_DAYS", 90)?,
+            page_ttl: Duration::from_secs(env_parse("STATUS_PAGE_TTL_SECS", 30)?),
+        })
+    }
+}
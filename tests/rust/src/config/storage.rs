@@ -0,0 +1,19 @@
+use std::path::PathBuf;
+
+use super::env_or;
+use crate::models::AppResult;
+
+/// Where generated files such as exports are kept
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    /// Directory files are written under; shared between instances, e.g. a mounted volume
+    pub root: PathBuf,
+}
+
+impl StorageConfig {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            root: PathBuf::from(env_or("STORAGE_ROOT", "./storage")),
+        })
+    }
+}
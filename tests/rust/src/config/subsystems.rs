@@ -0,0 +1,25 @@
+use super::env_parse;
+use crate::models::AppResult;
+
+/// Optional subsystems an instance can run without.
+///
+/// A switched-off subsystem is replaced by a stand-in doing nothing, so
+/// callers need not check for it.
+#[derive(Debug, Clone)]
+pub struct SubsystemsConfig {
+    /// Mirror user mutations into the search cluster; off on instances
+    /// that should leave indexing to others. Searches still use the cluster.
+    pub search_indexing: bool,
+    /// Accept delivery status callbacks from email providers; when off they
+    /// are acknowledged and dropped
+    pub delivery_webhooks: bool,
+}
+
+impl SubsystemsConfig {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            search_indexing: env_parse("SEARCH_INDEXING_ENABLED", true)?,
+            delivery_webhooks: env_parse("DELIVERY_WEBHOOKS_ENABLED", true)?,
+        })
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use super::{env_or, env_pairs, env_parse, RateLimit};
+use crate::models::{AppError, AppResult, TenantContext};
+
+/// Limits a tenant's users and API keys share, on top of their own; None is unlimited
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TenantLimits {
+    /// Requests from all of the tenant's callers and addresses together
+    pub requests: Option<RateLimit>,
+    /// Notifications queued per UTC day
+    pub notifications_per_day: Option<u64>,
+    /// Accounts onboarded into the tenant
+    pub max_users: Option<u64>,
+}
+
+/// Tenant limits, with overrides for individual tenants
+#[derive(Debug, Clone, Default)]
+pub struct TenantLimitsConfig {
+    pub default: TenantLimits,
+    pub tenants: HashMap<String, TenantLimits>,
+}
+
+impl TenantLimitsConfig {
+    /// Every tenant gets `TENANT_RATE_LIMIT` (`requests/seconds`),
+    /// `TENANT_NOTIFICATIONS_PER_DAY` and `TENANT_MAX_USERS`; a tenant listed
+    /// as `tenant=value,...` in `TENANT_RATE_LIMITS`,
+    /// `TENANT_NOTIFICATION_LIMITS` or `TENANT_USER_LIMITS` gets that value
+    /// instead. Zero turns a limit off.
+    pub fn from_env() -> AppResult<Self> {
+        let default = TenantLimits {
+            requests: parse_rate("TENANT_RATE_LIMIT", &env_or("TENANT_RATE_LIMIT", "0"))?,
+            notifications_per_day: nonzero(env_parse("TENANT_NOTIFICATIONS_PER_DAY", 0)?),
+            max_users: nonzero(env_parse("TENANT_MAX_USERS", 0)?),
+        };
+
+        let mut tenants: HashMap<String, TenantLimits> = HashMap::new();
+        for (tenant, value) in tenant_pairs("TENANT_RATE_LIMITS")? {
+            tenants.entry(tenant).or_insert(default).requests = parse_rate("TENANT_RATE_LIMITS", &value)?;
+        }
+        for (tenant, value) in tenant_pairs("TENANT_NOTIFICATION_LIMITS")? {
+            tenants.entry(tenant).or_insert(default).notifications_per_day =
+                nonzero(parse_count("TENANT_NOTIFICATION_LIMITS", &value)?);
+        }
+        for (tenant, value) in tenant_pairs("TENANT_USER_LIMITS")? {
+            tenants.entry(tenant).or_insert(default).max_users = nonzero(parse_count("TENANT_USER_LIMITS", &value)?);
+        }
+
+        Ok(Self { default, tenants })
+    }
+
+    pub fn for_tenant(&self, tenant: &TenantContext) -> TenantLimits {
+        self.tenants.get(&tenant.tenant_id).copied().unwrap_or(self.default)
+    }
+
+    /// Whether any tenant is held to a request rate
+    pub fn limits_requests(&self) -> bool {
+        std::iter::once(&self.default)
+            .chain(self.tenants.values())
+            .any(|limits| limits.requests.is_some())
+    }
+}
+
+/// `tenant=value` pairs, refusing names that are not tenant ids
+fn tenant_pairs(key: &str) -> AppResult<HashMap<String, String>> {
+    let pairs = env_pairs(key)?;
+    for tenant in pairs.keys() {
+        TenantContext::new(tenant)
+            .map_err(|_| AppError::Config(format!("{} names an invalid tenant: {}", key, tenant)))?;
+    }
+    Ok(pairs)
+}
+
+fn nonzero(count: u64) -> Option<u64> {
+    (count > 0).then_some(count)
+}
+
+fn parse_count(key: &str, raw: &str) -> AppResult<u64> {
+    raw.parse()
+        .map_err(|_| AppError::Config(format!("{} has an invalid value: {}", key, raw)))
+}
+
+fn parse_rate(key: &str, raw: &str) -> AppResult<Option<RateLimit>> {
+    if raw.trim() == "0" {
+        return Ok(None);
+    }
+    RateLimit::parse(key, raw).map(Some)
+}
@@ -0,0 +1,123 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use super::{env_or, env_parse};
+use crate::models::AppResult;
+
+const DEFAULT_RESERVED: &str = "admin,administrator,root,superuser,support,help,helpdesk,system,security,\
+    staff,moderator,official,api,www,mail,postmaster,hostmaster,abuse,noreply,billing,info";
+const DEFAULT_BLOCKED_WORDS: &str = "fuck,shit,cunt,bitch,asshole,bastard,whore,slut";
+
+/// A specific way a username breaks the policy; `code` is stable for clients to match on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsernameViolation {
+    TooShort { min: usize },
+    TooLong { max: usize },
+    InvalidCharacters,
+    InvalidStart,
+    Reserved,
+    Profane,
+}
+
+impl UsernameViolation {
+    pub fn code(&self) -> &'static str {
+        match self {
+            UsernameViolation::TooShort { .. } => "username_too_short",
+            UsernameViolation::TooLong { .. } => "username_too_long",
+            UsernameViolation::InvalidCharacters => "username_invalid_characters",
+            UsernameViolation::InvalidStart => "username_invalid_start",
+            UsernameViolation::Reserved => "username_reserved",
+            UsernameViolation::Profane => "username_profane",
+        }
+    }
+}
+
+impl fmt::Display for UsernameViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            UsernameViolation::TooShort { min } => format!("Username must be at least {} characters", min),
+            UsernameViolation::TooLong { max } => format!("Username must be at most {} characters", max),
+            UsernameViolation::InvalidCharacters => "Username contains characters that are not allowed".to_string(),
+            UsernameViolation::InvalidStart => "Username must start with a letter or digit".to_string(),
+            UsernameViolation::Reserved => "Username is reserved".to_string(),
+            UsernameViolation::Profane => "Username contains a blocked word".to_string(),
+        };
+        write!(f, "{} ({})", message, self.code())
+    }
+}
+
+/// Rules a username must satisfy
+///
+/// Reserved names and blocked words are compared with case and separators
+/// folded away, so `Ad.Min` is as reserved as `admin`. Blocked words match
+/// anywhere in the name, which can catch innocent names; tune the list with
+/// `USERNAME_BLOCKED_WORDS`.
+#[derive(Debug, Clone)]
+pub struct UsernamePolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    /// Allowed besides ASCII letters and digits
+    pub allowed_symbols: String,
+    pub reserved: HashSet<String>,
+    pub blocked_words: Vec<String>,
+}
+
+impl UsernamePolicy {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            min_length: env_parse("USERNAME_MIN_LENGTH", 3)?,
+            max_length: env_parse("USERNAME_MAX_LENGTH", 32)?,
+            allowed_symbols: env_or("USERNAME_ALLOWED_SYMBOLS", "._-"),
+            reserved: word_list(&env_or("USERNAME_RESERVED", DEFAULT_RESERVED)).collect(),
+            blocked_words: word_list(&env_or("USERNAME_BLOCKED_WORDS", DEFAULT_BLOCKED_WORDS)).collect(),
+        })
+    }
+
+    /// Every rule the username breaks, empty if it is acceptable
+    pub fn check(&self, username: &str) -> Vec<UsernameViolation> {
+        let mut violations = Vec::new();
+        let length = username.chars().count();
+        if length < self.min_length {
+            violations.push(UsernameViolation::TooShort { min: self.min_length });
+        }
+        if length > self.max_length {
+            violations.push(UsernameViolation::TooLong { max: self.max_length });
+        }
+        if !username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || self.allowed_symbols.contains(c))
+        {
+            violations.push(UsernameViolation::InvalidCharacters);
+        }
+        if username.chars().next().is_some_and(|c| !c.is_ascii_alphanumeric()) {
+            violations.push(UsernameViolation::InvalidStart);
+        }
+
+        let folded = fold(username);
+        if self.reserved.contains(&folded) {
+            violations.push(UsernameViolation::Reserved);
+        }
+        if self.blocked_words.iter().any(|word| folded.contains(word.as_str())) {
+            violations.push(UsernameViolation::Profane);
+        }
+        violations
+    }
+
+    /// Problems with a username as validation messages, each ending in its code
+    pub fn validate(&self, username: &str) -> Vec<String> {
+        self.check(username).iter().map(ToString::to_string).collect()
+    }
+}
+
+fn word_list(raw: &str) -> impl Iterator<Item = String> + '_ {
+    raw.split(',').map(fold).filter(|word| !word.is_empty())
+}
+
+/// Lowercased with everything but letters and digits removed
+fn fold(value: &str) -> String {
+    value
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
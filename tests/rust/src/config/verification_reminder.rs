@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use super::{env_or, env_parse};
+use crate::models::AppResult;
+
+const DEFAULT_TEMPLATES: &str = "verification_reminder,verification_reminder_second,verification_reminder_final";
+
+/// Reminders to users who have not confirmed their email address, and
+/// when their account is suspended for it
+#[derive(Debug, Clone)]
+pub struct VerificationReminderPolicy {
+    /// How long after signing up an unverified user gets the first reminder; zero sends none
+    pub first_after: Duration,
+    /// Time between one reminder and the next
+    pub interval: Duration,
+    /// Template of each reminder in turn, so later ones can be more pressing; each is sent once
+    pub templates: Vec<String>,
+    /// How long after signing up a still unverified account is suspended; zero never suspends
+    pub suspend_after: Duration,
+    /// How often the campaign looks for users to remind
+    pub run_interval: Duration,
+}
+
+impl VerificationReminderPolicy {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            first_after: Duration::from_secs(env_parse("VERIFICATION_REMINDER_AFTER_DAYS", 3u64)? * 86_400),
+            interval: Duration::from_secs(env_parse("VERIFICATION_REMINDER_INTERVAL_DAYS", 4u64)?.max(1) * 86_400),
+            templates: env_or("VERIFICATION_REMINDER_TEMPLATES", DEFAULT_TEMPLATES)
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(String::from)
+                .collect(),
+            suspend_after: Duration::from_secs(env_parse("VERIFICATION_SUSPEND_AFTER_DAYS", 30u64)? * 86_400),
+            run_interval: Duration::from_secs(env_parse("VERIFICATION_REMINDER_RUN_INTERVAL_SECS", 3600)?),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        (!self.first_after.is_zero() && !self.templates.is_empty()) || !self.suspend_after.is_zero()
+    }
+
+    /// How many reminders a user unverified for this long should have had by now
+    pub fn reminders_due(&self, unverified_for: Duration) -> usize {
+        if self.first_after.is_zero() || unverified_for < self.first_after {
+            return 0;
+        }
+        let steps = (unverified_for - self.first_after).as_secs() / self.interval.as_secs().max(1);
+        (steps as usize + 1).min(self.templates.len())
+    }
+
+    /// Whether an account unverified for this long is past the deadline
+    pub fn suspends(&self, unverified_for: Duration) -> bool {
+        !self.suspend_after.is_zero() && unverified_for >= self.suspend_after
+    }
+}
@@ -0,0 +1,50 @@
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::time::Duration;
+
+use crate::models::{AppError, AppResult};
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+const MAX_CONNECTIONS: u32 = 20;
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connection pool of one PostgreSQL database; repositories share it through an `Arc`
+pub struct Database {
+    pool: PgPool,
+}
+
+impl Database {
+    /// Open a pool on `url`, failing when the database cannot be reached
+    pub async fn connect(url: &str) -> AppResult<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .acquire_timeout(ACQUIRE_TIMEOUT)
+            .connect(url)
+            .await?;
+        Ok(Self { pool })
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Apply the migrations this build carries that the database has not seen yet
+    pub async fn migrate(&self) -> AppResult<()> {
+        MIGRATOR
+            .run(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.into()))
+    }
+
+    pub async fn ping(&self) -> AppResult<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Wait for checked-out connections to return, then close them all
+    pub async fn close(&self) -> AppResult<()> {
+        self.pool.close().await;
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::AppResult;
+use crate::services::UserService;
+use crate::utils::{Logger, Metrics};
+
+const ERASURE_BATCH: i64 = 100;
+
+/// Background job permanently erasing accounts once their deletion grace period ends
+pub struct AccountErasureJob {
+    user_service: Arc<UserService>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl AccountErasureJob {
+    pub fn new(user_service: Arc<UserService>, metrics: Arc<Metrics>, logger: Arc<Logger>) -> Self {
+        Self {
+            user_service,
+            metrics,
+            logger,
+        }
+    }
+
+    /// Erase every due account, returning how many were removed.
+    ///
+    /// Shutdown is honoured between batches; accounts not reached yet are
+    /// still due on the next run.
+    pub async fn run_once(&self, shutdown: &CancellationToken) -> AppResult<usize> {
+        let mut erased = 0;
+        while !shutdown.is_cancelled() {
+            let batch = self.user_service.erase_due_accounts(ERASURE_BATCH).await?;
+            erased += batch;
+            if (batch as i64) < ERASURE_BATCH {
+                break;
+            }
+        }
+
+        if erased > 0 {
+            self.metrics
+                .add_to_counter("accounts.erased", erased as u64)
+                .await?;
+            self.logger
+                .info(&format!("Erased {} accounts after their deletion grace period", erased));
+        }
+        Ok(erased)
+    }
+
+    pub fn spawn(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.run_once(&shutdown).await {
+                    self.logger.error(&format!("Account erasure failed: {}", e));
+                }
+            }
+        })
+    }
+}
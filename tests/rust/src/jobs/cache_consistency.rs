@@ -0,0 +1,255 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::config::CacheConfig;
+use crate::models::{AppError, AppResult, User};
+use crate::repositories::{CachedUser, UserRepository};
+use crate::services::{CachePolicy, CacheService};
+use crate::utils::{Logger, Metrics};
+
+/// Outcome of one consistency check
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConsistencyReport {
+    pub sampled: usize,
+    pub consistent: usize,
+    /// Entries differing from the stored account
+    pub drifted: usize,
+    /// Entries of accounts no longer in the database
+    pub orphaned: usize,
+    /// Entries that could not be decoded
+    pub corrupt: usize,
+    /// Entries rewritten or expired while being checked
+    pub skipped: usize,
+    pub evicted: usize,
+}
+
+impl ConsistencyReport {
+    /// Share of checked entries that were wrong in any way
+    pub fn drift_ratio(&self) -> f64 {
+        let checked = self.sampled - self.skipped;
+        if checked == 0 {
+            return 0.0;
+        }
+        (self.drifted + self.orphaned + self.corrupt) as f64 / checked as f64
+    }
+}
+
+/// What a sampled entry turned out to be
+enum Verdict {
+    Consistent,
+    Drifted(Vec<String>),
+    Orphaned,
+    Corrupt,
+    Skipped,
+}
+
+/// Background job comparing a sample of cached users with the database.
+///
+/// Entries are sampled by walking the cache keyspace with `SCAN`, picking up
+/// where the previous run stopped, so successive runs cover every entry.
+/// Drift points at an invalidation bug: a write path that changed an account
+/// without going through `CachingUserRepository`. Stale entries are only
+/// reported unless auto-eviction is turned on.
+pub struct CacheConsistencyJob {
+    cache: Arc<CacheService>,
+    policy: CachePolicy,
+    /// Read directly, bypassing the cache being checked
+    users: Arc<dyn UserRepository>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+    sample_size: usize,
+    auto_evict: bool,
+    cursor: AtomicU64,
+}
+
+impl CacheConsistencyJob {
+    pub fn new(
+        cache: Arc<CacheService>,
+        policy: CachePolicy,
+        users: Arc<dyn UserRepository>,
+        config: &CacheConfig,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        Self {
+            cache,
+            policy,
+            users,
+            metrics,
+            logger,
+            sample_size: config.verify_sample_size,
+            auto_evict: config.verify_auto_evict,
+            cursor: AtomicU64::new(0),
+        }
+    }
+
+    /// Check one sample of cached users, evicting the stale ones when configured to
+    pub async fn run_once(&self, shutdown: &CancellationToken) -> AppResult<ConsistencyReport> {
+        let mut report = ConsistencyReport::default();
+        if !self.policy.is_enabled() {
+            return Ok(report);
+        }
+
+        for id in self.sample().await? {
+            if shutdown.is_cancelled() {
+                break;
+            }
+            report.sampled += 1;
+            let stale = match self.verify(&id).await? {
+                Verdict::Consistent => {
+                    report.consistent += 1;
+                    false
+                }
+                Verdict::Skipped => {
+                    report.skipped += 1;
+                    false
+                }
+                Verdict::Drifted(fields) => {
+                    report.drifted += 1;
+                    self.logger
+                        .warn(&format!("Cached user {} differs from the database in: {}", id, fields.join(", ")));
+                    for field in &fields {
+                        let _ = self
+                            .metrics
+                            .increment_labeled_counter("cache.consistency.drift_fields", &[("field", field)])
+                            .await;
+                    }
+                    true
+                }
+                Verdict::Orphaned => {
+                    report.orphaned += 1;
+                    self.logger.warn(&format!("Cached user {} no longer exists in the database", id));
+                    true
+                }
+                Verdict::Corrupt => {
+                    report.corrupt += 1;
+                    self.logger.warn(&format!("Cached user {} could not be decoded", id));
+                    true
+                }
+            };
+            if stale && self.auto_evict {
+                self.cache.evict(&self.policy, &id).await?;
+                report.evicted += 1;
+            }
+        }
+
+        self.record(&report).await?;
+        Ok(report)
+    }
+
+    pub fn spawn(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.run_once(&shutdown).await {
+                    self.logger.error(&format!("Cache consistency check failed: {}", e));
+                }
+            }
+        })
+    }
+
+    /// Ids of the next cached users in keyspace order, wrapping around at the end
+    async fn sample(&self) -> AppResult<Vec<String>> {
+        let mut cursor = self.cursor.load(Ordering::Relaxed);
+        let mut ids = Vec::with_capacity(self.sample_size);
+        loop {
+            let (next, found) = self.cache.scan_ids(&self.policy, cursor, self.sample_size).await?;
+            ids.extend(found);
+            cursor = next;
+            if cursor == 0 || ids.len() >= self.sample_size {
+                break;
+            }
+        }
+        self.cursor.store(cursor, Ordering::Relaxed);
+        ids.truncate(self.sample_size);
+        Ok(ids)
+    }
+
+    async fn verify(&self, id: &str) -> AppResult<Verdict> {
+        let Ok(user_id) = Uuid::parse_str(id) else {
+            return Ok(Verdict::Corrupt);
+        };
+        let cached = match self.cache.peek::<CachedUser>(&self.policy, id).await {
+            Ok(Some(cached)) => cached.user,
+            Ok(None) => return Ok(Verdict::Skipped),
+            Err(AppError::Cache(_)) => return Ok(Verdict::Corrupt),
+            Err(e) => return Err(e),
+        };
+        let Some(stored) = self.users.find_by_id(user_id).await? else {
+            return Ok(Verdict::Orphaned);
+        };
+
+        let cached_fields = snapshot(&cached)?;
+        let fields = differing_fields(&cached_fields, &snapshot(&stored)?);
+        if fields.is_empty() {
+            return Ok(Verdict::Consistent);
+        }
+
+        // A write landing between the two reads refreshes the entry; only an
+        // entry left as it was is stale
+        match self.cache.peek::<CachedUser>(&self.policy, id).await {
+            Ok(Some(again)) if snapshot(&again.user)? == cached_fields => Ok(Verdict::Drifted(fields)),
+            _ => Ok(Verdict::Skipped),
+        }
+    }
+
+    async fn record(&self, report: &ConsistencyReport) -> AppResult<()> {
+        self.metrics
+            .add_to_counter("cache.consistency.sampled", report.sampled as u64)
+            .await?;
+        self.metrics
+            .add_to_counter("cache.consistency.drifted", report.drifted as u64)
+            .await?;
+        self.metrics
+            .add_to_counter("cache.consistency.orphaned", report.orphaned as u64)
+            .await?;
+        self.metrics
+            .add_to_counter("cache.consistency.corrupt", report.corrupt as u64)
+            .await?;
+        self.metrics
+            .add_to_counter("cache.consistency.evicted", report.evicted as u64)
+            .await?;
+        self.metrics
+            .set_gauge("cache.consistency.drift_ratio", report.drift_ratio())
+            .await?;
+
+        let stale = report.drifted + report.orphaned + report.corrupt;
+        if stale > 0 {
+            self.logger.warn(&format!(
+                "Cache consistency: {} of {} sampled users stale, {} evicted",
+                stale, report.sampled, report.evicted
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn snapshot(user: &User) -> AppResult<Value> {
+    serde_json::to_value(user).map_err(|e| AppError::Internal(format!("Unserializable user {}: {}", user.id, e)))
+}
+
+/// Top-level fields whose values differ, by name only so no account data is logged
+fn differing_fields(cached: &Value, stored: &Value) -> Vec<String> {
+    let (Value::Object(cached), Value::Object(stored)) = (cached, stored) else {
+        return vec!["user".to_string()];
+    };
+    let mut fields: Vec<String> = cached
+        .keys()
+        .chain(stored.keys())
+        .filter(|field| cached.get(*field) != stored.get(*field))
+        .cloned()
+        .collect();
+    fields.sort();
+    fields.dedup();
+    fields
+}
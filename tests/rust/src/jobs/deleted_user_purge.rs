@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::AppResult;
+use crate::services::UserService;
+use crate::utils::{Logger, Metrics};
+
+const PURGE_BATCH: i64 = 100;
+
+/// Background job purging users who stayed soft-deleted past the retention period
+pub struct DeletedUserPurgeJob {
+    user_service: Arc<UserService>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl DeletedUserPurgeJob {
+    pub fn new(user_service: Arc<UserService>, metrics: Arc<Metrics>, logger: Arc<Logger>) -> Self {
+        Self {
+            user_service,
+            metrics,
+            logger,
+        }
+    }
+
+    /// Purge every due user, returning how many were removed.
+    ///
+    /// Shutdown is honoured between batches; users not reached yet are
+    /// still due on the next run.
+    pub async fn run_once(&self, shutdown: &CancellationToken) -> AppResult<usize> {
+        let mut purged = 0;
+        while !shutdown.is_cancelled() {
+            let batch = self.user_service.purge_deleted_users(PURGE_BATCH).await?;
+            purged += batch;
+            if (batch as i64) < PURGE_BATCH {
+                break;
+            }
+        }
+
+        if purged > 0 {
+            self.metrics
+                .add_to_counter("accounts.purged", purged as u64)
+                .await?;
+            self.logger
+                .info(&format!("Purged {} users deleted past their retention", purged));
+        }
+        Ok(purged)
+    }
+
+    pub fn spawn(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.run_once(&shutdown).await {
+                    self.logger.error(&format!("Deleted user purge failed: {}", e));
+                }
+            }
+        })
+    }
+}
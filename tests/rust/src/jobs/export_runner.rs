@@ -0,0 +1,67 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::{AppResult, JobQueue};
+use crate::services::{ExportService, JobQueues};
+use crate::utils::Logger;
+
+const EXPIRY_BATCH: i64 = 100;
+
+/// Background job running queued exports and deleting expired export files
+pub struct ExportRunner {
+    exports: Arc<ExportService>,
+    queues: Arc<JobQueues>,
+    logger: Arc<Logger>,
+}
+
+impl ExportRunner {
+    pub fn new(exports: Arc<ExportService>, queues: Arc<JobQueues>, logger: Arc<Logger>) -> Self {
+        Self { exports, queues, logger }
+    }
+
+    /// Run exports until the queue is empty, then expire old files,
+    /// returning how many exports ran. While the queue is paused only
+    /// expired files are deleted.
+    pub async fn run_once(&self, shutdown: &CancellationToken) -> AppResult<usize> {
+        let mut ran = 0;
+        let paused = self.queues.is_paused(JobQueue::Exports).await;
+        while !paused && !shutdown.is_cancelled() {
+            if self.exports.run_next().await?.is_none() {
+                break;
+            }
+            ran += 1;
+        }
+
+        let mut expired = 0;
+        while !shutdown.is_cancelled() {
+            let batch = self.exports.expire_artifacts(EXPIRY_BATCH).await?;
+            expired += batch;
+            if (batch as i64) < EXPIRY_BATCH {
+                break;
+            }
+        }
+        if expired > 0 {
+            self.logger
+                .info(&format!("Deleted {} export files past their retention", expired));
+        }
+        Ok(ran)
+    }
+
+    pub fn spawn(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                self.queues.record_run(JobQueue::Exports, interval).await;
+                if let Err(e) = self.run_once(&shutdown).await {
+                    self.logger.error(&format!("Export runner failed: {}", e));
+                }
+            }
+        })
+    }
+}
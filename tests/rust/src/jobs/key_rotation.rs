@@ -0,0 +1,264 @@
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::AppResult;
+use crate::utils::{EncryptedField, KeyRing, Logger, Metrics};
+
+/// Rows remaining per key version, as reported to the health metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyVersionHealth {
+    pub active_version: u32,
+    pub rows_by_version: Vec<(u32, i64)>,
+    pub rows_on_old_keys: i64,
+}
+
+/// Outcome of a single rotation pass
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RotationReport {
+    pub batches: u32,
+    pub rows_rotated: u64,
+    /// Rows whose stored PII could not be re-encrypted; they stay on their old key
+    pub rows_skipped: u64,
+}
+
+/// Outcome of one batch; `last_id` is where the next batch of the pass starts
+struct BatchOutcome {
+    rotated: u64,
+    skipped: u64,
+    last_id: Option<Uuid>,
+}
+
+/// Region label of the home database in rotation metrics
+const HOME_REGION: &str = "home";
+
+/// Background job re-encrypting user PII columns under the active key.
+///
+/// One runs per database: the home one and each region's, which share the
+/// key ring. Metrics carry a `region` label to tell them apart.
+pub struct KeyRotationJob {
+    database: Arc<Database>,
+    /// None for the home database
+    region: Option<String>,
+    key_ring: Arc<KeyRing>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+    batch_size: i64,
+}
+
+impl KeyRotationJob {
+    pub fn new(
+        database: Arc<Database>,
+        region: Option<String>,
+        key_ring: Arc<KeyRing>,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+        batch_size: i64,
+    ) -> Self {
+        Self {
+            database,
+            region,
+            key_ring,
+            metrics,
+            logger,
+            batch_size,
+        }
+    }
+
+    /// Re-encrypt batches until no rows remain on old keys or shutdown is requested.
+    ///
+    /// Each batch commits on its own, so rows not reached yet simply stay
+    /// on their old key until the next pass. A row that cannot be
+    /// re-encrypted is logged and skipped rather than failing its batch; the
+    /// pass walks past it by id, and the next pass tries it again.
+    pub async fn run_once(&self, shutdown: &CancellationToken) -> AppResult<RotationReport> {
+        let started = Instant::now();
+        let mut report = RotationReport::default();
+        let mut after = None;
+
+        while !shutdown.is_cancelled() {
+            let batch = self.rotate_batch(after).await?;
+            let Some(last_id) = batch.last_id else {
+                break;
+            };
+            after = Some(last_id);
+            report.batches += 1;
+            report.rows_rotated += batch.rotated;
+            report.rows_skipped += batch.skipped;
+            if batch.rotated > 0 {
+                self.metrics
+                    .add_to_labeled_counter("encryption.rows_rotated", &[("region", self.region())], batch.rotated)
+                    .await?;
+            }
+            if batch.skipped > 0 {
+                self.metrics
+                    .add_to_labeled_counter("encryption.rows_skipped", &[("region", self.region())], batch.skipped)
+                    .await?;
+            }
+        }
+
+        if report.rows_rotated > 0 {
+            self.logger.info(&format!(
+                "Re-encrypted {} user rows in region {} under key v{} in {} batches",
+                report.rows_rotated,
+                self.region(),
+                self.key_ring.active_version(),
+                report.batches
+            ));
+        }
+        if report.rows_skipped > 0 {
+            self.logger.warn(&format!(
+                "Left {} user rows in region {} on old keys that could not be re-encrypted",
+                report.rows_skipped,
+                self.region()
+            ));
+        }
+
+        self.metrics
+            .record_labeled_duration("encryption.rotation.duration", &[("region", self.region())], started.elapsed())
+            .await?;
+
+        Ok(report)
+    }
+
+    /// Count rows per key version and publish them as gauges
+    pub async fn report_health(&self) -> AppResult<KeyVersionHealth> {
+        let rows = sqlx::query(
+            "SELECT pii_key_version, COUNT(*) AS row_count FROM users GROUP BY pii_key_version",
+        )
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let active_version = self.key_ring.active_version();
+        let mut rows_by_version = Vec::with_capacity(rows.len());
+        let mut rows_on_old_keys = 0;
+
+        for row in rows {
+            let version = row.try_get::<i32, _>("pii_key_version")? as u32;
+            let count: i64 = row.try_get("row_count")?;
+
+            if version != active_version {
+                rows_on_old_keys += count;
+            }
+            self.metrics
+                .set_labeled_gauge(
+                    "encryption.rows_by_key_version",
+                    &[("region", self.region()), ("version", version.to_string().as_str())],
+                    count as f64,
+                )
+                .await?;
+            rows_by_version.push((version, count));
+        }
+
+        self.metrics
+            .set_labeled_gauge("encryption.rows_on_old_keys", &[("region", self.region())], rows_on_old_keys as f64)
+            .await?;
+
+        Ok(KeyVersionHealth {
+            active_version,
+            rows_by_version,
+            rows_on_old_keys,
+        })
+    }
+
+    /// Run rotation passes on a fixed interval until shutdown
+    pub fn spawn(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+
+                if let Err(e) = self.run_once(&shutdown).await {
+                    self.logger
+                        .error(&format!("PII key rotation failed in region {}: {}", self.region(), e));
+                }
+                if let Err(e) = self.report_health().await {
+                    self.logger
+                        .warn(&format!("Failed to report PII key health of region {}: {}", self.region(), e));
+                }
+            }
+        })
+    }
+
+    /// Lock and re-encrypt one batch of rows with ids past `after`
+    async fn rotate_batch(&self, after: Option<Uuid>) -> AppResult<BatchOutcome> {
+        let active_version = self.key_ring.active_version() as i32;
+        let mut tx = self.database.pool().begin().await?;
+
+        let rows = sqlx::query(
+            "SELECT id, first_name, last_name FROM users \
+             WHERE pii_key_version <> $1 AND ($3::uuid IS NULL OR id > $3) \
+             ORDER BY id LIMIT $2 FOR UPDATE SKIP LOCKED",
+        )
+        .bind(active_version)
+        .bind(self.batch_size)
+        .bind(after)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut outcome = BatchOutcome {
+            rotated: 0,
+            skipped: 0,
+            last_id: None,
+        };
+        for row in &rows {
+            let id: Uuid = row.try_get("id")?;
+            outcome.last_id = Some(id);
+            let (first_name, last_name) = match self.reencrypt_names(row) {
+                Ok(names) => names,
+                Err(e) => {
+                    self.logger.warn(&format!(
+                        "Skipping PII key rotation of user {} in region {}: {}",
+                        id,
+                        self.region(),
+                        e
+                    ));
+                    outcome.skipped += 1;
+                    continue;
+                }
+            };
+
+            sqlx::query(
+                "UPDATE users SET first_name = $1, last_name = $2, pii_key_version = $3 \
+                 WHERE id = $4",
+            )
+            .bind(first_name)
+            .bind(last_name)
+            .bind(active_version)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+            outcome.rotated += 1;
+        }
+
+        tx.commit().await?;
+        Ok(outcome)
+    }
+
+    fn region(&self) -> &str {
+        self.region.as_deref().unwrap_or(HOME_REGION)
+    }
+
+    fn reencrypt_names(&self, row: &PgRow) -> AppResult<(String, String)> {
+        let first_name = self.reencrypt(&row.try_get::<String, _>("first_name")?)?;
+        let last_name = self.reencrypt(&row.try_get::<String, _>("last_name")?)?;
+        Ok((first_name, last_name))
+    }
+
+    fn reencrypt(&self, stored: &str) -> AppResult<String> {
+        if stored.is_empty() {
+            return Ok(String::new());
+        }
+        let field = EncryptedField::parse(stored)?;
+        Ok(self.key_ring.rotate(&field)?.encode())
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::AppResult;
+use crate::services::UserService;
+use crate::utils::{Logger, Metrics};
+
+const UNLOCK_BATCH: i64 = 100;
+
+/// Background job clearing failed sign-in lockouts once they run out
+pub struct LockoutExpiryJob {
+    user_service: Arc<UserService>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl LockoutExpiryJob {
+    pub fn new(user_service: Arc<UserService>, metrics: Arc<Metrics>, logger: Arc<Logger>) -> Self {
+        Self {
+            user_service,
+            metrics,
+            logger,
+        }
+    }
+
+    /// Unlock every account whose lockout has expired, returning how many were unlocked
+    pub async fn run_once(&self, shutdown: &CancellationToken) -> AppResult<usize> {
+        let mut unlocked = 0;
+        while !shutdown.is_cancelled() {
+            let batch = self.user_service.unlock_expired_lockouts(UNLOCK_BATCH).await?;
+            unlocked += batch;
+            if (batch as i64) < UNLOCK_BATCH {
+                break;
+            }
+        }
+
+        if unlocked > 0 {
+            self.metrics
+                .add_to_counter("accounts.unlocked", unlocked as u64)
+                .await?;
+            self.logger
+                .info(&format!("Unlocked {} accounts after their lockout expired", unlocked));
+        }
+        Ok(unlocked)
+    }
+
+    pub fn spawn(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.run_once(&shutdown).await {
+                    self.logger.error(&format!("Lockout expiry failed: {}", e));
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,27 @@
+pub mod key_rotation;
+pub mod user_search_projection;
+pub mod outbox_relay;
+pub mod outbox_retention;
+pub mod account_erasure;
+pub mod lockout_expiry;
+pub mod cache_consistency;
+pub mod export_runner;
+pub mod secret_refresh;
+pub mod role_grant_expiry;
+pub mod notification_archival;
+pub mod verification_reminders;
+pub mod deleted_user_purge;
+
+pub use key_rotation::{KeyRotationJob, KeyVersionHealth, RotationReport};
+pub use user_search_projection::UserSearchProjection;
+pub use outbox_relay::OutboxRelay;
+pub use outbox_retention::OutboxRetentionJob;
+pub use account_erasure::AccountErasureJob;
+pub use lockout_expiry::LockoutExpiryJob;
+pub use cache_consistency::{CacheConsistencyJob, ConsistencyReport};
+pub use export_runner::ExportRunner;
+pub use secret_refresh::SecretRefreshJob;
+pub use role_grant_expiry::RoleGrantExpiryJob;
+pub use notification_archival::NotificationArchivalJob;
+pub use verification_reminders::{VerificationReminderJob, VerificationReminderReport};
+pub use deleted_user_purge::DeletedUserPurgeJob;
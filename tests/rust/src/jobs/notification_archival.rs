@@ -0,0 +1,82 @@
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::NotificationConfig;
+use crate::models::{AppError, AppResult};
+use crate::repositories::{NotificationArchiveRepository, NotificationRepository};
+use crate::utils::{Logger, Metrics};
+
+/// Background job moving delivered notifications past their retention to cold storage
+pub struct NotificationArchivalJob {
+    notifications: Arc<dyn NotificationRepository>,
+    archive: Arc<NotificationArchiveRepository>,
+    archive_after: Duration,
+    batch_size: i64,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl NotificationArchivalJob {
+    /// None when archival is switched off
+    pub fn from_config(
+        notifications: Arc<dyn NotificationRepository>,
+        archive: Arc<NotificationArchiveRepository>,
+        config: &NotificationConfig,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> Option<Self> {
+        config.archive_after.map(|archive_after| Self {
+            notifications,
+            archive,
+            archive_after,
+            batch_size: config.archive_batch_size.max(1),
+            metrics,
+            logger,
+        })
+    }
+
+    /// Archive every notification past the retention, returning how many were moved.
+    ///
+    /// Each batch becomes one archive file. Shutdown is honoured between
+    /// batches; what is left is archived on the next run.
+    pub async fn run_once(&self, shutdown: &CancellationToken) -> AppResult<u64> {
+        let retention = chrono::Duration::from_std(self.archive_after)
+            .map_err(|e| AppError::Config(format!("Invalid notification archive age: {}", e)))?;
+        let cutoff = Utc::now() - retention;
+        let mut archived = 0;
+        while !shutdown.is_cancelled() {
+            let batch = self.notifications.settled_before(cutoff, self.batch_size).await?;
+            archived += self.archive.archive(&batch).await?;
+            if (batch.len() as i64) < self.batch_size {
+                break;
+            }
+        }
+
+        if archived > 0 {
+            self.metrics
+                .add_to_counter("notifications.archived", archived)
+                .await?;
+            self.logger
+                .info(&format!("Archived {} notifications created before {}", archived, cutoff));
+        }
+        Ok(archived)
+    }
+
+    pub fn spawn(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.run_once(&shutdown).await {
+                    self.logger.error(&format!("Notification archival failed: {}", e));
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,132 @@
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::{AppResult, JobQueue};
+use crate::repositories::OutboxRepository;
+use crate::services::{EventPublisher, JobQueues};
+use crate::utils::{Logger, Metrics};
+
+/// Relays committed outbox rows to the configured publisher.
+///
+/// Rows are locked with `SKIP LOCKED`, published in id order and marked
+/// sent in the same transaction. A crash between publishing and commit
+/// republishes the row with its original idempotency key, which the
+/// producer and consumers use to discard the duplicate.
+pub struct OutboxRelay {
+    repository: Arc<OutboxRepository>,
+    publisher: Arc<dyn EventPublisher>,
+    /// Whether operators have paused publishing
+    queues: Arc<JobQueues>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+    batch_size: i64,
+}
+
+impl OutboxRelay {
+    pub fn new(
+        repository: Arc<OutboxRepository>,
+        publisher: Arc<dyn EventPublisher>,
+        queues: Arc<JobQueues>,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+        batch_size: i64,
+    ) -> Self {
+        Self {
+            repository,
+            publisher,
+            queues,
+            metrics,
+            logger,
+            batch_size,
+        }
+    }
+
+    /// Publish one batch, returning how many messages were marked sent.
+    ///
+    /// Stops at the first failure so later events for the same aggregate
+    /// are never delivered ahead of earlier ones.
+    pub async fn run_once(&self) -> AppResult<usize> {
+        let mut tx = self.repository.begin().await?;
+        let messages = self.repository.claim_batch(&mut tx, self.batch_size).await?;
+        let mut published = 0;
+
+        for message in &messages {
+            match self.publisher.publish(message).await {
+                Ok(()) => {
+                    self.repository.mark_published(&mut tx, message.id).await?;
+                    published += 1;
+                }
+                Err(e) => {
+                    self.logger.warn(&format!(
+                        "Failed to publish outbox message {} (attempt {}): {}",
+                        message.id,
+                        message.attempts + 1,
+                        e
+                    ));
+                    self.repository
+                        .record_failure(&mut tx, message.id, &e.to_string())
+                        .await?;
+                    self.metrics.increment_counter("outbox.publish_failures").await?;
+                    break;
+                }
+            }
+        }
+
+        tx.commit().await?;
+        self.metrics
+            .add_to_counter("outbox.published", published as u64)
+            .await?;
+        Ok(published)
+    }
+
+    /// Publish backlog size and age of the oldest pending message as gauges
+    pub async fn report_lag(&self) -> AppResult<()> {
+        let lag = self.repository.lag().await?;
+        let lag_seconds = lag
+            .oldest_pending_at
+            .map(|oldest| (Utc::now() - oldest).num_milliseconds().max(0) as f64 / 1000.0)
+            .unwrap_or(0.0);
+
+        self.metrics.set_gauge("outbox.pending", lag.pending as f64).await?;
+        self.metrics.set_gauge("outbox.lag_seconds", lag_seconds).await?;
+        Ok(())
+    }
+
+    /// Poll the outbox until shutdown, draining full batches immediately.
+    ///
+    /// A batch in progress is finished before stopping so its transaction
+    /// commits what was already published. Nothing is published while the
+    /// queue is paused, but the lag is still reported.
+    pub fn spawn(self: Arc<Self>, poll_interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while !shutdown.is_cancelled() {
+                self.queues.record_run(JobQueue::Outbox, poll_interval).await;
+                let drained = if self.queues.is_paused(JobQueue::Outbox).await {
+                    true
+                } else {
+                    match self.run_once().await {
+                        Ok(published) => published < self.batch_size as usize,
+                        Err(e) => {
+                            self.logger.error(&format!("Outbox relay failed: {}", e));
+                            true
+                        }
+                    }
+                };
+
+                if let Err(e) = self.report_lag().await {
+                    self.logger.warn(&format!("Failed to report outbox lag: {}", e));
+                }
+
+                if drained {
+                    tokio::select! {
+                        _ = shutdown.cancelled() => break,
+                        _ = tokio::time::sleep(poll_interval) => {}
+                    }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,75 @@
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::{AppError, AppResult};
+use crate::repositories::OutboxRepository;
+use crate::utils::{Logger, Metrics};
+
+const RETENTION_BATCH: i64 = 1000;
+
+/// Background job deleting outbox rows published longer ago than the retention
+pub struct OutboxRetentionJob {
+    repository: Arc<OutboxRepository>,
+    retention: Duration,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl OutboxRetentionJob {
+    pub fn new(
+        repository: Arc<OutboxRepository>,
+        retention: Duration,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        Self {
+            repository,
+            retention,
+            metrics,
+            logger,
+        }
+    }
+
+    /// Delete every published row past the retention, returning how many went.
+    ///
+    /// Unpublished rows are kept however old they are. Shutdown is honoured
+    /// between batches; what is left is deleted on the next run.
+    pub async fn run_once(&self, shutdown: &CancellationToken) -> AppResult<u64> {
+        let retention = chrono::Duration::from_std(self.retention)
+            .map_err(|e| AppError::Config(format!("Invalid outbox retention: {}", e)))?;
+        let cutoff = Utc::now() - retention;
+        let mut deleted = 0;
+        while !shutdown.is_cancelled() {
+            let batch = self.repository.delete_published_before(cutoff, RETENTION_BATCH).await?;
+            deleted += batch;
+            if (batch as i64) < RETENTION_BATCH {
+                break;
+            }
+        }
+
+        if deleted > 0 {
+            self.metrics.add_to_counter("outbox.deleted", deleted).await?;
+            self.logger
+                .info(&format!("Deleted {} outbox messages published before {}", deleted, cutoff));
+        }
+        Ok(deleted)
+    }
+
+    pub fn spawn(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.run_once(&shutdown).await {
+                    self.logger.error(&format!("Outbox retention failed: {}", e));
+                }
+            }
+        })
+    }
+}
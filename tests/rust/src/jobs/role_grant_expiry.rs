@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::AppResult;
+use crate::services::UserService;
+use crate::utils::{Logger, Metrics};
+
+const EXPIRY_BATCH: i64 = 100;
+
+/// Background job closing temporary roles once they run out.
+///
+/// Expired grants already stop counting towards the effective role; closing
+/// them is what puts the reversion on the audit record.
+pub struct RoleGrantExpiryJob {
+    user_service: Arc<UserService>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl RoleGrantExpiryJob {
+    pub fn new(user_service: Arc<UserService>, metrics: Arc<Metrics>, logger: Arc<Logger>) -> Self {
+        Self {
+            user_service,
+            metrics,
+            logger,
+        }
+    }
+
+    /// Close every expired grant, returning how many were closed
+    pub async fn run_once(&self, shutdown: &CancellationToken) -> AppResult<usize> {
+        let mut ended = 0;
+        while !shutdown.is_cancelled() {
+            let batch = self.user_service.end_expired_role_grants(EXPIRY_BATCH).await?;
+            ended += batch;
+            if (batch as i64) < EXPIRY_BATCH {
+                break;
+            }
+        }
+
+        if ended > 0 {
+            self.metrics
+                .add_to_counter("role_grants.expired", ended as u64)
+                .await?;
+            self.logger
+                .info(&format!("Reverted {} temporary roles after they expired", ended));
+        }
+        Ok(ended)
+    }
+
+    pub fn spawn(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.run_once(&shutdown).await {
+                    self.logger.error(&format!("Role grant expiry failed: {}", e));
+                }
+            }
+        })
+    }
+}
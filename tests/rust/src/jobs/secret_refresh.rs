@@ -0,0 +1,90 @@
+use sqlx::postgres::PgConnectOptions;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::AuthConfig;
+use crate::database::Database;
+use crate::middleware::AuthMiddleware;
+use crate::models::{AppError, AppResult};
+use crate::services::SecretStore;
+use crate::utils::{Logger, Metrics};
+
+/// Background job fetching secrets again and applying rotated ones.
+///
+/// New JWT signing keys take effect at once, and a new database URL is
+/// used for connections opened from then on, so rotated database
+/// credentials replace the old ones as the pool recycles connections.
+/// Other secrets are only read at startup and need a restart.
+pub struct SecretRefreshJob {
+    secrets: Arc<SecretStore>,
+    auth: Option<Arc<AuthMiddleware>>,
+    database: Arc<Database>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl SecretRefreshJob {
+    pub fn new(
+        secrets: Arc<SecretStore>,
+        auth: Option<Arc<AuthMiddleware>>,
+        database: Arc<Database>,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        Self {
+            secrets,
+            auth,
+            database,
+            metrics,
+            logger,
+        }
+    }
+
+    /// Refresh every secret, returning the variables whose value changed
+    pub async fn run_once(&self) -> AppResult<Vec<String>> {
+        let changed = self.secrets.refresh().await?;
+        for var in &changed {
+            match var.as_str() {
+                "JWT_SIGNING_KEYS" => match &self.auth {
+                    Some(auth) => auth.rotate_keys(&AuthConfig::from_env()?)?,
+                    None => self.logger.warn("JWT signing keys appeared while running; restart to enable sign-in"),
+                },
+                "DATABASE_URL" => {
+                    let url = self.secrets.get(var).unwrap_or_default();
+                    let options: PgConnectOptions = url
+                        .parse()
+                        .map_err(|e| AppError::Config(format!("Rotated DATABASE_URL is invalid: {}", e)))?;
+                    self.database.pool().set_connect_options(options);
+                }
+                _ => self
+                    .logger
+                    .warn(&format!("Secret {} changed; it takes effect after a restart", var)),
+            }
+            self.metrics
+                .increment_labeled_counter("secrets.rotated", &[("var", var.as_str())])
+                .await?;
+            self.logger.info(&format!("Applied rotated secret {}", var));
+        }
+        Ok(changed)
+    }
+
+    pub fn spawn(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes at once, and secrets were just fetched
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.run_once().await {
+                    self.logger.error(&format!("Secret refresh failed: {}", e));
+                    let _ = self.metrics.increment_counter("secrets.refresh_failed").await;
+                }
+            }
+        })
+    }
+}
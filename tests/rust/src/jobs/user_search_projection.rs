@@ -0,0 +1,181 @@
+use futures::TryStreamExt;
+use sqlx::Row;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::database::Database;
+use crate::models::{AppResult, UserEvent};
+use crate::repositories::{UserSearchEntry, UserSearchRepository};
+use crate::utils::{EncryptedField, KeyRing, Logger, Metrics};
+
+/// Rows projected between persisted rebuild checkpoints
+const CHECKPOINT_EVERY: u64 = 500;
+
+/// Worker keeping the `user_search` read model in sync with user events
+pub struct UserSearchProjection {
+    repository: Arc<UserSearchRepository>,
+    database: Arc<Database>,
+    key_ring: Arc<KeyRing>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl UserSearchProjection {
+    pub fn new(
+        repository: Arc<UserSearchRepository>,
+        database: Arc<Database>,
+        key_ring: Arc<KeyRing>,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        Self {
+            repository,
+            database,
+            key_ring,
+            metrics,
+            logger,
+        }
+    }
+
+    /// Apply a single event to the read model
+    pub async fn apply(&self, event: &UserEvent) -> AppResult<()> {
+        match event {
+            UserEvent::Created { user } | UserEvent::Updated { user } => {
+                if user.deleted_at.is_some() {
+                    self.repository.remove(user.id).await
+                } else {
+                    self.repository.upsert(&UserSearchEntry::from(user)).await
+                }
+            }
+            UserEvent::LoggedIn { user_id, at } => self.repository.record_login(*user_id, *at).await,
+            UserEvent::Deleted { user_id, .. } => self.repository.remove(*user_id).await,
+        }
+    }
+
+    /// Rebuild the read model from the transactional users table.
+    ///
+    /// Users are projected in id order with a checkpoint persisted every
+    /// few hundred rows, so a rebuild cut short by shutdown resumes where it
+    /// stopped on the next start instead of leaving the projection partial.
+    pub async fn rebuild(&self, shutdown: &CancellationToken) -> AppResult<u64> {
+        let mut resume_after = self
+            .repository
+            .rebuild_checkpoint()
+            .await?
+            .and_then(|checkpoint| checkpoint.resume_after);
+        self.repository.save_rebuild_checkpoint(resume_after).await?;
+
+        let mut rows = sqlx::query(
+            "SELECT id, email, username, first_name, last_name, role, status, email_verified, \
+             last_login, login_count, created_at FROM users \
+             WHERE deleted_at IS NULL AND region IS NULL AND ($1::uuid IS NULL OR id > $1) ORDER BY id",
+        )
+        .bind(resume_after)
+        .fetch(self.database.pool());
+
+        let mut projected = 0;
+        while let Some(row) = rows.try_next().await? {
+            if shutdown.is_cancelled() {
+                self.repository.save_rebuild_checkpoint(resume_after).await?;
+                self.logger.warn(&format!(
+                    "User search projection rebuild interrupted after {} users; it resumes on next start",
+                    projected
+                ));
+                return Ok(projected);
+            }
+            let first_name = self.decrypt(&row.try_get::<String, _>("first_name")?)?;
+            let last_name = self.decrypt(&row.try_get::<String, _>("last_name")?)?;
+
+            let entry = UserSearchEntry {
+                user_id: row.try_get("id")?,
+                email: row.try_get("email")?,
+                username: row.try_get("username")?,
+                full_name: format!("{} {}", first_name, last_name).trim().to_string(),
+                role: row.try_get("role")?,
+                status: row.try_get("status")?,
+                email_verified: row.try_get("email_verified")?,
+                last_login: row.try_get("last_login")?,
+                login_count: row.try_get("login_count")?,
+                created_at: row.try_get("created_at")?,
+            };
+            self.repository.upsert(&entry).await?;
+            resume_after = Some(entry.user_id);
+            projected += 1;
+            if projected % CHECKPOINT_EVERY == 0 {
+                self.repository.save_rebuild_checkpoint(resume_after).await?;
+            }
+        }
+        self.repository.finish_rebuild().await?;
+
+        self.logger
+            .info(&format!("Rebuilt user search projection with {} users", projected));
+        Ok(projected)
+    }
+
+    /// Consume events until the bus is closed, rebuilding when events were missed
+    pub fn spawn(
+        self: Arc<Self>,
+        mut events: broadcast::Receiver<UserEvent>,
+        shutdown: CancellationToken,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let needs_rebuild = match self.repository.rebuild_checkpoint().await {
+                Ok(Some(_)) => Ok(true),
+                Ok(None) => self.repository.is_empty().await,
+                Err(e) => Err(e),
+            };
+            match needs_rebuild {
+                Ok(true) => self.rebuild_logged(&shutdown).await,
+                Ok(false) => {}
+                Err(e) => self
+                    .logger
+                    .warn(&format!("Could not inspect user search projection: {}", e)),
+            }
+
+            loop {
+                let received = tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    received = events.recv() => received,
+                };
+                match received {
+                    Ok(event) => {
+                        if let Err(e) = self.apply(&event).await {
+                            self.logger.error(&format!(
+                                "Failed to project event for user {}: {}",
+                                event.user_id(),
+                                e
+                            ));
+                            let _ = self.metrics.increment_counter("projection.user_search.errors").await;
+                        } else {
+                            let _ = self.metrics.increment_counter("projection.user_search.applied").await;
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        self.logger.warn(&format!(
+                            "User search projection missed {} events, rebuilding",
+                            missed
+                        ));
+                        self.rebuild_logged(&shutdown).await;
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    async fn rebuild_logged(&self, shutdown: &CancellationToken) {
+        if let Err(e) = self.rebuild(shutdown).await {
+            self.logger
+                .error(&format!("Failed to rebuild user search projection: {}", e));
+        }
+    }
+
+    fn decrypt(&self, stored: &str) -> AppResult<String> {
+        if stored.is_empty() {
+            return Ok(String::new());
+        }
+        self.key_ring.decrypt(&EncryptedField::parse(stored)?)
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::config::VerificationReminderPolicy;
+use crate::models::{AppResult, TenantContext, User, UserCursor};
+use crate::repositories::VerificationReminderRepository;
+use crate::services::UserService;
+use crate::utils::{Logger, Metrics};
+
+const CAMPAIGN_BATCH: i64 = 200;
+
+/// What one run of the campaign did
+#[derive(Debug, Default)]
+pub struct VerificationReminderReport {
+    pub reminded: usize,
+    pub suspended: usize,
+    /// Reminders claimed but not sent; they are not retried
+    pub failed: usize,
+}
+
+/// Scheduled campaign reminding users who have not verified their email.
+///
+/// Works through the segment of unverified active users: each is sent
+/// the reminder template for how long they have been unverified, so
+/// reminders grow more pressing, and is suspended once past the deadline.
+/// A user who was missed for a while gets the reminder they are due rather
+/// than every earlier one. Each reminder is claimed in Postgres before it
+/// is sent, so instances running the campaign at once never send it twice.
+pub struct VerificationReminderJob {
+    user_service: Arc<UserService>,
+    reminders: Arc<VerificationReminderRepository>,
+    policy: VerificationReminderPolicy,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl VerificationReminderJob {
+    /// None when the policy neither reminds nor suspends anyone
+    pub fn from_config(
+        user_service: Arc<UserService>,
+        reminders: Arc<VerificationReminderRepository>,
+        policy: &VerificationReminderPolicy,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> Option<Self> {
+        policy.is_enabled().then(|| Self {
+            user_service,
+            reminders,
+            policy: policy.clone(),
+            metrics,
+            logger,
+        })
+    }
+
+    /// Remind or suspend every user in the segment who is due.
+    ///
+    /// Shutdown is honoured between pages; users not reached yet are still
+    /// due on the next run.
+    pub async fn run_once(&self, shutdown: &CancellationToken) -> AppResult<VerificationReminderReport> {
+        let mut report = VerificationReminderReport::default();
+        let Some(cutoff) = self.cutoff() else {
+            return Ok(report);
+        };
+
+        let mut cursor: Option<UserCursor> = None;
+        while !shutdown.is_cancelled() {
+            let page = self.user_service.unverified_users(cutoff, cursor.as_ref(), CAMPAIGN_BATCH).await?;
+            let ids: Vec<Uuid> = page.users.iter().map(|user| user.id).collect();
+            let sent = self.reminders.sent(&ids).await?;
+            for user in &page.users {
+                let unverified_for = (Utc::now() - user.created_at).to_std().unwrap_or_default();
+                if self.policy.suspends(unverified_for) {
+                    if self.user_service.suspend_unverified(user.id).await?.is_some() {
+                        report.suspended += 1;
+                    }
+                    continue;
+                }
+                let due = self.policy.reminders_due(unverified_for);
+                if due > sent.get(&user.id).copied().unwrap_or(0) {
+                    self.remind(user, due, &mut report).await?;
+                }
+            }
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        if report.reminded > 0 || report.suspended > 0 {
+            self.metrics
+                .add_to_counter("verification_reminders.sent", report.reminded as u64)
+                .await?;
+            self.metrics
+                .add_to_counter("verification_reminders.suspended", report.suspended as u64)
+                .await?;
+            self.logger.info(&format!(
+                "Sent {} email verification reminders and suspended {} unverified accounts",
+                report.reminded, report.suspended
+            ));
+        }
+        Ok(report)
+    }
+
+    pub fn spawn(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.policy.run_interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.run_once(&shutdown).await {
+                    self.logger.error(&format!("Verification reminder campaign failed: {}", e));
+                }
+            }
+        })
+    }
+
+    /// Send the `due`th reminder unless another instance already has
+    async fn remind(&self, user: &User, due: usize, report: &mut VerificationReminderReport) -> AppResult<()> {
+        if !self.reminders.claim(user.id, due).await? {
+            return Ok(());
+        }
+        let template = &self.policy.templates[due - 1];
+        let sent = self
+            .user_service
+            .send_verification_reminder(&TenantContext::default(), user, template, self.suspends_at(user))
+            .await;
+        match sent {
+            Ok(()) => report.reminded += 1,
+            Err(e) => {
+                report.failed += 1;
+                self.logger
+                    .warn(&format!("Failed to send verification reminder {} to {}: {}", due, user.id, e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Users who signed up after this are not due anything yet; None when the campaign is off
+    fn cutoff(&self) -> Option<DateTime<Utc>> {
+        let reminding = (!self.policy.templates.is_empty()).then_some(self.policy.first_after);
+        let earliest = [reminding, Some(self.policy.suspend_after)]
+            .into_iter()
+            .flatten()
+            .filter(|after| !after.is_zero())
+            .min()?;
+        Some(Utc::now() - chrono::Duration::from_std(earliest).ok()?)
+    }
+
+    fn suspends_at(&self, user: &User) -> Option<DateTime<Utc>> {
+        if self.policy.suspend_after.is_zero() {
+            return None;
+        }
+        Some(user.created_at + chrono::Duration::from_std(self.policy.suspend_after).ok()?)
+    }
+}
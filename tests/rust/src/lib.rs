@@ -1,10 +1,14 @@
 // Main library file exposing all modules
 
+pub mod api;
+pub mod cli;
 pub mod config;
 pub mod database;
+pub mod jobs;
 pub mod middleware;
 pub mod models;
 pub mod repositories;
+pub mod saga;
 pub mod services;
 pub mod utils;
 
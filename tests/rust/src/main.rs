@@ -1,16 +1,60 @@
 use anyhow::Result;
+use axum::Router;
+use clap::Parser;
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::signal;
-use tracing::{info, error};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, error, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
 
 use crawler_test_rust::{
-    config::AppConfig,
-    services::{UserService, NotificationService, CacheService},
+    api,
+    cli::{self, Cli, Command},
+    config::{AppConfig, OutboxPublisherKind, SecretsConfig},
+    services::{
+        UserService, NotificationService, CacheService, EventBus, SearchService, SearchIndexer, NoopSearchIndexer,
+        EventPublisher, EventBusPublisher, FanOutPublisher, KafkaPublisher, QuotaService,
+        EmailChannel, EmailTracker, ReportService, NotificationDispatcher, CacheEntity, CachePolicies,
+        PresenceService, AnnouncementService, OAuthService, SessionService, ApiKeyService, AuditService,
+        TokenRevocations, DeliveryWebhooks, ProviderCallbacks, NoopProviderCallbacks, PolicyEngine, Passkeys,
+        StorageService, ExportService, StatusService, SecretStore, TenantLimitService, ReadOnlyMode, LoginAnalytics,
+        MaintenanceMode, HealthRegistry, HttpProbe,
+        ServiceAccountService, NotificationBudget, TrustedDevices, JobQueues, SamlService, OrganizationService,
+    },
     database::Database,
-    models::{User, UserRole, CreateUserRequest},
-    utils::{Logger, Metrics},
-    middleware::AuthMiddleware,
-    repositories::{UserRepository, PostgresUserRepository},
+    models::{AppError, User, UserRole, CreateUserRequest, TenantContext},
+    utils::{
+        AppInfo, IdGenerator, Logger, Metrics, KeyRing, LatencyBudgetLayer, SampledSpans, ShutdownReport, UrlSigner,
+    },
+    middleware::{
+        AuthMiddleware, CsrfProtection, IdempotencyMiddleware, IpAccessMiddleware, MiddlewareStack, RateLimitMiddleware,
+        RequestCapture, MaintenanceGate,
+    },
+    jobs::{
+        KeyRotationJob, UserSearchProjection, OutboxRelay, AccountErasureJob, LockoutExpiryJob, CacheConsistencyJob,
+        ExportRunner, SecretRefreshJob, RoleGrantExpiryJob, NotificationArchivalJob, VerificationReminderJob,
+        DeletedUserPurgeJob, OutboxRetentionJob,
+    },
+    repositories::{
+        UserRepository, PostgresUserRepository, UserSearchRepository, OutboxRepository, AuditRepository,
+        GroupRepository, PostgresGroupRepository, NotificationRepository, PostgresNotificationRepository,
+        AccountDeletionRepository, EmailVerificationRepository, SessionRepository, PostgresSessionRepository,
+        BulkOperationRepository, BroadcastRepository, PasswordHistoryRepository, PasswordResetRepository,
+        PostgresSecondFactorRepository, TemplateRepository, PostgresTemplateRepository, TenantBrandingRepository,
+        PostgresTenantBrandingRepository, CachingUserRepository, CachingTemplateRepository,
+        PostgresAnnouncementRepository, PostgresOAuthIdentityRepository, ApiKeyRepository, SuppressionRepository,
+        DatabaseRouter, RegionalUserRepository, AuditLogRepository, RoleRequestRepository,
+        ProviderCallbackRepository, PolicyRepository, PasskeyRepository, ExportJobRepository,
+        HealthCheckRepository, TenantUserRepository, RoleGrantRepository, ServiceAccountRepository,
+        LoginHistoryRepository, TrustedDeviceRepository, ArchivingNotificationRepository,
+        NotificationArchiveRepository, OrganizationRepository, VerificationReminderRepository,
+    },
+    saga::{SagaCoordinator, UserOnboardingSaga},
 };
 
 /// Application state containing all services and dependencies
@@ -22,20 +66,89 @@ pub struct AppState {
     pub database: Arc<dyn Database>,
     pub logger: Arc<Logger>,
     pub metrics: Arc<Metrics>,
+    pub key_ring: Arc<KeyRing>,
+    pub event_bus: Arc<EventBus>,
+    pub user_search: Arc<UserSearchRepository>,
+    pub search_service: Arc<SearchService>,
+    /// Does nothing when search indexing is switched off
+    pub search_indexer: Arc<dyn SearchIndexer>,
+    pub sagas: Arc<SagaCoordinator>,
+    pub outbox: Arc<OutboxRepository>,
+    /// Outboxes of the other regions' databases, relayed like the home one
+    pub regional_outboxes: Vec<Arc<OutboxRepository>>,
+    /// Databases of the other regions, whose PII is re-encrypted like the home one's
+    pub database_router: Arc<DatabaseRouter>,
+    pub quota_service: Arc<QuotaService>,
+    pub tenant_limits: Arc<TenantLimitService>,
+    pub read_only: Arc<ReadOnlyMode>,
+    pub maintenance: Arc<MaintenanceMode>,
+    pub login_analytics: Arc<LoginAnalytics>,
+    pub email_channel: Arc<EmailChannel>,
+    /// Present when email open and click tracking is configured
+    pub email_tracker: Option<Arc<EmailTracker>>,
+    /// Present when a URL signing secret is configured
+    pub url_signer: Option<Arc<UrlSigner>>,
+    /// Present when JWT signing keys are configured
+    pub auth: Option<Arc<AuthMiddleware>>,
+    /// Present when client address allow or deny lists are configured
+    pub ip_access: Option<Arc<IpAccessMiddleware>>,
+    /// Present when CSRF checks are enabled
+    pub csrf: Option<Arc<CsrfProtection>>,
+    pub api_keys: Arc<ApiKeyService>,
+    pub service_accounts: Arc<ServiceAccountService>,
+    pub organization_service: Arc<OrganizationService>,
+    /// Present when an external sign-in provider is configured
+    pub oauth: Option<Arc<OAuthService>>,
+    /// Present when a SAML identity provider is configured
+    pub saml: Option<Arc<SamlService>>,
+    pub notification_dispatcher: Arc<NotificationDispatcher>,
+    pub notification_budget: Arc<NotificationBudget>,
+    pub report_service: Arc<ReportService>,
+    pub storage: Arc<StorageService>,
+    /// Present when a URL signing secret is configured, since download links are signed
+    pub exports: Option<Arc<ExportService>>,
+    /// Operator view of the export queue and the outbox
+    pub job_queues: Arc<JobQueues>,
+    /// Present unless notification archival is switched off
+    pub notification_archival: Option<Arc<NotificationArchivalJob>>,
+    /// Present unless the verification reminder campaign neither reminds nor suspends
+    pub verification_reminders: Option<Arc<VerificationReminderJob>>,
+    pub status: Arc<StatusService>,
+    /// Present when configuration variables refer to secrets in a secret backend
+    pub secrets: Option<Arc<SecretStore>>,
+    pub presence: Arc<PresenceService>,
+    pub announcements: Arc<AnnouncementService>,
+    pub audit_log: Arc<AuditService>,
+    /// Users as stored, without the cache in front; what cached entries are checked against
+    pub stored_users: Arc<dyn UserRepository>,
+    pub policies: Arc<PolicyEngine>,
+    pub passkeys: Arc<Passkeys>,
+    /// Present when a trusted device secret is configured
+    pub trusted_devices: Option<Arc<TrustedDevices>>,
+    /// Drops callbacks when delivery webhooks are switched off
+    pub delivery_webhooks: Arc<dyn ProviderCallbacks>,
+    /// Cancelled on shutdown; long-running work stops at its next checkpoint
+    pub shutdown: CancellationToken,
 }
 
 /// Main application struct
 pub struct Application {
     state: AppState,
     config: AppConfig,
+    info: Arc<AppInfo>,
 }
 
 impl Application {
-    /// Create a new application instance
-    pub async fn new() -> Result<Self> {
+    /// Create a new application instance; `secrets` must already be loaded
+    /// so the configuration sees the secrets its variables refer to
+    pub async fn new(secrets: Option<Arc<SecretStore>>) -> Result<Self> {
         let config = AppConfig::from_env()?;
         let logger = Arc::new(Logger::new(&config.log_level)?);
-        
+        // Before anything creates an entity, so every new id follows the configured strategy
+        IdGenerator::new(&config.ids).install();
+
+        let app_info = Arc::new(AppInfo::new(config.profile.clone(), config.enabled_features()));
+        info!("{}", app_info.banner());
         info!("Initializing application with config: {:?}", config);
 
         // Initialize database connection
@@ -46,27 +159,381 @@ impl Application {
         // Run database migrations
         database.migrate().await?;
 
+        // Connect the databases of the other regions accounts can be stored in
+        let database_router = Arc::new(DatabaseRouter::connect(database.clone(), &config.residency).await?);
+        for (_, regional) in database_router.regions() {
+            regional.migrate().await?;
+        }
+
         // Initialize cache service
         let cache_service = Arc::new(
             CacheService::new(&config.redis_url).await?
         );
 
         // Initialize metrics
-        let metrics = Arc::new(Metrics::new()?);
+        let metrics = Arc::new(Metrics::new(config.metrics_max_label_sets, logger.clone())?);
 
-        // Initialize repository layer
-        let user_repo: Arc<dyn UserRepository> = Arc::new(
-            PostgresUserRepository::new(database.clone())
+        // Load field-level encryption keys
+        let key_ring = Arc::new(KeyRing::from_config(&config.encryption)?);
+
+        // Domain events feeding read models
+        let event_bus = Arc::new(EventBus::new(1024));
+
+        let shutdown = CancellationToken::new();
+
+        // Writes are refused while read-only, for database maintenance
+        let read_only = Arc::new(
+            ReadOnlyMode::load(cache_service.clone(), &config.maintenance, metrics.clone(), logger.clone()).await?
         );
+        // All but admins are turned away in maintenance mode
+        let maintenance = Arc::new(
+            MaintenanceMode::load(cache_service.clone(), &config.maintenance, metrics.clone(), logger.clone()).await?
+        );
+
+        // Initialize repository layer
+        let cache_policies = CachePolicies::from_config(&config.cache);
+        let outbox = Arc::new(OutboxRepository::new(database.clone()));
+        let mut regional_outboxes = Vec::new();
+        let mut regional_users: HashMap<String, Arc<dyn UserRepository>> = HashMap::new();
+        for (region, regional) in database_router.regions() {
+            let regional_outbox = Arc::new(OutboxRepository::new(regional.clone()));
+            regional_users.insert(
+                region.to_string(),
+                Arc::new(PostgresUserRepository::new(regional.clone(), key_ring.clone(), regional_outbox.clone())),
+            );
+            regional_outboxes.push(regional_outbox);
+        }
+        let stored_users: Arc<dyn UserRepository> = Arc::new(RegionalUserRepository::new(
+            Arc::new(PostgresUserRepository::new(database.clone(), key_ring.clone(), outbox.clone())),
+            database_router.clone(),
+            regional_users,
+        ));
+        let user_repo: Arc<dyn UserRepository> = Arc::new(CachingUserRepository::new(
+            stored_users.clone(),
+            cache_service.clone(),
+            cache_policies.get(CacheEntity::User),
+        ));
+        let group_repo: Arc<dyn GroupRepository> = Arc::new(PostgresGroupRepository::new(database.clone()));
+        let storage = Arc::new(StorageService::new(&config.storage));
+        let notification_archive = Arc::new(NotificationArchiveRepository::new(database.clone(), storage.clone()));
+        let notification_repo: Arc<dyn NotificationRepository> = Arc::new(ArchivingNotificationRepository::new(
+            Arc::new(PostgresNotificationRepository::new(database.clone())),
+            notification_archive.clone(),
+        ));
+        let notification_archival = NotificationArchivalJob::from_config(
+            notification_repo.clone(),
+            notification_archive,
+            &config.notification_config,
+            metrics.clone(),
+            logger.clone(),
+        )
+        .map(Arc::new);
+        let template_repo: Arc<dyn TemplateRepository> = Arc::new(CachingTemplateRepository::new(
+            Arc::new(PostgresTemplateRepository::new(database.clone())),
+            cache_service.clone(),
+            cache_policies.get(CacheEntity::Template),
+        ));
+        let branding_repo: Arc<dyn TenantBrandingRepository> =
+            Arc::new(PostgresTenantBrandingRepository::new(database.clone()));
+        let user_search = Arc::new(UserSearchRepository::new(database.clone()));
+        let account_deletions = Arc::new(AccountDeletionRepository::new(database.clone()));
+        let session_repo: Arc<dyn SessionRepository> = Arc::new(PostgresSessionRepository::new(database.clone()));
 
         // Initialize services
-        let user_service = Arc::new(
-            UserService::new(user_repo, cache_service.clone(), logger.clone()).await?
+        let email_tracker = EmailTracker::from_config(&config.notification_config)?.map(Arc::new);
+        let url_signer = config
+            .links
+            .signing_secret
+            .as_ref()
+            .map(|secret| Arc::new(UrlSigner::new(&config.links.public_base_url, secret)));
+        let api_keys = Arc::new(ApiKeyService::new(
+            Arc::new(ApiKeyRepository::new(database.clone())),
+            config.auth.api_key_rotation_grace,
+            read_only.clone(),
+            logger.clone(),
+        ));
+        let service_accounts = Arc::new(ServiceAccountService::new(
+            Arc::new(ServiceAccountRepository::new(database.clone())),
+            read_only.clone(),
+            logger.clone(),
+        ));
+        let revocations = Arc::new(TokenRevocations::new(cache_service.clone()));
+        let policies = Arc::new(
+            PolicyEngine::load(
+                &config.authorization,
+                Some(Arc::new(PolicyRepository::new(database.clone()))),
+                logger.clone(),
+            ).await?
         );
+        let organizations = Arc::new(OrganizationRepository::new(database.clone()));
+        let live_sessions = Arc::new(SessionService::new(cache_service.clone()));
+        let auth = AuthMiddleware::from_config(&config.auth)?.map(|auth| {
+            Arc::new(
+                auth.with_api_keys(api_keys.clone())
+                    .with_revocations(revocations)
+                    .with_policies(policies.clone())
+                    .with_organizations(organizations.clone())
+                    .with_session_policies(config.accounts.sessions.clone())
+                    .with_sessions(live_sessions.clone()),
+            )
+        });
+        let ip_access = IpAccessMiddleware::load(&config.ip_access, metrics.clone(), logger.clone())
+            .await?
+            .map(Arc::new);
+        let csrf = CsrfProtection::from_config(cache_service.clone(), &config.middleware.csrf, metrics.clone())
+            .map(Arc::new);
+        let email_channel = Arc::new(EmailChannel::new(&config.notification_config, email_tracker.clone())?);
+
+        let broadcasts = Arc::new(BroadcastRepository::new(database.clone()));
+        let suppressions = Arc::new(SuppressionRepository::new(database.clone()));
+        let notification_budget = Arc::new(NotificationBudget::new(
+            &config.notification_config.budgets,
+            cache_service.clone(),
+            metrics.clone(),
+        ));
+        let notification_dispatcher = Arc::new(NotificationDispatcher::new(
+            &config.notification_config,
+            notification_repo.clone(),
+            broadcasts.clone(),
+            suppressions.clone(),
+            notification_budget.clone(),
+            user_repo.clone(),
+            email_channel.clone(),
+            metrics.clone(),
+            logger.clone(),
+        ));
+
+        let presence = Arc::new(PresenceService::new(cache_service.clone(), config.presence_ttl));
+        let announcements = Arc::new(AnnouncementService::new(
+            Arc::new(PostgresAnnouncementRepository::new(database.clone())),
+            group_repo.clone(),
+            read_only.clone(),
+        ));
+
+        let tenant_limits = Arc::new(TenantLimitService::new(
+            config.tenant_limits.clone(),
+            cache_service.clone(),
+            Arc::new(TenantUserRepository::new(database.clone())),
+            metrics.clone(),
+        ));
 
         let notification_service = Arc::new(
-            NotificationService::new(&config.notification_config, logger.clone()).await?
+            NotificationService::new(
+                &config.notification_config,
+                notification_repo.clone(),
+                user_repo.clone(),
+                group_repo.clone(),
+                template_repo,
+                branding_repo,
+                broadcasts,
+                suppressions,
+                notification_dispatcher.clone(),
+                email_channel.clone(),
+                presence.clone(),
+                tenant_limits.clone(),
+                read_only.clone(),
+                shutdown.clone(),
+                logger.clone(),
+            ).await?
         );
+        let delivery_webhooks: Arc<dyn ProviderCallbacks> = if config.subsystems.delivery_webhooks {
+            Arc::new(DeliveryWebhooks::new(
+                &config.notification_config,
+                Arc::new(ProviderCallbackRepository::new(database.clone())),
+                notification_service.clone(),
+                read_only.clone(),
+                logger.clone(),
+            ))
+        } else {
+            logger.info("Delivery webhooks are switched off; provider callbacks are dropped");
+            Arc::new(NoopProviderCallbacks)
+        };
+
+        let login_analytics = Arc::new(LoginAnalytics::new(cache_service.clone(), &config.accounts.lockout_policy));
+
+        let audit_log = Arc::new(AuditService::new(
+            Arc::new(AuditLogRepository::new(database.clone())),
+            metrics.clone(),
+            logger.clone(),
+        ));
+
+        let trusted_devices = TrustedDevices::from_config(
+            Arc::new(TrustedDeviceRepository::new(database.clone())),
+            &config.auth,
+        )
+        .map(Arc::new);
+
+        let user_service = Arc::new(
+            UserService::new(
+                user_repo,
+                group_repo,
+                account_deletions,
+                notification_repo,
+                notification_service.clone(),
+                session_repo,
+                live_sessions.clone(),
+                Arc::new(BulkOperationRepository::new(database.clone())),
+                Arc::new(PasswordHistoryRepository::new(database.clone())),
+                Arc::new(PasswordResetRepository::new(database.clone())),
+                Arc::new(EmailVerificationRepository::new(database.clone())),
+                Arc::new(AuditRepository::new(database.clone())),
+                audit_log.clone(),
+                Arc::new(RoleRequestRepository::new(database.clone())),
+                Arc::new(RoleGrantRepository::new(database.clone())),
+                organizations.clone(),
+                policies.clone(),
+                Arc::new(PostgresSecondFactorRepository::new(database.clone())),
+                key_ring.clone(),
+                cache_service.clone(),
+                read_only.clone(),
+                login_analytics.clone(),
+                Arc::new(LoginHistoryRepository::new(database.clone())),
+                trusted_devices.clone(),
+                metrics.clone(),
+                config.accounts.clone(),
+                shutdown.clone(),
+                logger.clone(),
+            ).await?
+        );
+        let organization_service = Arc::new(OrganizationService::new(
+            organizations.clone(),
+            Arc::new(TenantUserRepository::new(database.clone())),
+            user_service.clone(),
+            Arc::new(AuditRepository::new(database.clone())),
+            read_only.clone(),
+            logger.clone(),
+        ));
+        let verification_reminders = VerificationReminderJob::from_config(
+            user_service.clone(),
+            Arc::new(VerificationReminderRepository::new(database.clone())),
+            &config.accounts.verification_reminders,
+            metrics.clone(),
+            logger.clone(),
+        )
+        .map(Arc::new);
+
+        let passkeys = Arc::new(Passkeys::new(
+            Arc::new(PasskeyRepository::new(database.clone())),
+            cache_service.clone(),
+            &config.accounts,
+        )?);
+
+        let external_identities = Arc::new(PostgresOAuthIdentityRepository::new(database.clone()));
+        let oauth = OAuthService::new(
+            &config.oauth,
+            &config.links.public_base_url,
+            external_identities.clone(),
+            user_service.clone(),
+            cache_service.clone(),
+            logger.clone(),
+        )
+        .await?
+        .map(Arc::new);
+        if oauth.is_some() && auth.is_none() {
+            logger.warn("Sign-in providers are configured without JWT signing keys; provider sign-in is disabled");
+        }
+        let saml = SamlService::from_config(
+            &config.saml,
+            &config.links.public_base_url,
+            external_identities,
+            user_service.clone(),
+            cache_service.clone(),
+            logger.clone(),
+        )?
+        .map(Arc::new);
+        if saml.is_some() && auth.is_none() {
+            logger.warn("A SAML identity provider is configured without JWT signing keys; SAML sign-in is disabled");
+        }
+
+        let search_service = Arc::new(SearchService::new(
+            config.search.clone(),
+            user_search.clone(),
+            metrics.clone(),
+            logger.clone(),
+        )?);
+        let search_indexer: Arc<dyn SearchIndexer> = if config.subsystems.search_indexing {
+            search_service.clone()
+        } else {
+            logger.info("Search indexing is switched off; the search cluster is left to other instances");
+            Arc::new(NoopSearchIndexer)
+        };
+
+        let quota_service = Arc::new(QuotaService::new(
+            cache_service.clone(),
+            database.clone(),
+            config.quota.clone(),
+            metrics.clone(),
+            logger.clone(),
+        ));
+
+        let report_service = Arc::new(ReportService::new(
+            database.clone(),
+            email_channel.clone(),
+            cache_service.clone(),
+            config.reports.clone(),
+            metrics.clone(),
+            logger.clone(),
+        )?);
+
+        let export_jobs = Arc::new(ExportJobRepository::new(database.clone()));
+        let exports = url_signer.as_ref().map(|signer| {
+            Arc::new(ExportService::new(
+                export_jobs.clone(),
+                storage.clone(),
+                user_service.clone(),
+                report_service.clone(),
+                notification_service.clone(),
+                policies.clone(),
+                signer.clone(),
+                read_only.clone(),
+                config.exports.clone(),
+                metrics.clone(),
+                logger.clone(),
+            ))
+        });
+        if exports.is_none() {
+            logger.warn("No URL signing secret is configured; background exports are disabled");
+        }
+        let job_queues = Arc::new(JobQueues::new(
+            export_jobs,
+            outbox.clone(),
+            cache_service.clone(),
+            read_only.clone(),
+            &config.exports,
+            logger.clone(),
+        ));
+
+        // Components the status page probes, in the order it lists them
+        let mut health = HealthRegistry::new();
+        health.register("database", database.clone());
+        health.register("cache", cache_service.clone());
+        if config.notification_config.channel_probes {
+            health.register("email", email_channel.clone());
+            let mut urls: Vec<_> = config.notification_config.probe_urls.iter().collect();
+            urls.sort();
+            for (component, url) in urls {
+                health.register(component, Arc::new(HttpProbe::new(url, config.status.probe_timeout)?));
+            }
+        }
+        let status = Arc::new(StatusService::new(
+            Arc::new(HealthCheckRepository::new(database.clone())),
+            Arc::new(health),
+            config.status.clone(),
+            metrics.clone(),
+            logger.clone(),
+        ));
+
+        // Register compensatable multi-step workflows
+        let mut sagas = SagaCoordinator::new(database.clone(), metrics.clone(), logger.clone());
+        sagas.register(UserOnboardingSaga::definition(
+            user_service.clone(),
+            notification_service.clone(),
+            search_service.clone(),
+            config.residency.clone(),
+            tenant_limits.clone(),
+            organizations.clone(),
+        ));
+        let sagas = Arc::new(sagas);
 
         let state = AppState {
             user_service,
@@ -75,9 +542,58 @@ impl Application {
             database,
             logger,
             metrics,
+            key_ring,
+            event_bus,
+            user_search,
+            search_service,
+            search_indexer,
+            sagas,
+            outbox,
+            regional_outboxes,
+            database_router,
+            quota_service,
+            tenant_limits,
+            read_only,
+            maintenance,
+            login_analytics,
+            email_channel,
+            email_tracker,
+            url_signer,
+            auth,
+            ip_access,
+            csrf,
+            api_keys,
+            service_accounts,
+            organization_service,
+            oauth,
+            saml,
+            notification_dispatcher,
+            notification_budget,
+            report_service,
+            storage,
+            exports,
+            job_queues,
+            notification_archival,
+            verification_reminders,
+            status,
+            secrets,
+            presence,
+            announcements,
+            audit_log,
+            stored_users,
+            policies,
+            passkeys,
+            trusted_devices,
+            delivery_webhooks,
+            shutdown,
         };
 
-        Ok(Self { state, config })
+        Ok(Self { state, config, info: app_info })
+    }
+
+    /// Build and runtime details of this process
+    pub fn info(&self) -> &AppInfo {
+        &self.info
     }
 
     /// Initialize the application and all its components
@@ -92,6 +608,12 @@ impl Application {
         // Verify database connectivity
         self.state.database.ping().await?;
 
+        // Finish or compensate sagas interrupted by a previous shutdown
+        let resumed = self.state.sagas.resume_pending().await?;
+        if resumed > 0 {
+            info!("Resumed {} interrupted sagas", resumed);
+        }
+
         info!("Application initialization completed successfully");
         Ok(())
     }
@@ -100,6 +622,228 @@ impl Application {
     pub async fn run(&self) -> Result<()> {
         self.initialize().await?;
 
+        let shutdown = &self.state.shutdown;
+        let mut background_tasks = Vec::new();
+
+        // Deliver queued notifications on the worker pool
+        background_tasks.extend(
+            self.state.notification_dispatcher.clone().spawn_workers(shutdown.clone()).await?
+        );
+
+        // Continue bulk operations and broadcasts interrupted by a previous shutdown
+        let user_service = self.state.user_service.clone();
+        background_tasks.push(tokio::spawn(async move {
+            if let Err(e) = user_service.resume_bulk_operations().await {
+                error!("Failed to resume bulk operations: {}", e);
+            }
+        }));
+        let notification_service = self.state.notification_service.clone();
+        background_tasks.push(tokio::spawn(async move {
+            if let Err(e) = notification_service.resume_broadcasts().await {
+                error!("Failed to resume broadcasts: {}", e);
+            }
+        }));
+
+        // Large breached password lists take a while to load; passwords are checked once it is in
+        let user_service = self.state.user_service.clone();
+        background_tasks.push(tokio::spawn(async move {
+            if let Err(e) = user_service.load_breached_passwords().await {
+                error!("Failed to load breached passwords: {}", e);
+            }
+        }));
+
+        // Re-encrypt PII left on retired keys in the background, in every region's database
+        let databases = std::iter::once((None, &self.state.database)).chain(
+            self.state
+                .database_router
+                .regions()
+                .map(|(region, database)| (Some(region.to_string()), database)),
+        );
+        for (region, database) in databases {
+            let key_rotation_job = Arc::new(KeyRotationJob::new(
+                database.clone(),
+                region,
+                self.state.key_ring.clone(),
+                self.state.metrics.clone(),
+                self.state.logger.clone(),
+                self.config.encryption.rotation_batch_size,
+            ));
+            background_tasks.push(key_rotation_job.spawn(self.config.encryption.rotation_interval, shutdown.clone()));
+        }
+
+        // Keep the user search read model in sync with user events
+        let user_search_projection = Arc::new(UserSearchProjection::new(
+            self.state.user_search.clone(),
+            self.state.database.clone(),
+            self.state.key_ring.clone(),
+            self.state.metrics.clone(),
+            self.state.logger.clone(),
+        ));
+        background_tasks.push(user_search_projection.spawn(self.state.event_bus.subscribe(), shutdown.clone()));
+
+        // Mirror user mutations into the search cluster
+        background_tasks.push(
+            self.state.search_indexer.clone().spawn_indexer(self.state.event_bus.subscribe(), shutdown.clone())
+        );
+
+        // Relay committed outbox events to the configured broker. The outbox is
+        // the only source of user events, so in-process subscribers get them
+        // from the relay as well when they go to Kafka.
+        let event_bus: Arc<dyn EventPublisher> = Arc::new(EventBusPublisher::new(
+            self.state.event_bus.clone(),
+            self.state.stored_users.clone(),
+        ));
+        let publisher: Arc<dyn EventPublisher> = match self.config.outbox.publisher {
+            OutboxPublisherKind::Kafka => Arc::new(FanOutPublisher::new(vec![
+                Arc::new(KafkaPublisher::new(&self.config.outbox)?),
+                event_bus,
+            ])),
+            OutboxPublisherKind::EventBus => event_bus,
+        };
+        let outboxes = std::iter::once(&self.state.outbox).chain(&self.state.regional_outboxes);
+        for outbox in outboxes {
+            let outbox_relay = Arc::new(OutboxRelay::new(
+                outbox.clone(),
+                publisher.clone(),
+                self.state.job_queues.clone(),
+                self.state.metrics.clone(),
+                self.state.logger.clone(),
+                self.config.outbox.batch_size,
+            ));
+            background_tasks.push(outbox_relay.spawn(self.config.outbox.poll_interval, shutdown.clone()));
+            if let Some(retention) = self.config.outbox.retention {
+                let retention_job = Arc::new(OutboxRetentionJob::new(
+                    outbox.clone(),
+                    retention,
+                    self.state.metrics.clone(),
+                    self.state.logger.clone(),
+                ));
+                background_tasks.push(retention_job.spawn(self.config.outbox.retention_interval, shutdown.clone()));
+            }
+        }
+
+        // Pick up authorization rules changed in the policy file or database
+        background_tasks.push(self.state.policies.clone().spawn_reload(shutdown.clone()));
+
+        // Pick up read-only mode switched on or off through another instance
+        background_tasks.push(self.state.read_only.clone().spawn_sync(shutdown.clone()));
+        background_tasks.push(self.state.maintenance.clone().spawn_sync(shutdown.clone()));
+
+        // Pick up address ranges changed in the IP rules file
+        if let Some(reload) = self.state.ip_access.clone().and_then(|access| access.spawn_reload(shutdown.clone())) {
+            background_tasks.push(reload);
+        }
+
+        // Persist API usage counters for billing
+        background_tasks.push(self.state.quota_service.clone().spawn_flush(shutdown.clone()));
+
+        // Erase accounts whose deletion grace period has ended
+        let account_erasure_job = Arc::new(AccountErasureJob::new(
+            self.state.user_service.clone(),
+            self.state.metrics.clone(),
+            self.state.logger.clone(),
+        ));
+        background_tasks.push(account_erasure_job.spawn(self.config.accounts.erasure_interval, shutdown.clone()));
+
+        // Purge soft-deleted users once they can no longer be restored
+        if self.config.accounts.purge_deleted_after.is_some() {
+            let purge_job = Arc::new(DeletedUserPurgeJob::new(
+                self.state.user_service.clone(),
+                self.state.metrics.clone(),
+                self.state.logger.clone(),
+            ));
+            background_tasks.push(purge_job.spawn(self.config.accounts.purge_interval, shutdown.clone()));
+        }
+
+        // Clear lockouts that have expired
+        let lockout_expiry_job = Arc::new(LockoutExpiryJob::new(
+            self.state.user_service.clone(),
+            self.state.metrics.clone(),
+            self.state.logger.clone(),
+        ));
+        let sweep_interval = self.config.accounts.lockout_policy.sweep_interval;
+        background_tasks.push(lockout_expiry_job.spawn(sweep_interval, shutdown.clone()));
+
+        // Remind users who leave their email unverified, and suspend them past the deadline
+        if let Some(reminders) = &self.state.verification_reminders {
+            background_tasks.push(reminders.clone().spawn(shutdown.clone()));
+        }
+
+        // Move delivered notifications past their retention to cold storage
+        if let Some(archival) = &self.state.notification_archival {
+            let archive_interval = self.config.notification_config.archive_interval;
+            background_tasks.push(archival.clone().spawn(archive_interval, shutdown.clone()));
+        }
+
+        // Close temporary roles that have run out
+        let role_grant_expiry_job = Arc::new(RoleGrantExpiryJob::new(
+            self.state.user_service.clone(),
+            self.state.metrics.clone(),
+            self.state.logger.clone(),
+        ));
+        let sweep_interval = self.config.accounts.role_grant_sweep_interval;
+        background_tasks.push(role_grant_expiry_job.spawn(sweep_interval, shutdown.clone()));
+
+        // Catch cached users that drifted from the database
+        if !self.config.cache.verify_interval.is_zero() {
+            let cache_consistency_job = Arc::new(CacheConsistencyJob::new(
+                self.state.cache_service.clone(),
+                CachePolicies::from_config(&self.config.cache).get(CacheEntity::User),
+                self.state.stored_users.clone(),
+                &self.config.cache,
+                self.state.metrics.clone(),
+                self.state.logger.clone(),
+            ));
+            let verify_interval = self.config.cache.verify_interval;
+            background_tasks.push(cache_consistency_job.spawn(verify_interval, shutdown.clone()));
+        }
+
+        // Keep the alerting gauges derived from counters current
+        background_tasks.push(
+            self.state.metrics.clone().spawn_derived(self.config.metrics_derived_interval, shutdown.clone())
+        );
+
+        // Email periodic reports to admins
+        background_tasks.push(self.state.report_service.clone().spawn_scheduler(shutdown.clone()));
+
+        // Pick up rotated secrets from the secret backends
+        if let Some(secrets) = &self.state.secrets {
+            let secret_refresh_job = Arc::new(SecretRefreshJob::new(
+                secrets.clone(),
+                self.state.auth.clone(),
+                self.state.database.clone(),
+                self.state.metrics.clone(),
+                self.state.logger.clone(),
+            ));
+            background_tasks.push(secret_refresh_job.spawn(secrets.refresh_interval(), shutdown.clone()));
+        }
+
+        // Probe component health for the status page
+        background_tasks.push(self.state.status.clone().spawn_probes(shutdown.clone()));
+
+        // Run queued exports and delete their files once retention is up
+        if let Some(exports) = &self.state.exports {
+            let export_runner = Arc::new(ExportRunner::new(
+                exports.clone(),
+                self.state.job_queues.clone(),
+                self.state.logger.clone(),
+            ));
+            background_tasks.push(export_runner.spawn(self.config.exports.poll_interval, shutdown.clone()));
+        }
+
+        // Serve the HTTP API
+        let listener = tokio::net::TcpListener::bind(&self.config.http_addr).await?;
+        info!("Listening on {}", self.config.http_addr);
+        let router = self.router();
+        let stopped = shutdown.clone().cancelled_owned();
+        background_tasks.push(tokio::spawn(async move {
+            // Peer addresses back the per-address rate limits of clients not behind a proxy
+            let service = router.into_make_service_with_connect_info::<SocketAddr>();
+            if let Err(e) = axum::serve(listener, service).with_graceful_shutdown(stopped).await {
+                error!("HTTP server failed: {}", e);
+            }
+        }));
+
         info!("Starting main application workflow");
 
         // Example workflow demonstrating complex dependency relationships
@@ -115,9 +859,46 @@ impl Application {
             }
         }
 
-        // Wait for shutdown signal
+        // Wait for shutdown signal, then give background work time to reach a checkpoint
         self.wait_for_shutdown().await;
+        let mut report = ShutdownReport::start(self.state.metrics.in_flight());
+        shutdown.cancel();
+        let aborts: Vec<_> = background_tasks.iter().map(|task| task.abort_handle()).collect();
+        let drained = tokio::time::timeout(
+            self.config.shutdown_grace_period,
+            futures::future::join_all(background_tasks),
+        )
+        .await;
+        match drained {
+            Ok(results) => {
+                for result in results {
+                    match result {
+                        Ok(()) => report.jobs_checkpointed += 1,
+                        Err(e) => {
+                            report.jobs_aborted += 1;
+                            report.error(format!("Background task failed: {}", e));
+                        }
+                    }
+                }
+            }
+            Err(_) => {
+                warn!(
+                    "Background tasks still running after {:?}; aborting them",
+                    self.config.shutdown_grace_period
+                );
+                for abort in aborts {
+                    if abort.is_finished() {
+                        report.jobs_checkpointed += 1;
+                    } else {
+                        report.jobs_aborted += 1;
+                        abort.abort();
+                    }
+                }
+            }
+        }
+        report.in_flight_abandoned = self.state.metrics.in_flight();
 
+        self.shutdown(&mut report).await;
         Ok(())
     }
 
@@ -125,7 +906,6 @@ impl Application {
     async fn execute_sample_workflow(&self) -> Result<()> {
         let logger = &self.state.logger;
         let user_service = &self.state.user_service;
-        let notification_service = &self.state.notification_service;
 
         // Create sample users with different roles
         let admin_request = CreateUserRequest {
@@ -133,7 +913,9 @@ impl Application {
             username: "admin_user".to_string(),
             first_name: "Admin".to_string(),
             last_name: "User".to_string(),
+            password: None,
             role: UserRole::Admin,
+            region: None,
         };
 
         let regular_request = CreateUserRequest {
@@ -141,12 +923,14 @@ impl Application {
             username: "regular_user".to_string(),
             first_name: "Regular".to_string(),
             last_name: "User".to_string(),
+            password: None,
             role: UserRole::User,
+            region: None,
         };
 
-        // Create users
-        let admin_user = user_service.create_user(admin_request).await?;
-        let regular_user = user_service.create_user(regular_request).await?;
+        // Onboard users through the saga so failures are compensated
+        let admin_user = self.onboard_user(&admin_request).await?;
+        let regular_user = self.onboard_user(&regular_request).await?;
 
         logger.info(&format!("Created admin user: {}", admin_user.id));
         logger.info(&format!("Created regular user: {}", regular_user.id));
@@ -155,15 +939,6 @@ impl Application {
         let users = user_service.get_active_users().await?;
         logger.info(&format!("Found {} active users", users.len()));
 
-        // Send notifications using the notification service
-        for user in &users {
-            if user.role == UserRole::Admin {
-                notification_service
-                    .send_welcome_notification(user.id, &user.email)
-                    .await?;
-            }
-        }
-
         // Demonstrate caching
         let cached_user = user_service.get_user_by_id(admin_user.id).await?;
         match cached_user {
@@ -182,6 +957,147 @@ impl Application {
         Ok(())
     }
 
+    /// Assemble the HTTP routes and middleware stack
+    fn router(&self) -> Router {
+        let mut router = Router::new()
+            .merge(api::usage::router(self.state.quota_service.clone(), self.state.tenant_limits.clone()))
+            .merge(api::version::router(self.info.clone()))
+            .merge(api::status::router(self.state.status.clone()))
+            .merge(api::maintenance::router(
+                self.state.read_only.clone(),
+                self.state.maintenance.clone(),
+                self.state.user_service.clone(),
+            ))
+            .merge(api::jobs::router(self.state.job_queues.clone(), self.state.user_service.clone()))
+            .merge(api::presence::router(self.state.presence.clone(), self.state.user_service.clone()))
+            .merge(api::users::router(self.state.user_service.clone()))
+            .merge(api::role_requests::router(self.state.user_service.clone()))
+            .merge(api::role_grants::router(self.state.user_service.clone()))
+            .merge(api::organizations::router(
+                self.state.organization_service.clone(),
+                self.state.user_service.clone(),
+            ))
+            .merge(api::announcements::router(
+                self.state.announcements.clone(),
+                self.state.user_service.clone(),
+            ))
+            .merge(api::admin::router(
+                self.state.user_service.clone(),
+                self.state.cache_service.clone(),
+                self.state.audit_log.clone(),
+                self.state.login_analytics.clone(),
+            ))
+            .merge(api::notifications::router(
+                self.state.notification_service.clone(),
+                self.state.notification_budget.clone(),
+                self.state.user_service.clone(),
+            ))
+            .merge(api::webhooks::router(
+                self.state.delivery_webhooks.clone(),
+                self.state.user_service.clone(),
+            ));
+        if let Some(tracker) = &self.state.email_tracker {
+            router = router.merge(api::tracking::router(
+                self.state.notification_service.clone(),
+                tracker.clone(),
+                self.state.user_service.clone(),
+            ));
+        }
+        if let (Some(exports), Some(signer)) = (&self.state.exports, &self.state.url_signer) {
+            router = router.merge(api::exports::router(
+                exports.clone(),
+                self.state.user_service.clone(),
+                signer.clone(),
+            ));
+        }
+        if let Some(auth) = &self.state.auth {
+            router = router.merge(api::auth::router(auth.clone(), self.state.user_service.clone()));
+            router = router.merge(api::passkeys::router(
+                self.state.passkeys.clone(),
+                auth.clone(),
+                self.state.user_service.clone(),
+            ));
+            if let Some(devices) = &self.state.trusted_devices {
+                router = router.merge(api::trusted_devices::router(
+                    devices.clone(),
+                    self.state.user_service.clone(),
+                ));
+            }
+            router = router.merge(api::api_keys::router(
+                self.state.api_keys.clone(),
+                self.state.user_service.clone(),
+            ));
+            router = router.merge(api::service_accounts::router(
+                self.state.service_accounts.clone(),
+                auth.clone(),
+                self.state.user_service.clone(),
+            ));
+            router = router.merge(api::internal::router(self.state.user_service.clone()));
+            if let Some(csrf) = &self.state.csrf {
+                router = router.merge(api::csrf::router(csrf.clone()));
+            }
+            if let Some(oauth) = &self.state.oauth {
+                router = router.merge(api::oauth::router(
+                    oauth.clone(),
+                    auth.clone(),
+                    self.state.user_service.clone(),
+                ));
+            }
+            if let Some(saml) = &self.state.saml {
+                router = router.merge(api::saml::router(
+                    saml.clone(),
+                    auth.clone(),
+                    self.state.user_service.clone(),
+                ));
+            }
+        }
+
+        // The layers and their order come from the configuration, per route group
+        let mut stack = MiddlewareStack::new(
+            self.config.middleware.clone(),
+            self.config.http_request_timeout,
+            self.state.metrics.clone(),
+        )
+        .with_proxy(self.config.proxy.clone())
+        .with_quota(self.state.quota_service.clone())
+        .with_maintenance(MaintenanceGate::new(
+            self.state.maintenance.clone(),
+            self.state.user_service.clone(),
+        ))
+        .with_idempotency(Arc::new(IdempotencyMiddleware::new(
+            self.state.cache_service.clone(),
+            self.config.middleware.idempotency_ttl,
+            self.state.metrics.clone(),
+        )));
+        let limiter = RateLimitMiddleware::from_config(self.state.cache_service.clone(), &self.config.rate_limits);
+        if let Some(limiter) = limiter {
+            stack = stack.with_rate_limits(Arc::new(limiter.with_tenant_limits(self.state.tenant_limits.clone())));
+        }
+        if let Some(access) = &self.state.ip_access {
+            stack = stack.with_ip_access(access.clone());
+        }
+        if let Some(csrf) = &self.state.csrf {
+            stack = stack.with_csrf(csrf.clone());
+        }
+        if let Some(auth) = &self.state.auth {
+            stack = stack.with_auth(auth.clone());
+        }
+        let capture = RequestCapture::from_config(&self.config.middleware.capture, self.state.storage.clone());
+        if let Some(capture) = capture {
+            stack = stack.with_capture(Arc::new(capture));
+        }
+        stack.apply(router)
+    }
+
+    /// Run the onboarding saga for a single user
+    async fn onboard_user(&self, request: &CreateUserRequest) -> Result<User> {
+        let context = UserOnboardingSaga::context(request, &TenantContext::default())?;
+        let record = self.state.sagas.start(UserOnboardingSaga::NAME, context).await?;
+        let user_id = UserOnboardingSaga::user_id(&record.into_result()?)?;
+        let user = self.state.user_service.get_user_by_id(user_id).await?;
+        Ok(user.ok_or_else(|| AppError::NotFound(format!("User {} not found", user_id)))?)
+    }
+
     /// Wait for shutdown signal
     async fn wait_for_shutdown(&self) {
         let ctrl_c = signal::ctrl_c();
@@ -191,45 +1107,165 @@ impl Application {
                 info!("Received Ctrl+C, shutting down...");
             }
         }
-
-        self.shutdown().await;
     }
 
-    /// Graceful shutdown
-    async fn shutdown(&self) {
+    /// Graceful shutdown, timing each service into `report` and logging it once done
+    async fn shutdown(&self, report: &mut ShutdownReport) {
         info!("Starting graceful shutdown");
 
         // Shutdown services in reverse dependency order
-        if let Err(e) = self.state.notification_service.shutdown().await {
+        let started = Instant::now();
+        let result = self.state.notification_service.shutdown().await;
+        if let Err(e) = &result {
             error!("Error shutting down notification service: {}", e);
         }
+        report.service("notification_service", started.elapsed(), result);
 
-        if let Err(e) = self.state.user_service.shutdown().await {
+        let started = Instant::now();
+        let result = self.state.user_service.shutdown().await;
+        if let Err(e) = &result {
             error!("Error shutting down user service: {}", e);
         }
+        report.service("user_service", started.elapsed(), result);
 
-        if let Err(e) = self.state.cache_service.close().await {
+        // Flush metrics while the services they describe have stopped but the logger is still up
+        let metrics = match self.state.metrics.flush().await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                report.error(format!("Final metrics flush failed: {}", e));
+                Default::default()
+            }
+        };
+
+        let started = Instant::now();
+        let result = self.state.cache_service.close().await;
+        if let Err(e) = &result {
             error!("Error closing cache service: {}", e);
         }
+        report.service("cache_service", started.elapsed(), result);
 
-        if let Err(e) = self.state.database.close().await {
+        let started = Instant::now();
+        let result = self.state.database.close().await;
+        if let Err(e) = &result {
             error!("Error closing database connection: {}", e);
         }
+        report.service("database", started.elapsed(), result);
 
-        info!("Graceful shutdown completed");
+        report.finish(metrics);
+        let line = format!("Shutdown report: {}", report.to_json());
+        if report.is_clean() {
+            self.state.logger.info(&line);
+        } else {
+            self.state.logger.warn(&line);
+        }
+        info!("Graceful shutdown completed in {}ms", report.total_ms);
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter("info,crawler_test_rust=debug")
+    // Initialize tracing; the budget layer times phase spans for per-request latency reports,
+    // while written spans are limited to sampled requests
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::EnvFilter::new("info,crawler_test_rust=debug"))
+        .with(tracing_subscriber::fmt::layer().with_filter(SampledSpans))
+        .with(LatencyBudgetLayer)
         .init();
 
+    let cli = Cli::parse();
+
+    // Fetch the secrets configuration variables refer to before any configuration is read
+    let secrets = SecretStore::load(&SecretsConfig::from_env()?).await?.map(Arc::new);
+
+    match cli.command {
+        Some(Command::DumpAnonymized(args)) => {
+            let config = AppConfig::from_env()?;
+            let output = args.output.clone();
+            let summary = cli::dump::run(&config, args).await?;
+            info!(
+                "Wrote {} users and {} notifications to {}",
+                summary.users,
+                summary.notifications,
+                output.display()
+            );
+            return Ok(());
+        }
+        Some(Command::Bench(args)) => {
+            let config = AppConfig::from_env()?;
+            let report = cli::bench::run(&config, args).await?;
+            info!("Benchmark finished after {:.1?}", report.elapsed);
+            for workload in &report.workloads {
+                info!("{}", workload);
+            }
+            return Ok(());
+        }
+        Some(Command::Seed(args)) => {
+            let config = AppConfig::from_env()?;
+            let summary = cli::seed::run(&config, args).await?;
+            info!(
+                "Seeded {} users, {} devices and {} notifications",
+                summary.users, summary.devices, summary.notifications
+            );
+            return Ok(());
+        }
+        Some(Command::Migrate(args)) => {
+            let config = AppConfig::from_env()?;
+            match args.action {
+                cli::migrate::MigrateAction::Run => {
+                    let migrated = cli::migrate::run(&config).await?;
+                    info!("Migrated {} databases", migrated);
+                }
+                cli::migrate::MigrateAction::Check(args) => {
+                    let report = cli::migrate::check(&config, args).await?;
+                    for finding in &report.lint {
+                        warn!("Lint: {}", finding);
+                    }
+                    for database in &report.databases {
+                        for version in &database.pending {
+                            warn!("[{}] migration {} is not applied", database.database, version);
+                        }
+                        for version in &database.unknown {
+                            warn!("[{}] applied migration {} is unknown or failed", database.database, version);
+                        }
+                        for version in &database.modified {
+                            warn!("[{}] migration {} changed after it was applied", database.database, version);
+                        }
+                        for drift in &database.drift {
+                            warn!("[{}] {}", database.database, drift);
+                        }
+                    }
+                    if !report.passed() {
+                        error!("Schema check failed");
+                        std::process::exit(1);
+                    }
+                    info!("Schema of {} databases matches the migrations", report.databases.len());
+                }
+            }
+            return Ok(());
+        }
+        Some(Command::Replay(args)) => {
+            let config = AppConfig::from_env()?;
+            let report = cli::replay::run(&config, args).await?;
+            for outcome in &report.outcomes {
+                if outcome.matches() {
+                    info!("{}", outcome);
+                } else {
+                    warn!("{}", outcome);
+                }
+            }
+            info!(
+                "Replayed {} captures, {} differ from what was captured",
+                report.outcomes.len(),
+                report.mismatches()
+            );
+            return Ok(());
+        }
+        Some(Command::Serve) | None => {}
+    }
+
     info!("Starting Crawler Test Rust Application");
 
-    let app = Application::new().await?;
+    let app = Application::new(secrets).await?;
     
     if let Err(e) = app.run().await {
         error!("Application failed: {}", e);
@@ -0,0 +1,668 @@
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderValue, Method};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::{AuthConfig, RouteGroup, SessionPolicies};
+use crate::models::{
+    ApiKeyContext, ApiKeyScope, AppError, AppResult, AuthContext, OrgRole, ServiceAccount, ServiceContext, ServiceScope,
+    ServiceToken, TenantContext, TokenKind, TokenPair, User, UserRole,
+};
+use crate::repositories::OrganizationRepository;
+use crate::services::{ApiKeyService, PolicyEngine, QuotaSubject, SessionService, TokenRevocations};
+
+/// Admin routes of one organization, reachable by its moderators and admins
+const ORGANIZATION_ROUTES: &str = "/admin/organizations/";
+/// Header service-to-service callers present their API key in
+pub const API_KEY_HEADER: &str = "x-api-key";
+/// Routes, with everything below them, that API keys may call; all others are for people and service accounts
+const API_KEY_ROUTES: [&str; 1] = ["/usage"];
+/// `typ` header of service account tokens, which carry `ServiceClaims` instead of `Claims`
+const SERVICE_TOKEN_TYPE: &str = "service+jwt";
+
+/// Claims carried by every token this service issues
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: Uuid,
+    pub role: UserRole,
+    pub kind: TokenKind,
+    /// Session the token belongs to, if it was issued for one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+    /// Sign-in the token descends from through refreshes; revoked as a whole on reuse
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fam: Option<Uuid>,
+    pub jti: Uuid,
+    pub iss: String,
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+impl Claims {
+    /// Tokens issued before families existed form a family of their own
+    pub fn family(&self) -> Uuid {
+        self.fam.unwrap_or(self.jti)
+    }
+}
+
+/// Claims of a service account token; there is no refresh token, callers trade their secret in again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceClaims {
+    /// The service account
+    pub sub: Uuid,
+    /// Tenant the account belongs to; the token is refused in any other
+    pub ten: String,
+    pub scp: Vec<ServiceScope>,
+    pub jti: Uuid,
+    pub iss: String,
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// A refresh token that validated, and whether it had been traded in before
+#[derive(Debug)]
+pub enum RefreshRedemption {
+    Fresh(Claims),
+    /// Its family has been revoked; the caller should end the session too
+    Reused(Claims),
+}
+
+struct SigningKey {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+/// Every key tokens may be verified with, and the one new tokens are signed with
+struct SigningKeys {
+    keys: HashMap<String, SigningKey>,
+    active_key_id: String,
+}
+
+impl SigningKeys {
+    fn from_config(config: &AuthConfig) -> AppResult<Option<Self>> {
+        let Some(active_key_id) = config.active_key_id.clone() else {
+            return Ok(None);
+        };
+        let keys: HashMap<String, SigningKey> = config
+            .signing_keys
+            .iter()
+            .map(|(key_id, secret)| {
+                let key = SigningKey {
+                    encoding: EncodingKey::from_secret(secret.as_bytes()),
+                    decoding: DecodingKey::from_secret(secret.as_bytes()),
+                };
+                (key_id.clone(), key)
+            })
+            .collect();
+        if !keys.contains_key(&active_key_id) {
+            return Err(AppError::Config(format!("JWT signing key {} is not configured", active_key_id)));
+        }
+        Ok(Some(Self { keys, active_key_id }))
+    }
+}
+
+/// Issues and verifies HS256 JWTs.
+///
+/// Tokens name their signing key in the `kid` header, so keys can be
+/// rotated by adding a new one, making it active, and removing the old one
+/// once its longest-lived refresh tokens have expired.
+pub struct AuthMiddleware {
+    /// Swapped whole when keys are rotated at runtime
+    signing: RwLock<Arc<SigningKeys>>,
+    issuer: String,
+    audience: String,
+    access_token_ttl: Duration,
+    refresh_token_ttl: Duration,
+    service_token_ttl: Duration,
+    api_keys: Option<Arc<ApiKeyService>>,
+    revocations: Option<Arc<TokenRevocations>>,
+    policies: Option<Arc<PolicyEngine>>,
+    organizations: Option<Arc<OrganizationRepository>>,
+    session_policies: Option<SessionPolicies>,
+    sessions: Option<Arc<SessionService>>,
+}
+
+impl AuthMiddleware {
+    /// None when no signing keys are configured, which leaves every request anonymous
+    pub fn from_config(config: &AuthConfig) -> AppResult<Option<Self>> {
+        let Some(signing) = SigningKeys::from_config(config)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            signing: RwLock::new(Arc::new(signing)),
+            issuer: config.issuer.clone(),
+            audience: config.audience.clone(),
+            access_token_ttl: config.access_token_ttl,
+            refresh_token_ttl: config.refresh_token_ttl,
+            service_token_ttl: config.service_token_ttl,
+            api_keys: None,
+            revocations: None,
+            policies: None,
+            organizations: None,
+            session_policies: None,
+            sessions: None,
+        }))
+    }
+
+    /// Also accept API keys in the `X-Api-Key` header
+    pub fn with_api_keys(mut self, api_keys: Arc<ApiKeyService>) -> Self {
+        self.api_keys = Some(api_keys);
+        self
+    }
+
+    /// Rotate refresh tokens: each may be traded in once, and reuse revokes its family
+    pub fn with_revocations(mut self, revocations: Arc<TokenRevocations>) -> Self {
+        self.revocations = Some(revocations);
+        self
+    }
+
+    /// Turn away tokens whose role lacks the admin permission at `/admin/` routes
+    /// before they reach a handler
+    pub fn with_policies(mut self, policies: Arc<PolicyEngine>) -> Self {
+        self.policies = Some(policies);
+        self
+    }
+
+    /// Let organization moderators and admins through to their organization's admin routes
+    /// without the global admin permission
+    pub fn with_organizations(mut self, organizations: Arc<OrganizationRepository>) -> Self {
+        self.organizations = Some(organizations);
+        self
+    }
+
+    /// Issue tokens with the lifetimes of the user's role where its session policy sets them
+    pub fn with_session_policies(mut self, session_policies: SessionPolicies) -> Self {
+        self.session_policies = Some(session_policies);
+        self
+    }
+
+    /// Refuse access tokens of sessions that were revoked, sat idle past
+    /// their timeout or outlived their lifetime, even before the token expires
+    pub fn with_sessions(mut self, sessions: Arc<SessionService>) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    /// Whether the session a token was issued for is still live; tokens
+    /// without a session only end when they expire
+    pub async fn check_session(&self, context: &AuthContext) -> AppResult<()> {
+        let (Some(sessions), Some(session_id)) = (&self.sessions, context.session_id) else {
+            return Ok(());
+        };
+        match sessions.get(session_id).await? {
+            Some(session) if session.user_id == context.user_id => Ok(()),
+            _ => Err(AppError::Unauthorized("Session is no longer valid".to_string())),
+        }
+    }
+
+    /// Whether a bearer token was issued to a service account rather than a user
+    pub fn is_service_token(&self, token: &str) -> bool {
+        decode_header(token).is_ok_and(|header| is_service_header(&header))
+    }
+
+    /// Whether a token's role may reach routes of `group`. Only a cheap
+    /// first check on the role the token was issued with; handlers still
+    /// check the caller's current account.
+    pub fn permits(&self, context: &AuthContext, group: RouteGroup) -> bool {
+        match (&self.policies, group) {
+            (Some(policies), RouteGroup::Admin) => policies.role_allows(&context.role, "admin"),
+            _ => true,
+        }
+    }
+
+    /// Whether the caller may reach an `/admin/organizations/{id}/` route of
+    /// that organization: moderators may read, only org admins may change
+    /// anything. Like `permits`, only a first check; handlers decide what
+    /// their org role allows.
+    pub async fn permits_organization(&self, context: &AuthContext, method: &Method, path: &str) -> bool {
+        let Some(organizations) = &self.organizations else {
+            return false;
+        };
+        let Some(organization_id) = path
+            .strip_prefix(ORGANIZATION_ROUTES)
+            .and_then(|rest| rest.split('/').next())
+            .and_then(|id| id.parse::<Uuid>().ok())
+        else {
+            return false;
+        };
+        let required = if *method == Method::GET || *method == Method::HEAD {
+            OrgRole::OrgModerator
+        } else {
+            OrgRole::OrgAdmin
+        };
+        match organizations.member_role(organization_id, context.user_id).await {
+            Ok(role) => role.is_some_and(|role| role.level() >= required.level()),
+            Err(e) => {
+                tracing::warn!("Failed to look up organization role of {}: {}", context.user_id, e);
+                false
+            }
+        }
+    }
+
+    /// Issue an access and refresh token for a signed-in user, starting a new token family
+    pub fn issue(&self, user: &User, session_id: Option<Uuid>) -> AppResult<TokenPair> {
+        self.issue_in_family(user, session_id, Uuid::new_v4())
+    }
+
+    /// Issue the pair that replaces a redeemed refresh token
+    pub fn issue_rotated(&self, user: &User, redeemed: &Claims) -> AppResult<TokenPair> {
+        self.issue_in_family(user, redeemed.sid, redeemed.family())
+    }
+
+    fn issue_in_family(&self, user: &User, session_id: Option<Uuid>, family: Uuid) -> AppResult<TokenPair> {
+        let (access_ttl, refresh_ttl) = self.token_ttls(&user.role);
+        Ok(TokenPair {
+            access_token: self.sign(user, session_id, None, TokenKind::Access, access_ttl)?,
+            refresh_token: self.sign(user, session_id, Some(family), TokenKind::Refresh, refresh_ttl)?,
+            token_type: "Bearer",
+            expires_in: access_ttl.as_secs(),
+        })
+    }
+
+    /// Issue a short-lived token for a service account, carrying only `scopes`
+    pub fn issue_service_token(&self, account: &ServiceAccount, scopes: Vec<ServiceScope>) -> AppResult<ServiceToken> {
+        let now = Utc::now().timestamp();
+        let claims = ServiceClaims {
+            sub: account.id,
+            ten: account.tenant_id.clone(),
+            scp: scopes.clone(),
+            jti: Uuid::new_v4(),
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            iat: now,
+            exp: now + self.service_token_ttl.as_secs() as i64,
+        };
+        Ok(ServiceToken {
+            access_token: self.encode_claims(&claims, Some(SERVICE_TOKEN_TYPE))?,
+            token_type: "Bearer",
+            expires_in: self.service_token_ttl.as_secs(),
+            scopes,
+        })
+    }
+
+    /// Access and refresh token lifetimes for `role`
+    fn token_ttls(&self, role: &UserRole) -> (Duration, Duration) {
+        let policy = self.session_policies.as_ref().map(|policies| policies.for_role(role));
+        (
+            policy
+                .and_then(|policy| policy.access_token_ttl)
+                .unwrap_or(self.access_token_ttl),
+            policy
+                .and_then(|policy| policy.refresh_token_ttl)
+                .unwrap_or(self.refresh_token_ttl),
+        )
+    }
+
+    /// Validate a refresh token and use it up.
+    ///
+    /// A token of a revoked family is refused outright. A token that was
+    /// already traded in revokes its family and comes back as `Reused`, so
+    /// both the thief and the legitimate client have to sign in again.
+    /// Without a revocation list tokens stay reusable until they expire.
+    pub async fn redeem_refresh(&self, refresh_token: &str) -> AppResult<RefreshRedemption> {
+        let claims = self.validate(refresh_token, TokenKind::Refresh)?;
+        let Some(revocations) = &self.revocations else {
+            return Ok(RefreshRedemption::Fresh(claims));
+        };
+        let family = claims.family();
+        if revocations.is_family_revoked(family).await? {
+            return Err(AppError::Unauthorized("Refresh token has been revoked".to_string()));
+        }
+        let expires_at = DateTime::from_timestamp(claims.exp, 0).unwrap_or_else(Utc::now);
+        if revocations.mark_used(claims.jti, expires_at).await? {
+            return Ok(RefreshRedemption::Fresh(claims));
+        }
+        // Long enough to outlive every refresh token of the family, whatever its role
+        let family_ttl = self
+            .session_policies
+            .as_ref()
+            .map_or(self.refresh_token_ttl, |policies| {
+                policies.longest_refresh_token_ttl(self.refresh_token_ttl)
+            });
+        revocations.revoke_family(family, family_ttl).await?;
+        tracing::warn!(
+            user_id = %claims.sub,
+            family = %family,
+            "Refresh token reused; revoked its token family"
+        );
+        Ok(RefreshRedemption::Reused(claims))
+    }
+
+    /// Replace the signing keys, e.g. after a secret rotation; tokens signed
+    /// with a key no longer listed stop validating
+    pub fn rotate_keys(&self, config: &AuthConfig) -> AppResult<()> {
+        let signing = SigningKeys::from_config(config)?
+            .ok_or_else(|| AppError::Config("JWT signing keys cannot be removed while running".to_string()))?;
+        *self.signing.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(signing);
+        Ok(())
+    }
+
+    fn signing_keys(&self) -> Arc<SigningKeys> {
+        self.signing.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Verify signature, issuer, audience, expiry and kind, returning the claims
+    pub fn validate(&self, token: &str, kind: TokenKind) -> AppResult<Claims> {
+        let claims: Claims = self.verify(token, false)?;
+        if claims.kind != kind {
+            return Err(invalid_token());
+        }
+        Ok(claims)
+    }
+
+    /// Verify a token's signature, issuer, audience and expiry, and that it is
+    /// a service token exactly when `service` is set
+    fn verify<T: DeserializeOwned>(&self, token: &str, service: bool) -> AppResult<T> {
+        let header = decode_header(token).map_err(|_| invalid_token())?;
+        if is_service_header(&header) != service {
+            return Err(invalid_token());
+        }
+        let signing = self.signing_keys();
+        let key = header
+            .kid
+            .as_deref()
+            .and_then(|key_id| signing.keys.get(key_id))
+            .ok_or_else(invalid_token)?;
+
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = decode::<T>(token, &key.decoding, &validation)
+            .map_err(|e| match e.kind() {
+                jsonwebtoken::errors::ErrorKind::ExpiredSignature => {
+                    AppError::Unauthorized("Token has expired".to_string())
+                }
+                _ => invalid_token(),
+            })?
+            .claims;
+        Ok(claims)
+    }
+
+    /// The caller behind an API key; keys only work for the tenant they were issued in
+    #[tracing::instrument(name = "auth", skip_all)]
+    pub async fn authorize_api_key(&self, value: &HeaderValue, tenant: &TenantContext) -> AppResult<ApiKeyContext> {
+        let api_keys = self.api_keys.as_ref().ok_or_else(invalid_api_key)?;
+        let key = value.to_str().map_err(|_| invalid_api_key())?.trim();
+        let context = api_keys.authenticate(key).await?;
+        if context.tenant_id != tenant.tenant_id {
+            return Err(invalid_api_key());
+        }
+        Ok(context)
+    }
+
+    /// The caller behind an access token
+    #[tracing::instrument(name = "auth", skip_all)]
+    pub fn authorize(&self, access_token: &str) -> AppResult<AuthContext> {
+        let claims = self.validate(access_token, TokenKind::Access)?;
+        Ok(AuthContext {
+            user_id: claims.sub,
+            role: claims.role,
+            session_id: claims.sid,
+            token_id: claims.jti,
+            expires_at: DateTime::from_timestamp(claims.exp, 0).unwrap_or_else(Utc::now),
+        })
+    }
+
+    /// The service principal behind a service token; tokens only work for the tenant they were issued in
+    #[tracing::instrument(name = "auth", skip_all)]
+    pub fn authorize_service(&self, token: &str, tenant: &TenantContext) -> AppResult<ServiceContext> {
+        let claims: ServiceClaims = self.verify(token, true)?;
+        if claims.ten != tenant.tenant_id {
+            return Err(invalid_token());
+        }
+        Ok(ServiceContext {
+            service_account_id: claims.sub,
+            tenant_id: claims.ten,
+            scopes: claims.scp,
+            token_id: claims.jti,
+            expires_at: DateTime::from_timestamp(claims.exp, 0).unwrap_or_else(Utc::now),
+        })
+    }
+
+    fn sign(
+        &self,
+        user: &User,
+        session_id: Option<Uuid>,
+        family: Option<Uuid>,
+        kind: TokenKind,
+        ttl: Duration,
+    ) -> AppResult<String> {
+        let now = Utc::now().timestamp();
+        let claims = Claims {
+            sub: user.id,
+            role: user.role.clone(),
+            kind,
+            sid: session_id,
+            fam: family,
+            jti: Uuid::new_v4(),
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            iat: now,
+            exp: now + ttl.as_secs() as i64,
+        };
+        self.encode_claims(&claims, None)
+    }
+
+    /// Sign with the active key, naming it in `kid`; `typ` marks tokens other than user ones
+    fn encode_claims<T: Serialize>(&self, claims: &T, token_type: Option<&str>) -> AppResult<String> {
+        let signing = self.signing_keys();
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(signing.active_key_id.clone());
+        if let Some(token_type) = token_type {
+            header.typ = Some(token_type.to_string());
+        }
+        encode(&header, claims, &signing.keys[&signing.active_key_id].encoding)
+            .map_err(|e| AppError::Internal(format!("Failed to sign token: {}", e)))
+    }
+}
+
+/// Attach `AuthContext` and the caller's `QuotaSubject` for requests with a bearer token.
+///
+/// Requests without an `Authorization` header continue anonymously and are
+/// left to the routes to refuse; a header with a bad or expired token, or
+/// one whose session has ended, is rejected here, so clients learn they
+/// need to refresh or sign in again. Requests with an `X-Api-Key` header
+/// get an `ApiKeyContext` instead and are metered against the key; keys
+/// only reach the routes open to them, with the scope `api_key_scope`
+/// asks for. Service account tokens get a `ServiceContext`
+/// and no `AuthContext`, so routes for people never mistake a service for
+/// one; they are not metered and never reach admin routes.
+pub async fn authenticate(
+    State(auth): State<Arc<AuthMiddleware>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if let Some(value) = request.headers().get(API_KEY_HEADER) {
+        let tenant = request.extensions().get::<TenantContext>().cloned().unwrap_or_default();
+        let context = match auth.authorize_api_key(value, &tenant).await {
+            Ok(context) => context,
+            Err(e) => return e.into_response(),
+        };
+        let Some(scope) = api_key_scope(request.method(), request.uri().path()) else {
+            return AppError::Forbidden("API keys cannot call this route".to_string()).into_response();
+        };
+        if let Err(e) = context.require(scope) {
+            return e.into_response();
+        }
+        request.extensions_mut().insert(QuotaSubject::ApiKey(context.key_id.to_string()));
+        request.extensions_mut().insert(context);
+        return next.run(request).await;
+    }
+
+    let Some(value) = request.headers().get(header::AUTHORIZATION) else {
+        return next.run(request).await;
+    };
+    let Some(token) = value
+        .to_str()
+        .ok()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+    else {
+        return invalid_token().into_response();
+    };
+    let group = RouteGroup::of_path(request.uri().path());
+
+    if auth.is_service_token(token) {
+        let tenant = request.extensions().get::<TenantContext>().cloned().unwrap_or_default();
+        let context = match auth.authorize_service(token, &tenant) {
+            Ok(context) => context,
+            Err(e) => return e.into_response(),
+        };
+        if group == RouteGroup::Admin {
+            return AppError::Forbidden("Service accounts cannot reach admin routes".to_string()).into_response();
+        }
+        request.extensions_mut().insert(context);
+        return next.run(request).await;
+    }
+
+    let context = match auth.authorize(token) {
+        Ok(context) => context,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = auth.check_session(&context).await {
+        return e.into_response();
+    }
+    if !auth.permits(&context, group)
+        && !auth
+            .permits_organization(&context, request.method(), request.uri().path())
+            .await
+    {
+        return AppError::Forbidden("Admin permission required".to_string()).into_response();
+    }
+
+    request.extensions_mut().insert(QuotaSubject::User(context.user_id));
+    request.extensions_mut().insert(context);
+    next.run(request).await
+}
+
+/// The scope an API key needs for a request: `read` to read, `write` for
+/// anything else. None when API keys cannot call the route at all.
+pub fn api_key_scope(method: &Method, path: &str) -> Option<ApiKeyScope> {
+    let open = API_KEY_ROUTES.iter().any(|route| {
+        path.strip_prefix(route)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    });
+    if !open {
+        return None;
+    }
+    if *method == Method::GET || *method == Method::HEAD {
+        Some(ApiKeyScope::Read)
+    } else {
+        Some(ApiKeyScope::Write)
+    }
+}
+
+fn is_service_header(header: &Header) -> bool {
+    header.typ.as_deref() == Some(SERVICE_TOKEN_TYPE)
+}
+
+fn invalid_token() -> AppError {
+    AppError::Unauthorized("Invalid token".to_string())
+}
+
+fn invalid_api_key() -> AppError {
+    AppError::Unauthorized("Invalid API key".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(key_id: &str, secret: &str) -> AuthConfig {
+        AuthConfig {
+            signing_keys: vec![(key_id.to_string(), secret.to_string())],
+            active_key_id: Some(key_id.to_string()),
+            issuer: "crawler".to_string(),
+            audience: "crawler-api".to_string(),
+            access_token_ttl: Duration::from_secs(900),
+            refresh_token_ttl: Duration::from_secs(86_400),
+            api_key_rotation_grace: Duration::from_secs(3600),
+            service_token_ttl: Duration::from_secs(300),
+            trusted_device_secret: None,
+            trusted_device_ttl: Duration::from_secs(86_400),
+        }
+    }
+
+    fn auth() -> AuthMiddleware {
+        AuthMiddleware::from_config(&config("k1", "test-secret"))
+            .unwrap()
+            .expect("signing keys are configured")
+    }
+
+    fn user() -> User {
+        User::new(
+            "ada@example.com".to_string(),
+            "ada".to_string(),
+            "Ada".to_string(),
+            "Lovelace".to_string(),
+            "hash".to_string(),
+        )
+    }
+
+    #[test]
+    fn issued_tokens_validate_as_their_own_kind_only() {
+        let auth = auth();
+        let (user, session_id) = (user(), Uuid::new_v4());
+        let pair = auth.issue(&user, Some(session_id)).unwrap();
+
+        let access = auth.validate(&pair.access_token, TokenKind::Access).unwrap();
+        assert_eq!(access.sub, user.id);
+        assert_eq!(access.sid, Some(session_id));
+        assert!(auth.validate(&pair.refresh_token, TokenKind::Access).is_err());
+        assert!(auth.validate(&pair.access_token, TokenKind::Refresh).is_err());
+        assert_eq!(pair.expires_in, 900);
+    }
+
+    #[tokio::test]
+    async fn redeemed_refresh_tokens_rotate_within_their_family() {
+        let auth = auth();
+        let user = user();
+        let pair = auth.issue(&user, Some(Uuid::new_v4())).unwrap();
+
+        let RefreshRedemption::Fresh(claims) = auth.redeem_refresh(&pair.refresh_token).await.unwrap() else {
+            panic!("a first redemption is fresh");
+        };
+        let rotated = auth.issue_rotated(&user, &claims).unwrap();
+        let next = auth.validate(&rotated.refresh_token, TokenKind::Refresh).unwrap();
+        assert_eq!(next.family(), claims.family());
+        assert_eq!(next.sid, claims.sid);
+        assert_ne!(next.jti, claims.jti);
+        assert!(auth.redeem_refresh(&pair.access_token).await.is_err());
+    }
+
+    #[test]
+    fn tokens_of_removed_keys_stop_validating() {
+        let auth = auth();
+        let pair = auth.issue(&user(), None).unwrap();
+        auth.rotate_keys(&config("k2", "another-secret")).unwrap();
+        assert!(auth.validate(&pair.access_token, TokenKind::Access).is_err());
+    }
+
+    #[test]
+    fn api_keys_read_with_safe_methods_and_write_with_the_rest() {
+        assert_eq!(api_key_scope(&Method::GET, "/usage"), Some(ApiKeyScope::Read));
+        assert_eq!(api_key_scope(&Method::HEAD, "/usage/history"), Some(ApiKeyScope::Read));
+        assert_eq!(api_key_scope(&Method::POST, "/usage/reset"), Some(ApiKeyScope::Write));
+        assert_eq!(api_key_scope(&Method::DELETE, "/usage"), Some(ApiKeyScope::Write));
+    }
+
+    #[test]
+    fn api_keys_cannot_call_user_routes() {
+        assert_eq!(api_key_scope(&Method::GET, "/users"), None);
+        assert_eq!(api_key_scope(&Method::GET, "/auth/sessions"), None);
+        assert_eq!(api_key_scope(&Method::POST, "/admin/users/bulk"), None);
+        assert_eq!(api_key_scope(&Method::GET, "/usage-export"), None);
+    }
+}
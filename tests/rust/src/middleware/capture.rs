@@ -0,0 +1,220 @@
+use axum::body::{to_bytes, Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::Utc;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
+use url::form_urlencoded;
+use uuid::Uuid;
+
+use super::{API_KEY_HEADER, CSRF_HEADER};
+use crate::config::RequestCaptureConfig;
+use crate::models::{
+    AppError, AppResult, AuthContext, CapturedBody, CapturedExchange, CapturedRequest, CapturedResponse,
+    TenantContext, REDACTED,
+};
+use crate::services::StorageService;
+
+/// Headers carrying credentials
+const SECRET_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    API_KEY_HEADER,
+    CSRF_HEADER,
+];
+/// Fields and parameters redacted wherever their name contains one of these
+const SECRET_PARTS: &[&str] = &["password", "secret", "token"];
+/// Fields and parameters redacted by exact name
+const SECRET_NAMES: &[&str] = &["code", "otp", "state", "samlresponse", "samlrequest", "signature", "api_key"];
+
+/// Records requests and their responses into storage so a bug can be
+/// replayed against a local instance with the `replay` subcommand.
+///
+/// Only requests of the configured users, or under the configured route
+/// prefixes, are recorded. Credentials and secret-looking fields are
+/// redacted before anything is stored, and only JSON and form bodies are
+/// kept.
+pub struct RequestCapture {
+    config: RequestCaptureConfig,
+    storage: Arc<StorageService>,
+}
+
+impl RequestCapture {
+    /// None unless some user or route was chosen for capture
+    pub fn from_config(config: &RequestCaptureConfig, storage: Arc<StorageService>) -> Option<Self> {
+        config.is_enabled().then(|| Self {
+            config: config.clone(),
+            storage,
+        })
+    }
+
+    pub fn wants(&self, path: &str, user: Option<Uuid>) -> bool {
+        user.is_some_and(|user| self.config.users.contains(&user))
+            || self.config.routes.iter().any(|prefix| path.starts_with(prefix.as_str()))
+    }
+
+    fn body(&self, headers: &HeaderMap, bytes: &Bytes) -> CapturedBody {
+        if bytes.is_empty() {
+            return CapturedBody::Empty;
+        }
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        let essence = content_type
+            .as_deref()
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase());
+        let omitted = || CapturedBody::Omitted {
+            content_type: content_type.clone(),
+            bytes: bytes.len(),
+        };
+        if bytes.len() > self.config.max_body_bytes {
+            return omitted();
+        }
+
+        match essence.as_deref() {
+            Some(kind) if kind == "application/json" || kind.ends_with("+json") => {
+                match serde_json::from_slice::<Value>(bytes) {
+                    Ok(mut content) => {
+                        redact_json(&mut content);
+                        CapturedBody::Json { content }
+                    }
+                    Err(_) => omitted(),
+                }
+            }
+            Some("application/x-www-form-urlencoded") => CapturedBody::Form {
+                content: redact_pairs(bytes),
+            },
+            _ => omitted(),
+        }
+    }
+
+    async fn store(&self, exchange: &CapturedExchange) -> AppResult<()> {
+        let content = serde_json::to_vec(exchange)
+            .map_err(|e| AppError::Internal(format!("Failed to encode capture: {}", e)))?;
+        self.storage.put(&CapturedExchange::storage_key(exchange.id), &content).await
+    }
+}
+
+/// Record the request and its response when the caller or route was chosen for capture.
+///
+/// Runs after auth, so captures by user know the caller. Storing happens in
+/// the background; a failure to store is logged and never reaches the
+/// caller.
+pub async fn capture_requests(State(capture): State<Arc<RequestCapture>>, request: Request, next: Next) -> Response {
+    let user_id = request.extensions().get::<AuthContext>().map(|context| context.user_id);
+    if !capture.wants(request.uri().path(), user_id) {
+        return next.run(request).await;
+    }
+    let tenant_id = request
+        .extensions()
+        .get::<TenantContext>()
+        .map(|tenant| tenant.tenant_id.clone());
+
+    let started = Instant::now();
+    let (parts, body) = request.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return AppError::Validation(vec![format!("Unreadable request body: {}", e)]).into_response(),
+    };
+    let captured_request = CapturedRequest {
+        method: parts.method.to_string(),
+        uri: redact_uri(parts.uri.path(), parts.uri.query()),
+        headers: redact_headers(&parts.headers),
+        body: capture.body(&parts.headers, &bytes),
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    let (parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return AppError::Internal(format!("Unreadable response body: {}", e)).into_response(),
+    };
+    let exchange = CapturedExchange {
+        id: Uuid::new_v4(),
+        captured_at: Utc::now(),
+        user_id,
+        tenant_id,
+        request: captured_request,
+        response: CapturedResponse {
+            status: parts.status.as_u16(),
+            headers: redact_headers(&parts.headers),
+            body: capture.body(&parts.headers, &bytes),
+        },
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+
+    tokio::spawn(async move {
+        match capture.store(&exchange).await {
+            Ok(()) => tracing::info!(
+                "Captured request {} {} {} -> {}",
+                exchange.id,
+                exchange.request.method,
+                exchange.request.uri,
+                exchange.response.status
+            ),
+            Err(e) => tracing::warn!("Failed to store capture {}: {}", exchange.id, e),
+        }
+    });
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_NAMES.contains(&name.as_str()) || SECRET_PARTS.iter().any(|part| name.contains(part))
+}
+
+fn redact_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SECRET_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+fn redact_uri(path: &str, query: Option<&str>) -> String {
+    match query {
+        Some(query) => format!("{}?{}", path, redact_pairs(query.as_bytes())),
+        None => path.to_string(),
+    }
+}
+
+/// A urlencoded string with the values of secret names replaced
+fn redact_pairs(encoded: &[u8]) -> String {
+    let mut serializer = form_urlencoded::Serializer::new(String::new());
+    for (name, value) in form_urlencoded::parse(encoded) {
+        let value = if is_secret(&name) { REDACTED.into() } else { value };
+        serializer.append_pair(&name, &value);
+    }
+    serializer.finish()
+}
+
+/// Replace secret fields at any depth; replays redact live responses the same way to compare them
+pub fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if is_secret(name) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
@@ -0,0 +1,35 @@
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use crate::config::ProxyConfig;
+
+/// Address of the client behind a request; absent when it cannot be told
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientAddress(pub IpAddr);
+
+/// Attach the `ClientAddress` that address lists, rate limits and the
+/// sign-in history all judge the request by.
+///
+/// Runs outermost in every route group's stack, so the address is read
+/// once, the same way for every layer and handler.
+pub async fn resolve_client_address(
+    State(proxy): State<Arc<ProxyConfig>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(peer)| peer.ip());
+    let forwarded_for = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok());
+    if let Some(address) = proxy.client_address(peer, forwarded_for) {
+        request.extensions_mut().insert(ClientAddress(address));
+    }
+    next.run(request).await
+}
@@ -0,0 +1,81 @@
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+use crate::config::CorsConfig;
+
+const ALLOWED_METHODS: &str = "GET, POST, PUT, PATCH, DELETE, OPTIONS";
+const EXPOSED_HEADERS: &str =
+    "retry-after, x-ratelimit-limit, x-ratelimit-remaining, x-quota-limit, x-quota-remaining, deprecation, sunset";
+
+/// Cross-origin access for browser clients on the allowed origins
+pub struct Cors {
+    config: CorsConfig,
+}
+
+impl Cors {
+    /// None when no origin is allowed, which leaves cross-origin requests to the browser's defaults
+    pub fn from_config(config: &CorsConfig) -> Option<Self> {
+        if config.allowed_origins.is_empty() {
+            return None;
+        }
+        Some(Self { config: config.clone() })
+    }
+
+    fn allows(&self, origin: &str) -> bool {
+        self.config
+            .allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed == origin)
+    }
+
+    fn decorate(&self, headers: &mut HeaderMap, origin: &HeaderValue) {
+        let wildcard = self.config.allowed_origins.iter().any(|allowed| allowed == "*");
+        if wildcard && !self.config.allow_credentials {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        } else {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+            headers.append(header::VARY, HeaderValue::from_static("origin"));
+        }
+        if self.config.allow_credentials {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+        }
+    }
+}
+
+/// Answer preflight requests and add CORS headers to responses for allowed origins.
+///
+/// Requests from other origins are served without CORS headers, so the
+/// browser withholds the response from the calling page.
+pub async fn apply_cors(State(cors): State<Arc<Cors>>, request: Request, next: Next) -> Response {
+    let Some(origin) = request
+        .headers()
+        .get(header::ORIGIN)
+        .filter(|origin| origin.to_str().is_ok_and(|origin| cors.allows(origin)))
+        .cloned()
+    else {
+        return next.run(request).await;
+    };
+
+    let preflight = request.method() == Method::OPTIONS
+        && request.headers().contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+    if preflight {
+        let mut response = StatusCode::NO_CONTENT.into_response();
+        let headers = response.headers_mut();
+        cors.decorate(headers, &origin);
+        headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static(ALLOWED_METHODS));
+        if let Some(requested) = request.headers().get(header::ACCESS_CONTROL_REQUEST_HEADERS) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, requested.clone());
+        }
+        headers.insert(header::ACCESS_CONTROL_MAX_AGE, HeaderValue::from(cors.config.max_age.as_secs()));
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    cors.decorate(headers, &origin);
+    headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, HeaderValue::from_static(EXPOSED_HEADERS));
+    response
+}
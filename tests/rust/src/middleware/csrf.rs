@@ -0,0 +1,164 @@
+use axum::extract::{Request, State};
+use axum::http::header::InvalidHeaderValue;
+use axum::http::{header, HeaderMap, HeaderValue, Method};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::CsrfConfig;
+use crate::models::{AppError, AppResult, AuthContext};
+use crate::services::CacheService;
+use crate::utils::Metrics;
+
+pub const CSRF_HEADER: &str = "x-csrf-token";
+pub const CSRF_COOKIE: &str = "csrf_token";
+
+/// A token handed to a browser, to be echoed in `X-CSRF-Token` on mutating requests
+#[derive(Debug, Clone, Serialize)]
+pub struct CsrfToken {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Double-submit CSRF tokens tied to sessions.
+///
+/// Each session gets a random token, kept in the cache by its hash and
+/// handed to the browser both as a cookie and in the response, so page
+/// scripts can send it back in `X-CSRF-Token`. A mutating request from a
+/// session passes only when its header matches both its cookie and the
+/// session's token; a forged cross-site request carries the cookie but
+/// cannot read it to fill in the header. Callers without a session, such as
+/// API keys, hold no ambient credentials and are not checked.
+pub struct CsrfProtection {
+    cache: Arc<CacheService>,
+    config: CsrfConfig,
+    metrics: Arc<Metrics>,
+}
+
+impl CsrfProtection {
+    /// None unless CSRF checks are enabled
+    pub fn from_config(cache: Arc<CacheService>, config: &CsrfConfig, metrics: Arc<Metrics>) -> Option<Self> {
+        config.enabled.then(|| Self {
+            cache,
+            config: config.clone(),
+            metrics,
+        })
+    }
+
+    /// Issue a new token for the session, replacing any it had
+    pub async fn issue(&self, session_id: Uuid) -> AppResult<CsrfToken> {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let token = URL_SAFE_NO_PAD.encode(bytes);
+        self.cache
+            .set(&token_key(session_id), &hash_token(&token), Some(self.config.token_ttl))
+            .await?;
+        let ttl = chrono::Duration::from_std(self.config.token_ttl)
+            .map_err(|e| AppError::Config(format!("Invalid CSRF token lifetime: {}", e)))?;
+        Ok(CsrfToken {
+            token,
+            expires_at: Utc::now() + ttl,
+        })
+    }
+
+    /// Put the token in the response's cookie and `X-CSRF-Token` header
+    pub fn attach(&self, response: &mut Response, token: &CsrfToken) -> AppResult<()> {
+        let invalid = |e: InvalidHeaderValue| AppError::Internal(format!("Invalid CSRF header: {}", e));
+        let headers = response.headers_mut();
+        headers.insert(CSRF_HEADER, HeaderValue::from_str(&token.token).map_err(invalid)?);
+        headers.append(
+            header::SET_COOKIE,
+            HeaderValue::from_str(&self.cookie(token)).map_err(invalid)?,
+        );
+        Ok(())
+    }
+
+    /// Issue a token for the session and attach it to `response`
+    pub async fn embed(&self, session_id: Uuid, mut response: Response) -> AppResult<Response> {
+        let token = self.issue(session_id).await?;
+        self.attach(&mut response, &token)?;
+        Ok(response)
+    }
+
+    /// Whether the request's header token matches its cookie and the session's token
+    pub async fn verify(&self, session_id: Uuid, headers: &HeaderMap) -> AppResult<bool> {
+        let Some(submitted) = headers.get(CSRF_HEADER).and_then(|value| value.to_str().ok()) else {
+            return Ok(false);
+        };
+        if cookie_token(headers) != Some(submitted) {
+            return Ok(false);
+        }
+        let stored: Option<String> = self.cache.get(&token_key(session_id)).await?;
+        Ok(stored.is_some_and(|stored| stored == hash_token(submitted)))
+    }
+
+    /// Not `HttpOnly`, since the page's scripts read it to fill in the header
+    fn cookie(&self, token: &CsrfToken) -> String {
+        let mut cookie = format!(
+            "{}={}; Path=/; SameSite=Strict; Max-Age={}",
+            CSRF_COOKIE,
+            token.token,
+            self.config.token_ttl.as_secs()
+        );
+        if self.config.secure_cookie {
+            cookie.push_str("; Secure");
+        }
+        cookie
+    }
+}
+
+/// Refuse mutating requests from signed-in sessions without a valid CSRF token.
+///
+/// Runs after authentication, which tells which session a request belongs
+/// to; safe methods and callers without a session pass through.
+pub async fn enforce_csrf(State(csrf): State<Arc<CsrfProtection>>, request: Request, next: Next) -> Response {
+    if !is_mutating(request.method()) {
+        return next.run(request).await;
+    }
+    let Some(session_id) = request
+        .extensions()
+        .get::<AuthContext>()
+        .and_then(|context| context.session_id)
+    else {
+        return next.run(request).await;
+    };
+    match csrf.verify(session_id, request.headers()).await {
+        Ok(true) => next.run(request).await,
+        Ok(false) => {
+            let _ = csrf.metrics.increment_counter("http.csrf_rejected").await;
+            AppError::Forbidden("Missing or invalid CSRF token".to_string()).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+fn is_mutating(method: &Method) -> bool {
+    matches!(*method, Method::POST | Method::PUT | Method::PATCH | Method::DELETE)
+}
+
+fn cookie_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == CSRF_COOKIE)
+        .map(|(_, value)| value)
+}
+
+fn token_key(session_id: Uuid) -> String {
+    format!("csrf:{}", session_id)
+}
+
+/// Tokens are kept and compared by hash, so a cache dump does not yield usable ones
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
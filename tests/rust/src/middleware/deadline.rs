@@ -0,0 +1,34 @@
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::time::Duration;
+
+use crate::models::AppError;
+use crate::utils::RequestContext;
+
+const TIMEOUT_HEADER: &str = "x-request-timeout-ms";
+
+/// Give every request a deadline and abandon its handler once it passes.
+///
+/// Clients may ask for a shorter deadline with `X-Request-Timeout-Ms`;
+/// `max_timeout` caps it. Dropping the handler future cancels whatever query
+/// it was waiting on, so no work continues for a caller that has gone.
+pub async fn propagate_deadline(
+    State(max_timeout): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let requested = request
+        .headers()
+        .get(TIMEOUT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_millis);
+    let timeout = requested.map_or(max_timeout, |requested| requested.min(max_timeout));
+
+    let context = RequestContext::with_timeout(timeout);
+    match tokio::time::timeout_at(context.deadline, context.scope(next.run(request))).await {
+        Ok(response) => response,
+        Err(_) => AppError::DeadlineExceeded("request".to_string()).into_response(),
+    }
+}
@@ -0,0 +1,73 @@
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::sync::Arc;
+
+use crate::services::QuotaSubject;
+use crate::utils::{Deprecations, Metrics};
+
+const DEPRECATED_FIELDS_HEADER: &str = "x-deprecated-fields";
+
+/// Tell clients which deprecated request fields they sent and count who still sends them.
+///
+/// Responses to such requests carry `Deprecation` (RFC 9745) with the
+/// earliest deprecation date, `Sunset` (RFC 8594) with the earliest removal
+/// date, and the field names in `X-Deprecated-Fields`. Usage is counted per
+/// route, field and client, so a field can be removed once its counter
+/// stops moving, or its remaining clients can be contacted first. Runs
+/// inside authentication so the client is known.
+pub async fn flag_deprecations(
+    State(metrics): State<Arc<Metrics>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |path| path.as_str())
+        .to_string();
+    let client = request
+        .extensions()
+        .get::<QuotaSubject>()
+        .map_or_else(|| "anonymous".to_string(), |subject| format!("{}:{}", subject.kind(), subject.id()));
+
+    let (mut response, used) = Deprecations::scope(next.run(request)).await;
+    if used.is_empty() {
+        return response;
+    }
+
+    for field in &used {
+        let _ = metrics
+            .increment_labeled_counter(
+                "http.request.deprecated_field",
+                &[("route", route.as_str()), ("field", field.name), ("client", client.as_str())],
+            )
+            .await;
+    }
+    tracing::info!(
+        route = %route,
+        client = %client,
+        fields = ?used.iter().map(|field| field.name).collect::<Vec<_>>(),
+        "Request used deprecated fields"
+    );
+
+    let headers = response.headers_mut();
+    if let Some(deprecated_on) = used.iter().filter_map(|field| field.deprecated_on()).min() {
+        let at = deprecated_on.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp();
+        if let Ok(value) = HeaderValue::from_str(&format!("@{}", at)) {
+            headers.insert("deprecation", value);
+        }
+    }
+    if let Some(sunset) = used.iter().filter_map(|field| field.sunset()).min() {
+        if let Ok(value) = HeaderValue::from_str(&sunset.format("%a, %d %b %Y 00:00:00 GMT").to_string()) {
+            headers.insert("sunset", value);
+        }
+    }
+    let names: Vec<&str> = used.iter().map(|field| field.name).collect();
+    if let Ok(value) = HeaderValue::from_str(&names.join(", ")) {
+        headers.insert(DEPRECATED_FIELDS_HEADER, value);
+    }
+
+    response
+}
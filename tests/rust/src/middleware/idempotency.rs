@@ -0,0 +1,211 @@
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::{AppError, AppResult, TenantContext};
+use crate::services::{CacheService, QuotaSubject};
+use crate::utils::Metrics;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const REPLAYED_HEADER: &str = "idempotent-replayed";
+/// Bodies above this are refused rather than fingerprinted
+const MAX_REQUEST_BYTES: usize = 1024 * 1024;
+/// Responses above this are passed on but not kept for replay
+const MAX_STORED_RESPONSE_BYTES: usize = 256 * 1024;
+/// How long a key stays claimed by a request still running; a crashed
+/// request frees its key after this
+const IN_FLIGHT_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+enum Entry {
+    InFlight {
+        fingerprint: String,
+    },
+    Completed {
+        fingerprint: String,
+        status: u16,
+        content_type: Option<String>,
+        /// Base64 of the response body
+        body: String,
+    },
+}
+
+enum Claim {
+    Claimed,
+    InFlight,
+    /// The key was used for a different request
+    Mismatch,
+    Replay(Response),
+}
+
+/// Responses to mutating requests kept by `Idempotency-Key`, so a client
+/// retrying after a lost response gets the original answer instead of
+/// repeating the change.
+///
+/// Keys are scoped to the tenant and the signed-in caller; requests without
+/// a key, or without a caller, go through untouched.
+pub struct IdempotencyMiddleware {
+    cache: Arc<CacheService>,
+    ttl: Duration,
+    metrics: Arc<Metrics>,
+}
+
+impl IdempotencyMiddleware {
+    pub fn new(cache: Arc<CacheService>, ttl: Duration, metrics: Arc<Metrics>) -> Self {
+        Self { cache, ttl, metrics }
+    }
+
+    async fn claim(&self, key: &str, fingerprint: &str) -> AppResult<Claim> {
+        let in_flight = Entry::InFlight {
+            fingerprint: fingerprint.to_string(),
+        };
+        if self.cache.set_if_absent(key, &in_flight, IN_FLIGHT_TTL).await? {
+            return Ok(Claim::Claimed);
+        }
+        let claim = match self.cache.get::<Entry>(key).await? {
+            // Gone between the two calls; let the request run unguarded rather than fail it
+            None => Claim::Claimed,
+            Some(Entry::InFlight { fingerprint: held }) | Some(Entry::Completed { fingerprint: held, .. })
+                if held != fingerprint =>
+            {
+                Claim::Mismatch
+            }
+            Some(Entry::InFlight { .. }) => Claim::InFlight,
+            Some(Entry::Completed {
+                status,
+                content_type,
+                body,
+                ..
+            }) => Claim::Replay(replay(status, content_type, &body)?),
+        };
+        Ok(claim)
+    }
+
+    /// Keep a response for replay; server errors free the key so the request can be retried
+    async fn finish(&self, key: &str, fingerprint: &str, response: Response) -> AppResult<Response> {
+        let status = response.status();
+        if status.is_server_error() {
+            self.cache.delete(key).await?;
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        let bytes = to_bytes(body, usize::MAX)
+            .await
+            .map_err(|e| AppError::Internal(format!("Unreadable response body: {}", e)))?;
+        if bytes.len() > MAX_STORED_RESPONSE_BYTES {
+            self.cache.delete(key).await?;
+        } else {
+            let content_type = parts
+                .headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from);
+            let completed = Entry::Completed {
+                fingerprint: fingerprint.to_string(),
+                status: status.as_u16(),
+                content_type,
+                body: STANDARD.encode(&bytes),
+            };
+            self.cache.set(key, &completed, Some(self.ttl)).await?;
+        }
+        Ok(Response::from_parts(parts, Body::from(bytes)))
+    }
+}
+
+/// Replay or guard mutating requests carrying an `Idempotency-Key`.
+///
+/// A key reused for a different body or route is refused, and one whose
+/// first request is still running answers 409. If the cache is
+/// unreachable the request runs unguarded rather than failing.
+pub async fn enforce_idempotency(
+    State(idempotency): State<Arc<IdempotencyMiddleware>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mutating = matches!(*request.method(), Method::POST | Method::PUT | Method::PATCH | Method::DELETE);
+    let Some(idempotency_key) = request.headers().get(IDEMPOTENCY_KEY_HEADER).filter(|_| mutating).cloned() else {
+        return next.run(request).await;
+    };
+    let Some(subject) = request.extensions().get::<QuotaSubject>().cloned() else {
+        return next.run(request).await;
+    };
+    let Some(idempotency_key) = idempotency_key
+        .to_str()
+        .ok()
+        .map(str::trim)
+        .filter(|key| !key.is_empty() && key.len() <= 255)
+        .map(String::from)
+    else {
+        return AppError::Validation(vec!["Idempotency-Key must be 1 to 255 visible characters".to_string()])
+            .into_response();
+    };
+    let tenant = request.extensions().get::<TenantContext>().cloned().unwrap_or_default();
+
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_REQUEST_BYTES).await else {
+        return (StatusCode::PAYLOAD_TOO_LARGE, "Request body too large for an idempotent request").into_response();
+    };
+    let fingerprint = {
+        let mut hasher = Sha256::new();
+        hasher.update(parts.method.as_str().as_bytes());
+        hasher.update(parts.uri.to_string().as_bytes());
+        hasher.update(&bytes);
+        STANDARD.encode(hasher.finalize())
+    };
+    let key = format!(
+        "idempotency:{}:{}:{}:{}",
+        tenant.tenant_id,
+        subject.kind(),
+        subject.id(),
+        idempotency_key
+    );
+    let request = Request::from_parts(parts, Body::from(bytes));
+
+    match idempotency.claim(&key, &fingerprint).await {
+        Ok(Claim::Claimed) => {
+            let response = next.run(request).await;
+            match idempotency.finish(&key, &fingerprint, response).await {
+                Ok(response) => response,
+                Err(e) => e.into_response(),
+            }
+        }
+        Ok(Claim::InFlight) => {
+            AppError::Conflict("A request with this idempotency key is still in progress".to_string()).into_response()
+        }
+        Ok(Claim::Mismatch) => AppError::Validation(vec![
+            "Idempotency key was already used for a different request".to_string(),
+        ])
+        .into_response(),
+        Ok(Claim::Replay(response)) => {
+            let _ = idempotency.metrics.increment_counter("http.idempotent_replays").await;
+            response
+        }
+        Err(e) => {
+            tracing::warn!("Idempotency store unavailable, running request unguarded: {}", e);
+            next.run(request).await
+        }
+    }
+}
+
+fn replay(status: u16, content_type: Option<String>, body: &str) -> AppResult<Response> {
+    let invalid = |e: String| AppError::Cache(format!("Corrupt idempotency entry: {}", e));
+    let body = STANDARD.decode(body).map_err(|e| invalid(e.to_string()))?;
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = StatusCode::from_u16(status).map_err(|e| invalid(e.to_string()))?;
+    let headers = response.headers_mut();
+    if let Some(content_type) = content_type.and_then(|value| HeaderValue::from_str(&value).ok()) {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    Ok(response)
+}
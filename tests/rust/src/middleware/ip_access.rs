@@ -0,0 +1,115 @@
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::ClientAddress;
+use crate::config::{IpAccessConfig, IpAccessRules, IpBlock};
+use crate::models::{AppError, AppResult, AuthContext, UserRole};
+use crate::utils::{Logger, Metrics};
+
+/// Allow and deny lists of client address ranges, globally and per role.
+///
+/// Rules from the environment always apply; those in the rules file are
+/// re-read periodically, so ranges can be blocked without a restart.
+pub struct IpAccessMiddleware {
+    rules: RwLock<Arc<IpAccessRules>>,
+    config: IpAccessConfig,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl IpAccessMiddleware {
+    /// None when no list is configured; an unreadable rules file fails startup
+    pub async fn load(
+        config: &IpAccessConfig,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> AppResult<Option<Self>> {
+        if !config.is_enabled() {
+            return Ok(None);
+        }
+        let access = Self {
+            rules: RwLock::new(Arc::new(config.rules.clone())),
+            config: config.clone(),
+            metrics,
+            logger,
+        };
+        access.reload().await?;
+        Ok(Some(access))
+    }
+
+    /// Re-read the rules file; on failure the rules loaded before stay in force
+    pub async fn reload(&self) -> AppResult<()> {
+        let mut rules = self.config.rules.clone();
+        if let Some(path) = &self.config.rules_file {
+            let raw = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| AppError::Config(format!("Cannot read IP rules file {}: {}", path, e)))?;
+            let configured: IpAccessRules = serde_json::from_str(&raw)
+                .map_err(|e| AppError::Config(format!("Invalid IP rules file {}: {}", path, e)))?;
+            let errors = configured.validate();
+            if !errors.is_empty() {
+                return Err(AppError::Config(errors.join("; ")));
+            }
+            rules.merge(configured);
+        }
+        *self.rules.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(rules);
+        Ok(())
+    }
+
+    pub fn check(&self, address: Option<IpAddr>, role: Option<&UserRole>) -> Result<(), IpBlock> {
+        let rules = self.rules.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        rules.check(address.map(|address| address.to_canonical()), role)
+    }
+
+    /// Nothing to do unless rules come from a file
+    pub fn spawn_reload(self: Arc<Self>, shutdown: CancellationToken) -> Option<JoinHandle<()>> {
+        self.config.rules_file.as_ref()?;
+        let interval = self.config.reload_interval.max(Duration::from_secs(1));
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes at once, and the rules were just loaded
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.reload().await {
+                    self.logger.error(&format!("Failed to reload IP access rules: {}", e));
+                }
+            }
+        }))
+    }
+
+}
+
+/// Refuse requests from addresses the lists keep out.
+///
+/// Runs after authentication so a signed-in caller's role lists apply, and
+/// before rate limiting so refused requests use up no tokens. A request
+/// whose address cannot be told is refused whenever an allowlist applies.
+pub async fn enforce_ip_access(
+    State(access): State<Arc<IpAccessMiddleware>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let role = request.extensions().get::<AuthContext>().map(|context| context.role.clone());
+    let address = request.extensions().get::<ClientAddress>().map(|ClientAddress(address)| *address);
+    match access.check(address, role.as_ref()) {
+        Ok(()) => next.run(request).await,
+        Err(block) => {
+            let role = role.as_ref().map_or("anonymous", UserRole::as_str);
+            let _ = access
+                .metrics
+                .increment_labeled_counter("http.ip_blocked", &[("list", block.as_str()), ("role", role)])
+                .await;
+            AppError::Forbidden("Requests from this address are not allowed".to_string()).into_response()
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::sync::Arc;
+
+use crate::utils::{LatencyBudget, Metrics};
+
+/// Break each request's latency down by phase into a summary log line and
+/// per-route histograms.
+///
+/// Phases are timed from the spans repositories and services already emit
+/// (see `LatencyBudgetLayer`); time outside any phase is reported as
+/// `other`. Routes are labelled by their matched pattern, never the raw
+/// path, so ids in URLs do not create new series.
+pub async fn report_latency(
+    State(metrics): State<Arc<Metrics>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |path| path.as_str())
+        .to_string();
+    let method = request.method().clone();
+
+    let in_flight = metrics.track_request();
+    let budget = LatencyBudget::start();
+    let response = budget.clone().scope(next.run(request)).await;
+    let breakdown = budget.breakdown();
+    drop(in_flight);
+
+    let phase = |name: &str| {
+        breakdown
+            .phases
+            .iter()
+            .find(|(phase, _)| phase.as_str() == name)
+            .map_or(0, |(_, spent)| spent.as_millis() as u64)
+    };
+    tracing::info!(
+        method = %method,
+        route = %route,
+        status = response.status().as_u16(),
+        total_ms = breakdown.total.as_millis() as u64,
+        auth_ms = phase("auth"),
+        db_ms = phase("db"),
+        cache_ms = phase("cache"),
+        render_ms = phase("render"),
+        other_ms = breakdown.other.as_millis() as u64,
+        "Request latency budget"
+    );
+
+    let _ = metrics.increment_counter("http.requests").await;
+    if response.status().is_server_error() {
+        let _ = metrics.increment_counter("http.request.errors").await;
+    }
+    let _ = metrics
+        .record_labeled_duration("http.request.duration", &[("route", route.as_str())], breakdown.total)
+        .await;
+    for (phase, spent) in breakdown.phases {
+        let _ = metrics
+            .record_labeled_duration(
+                "http.request.phase",
+                &[("route", route.as_str()), ("phase", phase.as_str())],
+                spent,
+            )
+            .await;
+    }
+    let _ = metrics
+        .record_labeled_duration(
+            "http.request.phase",
+            &[("route", route.as_str()), ("phase", "other")],
+            breakdown.other,
+        )
+        .await;
+
+    response
+}
@@ -0,0 +1,33 @@
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::time::Instant;
+
+use crate::models::{AuthContext, TenantContext};
+
+/// Log one line per request with its outcome and caller.
+///
+/// Routes are logged by their matched pattern so ids in URLs stay out of
+/// the logs; the caller is only known when this runs after auth.
+pub async fn log_requests(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |path| path.as_str())
+        .to_string();
+    let tenant = request.extensions().get::<TenantContext>().map(|tenant| tenant.tenant_id.clone());
+    let user = request.extensions().get::<AuthContext>().map(|context| context.user_id);
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let status = response.status();
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+
+    if status.is_server_error() {
+        tracing::warn!(%method, route, status = status.as_u16(), elapsed_ms, ?tenant, ?user, "Request failed");
+    } else {
+        tracing::info!(%method, route, status = status.as_u16(), elapsed_ms, ?tenant, ?user, "Request served");
+    }
+    response
+}
@@ -0,0 +1,56 @@
+use axum::extract::{Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+use crate::config::RouteGroup;
+use crate::models::AuthContext;
+use crate::services::{MaintenanceMode, UserService};
+
+/// Health checks keep answering so load balancers and the status page see the mode
+const OPEN_PATHS: &[&str] = &["/status", "/version"];
+
+/// The mode and the users to tell admins by
+#[derive(Clone)]
+pub struct MaintenanceGate {
+    mode: Arc<MaintenanceMode>,
+    users: Arc<UserService>,
+}
+
+impl MaintenanceGate {
+    pub fn new(mode: Arc<MaintenanceMode>, users: Arc<UserService>) -> Self {
+        Self { mode, users }
+    }
+
+    /// Whether the caller currently holds the admin permission; the role in
+    /// the token may have been taken away since it was issued
+    async fn is_admin(&self, context: &AuthContext) -> bool {
+        match self.users.authenticated_user(context).await {
+            Ok(user) => self.users.policies().allows(&user, "admin", None),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Turn callers away while maintenance mode is on.
+///
+/// Runs after auth: admins get through untouched, and reads pass when the
+/// mode allows them. Sign-in stays open so an admin without a token can
+/// get one.
+pub async fn enforce_maintenance(State(gate): State<MaintenanceGate>, request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if RouteGroup::of_path(path) == RouteGroup::Auth || OPEN_PATHS.contains(&path) {
+        return next.run(request).await;
+    }
+
+    let is_read = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let Err(e) = gate.mode.check(is_read) else {
+        return next.run(request).await;
+    };
+    // Callers are only looked up while they would otherwise be turned away
+    match request.extensions().get::<AuthContext>() {
+        Some(context) if gate.is_admin(context).await => next.run(request).await,
+        _ => e.into_response(),
+    }
+}
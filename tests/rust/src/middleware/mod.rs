@@ -0,0 +1,37 @@
+pub mod auth;
+pub mod capture;
+pub mod client_address;
+pub mod cors;
+pub mod csrf;
+pub mod deadline;
+pub mod deprecation;
+pub mod idempotency;
+pub mod ip_access;
+pub mod latency;
+pub mod logging;
+pub mod maintenance;
+pub mod quota;
+pub mod rate_limit;
+pub mod sampling;
+pub mod signed_url;
+pub mod stack;
+pub mod tenant;
+
+pub use auth::{authenticate, AuthMiddleware, Claims, RefreshRedemption, API_KEY_HEADER};
+pub use capture::{capture_requests, RequestCapture};
+pub use client_address::{resolve_client_address, ClientAddress};
+pub use cors::{apply_cors, Cors};
+pub use csrf::{enforce_csrf, CsrfProtection, CsrfToken, CSRF_COOKIE, CSRF_HEADER};
+pub use deadline::propagate_deadline;
+pub use deprecation::flag_deprecations;
+pub use idempotency::{enforce_idempotency, IdempotencyMiddleware, IDEMPOTENCY_KEY_HEADER};
+pub use ip_access::{enforce_ip_access, IpAccessMiddleware};
+pub use latency::report_latency;
+pub use logging::log_requests;
+pub use maintenance::{enforce_maintenance, MaintenanceGate};
+pub use quota::enforce_quota;
+pub use rate_limit::{enforce_rate_limit, RateLimitMiddleware};
+pub use sampling::{sample_traces, TraceSampler};
+pub use signed_url::require_signed_url;
+pub use stack::MiddlewareStack;
+pub use tenant::resolve_tenant;
@@ -0,0 +1,40 @@
+use axum::extract::{Request, State};
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+use crate::models::AppError;
+use crate::services::{QuotaDecision, QuotaService, QuotaSubject};
+
+/// Meter authenticated calls and reject them once the period's quota is spent.
+///
+/// Requests without a `QuotaSubject` extension (unauthenticated routes) are
+/// not metered. If the counter store is unreachable the call is let through
+/// rather than failing the request.
+pub async fn enforce_quota(
+    State(quota): State<Arc<QuotaService>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(subject) = request.extensions().get::<QuotaSubject>().cloned() else {
+        return next.run(request).await;
+    };
+
+    match quota.record_call(&subject).await {
+        Ok(QuotaDecision::Allowed { used, limit }) => {
+            let mut response = next.run(request).await;
+            let headers = response.headers_mut();
+            headers.insert("x-quota-limit", HeaderValue::from(limit));
+            headers.insert("x-quota-remaining", HeaderValue::from(limit.saturating_sub(used)));
+            response
+        }
+        Ok(QuotaDecision::Exceeded { limit, resets_at }) => {
+            AppError::QuotaExceeded { limit, resets_at }.into_response()
+        }
+        Err(e) => {
+            tracing::warn!("Quota metering unavailable, allowing request: {}", e);
+            next.run(request).await
+        }
+    }
+}
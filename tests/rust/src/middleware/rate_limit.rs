@@ -0,0 +1,122 @@
+use axum::extract::{Request, State};
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+use super::ClientAddress;
+use crate::config::{RateLimit, RateLimitConfig, RouteGroup};
+use crate::models::{AppError, AppResult, TenantContext};
+use crate::services::{CacheService, QuotaSubject, TenantLimitService, TokenBucket};
+
+/// Token buckets per client address and per signed-in caller, shared by
+/// every instance through the cache.
+///
+/// Each request takes a token from its address's bucket for the route group
+/// and, when authenticated, from its user's or API key's bucket as well.
+/// With tenant limits, every request also takes one from its tenant's
+/// bucket, whatever the route group.
+pub struct RateLimitMiddleware {
+    cache: Arc<CacheService>,
+    config: RateLimitConfig,
+    tenants: Option<Arc<TenantLimitService>>,
+}
+
+impl RateLimitMiddleware {
+    /// None when rate limiting is turned off
+    pub fn from_config(cache: Arc<CacheService>, config: &RateLimitConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            cache,
+            config: config.clone(),
+            tenants: None,
+        })
+    }
+
+    /// Hold tenants to their request rates as well; skipped when no tenant has one
+    pub fn with_tenant_limits(mut self, tenants: Arc<TenantLimitService>) -> Self {
+        self.tenants = tenants.limits_requests().then_some(tenants);
+        self
+    }
+
+    /// The bucket with the fewest tokens left, or `AppError::RateLimited` when one is empty
+    async fn check(
+        &self,
+        group: RouteGroup,
+        address: Option<&str>,
+        subject: Option<&QuotaSubject>,
+        tenant: Option<&TenantContext>,
+    ) -> AppResult<Option<(RateLimit, TokenBucket)>> {
+        let mut buckets = Vec::with_capacity(2);
+        if let (Some(address), Some(limit)) = (address, self.config.per_ip.get(&group)) {
+            buckets.push((format!("ratelimit:{}:ip:{}", group.as_str(), address), *limit));
+        }
+        if let (Some(subject), Some(limit)) = (subject, self.config.per_subject.get(&group)) {
+            let key = format!("ratelimit:{}:{}:{}", group.as_str(), subject.kind(), subject.id());
+            buckets.push((key, *limit));
+        }
+
+        let mut tightest: Option<(RateLimit, TokenBucket)> = None;
+        for (key, limit) in buckets {
+            let bucket = self.cache.take_token(&key, limit.burst, limit.refill_rate()).await?;
+            if !bucket.taken {
+                return Err(AppError::RateLimited {
+                    retry_after: bucket.retry_after,
+                });
+            }
+            let tighter = match tightest {
+                Some((_, current)) => bucket.remaining < current.remaining,
+                None => true,
+            };
+            if tighter {
+                tightest = Some((limit, bucket));
+            }
+        }
+        if let (Some(tenants), Some(tenant)) = (&self.tenants, tenant) {
+            if let Some((limit, bucket)) = tenants.take_request(tenant).await? {
+                if tightest.map_or(true, |(_, current)| bucket.remaining < current.remaining) {
+                    tightest = Some((limit, bucket));
+                }
+            }
+        }
+        Ok(tightest)
+    }
+}
+
+/// Reject requests once their route group's bucket is empty.
+///
+/// Runs after authentication so callers are limited by who they are as well
+/// as where they connect from. If the counter store is unreachable the
+/// request is let through rather than failed.
+pub async fn enforce_rate_limit(
+    State(limiter): State<Arc<RateLimitMiddleware>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let group = RouteGroup::of_path(request.uri().path());
+    let address = request
+        .extensions()
+        .get::<ClientAddress>()
+        .map(|ClientAddress(address)| address.to_string());
+    let subject = request.extensions().get::<QuotaSubject>().cloned();
+    let tenant = request.extensions().get::<TenantContext>().cloned();
+
+    match limiter
+        .check(group, address.as_deref(), subject.as_ref(), tenant.as_ref())
+        .await
+    {
+        Ok(tightest) => {
+            let mut response = next.run(request).await;
+            if let Some((limit, bucket)) = tightest {
+                let headers = response.headers_mut();
+                headers.insert("x-ratelimit-limit", HeaderValue::from(limit.burst));
+                headers.insert("x-ratelimit-remaining", HeaderValue::from(bucket.remaining));
+            }
+            response
+        }
+        Err(e @ AppError::RateLimited { .. }) => e.into_response(),
+        Err(e) => {
+            tracing::warn!("Rate limiting unavailable, allowing request: {}", e);
+            next.run(request).await
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use rand::Rng;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::field::Empty;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::config::TraceSamplingConfig;
+use crate::models::AuthContext;
+use crate::utils::{Metrics, TraceDecision};
+
+/// Decides which requests' spans are kept.
+///
+/// A request is sampled when one of `force_users` sends the force header,
+/// otherwise at the rate of the longest route prefix matching its path, or
+/// the default rate. The decision is made before the request's span is
+/// created, so an unsampled request costs the exporter nothing.
+pub struct TraceSampler {
+    config: TraceSamplingConfig,
+    metrics: Arc<Metrics>,
+}
+
+impl TraceSampler {
+    pub fn new(config: &TraceSamplingConfig, metrics: Arc<Metrics>) -> Self {
+        Self {
+            config: config.clone(),
+            metrics,
+        }
+    }
+
+    /// Whether to sample the request, and why when it is
+    pub fn decide(&self, path: &str, headers: &HeaderMap, user: Option<Uuid>) -> (bool, &'static str) {
+        if self.forced(headers, user) {
+            return (true, "forced");
+        }
+        let (rate, reason) = self
+            .config
+            .routes
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .map_or((self.config.default_rate, "default"), |(_, rate)| (*rate, "route"));
+        (rate > 0.0 && rand::thread_rng().gen::<f64>() < rate, reason)
+    }
+
+    /// The force header only counts from users listed for it, so clients cannot raise trace volume at will
+    fn forced(&self, headers: &HeaderMap, user: Option<Uuid>) -> bool {
+        user.is_some_and(|user| self.config.force_users.contains(&user))
+            && headers.contains_key(self.config.force_header.as_str())
+    }
+
+    async fn sampled(&self, reason: &str) {
+        let _ = self
+            .metrics
+            .increment_labeled_counter("tracing.sampled", &[("reason", reason)])
+            .await;
+    }
+}
+
+/// Wrap the request in an `http.request` span kept or dropped as sampled.
+///
+/// Runs after auth, so forced sampling knows the caller. A request that was
+/// not sampled but fails with a server error is still traced when
+/// `sample_errors` is set: its request span is recorded once the response
+/// is known, though the spans made while serving it are already gone.
+pub async fn sample_traces(State(sampler): State<Arc<TraceSampler>>, request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |path| path.as_str())
+        .to_string();
+    let user = request.extensions().get::<AuthContext>().map(|context| context.user_id);
+    let method = request.method().clone();
+    let (sampled, reason) = sampler.decide(request.uri().path(), request.headers(), user);
+    if sampled {
+        sampler.sampled(reason).await;
+    }
+
+    let decision = TraceDecision::new(sampled);
+    decision
+        .clone()
+        .scope(async move {
+            let started = Instant::now();
+            let span = tracing::info_span!("http.request", %method, route, status = Empty, sampled = reason);
+            let response = next.run(request).instrument(span.clone()).await;
+            let status = response.status();
+            span.record("status", status.as_u16());
+
+            if !decision.is_sampled() && status.is_server_error() && sampler.config.sample_errors {
+                decision.sample();
+                sampler.sampled("error").await;
+                let elapsed_ms = started.elapsed().as_millis() as u64;
+                let span = tracing::warn_span!(
+                    "http.request",
+                    %method,
+                    route,
+                    status = status.as_u16(),
+                    elapsed_ms,
+                    sampled = "error"
+                );
+                drop(span.enter());
+            }
+            response
+        })
+        .await
+}
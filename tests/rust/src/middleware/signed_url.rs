@@ -0,0 +1,29 @@
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+use crate::utils::UrlSigner;
+
+/// Only let through requests whose URL was signed by `UrlSigner` and has not expired.
+///
+/// Meant for `route_layer` on link targets that act without a session, so
+/// the link itself is the credential.
+pub async fn require_signed_url(
+    State(signer): State<Arc<UrlSigner>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map_or_else(|| request.uri().path().to_string(), |pq| pq.as_str().to_string());
+
+    match signer.verify(&path_and_query) {
+        Ok(_) => next.run(request).await,
+        Err(e) => {
+            tracing::warn!("Rejected signed link {}: {}", request.uri().path(), e);
+            e.into_response()
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use axum::extract::Request;
+use axum::middleware::{from_fn, from_fn_with_state};
+use axum::Router;
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+
+use super::{
+    apply_cors, authenticate, capture_requests, enforce_csrf, enforce_idempotency, enforce_ip_access,
+    enforce_maintenance, enforce_quota, enforce_rate_limit, flag_deprecations, log_requests, propagate_deadline,
+    report_latency, resolve_client_address, resolve_tenant, sample_traces, AuthMiddleware, Cors, CsrfProtection,
+    IdempotencyMiddleware, IpAccessMiddleware, MaintenanceGate, RateLimitMiddleware, RequestCapture, TraceSampler,
+};
+use crate::config::{MiddlewareConfig, MiddlewareLayer, ProxyConfig, RouteGroup};
+use crate::services::QuotaService;
+use crate::utils::Metrics;
+
+/// Builds the HTTP middleware pipeline from `MiddlewareConfig`.
+///
+/// Components are handed in with the `with_*` methods; a layer listed in
+/// the configuration whose component was not given is skipped, as auth is
+/// when no signing keys are configured. Each route group gets its own copy
+/// of the routes wrapped in its own stack, and requests are dispatched to
+/// their group's copy by path. Every stack first resolves the client's
+/// address, whichever layers it lists.
+pub struct MiddlewareStack {
+    config: MiddlewareConfig,
+    request_timeout: Duration,
+    proxy: Arc<ProxyConfig>,
+    metrics: Arc<Metrics>,
+    cors: Option<Arc<Cors>>,
+    sampler: Arc<TraceSampler>,
+    auth: Option<Arc<AuthMiddleware>>,
+    ip_access: Option<Arc<IpAccessMiddleware>>,
+    rate_limits: Option<Arc<RateLimitMiddleware>>,
+    csrf: Option<Arc<CsrfProtection>>,
+    idempotency: Option<Arc<IdempotencyMiddleware>>,
+    quota: Option<Arc<QuotaService>>,
+    capture: Option<Arc<RequestCapture>>,
+    maintenance: Option<MaintenanceGate>,
+}
+
+impl MiddlewareStack {
+    pub fn new(config: MiddlewareConfig, request_timeout: Duration, metrics: Arc<Metrics>) -> Self {
+        let cors = Cors::from_config(&config.cors).map(Arc::new);
+        let sampler = Arc::new(TraceSampler::new(&config.sampling, metrics.clone()));
+        Self {
+            config,
+            request_timeout,
+            proxy: Arc::new(ProxyConfig::default()),
+            metrics,
+            cors,
+            sampler,
+            auth: None,
+            ip_access: None,
+            rate_limits: None,
+            csrf: None,
+            idempotency: None,
+            quota: None,
+            capture: None,
+            maintenance: None,
+        }
+    }
+
+    /// Read client addresses through these proxies; without it the peer is always the client
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Arc::new(proxy);
+        self
+    }
+
+    pub fn with_auth(mut self, auth: Arc<AuthMiddleware>) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    pub fn with_ip_access(mut self, ip_access: Arc<IpAccessMiddleware>) -> Self {
+        self.ip_access = Some(ip_access);
+        self
+    }
+
+    pub fn with_rate_limits(mut self, rate_limits: Arc<RateLimitMiddleware>) -> Self {
+        self.rate_limits = Some(rate_limits);
+        self
+    }
+
+    pub fn with_csrf(mut self, csrf: Arc<CsrfProtection>) -> Self {
+        self.csrf = Some(csrf);
+        self
+    }
+
+    pub fn with_idempotency(mut self, idempotency: Arc<IdempotencyMiddleware>) -> Self {
+        self.idempotency = Some(idempotency);
+        self
+    }
+
+    pub fn with_quota(mut self, quota: Arc<QuotaService>) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    pub fn with_capture(mut self, capture: Arc<RequestCapture>) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    pub fn with_maintenance(mut self, maintenance: MaintenanceGate) -> Self {
+        self.maintenance = Some(maintenance);
+        self
+    }
+
+    /// Wrap `routes` in each group's stack and route requests to their group by path
+    pub fn apply(&self, routes: Router) -> Router {
+        let auth = self.layered(routes.clone(), self.config.stack(RouteGroup::Auth));
+        let admin = self.layered(routes.clone(), self.config.stack(RouteGroup::Admin));
+        let api = self.layered(routes, self.config.stack(RouteGroup::Api));
+
+        Router::new().fallback_service(tower::service_fn(move |request: Request| {
+            let group = match RouteGroup::of_path(request.uri().path()) {
+                RouteGroup::Auth => auth.clone(),
+                RouteGroup::Admin => admin.clone(),
+                RouteGroup::Api => api.clone(),
+            };
+            async move { group.oneshot(request).await }
+        }))
+    }
+
+    /// Layers are listed outermost first, so they are added innermost first
+    fn layered(&self, mut router: Router, layers: &[MiddlewareLayer]) -> Router {
+        for layer in layers.iter().rev() {
+            router = match layer {
+                MiddlewareLayer::Deadline => router.layer(from_fn_with_state(self.request_timeout, propagate_deadline)),
+                MiddlewareLayer::Cors => match &self.cors {
+                    Some(cors) => router.layer(from_fn_with_state(cors.clone(), apply_cors)),
+                    None => router,
+                },
+                MiddlewareLayer::Latency => router.layer(from_fn_with_state(self.metrics.clone(), report_latency)),
+                MiddlewareLayer::Logging => router.layer(from_fn(log_requests)),
+                MiddlewareLayer::Tenant => router.layer(from_fn(resolve_tenant)),
+                MiddlewareLayer::Auth => match &self.auth {
+                    Some(auth) => router.layer(from_fn_with_state(auth.clone(), authenticate)),
+                    None => router,
+                },
+                MiddlewareLayer::IpAccess => match &self.ip_access {
+                    Some(access) => router.layer(from_fn_with_state(access.clone(), enforce_ip_access)),
+                    None => router,
+                },
+                MiddlewareLayer::RateLimit => match &self.rate_limits {
+                    Some(limiter) => router.layer(from_fn_with_state(limiter.clone(), enforce_rate_limit)),
+                    None => router,
+                },
+                MiddlewareLayer::Csrf => match &self.csrf {
+                    Some(csrf) => router.layer(from_fn_with_state(csrf.clone(), enforce_csrf)),
+                    None => router,
+                },
+                MiddlewareLayer::Idempotency => match &self.idempotency {
+                    Some(idempotency) => router.layer(from_fn_with_state(idempotency.clone(), enforce_idempotency)),
+                    None => router,
+                },
+                MiddlewareLayer::Quota => match &self.quota {
+                    Some(quota) => router.layer(from_fn_with_state(quota.clone(), enforce_quota)),
+                    None => router,
+                },
+                MiddlewareLayer::Deprecation => {
+                    router.layer(from_fn_with_state(self.metrics.clone(), flag_deprecations))
+                }
+                MiddlewareLayer::Sampling => router.layer(from_fn_with_state(self.sampler.clone(), sample_traces)),
+                MiddlewareLayer::Capture => match &self.capture {
+                    Some(capture) => router.layer(from_fn_with_state(capture.clone(), capture_requests)),
+                    None => router,
+                },
+                MiddlewareLayer::Maintenance => match &self.maintenance {
+                    Some(maintenance) => router.layer(from_fn_with_state(maintenance.clone(), enforce_maintenance)),
+                    None => router,
+                },
+            };
+        }
+        router.layer(from_fn_with_state(self.proxy.clone(), resolve_client_address))
+    }
+}
@@ -0,0 +1,33 @@
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::models::{AppError, TenantContext};
+use crate::utils::TenantScope;
+
+const TENANT_HEADER: &str = "x-tenant-id";
+
+/// Attach the request's `TenantContext` as an extension, and install it as
+/// the `TenantScope` that confines user listings to the tenant.
+///
+/// The tenant comes from the `X-Tenant-Id` header; requests without one act
+/// on the default tenant. A malformed id is rejected rather than silently
+/// falling back, so a misconfigured white-label client is noticed.
+pub async fn resolve_tenant(mut request: Request, next: Next) -> Response {
+    let tenant = match request.headers().get(TENANT_HEADER) {
+        None => TenantContext::default(),
+        Some(value) => {
+            let parsed = value
+                .to_str()
+                .map_err(|_| AppError::Validation(vec!["Invalid tenant header".to_string()]))
+                .and_then(|id| TenantContext::new(id.trim()));
+            match parsed {
+                Ok(tenant) => tenant,
+                Err(e) => return e.into_response(),
+            }
+        }
+    };
+
+    request.extensions_mut().insert(tenant.clone());
+    TenantScope::scope(tenant, next.run(request)).await
+}
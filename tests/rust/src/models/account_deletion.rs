@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::group::Group;
+use super::notification::Notification;
+use super::user::User;
+
+/// A user's request to have their account erased
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AccountDeletion {
+    pub user_id: Uuid,
+    pub requested_at: DateTime<Utc>,
+    pub erase_after: DateTime<Utc>,
+    /// Status to restore if the deletion is cancelled
+    pub previous_status: String,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub erased_at: Option<DateTime<Utc>>,
+}
+
+impl AccountDeletion {
+    pub fn is_pending(&self) -> bool {
+        self.cancelled_at.is_none() && self.erased_at.is_none()
+    }
+}
+
+/// Everything stored about a user, for portability requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDataExport {
+    #[serde(serialize_with = "super::user::serialize_without_secrets")]
+    pub user: User,
+    pub groups: Vec<Group>,
+    pub notifications: Vec<Notification>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Result of requesting account deletion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountDeletionRequest {
+    pub deletion: AccountDeletion,
+    pub export: UserDataExport,
+}
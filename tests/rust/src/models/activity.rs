@@ -0,0 +1,185 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::notification::Notification;
+
+/// Something that happened to an account, as kept in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Login,
+    ProfileUpdated,
+    RoleChanged,
+    StatusChanged,
+    PasswordChanged,
+    EmailVerified,
+    SessionsRevoked,
+    BulkOperationApplied,
+    BulkOperationUndone,
+    AccountUnlocked,
+    AccountRestored,
+    DeviceTrusted,
+    DeviceUntrusted,
+    OrganizationRoleChanged,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Login => "login",
+            AuditAction::ProfileUpdated => "profile_updated",
+            AuditAction::RoleChanged => "role_changed",
+            AuditAction::StatusChanged => "status_changed",
+            AuditAction::PasswordChanged => "password_changed",
+            AuditAction::EmailVerified => "email_verified",
+            AuditAction::SessionsRevoked => "sessions_revoked",
+            AuditAction::BulkOperationApplied => "bulk_operation_applied",
+            AuditAction::BulkOperationUndone => "bulk_operation_undone",
+            AuditAction::AccountUnlocked => "account_unlocked",
+            AuditAction::AccountRestored => "account_restored",
+            AuditAction::DeviceTrusted => "device_trusted",
+            AuditAction::DeviceUntrusted => "device_untrusted",
+            AuditAction::OrganizationRoleChanged => "organization_role_changed",
+        }
+    }
+
+    fn summary(&self) -> &'static str {
+        match self {
+            AuditAction::Login => "Signed in",
+            AuditAction::ProfileUpdated => "Profile updated",
+            AuditAction::RoleChanged => "Role changed",
+            AuditAction::StatusChanged => "Status changed",
+            AuditAction::PasswordChanged => "Password changed",
+            AuditAction::EmailVerified => "Email verified",
+            AuditAction::SessionsRevoked => "Signed out everywhere",
+            AuditAction::BulkOperationApplied => "Changed by a bulk operation",
+            AuditAction::BulkOperationUndone => "Bulk operation undone",
+            AuditAction::AccountUnlocked => "Account unlocked",
+            AuditAction::AccountRestored => "Account restored",
+            AuditAction::DeviceTrusted => "Device trusted",
+            AuditAction::DeviceUntrusted => "Device no longer trusted",
+            AuditAction::OrganizationRoleChanged => "Organization role changed",
+        }
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "login" => Ok(AuditAction::Login),
+            "profile_updated" => Ok(AuditAction::ProfileUpdated),
+            "role_changed" => Ok(AuditAction::RoleChanged),
+            "status_changed" => Ok(AuditAction::StatusChanged),
+            "password_changed" => Ok(AuditAction::PasswordChanged),
+            "email_verified" => Ok(AuditAction::EmailVerified),
+            "sessions_revoked" => Ok(AuditAction::SessionsRevoked),
+            "bulk_operation_applied" => Ok(AuditAction::BulkOperationApplied),
+            "bulk_operation_undone" => Ok(AuditAction::BulkOperationUndone),
+            "account_unlocked" => Ok(AuditAction::AccountUnlocked),
+            "account_restored" => Ok(AuditAction::AccountRestored),
+            "device_trusted" => Ok(AuditAction::DeviceTrusted),
+            "device_untrusted" => Ok(AuditAction::DeviceUntrusted),
+            "organization_role_changed" => Ok(AuditAction::OrganizationRoleChanged),
+            other => Err(format!("Unknown audit action: {}", other)),
+        }
+    }
+}
+
+/// One entry of a user's audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub user_id: Uuid,
+    /// Who made the change; None when the system recorded it on its own
+    pub actor_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEvent {
+    pub fn new(user_id: Uuid, actor_id: Option<Uuid>, action: AuditAction, details: serde_json::Value) -> Self {
+        Self {
+            user_id,
+            actor_id,
+            action,
+            details,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Made by someone other than the account owner
+    pub fn by_admin(&self) -> bool {
+        self.actor_id.is_some_and(|actor| actor != self.user_id)
+    }
+}
+
+/// Broad category of a timeline entry, for filtering in support tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Login,
+    ProfileChange,
+    Notification,
+    AdminAction,
+}
+
+/// One event in a user's activity timeline
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEntry {
+    pub at: DateTime<Utc>,
+    pub kind: ActivityKind,
+    /// Audit action or notification type
+    pub action: String,
+    pub actor_id: Option<Uuid>,
+    pub summary: String,
+    pub details: serde_json::Value,
+}
+
+impl From<AuditEvent> for ActivityEntry {
+    fn from(event: AuditEvent) -> Self {
+        let kind = if event.by_admin() {
+            ActivityKind::AdminAction
+        } else if event.action == AuditAction::Login {
+            ActivityKind::Login
+        } else {
+            ActivityKind::ProfileChange
+        };
+        Self {
+            at: event.created_at,
+            kind,
+            action: event.action.as_str().to_string(),
+            actor_id: event.actor_id,
+            summary: event.action.summary().to_string(),
+            details: event.details,
+        }
+    }
+}
+
+impl From<Notification> for ActivityEntry {
+    fn from(notification: Notification) -> Self {
+        Self {
+            at: notification.created_at,
+            kind: ActivityKind::Notification,
+            action: notification.notification_type.as_str().to_string(),
+            actor_id: None,
+            summary: notification.title,
+            details: serde_json::json!({
+                "notification_id": notification.id,
+                "channel": notification.channel.as_str(),
+                "status": notification.status.as_str(),
+            }),
+        }
+    }
+}
+
+/// A page of a user's timeline, newest first
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityPage {
+    pub entries: Vec<ActivityEntry>,
+    /// Pass as `before` to fetch the next, older page; None on the last page
+    pub next_before: Option<DateTime<Utc>>,
+}
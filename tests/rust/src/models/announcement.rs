@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::user::UserRole;
+use crate::utils::new_id;
+
+const MAX_TITLE_LENGTH: usize = 120;
+const MAX_MESSAGE_LENGTH: usize = 2000;
+
+/// How prominently clients render an announcement
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnouncementSeverity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+impl AnnouncementSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnouncementSeverity::Info => "info",
+            AnnouncementSeverity::Warning => "warning",
+            AnnouncementSeverity::Critical => "critical",
+        }
+    }
+}
+
+impl FromStr for AnnouncementSeverity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "info" => Ok(AnnouncementSeverity::Info),
+            "warning" => Ok(AnnouncementSeverity::Warning),
+            "critical" => Ok(AnnouncementSeverity::Critical),
+            other => Err(format!("Unknown announcement severity: {}", other)),
+        }
+    }
+}
+
+/// Who sees an announcement; an empty audience addresses everyone
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnnouncementAudience {
+    /// Roles that see it; empty means every role
+    #[serde(default)]
+    pub roles: Vec<UserRole>,
+    /// Group whose members see it; None means no group restriction
+    #[serde(default)]
+    pub group_id: Option<Uuid>,
+}
+
+impl AnnouncementAudience {
+    /// Both restrictions apply when both are set
+    pub fn includes(&self, role: &UserRole, group_ids: &[Uuid]) -> bool {
+        let role_matches = self.roles.is_empty() || self.roles.contains(role);
+        let group_matches = self.group_id.map_or(true, |group_id| group_ids.contains(&group_id));
+        role_matches && group_matches
+    }
+}
+
+/// A banner or notice shown in-app to its audience while it is live
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Announcement {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub title: String,
+    pub message: String,
+    pub severity: AnnouncementSeverity,
+    pub audience: AnnouncementAudience,
+    pub starts_at: DateTime<Utc>,
+    /// None keeps it live until withdrawn
+    pub ends_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Announcement {
+    pub fn new(tenant_id: String, request: CreateAnnouncementRequest, created_by: Option<Uuid>) -> Self {
+        let now = Utc::now();
+
+        Self {
+            id: new_id(),
+            tenant_id,
+            title: request.title.trim().to_string(),
+            message: request.message.trim().to_string(),
+            severity: request.severity,
+            audience: request.audience,
+            starts_at: request.starts_at.unwrap_or(now),
+            ends_at: request.ends_at,
+            created_by,
+            created_at: now,
+        }
+    }
+
+    pub fn is_live(&self, at: DateTime<Utc>) -> bool {
+        self.starts_at <= at && self.ends_at.map_or(true, |ends_at| at < ends_at)
+    }
+}
+
+/// Request struct for publishing an announcement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAnnouncementRequest {
+    pub title: String,
+    pub message: String,
+    #[serde(default)]
+    pub severity: AnnouncementSeverity,
+    #[serde(default)]
+    pub audience: AnnouncementAudience,
+    /// Defaults to immediately
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+impl CreateAnnouncementRequest {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        let title = self.title.trim();
+        if title.is_empty() {
+            errors.push("Announcement title is required".to_string());
+        } else if title.len() > MAX_TITLE_LENGTH {
+            errors.push(format!("Announcement title must be at most {} characters", MAX_TITLE_LENGTH));
+        }
+
+        let message = self.message.trim();
+        if message.is_empty() {
+            errors.push("Announcement message is required".to_string());
+        } else if message.len() > MAX_MESSAGE_LENGTH {
+            errors.push(format!("Announcement message must be at most {} characters", MAX_MESSAGE_LENGTH));
+        }
+
+        if let Some(ends_at) = self.ends_at {
+            if ends_at <= self.starts_at.unwrap_or_else(Utc::now) {
+                errors.push("Announcement must end after it starts".to_string());
+            }
+        }
+
+        errors
+    }
+}
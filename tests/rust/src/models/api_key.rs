@@ -0,0 +1,147 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::error::{AppError, AppResult};
+use crate::utils::new_id;
+
+const MAX_NAME_LENGTH: usize = 100;
+
+/// What an API key may do; each scope includes the ones before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyScope {
+    Read,
+    Write,
+    Admin,
+}
+
+impl ApiKeyScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::Read => "read",
+            ApiKeyScope::Write => "write",
+            ApiKeyScope::Admin => "admin",
+        }
+    }
+
+    pub fn allows(&self, required: ApiKeyScope) -> bool {
+        *self >= required
+    }
+}
+
+impl FromStr for ApiKeyScope {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "read" => Ok(ApiKeyScope::Read),
+            "write" => Ok(ApiKeyScope::Write),
+            "admin" => Ok(ApiKeyScope::Admin),
+            other => Err(format!("Unknown API key scope: {}", other)),
+        }
+    }
+}
+
+/// A credential for service-to-service callers.
+///
+/// Only a hash of the key is stored; the key itself is shown once, when it
+/// is created or rotated. `prefix` is its first few characters, kept so
+/// operators can tell keys apart in listings and logs.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub name: String,
+    pub prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scope: ApiKeyScope,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Key this one replaced, if it was issued by rotation
+    pub rotated_from: Option<Uuid>,
+}
+
+impl ApiKey {
+    pub fn new(
+        tenant_id: String,
+        name: String,
+        scope: ApiKeyScope,
+        prefix: String,
+        key_hash: String,
+        created_by: Uuid,
+    ) -> Self {
+        Self {
+            id: new_id(),
+            tenant_id,
+            name,
+            prefix,
+            key_hash,
+            scope,
+            created_by,
+            created_at: Utc::now(),
+            last_used_at: None,
+            expires_at: None,
+            revoked_at: None,
+            rotated_from: None,
+        }
+    }
+
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.map_or(true, |expires_at| expires_at > now)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scope: ApiKeyScope,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl CreateApiKeyRequest {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let name = self.name.trim();
+        if name.is_empty() {
+            errors.push("Name is required".to_string());
+        } else if name.chars().count() > MAX_NAME_LENGTH {
+            errors.push(format!("Name must be at most {} characters", MAX_NAME_LENGTH));
+        }
+        if self.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+            errors.push("Expiry must be in the future".to_string());
+        }
+        errors
+    }
+}
+
+/// A key as returned when it is created or rotated; the only time `key` is visible
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+/// The API key a request was authenticated with
+#[derive(Debug, Clone)]
+pub struct ApiKeyContext {
+    pub key_id: Uuid,
+    pub tenant_id: String,
+    pub scope: ApiKeyScope,
+}
+
+impl ApiKeyContext {
+    pub fn require(&self, scope: ApiKeyScope) -> AppResult<()> {
+        if !self.scope.allows(scope) {
+            return Err(AppError::Forbidden(format!("API key lacks the {} scope", scope.as_str())));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::user::User;
+use crate::utils::new_id;
+
+const MAX_AUDIT_PAGE: i64 = 500;
+
+/// A security-sensitive action kept in the compliance audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditLogAction {
+    UserCreated,
+    RoleChanged,
+    StatusChanged,
+    UserDeleted,
+    UserRestored,
+    LoginFailed,
+    RoleRequested,
+    RoleRequestDenied,
+    RoleElevated,
+    RoleElevationEnded,
+}
+
+impl AuditLogAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditLogAction::UserCreated => "user_created",
+            AuditLogAction::RoleChanged => "role_changed",
+            AuditLogAction::StatusChanged => "status_changed",
+            AuditLogAction::UserDeleted => "user_deleted",
+            AuditLogAction::UserRestored => "user_restored",
+            AuditLogAction::LoginFailed => "login_failed",
+            AuditLogAction::RoleRequested => "role_requested",
+            AuditLogAction::RoleRequestDenied => "role_request_denied",
+            AuditLogAction::RoleElevated => "role_elevated",
+            AuditLogAction::RoleElevationEnded => "role_elevation_ended",
+        }
+    }
+}
+
+impl FromStr for AuditLogAction {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "user_created" => Ok(AuditLogAction::UserCreated),
+            "role_changed" => Ok(AuditLogAction::RoleChanged),
+            "status_changed" => Ok(AuditLogAction::StatusChanged),
+            "user_deleted" => Ok(AuditLogAction::UserDeleted),
+            "user_restored" => Ok(AuditLogAction::UserRestored),
+            "login_failed" => Ok(AuditLogAction::LoginFailed),
+            "role_requested" => Ok(AuditLogAction::RoleRequested),
+            "role_request_denied" => Ok(AuditLogAction::RoleRequestDenied),
+            "role_elevated" => Ok(AuditLogAction::RoleElevated),
+            "role_elevation_ended" => Ok(AuditLogAction::RoleElevationEnded),
+            other => Err(format!("Unknown audit log action: {}", other)),
+        }
+    }
+}
+
+/// Who did what to whom, with the target's state before and after.
+///
+/// Unlike a user's own `AuditEvent` timeline, entries are not tied to the
+/// account's lifetime: they outlive the users they mention, so a deletion
+/// stays on record after the account is gone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLog {
+    pub id: Uuid,
+    /// Who acted; None when the system acted on its own or the caller was anonymous
+    pub actor_id: Option<Uuid>,
+    pub action: AuditLogAction,
+    pub target_id: Option<Uuid>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    /// Anything else worth keeping, such as whether a failed sign-in locked the account
+    pub context: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditLog {
+    pub fn new(actor_id: Option<Uuid>, action: AuditLogAction, target_id: Option<Uuid>) -> Self {
+        Self {
+            id: new_id(),
+            actor_id,
+            action,
+            target_id,
+            before: None,
+            after: None,
+            context: serde_json::json!({}),
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_before(mut self, user: &User) -> Self {
+        self.before = Some(snapshot(user));
+        self
+    }
+
+    pub fn with_after(mut self, user: &User) -> Self {
+        self.after = Some(snapshot(user));
+        self
+    }
+
+    pub fn with_context(mut self, context: serde_json::Value) -> Self {
+        self.context = context;
+        self
+    }
+}
+
+/// The parts of a user compliance reviews look at; never names, emails or other PII
+fn snapshot(user: &User) -> serde_json::Value {
+    serde_json::json!({
+        "id": user.id,
+        "role": user.role.as_str(),
+        "status": user.status.as_str(),
+        "email_verified": user.email_verified,
+        "failed_login_attempts": user.failed_login_attempts,
+        "locked_until": user.locked_until,
+        "region": user.region,
+        "deleted_at": user.deleted_at,
+    })
+}
+
+/// Filtering options for audit log reviews
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditLogFilters {
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub action: Option<AuditLogAction>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl AuditLogFilters {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                errors.push("from must not be after to".to_string());
+            }
+        }
+        if let Some(limit) = self.limit {
+            if !(1..=MAX_AUDIT_PAGE).contains(&limit) {
+                errors.push(format!("limit must be between 1 and {}", MAX_AUDIT_PAGE));
+            }
+        }
+        if self.offset.is_some_and(|offset| offset < 0) {
+            errors.push("offset must not be negative".to_string());
+        }
+        errors
+    }
+}
+
+/// A page of matching audit log entries, newest first
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditLog>,
+    /// Entries matching the filters across all pages
+    pub total: i64,
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::user::UserRole;
+
+/// What a token may be used for; refresh tokens are never accepted as access tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {
+    Access,
+    Refresh,
+}
+
+/// The authenticated caller of a request, taken from a verified access token
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub user_id: Uuid,
+    /// Role when the token was issued; re-read the user where a change must take effect at once
+    pub role: UserRole,
+    pub session_id: Option<Uuid>,
+    pub token_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl AuthContext {
+    pub fn has_role(&self, required: &UserRole) -> bool {
+        self.role.level() >= required.level()
+    }
+}
+
+/// Tokens handed to a client at sign-in or refresh
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: &'static str,
+    /// Seconds until the access token expires
+    pub expires_in: u64,
+}
+
+/// Who an external identity provider says signed in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalIdentity {
+    /// Configured name of the provider, e.g. `github`
+    pub provider: String,
+    /// The provider's stable id for the account; emails can change, this cannot
+    pub subject: String,
+    pub email: Option<String>,
+    /// Only verified emails are trusted to match or create accounts
+    pub email_verified: bool,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    /// Handle to base a username on, if the provider has one
+    pub username: Option<String>,
+}
@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::notification::{NotificationPriority, NotificationType};
+use crate::utils::new_id;
+
+/// Who a broadcast is addressed to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum BroadcastTarget {
+    Users(Vec<Uuid>),
+    Group(Uuid),
+}
+
+/// Content of a broadcast, rendered identically for every recipient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BroadcastMessage {
+    pub notification_type: NotificationType,
+    #[serde(default)]
+    pub priority: NotificationPriority,
+    pub title: String,
+    pub message: String,
+}
+
+/// Per-recipient outcome counts of a broadcast
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BroadcastSummary {
+    pub targeted: usize,
+    /// Handed to the dispatcher; delivery outcomes are recorded per notification
+    pub queued: usize,
+    pub skipped: usize,
+}
+
+impl BroadcastSummary {
+    /// Count recipients that no longer exist as targeted but skipped
+    pub fn unresolved(&mut self, count: usize) {
+        self.targeted += count;
+        self.skipped += count;
+    }
+}
+
+/// Lifecycle of a broadcast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BroadcastStatus {
+    Running,
+    /// Stopped at a batch boundary by shutdown; resumed on the next start
+    Interrupted,
+    Completed,
+    Failed,
+    /// Stopped by an administrator; queues no further recipients
+    Cancelled,
+}
+
+impl BroadcastStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BroadcastStatus::Running => "running",
+            BroadcastStatus::Interrupted => "interrupted",
+            BroadcastStatus::Completed => "completed",
+            BroadcastStatus::Failed => "failed",
+            BroadcastStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl FromStr for BroadcastStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "running" => Ok(BroadcastStatus::Running),
+            "interrupted" => Ok(BroadcastStatus::Interrupted),
+            "completed" => Ok(BroadcastStatus::Completed),
+            "failed" => Ok(BroadcastStatus::Failed),
+            "cancelled" => Ok(BroadcastStatus::Cancelled),
+            other => Err(format!("Unknown broadcast status: {}", other)),
+        }
+    }
+}
+
+/// A broadcast and how far through its recipients it has got
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Broadcast {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub target: BroadcastTarget,
+    pub message: BroadcastMessage,
+    /// Recipients are processed in user id order; everyone up to this id is done
+    pub cursor: Option<Uuid>,
+    pub summary: BroadcastSummary,
+    pub status: BroadcastStatus,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl Broadcast {
+    pub fn new(tenant_id: String, target: BroadcastTarget, message: BroadcastMessage) -> Self {
+        Self {
+            id: new_id(),
+            tenant_id,
+            target,
+            message,
+            cursor: None,
+            summary: BroadcastSummary::default(),
+            status: BroadcastStatus::Running,
+            error: None,
+            created_at: Utc::now(),
+            completed_at: None,
+        }
+    }
+}
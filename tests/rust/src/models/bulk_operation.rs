@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::user::{User, UserFilters, UserRole};
+use crate::utils::new_id;
+
+/// Change applied to every user in a bulk selection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "role", rename_all = "snake_case")]
+pub enum BulkAction {
+    Suspend,
+    ChangeRole(UserRole),
+    /// Soft delete, so the operation stays undoable
+    Delete,
+}
+
+/// Lifecycle of a bulk operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkStatus {
+    Running,
+    /// Stopped at a batch boundary by shutdown; resumed on the next start
+    Interrupted,
+    Completed,
+    Failed,
+    Undone,
+}
+
+impl BulkStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BulkStatus::Running => "running",
+            BulkStatus::Interrupted => "interrupted",
+            BulkStatus::Completed => "completed",
+            BulkStatus::Failed => "failed",
+            BulkStatus::Undone => "undone",
+        }
+    }
+}
+
+impl FromStr for BulkStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "running" => Ok(BulkStatus::Running),
+            "interrupted" => Ok(BulkStatus::Interrupted),
+            "completed" => Ok(BulkStatus::Completed),
+            "failed" => Ok(BulkStatus::Failed),
+            "undone" => Ok(BulkStatus::Undone),
+            other => Err(format!("Unknown bulk operation status: {}", other)),
+        }
+    }
+}
+
+/// What a bulk operation would touch, shown before it is executed
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkPreview {
+    pub action: BulkAction,
+    pub total: i64,
+    pub sample: Vec<User>,
+}
+
+/// A bulk operation and its progress
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkOperation {
+    pub id: Uuid,
+    pub action: BulkAction,
+    pub filters: UserFilters,
+    pub requested_by: Uuid,
+    pub status: BulkStatus,
+    pub total: i32,
+    pub processed: i32,
+    pub skipped: i32,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub undo_until: Option<DateTime<Utc>>,
+    pub undone_at: Option<DateTime<Utc>>,
+}
+
+impl BulkOperation {
+    pub fn new(action: BulkAction, filters: UserFilters, requested_by: Uuid) -> Self {
+        Self {
+            id: new_id(),
+            action,
+            filters,
+            requested_by,
+            status: BulkStatus::Running,
+            total: 0,
+            processed: 0,
+            skipped: 0,
+            error: None,
+            created_at: Utc::now(),
+            completed_at: None,
+            undo_until: None,
+            undone_at: None,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.status == BulkStatus::Completed
+            && self.undo_until.map(|until| until > Utc::now()).unwrap_or(false)
+    }
+}
+
+/// Prior state of one user touched by a bulk operation
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BulkOperationItem {
+    pub operation_id: Uuid,
+    pub user_id: Uuid,
+    pub previous_role: String,
+    pub previous_status: String,
+    pub previous_deleted_at: Option<DateTime<Utc>>,
+    pub applied_at: Option<DateTime<Utc>>,
+    pub skipped_reason: Option<String>,
+}
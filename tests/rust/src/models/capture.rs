@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Stands in for every value taken out of a capture
+pub const REDACTED: &str = "<redacted>";
+
+/// A body as kept in a capture
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CapturedBody {
+    Empty,
+    /// JSON with secret fields redacted
+    Json { content: serde_json::Value },
+    /// A urlencoded form with secret fields redacted
+    Form { content: String },
+    /// Anything else, and bodies over the size limit, are not kept
+    Omitted { content_type: Option<String>, bytes: usize },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedRequest {
+    pub method: String,
+    /// Path and query, secret parameters redacted
+    pub uri: String,
+    /// Lowercase names; credentials are kept as `<redacted>` so it shows they were sent
+    pub headers: BTreeMap<String, String>,
+    pub body: CapturedBody,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: CapturedBody,
+}
+
+/// A request and the response it got, recorded to reproduce a bug
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedExchange {
+    pub id: Uuid,
+    pub captured_at: DateTime<Utc>,
+    /// Signed-in caller, when there was one
+    pub user_id: Option<Uuid>,
+    pub tenant_id: Option<String>,
+    pub request: CapturedRequest,
+    pub response: CapturedResponse,
+    pub elapsed_ms: u64,
+}
+
+impl CapturedExchange {
+    /// Storage key of a capture
+    pub fn storage_key(id: Uuid) -> String {
+        format!("captures/{}.json", id)
+    }
+}
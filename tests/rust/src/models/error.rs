@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+/// Application-wide error type shared by services, repositories and middleware
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("Validation failed: {}", .0.join(", "))]
+    Validation(Vec<String>),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("API quota of {limit} calls exceeded until {resets_at}")]
+    QuotaExceeded { limit: u64, resets_at: DateTime<Utc> },
+
+    #[error("Rate limit exceeded; retry in {}s", .retry_after.as_secs().max(1))]
+    RateLimited { retry_after: std::time::Duration },
+
+    /// Writes are refused while the application is in read-only mode
+    #[error("Read-only mode: {0}")]
+    ReadOnly(String),
+
+    /// Requests other than admins' are refused while the application is in maintenance mode
+    #[error("Maintenance mode: {0}")]
+    Maintenance(String),
+
+    /// A conditional write sent no `If-Match`
+    #[error("Precondition required: {0}")]
+    PreconditionRequired(String),
+
+    /// A conditional write named a version other than the current one, whose `ETag` is sent back
+    #[error("Precondition failed: {message}")]
+    PreconditionFailed { message: String, etag: String },
+
+    #[error("Deadline exceeded during {0}")]
+    DeadlineExceeded(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Cache error: {0}")]
+    Cache(String),
+
+    #[error("Encryption error: {0}")]
+    Encryption(String),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+/// Convenience result alias used throughout the library
+pub type AppResult<T> = Result<T, AppError>;
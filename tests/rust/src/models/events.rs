@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::user::{User, UserRole, UserStatus};
+
+/// Domain events emitted whenever a user is mutated; what leaves the
+/// process is the `UserEventPayload` of one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UserEvent {
+    /// Events reach brokers outside the service, so users go without their password hash
+    Created {
+        #[serde(serialize_with = "super::user::serialize_without_secrets")]
+        user: User,
+    },
+    Updated {
+        #[serde(serialize_with = "super::user::serialize_without_secrets")]
+        user: User,
+    },
+    LoggedIn { user_id: Uuid, at: DateTime<Utc> },
+    Deleted { user_id: Uuid, at: DateTime<Utc> },
+}
+
+/// What a user event keeps once it leaves the process: the user's id and
+/// fields that identify no one. Outbox rows and broker records outlive the
+/// change they describe, so names and addresses are read back from the
+/// service instead of travelling with the event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UserEventPayload {
+    Created {
+        user_id: Uuid,
+        version: i64,
+        role: UserRole,
+        status: UserStatus,
+        region: Option<String>,
+        at: DateTime<Utc>,
+    },
+    Updated {
+        user_id: Uuid,
+        version: i64,
+        role: UserRole,
+        status: UserStatus,
+        region: Option<String>,
+        at: DateTime<Utc>,
+    },
+    LoggedIn { user_id: Uuid, at: DateTime<Utc> },
+    Deleted { user_id: Uuid, at: DateTime<Utc> },
+}
+
+impl From<&UserEvent> for UserEventPayload {
+    fn from(event: &UserEvent) -> Self {
+        match event {
+            UserEvent::Created { user } => UserEventPayload::Created {
+                user_id: user.id,
+                version: user.version,
+                role: user.role.clone(),
+                status: user.status.clone(),
+                region: user.region.clone(),
+                at: user.updated_at,
+            },
+            UserEvent::Updated { user } => UserEventPayload::Updated {
+                user_id: user.id,
+                version: user.version,
+                role: user.role.clone(),
+                status: user.status.clone(),
+                region: user.region.clone(),
+                at: user.updated_at,
+            },
+            UserEvent::LoggedIn { user_id, at } => UserEventPayload::LoggedIn {
+                user_id: *user_id,
+                at: *at,
+            },
+            UserEvent::Deleted { user_id, at } => UserEventPayload::Deleted {
+                user_id: *user_id,
+                at: *at,
+            },
+        }
+    }
+}
+
+impl UserEvent {
+    /// Id of the user the event refers to
+    pub fn user_id(&self) -> Uuid {
+        match self {
+            UserEvent::Created { user } | UserEvent::Updated { user } => user.id,
+            UserEvent::LoggedIn { user_id, .. } | UserEvent::Deleted { user_id, .. } => *user_id,
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::utils::new_id;
+
+/// What an export contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportKind {
+    /// Everything stored about the requester, as JSON
+    UserData,
+    /// The admin reports, in the configured report format
+    NewSignups,
+    NotificationDelivery,
+    LockedAccounts,
+}
+
+impl ExportKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportKind::UserData => "user_data",
+            ExportKind::NewSignups => "new_signups",
+            ExportKind::NotificationDelivery => "notification_delivery",
+            ExportKind::LockedAccounts => "locked_accounts",
+        }
+    }
+}
+
+impl FromStr for ExportKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "user_data" => Ok(ExportKind::UserData),
+            "new_signups" => Ok(ExportKind::NewSignups),
+            "notification_delivery" => Ok(ExportKind::NotificationDelivery),
+            "locked_accounts" => Ok(ExportKind::LockedAccounts),
+            other => Err(format!("Unknown export kind: {}", other)),
+        }
+    }
+}
+
+/// Lifecycle of an export job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    /// Completed, but its file has been deleted after the retention period
+    Expired,
+    /// Taken off the queue by an operator before it ran
+    Cancelled,
+}
+
+impl ExportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportStatus::Pending => "pending",
+            ExportStatus::Running => "running",
+            ExportStatus::Completed => "completed",
+            ExportStatus::Failed => "failed",
+            ExportStatus::Expired => "expired",
+            ExportStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl FromStr for ExportStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pending" => Ok(ExportStatus::Pending),
+            "running" => Ok(ExportStatus::Running),
+            "completed" => Ok(ExportStatus::Completed),
+            "failed" => Ok(ExportStatus::Failed),
+            "expired" => Ok(ExportStatus::Expired),
+            "cancelled" => Ok(ExportStatus::Cancelled),
+            other => Err(format!("Unknown export status: {}", other)),
+        }
+    }
+}
+
+/// An export generated in the background and kept in storage until it expires
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportJob {
+    pub id: Uuid,
+    pub requested_by: Uuid,
+    /// Tenant the requester's notification is branded for
+    pub tenant_id: String,
+    pub kind: ExportKind,
+    pub status: ExportStatus,
+    /// Storage key of the generated file
+    #[serde(skip_serializing)]
+    pub artifact_key: Option<String>,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub size_bytes: Option<i64>,
+    pub error: Option<String>,
+    pub attempts: i32,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// When the file is deleted and download links stop working
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl ExportJob {
+    pub fn new(requested_by: Uuid, tenant_id: &str, kind: ExportKind) -> Self {
+        Self {
+            id: new_id(),
+            requested_by,
+            tenant_id: tenant_id.to_string(),
+            kind,
+            status: ExportStatus::Pending,
+            artifact_key: None,
+            filename: None,
+            content_type: None,
+            size_bytes: None,
+            error: None,
+            attempts: 0,
+            created_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+            expires_at: None,
+        }
+    }
+}
+
+/// An export job with a link to its file while it can be downloaded
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportJobView {
+    #[serde(flatten)]
+    pub job: ExportJob,
+    pub download_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateExportRequest {
+    pub kind: ExportKind,
+}
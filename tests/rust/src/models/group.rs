@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::utils::new_id;
+
+/// Named collection of users that can be targeted as a unit
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Group {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Group {
+    pub fn new(name: String, description: Option<String>, created_by: Option<Uuid>) -> Self {
+        let now = Utc::now();
+
+        Self {
+            id: new_id(),
+            name,
+            description,
+            created_by,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// A user's membership in a group
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct GroupMembership {
+    pub group_id: Uuid,
+    pub user_id: Uuid,
+    pub added_by: Option<Uuid>,
+    pub added_at: DateTime<Utc>,
+}
+
+/// Request struct for creating a new group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateGroupRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub created_by: Option<Uuid>,
+}
+
+impl CreateGroupRequest {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        let name = self.name.trim();
+        if name.is_empty() {
+            errors.push("Group name is required".to_string());
+        } else if name.len() > 100 {
+            errors.push("Group name must be at most 100 characters".to_string());
+        }
+
+        errors
+    }
+}
@@ -0,0 +1,132 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Most jobs one listing returns
+pub const MAX_JOB_PAGE: i64 = 200;
+
+/// A background work queue operators can look into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobQueue {
+    /// Exports waiting for the export runner
+    Exports,
+    /// Events waiting for the outbox relay
+    Outbox,
+}
+
+impl JobQueue {
+    pub const ALL: [JobQueue; 2] = [JobQueue::Exports, JobQueue::Outbox];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobQueue::Exports => "exports",
+            JobQueue::Outbox => "outbox",
+        }
+    }
+}
+
+impl FromStr for JobQueue {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "exports" => Ok(JobQueue::Exports),
+            "outbox" => Ok(JobQueue::Outbox),
+            other => Err(format!("Unknown job queue: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Failed,
+    /// Taken off the queue by an operator
+    Cancelled,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl FromStr for JobState {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "queued" => Ok(JobState::Queued),
+            "running" => Ok(JobState::Running),
+            "failed" => Ok(JobState::Failed),
+            "cancelled" => Ok(JobState::Cancelled),
+            other => Err(format!("Unknown job state: {}", other)),
+        }
+    }
+}
+
+/// A job as shown to operators; payloads are summarized, never shown whole
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedJob {
+    pub queue: JobQueue,
+    /// A UUID for exports, a sequence number for outbox messages
+    pub id: String,
+    pub state: JobState,
+    pub summary: serde_json::Value,
+    pub attempts: i32,
+    /// None when the job is retried until it succeeds
+    pub max_attempts: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    /// When a runner is next expected to pick the job up; None when nothing will
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+/// Why and since when a queue's runners have been held
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuePause {
+    pub reason: String,
+    pub since: DateTime<Utc>,
+    pub paused_by: Uuid,
+}
+
+/// When a queue's runners last ran and expect to run next
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueRun {
+    pub last_run_at: DateTime<Utc>,
+    pub next_run_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobQueueSummary {
+    pub queue: JobQueue,
+    pub queued: i64,
+    pub running: i64,
+    pub failed: i64,
+    pub oldest_queued_at: Option<DateTime<Utc>>,
+    pub paused: Option<QueuePause>,
+    /// None until a runner has been heard from
+    #[serde(flatten)]
+    pub last_run: Option<QueueRun>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JobFilters {
+    pub state: Option<JobState>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PauseQueueRequest {
+    pub reason: Option<String>,
+}
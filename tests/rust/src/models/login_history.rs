@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::session::ClientInfo;
+use crate::utils::new_id;
+
+pub const MAX_LOGIN_HISTORY_PAGE: i64 = 500;
+
+/// Why a sign-in was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginFailureReason {
+    UnknownAccount,
+    WrongPassword,
+    WrongSecondFactor,
+    LockedOut,
+    /// The client address failed too many sign-ins across accounts
+    AddressThrottled,
+}
+
+impl LoginFailureReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginFailureReason::UnknownAccount => "unknown_account",
+            LoginFailureReason::WrongPassword => "wrong_password",
+            LoginFailureReason::WrongSecondFactor => "wrong_second_factor",
+            LoginFailureReason::LockedOut => "locked_out",
+            LoginFailureReason::AddressThrottled => "address_throttled",
+        }
+    }
+}
+
+impl FromStr for LoginFailureReason {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "unknown_account" => Ok(LoginFailureReason::UnknownAccount),
+            "wrong_password" => Ok(LoginFailureReason::WrongPassword),
+            "wrong_second_factor" => Ok(LoginFailureReason::WrongSecondFactor),
+            "locked_out" => Ok(LoginFailureReason::LockedOut),
+            "address_throttled" => Ok(LoginFailureReason::AddressThrottled),
+            other => Err(format!("Unknown login failure reason: {}", other)),
+        }
+    }
+}
+
+/// A sign-in attempt and the client it came from
+#[derive(Debug, Clone, Serialize)]
+pub struct LoginAttempt {
+    pub id: Uuid,
+    /// None when no account has the email tried
+    pub user_id: Option<Uuid>,
+    pub email: String,
+    pub succeeded: bool,
+    pub failure_reason: Option<LoginFailureReason>,
+    pub ip_address: Option<String>,
+    pub user_agent: String,
+    pub country: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LoginAttempt {
+    pub fn succeeded(user_id: Uuid, email: &str, client: &ClientInfo) -> Self {
+        Self::new(Some(user_id), email, None, client)
+    }
+
+    pub fn failed(user_id: Option<Uuid>, email: &str, reason: LoginFailureReason, client: &ClientInfo) -> Self {
+        Self::new(user_id, email, Some(reason), client)
+    }
+
+    fn new(
+        user_id: Option<Uuid>,
+        email: &str,
+        failure_reason: Option<LoginFailureReason>,
+        client: &ClientInfo,
+    ) -> Self {
+        Self {
+            id: new_id(),
+            user_id,
+            email: email.to_string(),
+            succeeded: failure_reason.is_none(),
+            failure_reason,
+            ip_address: client.ip_address.clone(),
+            user_agent: client.user_agent.clone(),
+            country: client.country.clone(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Filtering options for a user's sign-in history
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LoginHistoryFilters {
+    pub succeeded: Option<bool>,
+    pub ip_address: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+impl LoginHistoryFilters {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                errors.push("from must not be after to".to_string());
+            }
+        }
+        if let Some(limit) = self.limit {
+            if !(1..=MAX_LOGIN_HISTORY_PAGE).contains(&limit) {
+                errors.push(format!("limit must be between 1 and {}", MAX_LOGIN_HISTORY_PAGE));
+            }
+        }
+        errors
+    }
+}
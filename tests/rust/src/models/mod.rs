@@ -1,7 +1,85 @@
 pub mod user;
 pub mod notification;
+pub mod group;
+pub mod account_deletion;
+pub mod session;
+pub mod login_history;
+pub mod second_factor;
+pub mod bulk_operation;
+pub mod broadcast;
+pub mod template;
+pub mod tenant;
 pub mod error;
+pub mod events;
+pub mod outbox;
+pub mod auth;
+pub mod announcement;
+pub mod activity;
+pub mod audit;
+pub mod api_key;
+pub mod suppression;
+pub mod settings;
+pub mod role_request;
+pub mod role_grant;
+pub mod service_account;
+pub mod provider_callback;
+pub mod policy;
+pub mod passkey;
+pub mod trusted_device;
+pub mod export_job;
+pub mod job_queue;
+pub mod status;
+pub mod capture;
+pub mod organization;
 
-pub use user::{User, UserRole, UserStatus, CreateUserRequest, UpdateUserRequest, UserFilters};
-pub use notification::{Notification, NotificationType, NotificationStatus};
-pub use error::{AppError, AppResult};
\ No newline at end of file
+pub use user::{
+    CreateUserRequest, SortDirection, UpdateUserRequest, User, UserCursor, UserFilters, UserPage, UserPreferences,
+    UserPublic, UserRole, UserSort, UserSortField, UserStatus,
+};
+pub use notification::{
+    EngagementEvent, Notification, NotificationChannel, NotificationFilters, NotificationPriority, NotificationStatus,
+    NotificationType, TemplateEngagement,
+};
+pub use group::{Group, GroupMembership, CreateGroupRequest};
+pub use account_deletion::{AccountDeletion, AccountDeletionRequest, UserDataExport};
+pub use session::{ClientInfo, Device, Session};
+pub use login_history::{LoginAttempt, LoginFailureReason, LoginHistoryFilters};
+pub use second_factor::{
+    Authenticator, ExternalLogin, SecondFactorProof, SecondFactorSummary, TotpCredential, TotpEnrollment,
+    WebAuthnChallenge,
+};
+pub use template::{NotificationTemplate, RenderedTemplate, TemplatePreview, TemplateRevision};
+pub use tenant::{TenantBranding, TenantContext};
+pub use bulk_operation::{BulkAction, BulkOperation, BulkOperationItem, BulkPreview, BulkStatus};
+pub use broadcast::{Broadcast, BroadcastMessage, BroadcastStatus, BroadcastSummary, BroadcastTarget};
+pub use error::{AppError, AppResult};
+pub use events::{UserEvent, UserEventPayload};
+pub use outbox::{NewOutboxMessage, OutboxMessage};
+pub use auth::{AuthContext, ExternalIdentity, TokenKind, TokenPair};
+pub use activity::{ActivityEntry, ActivityKind, ActivityPage, AuditAction, AuditEvent};
+pub use audit::{AuditLog, AuditLogAction, AuditLogFilters, AuditLogPage};
+pub use suppression::{CreateSuppressionRequest, Suppression};
+pub use provider_callback::{
+    CallbackFilters, CallbackOutcome, DeliveryEvent, DeliveryEventKind, ProviderCallback,
+};
+pub use passkey::{PasskeyCredential, RegisterPasskeyRequest};
+pub use trusted_device::{IssuedTrustedDevice, RenameDeviceRequest, TrustDeviceRequest, TrustedDevice};
+pub use export_job::{CreateExportRequest, ExportJob, ExportJobView, ExportKind, ExportStatus};
+pub use job_queue::{
+    JobFilters, JobQueue, JobQueueSummary, JobState, PauseQueueRequest, QueuePause, QueueRun, QueuedJob,
+};
+pub use capture::{CapturedBody, CapturedExchange, CapturedRequest, CapturedResponse, REDACTED};
+pub use organization::{
+    CreateOrganizationRequest, OrgRole, Organization, OrganizationMember, SetOrganizationMemberRequest,
+};
+pub use status::{ComponentStatus, DailyUptime, HealthSample, Incident, OverallStatus, StatusPage};
+pub use policy::{EffectivePermissions, PolicyCondition, PolicyEffect, PolicyResource, PolicyRule};
+pub use role_request::{CreateRoleRequest, RoleRequest, RoleRequestDecision, RoleRequestStatus};
+pub use role_grant::{GrantTemporaryRoleRequest, RoleGrant};
+pub use service_account::{
+    CreateServiceAccountRequest, IssuedServiceAccount, ServiceAccount, ServiceContext, ServiceScope, ServiceToken,
+    ServiceTokenRequest,
+};
+pub use settings::{Setting, SettingDefinition, SettingKind, SettingsRegistry};
+pub use api_key::{ApiKey, ApiKeyContext, ApiKeyScope, CreateApiKeyRequest, IssuedApiKey};
+pub use announcement::{Announcement, AnnouncementAudience, AnnouncementSeverity, CreateAnnouncementRequest};
\ No newline at end of file
@@ -0,0 +1,321 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::utils::new_id;
+
+/// Category of a notification, used for templates and user preferences
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationType {
+    Welcome,
+    Security,
+    System,
+    Announcement,
+    Marketing,
+}
+
+impl NotificationType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationType::Welcome => "welcome",
+            NotificationType::Security => "security",
+            NotificationType::System => "system",
+            NotificationType::Announcement => "announcement",
+            NotificationType::Marketing => "marketing",
+        }
+    }
+}
+
+impl FromStr for NotificationType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "welcome" => Ok(NotificationType::Welcome),
+            "security" => Ok(NotificationType::Security),
+            "system" => Ok(NotificationType::System),
+            "announcement" => Ok(NotificationType::Announcement),
+            "marketing" => Ok(NotificationType::Marketing),
+            other => Err(format!("Unknown notification type: {}", other)),
+        }
+    }
+}
+
+/// Urgency of a notification, which selects among its type's delivery routes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl NotificationPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationPriority::Low => "low",
+            NotificationPriority::Normal => "normal",
+            NotificationPriority::High => "high",
+            NotificationPriority::Urgent => "urgent",
+        }
+    }
+}
+
+impl FromStr for NotificationPriority {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "low" => Ok(NotificationPriority::Low),
+            "normal" => Ok(NotificationPriority::Normal),
+            "high" => Ok(NotificationPriority::High),
+            "urgent" => Ok(NotificationPriority::Urgent),
+            other => Err(format!("Unknown notification priority: {}", other)),
+        }
+    }
+}
+
+/// Delivery lifecycle of a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationStatus {
+    Pending,
+    Sent,
+    Delivered,
+    Read,
+    Failed,
+    /// Withdrawn before delivery, by an administrator or a suppression entry
+    Cancelled,
+}
+
+impl NotificationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationStatus::Pending => "pending",
+            NotificationStatus::Sent => "sent",
+            NotificationStatus::Delivered => "delivered",
+            NotificationStatus::Read => "read",
+            NotificationStatus::Failed => "failed",
+            NotificationStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            NotificationStatus::Read | NotificationStatus::Failed | NotificationStatus::Cancelled
+        )
+    }
+}
+
+impl FromStr for NotificationStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pending" => Ok(NotificationStatus::Pending),
+            "sent" => Ok(NotificationStatus::Sent),
+            "delivered" => Ok(NotificationStatus::Delivered),
+            "read" => Ok(NotificationStatus::Read),
+            "failed" => Ok(NotificationStatus::Failed),
+            "cancelled" => Ok(NotificationStatus::Cancelled),
+            other => Err(format!("Unknown notification status: {}", other)),
+        }
+    }
+}
+
+/// Channel a notification is delivered through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationChannel {
+    Email,
+    InApp,
+}
+
+impl NotificationChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationChannel::Email => "email",
+            NotificationChannel::InApp => "in_app",
+        }
+    }
+}
+
+impl FromStr for NotificationChannel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "email" => Ok(NotificationChannel::Email),
+            "in_app" => Ok(NotificationChannel::InApp),
+            other => Err(format!("Unknown notification channel: {}", other)),
+        }
+    }
+}
+
+/// Kind of tracked interaction with a delivered email
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngagementEvent {
+    Open,
+    Click,
+}
+
+/// Engagement of emails rendered from one template revision
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateEngagement {
+    pub template_key: String,
+    pub template_version: i32,
+    pub sent: i64,
+    pub opened: i64,
+    pub clicked: i64,
+    /// Share of sent emails opened or clicked at least once
+    pub open_rate: f64,
+    pub click_rate: f64,
+}
+
+impl TemplateEngagement {
+    pub fn new(template_key: String, template_version: i32, sent: i64, opened: i64, clicked: i64) -> Self {
+        let rate = |count: i64| if sent > 0 { count as f64 / sent as f64 } else { 0.0 };
+        Self {
+            template_key,
+            template_version,
+            sent,
+            opened,
+            clicked,
+            open_rate: rate(opened),
+            click_rate: rate(clicked),
+        }
+    }
+}
+
+/// A single notification addressed to one user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub notification_type: NotificationType,
+    pub channel: NotificationChannel,
+    pub status: NotificationStatus,
+    pub recipient: String,
+    pub title: String,
+    pub message: String,
+    /// Rendered HTML alternative for email
+    pub html_message: Option<String>,
+    /// Tenant whose branding the content was rendered with
+    pub tenant_id: Option<String>,
+    /// Sender mailbox overriding the channel default
+    pub sender: Option<String>,
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// Template revision the content was rendered from, if any
+    pub template_key: Option<String>,
+    pub template_version: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub read_at: Option<DateTime<Utc>>,
+    /// First tracked email open and link click
+    pub opened_at: Option<DateTime<Utc>>,
+    pub clicked_at: Option<DateTime<Utc>>,
+    pub open_count: i32,
+    pub click_count: i32,
+}
+
+impl Notification {
+    /// Create a pending notification
+    pub fn new(
+        user_id: Uuid,
+        notification_type: NotificationType,
+        channel: NotificationChannel,
+        recipient: String,
+        title: String,
+        message: String,
+    ) -> Self {
+        Self {
+            id: new_id(),
+            user_id,
+            notification_type,
+            channel,
+            status: NotificationStatus::Pending,
+            recipient,
+            title,
+            message,
+            html_message: None,
+            tenant_id: None,
+            sender: None,
+            metadata: HashMap::new(),
+            template_key: None,
+            template_version: None,
+            created_at: Utc::now(),
+            sent_at: None,
+            read_at: None,
+            opened_at: None,
+            clicked_at: None,
+            open_count: 0,
+            click_count: 0,
+        }
+    }
+
+    /// Record a successful hand-off to the delivery channel
+    pub fn mark_sent(&mut self) {
+        self.status = NotificationStatus::Sent;
+        self.sent_at = Some(Utc::now());
+    }
+
+    /// Record the provider's confirmation that the message reached the recipient
+    pub fn mark_delivered(&mut self) {
+        self.status = NotificationStatus::Delivered;
+    }
+
+    /// Record a delivery failure with its reason
+    pub fn mark_failed(&mut self, reason: &str) {
+        self.status = NotificationStatus::Failed;
+        self.metadata
+            .insert("failure_reason".to_string(), serde_json::json!(reason));
+    }
+
+    /// Withdraw an undelivered notification with the reason it will not be sent
+    pub fn mark_cancelled(&mut self, reason: &str) {
+        self.status = NotificationStatus::Cancelled;
+        self.metadata
+            .insert("cancel_reason".to_string(), serde_json::json!(reason));
+    }
+
+    /// Broadcast the notification was sent as part of, if any
+    pub fn broadcast_id(&self) -> Option<Uuid> {
+        self.metadata
+            .get("broadcast_id")
+            .and_then(|value| value.as_str())
+            .and_then(|id| id.parse().ok())
+    }
+}
+
+/// Which pending notifications to cancel; at least one criterion is required
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NotificationFilters {
+    /// Also stops the broadcast from queueing any more recipients
+    pub broadcast_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub notification_type: Option<NotificationType>,
+    pub channel: Option<NotificationChannel>,
+    pub template_key: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+}
+
+impl NotificationFilters {
+    pub fn validate(&self) -> Vec<String> {
+        let empty = self.broadcast_id.is_none()
+            && self.user_id.is_none()
+            && self.notification_type.is_none()
+            && self.channel.is_none()
+            && self.template_key.is_none()
+            && self.created_after.is_none();
+        if empty {
+            return vec!["At least one filter is required to cancel notifications".to_string()];
+        }
+        Vec::new()
+    }
+}
@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::utils::new_id;
+
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Role of a member within one organization, on top of their global role
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrgRole {
+    Member,
+    /// Sees the accounts of the organization's members
+    OrgModerator,
+    /// Also edits them, changes their status and manages membership
+    OrgAdmin,
+}
+
+impl OrgRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrgRole::Member => "member",
+            OrgRole::OrgModerator => "org_moderator",
+            OrgRole::OrgAdmin => "org_admin",
+        }
+    }
+
+    pub fn level(&self) -> u8 {
+        match self {
+            OrgRole::Member => 1,
+            OrgRole::OrgModerator => 2,
+            OrgRole::OrgAdmin => 3,
+        }
+    }
+
+    /// Org roles only reach members ranked strictly below
+    pub fn can_manage(&self, other: &OrgRole) -> bool {
+        self.level() > other.level()
+    }
+}
+
+impl FromStr for OrgRole {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "member" => Ok(OrgRole::Member),
+            "org_moderator" => Ok(OrgRole::OrgModerator),
+            "org_admin" => Ok(OrgRole::OrgAdmin),
+            other => Err(format!("Unknown organization role: {}", other)),
+        }
+    }
+}
+
+/// A group of users within one tenant, whose org admins manage its members
+#[derive(Debug, Clone, Serialize)]
+pub struct Organization {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub name: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Organization {
+    pub fn new(tenant_id: String, name: String, created_by: Option<Uuid>) -> Self {
+        Self {
+            id: new_id(),
+            tenant_id,
+            name,
+            created_by,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrganizationMember {
+    pub organization_id: Uuid,
+    pub user_id: Uuid,
+    pub role: OrgRole,
+    pub added_by: Option<Uuid>,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateOrganizationRequest {
+    pub name: String,
+}
+
+impl CreateOrganizationRequest {
+    pub fn validate(&self) -> Vec<String> {
+        let name = self.name.trim();
+        let mut errors = Vec::new();
+        if name.is_empty() {
+            errors.push("Organization name is required".to_string());
+        } else if name.chars().count() > MAX_NAME_LENGTH {
+            errors.push(format!("Organization name must be at most {} characters", MAX_NAME_LENGTH));
+        }
+        errors
+    }
+}
+
+/// Add a user to an organization, or change the role they hold there
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetOrganizationMemberRequest {
+    #[serde(default = "default_role")]
+    pub role: OrgRole,
+}
+
+fn default_role() -> OrgRole {
+    OrgRole::Member
+}
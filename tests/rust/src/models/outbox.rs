@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::error::{AppError, AppResult};
+use super::events::{UserEvent, UserEventPayload};
+
+/// An event waiting in the transactional outbox
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OutboxMessage {
+    pub id: i64,
+    pub aggregate_type: String,
+    pub aggregate_id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    /// Stable key used by the producer and consumers to deduplicate redeliveries
+    pub idempotency_key: Uuid,
+    pub attempts: i32,
+    /// Why the latest publish failed; only read for operators
+    #[sqlx(default)]
+    #[serde(default)]
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An event to be written to the outbox within a domain transaction
+#[derive(Debug, Clone)]
+pub struct NewOutboxMessage {
+    pub aggregate_type: String,
+    pub aggregate_id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    pub idempotency_key: Uuid,
+}
+
+impl NewOutboxMessage {
+    /// The event as a `UserEventPayload`, which leaves out the user's personal data
+    pub fn from_user_event(event: &UserEvent) -> AppResult<Self> {
+        let payload = serde_json::to_value(UserEventPayload::from(event))
+            .map_err(|e| AppError::Internal(format!("Unserializable user event: {}", e)))?;
+        let event_type = payload["type"].as_str().unwrap_or("unknown").to_string();
+
+        Ok(Self {
+            aggregate_type: "user".to_string(),
+            aggregate_id: event.user_id(),
+            event_type,
+            payload,
+            idempotency_key: Uuid::new_v4(),
+        })
+    }
+}
@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A passkey a user signs in with instead of a password
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PasskeyCredential {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Label chosen by the user, e.g. "Phone"
+    pub name: String,
+    /// Base64url credential id reported by the authenticator
+    pub credential_id: String,
+    /// Serialized credential with its public key and signature counter
+    #[serde(skip_serializing)]
+    pub public_key: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterPasskeyRequest {
+    pub name: String,
+}
@@ -0,0 +1,244 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::organization::OrgRole;
+use super::user::{User, UserRole};
+
+/// Matches every role or every permission in a rule
+pub const WILDCARD: &str = "*";
+
+/// Whether a matching rule grants a permission or withholds it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyEffect {
+    #[default]
+    Allow,
+    /// Wins over every rule allowing the same permission
+    Deny,
+}
+
+impl PolicyEffect {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyEffect::Allow => "allow",
+            PolicyEffect::Deny => "deny",
+        }
+    }
+}
+
+impl FromStr for PolicyEffect {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "allow" => Ok(PolicyEffect::Allow),
+            "deny" => Ok(PolicyEffect::Deny),
+            other => Err(format!("Unknown policy effect: {}", other)),
+        }
+    }
+}
+
+/// What must hold between the caller and the resource for a rule to apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyCondition {
+    /// The resource belongs to the caller
+    Owner,
+    /// The caller's role ranks above the role of the resource's owner
+    OutranksOwner,
+    /// The caller moderates or administers an organization of the owner's, ranking above them there
+    OrgModerator,
+    /// The caller administers an organization of the owner's
+    OrgAdmin,
+}
+
+impl PolicyCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyCondition::Owner => "owner",
+            PolicyCondition::OutranksOwner => "outranks_owner",
+            PolicyCondition::OrgModerator => "org_moderator",
+            PolicyCondition::OrgAdmin => "org_admin",
+        }
+    }
+
+    fn holds(&self, actor: &User, resource: &PolicyResource) -> bool {
+        match self {
+            PolicyCondition::Owner => resource.owner_id == Some(actor.id),
+            PolicyCondition::OutranksOwner => resource
+                .owner_role
+                .as_ref()
+                .is_some_and(|owner_role| actor.role.can_manage(owner_role)),
+            PolicyCondition::OrgModerator => resource
+                .org_role
+                .is_some_and(|org_role| org_role.level() >= OrgRole::OrgModerator.level()),
+            PolicyCondition::OrgAdmin => resource.org_role == Some(OrgRole::OrgAdmin),
+        }
+    }
+}
+
+impl FromStr for PolicyCondition {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "owner" => Ok(PolicyCondition::Owner),
+            "outranks_owner" => Ok(PolicyCondition::OutranksOwner),
+            "org_moderator" => Ok(PolicyCondition::OrgModerator),
+            "org_admin" => Ok(PolicyCondition::OrgAdmin),
+            other => Err(format!("Unknown policy condition: {}", other)),
+        }
+    }
+}
+
+/// The thing a permission is checked against, for resource-level rules
+#[derive(Debug, Clone)]
+pub struct PolicyResource {
+    /// Kind of resource, e.g. `user`
+    pub kind: &'static str,
+    pub owner_id: Option<Uuid>,
+    pub owner_role: Option<UserRole>,
+    /// The caller's standing over the owner through organizations they share
+    pub org_role: Option<OrgRole>,
+}
+
+impl PolicyResource {
+    /// A user account, owned by the user themselves
+    pub fn user(user: &User) -> Self {
+        Self {
+            kind: "user",
+            owner_id: Some(user.id),
+            owner_role: Some(user.role.clone()),
+            org_role: None,
+        }
+    }
+
+    /// A resource known only by its owner, e.g. a user that could not be found
+    pub fn owned(kind: &'static str, owner_id: Uuid) -> Self {
+        Self {
+            kind,
+            owner_id: Some(owner_id),
+            owner_role: None,
+            org_role: None,
+        }
+    }
+
+    pub fn with_org_role(mut self, org_role: Option<OrgRole>) -> Self {
+        self.org_role = org_role;
+        self
+    }
+}
+
+/// One role→permission mapping, optionally limited to a kind of resource
+/// and a condition on it.
+///
+/// `role` and `permission` accept `*`, and a permission ending in `:*`
+/// covers its whole namespace, so `users:*` grants `users:update`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub role: String,
+    pub permission: String,
+    /// Kind of resource the rule is limited to; None applies to any
+    #[serde(default)]
+    pub resource: Option<String>,
+    #[serde(default)]
+    pub condition: Option<PolicyCondition>,
+    #[serde(default)]
+    pub effect: PolicyEffect,
+}
+
+impl PolicyRule {
+    pub fn allow(role: &str, permission: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            permission: permission.to_string(),
+            resource: None,
+            condition: None,
+            effect: PolicyEffect::Allow,
+        }
+    }
+
+    pub fn on(mut self, resource: &str, condition: PolicyCondition) -> Self {
+        self.resource = Some(resource.to_string());
+        self.condition = Some(condition);
+        self
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.role != WILDCARD && self.role.parse::<UserRole>().is_err() {
+            errors.push(format!("Unknown role in policy: {}", self.role));
+        }
+        let pattern = self.permission.strip_suffix(":*").unwrap_or(&self.permission);
+        if pattern.is_empty() || (pattern != WILDCARD && pattern.contains('*')) {
+            errors.push(format!("Invalid permission pattern in policy: {}", self.permission));
+        }
+        if self.condition.is_some() && self.resource.is_none() {
+            errors.push(format!("Conditional policy for {} must name a resource", self.permission));
+        }
+        errors
+    }
+
+    /// Whether the rule speaks about `permission` for `role`, ignoring resource and condition
+    pub fn covers(&self, role: &UserRole, permission: &str) -> bool {
+        self.is_for(role) && permission_matches(&self.permission, permission)
+    }
+
+    pub fn is_for(&self, role: &UserRole) -> bool {
+        self.role == WILDCARD || self.role == role.as_str()
+    }
+
+    /// Whether the rule's permission pattern takes in everything `pattern` does
+    pub fn includes(&self, pattern: &str) -> bool {
+        permission_matches(&self.permission, pattern)
+    }
+
+    /// Whether the rule applies to `actor` exercising `permission` on `resource`.
+    /// Resource-level rules never apply to checks made without a resource.
+    pub fn applies(&self, actor: &User, permission: &str, resource: Option<&PolicyResource>) -> bool {
+        if !self.covers(&actor.role, permission) {
+            return false;
+        }
+        let Some(kind) = &self.resource else {
+            return true;
+        };
+        let Some(resource) = resource.filter(|resource| resource.kind == kind) else {
+            return false;
+        };
+        self.condition.map_or(true, |condition| condition.holds(actor, resource))
+    }
+}
+
+/// Everything a user may do outright, whatever the resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectivePermissions {
+    pub user_id: Uuid,
+    /// The user's own role, or a higher one temporarily granted to them
+    pub role: UserRole,
+    /// Permission patterns granted, e.g. `users:*`
+    pub allowed: Vec<String>,
+    /// Patterns withheld even where an allowed pattern covers them
+    pub denied: Vec<String>,
+    /// When the first temporary role counted runs out and the set shrinks
+    pub expires_at: Option<DateTime<Utc>>,
+    pub computed_at: DateTime<Utc>,
+}
+
+impl EffectivePermissions {
+    /// Whether the set grants `permission`, deciding as the policy engine does
+    pub fn allows(&self, permission: &str) -> bool {
+        !self.denied.iter().any(|pattern| permission_matches(pattern, permission))
+            && self.allowed.iter().any(|pattern| permission_matches(pattern, permission))
+    }
+}
+
+fn permission_matches(pattern: &str, permission: &str) -> bool {
+    if pattern == WILDCARD || pattern == permission {
+        return true;
+    }
+    pattern
+        .strip_suffix(":*")
+        .is_some_and(|namespace| permission == namespace || permission.starts_with(&format!("{}:", namespace)))
+}
@@ -0,0 +1,111 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::utils::new_id;
+
+/// What a delivery provider reports about a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryEventKind {
+    Delivered,
+    Bounced,
+    Failed,
+}
+
+/// A status update in the normalized shape providers are configured to post.
+///
+/// ```json
+/// { "notification_id": "…", "event": "bounced", "reason": "mailbox full" }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryEvent {
+    pub notification_id: Uuid,
+    pub event: DeliveryEventKind,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// How processing a callback went the last time it ran
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallbackOutcome {
+    /// Stored but not processed, e.g. because its signature did not verify
+    Pending,
+    Applied,
+    /// Valid, but about a notification that is gone or already final
+    Ignored,
+    Failed,
+}
+
+impl CallbackOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallbackOutcome::Pending => "pending",
+            CallbackOutcome::Applied => "applied",
+            CallbackOutcome::Ignored => "ignored",
+            CallbackOutcome::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for CallbackOutcome {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pending" => Ok(CallbackOutcome::Pending),
+            "applied" => Ok(CallbackOutcome::Applied),
+            "ignored" => Ok(CallbackOutcome::Ignored),
+            "failed" => Ok(CallbackOutcome::Failed),
+            other => Err(format!("Unknown callback outcome: {}", other)),
+        }
+    }
+}
+
+/// A provider callback exactly as it arrived, kept for inspection and replay
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderCallback {
+    pub id: Uuid,
+    pub provider: String,
+    /// The few request headers worth keeping; credentials are never stored
+    pub headers: serde_json::Value,
+    /// Raw request body, byte for byte when it was UTF-8
+    pub payload: String,
+    pub signature_valid: bool,
+    pub notification_id: Option<Uuid>,
+    pub outcome: CallbackOutcome,
+    pub error: Option<String>,
+    pub replay_count: i32,
+    pub received_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+impl ProviderCallback {
+    pub fn new(provider: String, headers: serde_json::Value, payload: String, signature_valid: bool) -> Self {
+        Self {
+            id: new_id(),
+            provider,
+            headers,
+            payload,
+            signature_valid,
+            notification_id: None,
+            outcome: CallbackOutcome::Pending,
+            error: None,
+            replay_count: 0,
+            received_at: Utc::now(),
+            processed_at: None,
+        }
+    }
+}
+
+/// Filtering options for browsing stored callbacks
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CallbackFilters {
+    pub provider: Option<String>,
+    pub signature_valid: Option<bool>,
+    pub outcome: Option<CallbackOutcome>,
+    pub notification_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::user::UserRole;
+use crate::utils::new_id;
+
+/// A role held for a limited time on top of the user's own.
+///
+/// While open and unexpired it raises the user's effective role; the role
+/// stored on the account never changes, so expiry needs nothing restored.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleGrant {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub role: UserRole,
+    pub granted_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Set once the grant has expired and been closed, or was revoked early
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl RoleGrant {
+    pub fn new(user_id: Uuid, role: UserRole, granted_by: Option<Uuid>, expires_at: DateTime<Utc>) -> Self {
+        Self {
+            id: new_id(),
+            user_id,
+            role,
+            granted_by,
+            created_at: Utc::now(),
+            expires_at,
+            ended_at: None,
+        }
+    }
+
+    pub fn is_active(&self, at: DateTime<Utc>) -> bool {
+        self.ended_at.is_none() && self.expires_at > at
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrantTemporaryRoleRequest {
+    pub role: UserRole,
+    /// How long the role is held for
+    pub duration_secs: u64,
+}
@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::user::UserRole;
+use crate::utils::new_id;
+
+const MAX_RATIONALE_LENGTH: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoleRequestStatus {
+    Pending,
+    Approved,
+    Denied,
+}
+
+impl RoleRequestStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoleRequestStatus::Pending => "pending",
+            RoleRequestStatus::Approved => "approved",
+            RoleRequestStatus::Denied => "denied",
+        }
+    }
+}
+
+impl FromStr for RoleRequestStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pending" => Ok(RoleRequestStatus::Pending),
+            "approved" => Ok(RoleRequestStatus::Approved),
+            "denied" => Ok(RoleRequestStatus::Denied),
+            other => Err(format!("Unknown role request status: {}", other)),
+        }
+    }
+}
+
+/// A request to raise a user's role, waiting for someone allowed to grant it.
+///
+/// Users ask for themselves, or someone who manages their current role asks
+/// on their behalf. Whoever decides must be able to manage both the user's
+/// role at the time and the role asked for, and may not be the requester.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleRequest {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub requested_by: Uuid,
+    /// The user's role when the request was made
+    pub current_role: UserRole,
+    pub requested_role: UserRole,
+    pub rationale: String,
+    pub status: RoleRequestStatus,
+    pub decided_by: Option<Uuid>,
+    pub decision_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+impl RoleRequest {
+    pub fn new(user_id: Uuid, requested_by: Uuid, current_role: UserRole, request: CreateRoleRequest) -> Self {
+        Self {
+            id: new_id(),
+            user_id,
+            requested_by,
+            current_role,
+            requested_role: request.role,
+            rationale: request.rationale.trim().to_string(),
+            status: RoleRequestStatus::Pending,
+            decided_by: None,
+            decision_note: None,
+            created_at: Utc::now(),
+            decided_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateRoleRequest {
+    /// The user whose role should change; the requester when omitted
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
+    pub role: UserRole,
+    pub rationale: String,
+}
+
+impl CreateRoleRequest {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let rationale = self.rationale.trim();
+        if rationale.is_empty() {
+            errors.push("Rationale is required".to_string());
+        }
+        if rationale.chars().count() > MAX_RATIONALE_LENGTH {
+            errors.push(format!("Rationale must be at most {} characters", MAX_RATIONALE_LENGTH));
+        }
+        errors
+    }
+}
+
+/// An approver's answer to a pending request
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoleRequestDecision {
+    pub approve: bool,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl RoleRequestDecision {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let note = self.note.as_deref().map(str::trim).unwrap_or_default();
+        if !self.approve && note.is_empty() {
+            errors.push("A note is required when denying a request".to_string());
+        }
+        if note.chars().count() > MAX_RATIONALE_LENGTH {
+            errors.push(format!("Note must be at most {} characters", MAX_RATIONALE_LENGTH));
+        }
+        errors
+    }
+}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use webauthn_rs::prelude::PublicKeyCredential;
+
+use super::user::User;
+
+/// A WebAuthn authenticator (security key or passkey) registered as a second factor
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Authenticator {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Label chosen by the user, e.g. "Work laptop"
+    pub name: String,
+    /// Base64url credential id reported by the authenticator
+    pub credential_id: String,
+    /// Serialized credential with its public key and signature counter
+    #[serde(skip_serializing)]
+    pub credential: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Second factors a user has set up
+#[derive(Debug, Clone, Serialize)]
+pub struct SecondFactorSummary {
+    pub authenticators: Vec<Authenticator>,
+    pub backup_codes_remaining: i64,
+    pub totp_enabled: bool,
+}
+
+impl SecondFactorSummary {
+    pub fn is_enrolled(&self) -> bool {
+        !self.authenticators.is_empty() || self.backup_codes_remaining > 0 || self.totp_enabled
+    }
+}
+
+/// Options for the browser's WebAuthn call, plus the id that ties its answer back to this ceremony
+#[derive(Debug, Clone, Serialize)]
+pub struct WebAuthnChallenge<T> {
+    pub ceremony_id: Uuid,
+    pub options: T,
+}
+
+/// What a user signing in offers as their second factor
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecondFactorProof {
+    /// A code from their authenticator app, or one of their backup codes
+    Code(String),
+    /// One of their authenticators' answer to a sign-in challenge
+    Assertion {
+        ceremony_id: Uuid,
+        credential: PublicKeyCredential,
+    },
+}
+
+/// Where a sign-in vouched for by an external identity provider stands
+#[derive(Debug)]
+pub enum ExternalLogin {
+    SignedIn(User),
+    /// The account signs in with a second factor, sent with this single-use
+    /// ticket to `UserService::finish_external_login`
+    SecondFactorRequired { ticket: String },
+}
+
+/// A TOTP authenticator app enrolled by a user
+#[derive(Debug, Clone, FromRow)]
+pub struct TotpCredential {
+    pub user_id: Uuid,
+    /// Encrypted base32 shared secret
+    pub secret: String,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What an authenticator app needs to be set up; shown to the user once
+#[derive(Debug, Clone, Serialize)]
+pub struct TotpEnrollment {
+    /// Base32 secret for manual entry
+    pub secret: String,
+    /// `otpauth://` URI the QR code encodes
+    pub provisioning_uri: String,
+    /// Base64 PNG of the QR code
+    pub qr_code: String,
+}
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::error::{AppError, AppResult};
+use crate::utils::new_id;
+
+const MAX_NAME_LENGTH: usize = 100;
+
+/// What a service token lets its caller do; unlike API key scopes, none includes another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ServiceScope {
+    #[serde(rename = "users:read")]
+    UsersRead,
+    #[serde(rename = "sessions:revoke")]
+    SessionsRevoke,
+}
+
+impl ServiceScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServiceScope::UsersRead => "users:read",
+            ServiceScope::SessionsRevoke => "sessions:revoke",
+        }
+    }
+}
+
+impl FromStr for ServiceScope {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "users:read" => Ok(ServiceScope::UsersRead),
+            "sessions:revoke" => Ok(ServiceScope::SessionsRevoke),
+            other => Err(format!("Unknown service scope: {}", other)),
+        }
+    }
+}
+
+/// A machine identity for internal callers.
+///
+/// It authenticates with its client secret, of which only a hash is
+/// stored, and gets short-lived tokens limited to the scopes it was created
+/// with. A disabled account gets no new tokens; those already issued run
+/// out within their short lifetime.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceAccount {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub name: String,
+    pub scopes: Vec<ServiceScope>,
+    #[serde(skip_serializing)]
+    pub secret_hash: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub disabled_at: Option<DateTime<Utc>>,
+}
+
+impl ServiceAccount {
+    pub fn new(
+        tenant_id: String,
+        name: String,
+        scopes: Vec<ServiceScope>,
+        secret_hash: String,
+        created_by: Uuid,
+    ) -> Self {
+        Self {
+            id: new_id(),
+            tenant_id,
+            name,
+            scopes: distinct(&scopes),
+            secret_hash,
+            created_by: Some(created_by),
+            created_at: Utc::now(),
+            last_used_at: None,
+            disabled_at: None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.disabled_at.is_none()
+    }
+
+    /// The scopes a token asking for `requested` gets; asking for any the account lacks is refused
+    pub fn token_scopes(&self, requested: Option<&[ServiceScope]>) -> AppResult<Vec<ServiceScope>> {
+        let Some(requested) = requested else {
+            return Ok(self.scopes.clone());
+        };
+        if let Some(missing) = requested.iter().find(|scope| !self.scopes.contains(scope)) {
+            return Err(AppError::Forbidden(format!(
+                "Service account {} lacks the {} scope",
+                self.id,
+                missing.as_str()
+            )));
+        }
+        Ok(distinct(requested))
+    }
+}
+
+/// Scopes in their first-listed order, each once
+fn distinct(scopes: &[ServiceScope]) -> Vec<ServiceScope> {
+    let mut unique = Vec::with_capacity(scopes.len());
+    for scope in scopes {
+        if !unique.contains(scope) {
+            unique.push(*scope);
+        }
+    }
+    unique
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateServiceAccountRequest {
+    pub name: String,
+    pub scopes: Vec<ServiceScope>,
+}
+
+impl CreateServiceAccountRequest {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let name = self.name.trim();
+        if name.is_empty() {
+            errors.push("Name is required".to_string());
+        } else if name.chars().count() > MAX_NAME_LENGTH {
+            errors.push(format!("Name must be at most {} characters", MAX_NAME_LENGTH));
+        }
+        if self.scopes.is_empty() {
+            errors.push("At least one scope is required".to_string());
+        }
+        errors
+    }
+}
+
+/// An account as returned when it is created; the only time `client_secret` is visible
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedServiceAccount {
+    #[serde(flatten)]
+    pub account: ServiceAccount,
+    pub client_secret: String,
+}
+
+/// Client credentials traded for a service token
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServiceTokenRequest {
+    pub client_id: Uuid,
+    pub client_secret: String,
+    /// Scopes the token should carry, all of the account's when omitted
+    #[serde(default)]
+    pub scopes: Option<Vec<ServiceScope>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceToken {
+    pub access_token: String,
+    pub token_type: &'static str,
+    /// Seconds until the token expires
+    pub expires_in: u64,
+    pub scopes: Vec<ServiceScope>,
+}
+
+/// The service principal a request was authenticated as, taken from a verified service token
+#[derive(Debug, Clone)]
+pub struct ServiceContext {
+    pub service_account_id: Uuid,
+    pub tenant_id: String,
+    pub scopes: Vec<ServiceScope>,
+    pub token_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ServiceContext {
+    pub fn require(&self, scope: ServiceScope) -> AppResult<()> {
+        if !self.scopes.contains(&scope) {
+            return Err(AppError::Forbidden(format!("Service token lacks the {} scope", scope.as_str())));
+        }
+        Ok(())
+    }
+}
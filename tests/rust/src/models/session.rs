@@ -0,0 +1,140 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::utils::new_id;
+
+/// Client details captured when a user signs in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientInfo {
+    pub user_agent: String,
+    pub ip_address: Option<String>,
+    /// Country code the edge proxy geolocated the address to
+    #[serde(default)]
+    pub country: Option<String>,
+}
+
+impl ClientInfo {
+    /// Coarse browser name from the user agent
+    pub fn browser(&self) -> &'static str {
+        let ua = self.user_agent.as_str();
+        // Order matters: Edge and Chrome both claim to be Safari, Edge claims to be Chrome
+        if ua.contains("Edg/") {
+            "Edge"
+        } else if ua.contains("Firefox/") {
+            "Firefox"
+        } else if ua.contains("Chrome/") || ua.contains("CriOS/") {
+            "Chrome"
+        } else if ua.contains("Safari/") {
+            "Safari"
+        } else {
+            "Unknown"
+        }
+    }
+
+    /// Coarse operating system name from the user agent
+    pub fn os(&self) -> &'static str {
+        let ua = self.user_agent.as_str();
+        if ua.contains("Android") {
+            "Android"
+        } else if ua.contains("iPhone") || ua.contains("iPad") {
+            "iOS"
+        } else if ua.contains("Windows") {
+            "Windows"
+        } else if ua.contains("Mac OS X") {
+            "macOS"
+        } else if ua.contains("Linux") {
+            "Linux"
+        } else {
+            "Unknown"
+        }
+    }
+}
+
+/// A browser or app a user has signed in from
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Device {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_agent: String,
+    pub browser: String,
+    pub os: String,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_active_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Device {
+    pub fn new(user_id: Uuid, client: &ClientInfo) -> Self {
+        let now = Utc::now();
+
+        Self {
+            id: new_id(),
+            user_id,
+            user_agent: client.user_agent.clone(),
+            browser: client.browser().to_string(),
+            os: client.os().to_string(),
+            ip_address: client.ip_address.clone(),
+            created_at: now,
+            last_active_at: now,
+            revoked_at: None,
+        }
+    }
+}
+
+/// A signed-in session on a device
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub device_id: Uuid,
+    /// Client the session was opened from; kept with the live session rather than in Postgres
+    #[sqlx(default)]
+    #[serde(default)]
+    pub user_agent: String,
+    /// Address of the most recent request made with the session
+    #[sqlx(default)]
+    #[serde(default)]
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_active_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Inactivity after which the session ends early, from its role's policy
+    #[sqlx(default)]
+    #[serde(default)]
+    pub idle_timeout_secs: Option<i64>,
+}
+
+impl Session {
+    pub fn new(device: &Device, lifetime: Duration, idle_timeout: Option<Duration>) -> Self {
+        let now = Utc::now();
+
+        Self {
+            id: new_id(),
+            user_id: device.user_id,
+            device_id: device.id,
+            user_agent: device.user_agent.clone(),
+            ip_address: device.ip_address.clone(),
+            created_at: now,
+            last_active_at: now,
+            expires_at: now + lifetime,
+            revoked_at: None,
+            idle_timeout_secs: idle_timeout.map(|timeout| timeout.num_seconds()),
+        }
+    }
+
+    /// When the session ends unless it is used again first
+    pub fn ends_at(&self) -> DateTime<Utc> {
+        match self.idle_timeout_secs {
+            Some(secs) => self.expires_at.min(self.last_active_at + Duration::seconds(secs)),
+            None => self.expires_at,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.ends_at() > Utc::now()
+    }
+}
@@ -0,0 +1,221 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use super::error::{AppError, AppResult};
+use super::user::UserPreferences;
+
+/// Rows per page in list views
+pub const PAGE_SIZE: Setting<i64> = Setting::new("page_size");
+/// Denser layout with less padding
+pub const COMPACT_VIEW: Setting<bool> = Setting::new("compact_view");
+/// How often summary emails are sent
+pub const DIGEST_FREQUENCY: Setting<String> = Setting::new("digest_frequency");
+/// Where the app opens after sign-in
+pub const START_PAGE: Setting<String> = Setting::new("start_page");
+
+/// Values a setting accepts
+#[derive(Debug, Clone)]
+pub enum SettingKind {
+    Bool,
+    Integer { min: i64, max: i64 },
+    Text { max_length: usize },
+    Choice(&'static [&'static str]),
+}
+
+impl SettingKind {
+    fn check(&self, key: &str, value: &Value) -> Result<(), String> {
+        match (self, value) {
+            (SettingKind::Bool, Value::Bool(_)) => Ok(()),
+            (SettingKind::Integer { min, max }, Value::Number(number)) => match number.as_i64() {
+                Some(n) if (*min..=*max).contains(&n) => Ok(()),
+                _ => Err(format!("Setting {} must be a whole number between {} and {}", key, min, max)),
+            },
+            (SettingKind::Text { max_length }, Value::String(text)) if text.chars().count() <= *max_length => Ok(()),
+            (SettingKind::Text { max_length }, Value::String(_)) => {
+                Err(format!("Setting {} must be at most {} characters", key, max_length))
+            }
+            (SettingKind::Choice(choices), Value::String(choice)) if choices.contains(&choice.as_str()) => Ok(()),
+            (SettingKind::Choice(choices), _) => {
+                Err(format!("Setting {} must be one of: {}", key, choices.join(", ")))
+            }
+            (kind, _) => Err(format!("Setting {} must be {}", key, kind.describe())),
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            SettingKind::Bool => "true or false",
+            SettingKind::Integer { .. } => "a whole number",
+            SettingKind::Text { .. } => "text",
+            SettingKind::Choice(_) => "one of its choices",
+        }
+    }
+}
+
+/// A known setting: its key, what it accepts and its value when unset
+#[derive(Debug, Clone)]
+pub struct SettingDefinition {
+    pub key: &'static str,
+    pub kind: SettingKind,
+    pub default: Value,
+    /// Keys older clients stored this setting under
+    pub legacy_keys: &'static [&'static str],
+}
+
+/// Key of a known setting together with the type its value reads as
+pub struct Setting<T> {
+    key: &'static str,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T> Setting<T> {
+    pub const fn new(key: &'static str) -> Self {
+        Self { key, value: PhantomData }
+    }
+
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+}
+
+/// The settings `UserPreferences::custom_settings` may hold.
+///
+/// Code reads and writes settings through typed `Setting` keys, so a typo
+/// is a compile error rather than a setting that silently never applies.
+/// Stored values that no longer pass validation read as the default, and
+/// values under a legacy key are moved to the current one by `migrate`.
+#[derive(Debug, Clone, Default)]
+pub struct SettingsRegistry {
+    definitions: HashMap<&'static str, SettingDefinition>,
+}
+
+impl SettingsRegistry {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The settings this deployment knows about
+    pub fn standard() -> Self {
+        Self::new()
+            .register(SettingDefinition {
+                key: PAGE_SIZE.key(),
+                kind: SettingKind::Integer { min: 10, max: 200 },
+                default: Value::from(25),
+                legacy_keys: &["pageSize", "items_per_page"],
+            })
+            .register(SettingDefinition {
+                key: COMPACT_VIEW.key(),
+                kind: SettingKind::Bool,
+                default: Value::Bool(false),
+                legacy_keys: &["compactMode", "compact"],
+            })
+            .register(SettingDefinition {
+                key: DIGEST_FREQUENCY.key(),
+                kind: SettingKind::Choice(&["daily", "weekly", "never"]),
+                default: Value::from("weekly"),
+                legacy_keys: &["digestFrequency"],
+            })
+            .register(SettingDefinition {
+                key: START_PAGE.key(),
+                kind: SettingKind::Text { max_length: 200 },
+                default: Value::from("/"),
+                legacy_keys: &["homePage"],
+            })
+    }
+
+    pub fn register(mut self, definition: SettingDefinition) -> Self {
+        self.definitions.insert(definition.key, definition);
+        self
+    }
+
+    pub fn definition(&self, key: &str) -> Option<&SettingDefinition> {
+        self.definitions.get(key)
+    }
+
+    /// The user's value for `setting`, or its default when unset or no longer valid
+    pub fn get<T: DeserializeOwned>(&self, preferences: &UserPreferences, setting: &Setting<T>) -> AppResult<T> {
+        let definition = self.require(setting.key)?;
+        let stored = std::iter::once(definition.key)
+            .chain(definition.legacy_keys.iter().copied())
+            .find_map(|key| preferences.custom_settings.get(key))
+            .filter(|value| definition.kind.check(definition.key, value).is_ok());
+        let value = stored.unwrap_or(&definition.default);
+        serde_json::from_value(value.clone())
+            .map_err(|e| AppError::Internal(format!("Setting {} does not read as its type: {}", setting.key, e)))
+    }
+
+    pub fn set<T: Serialize>(
+        &self,
+        preferences: &mut UserPreferences,
+        setting: &Setting<T>,
+        value: T,
+    ) -> AppResult<()> {
+        let value = serde_json::to_value(value)
+            .map_err(|e| AppError::Internal(format!("Unserializable setting {}: {}", setting.key, e)))?;
+        self.set_value(preferences, setting.key, value)
+    }
+
+    /// Set a setting from untyped input such as a request body
+    pub fn set_value(&self, preferences: &mut UserPreferences, key: &str, value: Value) -> AppResult<()> {
+        let definition = self
+            .definition(key)
+            .ok_or_else(|| AppError::Validation(vec![format!("Unknown setting: {}", key)]))?;
+        definition
+            .kind
+            .check(key, &value)
+            .map_err(|e| AppError::Validation(vec![e]))?;
+        for legacy in definition.legacy_keys {
+            preferences.custom_settings.remove(*legacy);
+        }
+        preferences.custom_settings.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Move values stored under legacy keys to their current key, returning the keys moved.
+    ///
+    /// A value already under the current key wins over a legacy one.
+    pub fn migrate(&self, preferences: &mut UserPreferences) -> Vec<String> {
+        let mut moved = Vec::new();
+        for definition in self.definitions.values() {
+            for legacy in definition.legacy_keys {
+                let Some(value) = preferences.custom_settings.remove(*legacy) else {
+                    continue;
+                };
+                preferences
+                    .custom_settings
+                    .entry(definition.key.to_string())
+                    .or_insert(value);
+                moved.push(legacy.to_string());
+            }
+        }
+        moved
+    }
+
+    /// Problems with the settings of `updated`.
+    ///
+    /// Keys nobody registered are refused unless `current` already holds
+    /// them, so accounts carrying settings from before the registry can
+    /// still save their preferences.
+    pub fn validate_update(&self, current: &UserPreferences, updated: &UserPreferences) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut keys: Vec<&String> = updated.custom_settings.keys().collect();
+        keys.sort();
+        for key in keys {
+            let value = &updated.custom_settings[key];
+            match self.definition(key) {
+                Some(definition) => errors.extend(definition.kind.check(key, value).err()),
+                None if current.custom_settings.contains_key(key) => {}
+                None => errors.push(format!("Unknown setting: {}", key)),
+            }
+        }
+        errors
+    }
+
+    fn require(&self, key: &str) -> AppResult<&SettingDefinition> {
+        self.definition(key)
+            .ok_or_else(|| AppError::Internal(format!("Setting {} is not registered", key)))
+    }
+}
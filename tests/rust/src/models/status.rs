@@ -0,0 +1,62 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+
+/// Outcome of probing one component once
+#[derive(Debug, Clone)]
+pub struct HealthSample {
+    pub component: String,
+    pub healthy: bool,
+    pub latency_ms: i64,
+    /// Kept for operators; never shown on the public status page
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Health of the service as a whole
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverallStatus {
+    Operational,
+    /// Some components are down
+    PartialOutage,
+    /// Every component is down
+    MajorOutage,
+}
+
+/// A stretch of failed checks, from the first failure to the next success
+#[derive(Debug, Clone, Serialize)]
+pub struct Incident {
+    pub component: String,
+    pub started_at: DateTime<Utc>,
+    /// None while the component is still down
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub duration_secs: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyUptime {
+    pub date: NaiveDate,
+    pub uptime_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentStatus {
+    pub name: String,
+    pub operational: bool,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    /// Share of healthy checks over the whole history
+    pub uptime_percent: f64,
+    /// Only days with checks are listed
+    pub daily: Vec<DailyUptime>,
+}
+
+/// Everything a public status page shows
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusPage {
+    pub status: OverallStatus,
+    pub components: Vec<ComponentStatus>,
+    /// Newest first
+    pub incidents: Vec<Incident>,
+    pub history_days: i64,
+    pub generated_at: DateTime<Utc>,
+}
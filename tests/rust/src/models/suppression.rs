@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::utils::new_id;
+
+/// A user or address that must never be contacted.
+///
+/// Entries are checked by the dispatcher just before delivery, so adding
+/// one also stops notifications that are already queued.
+#[derive(Debug, Clone, Serialize)]
+pub struct Suppression {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub user_id: Option<Uuid>,
+    /// Stored lowercase; matched against the recipient of every channel
+    pub email: Option<String>,
+    pub reason: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Suppression {
+    pub fn new(tenant_id: String, request: CreateSuppressionRequest, created_by: Option<Uuid>) -> Self {
+        Self {
+            id: new_id(),
+            tenant_id,
+            user_id: request.user_id,
+            email: request.email.map(|email| email.trim().to_lowercase()),
+            reason: request.reason.trim().to_string(),
+            created_by,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateSuppressionRequest {
+    pub user_id: Option<Uuid>,
+    pub email: Option<String>,
+    pub reason: String,
+}
+
+impl CreateSuppressionRequest {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let email = self.email.as_deref().map(str::trim);
+        if self.user_id.is_none() && email.is_none() {
+            errors.push("A user id or an email is required".to_string());
+        }
+        if email.is_some_and(|email| !email.contains('@')) {
+            errors.push("Email is invalid".to_string());
+        }
+        if self.reason.trim().is_empty() {
+            errors.push("Reason is required".to_string());
+        }
+        errors
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::error::AppResult;
+use super::notification::{NotificationChannel, NotificationType};
+use crate::utils::template;
+
+/// Content for every channel a notification type can be sent on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationTemplate {
+    pub key: String,
+    pub notification_type: NotificationType,
+    pub email_subject: String,
+    pub email_body: String,
+    pub email_html: Option<String>,
+    pub in_app_title: String,
+    pub in_app_body: String,
+    /// Revision currently in effect
+    pub version: i32,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A stored revision of a template; `template.updated_at` is when it was written
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateRevision {
+    #[serde(flatten)]
+    pub template: NotificationTemplate,
+    /// Set when this revision restored the content of an older one
+    pub rolled_back_from: Option<i32>,
+}
+
+/// A template rendered for one channel
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedTemplate {
+    pub channel: NotificationChannel,
+    pub subject: String,
+    pub body: String,
+    pub html_body: Option<String>,
+}
+
+/// Renderings of a template for every channel
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplatePreview {
+    pub template_key: String,
+    pub renderings: Vec<RenderedTemplate>,
+}
+
+impl NotificationTemplate {
+    pub const CHANNELS: [NotificationChannel; 2] = [NotificationChannel::Email, NotificationChannel::InApp];
+
+    #[tracing::instrument(name = "render", skip_all, fields(template = %self.key))]
+    pub fn render(
+        &self,
+        channel: NotificationChannel,
+        params: &HashMap<String, String>,
+    ) -> AppResult<RenderedTemplate> {
+        match channel {
+            NotificationChannel::Email => Ok(RenderedTemplate {
+                channel,
+                subject: template::render(&self.email_subject, params)?,
+                body: template::render(&self.email_body, params)?,
+                html_body: self
+                    .email_html
+                    .as_deref()
+                    .map(|html| template::render_html(html, params))
+                    .transpose()?,
+            }),
+            NotificationChannel::InApp => Ok(RenderedTemplate {
+                channel,
+                subject: template::render(&self.in_app_title, params)?,
+                body: template::render(&self.in_app_body, params)?,
+                html_body: None,
+            }),
+        }
+    }
+}
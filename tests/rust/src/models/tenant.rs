@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::error::{AppError, AppResult};
+
+const DEFAULT_TENANT: &str = "default";
+
+/// The tenant a request or background operation acts on behalf of
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TenantContext {
+    pub tenant_id: String,
+}
+
+impl TenantContext {
+    /// Tenant ids are lowercase slugs so they are safe in headers, keys and metric names
+    pub fn new(tenant_id: &str) -> AppResult<Self> {
+        let valid = !tenant_id.is_empty()
+            && tenant_id.len() <= 64
+            && tenant_id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if !valid {
+            return Err(AppError::Validation(vec![format!("Invalid tenant id: {}", tenant_id)]));
+        }
+        Ok(Self {
+            tenant_id: tenant_id.to_string(),
+        })
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.tenant_id == DEFAULT_TENANT
+    }
+}
+
+impl Default for TenantContext {
+    fn default() -> Self {
+        Self {
+            tenant_id: DEFAULT_TENANT.to_string(),
+        }
+    }
+}
+
+/// Look and sender identity of a tenant's outgoing notifications.
+///
+/// Stored rows only hold the fields a tenant overrides; unset fields fall
+/// back to the deployment defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TenantBranding {
+    pub tenant_id: String,
+    pub sender_address: Option<String>,
+    pub sender_name: Option<String>,
+    pub logo_url: Option<String>,
+    pub primary_color: Option<String>,
+    pub accent_color: Option<String>,
+    pub footer: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl TenantBranding {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if let Some(address) = &self.sender_address {
+            if !address.contains('@') {
+                errors.push("Sender address must be an email address".to_string());
+            }
+        }
+        if let Some(url) = &self.logo_url {
+            if !url.starts_with("https://") {
+                errors.push("Logo URL must use https".to_string());
+            }
+        }
+        for (field, color) in [("Primary color", &self.primary_color), ("Accent color", &self.accent_color)] {
+            if let Some(color) = color {
+                let hex = color.strip_prefix('#').unwrap_or("");
+                if !(hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit())) {
+                    errors.push(format!("{} must be a hex color like #1a73e8", field));
+                }
+            }
+        }
+        if self.footer.as_ref().is_some_and(|footer| footer.len() > 1000) {
+            errors.push("Footer must be at most 1000 characters".to_string());
+        }
+
+        errors
+    }
+
+    /// Fill every field this branding leaves unset from `fallback`
+    pub fn with_fallback(self, fallback: &TenantBranding) -> TenantBranding {
+        TenantBranding {
+            tenant_id: self.tenant_id,
+            sender_address: self.sender_address.or_else(|| fallback.sender_address.clone()),
+            sender_name: self.sender_name.or_else(|| fallback.sender_name.clone()),
+            logo_url: self.logo_url.or_else(|| fallback.logo_url.clone()),
+            primary_color: self.primary_color.or_else(|| fallback.primary_color.clone()),
+            accent_color: self.accent_color.or_else(|| fallback.accent_color.clone()),
+            footer: self.footer.or_else(|| fallback.footer.clone()),
+            updated_at: self.updated_at,
+        }
+    }
+
+    /// Mailbox to send from, in `Name <address>` form
+    pub fn sender(&self) -> Option<String> {
+        let address = self.sender_address.as_ref()?;
+        Some(match &self.sender_name {
+            Some(name) => format!("{} <{}>", name, address),
+            None => address.clone(),
+        })
+    }
+
+    /// Template parameters exposing the branding; always present so templates
+    /// can reference them without failing for tenants that set nothing
+    pub fn template_params(&self) -> HashMap<String, String> {
+        let value = |field: &Option<String>| field.clone().unwrap_or_default();
+        HashMap::from([
+            ("brand_name".to_string(), value(&self.sender_name)),
+            ("brand_logo_url".to_string(), value(&self.logo_url)),
+            ("brand_primary_color".to_string(), value(&self.primary_color)),
+            ("brand_accent_color".to_string(), value(&self.accent_color)),
+            ("brand_footer".to_string(), value(&self.footer)),
+        ])
+    }
+}
@@ -0,0 +1,78 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::session::ClientInfo;
+use crate::utils::new_id;
+
+pub const MAX_DEVICE_NAME_LEN: usize = 100;
+
+/// A browser whose sign-ins skip the second factor, named by the signed cookie it keeps
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TrustedDevice {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Label chosen by the user, or the browser and operating system
+    pub name: String,
+    pub browser: String,
+    pub os: String,
+    /// Address the device was trusted from
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl TrustedDevice {
+    pub fn new(user_id: Uuid, name: Option<String>, client: &ClientInfo, lifetime: Duration) -> Self {
+        let now = Utc::now();
+
+        Self {
+            id: new_id(),
+            user_id,
+            name: name.unwrap_or_else(|| format!("{} on {}", client.browser(), client.os())),
+            browser: client.browser().to_string(),
+            os: client.os().to_string(),
+            ip_address: client.ip_address.clone(),
+            created_at: now,
+            last_used_at: None,
+            expires_at: now + lifetime,
+            revoked_at: None,
+        }
+    }
+}
+
+/// A newly trusted device and the cookie value naming it, which is only ever set as a cookie
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedTrustedDevice {
+    #[serde(flatten)]
+    pub device: TrustedDevice,
+    #[serde(skip)]
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrustDeviceRequest {
+    pub name: Option<String>,
+    /// Authenticator or backup code, required when the user has two-factor sign-in enabled
+    pub code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenameDeviceRequest {
+    pub name: String,
+}
+
+/// A trimmed device name, refusing empty and overlong ones
+pub fn device_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Device name is required".to_string());
+    }
+    if name.chars().count() > MAX_DEVICE_NAME_LEN {
+        return Err(format!("Device name must be at most {} characters", MAX_DEVICE_NAME_LEN));
+    }
+    Ok(name.to_string())
+}
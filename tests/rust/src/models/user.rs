@@ -1,7 +1,15 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use uuid::Uuid;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use super::notification::{NotificationChannel, NotificationType};
+use crate::utils::{new_id, DeprecatedField, Deprecations};
 
 /// User role enumeration with hierarchical permissions
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -14,6 +22,8 @@ pub enum UserRole {
 }
 
 impl UserRole {
+    pub const ALL: [UserRole; 4] = [UserRole::User, UserRole::Moderator, UserRole::Admin, UserRole::SuperAdmin];
+
     /// Get the hierarchical level of the role (higher number = more permissions)
     pub fn level(&self) -> u8 {
         match self {
@@ -29,22 +39,68 @@ impl UserRole {
         self.level() > other.level()
     }
 
-    /// Get all permissions for this role
-    pub fn permissions(&self) -> Vec<&'static str> {
+    /// Serialized name of the role as stored in the database
+    pub fn as_str(&self) -> &'static str {
         match self {
-            UserRole::User => vec!["read"],
-            UserRole::Moderator => vec!["read", "write", "moderate"],
-            UserRole::Admin => vec!["read", "write", "moderate", "admin", "delete"],
-            UserRole::SuperAdmin => vec!["read", "write", "moderate", "admin", "delete", "super_admin"],
+            UserRole::User => "user",
+            UserRole::Moderator => "moderator",
+            UserRole::Admin => "admin",
+            UserRole::SuperAdmin => "superadmin",
         }
     }
+}
+
+impl FromStr for UserRole {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "user" => Ok(UserRole::User),
+            "moderator" => Ok(UserRole::Moderator),
+            "admin" => Ok(UserRole::Admin),
+            "superadmin" => Ok(UserRole::SuperAdmin),
+            other => Err(format!("Unknown user role: {}", other)),
+        }
+    }
+}
+
+/// Role spellings of the first API version, still accepted in requests
+pub static LEGACY_ROLE_NAMES: DeprecatedField = DeprecatedField {
+    name: "user.role.legacy_value",
+    deprecated_on: "2026-10-14",
+    sunset: "2027-04-14",
+    replacement: "one of user, moderator, admin, superadmin",
+};
 
-    /// Check if role has specific permission
-    pub fn has_permission(&self, permission: &str) -> bool {
-        self.permissions().contains(&permission)
+impl UserRole {
+    /// Parse a role sent by a client, accepting and reporting legacy spellings
+    pub fn from_request(value: &str) -> Result<Self, String> {
+        if let Ok(role) = value.parse() {
+            return Ok(role);
+        }
+        let role = match value.to_ascii_lowercase().replace('-', "_").as_str() {
+            "member" | "user" => UserRole::User,
+            "mod" | "moderator" => UserRole::Moderator,
+            "administrator" | "admin" => UserRole::Admin,
+            "super_admin" | "superadmin" => UserRole::SuperAdmin,
+            _ => return Err(format!("Unknown user role: {}", value)),
+        };
+        Deprecations::record(&LEGACY_ROLE_NAMES);
+        Ok(role)
     }
 }
 
+fn request_role<'de, D: Deserializer<'de>>(deserializer: D) -> Result<UserRole, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    UserRole::from_request(&raw).map_err(serde::de::Error::custom)
+}
+
+fn optional_request_role<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<UserRole>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|raw| UserRole::from_request(&raw).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
 /// User account status
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -63,10 +119,39 @@ impl UserStatus {
     pub fn can_authenticate(&self) -> bool {
         matches!(self, UserStatus::Active | UserStatus::Inactive)
     }
+
+    /// Serialized name of the status as stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserStatus::Active => "active",
+            UserStatus::Inactive => "inactive",
+            UserStatus::Suspended => "suspended",
+            UserStatus::Deleted => "deleted",
+        }
+    }
 }
 
-/// Main User struct with complex relationships
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl FromStr for UserStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "active" => Ok(UserStatus::Active),
+            "inactive" => Ok(UserStatus::Inactive),
+            "suspended" => Ok(UserStatus::Suspended),
+            "deleted" => Ok(UserStatus::Deleted),
+            other => Err(format!("Unknown user status: {}", other)),
+        }
+    }
+}
+
+/// Main User struct with complex relationships.
+///
+/// Serializing a user keeps every field, which the cache relies on. What
+/// leaves the service goes out as a `UserPublic`, and events carry users
+/// without their password hash. Debug output masks the hash and the
+/// metadata values.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct User {
     pub id: Uuid,
     pub email: String,
@@ -79,12 +164,101 @@ pub struct User {
     pub last_login: Option<DateTime<Utc>>,
     pub login_count: i64,
     pub failed_login_attempts: i32,
+    /// Set when failed sign-ins lock the account; sign-in is refused until then
+    #[serde(default)]
+    pub locked_until: Option<DateTime<Utc>>,
+    /// Empty in users read back from events
+    #[serde(default)]
     pub password_hash: String,
     pub metadata: HashMap<String, serde_json::Value>,
     pub preferences: UserPreferences,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
+    /// Incremented by every write; clients send it back to detect concurrent edits
+    #[serde(default)]
+    pub version: i64,
+    /// Region whose database stores the account; None for the home region
+    #[serde(default)]
+    pub region: Option<String>,
+}
+
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("User")
+            .field("id", &self.id)
+            .field("email", &self.email)
+            .field("username", &self.username)
+            .field("first_name", &self.first_name)
+            .field("last_name", &self.last_name)
+            .field("role", &self.role)
+            .field("status", &self.status)
+            .field("email_verified", &self.email_verified)
+            .field("last_login", &self.last_login)
+            .field("login_count", &self.login_count)
+            .field("failed_login_attempts", &self.failed_login_attempts)
+            .field("locked_until", &self.locked_until)
+            .field("password_hash", &"<redacted>")
+            .field("metadata", &self.metadata.keys().collect::<BTreeSet<_>>())
+            .field("preferences", &self.preferences)
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .field("deleted_at", &self.deleted_at)
+            .field("version", &self.version)
+            .field("region", &self.region)
+            .finish()
+    }
+}
+
+/// Serialize a user without its password hash, for `serialize_with` on data leaving the service
+pub fn serialize_without_secrets<S: Serializer>(user: &User, serializer: S) -> Result<S::Ok, S::Error> {
+    User {
+        password_hash: String::new(),
+        ..user.clone()
+    }
+    .serialize(serializer)
+}
+
+/// A user as shown outside the service; credentials and lockout counters stay internal
+#[derive(Debug, Clone, Serialize)]
+pub struct UserPublic {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub role: UserRole,
+    pub status: UserStatus,
+    pub email_verified: bool,
+    pub last_login: Option<DateTime<Utc>>,
+    pub metadata: HashMap<String, serde_json::Value>,
+    pub preferences: UserPreferences,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
+    pub region: Option<String>,
+}
+
+impl From<User> for UserPublic {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            first_name: user.first_name,
+            last_name: user.last_name,
+            role: user.role,
+            status: user.status,
+            email_verified: user.email_verified,
+            last_login: user.last_login,
+            metadata: user.metadata,
+            preferences: user.preferences,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            version: user.version,
+            region: user.region,
+        }
+    }
 }
 
 /// User preferences and settings
@@ -97,6 +271,9 @@ pub struct UserPreferences {
     pub email_notifications: bool,
     pub two_factor_enabled: bool,
     pub custom_settings: HashMap<String, serde_json::Value>,
+    /// Channels this user wants per notification type, in order, replacing the deployment's route
+    #[serde(default)]
+    pub channel_routing: HashMap<NotificationType, Vec<NotificationChannel>>,
 }
 
 impl Default for UserPreferences {
@@ -109,6 +286,7 @@ impl Default for UserPreferences {
             email_notifications: true,
             two_factor_enabled: false,
             custom_settings: HashMap::new(),
+            channel_routing: HashMap::new(),
         }
     }
 }
@@ -125,7 +303,7 @@ impl User {
         let now = Utc::now();
         
         Self {
-            id: Uuid::new_v4(),
+            id: new_id(),
             email,
             username,
             first_name,
@@ -136,12 +314,15 @@ impl User {
             last_login: None,
             login_count: 0,
             failed_login_attempts: 0,
+            locked_until: None,
             password_hash,
             metadata: HashMap::new(),
             preferences: UserPreferences::default(),
             created_at: now,
             updated_at: now,
             deleted_at: None,
+            version: 1,
+            region: None,
         }
     }
 
@@ -162,15 +343,7 @@ impl User {
 
     /// Check if user is currently locked out due to failed attempts
     pub fn is_locked_out(&self) -> bool {
-        self.failed_login_attempts >= 5
-    }
-
-    /// Check if user has a specific permission
-    pub fn has_permission(&self, permission: &str) -> bool {
-        if !self.can_authenticate() {
-            return false;
-        }
-        self.role.has_permission(permission)
+        self.locked_until.is_some_and(|until| until > Utc::now())
     }
 
     /// Record a successful login
@@ -178,18 +351,32 @@ impl User {
         self.last_login = Some(Utc::now());
         self.login_count += 1;
         self.failed_login_attempts = 0;
+        self.locked_until = None;
         self.updated_at = Utc::now();
     }
 
-    /// Record a failed login attempt
-    pub fn record_failed_login(&mut self) {
+    /// Record a failed login attempt, locking the account for `lockout` once
+    /// `threshold` attempts are reached; returns true for the attempt that locks it
+    pub fn record_failed_login(&mut self, threshold: i32, lockout: chrono::Duration) -> bool {
+        let now = Utc::now();
+        // Attempts before an expired lockout no longer count
+        if self.locked_until.is_some_and(|until| until <= now) {
+            self.failed_login_attempts = 0;
+            self.locked_until = None;
+        }
         self.failed_login_attempts += 1;
-        self.updated_at = Utc::now();
+        self.updated_at = now;
+        if self.failed_login_attempts >= threshold && self.locked_until.is_none() {
+            self.locked_until = Some(now + lockout);
+            return true;
+        }
+        false
     }
 
-    /// Reset failed login attempts
+    /// Reset failed login attempts, lifting any lockout
     pub fn reset_failed_attempts(&mut self) {
         self.failed_login_attempts = 0;
+        self.locked_until = None;
         self.updated_at = Utc::now();
     }
 
@@ -200,6 +387,13 @@ impl User {
         self.updated_at = Utc::now();
     }
 
+    /// Undo a soft delete; the account comes back active
+    pub fn restore(&mut self) {
+        self.status = UserStatus::Active;
+        self.deleted_at = None;
+        self.updated_at = Utc::now();
+    }
+
     /// Validate user data
     pub fn validate(&self) -> Vec<String> {
         let mut errors = Vec::new();
@@ -230,13 +424,21 @@ impl User {
 }
 
 /// Request struct for creating a new user
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CreateUserRequest {
     pub email: String,
     pub username: String,
     pub first_name: String,
     pub last_name: String,
+    /// None creates a passwordless account, as for identities from external providers.
+    /// Never serialized, so stored requests carry no password
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+    #[serde(deserialize_with = "request_role")]
     pub role: UserRole,
+    /// Region to store the account in; defaults to the tenant's region
+    #[serde(default)]
+    pub region: Option<String>,
 }
 
 impl CreateUserRequest {
@@ -247,10 +449,6 @@ impl CreateUserRequest {
             errors.push("Invalid email address".to_string());
         }
 
-        if self.username.len() < 3 {
-            errors.push("Username must be at least 3 characters".to_string());
-        }
-
         if self.first_name.is_empty() {
             errors.push("First name is required".to_string());
         }
@@ -263,6 +461,20 @@ impl CreateUserRequest {
     }
 }
 
+impl fmt::Debug for CreateUserRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateUserRequest")
+            .field("email", &self.email)
+            .field("username", &self.username)
+            .field("first_name", &self.first_name)
+            .field("last_name", &self.last_name)
+            .field("password", &self.password.is_some())
+            .field("role", &self.role)
+            .field("region", &self.region)
+            .finish()
+    }
+}
+
 /// Request struct for updating user data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateUserRequest {
@@ -270,6 +482,7 @@ pub struct UpdateUserRequest {
     pub username: Option<String>,
     pub first_name: Option<String>,
     pub last_name: Option<String>,
+    #[serde(default, deserialize_with = "optional_request_role")]
     pub role: Option<UserRole>,
     pub status: Option<UserStatus>,
     pub preferences: Option<UserPreferences>,
@@ -286,6 +499,14 @@ pub struct UserFilters {
     pub created_before: Option<DateTime<Utc>>,
     pub last_login_after: Option<DateTime<Utc>>,
     pub search_term: Option<String>,
+    /// Only users onboarded into this tenant, on top of the tenant of the
+    /// request when there is one; lets work outside requests pick a tenant
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    /// Order of the listing, most significant first; empty lists newest
+    /// first. Ties are always broken by id.
+    #[serde(default)]
+    pub sort: Vec<UserSort>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
@@ -310,8 +531,203 @@ impl UserFilters {
         self
     }
 
+    pub fn with_tenant(mut self, tenant_id: String) -> Self {
+        self.tenant_id = Some(tenant_id);
+        self
+    }
+
     pub fn with_limit(mut self, limit: i64) -> Self {
         self.limit = Some(limit);
         self
     }
-}
\ No newline at end of file
+
+    pub fn with_sort(mut self, sort: UserSort) -> Self {
+        self.sort.push(sort);
+        self
+    }
+
+    /// The order users are listed in, for merging listings fetched separately
+    pub fn compare(&self, a: &User, b: &User) -> Ordering {
+        let keys: &[UserSort] = if self.sort.is_empty() { &[UserSort::NEWEST_FIRST] } else { &self.sort };
+        keys.iter()
+            .fold(Ordering::Equal, |order, key| order.then_with(|| key.compare(a, b)))
+            .then_with(|| a.id.cmp(&b.id))
+    }
+
+    /// The segment of active users who signed up before `cutoff` and still have not verified their email
+    pub fn unverified_before(cutoff: DateTime<Utc>) -> Self {
+        Self {
+            status: Some(UserStatus::Active),
+            email_verified: Some(false),
+            created_before: Some(cutoff),
+            ..Default::default()
+        }
+    }
+}
+
+/// Field a user listing can be ordered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSortField {
+    CreatedAt,
+    /// Users who never signed in come last in either direction
+    LastLogin,
+    Username,
+    Email,
+}
+
+impl UserSortField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserSortField::CreatedAt => "created_at",
+            UserSortField::LastLogin => "last_login",
+            UserSortField::Username => "username",
+            UserSortField::Email => "email",
+        }
+    }
+}
+
+impl FromStr for UserSortField {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "created_at" => Ok(UserSortField::CreatedAt),
+            "last_login" => Ok(UserSortField::LastLogin),
+            "username" => Ok(UserSortField::Username),
+            "email" => Ok(UserSortField::Email),
+            other => Err(format!("Unknown sort field: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        }
+    }
+}
+
+/// One key of a user listing's order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserSort {
+    pub field: UserSortField,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+impl UserSort {
+    /// The order of listings that ask for none
+    pub const NEWEST_FIRST: UserSort = UserSort::desc(UserSortField::CreatedAt);
+
+    pub const fn asc(field: UserSortField) -> Self {
+        Self {
+            field,
+            direction: SortDirection::Asc,
+        }
+    }
+
+    pub const fn desc(field: UserSortField) -> Self {
+        Self {
+            field,
+            direction: SortDirection::Desc,
+        }
+    }
+
+    /// Compares text bytewise, as Postgres does under the `C` collation the listing sorts with
+    pub fn compare(&self, a: &User, b: &User) -> Ordering {
+        let directed = |order: Ordering| match self.direction {
+            SortDirection::Asc => order,
+            SortDirection::Desc => order.reverse(),
+        };
+        match self.field {
+            UserSortField::CreatedAt => directed(a.created_at.cmp(&b.created_at)),
+            UserSortField::Username => directed(a.username.cmp(&b.username)),
+            UserSortField::Email => directed(a.email.cmp(&b.email)),
+            UserSortField::LastLogin => match (a.last_login, b.last_login) {
+                (Some(a), Some(b)) => directed(a.cmp(&b)),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+        }
+    }
+}
+
+/// Position in the user listing, which is ordered newest first with ties
+/// broken by id, so every user has exactly one place in it.
+///
+/// A page continues after the last user of the previous one rather than
+/// counting past an offset, so users created or deleted meanwhile neither
+/// shift a page nor get repeated. Clients get it as an opaque string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl UserCursor {
+    /// Continue after `user`
+    pub fn after(user: &User) -> Self {
+        Self {
+            created_at: user.created_at,
+            id: user.id,
+        }
+    }
+}
+
+impl fmt::Display for UserCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = format!("{}:{}", self.created_at.timestamp_micros(), self.id.simple());
+        f.write_str(&URL_SAFE_NO_PAD.encode(position))
+    }
+}
+
+impl FromStr for UserCursor {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || "Invalid user cursor".to_string();
+        let decoded = URL_SAFE_NO_PAD.decode(value).map_err(|_| invalid())?;
+        let position = String::from_utf8(decoded).map_err(|_| invalid())?;
+        let (micros, id) = position.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            created_at: micros
+                .parse()
+                .ok()
+                .and_then(DateTime::from_timestamp_micros)
+                .ok_or_else(invalid)?,
+            id: id.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl Serialize for UserCursor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for UserCursor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A page of the user listing
+#[derive(Debug, Clone)]
+pub struct UserPage {
+    pub users: Vec<User>,
+    /// Pass back to fetch the next page; None on the last page
+    pub next_cursor: Option<UserCursor>,
+}
@@ -0,0 +1,95 @@
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AccountDeletion, AppResult};
+
+const DELETION_COLUMNS: &str =
+    "user_id, requested_at, erase_after, previous_status, cancelled_at, erased_at";
+
+/// Tracks account deletion requests through their grace period
+pub struct AccountDeletionRepository {
+    database: Arc<Database>,
+}
+
+impl AccountDeletionRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    /// Record a deletion request, replacing any earlier cancelled one
+    pub async fn schedule(&self, deletion: &AccountDeletion) -> AppResult<AccountDeletion> {
+        let sql = format!(
+            "INSERT INTO account_deletions (user_id, requested_at, erase_after, previous_status) \
+             VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (user_id) DO UPDATE SET \
+                requested_at = EXCLUDED.requested_at, erase_after = EXCLUDED.erase_after, \
+                previous_status = EXCLUDED.previous_status, cancelled_at = NULL \
+             WHERE account_deletions.cancelled_at IS NOT NULL \
+             RETURNING {}",
+            DELETION_COLUMNS
+        );
+        let scheduled = sqlx::query_as::<_, AccountDeletion>(&sql)
+            .bind(deletion.user_id)
+            .bind(deletion.requested_at)
+            .bind(deletion.erase_after)
+            .bind(&deletion.previous_status)
+            .fetch_optional(self.database.pool())
+            .await?;
+
+        // Already pending: keep the original schedule rather than extending it
+        match scheduled {
+            Some(scheduled) => Ok(scheduled),
+            None => Ok(self.find(deletion.user_id).await?.unwrap_or_else(|| deletion.clone())),
+        }
+    }
+
+    pub async fn find(&self, user_id: Uuid) -> AppResult<Option<AccountDeletion>> {
+        let sql = format!("SELECT {} FROM account_deletions WHERE user_id = $1", DELETION_COLUMNS);
+        let deletion = sqlx::query_as::<_, AccountDeletion>(&sql)
+            .bind(user_id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        Ok(deletion)
+    }
+
+    /// Cancel a pending deletion, returning it if one existed
+    pub async fn cancel(&self, user_id: Uuid) -> AppResult<Option<AccountDeletion>> {
+        let sql = format!(
+            "UPDATE account_deletions SET cancelled_at = NOW() \
+             WHERE user_id = $1 AND cancelled_at IS NULL AND erased_at IS NULL \
+             RETURNING {}",
+            DELETION_COLUMNS
+        );
+        let cancelled = sqlx::query_as::<_, AccountDeletion>(&sql)
+            .bind(user_id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        Ok(cancelled)
+    }
+
+    /// Pending deletions whose grace period has elapsed
+    pub async fn due(&self, limit: i64) -> AppResult<Vec<AccountDeletion>> {
+        let sql = format!(
+            "SELECT {} FROM account_deletions \
+             WHERE cancelled_at IS NULL AND erased_at IS NULL AND erase_after <= $1 \
+             ORDER BY erase_after LIMIT $2",
+            DELETION_COLUMNS
+        );
+        let due = sqlx::query_as::<_, AccountDeletion>(&sql)
+            .bind(Utc::now())
+            .bind(limit)
+            .fetch_all(self.database.pool())
+            .await?;
+        Ok(due)
+    }
+
+    pub async fn mark_erased(&self, user_id: Uuid) -> AppResult<()> {
+        sqlx::query("UPDATE account_deletions SET erased_at = NOW() WHERE user_id = $1")
+            .bind(user_id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, Announcement};
+
+const ANNOUNCEMENT_COLUMNS: &str =
+    "id, tenant_id, title, message, severity, audience, starts_at, ends_at, created_by, created_at";
+
+/// Persistence boundary for announcements and their dismissals
+#[async_trait]
+pub trait AnnouncementRepository: Send + Sync {
+    async fn create(&self, announcement: &Announcement) -> AppResult<()>;
+    async fn find_by_id(&self, id: Uuid) -> AppResult<Option<Announcement>>;
+    /// Announcements of the tenant live at `at`, newest first
+    async fn live(&self, tenant_id: &str, at: DateTime<Utc>) -> AppResult<Vec<Announcement>>;
+    /// End a live announcement at `at`; false if it had already ended
+    async fn end(&self, id: Uuid, at: DateTime<Utc>) -> AppResult<bool>;
+    /// Returns false when the user had already dismissed it
+    async fn dismiss(&self, announcement_id: Uuid, user_id: Uuid) -> AppResult<bool>;
+    /// Which of `announcement_ids` the user has dismissed
+    async fn dismissed(&self, user_id: Uuid, announcement_ids: &[Uuid]) -> AppResult<HashSet<Uuid>>;
+}
+
+pub struct PostgresAnnouncementRepository {
+    database: Arc<Database>,
+}
+
+impl PostgresAnnouncementRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl AnnouncementRepository for PostgresAnnouncementRepository {
+    async fn create(&self, announcement: &Announcement) -> AppResult<()> {
+        let audience = serde_json::to_value(&announcement.audience)
+            .map_err(|e| AppError::Internal(format!("Unserializable announcement audience: {}", e)))?;
+        sqlx::query(
+            "INSERT INTO announcements (id, tenant_id, title, message, severity, audience, starts_at, ends_at, \
+                created_by, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        )
+        .bind(announcement.id)
+        .bind(&announcement.tenant_id)
+        .bind(&announcement.title)
+        .bind(&announcement.message)
+        .bind(announcement.severity.as_str())
+        .bind(audience)
+        .bind(announcement.starts_at)
+        .bind(announcement.ends_at)
+        .bind(announcement.created_by)
+        .bind(announcement.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> AppResult<Option<Announcement>> {
+        let sql = format!("SELECT {} FROM announcements WHERE id = $1", ANNOUNCEMENT_COLUMNS);
+        let row = sqlx::query(&sql)
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.as_ref().map(map_announcement).transpose()
+    }
+
+    async fn live(&self, tenant_id: &str, at: DateTime<Utc>) -> AppResult<Vec<Announcement>> {
+        let sql = format!(
+            "SELECT {} FROM announcements \
+             WHERE tenant_id = $1 AND starts_at <= $2 AND (ends_at IS NULL OR ends_at > $2) \
+             ORDER BY starts_at DESC",
+            ANNOUNCEMENT_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(tenant_id)
+            .bind(at)
+            .fetch_all(self.database.pool())
+            .await?;
+        rows.iter().map(map_announcement).collect()
+    }
+
+    async fn end(&self, id: Uuid, at: DateTime<Utc>) -> AppResult<bool> {
+        let ended = sqlx::query(
+            "UPDATE announcements SET ends_at = $2 WHERE id = $1 AND (ends_at IS NULL OR ends_at > $2)",
+        )
+        .bind(id)
+        .bind(at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(ended.rows_affected() == 1)
+    }
+
+    async fn dismiss(&self, announcement_id: Uuid, user_id: Uuid) -> AppResult<bool> {
+        let inserted = sqlx::query(
+            "INSERT INTO announcement_dismissals (announcement_id, user_id) VALUES ($1, $2) \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(announcement_id)
+        .bind(user_id)
+        .execute(self.database.pool())
+        .await?;
+        Ok(inserted.rows_affected() == 1)
+    }
+
+    async fn dismissed(&self, user_id: Uuid, announcement_ids: &[Uuid]) -> AppResult<HashSet<Uuid>> {
+        if announcement_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT announcement_id FROM announcement_dismissals WHERE user_id = $1 AND announcement_id = ANY($2)",
+        )
+        .bind(user_id)
+        .bind(announcement_ids)
+        .fetch_all(self.database.pool())
+        .await?;
+        Ok(ids.into_iter().collect())
+    }
+}
+
+fn map_announcement(row: &PgRow) -> AppResult<Announcement> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt announcement row: {}", e));
+
+    Ok(Announcement {
+        id: row.try_get("id")?,
+        tenant_id: row.try_get("tenant_id")?,
+        title: row.try_get("title")?,
+        message: row.try_get("message")?,
+        severity: row.try_get::<String, _>("severity")?.parse().map_err(invalid)?,
+        audience: serde_json::from_value(row.try_get("audience")?).map_err(|e| invalid(e.to_string()))?,
+        starts_at: row.try_get("starts_at")?,
+        ends_at: row.try_get("ends_at")?,
+        created_by: row.try_get("created_by")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::query::Query;
+use sqlx::{Postgres, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{ApiKey, AppError, AppResult};
+
+const COLUMNS: &str = "id, tenant_id, name, prefix, key_hash, scope, created_by, created_at, last_used_at, \
+                       expires_at, revoked_at, rotated_from";
+
+/// API keys of every tenant, looked up by the hash of the key
+pub struct ApiKeyRepository {
+    database: Arc<Database>,
+}
+
+impl ApiKeyRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn create(&self, key: &ApiKey) -> AppResult<()> {
+        insert(key).execute(self.database.pool()).await?;
+        Ok(())
+    }
+
+    pub async fn find(&self, tenant_id: &str, id: Uuid) -> AppResult<Option<ApiKey>> {
+        let row = sqlx::query(&format!("SELECT {} FROM api_keys WHERE tenant_id = $1 AND id = $2", COLUMNS))
+            .bind(tenant_id)
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.as_ref().map(map_key).transpose()
+    }
+
+    pub async fn find_by_hash(&self, key_hash: &str) -> AppResult<Option<ApiKey>> {
+        let row = sqlx::query(&format!("SELECT {} FROM api_keys WHERE key_hash = $1", COLUMNS))
+            .bind(key_hash)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.as_ref().map(map_key).transpose()
+    }
+
+    /// Keys of a tenant, newest first, including revoked ones
+    pub async fn list(&self, tenant_id: &str) -> AppResult<Vec<ApiKey>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM api_keys WHERE tenant_id = $1 ORDER BY created_at DESC",
+            COLUMNS
+        ))
+        .bind(tenant_id)
+        .fetch_all(self.database.pool())
+        .await?;
+        rows.iter().map(map_key).collect()
+    }
+
+    pub async fn touch(&self, id: Uuid, at: DateTime<Utc>) -> AppResult<()> {
+        sqlx::query("UPDATE api_keys SET last_used_at = $2 WHERE id = $1")
+            .bind(id)
+            .bind(at)
+            .execute(self.database.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// False if the key was already revoked
+    pub async fn revoke(&self, id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+            .bind(id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Store `replacement` and have the key it replaces expire at `retire_at`
+    pub async fn rotate(&self, replacement: &ApiKey, retire_at: DateTime<Utc>) -> AppResult<()> {
+        let Some(previous) = replacement.rotated_from else {
+            return Err(AppError::Internal("Rotated API key does not name the key it replaces".to_string()));
+        };
+        let mut tx = self.database.pool().begin().await?;
+        let retired = sqlx::query(
+            "UPDATE api_keys SET expires_at = LEAST(COALESCE(expires_at, $2), $2) \
+             WHERE id = $1 AND revoked_at IS NULL",
+        )
+        .bind(previous)
+        .bind(retire_at)
+        .execute(&mut *tx)
+        .await?;
+        if retired.rows_affected() == 0 {
+            return Err(AppError::Conflict(format!("API key {} has been revoked", previous)));
+        }
+        insert(replacement).execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+fn insert(key: &ApiKey) -> Query<'_, Postgres, PgArguments> {
+    sqlx::query(
+        "INSERT INTO api_keys (id, tenant_id, name, prefix, key_hash, scope, created_by, created_at, \
+         expires_at, rotated_from) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+    )
+    .bind(key.id)
+    .bind(&key.tenant_id)
+    .bind(&key.name)
+    .bind(&key.prefix)
+    .bind(&key.key_hash)
+    .bind(key.scope.as_str())
+    .bind(key.created_by)
+    .bind(key.created_at)
+    .bind(key.expires_at)
+    .bind(key.rotated_from)
+}
+
+fn map_key(row: &PgRow) -> AppResult<ApiKey> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt API key row: {}", e));
+
+    Ok(ApiKey {
+        id: row.try_get("id")?,
+        tenant_id: row.try_get("tenant_id")?,
+        name: row.try_get("name")?,
+        prefix: row.try_get("prefix")?,
+        key_hash: row.try_get("key_hash")?,
+        scope: row.try_get::<String, _>("scope")?.parse().map_err(invalid)?,
+        created_by: row.try_get("created_by")?,
+        created_at: row.try_get("created_at")?,
+        last_used_at: row.try_get("last_used_at")?,
+        expires_at: row.try_get("expires_at")?,
+        revoked_at: row.try_get("revoked_at")?,
+        rotated_from: row.try_get("rotated_from")?,
+    })
+}
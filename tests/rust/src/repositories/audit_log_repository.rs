@@ -0,0 +1,96 @@
+use sqlx::postgres::PgRow;
+use sqlx::{Postgres, QueryBuilder, Row};
+use std::sync::Arc;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, AuditLog, AuditLogFilters};
+
+const DEFAULT_PAGE_SIZE: i64 = 100;
+
+const AUDIT_LOG_COLUMNS: &str = "id, actor_id, action, target_id, before, after, context, created_at";
+
+/// Compliance audit log of security-sensitive actions
+pub struct AuditLogRepository {
+    database: Arc<Database>,
+}
+
+impl AuditLogRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn record(&self, entry: &AuditLog) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO audit_log (id, actor_id, action, target_id, before, after, context, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(entry.id)
+        .bind(entry.actor_id)
+        .bind(entry.action.as_str())
+        .bind(entry.target_id)
+        .bind(&entry.before)
+        .bind(&entry.after)
+        .bind(&entry.context)
+        .bind(entry.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Entries matching `filters`, newest first
+    pub async fn query(&self, filters: &AuditLogFilters) -> AppResult<Vec<AuditLog>> {
+        let mut query =
+            QueryBuilder::<Postgres>::new(format!("SELECT {} FROM audit_log WHERE TRUE", AUDIT_LOG_COLUMNS));
+        push_filters(&mut query, filters);
+        query.push(" ORDER BY created_at DESC, id");
+        query
+            .push(" LIMIT ")
+            .push_bind(filters.limit.unwrap_or(DEFAULT_PAGE_SIZE));
+        query.push(" OFFSET ").push_bind(filters.offset.unwrap_or(0));
+
+        let rows = query.build().fetch_all(self.database.pool()).await?;
+        rows.iter().map(map_entry).collect()
+    }
+
+    pub async fn count(&self, filters: &AuditLogFilters) -> AppResult<i64> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM audit_log WHERE TRUE");
+        push_filters(&mut query, filters);
+        Ok(query
+            .build_query_scalar::<i64>()
+            .fetch_one(self.database.pool())
+            .await?)
+    }
+}
+
+fn push_filters(query: &mut QueryBuilder<'_, Postgres>, filters: &AuditLogFilters) {
+    if let Some(actor_id) = filters.actor_id {
+        query.push(" AND actor_id = ").push_bind(actor_id);
+    }
+    if let Some(target_id) = filters.target_id {
+        query.push(" AND target_id = ").push_bind(target_id);
+    }
+    if let Some(action) = filters.action {
+        query.push(" AND action = ").push_bind(action.as_str());
+    }
+    if let Some(from) = filters.from {
+        query.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = filters.to {
+        query.push(" AND created_at < ").push_bind(to);
+    }
+}
+
+fn map_entry(row: &PgRow) -> AppResult<AuditLog> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt audit log row: {}", e));
+
+    Ok(AuditLog {
+        id: row.try_get("id")?,
+        actor_id: row.try_get("actor_id")?,
+        action: row.try_get::<String, _>("action")?.parse().map_err(invalid)?,
+        target_id: row.try_get("target_id")?,
+        before: row.try_get("before")?,
+        after: row.try_get("after")?,
+        context: row.try_get("context")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, AuditEvent};
+
+/// Per-user audit log of account events
+pub struct AuditRepository {
+    database: Arc<Database>,
+}
+
+impl AuditRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn record(&self, event: &AuditEvent) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO user_audit_events (user_id, actor_id, action, details, created_at) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(event.user_id)
+        .bind(event.actor_id)
+        .bind(event.action.as_str())
+        .bind(&event.details)
+        .bind(event.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// The user's most recent `limit` events older than `before`, newest first
+    pub async fn for_user(
+        &self,
+        user_id: Uuid,
+        before: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> AppResult<Vec<AuditEvent>> {
+        let rows = sqlx::query(
+            "SELECT user_id, actor_id, action, details, created_at FROM user_audit_events \
+             WHERE user_id = $1 AND ($2::timestamptz IS NULL OR created_at < $2) \
+             ORDER BY created_at DESC, id DESC LIMIT $3",
+        )
+        .bind(user_id)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        rows.iter().map(map_event).collect()
+    }
+}
+
+fn map_event(row: &PgRow) -> AppResult<AuditEvent> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt audit event row: {}", e));
+
+    Ok(AuditEvent {
+        user_id: row.try_get("user_id")?,
+        actor_id: row.try_get("actor_id")?,
+        action: row.try_get::<String, _>("action")?.parse().map_err(invalid)?,
+        details: row.try_get("details")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
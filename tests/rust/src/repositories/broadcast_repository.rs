@@ -0,0 +1,128 @@
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, Broadcast, BroadcastSummary};
+
+const BROADCAST_COLUMNS: &str = "id, tenant_id, target, message, cursor, targeted, queued, \
+    skipped, status, error, created_at, completed_at";
+
+/// Stores broadcasts with the cursor needed to resume them
+pub struct BroadcastRepository {
+    database: Arc<Database>,
+}
+
+impl BroadcastRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn create(&self, broadcast: &Broadcast) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO broadcasts (id, tenant_id, target, message, status, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(broadcast.id)
+        .bind(&broadcast.tenant_id)
+        .bind(to_json(&broadcast.target)?)
+        .bind(to_json(&broadcast.message)?)
+        .bind(broadcast.status.as_str())
+        .bind(broadcast.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Persist cursor, counters and status; a cancelled broadcast keeps its status
+    pub async fn save_progress(&self, broadcast: &Broadcast) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE broadcasts SET cursor = $2, targeted = $3, queued = $4, skipped = $5, \
+                status = CASE WHEN status = 'cancelled' THEN status ELSE $6 END, error = $7, \
+                completed_at = COALESCE($8, completed_at) \
+             WHERE id = $1",
+        )
+        .bind(broadcast.id)
+        .bind(broadcast.cursor)
+        .bind(broadcast.summary.targeted as i32)
+        .bind(broadcast.summary.queued as i32)
+        .bind(broadcast.summary.skipped as i32)
+        .bind(broadcast.status.as_str())
+        .bind(&broadcast.error)
+        .bind(broadcast.completed_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Stop a running or interrupted broadcast; false if it had already finished
+    pub async fn cancel(&self, tenant_id: &str, id: Uuid) -> AppResult<bool> {
+        let cancelled = sqlx::query(
+            "UPDATE broadcasts SET status = 'cancelled', completed_at = NOW() \
+             WHERE tenant_id = $1 AND id = $2 AND status IN ('running', 'interrupted')",
+        )
+        .bind(tenant_id)
+        .bind(id)
+        .execute(self.database.pool())
+        .await?;
+        Ok(cancelled.rows_affected() == 1)
+    }
+
+    pub async fn is_cancelled(&self, id: Uuid) -> AppResult<bool> {
+        let cancelled = sqlx::query_scalar("SELECT status = 'cancelled' FROM broadcasts WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        Ok(cancelled.unwrap_or(false))
+    }
+
+    /// Broadcasts stopped by a shutdown, oldest first
+    pub async fn interrupted(&self) -> AppResult<Vec<Broadcast>> {
+        let sql = format!(
+            "SELECT {} FROM broadcasts WHERE status = 'interrupted' ORDER BY created_at",
+            BROADCAST_COLUMNS
+        );
+        let rows = sqlx::query(&sql).fetch_all(self.database.pool()).await?;
+        rows.iter().map(map_broadcast).collect()
+    }
+
+    /// Move an interrupted broadcast back to running; false if another
+    /// instance already picked it up
+    pub async fn claim_interrupted(&self, id: Uuid) -> AppResult<bool> {
+        let claimed = sqlx::query(
+            "UPDATE broadcasts SET status = 'running' WHERE id = $1 AND status = 'interrupted'",
+        )
+        .bind(id)
+        .execute(self.database.pool())
+        .await?;
+        Ok(claimed.rows_affected() == 1)
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> AppResult<serde_json::Value> {
+    serde_json::to_value(value)
+        .map_err(|e| AppError::Internal(format!("Unserializable broadcast field: {}", e)))
+}
+
+fn map_broadcast(row: &PgRow) -> AppResult<Broadcast> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt broadcast row: {}", e));
+    let count = |column: &str| -> AppResult<usize> { Ok(row.try_get::<i32, _>(column)?.max(0) as usize) };
+
+    Ok(Broadcast {
+        id: row.try_get("id")?,
+        tenant_id: row.try_get("tenant_id")?,
+        target: serde_json::from_value(row.try_get("target")?).map_err(|e| invalid(e.to_string()))?,
+        message: serde_json::from_value(row.try_get("message")?).map_err(|e| invalid(e.to_string()))?,
+        cursor: row.try_get("cursor")?,
+        summary: BroadcastSummary {
+            targeted: count("targeted")?,
+            queued: count("queued")?,
+            skipped: count("skipped")?,
+        },
+        status: row.try_get::<String, _>("status")?.parse().map_err(invalid)?,
+        error: row.try_get("error")?,
+        created_at: row.try_get("created_at")?,
+        completed_at: row.try_get("completed_at")?,
+    })
+}
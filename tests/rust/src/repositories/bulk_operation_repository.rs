@@ -0,0 +1,209 @@
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, BulkOperation, BulkOperationItem, User};
+
+const OPERATION_COLUMNS: &str = "id, action, filters, requested_by, status, total, processed, \
+    skipped, error, created_at, completed_at, undo_until, undone_at";
+const ITEM_COLUMNS: &str = "operation_id, user_id, previous_role, previous_status, \
+    previous_deleted_at, applied_at, skipped_reason";
+
+/// Stores bulk operations and the prior user state needed to undo them
+pub struct BulkOperationRepository {
+    database: Arc<Database>,
+}
+
+impl BulkOperationRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn create(&self, operation: &BulkOperation) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO bulk_operations (id, action, filters, requested_by, status, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(operation.id)
+        .bind(to_json(&operation.action)?)
+        .bind(to_json(&operation.filters)?)
+        .bind(operation.requested_by)
+        .bind(operation.status.as_str())
+        .bind(operation.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find(&self, id: Uuid) -> AppResult<Option<BulkOperation>> {
+        let sql = format!("SELECT {} FROM bulk_operations WHERE id = $1", OPERATION_COLUMNS);
+        let row = sqlx::query(&sql)
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.map(|row| map_operation(&row)).transpose()
+    }
+
+    /// Operations stopped by a shutdown, oldest first
+    pub async fn interrupted(&self) -> AppResult<Vec<BulkOperation>> {
+        let sql = format!(
+            "SELECT {} FROM bulk_operations WHERE status = 'interrupted' ORDER BY created_at",
+            OPERATION_COLUMNS
+        );
+        let rows = sqlx::query(&sql).fetch_all(self.database.pool()).await?;
+        rows.iter().map(map_operation).collect()
+    }
+
+    /// Move an interrupted operation back to running; false if another
+    /// instance already picked it up
+    pub async fn claim_interrupted(&self, id: Uuid) -> AppResult<bool> {
+        let claimed = sqlx::query(
+            "UPDATE bulk_operations SET status = 'running' WHERE id = $1 AND status = 'interrupted'",
+        )
+        .bind(id)
+        .execute(self.database.pool())
+        .await?;
+        Ok(claimed.rows_affected() == 1)
+    }
+
+    /// Persist status, counters and timestamps
+    pub async fn save_progress(&self, operation: &BulkOperation) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE bulk_operations SET status = $2, total = $3, processed = $4, skipped = $5, \
+                error = $6, completed_at = $7, undo_until = $8, undone_at = $9 \
+             WHERE id = $1",
+        )
+        .bind(operation.id)
+        .bind(operation.status.as_str())
+        .bind(operation.total)
+        .bind(operation.processed)
+        .bind(operation.skipped)
+        .bind(&operation.error)
+        .bind(operation.completed_at)
+        .bind(operation.undo_until)
+        .bind(operation.undone_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Snapshot the current state of users selected by an operation,
+    /// returning how many were newly recorded
+    pub async fn record_items(&self, operation_id: Uuid, users: &[User]) -> AppResult<u64> {
+        if users.is_empty() {
+            return Ok(0);
+        }
+
+        let mut query = sqlx::QueryBuilder::new(
+            "INSERT INTO bulk_operation_items \
+                (operation_id, user_id, previous_role, previous_status, previous_deleted_at) ",
+        );
+        query.push_values(users, |mut row, user| {
+            row.push_bind(operation_id)
+                .push_bind(user.id)
+                .push_bind(user.role.as_str())
+                .push_bind(user.status.as_str())
+                .push_bind(user.deleted_at);
+        });
+        query.push(" ON CONFLICT (operation_id, user_id) DO NOTHING");
+        let inserted = query.build().execute(self.database.pool()).await?;
+        Ok(inserted.rows_affected())
+    }
+
+    /// Items not yet applied or skipped
+    pub async fn pending_items(&self, operation_id: Uuid, limit: i64) -> AppResult<Vec<BulkOperationItem>> {
+        let sql = format!(
+            "SELECT {} FROM bulk_operation_items \
+             WHERE operation_id = $1 AND applied_at IS NULL AND skipped_reason IS NULL \
+             ORDER BY user_id LIMIT $2",
+            ITEM_COLUMNS
+        );
+        let items = sqlx::query_as::<_, BulkOperationItem>(&sql)
+            .bind(operation_id)
+            .bind(limit)
+            .fetch_all(self.database.pool())
+            .await?;
+        Ok(items)
+    }
+
+    /// Applied items after `after` in user id order, for undo
+    pub async fn applied_items(
+        &self,
+        operation_id: Uuid,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> AppResult<Vec<BulkOperationItem>> {
+        let sql = format!(
+            "SELECT {} FROM bulk_operation_items \
+             WHERE operation_id = $1 AND applied_at IS NOT NULL AND ($2::uuid IS NULL OR user_id > $2) \
+             ORDER BY user_id LIMIT $3",
+            ITEM_COLUMNS
+        );
+        let items = sqlx::query_as::<_, BulkOperationItem>(&sql)
+            .bind(operation_id)
+            .bind(after)
+            .bind(limit)
+            .fetch_all(self.database.pool())
+            .await?;
+        Ok(items)
+    }
+
+    pub async fn mark_applied(&self, operation_id: Uuid, user_id: Uuid) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE bulk_operation_items SET applied_at = NOW() WHERE operation_id = $1 AND user_id = $2",
+        )
+        .bind(operation_id)
+        .bind(user_id)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Drop a user's prior state from every operation, once the user is purged
+    pub async fn forget_user(&self, user_id: Uuid) -> AppResult<u64> {
+        let result = sqlx::query("DELETE FROM bulk_operation_items WHERE user_id = $1")
+            .bind(user_id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn mark_skipped(&self, operation_id: Uuid, user_id: Uuid, reason: &str) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE bulk_operation_items SET skipped_reason = $3 WHERE operation_id = $1 AND user_id = $2",
+        )
+        .bind(operation_id)
+        .bind(user_id)
+        .bind(reason)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> AppResult<serde_json::Value> {
+    serde_json::to_value(value)
+        .map_err(|e| AppError::Internal(format!("Unserializable bulk operation field: {}", e)))
+}
+
+fn map_operation(row: &PgRow) -> AppResult<BulkOperation> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt bulk operation row: {}", e));
+
+    Ok(BulkOperation {
+        id: row.try_get("id")?,
+        action: serde_json::from_value(row.try_get("action")?).map_err(|e| invalid(e.to_string()))?,
+        filters: serde_json::from_value(row.try_get("filters")?).map_err(|e| invalid(e.to_string()))?,
+        requested_by: row.try_get("requested_by")?,
+        status: row.try_get::<String, _>("status")?.parse().map_err(invalid)?,
+        total: row.try_get("total")?,
+        processed: row.try_get("processed")?,
+        skipped: row.try_get("skipped")?,
+        error: row.try_get("error")?,
+        created_at: row.try_get("created_at")?,
+        completed_at: row.try_get("completed_at")?,
+        undo_until: row.try_get("undo_until")?,
+        undone_at: row.try_get("undone_at")?,
+    })
+}
@@ -0,0 +1,211 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::template_repository::TemplateRepository;
+use super::user_repository::UserRepository;
+use crate::models::{AppResult, NotificationTemplate, TemplateRevision, User, UserCursor, UserFilters, UserPage};
+use crate::services::{CachePolicy, CacheService};
+use crate::utils::TenantScope;
+
+/// A cached user with the tenant scope it was read or written in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedUser {
+    /// None outside any request
+    pub tenant_id: Option<String>,
+    pub user: User,
+}
+
+/// Read-through cache in front of a `UserRepository`.
+///
+/// Lookups by id are served from the cache, and writes refresh or evict
+/// the entry, so services never manage user cache keys or TTLs. Cache
+/// failures are logged and fall through to the wrapped repository.
+///
+/// Lookups are confined to the request's tenant, so an entry is only served
+/// within the tenant scope it was cached in; other scopes read through.
+pub struct CachingUserRepository {
+    inner: Arc<dyn UserRepository>,
+    cache: Arc<CacheService>,
+    policy: CachePolicy,
+}
+
+impl CachingUserRepository {
+    pub fn new(inner: Arc<dyn UserRepository>, cache: Arc<CacheService>, policy: CachePolicy) -> Self {
+        Self { inner, cache, policy }
+    }
+
+    async fn store(&self, user: &User) {
+        let entry = CachedUser {
+            tenant_id: current_tenant(),
+            user: user.clone(),
+        };
+        if let Err(e) = self.cache.store(&self.policy, &user.id.to_string(), &entry).await {
+            tracing::warn!("User cache write failed: {}", e);
+        }
+    }
+
+    async fn evict(&self, id: Uuid) {
+        if let Err(e) = self.cache.evict(&self.policy, &id.to_string()).await {
+            tracing::warn!("User cache invalidation failed: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl UserRepository for CachingUserRepository {
+    async fn create(&self, user: &User) -> AppResult<User> {
+        let created = self.inner.create(user).await?;
+        self.store(&created).await;
+        Ok(created)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> AppResult<Option<User>> {
+        match self.cache.fetch::<CachedUser>(&self.policy, &id.to_string()).await {
+            Ok(Some(entry)) if entry.tenant_id == current_tenant() => return Ok(Some(entry.user)),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("User cache read failed: {}", e),
+        }
+
+        let user = self.inner.find_by_id(id).await?;
+        if let Some(user) = &user {
+            self.store(user).await;
+        }
+        Ok(user)
+    }
+
+    async fn find_by_ids(&self, ids: &[Uuid]) -> AppResult<Vec<User>> {
+        self.inner.find_by_ids(ids).await
+    }
+
+    async fn find_by_email(&self, email: &str) -> AppResult<Option<User>> {
+        self.inner.find_by_email(email).await
+    }
+
+    async fn find_by_username(&self, username: &str) -> AppResult<Option<User>> {
+        self.inner.find_by_username(username).await
+    }
+
+    async fn email_taken(&self, email: &str) -> AppResult<bool> {
+        self.inner.email_taken(email).await
+    }
+
+    async fn username_taken(&self, username: &str) -> AppResult<bool> {
+        self.inner.username_taken(username).await
+    }
+
+    async fn update(&self, user: &User) -> AppResult<User> {
+        // Evict first so a failed update cannot leave a stale entry behind
+        self.evict(user.id).await;
+        let updated = self.inner.update(user).await?;
+        self.store(&updated).await;
+        Ok(updated)
+    }
+
+    async fn update_versioned(&self, user: &User, expected_version: i64) -> AppResult<User> {
+        self.evict(user.id).await;
+        let updated = self.inner.update_versioned(user, expected_version).await?;
+        self.store(&updated).await;
+        Ok(updated)
+    }
+
+    async fn mark_email_verified(&self, id: Uuid, email: &str) -> AppResult<Option<User>> {
+        self.evict(id).await;
+        let verified = self.inner.mark_email_verified(id, email).await?;
+        if let Some(user) = &verified {
+            self.store(user).await;
+        }
+        Ok(verified)
+    }
+
+    async fn delete(&self, id: Uuid) -> AppResult<()> {
+        self.inner.delete(id).await?;
+        self.evict(id).await;
+        Ok(())
+    }
+
+    async fn list(&self, filters: &UserFilters) -> AppResult<Vec<User>> {
+        self.inner.list(filters).await
+    }
+
+    async fn list_page(&self, filters: &UserFilters, cursor: Option<&UserCursor>) -> AppResult<UserPage> {
+        self.inner.list_page(filters, cursor).await
+    }
+
+    async fn count(&self, filters: &UserFilters) -> AppResult<i64> {
+        self.inner.count(filters).await
+    }
+
+    async fn find_expired_lockouts(&self, limit: i64) -> AppResult<Vec<User>> {
+        self.inner.find_expired_lockouts(limit).await
+    }
+
+    async fn find_deleted_before(&self, cutoff: DateTime<Utc>, limit: i64) -> AppResult<Vec<User>> {
+        self.inner.find_deleted_before(cutoff, limit).await
+    }
+}
+
+fn current_tenant() -> Option<String> {
+    TenantScope::current().map(|tenant| tenant.tenant_id)
+}
+
+/// Read-through cache in front of a `TemplateRepository`; templates are read
+/// on every send but change rarely
+pub struct CachingTemplateRepository {
+    inner: Arc<dyn TemplateRepository>,
+    cache: Arc<CacheService>,
+    policy: CachePolicy,
+}
+
+impl CachingTemplateRepository {
+    pub fn new(inner: Arc<dyn TemplateRepository>, cache: Arc<CacheService>, policy: CachePolicy) -> Self {
+        Self { inner, cache, policy }
+    }
+}
+
+#[async_trait]
+impl TemplateRepository for CachingTemplateRepository {
+    async fn find(&self, key: &str) -> AppResult<Option<NotificationTemplate>> {
+        match self.cache.fetch::<NotificationTemplate>(&self.policy, key).await {
+            Ok(Some(template)) => return Ok(Some(template)),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Template cache read failed: {}", e),
+        }
+
+        let template = self.inner.find(key).await?;
+        if let Some(template) = &template {
+            if let Err(e) = self.cache.store(&self.policy, key, template).await {
+                tracing::warn!("Template cache write failed: {}", e);
+            }
+        }
+        Ok(template)
+    }
+
+    async fn list(&self) -> AppResult<Vec<NotificationTemplate>> {
+        self.inner.list().await
+    }
+
+    async fn save_revision(
+        &self,
+        template: &NotificationTemplate,
+        author_id: Option<Uuid>,
+        rolled_back_from: Option<i32>,
+    ) -> AppResult<NotificationTemplate> {
+        let saved = self.inner.save_revision(template, author_id, rolled_back_from).await?;
+        // Evict rather than store so a slower concurrent edit cannot overwrite a newer entry
+        if let Err(e) = self.cache.evict(&self.policy, &saved.key).await {
+            tracing::warn!("Template cache invalidation failed: {}", e);
+        }
+        Ok(saved)
+    }
+
+    async fn revisions(&self, key: &str) -> AppResult<Vec<TemplateRevision>> {
+        self.inner.revisions(key).await
+    }
+
+    async fn revision(&self, key: &str, version: i32) -> AppResult<Option<TemplateRevision>> {
+        self.inner.revision(key, version).await
+    }
+}
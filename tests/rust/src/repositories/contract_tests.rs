@@ -0,0 +1,544 @@
+//! Conformance checks every `UserRepository` backend must pass.
+//!
+//! A new backend proves it behaves like the Postgres one by running the
+//! suite from its own tests:
+//!
+//! ```ignore
+//! #[tokio::test]
+//! async fn in_memory_repository_conforms() {
+//!     contract_tests::assert_user_repository_contract(Arc::new(InMemoryUserRepository::new())).await;
+//! }
+//! ```
+//!
+//! Every case tags the users it creates with a per-run marker and only
+//! looks at tagged users, so the suite can run against a shared database
+//! next to existing data. Users are deleted again when a case passes.
+
+use chrono::{Duration, Utc};
+use futures::future::join_all;
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::UserRepository;
+use crate::models::{
+    AppError, AppResult, TenantContext, User, UserCursor, UserFilters, UserRole, UserSort, UserSortField, UserStatus,
+};
+use crate::utils::TenantScope;
+
+const CONCURRENT_WRITERS: usize = 16;
+const PAGE_SIZE: i64 = 2;
+const PAGED_USERS: usize = 5;
+
+/// One broken expectation of the contract
+#[derive(Debug, Clone)]
+pub struct ContractViolation {
+    pub case: &'static str,
+    pub detail: String,
+}
+
+impl fmt::Display for ContractViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.case, self.detail)
+    }
+}
+
+/// Run every case against `repository` and return what it got wrong.
+///
+/// Errors from the backend are reported as violations of the case they
+/// happened in rather than aborting the suite.
+pub async fn check_user_repository(repository: Arc<dyn UserRepository>) -> Vec<ContractViolation> {
+    let run = Uuid::new_v4().simple().to_string()[..12].to_string();
+    let mut violations = Vec::new();
+    for (case, outcome) in [
+        ("crud", run_case(crud(repository.as_ref(), &run)).await),
+        ("missing", run_case(missing(repository.as_ref())).await),
+        ("find_by_ids", run_case(find_by_ids(repository.as_ref(), &run)).await),
+        ("tenant_scope", run_case(tenant_scope(repository.as_ref(), &run)).await),
+        ("filters", run_case(filters(repository.as_ref(), &run)).await),
+        ("pagination", run_case(pagination(repository.as_ref(), &run)).await),
+        ("cursor_pagination", run_case(cursor_pagination(repository.as_ref(), &run)).await),
+        ("sorting", run_case(sorting(repository.as_ref(), &run)).await),
+        ("soft_delete", run_case(soft_delete(repository.as_ref(), &run)).await),
+        ("versioning", run_case(versioning(repository.as_ref(), &run)).await),
+        ("email_verification", run_case(email_verification(repository.as_ref(), &run)).await),
+        ("lockouts", run_case(lockouts(repository.as_ref(), &run)).await),
+        ("concurrency", run_case(concurrency(repository.clone(), &run)).await),
+    ] {
+        if let Err(detail) = outcome {
+            violations.push(ContractViolation { case, detail });
+        }
+    }
+    violations
+}
+
+/// `check_user_repository` for use in a test; panics listing every violation
+pub async fn assert_user_repository_contract(repository: Arc<dyn UserRepository>) {
+    let violations = check_user_repository(repository).await;
+    if !violations.is_empty() {
+        let listed: Vec<String> = violations.iter().map(ToString::to_string).collect();
+        panic!("UserRepository contract violated:\n  {}", listed.join("\n  "));
+    }
+}
+
+type CaseResult = Result<(), String>;
+
+async fn run_case(case: impl Future<Output = AppResult<CaseResult>>) -> CaseResult {
+    case.await.unwrap_or_else(|e| Err(format!("backend error: {}", e)))
+}
+
+macro_rules! expect {
+    ($condition:expr, $($message:tt)+) => {
+        if !$condition {
+            return Ok(Err(format!($($message)+)));
+        }
+    };
+}
+
+/// A valid user whose email and username carry the run marker
+fn tagged_user(run: &str, label: &str) -> User {
+    let suffix = &Uuid::new_v4().simple().to_string()[..8];
+    User::new(
+        format!("contract.{}.{}.{}@example.com", run, label, suffix),
+        format!("contract_{}_{}_{}", run, label, suffix),
+        "Contract".to_string(),
+        label.to_string(),
+        "contract-test-hash".to_string(),
+    )
+}
+
+fn tagged(run: &str) -> UserFilters {
+    UserFilters::new().with_search(run.to_string())
+}
+
+fn ids(users: &[User]) -> HashSet<Uuid> {
+    users.iter().map(|user| user.id).collect()
+}
+
+async fn cleanup(repository: &dyn UserRepository, users: &[User]) -> AppResult<()> {
+    for user in users {
+        repository.delete(user.id).await?;
+    }
+    Ok(())
+}
+
+async fn crud(repository: &dyn UserRepository, run: &str) -> AppResult<CaseResult> {
+    let user = tagged_user(run, "crud");
+    let created = repository.create(&user).await?;
+    expect!(created.id == user.id, "create returned id {} for user {}", created.id, user.id);
+    expect!(
+        created.email == user.email && created.username == user.username,
+        "create changed email or username"
+    );
+    expect!(
+        created.first_name == user.first_name && created.last_name == user.last_name,
+        "create did not round-trip names"
+    );
+
+    let by_id = repository.find_by_id(user.id).await?;
+    expect!(by_id.as_ref().map(|u| u.id) == Some(user.id), "find_by_id did not find the created user");
+    let by_email = repository.find_by_email(&user.email).await?;
+    expect!(by_email.map(|u| u.id) == Some(user.id), "find_by_email did not find the created user");
+    let by_username = repository.find_by_username(&user.username).await?;
+    expect!(by_username.map(|u| u.id) == Some(user.id), "find_by_username did not find the created user");
+
+    let mut changed = created.clone();
+    changed.first_name = "Updated".to_string();
+    changed.role = UserRole::Moderator;
+    changed.email_verified = true;
+    changed.touch();
+    let updated = repository.update(&changed).await?;
+    expect!(updated.first_name == "Updated", "update did not return the new first name");
+    let reloaded = repository.find_by_id(user.id).await?;
+    expect!(
+        reloaded.as_ref().is_some_and(|u| u.first_name == "Updated"
+            && u.role == UserRole::Moderator
+            && u.email_verified),
+        "update was not persisted"
+    );
+
+    repository.delete(user.id).await?;
+    expect!(repository.find_by_id(user.id).await?.is_none(), "user still found after delete");
+    Ok(Ok(()))
+}
+
+async fn versioning(repository: &dyn UserRepository, run: &str) -> AppResult<CaseResult> {
+    let created = repository.create(&tagged_user(run, "versioned")).await?;
+    let mut changed = created.clone();
+    changed.first_name = "Versioned".to_string();
+    let updated = repository.update_versioned(&changed, created.version).await?;
+    expect!(
+        updated.version > created.version,
+        "update_versioned left the version at {}",
+        updated.version
+    );
+
+    // A writer still holding the old version must be refused without effect
+    changed.first_name = "Stale".to_string();
+    let stale = repository.update_versioned(&changed, created.version).await;
+    expect!(
+        matches!(stale, Err(AppError::Conflict(_))),
+        "update_versioned with a stale version returned {:?}",
+        stale.map(|u| u.version)
+    );
+    let reloaded = repository.find_by_id(created.id).await?;
+    expect!(
+        reloaded.as_ref().is_some_and(|u| u.first_name == "Versioned" && u.version == updated.version),
+        "a refused update_versioned changed the stored user"
+    );
+
+    let plain = repository.update(&updated).await?;
+    expect!(plain.version > updated.version, "update did not advance the version");
+
+    repository.delete(created.id).await?;
+    Ok(Ok(()))
+}
+
+async fn email_verification(repository: &dyn UserRepository, run: &str) -> AppResult<CaseResult> {
+    let created = repository.create(&tagged_user(run, "unverified")).await?;
+    expect!(!created.email_verified, "a new user was created with a verified email");
+
+    // A link sent to an earlier address must not verify the current one
+    let stale = repository
+        .mark_email_verified(created.id, "previous.address@example.com")
+        .await?;
+    expect!(stale.is_none(), "mark_email_verified accepted an email the user no longer has");
+
+    let verified = repository.mark_email_verified(created.id, &created.email).await?;
+    expect!(
+        verified.as_ref().is_some_and(|u| u.email_verified && u.version > created.version),
+        "mark_email_verified did not verify the email and advance the version"
+    );
+    let reloaded = repository.find_by_id(created.id).await?;
+    expect!(
+        reloaded.is_some_and(|u| u.email_verified),
+        "the verified email was not persisted"
+    );
+
+    repository.delete(created.id).await?;
+    Ok(Ok(()))
+}
+
+async fn lockouts(repository: &dyn UserRepository, run: &str) -> AppResult<CaseResult> {
+    let mut expired = tagged_user(run, "lock_expired");
+    expired.locked_until = Some(chrono::Utc::now() - chrono::Duration::minutes(1));
+    let expired = repository.create(&expired).await?;
+    let mut active = tagged_user(run, "lock_active");
+    active.locked_until = Some(chrono::Utc::now() + chrono::Duration::hours(1));
+    let active = repository.create(&active).await?;
+    expect!(
+        repository.find_by_id(active.id).await?.is_some_and(|u| u.locked_until.is_some()),
+        "locked_until was not persisted"
+    );
+
+    let found = ids(&repository.find_expired_lockouts(10_000).await?);
+    expect!(found.contains(&expired.id), "find_expired_lockouts missed an expired lockout");
+    expect!(!found.contains(&active.id), "find_expired_lockouts returned a lockout still in force");
+
+    cleanup(repository, &[expired, active]).await?;
+    Ok(Ok(()))
+}
+
+async fn missing(repository: &dyn UserRepository) -> AppResult<CaseResult> {
+    let ghost = User::new(
+        format!("ghost.{}@example.com", Uuid::new_v4().simple()),
+        format!("ghost_{}", Uuid::new_v4().simple()),
+        "Ghost".to_string(),
+        "User".to_string(),
+        "contract-test-hash".to_string(),
+    );
+    expect!(repository.find_by_id(ghost.id).await?.is_none(), "find_by_id found an unknown id");
+    expect!(repository.find_by_email(&ghost.email).await?.is_none(), "find_by_email found an unknown email");
+    expect!(
+        matches!(repository.update(&ghost).await, Err(AppError::NotFound(_))),
+        "update of an unknown user must fail with NotFound"
+    );
+    expect!(
+        matches!(repository.delete(ghost.id).await, Err(AppError::NotFound(_))),
+        "delete of an unknown user must fail with NotFound"
+    );
+    Ok(Ok(()))
+}
+
+async fn find_by_ids(repository: &dyn UserRepository, run: &str) -> AppResult<CaseResult> {
+    let mut users = Vec::new();
+    for label in ["ids_a", "ids_b", "ids_c"] {
+        users.push(repository.create(&tagged_user(run, label)).await?);
+    }
+
+    let mut wanted: Vec<Uuid> = users.iter().take(2).map(|u| u.id).collect();
+    wanted.push(Uuid::new_v4());
+    let found = repository.find_by_ids(&wanted).await?;
+    expect!(
+        ids(&found) == ids(&users[..2]) && found.len() == 2,
+        "find_by_ids must return exactly the existing requested users, got {}",
+        found.len()
+    );
+    expect!(repository.find_by_ids(&[]).await?.is_empty(), "find_by_ids of no ids must be empty");
+
+    cleanup(repository, &users).await?;
+    Ok(Ok(()))
+}
+
+/// Reads within a request only see its tenant's users, while uniqueness spans every tenant
+async fn tenant_scope(repository: &dyn UserRepository, run: &str) -> AppResult<CaseResult> {
+    // Created without onboarding, so the user belongs to the default tenant
+    let user = repository.create(&tagged_user(run, "tenant")).await?;
+    let elsewhere = TenantContext::new("contract-elsewhere")?;
+
+    let found_elsewhere = TenantScope::scope(elsewhere.clone(), async {
+        AppResult::Ok(
+            repository.find_by_id(user.id).await?.is_some()
+                || !repository.find_by_ids(&[user.id]).await?.is_empty()
+                || repository.find_by_email(&user.email).await?.is_some()
+                || repository.find_by_username(&user.username).await?.is_some()
+                || repository.count(&tagged(run)).await? > 0,
+        )
+    })
+    .await?;
+    expect!(!found_elsewhere, "a request of another tenant found the user");
+    let taken_elsewhere = TenantScope::scope(elsewhere, async {
+        AppResult::Ok(repository.email_taken(&user.email).await? && repository.username_taken(&user.username).await?)
+    })
+    .await?;
+    expect!(taken_elsewhere, "the user's email and username must be taken in every tenant");
+    let found_at_home = TenantScope::scope(TenantContext::default(), async {
+        AppResult::Ok(
+            repository.find_by_id(user.id).await?.is_some() && repository.find_by_email(&user.email).await?.is_some(),
+        )
+    })
+    .await?;
+    expect!(found_at_home, "a request of the user's own tenant did not find them");
+
+    cleanup(repository, &[user]).await?;
+    Ok(Ok(()))
+}
+
+async fn filters(repository: &dyn UserRepository, run: &str) -> AppResult<CaseResult> {
+    let mut admin = tagged_user(run, "filter_admin");
+    admin.role = UserRole::Admin;
+    admin.email_verified = true;
+    let mut suspended = tagged_user(run, "filter_suspended");
+    suspended.status = UserStatus::Suspended;
+    let mut old = tagged_user(run, "filter_old");
+    old.created_at = Utc::now() - Duration::days(30);
+    let users = vec![
+        repository.create(&admin).await?,
+        repository.create(&suspended).await?,
+        repository.create(&old).await?,
+    ];
+
+    let all = repository.list(&tagged(run)).await?;
+    expect!(ids(&all) == ids(&users), "search by run marker must return exactly the run's users");
+    let upper = repository.list(&tagged(&run.to_uppercase())).await?;
+    expect!(ids(&upper) == ids(&users), "search must be case-insensitive");
+
+    let admins = repository.list(&tagged(run).with_role(UserRole::Admin)).await?;
+    expect!(ids(&admins) == HashSet::from([admin.id]), "role filter returned the wrong users");
+    let suspended_only = repository.list(&tagged(run).with_status(UserStatus::Suspended)).await?;
+    expect!(ids(&suspended_only) == HashSet::from([suspended.id]), "status filter returned the wrong users");
+
+    let verified = repository
+        .list(&UserFilters { email_verified: Some(true), ..tagged(run) })
+        .await?;
+    expect!(ids(&verified) == HashSet::from([admin.id]), "email_verified filter returned the wrong users");
+
+    let cutoff = Utc::now() - Duration::days(1);
+    let recent = repository
+        .list(&UserFilters { created_after: Some(cutoff), ..tagged(run) })
+        .await?;
+    expect!(ids(&recent) == HashSet::from([admin.id, suspended.id]), "created_after filter returned the wrong users");
+    let older = repository
+        .list(&UserFilters { created_before: Some(cutoff), ..tagged(run) })
+        .await?;
+    expect!(ids(&older) == HashSet::from([old.id]), "created_before filter returned the wrong users");
+
+    let counted = repository.count(&tagged(run).with_role(UserRole::Admin)).await?;
+    expect!(counted == 1, "count must agree with list for the same filters, got {}", counted);
+
+    cleanup(repository, &users).await?;
+    Ok(Ok(()))
+}
+
+async fn pagination(repository: &dyn UserRepository, run: &str) -> AppResult<CaseResult> {
+    let mut users = Vec::new();
+    for index in 0..PAGED_USERS {
+        let mut user = tagged_user(run, "page");
+        // Distinct creation times make the expected order unambiguous
+        user.created_at = Utc::now() - Duration::minutes(index as i64);
+        users.push(repository.create(&user).await?);
+    }
+
+    let mut seen = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page = repository
+            .list(&UserFilters { offset: Some(offset), ..tagged(run).with_limit(PAGE_SIZE) })
+            .await?;
+        expect!(page.len() as i64 <= PAGE_SIZE, "page of {} exceeds limit {}", page.len(), PAGE_SIZE);
+        if page.is_empty() {
+            break;
+        }
+        offset += page.len() as i64;
+        seen.extend(page);
+    }
+
+    expect!(seen.len() == PAGED_USERS, "pages returned {} users, expected {}", seen.len(), PAGED_USERS);
+    expect!(ids(&seen) == ids(&users), "pages skipped or repeated users");
+    expect!(
+        seen.windows(2).all(|pair| pair[0].created_at >= pair[1].created_at),
+        "users must be listed newest first"
+    );
+    let total = repository.count(&tagged(run)).await?;
+    expect!(total == PAGED_USERS as i64, "count ignores limit and offset, expected {} got {}", PAGED_USERS, total);
+
+    cleanup(repository, &users).await?;
+    Ok(Ok(()))
+}
+
+async fn cursor_pagination(repository: &dyn UserRepository, run: &str) -> AppResult<CaseResult> {
+    let created_at = Utc::now();
+    let mut users = Vec::new();
+    for index in 0..PAGED_USERS {
+        let mut user = tagged_user(run, "cursor");
+        // Pairs share a creation time, so pages must break ties by id to neither skip nor repeat
+        user.created_at = created_at - Duration::minutes((index / 2) as i64);
+        users.push(repository.create(&user).await?);
+    }
+
+    let mut seen: Vec<User> = Vec::new();
+    let mut cursor: Option<UserCursor> = None;
+    loop {
+        let page = repository.list_page(&tagged(run).with_limit(PAGE_SIZE), cursor.as_ref()).await?;
+        expect!(page.users.len() as i64 <= PAGE_SIZE, "page of {} exceeds limit {}", page.users.len(), PAGE_SIZE);
+        expect!(seen.len() <= PAGED_USERS, "cursor pages do not end");
+        seen.extend(page.users);
+        match page.next_cursor {
+            // Cursors leave as strings and come back parsed
+            Some(next) => {
+                let parsed = next.to_string().parse::<UserCursor>();
+                expect!(parsed.as_ref() == Ok(&next), "cursor {:?} does not survive encoding", next);
+                cursor = Some(next);
+            }
+            None => break,
+        }
+    }
+
+    expect!(seen.len() == PAGED_USERS, "cursor pages returned {} users, expected {}", seen.len(), PAGED_USERS);
+    expect!(ids(&seen) == ids(&users), "cursor pages skipped or repeated users");
+    expect!(
+        seen.windows(2).all(|pair| pair[0].created_at > pair[1].created_at
+            || (pair[0].created_at == pair[1].created_at && pair[0].id < pair[1].id)),
+        "users must be listed newest first, ties by id"
+    );
+    let first = repository.list(&tagged(run).with_limit(PAGE_SIZE)).await?;
+    expect!(
+        ids(&first) == ids(&seen[..first.len()]),
+        "the first cursor page differs from the first offset page"
+    );
+
+    cleanup(repository, &users).await?;
+    Ok(Ok(()))
+}
+
+async fn sorting(repository: &dyn UserRepository, run: &str) -> AppResult<CaseResult> {
+    let mut users = Vec::new();
+    for index in 0..PAGED_USERS {
+        let mut user = tagged_user(run, "sort");
+        // Every other user never signed in, and pairs share a sign-in time
+        user.last_login = (index % 2 == 0).then(|| Utc::now() - Duration::minutes((index / 4) as i64));
+        users.push(repository.create(&user).await?);
+    }
+
+    for sort in [
+        vec![UserSort::asc(UserSortField::Username)],
+        vec![UserSort::desc(UserSortField::Email)],
+        vec![UserSort::desc(UserSortField::LastLogin), UserSort::asc(UserSortField::CreatedAt)],
+        vec![UserSort::asc(UserSortField::LastLogin)],
+    ] {
+        let filters = UserFilters { sort: sort.clone(), ..tagged(run) };
+        let listed = repository.list(&filters).await?;
+        let mut expected = users.clone();
+        expected.sort_by(|a, b| filters.compare(a, b));
+        let listed: Vec<Uuid> = listed.iter().map(|user| user.id).collect();
+        let expected: Vec<Uuid> = expected.iter().map(|user| user.id).collect();
+        expect!(listed == expected, "listing sorted by {:?} is out of order", sort);
+    }
+
+    let sorted = tagged(run).with_sort(UserSort::asc(UserSortField::Username));
+    expect!(
+        matches!(repository.list_page(&sorted, None).await, Err(AppError::Validation(_))),
+        "cursor pages must refuse an order other than newest first"
+    );
+
+    cleanup(repository, &users).await?;
+    Ok(Ok(()))
+}
+
+async fn soft_delete(repository: &dyn UserRepository, run: &str) -> AppResult<CaseResult> {
+    let mut user = repository.create(&tagged_user(run, "soft_delete")).await?;
+    user.soft_delete();
+    repository.update(&user).await?;
+
+    let reloaded = repository.find_by_id(user.id).await?;
+    expect!(
+        reloaded
+            .as_ref()
+            .is_some_and(|u| u.deleted_at.is_some() && u.status == UserStatus::Deleted),
+        "soft-deleted user must still be found with deleted_at and Deleted status"
+    );
+    let deleted = repository.list(&tagged(run).with_status(UserStatus::Deleted)).await?;
+    expect!(ids(&deleted) == HashSet::from([user.id]), "soft-deleted user must be listable by status");
+
+    let deleted_at = reloaded.and_then(|u| u.deleted_at).unwrap_or_else(Utc::now);
+    let due = repository.find_deleted_before(deleted_at + Duration::seconds(1), 10_000).await?;
+    expect!(
+        due.iter().any(|u| u.id == user.id),
+        "soft-deleted user must be found once deleted before the cutoff"
+    );
+    let early = repository.find_deleted_before(deleted_at - Duration::minutes(1), 10_000).await?;
+    expect!(
+        !early.iter().any(|u| u.id == user.id),
+        "user deleted after the cutoff must not be found"
+    );
+    expect!(
+        due.windows(2).all(|pair| pair[0].deleted_at <= pair[1].deleted_at),
+        "deleted users must come longest deleted first"
+    );
+
+    cleanup(repository, &[user]).await?;
+    Ok(Ok(()))
+}
+
+async fn concurrency(repository: Arc<dyn UserRepository>, run: &str) -> AppResult<CaseResult> {
+    let creates = (0..CONCURRENT_WRITERS).map(|_| {
+        let repository = repository.clone();
+        let user = tagged_user(run, "concurrent");
+        async move { repository.create(&user).await }
+    });
+    let users = join_all(creates).await.into_iter().collect::<AppResult<Vec<User>>>()?;
+    let listed = repository
+        .list(&tagged(run).with_limit(CONCURRENT_WRITERS as i64 * 2))
+        .await?;
+    expect!(ids(&listed) == ids(&users), "concurrent creates were lost or duplicated");
+
+    // Racing updates of one user must each succeed and leave one of the written values
+    let target = users[0].clone();
+    let updates = (0..CONCURRENT_WRITERS).map(|index| {
+        let repository = repository.clone();
+        let mut user = target.clone();
+        user.last_name = format!("writer{}", index);
+        async move { repository.update(&user).await }
+    });
+    join_all(updates).await.into_iter().collect::<AppResult<Vec<User>>>()?;
+    let survivor = repository.find_by_id(target.id).await?;
+    expect!(
+        survivor.is_some_and(|u| u.last_name.starts_with("writer")),
+        "a racing update must win as a whole"
+    );
+
+    cleanup(repository.as_ref(), &users).await?;
+    Ok(Ok(()))
+}
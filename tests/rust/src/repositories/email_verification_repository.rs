@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::AppResult;
+
+/// Outstanding email verification tokens, stored by hash
+pub struct EmailVerificationRepository {
+    database: Arc<Database>,
+}
+
+impl EmailVerificationRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    /// Store a token for `email`, superseding any the user still had outstanding
+    pub async fn issue(
+        &self,
+        user_id: Uuid,
+        email: &str,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> AppResult<()> {
+        let mut tx = self.database.pool().begin().await?;
+        sqlx::query("UPDATE email_verification_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO email_verification_tokens (token_hash, user_id, email, expires_at) \
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(token_hash)
+        .bind(user_id)
+        .bind(email)
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Spend an unused, unexpired token, returning the user and the email it was sent to
+    pub async fn consume(&self, token_hash: &str) -> AppResult<Option<(Uuid, String)>> {
+        let redeemed = sqlx::query_as(
+            "UPDATE email_verification_tokens SET used_at = NOW() \
+             WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW() \
+             RETURNING user_id, email",
+        )
+        .bind(token_hash)
+        .fetch_optional(self.database.pool())
+        .await?;
+        Ok(redeemed)
+    }
+}
@@ -0,0 +1,238 @@
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, ExportJob, ExportStatus};
+
+const JOB_COLUMNS: &str = "id, requested_by, tenant_id, kind, status, artifact_key, filename, \
+    content_type, size_bytes, error, attempts, created_at, started_at, completed_at, expires_at";
+
+/// Stores export jobs; the queue is claimed with `SKIP LOCKED` so any number of instances can run it
+pub struct ExportJobRepository {
+    database: Arc<Database>,
+}
+
+impl ExportJobRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn create(&self, job: &ExportJob) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO export_jobs (id, requested_by, tenant_id, kind, status, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(job.id)
+        .bind(job.requested_by)
+        .bind(&job.tenant_id)
+        .bind(job.kind.as_str())
+        .bind(job.status.as_str())
+        .bind(job.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find(&self, id: Uuid) -> AppResult<Option<ExportJob>> {
+        let sql = format!("SELECT {} FROM export_jobs WHERE id = $1", JOB_COLUMNS);
+        let row = sqlx::query(&sql)
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.map(|row| map_job(&row)).transpose()
+    }
+
+    /// A user's exports, newest first
+    pub async fn for_user(&self, user_id: Uuid, limit: i64) -> AppResult<Vec<ExportJob>> {
+        let sql = format!(
+            "SELECT {} FROM export_jobs WHERE requested_by = $1 ORDER BY created_at DESC LIMIT $2",
+            JOB_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(user_id)
+            .bind(limit)
+            .fetch_all(self.database.pool())
+            .await?;
+        rows.iter().map(map_job).collect()
+    }
+
+    /// Exports of a user still queued or running
+    pub async fn count_pending(&self, user_id: Uuid) -> AppResult<i64> {
+        let count = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM export_jobs WHERE requested_by = $1 AND status IN ('pending', 'running')",
+        )
+        .bind(user_id)
+        .fetch_one(self.database.pool())
+        .await?;
+        Ok(count)
+    }
+
+    /// Take the oldest queued job, or one whose runner has not been heard
+    /// from since `stale_before`, and mark it running
+    pub async fn claim_next(&self, stale_before: DateTime<Utc>, max_attempts: i32) -> AppResult<Option<ExportJob>> {
+        let sql = format!(
+            "UPDATE export_jobs SET status = 'running', started_at = NOW(), attempts = attempts + 1 \
+             WHERE id = ( \
+                 SELECT id FROM export_jobs \
+                 WHERE (status = 'pending' OR (status = 'running' AND started_at < $1)) AND attempts < $2 \
+                 ORDER BY created_at LIMIT 1 FOR UPDATE SKIP LOCKED \
+             ) RETURNING {}",
+            JOB_COLUMNS
+        );
+        let row = sqlx::query(&sql)
+            .bind(stale_before)
+            .bind(max_attempts)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.map(|row| map_job(&row)).transpose()
+    }
+
+    /// Give up on stale jobs that have used all their attempts, returning how many
+    pub async fn abandon_stale(&self, stale_before: DateTime<Utc>, max_attempts: i32) -> AppResult<u64> {
+        let abandoned = sqlx::query(
+            "UPDATE export_jobs SET status = 'failed', completed_at = NOW(), \
+                error = 'Export was interrupted too many times' \
+             WHERE status = 'running' AND started_at < $1 AND attempts >= $2",
+        )
+        .bind(stale_before)
+        .bind(max_attempts)
+        .execute(self.database.pool())
+        .await?;
+        Ok(abandoned.rows_affected())
+    }
+
+    /// Record the stored file of a finished job
+    pub async fn complete(&self, job: &ExportJob) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE export_jobs SET status = 'completed', artifact_key = $2, filename = $3, \
+                content_type = $4, size_bytes = $5, completed_at = $6, expires_at = $7 \
+             WHERE id = $1",
+        )
+        .bind(job.id)
+        .bind(&job.artifact_key)
+        .bind(&job.filename)
+        .bind(&job.content_type)
+        .bind(job.size_bytes)
+        .bind(job.completed_at)
+        .bind(job.expires_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn fail(&self, id: Uuid, error: &str) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE export_jobs SET status = 'failed', error = $2, completed_at = NOW() WHERE id = $1",
+        )
+        .bind(id)
+        .bind(error)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Completed jobs past their expiry, oldest first
+    pub async fn expired(&self, limit: i64) -> AppResult<Vec<ExportJob>> {
+        let sql = format!(
+            "SELECT {} FROM export_jobs WHERE status = 'completed' AND expires_at <= NOW() \
+             ORDER BY expires_at LIMIT $1",
+            JOB_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(limit)
+            .fetch_all(self.database.pool())
+            .await?;
+        rows.iter().map(map_job).collect()
+    }
+
+    /// Queued, running and failed jobs, and when the oldest queued one was created
+    pub async fn queue_counts(&self) -> AppResult<(i64, i64, i64, Option<DateTime<Utc>>)> {
+        let counts = sqlx::query_as(
+            "SELECT COUNT(*) FILTER (WHERE status = 'pending'), \
+                COUNT(*) FILTER (WHERE status = 'running'), \
+                COUNT(*) FILTER (WHERE status = 'failed'), \
+                MIN(created_at) FILTER (WHERE status = 'pending') \
+             FROM export_jobs WHERE status IN ('pending', 'running', 'failed')",
+        )
+        .fetch_one(self.database.pool())
+        .await?;
+        Ok(counts)
+    }
+
+    /// Jobs in any of `statuses`, oldest first so the head of the queue comes first
+    pub async fn with_status(&self, statuses: &[ExportStatus], limit: i64) -> AppResult<Vec<ExportJob>> {
+        let statuses: Vec<&str> = statuses.iter().map(ExportStatus::as_str).collect();
+        let sql = format!(
+            "SELECT {} FROM export_jobs WHERE status = ANY($1) ORDER BY created_at LIMIT $2",
+            JOB_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(&statuses)
+            .bind(limit)
+            .fetch_all(self.database.pool())
+            .await?;
+        rows.iter().map(map_job).collect()
+    }
+
+    /// Queue a failed job again with a fresh set of attempts; None unless it had failed
+    pub async fn requeue_failed(&self, id: Uuid) -> AppResult<Option<ExportJob>> {
+        let sql = format!(
+            "UPDATE export_jobs SET status = 'pending', attempts = 0, error = NULL, \
+                started_at = NULL, completed_at = NULL \
+             WHERE id = $1 AND status = 'failed' RETURNING {}",
+            JOB_COLUMNS
+        );
+        let row = sqlx::query(&sql)
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.map(|row| map_job(&row)).transpose()
+    }
+
+    /// Take a job off the queue; None unless it was still waiting to run
+    pub async fn cancel_pending(&self, id: Uuid) -> AppResult<Option<ExportJob>> {
+        let sql = format!(
+            "UPDATE export_jobs SET status = 'cancelled', completed_at = NOW() \
+             WHERE id = $1 AND status = 'pending' RETURNING {}",
+            JOB_COLUMNS
+        );
+        let row = sqlx::query(&sql)
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.map(|row| map_job(&row)).transpose()
+    }
+
+    pub async fn mark_expired(&self, id: Uuid) -> AppResult<()> {
+        sqlx::query("UPDATE export_jobs SET status = 'expired', artifact_key = NULL WHERE id = $1")
+            .bind(id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(())
+    }
+}
+
+fn map_job(row: &PgRow) -> AppResult<ExportJob> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt export job row: {}", e));
+
+    Ok(ExportJob {
+        id: row.try_get("id")?,
+        requested_by: row.try_get("requested_by")?,
+        tenant_id: row.try_get("tenant_id")?,
+        kind: row.try_get::<String, _>("kind")?.parse().map_err(invalid)?,
+        status: row.try_get::<String, _>("status")?.parse().map_err(invalid)?,
+        artifact_key: row.try_get("artifact_key")?,
+        filename: row.try_get("filename")?,
+        content_type: row.try_get("content_type")?,
+        size_bytes: row.try_get("size_bytes")?,
+        error: row.try_get("error")?,
+        attempts: row.try_get("attempts")?,
+        created_at: row.try_get("created_at")?,
+        started_at: row.try_get("started_at")?,
+        completed_at: row.try_get("completed_at")?,
+        expires_at: row.try_get("expires_at")?,
+    })
+}
@@ -0,0 +1,127 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, Group, GroupMembership};
+
+/// Persistence boundary for user groups and their memberships
+#[async_trait]
+pub trait GroupRepository: Send + Sync {
+    async fn create(&self, group: &Group) -> AppResult<Group>;
+    async fn find_by_id(&self, id: Uuid) -> AppResult<Option<Group>>;
+    async fn delete(&self, id: Uuid) -> AppResult<()>;
+    /// Returns false when the user was already a member
+    async fn add_member(&self, group_id: Uuid, user_id: Uuid, added_by: Option<Uuid>) -> AppResult<bool>;
+    /// Returns false when the user was not a member
+    async fn remove_member(&self, group_id: Uuid, user_id: Uuid) -> AppResult<bool>;
+    /// One page of members ordered by user id, starting after `after`
+    async fn member_page(&self, group_id: Uuid, after: Option<Uuid>, limit: i64) -> AppResult<Vec<GroupMembership>>;
+    async fn groups_for_user(&self, user_id: Uuid) -> AppResult<Vec<Group>>;
+}
+
+pub struct PostgresGroupRepository {
+    database: Arc<Database>,
+}
+
+impl PostgresGroupRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl GroupRepository for PostgresGroupRepository {
+    async fn create(&self, group: &Group) -> AppResult<Group> {
+        let created = sqlx::query_as::<_, Group>(
+            "INSERT INTO groups (id, name, description, created_by, created_at, updated_at) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT DO NOTHING \
+             RETURNING id, name, description, created_by, created_at, updated_at",
+        )
+        .bind(group.id)
+        .bind(&group.name)
+        .bind(&group.description)
+        .bind(group.created_by)
+        .bind(group.created_at)
+        .bind(group.updated_at)
+        .fetch_optional(self.database.pool())
+        .await?;
+
+        created.ok_or_else(|| AppError::Conflict(format!("Group {} already exists", group.name)))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> AppResult<Option<Group>> {
+        let group = sqlx::query_as::<_, Group>(
+            "SELECT id, name, description, created_by, created_at, updated_at FROM groups WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(self.database.pool())
+        .await?;
+        Ok(group)
+    }
+
+    async fn delete(&self, id: Uuid) -> AppResult<()> {
+        let deleted = sqlx::query("DELETE FROM groups WHERE id = $1")
+            .bind(id)
+            .execute(self.database.pool())
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(AppError::NotFound(format!("Group {} not found", id)));
+        }
+        Ok(())
+    }
+
+    async fn add_member(&self, group_id: Uuid, user_id: Uuid, added_by: Option<Uuid>) -> AppResult<bool> {
+        let inserted = sqlx::query(
+            "INSERT INTO group_members (group_id, user_id, added_by) VALUES ($1, $2, $3) \
+             ON CONFLICT (group_id, user_id) DO NOTHING",
+        )
+        .bind(group_id)
+        .bind(user_id)
+        .bind(added_by)
+        .execute(self.database.pool())
+        .await?;
+
+        sqlx::query("UPDATE groups SET updated_at = NOW() WHERE id = $1")
+            .bind(group_id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(inserted.rows_affected() > 0)
+    }
+
+    async fn remove_member(&self, group_id: Uuid, user_id: Uuid) -> AppResult<bool> {
+        let removed = sqlx::query("DELETE FROM group_members WHERE group_id = $1 AND user_id = $2")
+            .bind(group_id)
+            .bind(user_id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(removed.rows_affected() > 0)
+    }
+
+    async fn member_page(&self, group_id: Uuid, after: Option<Uuid>, limit: i64) -> AppResult<Vec<GroupMembership>> {
+        let members = sqlx::query_as::<_, GroupMembership>(
+            "SELECT group_id, user_id, added_by, added_at FROM group_members \
+             WHERE group_id = $1 AND ($2::uuid IS NULL OR user_id > $2) \
+             ORDER BY user_id LIMIT $3",
+        )
+        .bind(group_id)
+        .bind(after)
+        .bind(limit)
+        .fetch_all(self.database.pool())
+        .await?;
+        Ok(members)
+    }
+
+    async fn groups_for_user(&self, user_id: Uuid) -> AppResult<Vec<Group>> {
+        let groups = sqlx::query_as::<_, Group>(
+            "SELECT g.id, g.name, g.description, g.created_by, g.created_at, g.updated_at \
+             FROM groups g JOIN group_members m ON m.group_id = g.id \
+             WHERE m.user_id = $1 ORDER BY g.name",
+        )
+        .bind(user_id)
+        .fetch_all(self.database.pool())
+        .await?;
+        Ok(groups)
+    }
+}
@@ -0,0 +1,100 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::FromRow;
+use std::sync::Arc;
+
+use crate::database::Database;
+use crate::models::{AppResult, HealthSample};
+
+/// Healthy and total checks of a component on one day
+#[derive(Debug, Clone, FromRow)]
+pub struct DailyCheckCounts {
+    pub component: String,
+    pub day: NaiveDate,
+    pub healthy: i64,
+    pub total: i64,
+}
+
+/// A check whose outcome differs from the component's previous one
+#[derive(Debug, Clone, FromRow)]
+pub struct HealthTransition {
+    pub component: String,
+    pub healthy: bool,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Stores component health checks and summarizes them for the status page
+pub struct HealthCheckRepository {
+    database: Arc<Database>,
+}
+
+impl HealthCheckRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn record(&self, samples: &[HealthSample]) -> AppResult<()> {
+        if samples.is_empty() {
+            return Ok(());
+        }
+        let mut query =
+            sqlx::QueryBuilder::new("INSERT INTO health_checks (component, healthy, latency_ms, error, checked_at) ");
+        query.push_values(samples, |mut row, sample| {
+            row.push_bind(&sample.component)
+                .push_bind(sample.healthy)
+                .push_bind(sample.latency_ms)
+                .push_bind(&sample.error)
+                .push_bind(sample.checked_at);
+        });
+        query.build().execute(self.database.pool()).await?;
+        Ok(())
+    }
+
+    pub async fn daily_counts(&self, since: DateTime<Utc>) -> AppResult<Vec<DailyCheckCounts>> {
+        let counts = sqlx::query_as::<_, DailyCheckCounts>(
+            "SELECT component, DATE(checked_at) AS day, COUNT(*) FILTER (WHERE healthy) AS healthy, COUNT(*) AS total \
+             FROM health_checks WHERE checked_at >= $1 \
+             GROUP BY component, day ORDER BY component, day",
+        )
+        .bind(since)
+        .fetch_all(self.database.pool())
+        .await?;
+        Ok(counts)
+    }
+
+    /// Checks since `since` that changed a component's health, oldest first;
+    /// each component's first check in the window counts as a change
+    pub async fn transitions(&self, since: DateTime<Utc>) -> AppResult<Vec<HealthTransition>> {
+        let transitions = sqlx::query_as::<_, HealthTransition>(
+            "SELECT component, healthy, checked_at FROM ( \
+                 SELECT component, healthy, checked_at, \
+                        LAG(healthy) OVER (PARTITION BY component ORDER BY checked_at) AS previous \
+                 FROM health_checks WHERE checked_at >= $1 \
+             ) checks WHERE previous IS DISTINCT FROM healthy \
+             ORDER BY component, checked_at",
+        )
+        .bind(since)
+        .fetch_all(self.database.pool())
+        .await?;
+        Ok(transitions)
+    }
+
+    /// The most recent check of every component
+    pub async fn latest(&self) -> AppResult<Vec<HealthTransition>> {
+        let latest = sqlx::query_as::<_, HealthTransition>(
+            "SELECT DISTINCT ON (component) component, healthy, checked_at FROM health_checks \
+             ORDER BY component, checked_at DESC",
+        )
+        .fetch_all(self.database.pool())
+        .await?;
+        Ok(latest)
+    }
+
+    /// Delete checks older than `before`, returning how many were deleted
+    pub async fn prune(&self, before: DateTime<Utc>) -> AppResult<u64> {
+        let pruned = sqlx::query("DELETE FROM health_checks WHERE checked_at < $1")
+            .bind(before)
+            .execute(self.database.pool())
+            .await?;
+        Ok(pruned.rows_affected())
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{Postgres, QueryBuilder, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, ClientInfo, LoginAttempt, LoginHistoryFilters};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+
+const COLUMNS: &str = "id, user_id, email, succeeded, failure_reason, ip_address, user_agent, country, created_at";
+
+/// Every sign-in attempt and the client it came from
+pub struct LoginHistoryRepository {
+    database: Arc<Database>,
+}
+
+impl LoginHistoryRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn record(&self, attempt: &LoginAttempt) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO login_history \
+                (id, user_id, email, succeeded, failure_reason, ip_address, user_agent, country, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+        .bind(attempt.id)
+        .bind(attempt.user_id)
+        .bind(&attempt.email)
+        .bind(attempt.succeeded)
+        .bind(attempt.failure_reason.map(|reason| reason.as_str()))
+        .bind(&attempt.ip_address)
+        .bind(&attempt.user_agent)
+        .bind(&attempt.country)
+        .bind(attempt.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// A user's attempts matching `filters`, newest first
+    pub async fn list_for_user(&self, user_id: Uuid, filters: &LoginHistoryFilters) -> AppResult<Vec<LoginAttempt>> {
+        let mut query =
+            QueryBuilder::<Postgres>::new(format!("SELECT {} FROM login_history WHERE user_id = ", COLUMNS));
+        query.push_bind(user_id);
+        if let Some(succeeded) = filters.succeeded {
+            query.push(" AND succeeded = ").push_bind(succeeded);
+        }
+        if let Some(ip_address) = &filters.ip_address {
+            query.push(" AND ip_address = ").push_bind(ip_address.clone());
+        }
+        if let Some(from) = filters.from {
+            query.push(" AND created_at >= ").push_bind(from);
+        }
+        if let Some(to) = filters.to {
+            query.push(" AND created_at < ").push_bind(to);
+        }
+        query.push(" ORDER BY created_at DESC, id");
+        query
+            .push(" LIMIT ")
+            .push_bind(filters.limit.unwrap_or(DEFAULT_PAGE_SIZE));
+
+        let rows = query.build().fetch_all(self.database.pool()).await?;
+        rows.iter().map(map_attempt).collect()
+    }
+
+    /// Failed attempts from a client address since `since`, whichever accounts they tried
+    pub async fn failures_from_address(&self, ip_address: &str, since: DateTime<Utc>) -> AppResult<i64> {
+        let count = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM login_history WHERE ip_address = $1 AND NOT succeeded AND created_at >= $2",
+        )
+        .bind(ip_address)
+        .bind(since)
+        .fetch_one(self.database.pool())
+        .await?;
+        Ok(count)
+    }
+
+    /// Whether the user signed in before, and whether from this client's address and user agent
+    pub async fn familiarity(&self, user_id: Uuid, client: &ClientInfo) -> AppResult<(bool, bool)> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) > 0 AS signed_in_before, \
+                COUNT(*) FILTER (WHERE ip_address IS NOT DISTINCT FROM $2 AND user_agent = $3) > 0 AS known_client \
+             FROM login_history WHERE user_id = $1 AND succeeded",
+        )
+        .bind(user_id)
+        .bind(&client.ip_address)
+        .bind(&client.user_agent)
+        .fetch_one(self.database.pool())
+        .await?;
+        Ok((row.try_get("signed_in_before")?, row.try_get("known_client")?))
+    }
+}
+
+fn map_attempt(row: &PgRow) -> AppResult<LoginAttempt> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt login history row: {}", e));
+
+    Ok(LoginAttempt {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        email: row.try_get("email")?,
+        succeeded: row.try_get("succeeded")?,
+        failure_reason: row
+            .try_get::<Option<String>, _>("failure_reason")?
+            .map(|reason| reason.parse())
+            .transpose()
+            .map_err(invalid)?,
+        ip_address: row.try_get("ip_address")?,
+        user_agent: row.try_get("user_agent")?,
+        country: row.try_get("country")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
@@ -0,0 +1,76 @@
+pub mod user_repository;
+pub mod user_search_repository;
+pub mod outbox_repository;
+pub mod group_repository;
+pub mod notification_repository;
+pub mod notification_archive_repository;
+pub mod account_deletion_repository;
+pub mod api_key_repository;
+pub mod audit_repository;
+pub mod audit_log_repository;
+pub mod email_verification_repository;
+pub mod session_repository;
+pub mod second_factor_repository;
+pub mod bulk_operation_repository;
+pub mod broadcast_repository;
+pub mod password_history_repository;
+pub mod password_reset_repository;
+pub mod login_history_repository;
+pub mod suppression_repository;
+pub mod template_repository;
+pub mod tenant_branding_repository;
+pub mod announcement_repository;
+pub mod oauth_identity_repository;
+pub mod role_request_repository;
+pub mod role_grant_repository;
+pub mod service_account_repository;
+pub mod provider_callback_repository;
+pub mod policy_repository;
+pub mod passkey_repository;
+pub mod trusted_device_repository;
+pub mod export_job_repository;
+pub mod health_check_repository;
+pub mod organization_repository;
+pub mod tenant_user_repository;
+pub mod verification_reminder_repository;
+pub mod regional_repository;
+pub mod caching_repository;
+pub mod contract_tests;
+
+pub use user_repository::{UserRepository, PostgresUserRepository};
+pub use user_search_repository::{RebuildCheckpoint, SearchFacets, UserSearchEntry, UserSearchRepository};
+pub use outbox_repository::{OutboxLag, OutboxRepository};
+pub use group_repository::{GroupRepository, PostgresGroupRepository};
+pub use notification_repository::{NotificationRepository, PostgresNotificationRepository};
+pub use notification_archive_repository::{ArchivingNotificationRepository, NotificationArchiveRepository};
+pub use account_deletion_repository::AccountDeletionRepository;
+pub use api_key_repository::ApiKeyRepository;
+pub use audit_repository::AuditRepository;
+pub use audit_log_repository::AuditLogRepository;
+pub use email_verification_repository::EmailVerificationRepository;
+pub use session_repository::{PostgresSessionRepository, SessionRepository};
+pub use second_factor_repository::{PostgresSecondFactorRepository, SecondFactorRepository};
+pub use bulk_operation_repository::BulkOperationRepository;
+pub use broadcast_repository::BroadcastRepository;
+pub use password_history_repository::PasswordHistoryRepository;
+pub use password_reset_repository::PasswordResetRepository;
+pub use login_history_repository::LoginHistoryRepository;
+pub use suppression_repository::SuppressionRepository;
+pub use template_repository::{PostgresTemplateRepository, TemplateRepository};
+pub use tenant_branding_repository::{PostgresTenantBrandingRepository, TenantBrandingRepository};
+pub use announcement_repository::{AnnouncementRepository, PostgresAnnouncementRepository};
+pub use oauth_identity_repository::{OAuthIdentityRepository, PostgresOAuthIdentityRepository};
+pub use role_request_repository::RoleRequestRepository;
+pub use role_grant_repository::RoleGrantRepository;
+pub use service_account_repository::ServiceAccountRepository;
+pub use provider_callback_repository::ProviderCallbackRepository;
+pub use policy_repository::PolicyRepository;
+pub use passkey_repository::PasskeyRepository;
+pub use trusted_device_repository::TrustedDeviceRepository;
+pub use export_job_repository::ExportJobRepository;
+pub use health_check_repository::{DailyCheckCounts, HealthCheckRepository, HealthTransition};
+pub use organization_repository::OrganizationRepository;
+pub use tenant_user_repository::TenantUserRepository;
+pub use verification_reminder_repository::VerificationReminderRepository;
+pub use regional_repository::{DatabaseRouter, RegionalUserRepository};
+pub use caching_repository::{CachedUser, CachingTemplateRepository, CachingUserRepository};
@@ -0,0 +1,223 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sqlx::Row;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::notification_repository::NotificationRepository;
+use crate::database::Database;
+use crate::models::{AppError, AppResult, EngagementEvent, Notification, NotificationFilters, TemplateEngagement};
+use crate::services::StorageService;
+use crate::utils::{new_id, RequestContext};
+
+const ARCHIVE_PREFIX: &str = "notifications/archive";
+
+/// Notifications moved out of Postgres into compressed files in storage.
+///
+/// Each archival batch becomes one gzipped JSON-lines file, and an index
+/// table keeps which file every archived notification is in, so reads
+/// open only the files they need. Index rows go with their user's account;
+/// the copies in the files are then unreachable.
+pub struct NotificationArchiveRepository {
+    database: Arc<Database>,
+    storage: Arc<StorageService>,
+}
+
+impl NotificationArchiveRepository {
+    pub fn new(database: Arc<Database>, storage: Arc<StorageService>) -> Self {
+        Self { database, storage }
+    }
+
+    /// Write `notifications` to a new archive file and move their rows to the index, returning how many moved.
+    ///
+    /// The file is written first and removed again if moving the rows
+    /// fails, so a notification is always either in Postgres or archived.
+    pub async fn archive(&self, notifications: &[Notification]) -> AppResult<u64> {
+        let Some(first) = notifications.first() else {
+            return Ok(0);
+        };
+        let key = format!("{}/{}/{}.jsonl.gz", ARCHIVE_PREFIX, first.created_at.format("%Y/%m"), new_id());
+        self.storage.put(&key, &encode(notifications)?).await?;
+        match self.move_rows(&key, notifications).await {
+            Ok(moved) => Ok(moved),
+            Err(e) => {
+                let _ = self.storage.delete(&key).await;
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn find(&self, id: Uuid) -> AppResult<Option<Notification>> {
+        let key: Option<String> = sqlx::query_scalar("SELECT archive_key FROM notification_archive WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        let Some(key) = key else {
+            return Ok(None);
+        };
+        Ok(self.read(&key).await?.into_iter().find(|notification| notification.id == id))
+    }
+
+    /// A user's archived notifications created before `before`, newest first
+    pub async fn list_for_user_before(
+        &self,
+        user_id: Uuid,
+        before: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> AppResult<Vec<Notification>> {
+        let rows = RequestContext::bounded("notification_archive.list_for_user", async {
+            Ok(sqlx::query(
+                "SELECT id, archive_key FROM notification_archive \
+                 WHERE user_id = $1 AND ($2::timestamptz IS NULL OR created_at < $2) \
+                 ORDER BY created_at DESC LIMIT $3",
+            )
+            .bind(user_id)
+            .bind(before)
+            .bind(limit)
+            .fetch_all(self.database.pool())
+            .await?)
+        })
+        .await?;
+
+        let mut wanted: HashMap<String, Vec<Uuid>> = HashMap::new();
+        for row in &rows {
+            wanted
+                .entry(row.try_get("archive_key")?)
+                .or_default()
+                .push(row.try_get("id")?);
+        }
+        let mut found = Vec::with_capacity(rows.len());
+        for (key, ids) in wanted {
+            let archived = self.read(&key).await?;
+            found.extend(archived.into_iter().filter(|notification| ids.contains(&notification.id)));
+        }
+        found.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(found)
+    }
+
+    async fn move_rows(&self, key: &str, notifications: &[Notification]) -> AppResult<u64> {
+        let ids: Vec<Uuid> = notifications.iter().map(|notification| notification.id).collect();
+        let mut tx = self.database.pool().begin().await?;
+        sqlx::query(
+            "INSERT INTO notification_archive (id, user_id, created_at, archive_key) \
+             SELECT id, user_id, created_at, $2 FROM notifications WHERE id = ANY($1) \
+             ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(&ids)
+        .bind(key)
+        .execute(&mut *tx)
+        .await?;
+        let moved = sqlx::query("DELETE FROM notifications WHERE id = ANY($1)")
+            .bind(&ids)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(moved.rows_affected())
+    }
+
+    async fn read(&self, key: &str) -> AppResult<Vec<Notification>> {
+        let Some(content) = self.storage.get(key).await? else {
+            return Err(AppError::Internal(format!("Notification archive {} is missing", key)));
+        };
+        decode(key, &content)
+    }
+}
+
+/// Read-through to the archive in front of a `NotificationRepository`.
+///
+/// Lookups by id and users' history find archived notifications as well,
+/// so callers need not know where a notification is kept. Writes only
+/// reach notifications still in Postgres.
+pub struct ArchivingNotificationRepository {
+    inner: Arc<dyn NotificationRepository>,
+    archive: Arc<NotificationArchiveRepository>,
+}
+
+impl ArchivingNotificationRepository {
+    pub fn new(inner: Arc<dyn NotificationRepository>, archive: Arc<NotificationArchiveRepository>) -> Self {
+        Self { inner, archive }
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for ArchivingNotificationRepository {
+    async fn create(&self, notification: &Notification) -> AppResult<()> {
+        self.inner.create(notification).await
+    }
+
+    async fn update_status(&self, notification: &Notification) -> AppResult<()> {
+        self.inner.update_status(notification).await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> AppResult<Option<Notification>> {
+        match self.inner.find_by_id(id).await? {
+            Some(notification) => Ok(Some(notification)),
+            None => self.archive.find(id).await,
+        }
+    }
+
+    async fn list_for_user(&self, user_id: Uuid, limit: i64) -> AppResult<Vec<Notification>> {
+        self.list_for_user_before(user_id, None, limit).await
+    }
+
+    /// Pending notifications are never archived, so the two lists can interleave and are merged by age
+    async fn list_for_user_before(
+        &self,
+        user_id: Uuid,
+        before: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> AppResult<Vec<Notification>> {
+        let mut notifications = self.inner.list_for_user_before(user_id, before, limit).await?;
+        notifications.extend(self.archive.list_for_user_before(user_id, before, limit).await?);
+        notifications.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        notifications.truncate(limit.max(0) as usize);
+        Ok(notifications)
+    }
+
+    async fn pending_before(&self, cutoff: DateTime<Utc>, limit: i64) -> AppResult<Vec<Notification>> {
+        self.inner.pending_before(cutoff, limit).await
+    }
+
+    async fn settled_before(&self, cutoff: DateTime<Utc>, limit: i64) -> AppResult<Vec<Notification>> {
+        self.inner.settled_before(cutoff, limit).await
+    }
+
+    async fn cancel_pending(&self, tenant_id: &str, filters: &NotificationFilters, reason: &str) -> AppResult<u64> {
+        self.inner.cancel_pending(tenant_id, filters, reason).await
+    }
+
+    async fn record_engagement(&self, id: Uuid, event: EngagementEvent) -> AppResult<bool> {
+        self.inner.record_engagement(id, event).await
+    }
+
+    async fn engagement_by_template(&self, since: DateTime<Utc>) -> AppResult<Vec<TemplateEngagement>> {
+        self.inner.engagement_by_template(since).await
+    }
+}
+
+fn encode(notifications: &[Notification]) -> AppResult<Vec<u8>> {
+    let failed = |e: String| AppError::Internal(format!("Failed to encode notification archive: {}", e));
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for notification in notifications {
+        serde_json::to_writer(&mut encoder, notification).map_err(|e| failed(e.to_string()))?;
+        encoder.write_all(b"\n").map_err(|e| failed(e.to_string()))?;
+    }
+    encoder.finish().map_err(|e| failed(e.to_string()))
+}
+
+fn decode(key: &str, content: &[u8]) -> AppResult<Vec<Notification>> {
+    let corrupt = |e: String| AppError::Internal(format!("Corrupt notification archive {}: {}", key, e));
+    BufReader::new(GzDecoder::new(content))
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+        .map(|line| {
+            let line = line.map_err(|e| corrupt(e.to_string()))?;
+            serde_json::from_str(&line).map_err(|e| corrupt(e.to_string()))
+        })
+        .collect()
+}
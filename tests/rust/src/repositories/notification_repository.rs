@@ -0,0 +1,287 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{
+    AppError, AppResult, EngagementEvent, Notification, NotificationFilters, TemplateEngagement, TenantContext,
+};
+use crate::utils::RequestContext;
+
+const NOTIFICATION_COLUMNS: &str = "id, user_id, notification_type, channel, status, recipient, \
+    title, message, html_message, tenant_id, sender, metadata, template_key, template_version, \
+    created_at, sent_at, read_at, opened_at, clicked_at, open_count, click_count";
+
+/// Persistence boundary for notifications
+#[async_trait]
+pub trait NotificationRepository: Send + Sync {
+    async fn create(&self, notification: &Notification) -> AppResult<()>;
+    /// Persist status, delivery timestamps and metadata changes
+    async fn update_status(&self, notification: &Notification) -> AppResult<()>;
+    async fn find_by_id(&self, id: Uuid) -> AppResult<Option<Notification>>;
+    async fn list_for_user(&self, user_id: Uuid, limit: i64) -> AppResult<Vec<Notification>>;
+    /// `list_for_user` starting below `before`, for paging back through history
+    async fn list_for_user_before(
+        &self,
+        user_id: Uuid,
+        before: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> AppResult<Vec<Notification>>;
+    /// Undelivered notifications created before `cutoff`, oldest first
+    async fn pending_before(&self, cutoff: DateTime<Utc>, limit: i64) -> AppResult<Vec<Notification>>;
+    /// Notifications past pending created before `cutoff`, oldest first, for archival
+    async fn settled_before(&self, cutoff: DateTime<Utc>, limit: i64) -> AppResult<Vec<Notification>>;
+    /// Mark the tenant's pending notifications matching `filters` cancelled; returns how many were
+    async fn cancel_pending(&self, tenant_id: &str, filters: &NotificationFilters, reason: &str) -> AppResult<u64>;
+    /// Count an open or click; returns false if the notification does not exist
+    async fn record_engagement(&self, id: Uuid, event: EngagementEvent) -> AppResult<bool>;
+    /// Sent, opened and clicked email counts per template revision since `since`
+    async fn engagement_by_template(&self, since: DateTime<Utc>) -> AppResult<Vec<TemplateEngagement>>;
+}
+
+pub struct PostgresNotificationRepository {
+    database: Arc<Database>,
+}
+
+impl PostgresNotificationRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for PostgresNotificationRepository {
+    async fn create(&self, notification: &Notification) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO notifications (id, user_id, notification_type, channel, status, recipient, \
+                title, message, html_message, tenant_id, sender, metadata, template_key, \
+                template_version, created_at, sent_at, read_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)",
+        )
+        .bind(notification.id)
+        .bind(notification.user_id)
+        .bind(notification.notification_type.as_str())
+        .bind(notification.channel.as_str())
+        .bind(notification.status.as_str())
+        .bind(&notification.recipient)
+        .bind(&notification.title)
+        .bind(&notification.message)
+        .bind(&notification.html_message)
+        .bind(&notification.tenant_id)
+        .bind(&notification.sender)
+        .bind(metadata_json(notification)?)
+        .bind(&notification.template_key)
+        .bind(notification.template_version)
+        .bind(notification.created_at)
+        .bind(notification.sent_at)
+        .bind(notification.read_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    async fn update_status(&self, notification: &Notification) -> AppResult<()> {
+        let updated = sqlx::query(
+            "UPDATE notifications SET status = $2, metadata = $3, sent_at = $4, read_at = $5 \
+             WHERE id = $1",
+        )
+        .bind(notification.id)
+        .bind(notification.status.as_str())
+        .bind(metadata_json(notification)?)
+        .bind(notification.sent_at)
+        .bind(notification.read_at)
+        .execute(self.database.pool())
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Err(AppError::NotFound(format!("Notification {} not found", notification.id)));
+        }
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> AppResult<Option<Notification>> {
+        let sql = format!("SELECT {} FROM notifications WHERE id = $1", NOTIFICATION_COLUMNS);
+        let row = sqlx::query(&sql)
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.map(|row| map_row(&row)).transpose()
+    }
+
+    async fn list_for_user(&self, user_id: Uuid, limit: i64) -> AppResult<Vec<Notification>> {
+        let sql = format!(
+            "SELECT {} FROM notifications WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2",
+            NOTIFICATION_COLUMNS
+        );
+        let rows = RequestContext::bounded("notifications.list_for_user", async {
+            Ok(sqlx::query(&sql)
+                .bind(user_id)
+                .bind(limit)
+                .fetch_all(self.database.pool())
+                .await?)
+        })
+        .await?;
+        rows.iter().map(map_row).collect()
+    }
+
+    async fn list_for_user_before(
+        &self,
+        user_id: Uuid,
+        before: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> AppResult<Vec<Notification>> {
+        let sql = format!(
+            "SELECT {} FROM notifications WHERE user_id = $1 AND ($2::timestamptz IS NULL OR created_at < $2) \
+             ORDER BY created_at DESC LIMIT $3",
+            NOTIFICATION_COLUMNS
+        );
+        let rows = RequestContext::bounded("notifications.list_for_user", async {
+            Ok(sqlx::query(&sql)
+                .bind(user_id)
+                .bind(before)
+                .bind(limit)
+                .fetch_all(self.database.pool())
+                .await?)
+        })
+        .await?;
+        rows.iter().map(map_row).collect()
+    }
+
+    async fn pending_before(&self, cutoff: DateTime<Utc>, limit: i64) -> AppResult<Vec<Notification>> {
+        let sql = format!(
+            "SELECT {} FROM notifications WHERE status = 'pending' AND created_at < $1 \
+             ORDER BY created_at LIMIT $2",
+            NOTIFICATION_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(cutoff)
+            .bind(limit)
+            .fetch_all(self.database.pool())
+            .await?;
+        rows.iter().map(map_row).collect()
+    }
+
+    async fn settled_before(&self, cutoff: DateTime<Utc>, limit: i64) -> AppResult<Vec<Notification>> {
+        let sql = format!(
+            "SELECT {} FROM notifications WHERE status <> 'pending' AND created_at < $1 \
+             ORDER BY created_at LIMIT $2",
+            NOTIFICATION_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(cutoff)
+            .bind(limit)
+            .fetch_all(self.database.pool())
+            .await?;
+        rows.iter().map(map_row).collect()
+    }
+
+    async fn cancel_pending(&self, tenant_id: &str, filters: &NotificationFilters, reason: &str) -> AppResult<u64> {
+        // Rows written before tenants existed belong to the default tenant
+        let cancelled = sqlx::query(
+            "UPDATE notifications \
+             SET status = 'cancelled', metadata = metadata || jsonb_build_object('cancel_reason', $2::text) \
+             WHERE status = 'pending' AND COALESCE(tenant_id, $3) = $1 \
+               AND ($4::uuid IS NULL OR metadata->>'broadcast_id' = $4::text) \
+               AND ($5::uuid IS NULL OR user_id = $5) \
+               AND ($6::text IS NULL OR notification_type = $6) \
+               AND ($7::text IS NULL OR channel = $7) \
+               AND ($8::text IS NULL OR template_key = $8) \
+               AND ($9::timestamptz IS NULL OR created_at >= $9)",
+        )
+        .bind(tenant_id)
+        .bind(reason)
+        .bind(TenantContext::default().tenant_id)
+        .bind(filters.broadcast_id)
+        .bind(filters.user_id)
+        .bind(filters.notification_type.map(|t| t.as_str()))
+        .bind(filters.channel.map(|c| c.as_str()))
+        .bind(&filters.template_key)
+        .bind(filters.created_after)
+        .execute(self.database.pool())
+        .await?;
+        Ok(cancelled.rows_affected())
+    }
+
+    async fn record_engagement(&self, id: Uuid, event: EngagementEvent) -> AppResult<bool> {
+        // A click proves the email was opened even when images were blocked
+        let sql = match event {
+            EngagementEvent::Open => {
+                "UPDATE notifications SET open_count = open_count + 1, \
+                    opened_at = COALESCE(opened_at, NOW()) WHERE id = $1"
+            }
+            EngagementEvent::Click => {
+                "UPDATE notifications SET click_count = click_count + 1, \
+                    clicked_at = COALESCE(clicked_at, NOW()), opened_at = COALESCE(opened_at, NOW()) \
+                 WHERE id = $1"
+            }
+        };
+        let updated = sqlx::query(sql).bind(id).execute(self.database.pool()).await?;
+        Ok(updated.rows_affected() > 0)
+    }
+
+    async fn engagement_by_template(&self, since: DateTime<Utc>) -> AppResult<Vec<TemplateEngagement>> {
+        let query = sqlx::query(
+            "SELECT template_key, template_version, \
+                COUNT(*) FILTER (WHERE sent_at IS NOT NULL) AS sent, \
+                COUNT(*) FILTER (WHERE opened_at IS NOT NULL) AS opened, \
+                COUNT(*) FILTER (WHERE clicked_at IS NOT NULL) AS clicked \
+             FROM notifications \
+             WHERE channel = 'email' AND template_key IS NOT NULL AND created_at >= $1 \
+             GROUP BY template_key, template_version \
+             ORDER BY template_key, template_version DESC",
+        )
+        .bind(since);
+        let rows = RequestContext::bounded("notifications.engagement", async {
+            Ok(query.fetch_all(self.database.pool()).await?)
+        })
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(TemplateEngagement::new(
+                    row.try_get("template_key")?,
+                    row.try_get("template_version")?,
+                    row.try_get("sent")?,
+                    row.try_get("opened")?,
+                    row.try_get("clicked")?,
+                ))
+            })
+            .collect()
+    }
+}
+
+fn metadata_json(notification: &Notification) -> AppResult<serde_json::Value> {
+    serde_json::to_value(&notification.metadata)
+        .map_err(|e| AppError::Internal(format!("Unserializable notification metadata: {}", e)))
+}
+
+fn map_row(row: &PgRow) -> AppResult<Notification> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt notification row: {}", e));
+
+    Ok(Notification {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        notification_type: row.try_get::<String, _>("notification_type")?.parse().map_err(invalid)?,
+        channel: row.try_get::<String, _>("channel")?.parse().map_err(invalid)?,
+        status: row.try_get::<String, _>("status")?.parse().map_err(invalid)?,
+        recipient: row.try_get("recipient")?,
+        title: row.try_get("title")?,
+        message: row.try_get("message")?,
+        html_message: row.try_get("html_message")?,
+        tenant_id: row.try_get("tenant_id")?,
+        sender: row.try_get("sender")?,
+        metadata: serde_json::from_value(row.try_get("metadata")?).map_err(|e| invalid(e.to_string()))?,
+        template_key: row.try_get("template_key")?,
+        template_version: row.try_get("template_version")?,
+        created_at: row.try_get("created_at")?,
+        sent_at: row.try_get("sent_at")?,
+        read_at: row.try_get("read_at")?,
+        opened_at: row.try_get("opened_at")?,
+        clicked_at: row.try_get("clicked_at")?,
+        open_count: row.try_get("open_count")?,
+        click_count: row.try_get("click_count")?,
+    })
+}
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppResult, ExternalIdentity};
+
+/// Persistence boundary for links between provider accounts and users
+#[async_trait]
+pub trait OAuthIdentityRepository: Send + Sync {
+    /// The user an identity is linked to, recording that it was just used
+    async fn linked_user(&self, provider: &str, subject: &str) -> AppResult<Option<Uuid>>;
+    /// Returns false when the identity was already linked
+    async fn link(&self, identity: &ExternalIdentity, user_id: Uuid) -> AppResult<bool>;
+}
+
+pub struct PostgresOAuthIdentityRepository {
+    database: Arc<Database>,
+}
+
+impl PostgresOAuthIdentityRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl OAuthIdentityRepository for PostgresOAuthIdentityRepository {
+    async fn linked_user(&self, provider: &str, subject: &str) -> AppResult<Option<Uuid>> {
+        let user_id = sqlx::query_scalar(
+            "UPDATE oauth_identities SET last_used_at = NOW() \
+             WHERE provider = $1 AND subject = $2 \
+             RETURNING user_id",
+        )
+        .bind(provider)
+        .bind(subject)
+        .fetch_optional(self.database.pool())
+        .await?;
+        Ok(user_id)
+    }
+
+    async fn link(&self, identity: &ExternalIdentity, user_id: Uuid) -> AppResult<bool> {
+        let linked = sqlx::query(
+            "INSERT INTO oauth_identities (provider, subject, user_id, email, last_used_at) \
+             VALUES ($1, $2, $3, $4, NOW()) \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(&identity.provider)
+        .bind(&identity.subject)
+        .bind(user_id)
+        .bind(&identity.email)
+        .execute(self.database.pool())
+        .await?;
+        Ok(linked.rows_affected() == 1)
+    }
+}
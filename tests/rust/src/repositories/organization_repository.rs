@@ -0,0 +1,163 @@
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, OrgRole, Organization, OrganizationMember};
+
+const ORGANIZATION_COLUMNS: &str = "id, tenant_id, name, created_by, created_at";
+const MEMBER_COLUMNS: &str = "organization_id, user_id, role, added_by, added_at";
+
+/// Organizations of every tenant and the roles their members hold in them
+pub struct OrganizationRepository {
+    database: Arc<Database>,
+}
+
+impl OrganizationRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    /// None when another organization of the tenant already has the name, ignoring case
+    pub async fn create(&self, organization: &Organization) -> AppResult<Option<Organization>> {
+        let row = sqlx::query(&format!(
+            "INSERT INTO organizations (id, tenant_id, name, created_by, created_at) VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT DO NOTHING RETURNING {}",
+            ORGANIZATION_COLUMNS
+        ))
+        .bind(organization.id)
+        .bind(&organization.tenant_id)
+        .bind(&organization.name)
+        .bind(organization.created_by)
+        .bind(organization.created_at)
+        .fetch_optional(self.database.pool())
+        .await?;
+        row.as_ref().map(map_organization).transpose()
+    }
+
+    pub async fn find(&self, tenant_id: &str, id: Uuid) -> AppResult<Option<Organization>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM organizations WHERE tenant_id = $1 AND id = $2",
+            ORGANIZATION_COLUMNS
+        ))
+        .bind(tenant_id)
+        .bind(id)
+        .fetch_optional(self.database.pool())
+        .await?;
+        row.as_ref().map(map_organization).transpose()
+    }
+
+    /// Organizations of a tenant, by name
+    pub async fn list(&self, tenant_id: &str) -> AppResult<Vec<Organization>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM organizations WHERE tenant_id = $1 ORDER BY LOWER(name), id",
+            ORGANIZATION_COLUMNS
+        ))
+        .bind(tenant_id)
+        .fetch_all(self.database.pool())
+        .await?;
+        rows.iter().map(map_organization).collect()
+    }
+
+    /// Members of an organization, longest standing first
+    pub async fn members(&self, organization_id: Uuid) -> AppResult<Vec<OrganizationMember>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM organization_members WHERE organization_id = $1 ORDER BY added_at, user_id",
+            MEMBER_COLUMNS
+        ))
+        .bind(organization_id)
+        .fetch_all(self.database.pool())
+        .await?;
+        rows.iter().map(map_member).collect()
+    }
+
+    /// The role a user holds in an organization; None when they are not a member
+    pub async fn member_role(&self, organization_id: Uuid, user_id: Uuid) -> AppResult<Option<OrgRole>> {
+        let role: Option<String> =
+            sqlx::query_scalar("SELECT role FROM organization_members WHERE organization_id = $1 AND user_id = $2")
+                .bind(organization_id)
+                .bind(user_id)
+                .fetch_optional(self.database.pool())
+                .await?;
+        role.map(|role| role.parse().map_err(corrupt)).transpose()
+    }
+
+    /// Add a member, or change the role of an existing one
+    pub async fn set_member(&self, member: &OrganizationMember) -> AppResult<OrganizationMember> {
+        let row = sqlx::query(&format!(
+            "INSERT INTO organization_members (organization_id, user_id, role, added_by, added_at) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (organization_id, user_id) DO UPDATE SET role = EXCLUDED.role RETURNING {}",
+            MEMBER_COLUMNS
+        ))
+        .bind(member.organization_id)
+        .bind(member.user_id)
+        .bind(member.role.as_str())
+        .bind(member.added_by)
+        .bind(member.added_at)
+        .fetch_one(self.database.pool())
+        .await?;
+        map_member(&row)
+    }
+
+    /// False when the user was not a member
+    pub async fn remove_member(&self, organization_id: Uuid, user_id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM organization_members WHERE organization_id = $1 AND user_id = $2")
+            .bind(organization_id)
+            .bind(user_id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The highest role the actor holds over the user in an organization
+    /// they share, counting only organizations where the actor outranks the
+    /// user's own role there; None when the actor has no standing over them
+    pub async fn standing(&self, actor_id: Uuid, user_id: Uuid) -> AppResult<Option<OrgRole>> {
+        let rows = sqlx::query(
+            "SELECT actor.role AS actor_role, target.role AS target_role \
+             FROM organization_members actor \
+             JOIN organization_members target ON target.organization_id = actor.organization_id \
+             WHERE actor.user_id = $1 AND target.user_id = $2",
+        )
+        .bind(actor_id)
+        .bind(user_id)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let mut standing: Option<OrgRole> = None;
+        for row in &rows {
+            let actor: OrgRole = row.try_get::<String, _>("actor_role")?.parse().map_err(corrupt)?;
+            let target: OrgRole = row.try_get::<String, _>("target_role")?.parse().map_err(corrupt)?;
+            if actor.can_manage(&target) && standing.map_or(true, |current| actor.level() > current.level()) {
+                standing = Some(actor);
+            }
+        }
+        Ok(standing)
+    }
+}
+
+fn corrupt(e: String) -> AppError {
+    AppError::Internal(format!("Corrupt organization member row: {}", e))
+}
+
+fn map_organization(row: &PgRow) -> AppResult<Organization> {
+    Ok(Organization {
+        id: row.try_get("id")?,
+        tenant_id: row.try_get("tenant_id")?,
+        name: row.try_get("name")?,
+        created_by: row.try_get("created_by")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+fn map_member(row: &PgRow) -> AppResult<OrganizationMember> {
+    Ok(OrganizationMember {
+        organization_id: row.try_get("organization_id")?,
+        user_id: row.try_get("user_id")?,
+        role: row.try_get::<String, _>("role")?.parse().map_err(corrupt)?,
+        added_by: row.try_get("added_by")?,
+        added_at: row.try_get("added_at")?,
+    })
+}
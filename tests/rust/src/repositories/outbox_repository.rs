@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use std::sync::Arc;
+
+use crate::database::Database;
+use crate::models::{AppResult, NewOutboxMessage, OutboxMessage};
+
+/// Backlog of unpublished outbox rows
+#[derive(Debug, Clone, Default)]
+pub struct OutboxLag {
+    pub pending: i64,
+    pub oldest_pending_at: Option<DateTime<Utc>>,
+}
+
+/// Persistence for the transactional outbox table
+pub struct OutboxRepository {
+    database: Arc<Database>,
+}
+
+impl OutboxRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    /// Write a message on the caller's connection, typically inside the domain transaction
+    pub async fn enqueue(&self, conn: &mut PgConnection, message: &NewOutboxMessage) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO outbox (aggregate_type, aggregate_id, event_type, payload, idempotency_key) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(&message.aggregate_type)
+        .bind(message.aggregate_id)
+        .bind(&message.event_type)
+        .bind(&message.payload)
+        .bind(message.idempotency_key)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Lock the oldest unpublished messages so concurrent relays skip them
+    pub async fn claim_batch(&self, conn: &mut PgConnection, limit: i64) -> AppResult<Vec<OutboxMessage>> {
+        let messages = sqlx::query_as::<_, OutboxMessage>(
+            "SELECT id, aggregate_type, aggregate_id, event_type, payload, idempotency_key, \
+             attempts, created_at FROM outbox \
+             WHERE published_at IS NULL ORDER BY id LIMIT $1 FOR UPDATE SKIP LOCKED",
+        )
+        .bind(limit)
+        .fetch_all(conn)
+        .await?;
+        Ok(messages)
+    }
+
+    pub async fn mark_published(&self, conn: &mut PgConnection, id: i64) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE outbox SET published_at = NOW(), attempts = attempts + 1, last_error = NULL \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record_failure(&self, conn: &mut PgConnection, id: i64, error: &str) -> AppResult<()> {
+        sqlx::query("UPDATE outbox SET attempts = attempts + 1, last_error = $2 WHERE id = $1")
+            .bind(id)
+            .bind(error)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Number of unpublished messages and when the oldest was written
+    pub async fn lag(&self) -> AppResult<OutboxLag> {
+        let (pending, oldest_pending_at): (i64, Option<DateTime<Utc>>) = sqlx::query_as(
+            "SELECT COUNT(*), MIN(created_at) FROM outbox WHERE published_at IS NULL",
+        )
+        .fetch_one(self.database.pool())
+        .await?;
+
+        Ok(OutboxLag {
+            pending,
+            oldest_pending_at,
+        })
+    }
+
+    /// Unpublished messages, oldest first; `failed` picks those whose last publish failed, or those never tried
+    pub async fn unpublished(&self, failed: Option<bool>, limit: i64) -> AppResult<Vec<OutboxMessage>> {
+        let messages = sqlx::query_as::<_, OutboxMessage>(
+            "SELECT id, aggregate_type, aggregate_id, event_type, payload, idempotency_key, \
+             attempts, last_error, created_at FROM outbox \
+             WHERE published_at IS NULL AND ($1::BOOLEAN IS NULL OR (last_error IS NOT NULL) = $1) \
+             ORDER BY id LIMIT $2",
+        )
+        .bind(failed)
+        .bind(limit)
+        .fetch_all(self.database.pool())
+        .await?;
+        Ok(messages)
+    }
+
+    /// Unpublished messages never tried and those whose last publish failed
+    pub async fn failure_counts(&self) -> AppResult<(i64, i64)> {
+        let counts = sqlx::query_as(
+            "SELECT COUNT(*) FILTER (WHERE last_error IS NULL), COUNT(*) FILTER (WHERE last_error IS NOT NULL) \
+             FROM outbox WHERE published_at IS NULL",
+        )
+        .fetch_one(self.database.pool())
+        .await?;
+        Ok(counts)
+    }
+
+    /// Delete up to `limit` messages published before `cutoff`, returning how many went
+    pub async fn delete_published_before(&self, cutoff: DateTime<Utc>, limit: i64) -> AppResult<u64> {
+        let deleted = sqlx::query(
+            "DELETE FROM outbox WHERE id IN \
+             (SELECT id FROM outbox WHERE published_at < $1 ORDER BY id LIMIT $2)",
+        )
+        .bind(cutoff)
+        .bind(limit)
+        .execute(self.database.pool())
+        .await?;
+        Ok(deleted.rows_affected())
+    }
+
+    /// Begin a transaction on the underlying pool
+    pub async fn begin(&self) -> AppResult<sqlx::Transaction<'static, sqlx::Postgres>> {
+        Ok(self.database.pool().begin().await?)
+    }
+}
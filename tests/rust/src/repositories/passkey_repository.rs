@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppResult, PasskeyCredential};
+
+const PASSKEY_COLUMNS: &str = "id, user_id, name, credential_id, public_key, created_at, last_used_at";
+
+/// Passkeys and the public keys their sign-ins are verified against
+pub struct PasskeyRepository {
+    database: Arc<Database>,
+}
+
+impl PasskeyRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn for_user(&self, user_id: Uuid) -> AppResult<Vec<PasskeyCredential>> {
+        let sql = format!(
+            "SELECT {} FROM passkeys WHERE user_id = $1 ORDER BY created_at",
+            PASSKEY_COLUMNS
+        );
+        let passkeys = sqlx::query_as::<_, PasskeyCredential>(&sql)
+            .bind(user_id)
+            .fetch_all(self.database.pool())
+            .await?;
+        Ok(passkeys)
+    }
+
+    pub async fn create(&self, passkey: &PasskeyCredential) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO passkeys (id, user_id, name, credential_id, public_key, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(passkey.id)
+        .bind(passkey.user_id)
+        .bind(&passkey.name)
+        .bind(&passkey.credential_id)
+        .bind(&passkey.public_key)
+        .bind(passkey.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Store the credential's new signature counter after a sign-in
+    pub async fn record_use(&self, id: Uuid, public_key: &serde_json::Value) -> AppResult<()> {
+        sqlx::query("UPDATE passkeys SET public_key = $2, last_used_at = NOW() WHERE id = $1")
+            .bind(id)
+            .bind(public_key)
+            .execute(self.database.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// False when the user has no such passkey
+    pub async fn delete(&self, user_id: Uuid, id: Uuid) -> AppResult<bool> {
+        let deleted = sqlx::query("DELETE FROM passkeys WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(deleted.rows_affected() == 1)
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::AppResult;
+
+/// Hashes of the passwords each user has had, newest first
+pub struct PasswordHistoryRepository {
+    database: Arc<Database>,
+}
+
+impl PasswordHistoryRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    /// The user's `limit` most recent password hashes
+    pub async fn recent(&self, user_id: Uuid, limit: usize) -> AppResult<Vec<String>> {
+        let hashes = sqlx::query_scalar(
+            "SELECT password_hash FROM password_history WHERE user_id = $1 \
+             ORDER BY created_at DESC, id DESC LIMIT $2",
+        )
+        .bind(user_id)
+        .bind(limit as i64)
+        .fetch_all(self.database.pool())
+        .await?;
+        Ok(hashes)
+    }
+
+    /// Add a hash and prune everything beyond the `keep` most recent
+    pub async fn record(&self, user_id: Uuid, password_hash: &str, keep: usize) -> AppResult<()> {
+        let mut tx = self.database.pool().begin().await?;
+        sqlx::query("INSERT INTO password_history (user_id, password_hash) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(password_hash)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "DELETE FROM password_history WHERE user_id = $1 AND id NOT IN ( \
+                SELECT id FROM password_history WHERE user_id = $1 \
+                ORDER BY created_at DESC, id DESC LIMIT $2)",
+        )
+        .bind(user_id)
+        .bind(keep as i64)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
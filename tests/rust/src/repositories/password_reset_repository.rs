@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, Transaction};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::AppResult;
+
+/// Outstanding password reset tokens, stored by hash
+pub struct PasswordResetRepository {
+    database: Arc<Database>,
+}
+
+impl PasswordResetRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    /// Store a new token, superseding any the user still had outstanding
+    pub async fn issue(&self, user_id: Uuid, token_hash: &str, expires_at: DateTime<Utc>) -> AppResult<()> {
+        let mut tx = self.database.pool().begin().await?;
+        sqlx::query("UPDATE password_reset_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO password_reset_tokens (token_hash, user_id, expires_at) VALUES ($1, $2, $3)")
+            .bind(token_hash)
+            .bind(user_id)
+            .bind(expires_at)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Spend a token in one statement, if it is unused and unexpired.
+    ///
+    /// The token stays spent only once the returned redemption is
+    /// committed; until then its row is locked, so a second use of the same
+    /// token waits and then finds it spent.
+    pub async fn redeem(&self, token_hash: &str) -> AppResult<Option<PasswordResetRedemption>> {
+        let mut tx = self.database.pool().begin().await?;
+        let user_id = sqlx::query_scalar(
+            "UPDATE password_reset_tokens SET used_at = NOW() \
+             WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW() \
+             RETURNING user_id",
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut *tx)
+        .await?;
+        Ok(user_id.map(|user_id| PasswordResetRedemption { user_id, tx }))
+    }
+}
+
+/// A token spent by `PasswordResetRepository::redeem`, kept spent by `commit` or given back by `release`
+pub struct PasswordResetRedemption {
+    pub user_id: Uuid,
+    tx: Transaction<'static, Postgres>,
+}
+
+impl PasswordResetRedemption {
+    /// Keep the token spent, together with every other token the user still had outstanding
+    pub async fn commit(mut self) -> AppResult<()> {
+        sqlx::query("UPDATE password_reset_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+            .bind(self.user_id)
+            .execute(&mut *self.tx)
+            .await?;
+        self.tx.commit().await?;
+        Ok(())
+    }
+
+    /// Give the token back, and release its row to whoever waits on it
+    pub async fn release(self) -> AppResult<()> {
+        self.tx.rollback().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, PolicyRule};
+
+/// Authorization rules managed in the database
+pub struct PolicyRepository {
+    database: Arc<Database>,
+}
+
+impl PolicyRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn all(&self) -> AppResult<Vec<PolicyRule>> {
+        let rows = sqlx::query(
+            "SELECT role, permission, resource, condition, effect FROM authz_policies ORDER BY created_at",
+        )
+        .fetch_all(self.database.pool())
+        .await?;
+        rows.iter().map(map_rule).collect()
+    }
+}
+
+fn map_rule(row: &PgRow) -> AppResult<PolicyRule> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt policy row: {}", e));
+
+    Ok(PolicyRule {
+        role: row.try_get("role")?,
+        permission: row.try_get("permission")?,
+        resource: row.try_get("resource")?,
+        condition: row
+            .try_get::<Option<String>, _>("condition")?
+            .map(|condition| condition.parse())
+            .transpose()
+            .map_err(invalid)?,
+        effect: row.try_get::<String, _>("effect")?.parse().map_err(invalid)?,
+    })
+}
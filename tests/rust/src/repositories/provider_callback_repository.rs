@@ -0,0 +1,117 @@
+use chrono::Utc;
+use sqlx::postgres::PgRow;
+use sqlx::{Postgres, QueryBuilder, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, CallbackFilters, CallbackOutcome, ProviderCallback};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+
+const CALLBACK_COLUMNS: &str = "id, provider, headers, payload, signature_valid, notification_id, outcome, error, \
+    replay_count, received_at, processed_at";
+
+/// Raw delivery provider callbacks and how processing them went
+pub struct ProviderCallbackRepository {
+    database: Arc<Database>,
+}
+
+impl ProviderCallbackRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn create(&self, callback: &ProviderCallback) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO provider_callbacks (id, provider, headers, payload, signature_valid, outcome, received_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(callback.id)
+        .bind(&callback.provider)
+        .bind(&callback.headers)
+        .bind(&callback.payload)
+        .bind(callback.signature_valid)
+        .bind(callback.outcome.as_str())
+        .bind(callback.received_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find(&self, id: Uuid) -> AppResult<Option<ProviderCallback>> {
+        let row = sqlx::query(&format!("SELECT {} FROM provider_callbacks WHERE id = $1", CALLBACK_COLUMNS))
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.as_ref().map(map_callback).transpose()
+    }
+
+    /// Callbacks matching `filters`, newest first
+    pub async fn list(&self, filters: &CallbackFilters) -> AppResult<Vec<ProviderCallback>> {
+        let mut query =
+            QueryBuilder::<Postgres>::new(format!("SELECT {} FROM provider_callbacks WHERE TRUE", CALLBACK_COLUMNS));
+        if let Some(provider) = &filters.provider {
+            query.push(" AND provider = ").push_bind(provider.clone());
+        }
+        if let Some(valid) = filters.signature_valid {
+            query.push(" AND signature_valid = ").push_bind(valid);
+        }
+        if let Some(outcome) = filters.outcome {
+            query.push(" AND outcome = ").push_bind(outcome.as_str());
+        }
+        if let Some(notification_id) = filters.notification_id {
+            query.push(" AND notification_id = ").push_bind(notification_id);
+        }
+        query.push(" ORDER BY received_at DESC, id");
+        query
+            .push(" LIMIT ")
+            .push_bind(filters.limit.unwrap_or(DEFAULT_PAGE_SIZE));
+
+        let rows = query.build().fetch_all(self.database.pool()).await?;
+        rows.iter().map(map_callback).collect()
+    }
+
+    /// Record the result of processing; `replayed` counts it as a replay
+    pub async fn record_outcome(
+        &self,
+        callback: &ProviderCallback,
+        replayed: bool,
+    ) -> AppResult<ProviderCallback> {
+        let row = sqlx::query(&format!(
+            "UPDATE provider_callbacks SET signature_valid = $2, notification_id = $3, outcome = $4, error = $5, \
+                processed_at = $6, replay_count = replay_count + $7 \
+             WHERE id = $1 RETURNING {}",
+            CALLBACK_COLUMNS
+        ))
+        .bind(callback.id)
+        .bind(callback.signature_valid)
+        .bind(callback.notification_id)
+        .bind(callback.outcome.as_str())
+        .bind(&callback.error)
+        .bind(Utc::now())
+        .bind(i32::from(replayed))
+        .fetch_optional(self.database.pool())
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Provider callback {} not found", callback.id)))?;
+        map_callback(&row)
+    }
+}
+
+fn map_callback(row: &PgRow) -> AppResult<ProviderCallback> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt provider callback row: {}", e));
+
+    Ok(ProviderCallback {
+        id: row.try_get("id")?,
+        provider: row.try_get("provider")?,
+        headers: row.try_get("headers")?,
+        payload: row.try_get("payload")?,
+        signature_valid: row.try_get("signature_valid")?,
+        notification_id: row.try_get("notification_id")?,
+        outcome: row.try_get::<String, _>("outcome")?.parse().map_err(invalid)?,
+        error: row.try_get("error")?,
+        replay_count: row.try_get("replay_count")?,
+        received_at: row.try_get("received_at")?,
+        processed_at: row.try_get("processed_at")?,
+    })
+}
@@ -0,0 +1,355 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::try_join_all;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::user_repository::{check_cursor_order, page, UserRepository, DEFAULT_PAGE_SIZE};
+use crate::config::ResidencyConfig;
+use crate::database::Database;
+use crate::models::{AppError, AppResult, User, UserCursor, UserFilters, UserPage, UserPreferences};
+
+/// The database of every region accounts can be stored in
+pub struct DatabaseRouter {
+    home_region: String,
+    home: Arc<Database>,
+    regions: HashMap<String, Arc<Database>>,
+}
+
+impl DatabaseRouter {
+    /// Connect to the database of every region besides the home one
+    pub async fn connect(home: Arc<Database>, config: &ResidencyConfig) -> AppResult<Self> {
+        let mut regions = HashMap::new();
+        for (region, url) in &config.region_databases {
+            regions.insert(region.clone(), Arc::new(Database::connect(url).await?));
+        }
+        Ok(Self {
+            home_region: config.home_region.clone(),
+            home,
+            regions,
+        })
+    }
+
+    pub fn home_region(&self) -> &str {
+        &self.home_region
+    }
+
+    pub fn home(&self) -> &Arc<Database> {
+        &self.home
+    }
+
+    /// Databases of the regions other than the home one
+    pub fn regions(&self) -> impl Iterator<Item = (&str, &Arc<Database>)> {
+        self.regions.iter().map(|(region, database)| (region.as_str(), database))
+    }
+}
+
+/// Routes each account to the `UserRepository` of its region.
+///
+/// Accounts of the home region are stored as usual. Any other account is
+/// stored in its region's database, and the home database only keeps an
+/// anchor row with its id, region, role and status, so sessions, audit
+/// events and other rows referencing users keep working there. That anchor
+/// is also how a lookup by id finds the region without asking all of them.
+///
+/// Lookups by email or username and the admin listings are federated: every
+/// region is queried concurrently and the results merged. Tenant
+/// memberships are only kept in the home database, so other regions count
+/// every account they store as the default tenant's when listings are
+/// confined to a request's tenant.
+pub struct RegionalUserRepository {
+    home: Arc<dyn UserRepository>,
+    router: Arc<DatabaseRouter>,
+    regions: HashMap<String, Arc<dyn UserRepository>>,
+}
+
+impl RegionalUserRepository {
+    pub fn new(
+        home: Arc<dyn UserRepository>,
+        router: Arc<DatabaseRouter>,
+        regions: HashMap<String, Arc<dyn UserRepository>>,
+    ) -> Self {
+        Self { home, router, regions }
+    }
+
+    /// Repository of a region other than the home one; None for the home region
+    fn remote(&self, region: Option<&str>) -> AppResult<Option<(&str, &Arc<dyn UserRepository>)>> {
+        match region {
+            None => Ok(None),
+            Some(region) if region == self.router.home_region() => Ok(None),
+            Some(region) => self
+                .regions
+                .get_key_value(region)
+                .map(|(region, repository)| Some((region.as_str(), repository)))
+                .ok_or_else(|| AppError::Validation(vec![format!("Unknown region: {}", region)])),
+        }
+    }
+
+    /// Region an existing account is stored in, read from its anchor
+    async fn region_of(&self, id: Uuid) -> AppResult<Option<String>> {
+        if self.regions.is_empty() {
+            return Ok(None);
+        }
+        let region: Option<Option<String>> = sqlx::query_scalar("SELECT region FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self.router.home().pool())
+            .await?;
+        Ok(region.flatten())
+    }
+
+    /// Run `query` against every region, the home one first
+    async fn federate<'a, T, F, Fut>(&'a self, query: F) -> AppResult<Vec<(Option<&'a str>, T)>>
+    where
+        F: Fn(&'a Arc<dyn UserRepository>) -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        let regions = std::iter::once((None, &self.home))
+            .chain(self.regions.iter().map(|(region, repository)| (Some(region.as_str()), repository)));
+        try_join_all(regions.map(|(region, repository)| {
+            let pending = query(repository);
+            async move { Ok((region, pending.await?)) }
+        }))
+        .await
+    }
+
+    async fn insert_anchor(&self, region: &str, user: &User) -> AppResult<()> {
+        let preferences = serde_json::to_value(UserPreferences::default())
+            .map_err(|e| AppError::Internal(format!("Unserializable user preferences: {}", e)))?;
+        sqlx::query(
+            "INSERT INTO users (id, email, username, first_name, last_name, role, status, email_verified, \
+                login_count, failed_login_attempts, password_hash, metadata, preferences, created_at, \
+                updated_at, deleted_at, region) \
+             VALUES ($1, $2, $3, '', '', $4, $5, FALSE, 0, 0, '', '{}', $6, $7, $8, $9, $10)",
+        )
+        .bind(user.id)
+        .bind(format!("{}@{}.anchor.invalid", user.id, region))
+        .bind(format!("anchor-{}", user.id))
+        .bind(user.role.as_str())
+        .bind(user.status.as_str())
+        .bind(preferences)
+        .bind(user.created_at)
+        .bind(user.updated_at)
+        .bind(user.deleted_at)
+        .bind(region)
+        .execute(self.router.home().pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Keep the anchor's role and status current for reports counting users
+    async fn sync_anchor(&self, user: &User) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE users SET role = $2, status = $3, updated_at = $4, deleted_at = $5 \
+             WHERE id = $1 AND region IS NOT NULL",
+        )
+        .bind(user.id)
+        .bind(user.role.as_str())
+        .bind(user.status.as_str())
+        .bind(user.updated_at)
+        .bind(user.deleted_at)
+        .execute(self.router.home().pool())
+        .await?;
+        Ok(())
+    }
+
+    /// The repository `id` is stored with, for writes addressed by id alone
+    async fn owner(&self, id: Uuid) -> AppResult<(Option<&str>, &Arc<dyn UserRepository>)> {
+        let region = self.region_of(id).await?;
+        match self.remote(region.as_deref()) {
+            Ok(Some((region, repository))) => Ok((Some(region), repository)),
+            Ok(None) => Ok((None, &self.home)),
+            Err(_) => Err(AppError::Internal(format!(
+                "User {} is stored in region {} which is not configured",
+                id,
+                region.unwrap_or_default()
+            ))),
+        }
+    }
+}
+
+/// `user` as stored in its region's own database, where it is not an anchor
+fn resident(user: &User) -> User {
+    User {
+        region: None,
+        ..user.clone()
+    }
+}
+
+fn tagged(region: Option<&str>, user: User) -> User {
+    User {
+        region: region.map(str::to_string),
+        ..user
+    }
+}
+
+#[async_trait]
+impl UserRepository for RegionalUserRepository {
+    async fn create(&self, user: &User) -> AppResult<User> {
+        let Some((region, repository)) = self.remote(user.region.as_deref())? else {
+            return self.home.create(&resident(user)).await;
+        };
+        let created = repository.create(&resident(user)).await?;
+        if let Err(e) = self.insert_anchor(region, &created).await {
+            if let Err(undo) = repository.delete(created.id).await {
+                tracing::warn!("Could not remove user {} from region {}: {}", created.id, region, undo);
+            }
+            return Err(e);
+        }
+        Ok(tagged(Some(region), created))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> AppResult<Option<User>> {
+        let (region, repository) = self.owner(id).await?;
+        Ok(repository.find_by_id(id).await?.map(|user| tagged(region, user)))
+    }
+
+    async fn find_by_ids(&self, ids: &[Uuid]) -> AppResult<Vec<User>> {
+        if self.regions.is_empty() {
+            return self.home.find_by_ids(ids).await;
+        }
+        let found = self.federate(|repository| repository.find_by_ids(ids)).await?;
+        let mut users: Vec<User> = found
+            .into_iter()
+            .flat_map(|(region, users)| users.into_iter().map(move |user| tagged(region, user)))
+            .collect();
+        users.sort_by_key(|user| user.id);
+        Ok(users)
+    }
+
+    async fn find_by_email(&self, email: &str) -> AppResult<Option<User>> {
+        let found = self.federate(|repository| repository.find_by_email(email)).await?;
+        Ok(found
+            .into_iter()
+            .find_map(|(region, user)| user.map(|user| tagged(region, user))))
+    }
+
+    async fn find_by_username(&self, username: &str) -> AppResult<Option<User>> {
+        let found = self.federate(|repository| repository.find_by_username(username)).await?;
+        Ok(found
+            .into_iter()
+            .find_map(|(region, user)| user.map(|user| tagged(region, user))))
+    }
+
+    async fn email_taken(&self, email: &str) -> AppResult<bool> {
+        let found = self.federate(|repository| repository.email_taken(email)).await?;
+        Ok(found.into_iter().any(|(_, taken)| taken))
+    }
+
+    async fn username_taken(&self, username: &str) -> AppResult<bool> {
+        let found = self.federate(|repository| repository.username_taken(username)).await?;
+        Ok(found.into_iter().any(|(_, taken)| taken))
+    }
+
+    async fn update(&self, user: &User) -> AppResult<User> {
+        let Some((region, repository)) = self.remote(user.region.as_deref())? else {
+            return self.home.update(&resident(user)).await;
+        };
+        let updated = repository.update(&resident(user)).await?;
+        self.sync_anchor(&updated).await?;
+        Ok(tagged(Some(region), updated))
+    }
+
+    async fn update_versioned(&self, user: &User, expected_version: i64) -> AppResult<User> {
+        let Some((region, repository)) = self.remote(user.region.as_deref())? else {
+            return self.home.update_versioned(&resident(user), expected_version).await;
+        };
+        let updated = repository.update_versioned(&resident(user), expected_version).await?;
+        self.sync_anchor(&updated).await?;
+        Ok(tagged(Some(region), updated))
+    }
+
+    async fn mark_email_verified(&self, id: Uuid, email: &str) -> AppResult<Option<User>> {
+        let (region, repository) = self.owner(id).await?;
+        Ok(repository
+            .mark_email_verified(id, email)
+            .await?
+            .map(|user| tagged(region, user)))
+    }
+
+    /// Removing the anchor also removes the rows referencing the user in the home database
+    async fn delete(&self, id: Uuid) -> AppResult<()> {
+        let (region, repository) = self.owner(id).await?;
+        repository.delete(id).await?;
+        if region.is_some() {
+            sqlx::query("DELETE FROM users WHERE id = $1 AND region IS NOT NULL")
+                .bind(id)
+                .execute(self.router.home().pool())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Each region is asked for everything up to the end of the page, then
+    /// the page is cut from the merged results in the usual order
+    async fn list(&self, filters: &UserFilters) -> AppResult<Vec<User>> {
+        if self.regions.is_empty() {
+            return self.home.list(filters).await;
+        }
+        let offset = filters.offset.unwrap_or(0).max(0) as usize;
+        let limit = filters.limit.unwrap_or(DEFAULT_PAGE_SIZE).max(0) as usize;
+        let window = UserFilters {
+            offset: Some(0),
+            limit: Some((offset + limit) as i64),
+            ..filters.clone()
+        };
+        let found = self.federate(|repository| repository.list(&window)).await?;
+        let mut users: Vec<User> = found
+            .into_iter()
+            .flat_map(|(region, users)| users.into_iter().map(move |user| tagged(region, user)))
+            .collect();
+        users.sort_by(|a, b| filters.compare(a, b));
+        Ok(users.into_iter().skip(offset).take(limit).collect())
+    }
+
+    /// Each region is asked for one more than the page after the cursor, so
+    /// merging them shows whether any region has more
+    async fn list_page(&self, filters: &UserFilters, cursor: Option<&UserCursor>) -> AppResult<UserPage> {
+        check_cursor_order(filters)?;
+        if self.regions.is_empty() {
+            return self.home.list_page(filters, cursor).await;
+        }
+        let limit = filters.limit.unwrap_or(DEFAULT_PAGE_SIZE).max(0);
+        let window = UserFilters {
+            limit: Some(limit + 1),
+            ..filters.clone()
+        };
+        let found = self.federate(|repository| repository.list_page(&window, cursor)).await?;
+        let mut users: Vec<User> = found
+            .into_iter()
+            .flat_map(|(region, page)| page.users.into_iter().map(move |user| tagged(region, user)))
+            .collect();
+        users.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(a.id.cmp(&b.id)));
+        Ok(page(&mut users, limit as usize))
+    }
+
+    async fn count(&self, filters: &UserFilters) -> AppResult<i64> {
+        let counts = self.federate(|repository| repository.count(filters)).await?;
+        Ok(counts.into_iter().map(|(_, count)| count).sum())
+    }
+
+    async fn find_expired_lockouts(&self, limit: i64) -> AppResult<Vec<User>> {
+        let found = self.federate(|repository| repository.find_expired_lockouts(limit)).await?;
+        let mut users: Vec<User> = found
+            .into_iter()
+            .flat_map(|(region, users)| users.into_iter().map(move |user| tagged(region, user)))
+            .collect();
+        users.sort_by_key(|user| user.locked_until);
+        users.truncate(limit.max(0) as usize);
+        Ok(users)
+    }
+
+    async fn find_deleted_before(&self, cutoff: DateTime<Utc>, limit: i64) -> AppResult<Vec<User>> {
+        let found = self
+            .federate(|repository| repository.find_deleted_before(cutoff, limit))
+            .await?;
+        let mut users: Vec<User> = found
+            .into_iter()
+            .flat_map(|(region, users)| users.into_iter().map(move |user| tagged(region, user)))
+            .collect();
+        users.sort_by_key(|user| (user.deleted_at, user.id));
+        users.truncate(limit.max(0) as usize);
+        Ok(users)
+    }
+}
@@ -0,0 +1,106 @@
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, RoleGrant};
+
+const ROLE_GRANT_COLUMNS: &str = "id, user_id, role, granted_by, created_at, expires_at, ended_at";
+
+/// Temporary role grants, open until they expire or are revoked
+pub struct RoleGrantRepository {
+    database: Arc<Database>,
+}
+
+impl RoleGrantRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn create(&self, grant: &RoleGrant) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO role_grants (id, user_id, role, granted_by, created_at, expires_at) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(grant.id)
+        .bind(grant.user_id)
+        .bind(grant.role.as_str())
+        .bind(grant.granted_by)
+        .bind(grant.created_at)
+        .bind(grant.expires_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Grants raising the user's role right now
+    pub async fn active_for_user(&self, user_id: Uuid) -> AppResult<Vec<RoleGrant>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM role_grants WHERE user_id = $1 AND ended_at IS NULL AND expires_at > NOW()",
+            ROLE_GRANT_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(self.database.pool())
+        .await?;
+        rows.iter().map(map_grant).collect()
+    }
+
+    /// A user's grants, newest first
+    pub async fn for_user(&self, user_id: Uuid) -> AppResult<Vec<RoleGrant>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM role_grants WHERE user_id = $1 ORDER BY created_at DESC",
+            ROLE_GRANT_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(self.database.pool())
+        .await?;
+        rows.iter().map(map_grant).collect()
+    }
+
+    /// Close an open grant of the user early; None when there is none by that id
+    pub async fn end(&self, user_id: Uuid, id: Uuid) -> AppResult<Option<RoleGrant>> {
+        let row = sqlx::query(&format!(
+            "UPDATE role_grants SET ended_at = NOW() \
+             WHERE id = $1 AND user_id = $2 AND ended_at IS NULL RETURNING {}",
+            ROLE_GRANT_COLUMNS
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(self.database.pool())
+        .await?;
+        row.as_ref().map(map_grant).transpose()
+    }
+
+    /// Close up to `limit` grants that have expired, longest expired first.
+    ///
+    /// Rows are claimed with `SKIP LOCKED`, so instances sweeping at once
+    /// each close, and report, different grants.
+    pub async fn end_expired(&self, limit: i64) -> AppResult<Vec<RoleGrant>> {
+        let rows = sqlx::query(&format!(
+            "UPDATE role_grants SET ended_at = NOW() WHERE id IN ( \
+                SELECT id FROM role_grants WHERE ended_at IS NULL AND expires_at <= NOW() \
+                ORDER BY expires_at LIMIT $1 FOR UPDATE SKIP LOCKED \
+             ) RETURNING {}",
+            ROLE_GRANT_COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(self.database.pool())
+        .await?;
+        rows.iter().map(map_grant).collect()
+    }
+}
+
+fn map_grant(row: &PgRow) -> AppResult<RoleGrant> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt role grant row: {}", e));
+
+    Ok(RoleGrant {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        role: row.try_get::<String, _>("role")?.parse().map_err(invalid)?,
+        granted_by: row.try_get("granted_by")?,
+        created_at: row.try_get("created_at")?,
+        expires_at: row.try_get("expires_at")?,
+        ended_at: row.try_get("ended_at")?,
+    })
+}
@@ -0,0 +1,125 @@
+use chrono::Utc;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, RoleRequest, RoleRequestStatus};
+
+const ROLE_REQUEST_COLUMNS: &str = "id, user_id, requested_by, current_role, requested_role, rationale, status, \
+    decided_by, decision_note, created_at, decided_at";
+
+/// Role upgrade requests and their decisions
+pub struct RoleRequestRepository {
+    database: Arc<Database>,
+}
+
+impl RoleRequestRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn create(&self, request: &RoleRequest) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO role_requests (id, user_id, requested_by, current_role, requested_role, rationale, \
+                status, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(request.id)
+        .bind(request.user_id)
+        .bind(request.requested_by)
+        .bind(request.current_role.as_str())
+        .bind(request.requested_role.as_str())
+        .bind(&request.rationale)
+        .bind(request.status.as_str())
+        .bind(request.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find(&self, id: Uuid) -> AppResult<Option<RoleRequest>> {
+        let row = sqlx::query(&format!("SELECT {} FROM role_requests WHERE id = $1", ROLE_REQUEST_COLUMNS))
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.as_ref().map(map_request).transpose()
+    }
+
+    pub async fn pending_for_user(&self, user_id: Uuid) -> AppResult<Option<RoleRequest>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM role_requests WHERE user_id = $1 AND status = 'pending'",
+            ROLE_REQUEST_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(self.database.pool())
+        .await?;
+        row.as_ref().map(map_request).transpose()
+    }
+
+    /// Requests waiting for a decision, oldest first
+    pub async fn pending(&self, limit: i64) -> AppResult<Vec<RoleRequest>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM role_requests WHERE status = 'pending' ORDER BY created_at LIMIT $1",
+            ROLE_REQUEST_COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(self.database.pool())
+        .await?;
+        rows.iter().map(map_request).collect()
+    }
+
+    /// A user's requests, newest first
+    pub async fn for_user(&self, user_id: Uuid) -> AppResult<Vec<RoleRequest>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM role_requests WHERE user_id = $1 ORDER BY created_at DESC",
+            ROLE_REQUEST_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(self.database.pool())
+        .await?;
+        rows.iter().map(map_request).collect()
+    }
+
+    /// Record the decision on a pending request; None if it was decided meanwhile
+    pub async fn decide(
+        &self,
+        id: Uuid,
+        status: RoleRequestStatus,
+        decided_by: Uuid,
+        note: Option<&str>,
+    ) -> AppResult<Option<RoleRequest>> {
+        let row = sqlx::query(&format!(
+            "UPDATE role_requests SET status = $2, decided_by = $3, decision_note = $4, decided_at = $5 \
+             WHERE id = $1 AND status = 'pending' RETURNING {}",
+            ROLE_REQUEST_COLUMNS
+        ))
+        .bind(id)
+        .bind(status.as_str())
+        .bind(decided_by)
+        .bind(note)
+        .bind(Utc::now())
+        .fetch_optional(self.database.pool())
+        .await?;
+        row.as_ref().map(map_request).transpose()
+    }
+}
+
+fn map_request(row: &PgRow) -> AppResult<RoleRequest> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt role request row: {}", e));
+
+    Ok(RoleRequest {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        requested_by: row.try_get("requested_by")?,
+        current_role: row.try_get::<String, _>("current_role")?.parse().map_err(invalid)?,
+        requested_role: row.try_get::<String, _>("requested_role")?.parse().map_err(invalid)?,
+        rationale: row.try_get("rationale")?,
+        status: row.try_get::<String, _>("status")?.parse().map_err(invalid)?,
+        decided_by: row.try_get("decided_by")?,
+        decision_note: row.try_get("decision_note")?,
+        created_at: row.try_get("created_at")?,
+        decided_at: row.try_get("decided_at")?,
+    })
+}
@@ -0,0 +1,189 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppResult, Authenticator, TotpCredential};
+
+const AUTHENTICATOR_COLUMNS: &str = "id, user_id, name, credential_id, credential, created_at, last_used_at";
+
+/// Persistence boundary for WebAuthn authenticators and backup codes
+#[async_trait]
+pub trait SecondFactorRepository: Send + Sync {
+    async fn authenticators(&self, user_id: Uuid) -> AppResult<Vec<Authenticator>>;
+    async fn create_authenticator(&self, authenticator: &Authenticator) -> AppResult<()>;
+    /// Store the credential's new signature counter after a successful assertion
+    async fn update_credential(&self, id: Uuid, credential: &serde_json::Value) -> AppResult<()>;
+    /// False when the user has no such authenticator
+    async fn delete_authenticator(&self, user_id: Uuid, id: Uuid) -> AppResult<bool>;
+    /// Discard every backup code of the user and store the new set
+    async fn replace_backup_codes(&self, user_id: Uuid, code_hashes: &[String]) -> AppResult<()>;
+    /// Mark an unused code as used; false if it does not exist or was already used
+    async fn consume_backup_code(&self, user_id: Uuid, code_hash: &str) -> AppResult<bool>;
+    async fn remaining_backup_codes(&self, user_id: Uuid) -> AppResult<i64>;
+    async fn totp_credential(&self, user_id: Uuid) -> AppResult<Option<TotpCredential>>;
+    /// Store a new unconfirmed secret, replacing any earlier unconfirmed one;
+    /// false if the user already has a confirmed credential
+    async fn save_pending_totp(&self, user_id: Uuid, secret: &str) -> AppResult<bool>;
+    /// Confirm a pending credential and advance its last used step;
+    /// false if there is nothing pending
+    async fn confirm_totp(&self, user_id: Uuid, step: i64) -> AppResult<bool>;
+    /// Accept a code's time step on a confirmed credential; false if that
+    /// step or a later one was already used
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> AppResult<bool>;
+    /// False when the user had no credential
+    async fn delete_totp(&self, user_id: Uuid) -> AppResult<bool>;
+}
+
+pub struct PostgresSecondFactorRepository {
+    database: Arc<Database>,
+}
+
+impl PostgresSecondFactorRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl SecondFactorRepository for PostgresSecondFactorRepository {
+    async fn authenticators(&self, user_id: Uuid) -> AppResult<Vec<Authenticator>> {
+        let sql = format!(
+            "SELECT {} FROM authenticators WHERE user_id = $1 ORDER BY created_at",
+            AUTHENTICATOR_COLUMNS
+        );
+        let authenticators = sqlx::query_as::<_, Authenticator>(&sql)
+            .bind(user_id)
+            .fetch_all(self.database.pool())
+            .await?;
+        Ok(authenticators)
+    }
+
+    async fn create_authenticator(&self, authenticator: &Authenticator) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO authenticators (id, user_id, name, credential_id, credential, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(authenticator.id)
+        .bind(authenticator.user_id)
+        .bind(&authenticator.name)
+        .bind(&authenticator.credential_id)
+        .bind(&authenticator.credential)
+        .bind(authenticator.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    async fn update_credential(&self, id: Uuid, credential: &serde_json::Value) -> AppResult<()> {
+        sqlx::query("UPDATE authenticators SET credential = $2, last_used_at = NOW() WHERE id = $1")
+            .bind(id)
+            .bind(credential)
+            .execute(self.database.pool())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_authenticator(&self, user_id: Uuid, id: Uuid) -> AppResult<bool> {
+        let deleted = sqlx::query("DELETE FROM authenticators WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(deleted.rows_affected() == 1)
+    }
+
+    async fn replace_backup_codes(&self, user_id: Uuid, code_hashes: &[String]) -> AppResult<()> {
+        let mut tx = self.database.pool().begin().await?;
+        sqlx::query("DELETE FROM backup_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO backup_codes (user_id, code_hash) SELECT $1, UNNEST($2::text[])")
+            .bind(user_id)
+            .bind(code_hashes)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn consume_backup_code(&self, user_id: Uuid, code_hash: &str) -> AppResult<bool> {
+        let consumed = sqlx::query(
+            "UPDATE backup_codes SET used_at = NOW() \
+             WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(self.database.pool())
+        .await?;
+        Ok(consumed.rows_affected() == 1)
+    }
+
+    async fn remaining_backup_codes(&self, user_id: Uuid) -> AppResult<i64> {
+        let remaining = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM backup_codes WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_one(self.database.pool())
+        .await?;
+        Ok(remaining)
+    }
+
+    async fn totp_credential(&self, user_id: Uuid) -> AppResult<Option<TotpCredential>> {
+        let credential = sqlx::query_as::<_, TotpCredential>(
+            "SELECT user_id, secret, confirmed_at, last_used_step, created_at \
+             FROM totp_credentials WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(self.database.pool())
+        .await?;
+        Ok(credential)
+    }
+
+    async fn save_pending_totp(&self, user_id: Uuid, secret: &str) -> AppResult<bool> {
+        let saved = sqlx::query(
+            "INSERT INTO totp_credentials (user_id, secret) VALUES ($1, $2) \
+             ON CONFLICT (user_id) DO UPDATE SET secret = EXCLUDED.secret, created_at = NOW() \
+             WHERE totp_credentials.confirmed_at IS NULL",
+        )
+        .bind(user_id)
+        .bind(secret)
+        .execute(self.database.pool())
+        .await?;
+        Ok(saved.rows_affected() == 1)
+    }
+
+    async fn confirm_totp(&self, user_id: Uuid, step: i64) -> AppResult<bool> {
+        let confirmed = sqlx::query(
+            "UPDATE totp_credentials SET confirmed_at = NOW(), last_used_step = $2 \
+             WHERE user_id = $1 AND confirmed_at IS NULL",
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(self.database.pool())
+        .await?;
+        Ok(confirmed.rows_affected() == 1)
+    }
+
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> AppResult<bool> {
+        let used = sqlx::query(
+            "UPDATE totp_credentials SET last_used_step = $2 \
+             WHERE user_id = $1 AND confirmed_at IS NOT NULL \
+                AND (last_used_step IS NULL OR last_used_step < $2)",
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(self.database.pool())
+        .await?;
+        Ok(used.rows_affected() == 1)
+    }
+
+    async fn delete_totp(&self, user_id: Uuid) -> AppResult<bool> {
+        let deleted = sqlx::query("DELETE FROM totp_credentials WHERE user_id = $1")
+            .bind(user_id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(deleted.rows_affected() == 1)
+    }
+}
@@ -0,0 +1,110 @@
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, ServiceAccount, ServiceScope};
+
+const COLUMNS: &str = "id, tenant_id, name, scopes, secret_hash, created_by, created_at, last_used_at, disabled_at";
+
+/// Service accounts of every tenant
+pub struct ServiceAccountRepository {
+    database: Arc<Database>,
+}
+
+impl ServiceAccountRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn create(&self, account: &ServiceAccount) -> AppResult<()> {
+        let scopes: Vec<&str> = account.scopes.iter().map(ServiceScope::as_str).collect();
+        sqlx::query(
+            "INSERT INTO service_accounts (id, tenant_id, name, scopes, secret_hash, created_by, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(account.id)
+        .bind(&account.tenant_id)
+        .bind(&account.name)
+        .bind(&scopes)
+        .bind(&account.secret_hash)
+        .bind(account.created_by)
+        .bind(account.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Any tenant's account, for checking client credentials
+    pub async fn get(&self, id: Uuid) -> AppResult<Option<ServiceAccount>> {
+        let row = sqlx::query(&format!("SELECT {} FROM service_accounts WHERE id = $1", COLUMNS))
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.as_ref().map(map_account).transpose()
+    }
+
+    pub async fn find(&self, tenant_id: &str, id: Uuid) -> AppResult<Option<ServiceAccount>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM service_accounts WHERE tenant_id = $1 AND id = $2",
+            COLUMNS
+        ))
+        .bind(tenant_id)
+        .bind(id)
+        .fetch_optional(self.database.pool())
+        .await?;
+        row.as_ref().map(map_account).transpose()
+    }
+
+    /// Accounts of a tenant, newest first, including disabled ones
+    pub async fn list(&self, tenant_id: &str) -> AppResult<Vec<ServiceAccount>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM service_accounts WHERE tenant_id = $1 ORDER BY created_at DESC",
+            COLUMNS
+        ))
+        .bind(tenant_id)
+        .fetch_all(self.database.pool())
+        .await?;
+        rows.iter().map(map_account).collect()
+    }
+
+    pub async fn touch(&self, id: Uuid) -> AppResult<()> {
+        sqlx::query("UPDATE service_accounts SET last_used_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// False if the account was already disabled
+    pub async fn disable(&self, id: Uuid) -> AppResult<bool> {
+        let result =
+            sqlx::query("UPDATE service_accounts SET disabled_at = NOW() WHERE id = $1 AND disabled_at IS NULL")
+                .bind(id)
+                .execute(self.database.pool())
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn map_account(row: &PgRow) -> AppResult<ServiceAccount> {
+    let invalid = |e: String| AppError::Internal(format!("Corrupt service account row: {}", e));
+
+    Ok(ServiceAccount {
+        id: row.try_get("id")?,
+        tenant_id: row.try_get("tenant_id")?,
+        name: row.try_get("name")?,
+        scopes: row
+            .try_get::<Vec<String>, _>("scopes")?
+            .iter()
+            .map(|scope| scope.parse())
+            .collect::<Result<_, _>>()
+            .map_err(invalid)?,
+        secret_hash: row.try_get("secret_hash")?,
+        created_by: row.try_get("created_by")?,
+        created_at: row.try_get("created_at")?,
+        last_used_at: row.try_get("last_used_at")?,
+        disabled_at: row.try_get("disabled_at")?,
+    })
+}
@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppResult, Device, Session};
+
+const DEVICE_COLUMNS: &str =
+    "id, user_id, user_agent, browser, os, ip_address, created_at, last_active_at, revoked_at";
+const SESSION_COLUMNS: &str =
+    "id, user_id, device_id, created_at, last_active_at, expires_at, revoked_at";
+
+/// Persistence boundary for devices and the sessions opened on them
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    /// Insert the device, or refresh the matching unrevoked one for the same user agent
+    async fn upsert_device(&self, device: &Device) -> AppResult<Device>;
+    async fn create_session(&self, session: &Session) -> AppResult<Session>;
+    async fn find_session(&self, id: Uuid) -> AppResult<Option<Session>>;
+    /// Unrevoked devices with at least one unexpired session, most recent first
+    async fn active_devices(&self, user_id: Uuid) -> AppResult<Vec<Device>>;
+    /// Revoke a device and all its sessions; false when the user has no such device
+    async fn revoke_device(&self, user_id: Uuid, device_id: Uuid) -> AppResult<bool>;
+    /// Revoke every device of a user and all their sessions, returning how many devices were revoked
+    async fn revoke_all_devices(&self, user_id: Uuid) -> AppResult<u64>;
+    async fn revoke_session(&self, id: Uuid) -> AppResult<()>;
+    async fn touch_session(&self, id: Uuid, ip_address: Option<&str>) -> AppResult<()>;
+}
+
+pub struct PostgresSessionRepository {
+    database: Arc<Database>,
+}
+
+impl PostgresSessionRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl SessionRepository for PostgresSessionRepository {
+    async fn upsert_device(&self, device: &Device) -> AppResult<Device> {
+        let sql = format!(
+            "INSERT INTO devices (id, user_id, user_agent, browser, os, ip_address, created_at, last_active_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             ON CONFLICT (user_id, user_agent) WHERE revoked_at IS NULL DO UPDATE SET \
+                ip_address = EXCLUDED.ip_address, last_active_at = EXCLUDED.last_active_at \
+             RETURNING {}",
+            DEVICE_COLUMNS
+        );
+        let device = sqlx::query_as::<_, Device>(&sql)
+            .bind(device.id)
+            .bind(device.user_id)
+            .bind(&device.user_agent)
+            .bind(&device.browser)
+            .bind(&device.os)
+            .bind(&device.ip_address)
+            .bind(device.created_at)
+            .bind(device.last_active_at)
+            .fetch_one(self.database.pool())
+            .await?;
+        Ok(device)
+    }
+
+    async fn create_session(&self, session: &Session) -> AppResult<Session> {
+        let sql = format!(
+            "INSERT INTO sessions (id, user_id, device_id, created_at, last_active_at, expires_at) \
+             VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+            SESSION_COLUMNS
+        );
+        let session = sqlx::query_as::<_, Session>(&sql)
+            .bind(session.id)
+            .bind(session.user_id)
+            .bind(session.device_id)
+            .bind(session.created_at)
+            .bind(session.last_active_at)
+            .bind(session.expires_at)
+            .fetch_one(self.database.pool())
+            .await?;
+        Ok(session)
+    }
+
+    async fn find_session(&self, id: Uuid) -> AppResult<Option<Session>> {
+        let sql = format!("SELECT {} FROM sessions WHERE id = $1", SESSION_COLUMNS);
+        let session = sqlx::query_as::<_, Session>(&sql)
+            .bind(id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        Ok(session)
+    }
+
+    async fn active_devices(&self, user_id: Uuid) -> AppResult<Vec<Device>> {
+        let sql = format!(
+            "SELECT {} FROM devices d WHERE d.user_id = $1 AND d.revoked_at IS NULL \
+             AND EXISTS (SELECT 1 FROM sessions s WHERE s.device_id = d.id \
+                         AND s.revoked_at IS NULL AND s.expires_at > NOW()) \
+             ORDER BY d.last_active_at DESC",
+            DEVICE_COLUMNS
+        );
+        let devices = sqlx::query_as::<_, Device>(&sql)
+            .bind(user_id)
+            .fetch_all(self.database.pool())
+            .await?;
+        Ok(devices)
+    }
+
+    async fn revoke_device(&self, user_id: Uuid, device_id: Uuid) -> AppResult<bool> {
+        let mut tx = self.database.pool().begin().await?;
+        let revoked = sqlx::query(
+            "UPDATE devices SET revoked_at = NOW() \
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(device_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if revoked.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE device_id = $1 AND revoked_at IS NULL")
+            .bind(device_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn revoke_all_devices(&self, user_id: Uuid) -> AppResult<u64> {
+        let mut tx = self.database.pool().begin().await?;
+        let revoked = sqlx::query("UPDATE devices SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(revoked.rows_affected())
+    }
+
+    async fn revoke_session(&self, id: Uuid) -> AppResult<()> {
+        sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+            .bind(id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(())
+    }
+
+    async fn touch_session(&self, id: Uuid, ip_address: Option<&str>) -> AppResult<()> {
+        sqlx::query(
+            "WITH touched AS ( \
+                UPDATE sessions SET last_active_at = NOW() \
+                WHERE id = $1 AND revoked_at IS NULL RETURNING device_id) \
+             UPDATE devices SET last_active_at = NOW(), ip_address = COALESCE($2, ip_address) \
+             WHERE id IN (SELECT device_id FROM touched)",
+        )
+        .bind(id)
+        .bind(ip_address)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppResult, Suppression};
+
+/// Per-tenant list of users and addresses notifications are never sent to
+pub struct SuppressionRepository {
+    database: Arc<Database>,
+}
+
+impl SuppressionRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn create(&self, suppression: &Suppression) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO notification_suppressions (id, tenant_id, user_id, email, reason, created_by, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(suppression.id)
+        .bind(&suppression.tenant_id)
+        .bind(suppression.user_id)
+        .bind(&suppression.email)
+        .bind(&suppression.reason)
+        .bind(suppression.created_by)
+        .bind(suppression.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// False if there was no such entry
+    pub async fn delete(&self, tenant_id: &str, id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM notification_suppressions WHERE tenant_id = $1 AND id = $2")
+            .bind(tenant_id)
+            .bind(id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn list(&self, tenant_id: &str) -> AppResult<Vec<Suppression>> {
+        let rows = sqlx::query(
+            "SELECT id, tenant_id, user_id, email, reason, created_by, created_at \
+             FROM notification_suppressions WHERE tenant_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(tenant_id)
+        .fetch_all(self.database.pool())
+        .await?;
+        rows.iter().map(map_suppression).collect()
+    }
+
+    /// Whether the user or the address they would be contacted at is suppressed
+    pub async fn is_suppressed(&self, tenant_id: &str, user_id: Uuid, recipient: &str) -> AppResult<bool> {
+        let suppressed = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM notification_suppressions \
+             WHERE tenant_id = $1 AND (user_id = $2 OR email = LOWER($3)))",
+        )
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(recipient.trim())
+        .fetch_one(self.database.pool())
+        .await?;
+        Ok(suppressed)
+    }
+}
+
+fn map_suppression(row: &PgRow) -> AppResult<Suppression> {
+    Ok(Suppression {
+        id: row.try_get("id")?,
+        tenant_id: row.try_get("tenant_id")?,
+        user_id: row.try_get("user_id")?,
+        email: row.try_get("email")?,
+        reason: row.try_get("reason")?,
+        created_by: row.try_get("created_by")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
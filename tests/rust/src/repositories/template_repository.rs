@@ -0,0 +1,176 @@
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult, NotificationTemplate, TemplateRevision};
+
+const TEMPLATE_COLUMNS: &str = "key, notification_type, email_subject, email_body, email_html, \
+    in_app_title, in_app_body, version, updated_by, updated_at";
+const REVISION_COLUMNS: &str = "template_key AS key, notification_type, email_subject, email_body, \
+    email_html, in_app_title, in_app_body, version, author_id AS updated_by, created_at AS updated_at, \
+    rolled_back_from";
+
+/// Persistence boundary for notification templates and their revision history
+#[async_trait]
+pub trait TemplateRepository: Send + Sync {
+    async fn find(&self, key: &str) -> AppResult<Option<NotificationTemplate>>;
+    async fn list(&self) -> AppResult<Vec<NotificationTemplate>>;
+    /// Store new content as the next revision and make it current
+    async fn save_revision(
+        &self,
+        template: &NotificationTemplate,
+        author_id: Option<Uuid>,
+        rolled_back_from: Option<i32>,
+    ) -> AppResult<NotificationTemplate>;
+    /// All revisions of a template, newest first
+    async fn revisions(&self, key: &str) -> AppResult<Vec<TemplateRevision>>;
+    async fn revision(&self, key: &str, version: i32) -> AppResult<Option<TemplateRevision>>;
+}
+
+pub struct PostgresTemplateRepository {
+    database: Arc<Database>,
+}
+
+impl PostgresTemplateRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl TemplateRepository for PostgresTemplateRepository {
+    async fn find(&self, key: &str) -> AppResult<Option<NotificationTemplate>> {
+        let sql = format!("SELECT {} FROM notification_templates WHERE key = $1", TEMPLATE_COLUMNS);
+        let row = sqlx::query(&sql)
+            .bind(key)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.map(|row| map_template(&row)).transpose()
+    }
+
+    async fn list(&self) -> AppResult<Vec<NotificationTemplate>> {
+        let sql = format!("SELECT {} FROM notification_templates ORDER BY key", TEMPLATE_COLUMNS);
+        let rows = sqlx::query(&sql).fetch_all(self.database.pool()).await?;
+        rows.iter().map(map_template).collect()
+    }
+
+    async fn save_revision(
+        &self,
+        template: &NotificationTemplate,
+        author_id: Option<Uuid>,
+        rolled_back_from: Option<i32>,
+    ) -> AppResult<NotificationTemplate> {
+        let mut tx = self.database.pool().begin().await?;
+
+        // Lock the current row so concurrent edits get consecutive versions
+        let current: Option<i32> =
+            sqlx::query_scalar("SELECT version FROM notification_templates WHERE key = $1 FOR UPDATE")
+                .bind(&template.key)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let version = current.map(|v| v + 1).unwrap_or(1);
+
+        let sql = format!(
+            "INSERT INTO notification_templates \
+                (key, notification_type, email_subject, email_body, email_html, in_app_title, \
+                 in_app_body, version, updated_by, updated_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, NOW()) \
+             ON CONFLICT (key) DO UPDATE SET \
+                notification_type = EXCLUDED.notification_type, email_subject = EXCLUDED.email_subject, \
+                email_body = EXCLUDED.email_body, email_html = EXCLUDED.email_html, \
+                in_app_title = EXCLUDED.in_app_title, in_app_body = EXCLUDED.in_app_body, \
+                version = EXCLUDED.version, updated_by = EXCLUDED.updated_by, updated_at = NOW() \
+             RETURNING {}",
+            TEMPLATE_COLUMNS
+        );
+        let row = sqlx::query(&sql)
+            .bind(&template.key)
+            .bind(template.notification_type.as_str())
+            .bind(&template.email_subject)
+            .bind(&template.email_body)
+            .bind(&template.email_html)
+            .bind(&template.in_app_title)
+            .bind(&template.in_app_body)
+            .bind(version)
+            .bind(author_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let saved = map_template(&row)?;
+
+        sqlx::query(
+            "INSERT INTO notification_template_revisions \
+                (template_key, version, notification_type, email_subject, email_body, email_html, \
+                 in_app_title, in_app_body, author_id, rolled_back_from, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+        )
+        .bind(&saved.key)
+        .bind(saved.version)
+        .bind(saved.notification_type.as_str())
+        .bind(&saved.email_subject)
+        .bind(&saved.email_body)
+        .bind(&saved.email_html)
+        .bind(&saved.in_app_title)
+        .bind(&saved.in_app_body)
+        .bind(author_id)
+        .bind(rolled_back_from)
+        .bind(saved.updated_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(saved)
+    }
+
+    async fn revisions(&self, key: &str) -> AppResult<Vec<TemplateRevision>> {
+        let sql = format!(
+            "SELECT {} FROM notification_template_revisions WHERE template_key = $1 ORDER BY version DESC",
+            REVISION_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(key)
+            .fetch_all(self.database.pool())
+            .await?;
+        rows.iter().map(map_revision).collect()
+    }
+
+    async fn revision(&self, key: &str, version: i32) -> AppResult<Option<TemplateRevision>> {
+        let sql = format!(
+            "SELECT {} FROM notification_template_revisions WHERE template_key = $1 AND version = $2",
+            REVISION_COLUMNS
+        );
+        let row = sqlx::query(&sql)
+            .bind(key)
+            .bind(version)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.map(|row| map_revision(&row)).transpose()
+    }
+}
+
+fn map_template(row: &PgRow) -> AppResult<NotificationTemplate> {
+    Ok(NotificationTemplate {
+        key: row.try_get("key")?,
+        notification_type: row
+            .try_get::<String, _>("notification_type")?
+            .parse()
+            .map_err(|e: String| AppError::Internal(format!("Corrupt template row: {}", e)))?,
+        email_subject: row.try_get("email_subject")?,
+        email_body: row.try_get("email_body")?,
+        email_html: row.try_get("email_html")?,
+        in_app_title: row.try_get("in_app_title")?,
+        in_app_body: row.try_get("in_app_body")?,
+        version: row.try_get("version")?,
+        updated_by: row.try_get("updated_by")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+fn map_revision(row: &PgRow) -> AppResult<TemplateRevision> {
+    Ok(TemplateRevision {
+        template: map_template(row)?,
+        rolled_back_from: row.try_get("rolled_back_from")?,
+    })
+}
@@ -0,0 +1,78 @@
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+
+use crate::database::Database;
+use crate::models::{AppResult, TenantBranding};
+
+const BRANDING_COLUMNS: &str = "tenant_id, sender_address, sender_name, logo_url, primary_color, \
+    accent_color, footer, updated_at";
+
+/// Persistence boundary for tenant branding overrides
+#[async_trait]
+pub trait TenantBrandingRepository: Send + Sync {
+    async fn find(&self, tenant_id: &str) -> AppResult<Option<TenantBranding>>;
+    /// Replace the tenant's overrides
+    async fn upsert(&self, branding: &TenantBranding) -> AppResult<TenantBranding>;
+}
+
+pub struct PostgresTenantBrandingRepository {
+    database: Arc<Database>,
+}
+
+impl PostgresTenantBrandingRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl TenantBrandingRepository for PostgresTenantBrandingRepository {
+    async fn find(&self, tenant_id: &str) -> AppResult<Option<TenantBranding>> {
+        let sql = format!("SELECT {} FROM tenant_branding WHERE tenant_id = $1", BRANDING_COLUMNS);
+        let row = sqlx::query(&sql)
+            .bind(tenant_id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.map(|row| map_row(&row)).transpose()
+    }
+
+    async fn upsert(&self, branding: &TenantBranding) -> AppResult<TenantBranding> {
+        let sql = format!(
+            "INSERT INTO tenant_branding \
+                (tenant_id, sender_address, sender_name, logo_url, primary_color, accent_color, footer, updated_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, NOW()) \
+             ON CONFLICT (tenant_id) DO UPDATE SET \
+                sender_address = EXCLUDED.sender_address, sender_name = EXCLUDED.sender_name, \
+                logo_url = EXCLUDED.logo_url, primary_color = EXCLUDED.primary_color, \
+                accent_color = EXCLUDED.accent_color, footer = EXCLUDED.footer, updated_at = NOW() \
+             RETURNING {}",
+            BRANDING_COLUMNS
+        );
+        let row = sqlx::query(&sql)
+            .bind(&branding.tenant_id)
+            .bind(&branding.sender_address)
+            .bind(&branding.sender_name)
+            .bind(&branding.logo_url)
+            .bind(&branding.primary_color)
+            .bind(&branding.accent_color)
+            .bind(&branding.footer)
+            .fetch_one(self.database.pool())
+            .await?;
+        map_row(&row)
+    }
+}
+
+fn map_row(row: &PgRow) -> AppResult<TenantBranding> {
+    Ok(TenantBranding {
+        tenant_id: row.try_get("tenant_id")?,
+        sender_address: row.try_get("sender_address")?,
+        sender_name: row.try_get("sender_name")?,
+        logo_url: row.try_get("logo_url")?,
+        primary_color: row.try_get("primary_color")?,
+        accent_color: row.try_get("accent_color")?,
+        footer: row.try_get("footer")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
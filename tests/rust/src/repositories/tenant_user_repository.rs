@@ -0,0 +1,64 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::AppResult;
+
+/// Which tenant each account was onboarded into
+pub struct TenantUserRepository {
+    database: Arc<Database>,
+}
+
+impl TenantUserRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn count(&self, tenant_id: &str) -> AppResult<i64> {
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM tenant_users WHERE tenant_id = $1")
+            .bind(tenant_id)
+            .fetch_one(self.database.pool())
+            .await?;
+        Ok(count)
+    }
+
+    /// Tenant the user was onboarded into; None for accounts no tenant has recorded
+    pub async fn tenant_of(&self, user_id: Uuid) -> AppResult<Option<String>> {
+        let tenant = sqlx::query_scalar("SELECT tenant_id FROM tenant_users WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        Ok(tenant)
+    }
+
+    /// Add the user to the tenant unless it already has `max_users`; false when it is full.
+    ///
+    /// Additions to one tenant are serialized by an advisory lock, so
+    /// concurrent sign-ups cannot take it past its limit.
+    pub async fn add(&self, tenant_id: &str, user_id: Uuid, max_users: Option<u64>) -> AppResult<bool> {
+        let mut tx = self.database.pool().begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('tenant_users:' || $1))")
+            .bind(tenant_id)
+            .execute(&mut *tx)
+            .await?;
+        if let Some(max_users) = max_users {
+            let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tenant_users WHERE tenant_id = $1")
+                .bind(tenant_id)
+                .fetch_one(&mut *tx)
+                .await?;
+            if count as u64 >= max_users {
+                return Ok(false);
+            }
+        }
+        sqlx::query(
+            "INSERT INTO tenant_users (user_id, tenant_id) VALUES ($1, $2) \
+             ON CONFLICT (user_id) DO UPDATE SET tenant_id = EXCLUDED.tenant_id",
+        )
+        .bind(user_id)
+        .bind(tenant_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
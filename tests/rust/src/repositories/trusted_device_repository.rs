@@ -0,0 +1,114 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppResult, TrustedDevice};
+
+const TRUSTED_DEVICE_COLUMNS: &str =
+    "id, user_id, name, browser, os, ip_address, created_at, last_used_at, expires_at, revoked_at";
+
+/// Devices whose sign-ins skip the second factor
+pub struct TrustedDeviceRepository {
+    database: Arc<Database>,
+}
+
+impl TrustedDeviceRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    /// Unrevoked, unexpired devices, most recently used first
+    pub async fn active_for_user(&self, user_id: Uuid) -> AppResult<Vec<TrustedDevice>> {
+        let sql = format!(
+            "SELECT {} FROM trusted_devices \
+             WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW() \
+             ORDER BY COALESCE(last_used_at, created_at) DESC",
+            TRUSTED_DEVICE_COLUMNS
+        );
+        let devices = sqlx::query_as::<_, TrustedDevice>(&sql)
+            .bind(user_id)
+            .fetch_all(self.database.pool())
+            .await?;
+        Ok(devices)
+    }
+
+    pub async fn find_active(&self, user_id: Uuid, id: Uuid) -> AppResult<Option<TrustedDevice>> {
+        let sql = format!(
+            "SELECT {} FROM trusted_devices \
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()",
+            TRUSTED_DEVICE_COLUMNS
+        );
+        let device = sqlx::query_as::<_, TrustedDevice>(&sql)
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(self.database.pool())
+            .await?;
+        Ok(device)
+    }
+
+    pub async fn create(&self, device: &TrustedDevice) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO trusted_devices (id, user_id, name, browser, os, ip_address, created_at, expires_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(device.id)
+        .bind(device.user_id)
+        .bind(&device.name)
+        .bind(&device.browser)
+        .bind(&device.os)
+        .bind(&device.ip_address)
+        .bind(device.created_at)
+        .bind(device.expires_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record_use(&self, id: Uuid) -> AppResult<()> {
+        sqlx::query("UPDATE trusted_devices SET last_used_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// None when the user has no such active device
+    pub async fn rename(&self, user_id: Uuid, id: Uuid, name: &str) -> AppResult<Option<TrustedDevice>> {
+        let sql = format!(
+            "UPDATE trusted_devices SET name = $3 \
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW() \
+             RETURNING {}",
+            TRUSTED_DEVICE_COLUMNS
+        );
+        let device = sqlx::query_as::<_, TrustedDevice>(&sql)
+            .bind(id)
+            .bind(user_id)
+            .bind(name)
+            .fetch_optional(self.database.pool())
+            .await?;
+        Ok(device)
+    }
+
+    /// False when the user has no such unrevoked device
+    pub async fn revoke(&self, user_id: Uuid, id: Uuid) -> AppResult<bool> {
+        let revoked = sqlx::query(
+            "UPDATE trusted_devices SET revoked_at = NOW() \
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(self.database.pool())
+        .await?;
+        Ok(revoked.rows_affected() == 1)
+    }
+
+    /// Revoke every device of a user, returning how many were
+    pub async fn revoke_all(&self, user_id: Uuid) -> AppResult<u64> {
+        let revoked =
+            sqlx::query("UPDATE trusted_devices SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+                .bind(user_id)
+                .execute(self.database.pool())
+                .await?;
+        Ok(revoked.rows_affected())
+    }
+}
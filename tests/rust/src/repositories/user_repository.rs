@@ -0,0 +1,619 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{PgConnection, Postgres, QueryBuilder, Row};
+use std::sync::Arc;
+use tracing::Span;
+use uuid::Uuid;
+
+use super::outbox_repository::OutboxRepository;
+use crate::database::Database;
+use crate::models::{
+    AppError, AppResult, NewOutboxMessage, SortDirection, TenantContext, User, UserCursor, UserEvent, UserFilters,
+    UserPage, UserSort, UserSortField,
+};
+use crate::utils::{EncryptedField, KeyRing, RequestContext, TenantScope};
+
+pub(crate) const DEFAULT_PAGE_SIZE: i64 = 50;
+
+const USER_COLUMNS: &str = "id, email, username, first_name, last_name, role, status, \
+    email_verified, last_login, login_count, failed_login_attempts, locked_until, password_hash, \
+    metadata, preferences, created_at, updated_at, deleted_at, version";
+
+/// Persistence boundary for user accounts
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn create(&self, user: &User) -> AppResult<User>;
+    async fn find_by_id(&self, id: Uuid) -> AppResult<Option<User>>;
+    async fn find_by_ids(&self, ids: &[Uuid]) -> AppResult<Vec<User>>;
+    async fn find_by_email(&self, email: &str) -> AppResult<Option<User>>;
+    async fn find_by_username(&self, username: &str) -> AppResult<Option<User>>;
+    /// Whether any account holds the email, in whichever tenant; emails are unique across tenants
+    async fn email_taken(&self, email: &str) -> AppResult<bool>;
+    /// Whether any account holds the username, in whichever tenant
+    async fn username_taken(&self, username: &str) -> AppResult<bool>;
+    async fn update(&self, user: &User) -> AppResult<User>;
+    /// `update`, but only while the stored version is still `expected_version`;
+    /// `AppError::Conflict` when another write got there first
+    async fn update_versioned(&self, user: &User, expected_version: i64) -> AppResult<User>;
+    /// Mark the email verified in one statement, but only while it is still `email`;
+    /// None when the user is gone or has changed email since
+    async fn mark_email_verified(&self, id: Uuid, email: &str) -> AppResult<Option<User>>;
+    async fn delete(&self, id: Uuid) -> AppResult<()>;
+    /// A page of `filters.limit` users in the order of `filters.sort`, newest
+    /// first when it is empty, with ties broken by id
+    async fn list(&self, filters: &UserFilters) -> AppResult<Vec<User>>;
+    /// The page after `cursor`, or the first page without one, newest first
+    /// with ties broken by id; `filters.offset` is ignored, and any other
+    /// `filters.sort` is refused. Seeking to the cursor keeps deep pages as
+    /// cheap as the first.
+    async fn list_page(&self, filters: &UserFilters, cursor: Option<&UserCursor>) -> AppResult<UserPage>;
+    async fn count(&self, filters: &UserFilters) -> AppResult<i64>;
+    /// Users whose lockout has run out but not yet been cleared, longest expired first
+    async fn find_expired_lockouts(&self, limit: i64) -> AppResult<Vec<User>>;
+    /// Soft-deleted users deleted before `cutoff`, longest deleted first
+    async fn find_deleted_before(&self, cutoff: DateTime<Utc>, limit: i64) -> AppResult<Vec<User>>;
+}
+
+/// PostgreSQL implementation; names are stored encrypted and every write
+/// records its domain event in the outbox within the same transaction.
+///
+/// Every read is confined to the tenant of the request being served, see
+/// `push_tenant_scope`, except the `*_taken` uniqueness checks.
+///
+/// Rows with a `region` are anchors for accounts stored in another region's
+/// database (see `RegionalUserRepository`) and are never read as users.
+pub struct PostgresUserRepository {
+    database: Arc<Database>,
+    key_ring: Arc<KeyRing>,
+    outbox: Arc<OutboxRepository>,
+}
+
+impl PostgresUserRepository {
+    pub fn new(database: Arc<Database>, key_ring: Arc<KeyRing>, outbox: Arc<OutboxRepository>) -> Self {
+        Self {
+            database,
+            key_ring,
+            outbox,
+        }
+    }
+
+    /// Lookup by a unique column; only the column name is traced, never the value
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(
+            db.system = "postgresql",
+            db.operation = %format!("users.find_by_{}", column),
+            db.rows = tracing::field::Empty
+        )
+    )]
+    async fn fetch_one_by(&self, column: &str, value: &str) -> AppResult<Option<User>> {
+        let sql = format!("SELECT {} FROM users WHERE {} = ", USER_COLUMNS, column);
+        let mut query = QueryBuilder::<Postgres>::new(sql);
+        query.push_bind(value).push(" AND region IS NULL");
+        push_tenant_scope(&mut query, None);
+        let row = RequestContext::bounded("users.find", async {
+            Ok(query.build().fetch_optional(self.database.pool()).await?)
+        })
+        .await?;
+        record_rows(row.is_some() as u64);
+        row.map(|row| self.map_row(&row)).transpose()
+    }
+
+    /// Insert or update a user; an update matches no row when the user is
+    /// missing or, with `expected_version`, has moved past that version
+    async fn write(
+        &self,
+        conn: &mut PgConnection,
+        user: &User,
+        insert: bool,
+        expected_version: Option<i64>,
+    ) -> AppResult<Option<PgRow>> {
+        let first_name = self.key_ring.encrypt(&user.first_name)?.encode();
+        let last_name = self.key_ring.encrypt(&user.last_name)?.encode();
+        let metadata = serde_json::to_value(&user.metadata)
+            .map_err(|e| AppError::Internal(format!("Unserializable user metadata: {}", e)))?;
+        let preferences = serde_json::to_value(&user.preferences)
+            .map_err(|e| AppError::Internal(format!("Unserializable user preferences: {}", e)))?;
+
+        let sql = if insert {
+            format!(
+                "INSERT INTO users (id, email, username, first_name, last_name, role, status, \
+                    email_verified, last_login, login_count, failed_login_attempts, password_hash, \
+                    metadata, preferences, created_at, updated_at, deleted_at, pii_key_version, locked_until) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19) \
+                 RETURNING {}",
+                USER_COLUMNS
+            )
+        } else {
+            format!(
+                "UPDATE users SET email = $2, username = $3, first_name = $4, last_name = $5, \
+                    role = $6, status = $7, email_verified = $8, last_login = $9, login_count = $10, \
+                    failed_login_attempts = $11, password_hash = $12, metadata = $13, \
+                    preferences = $14, created_at = $15, updated_at = $16, deleted_at = $17, \
+                    pii_key_version = $18, locked_until = $19, version = version + 1 \
+                 WHERE id = $1 {} RETURNING {}",
+                if expected_version.is_some() { "AND version = $20" } else { "" },
+                USER_COLUMNS
+            )
+        };
+
+        let mut query = sqlx::query(&sql)
+            .bind(user.id)
+            .bind(&user.email)
+            .bind(&user.username)
+            .bind(first_name)
+            .bind(last_name)
+            .bind(user.role.as_str())
+            .bind(user.status.as_str())
+            .bind(user.email_verified)
+            .bind(user.last_login)
+            .bind(user.login_count)
+            .bind(user.failed_login_attempts)
+            .bind(&user.password_hash)
+            .bind(metadata)
+            .bind(preferences)
+            .bind(user.created_at)
+            .bind(user.updated_at)
+            .bind(user.deleted_at)
+            .bind(self.key_ring.active_version() as i32)
+            .bind(user.locked_until);
+        if let Some(expected_version) = expected_version {
+            query = query.bind(expected_version);
+        }
+        Ok(query.fetch_optional(&mut *conn).await?)
+    }
+
+    /// Whether a unique column holds `value` in any tenant
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(db.system = "postgresql", db.operation = %format!("users.{}_taken", column))
+    )]
+    async fn taken(&self, column: &str, value: &str) -> AppResult<bool> {
+        let sql = format!("SELECT EXISTS (SELECT 1 FROM users WHERE {} = $1 AND region IS NULL)", column);
+        RequestContext::bounded("users.taken", async {
+            Ok(sqlx::query_scalar(&sql)
+                .bind(value)
+                .fetch_one(self.database.pool())
+                .await?)
+        })
+        .await
+    }
+
+    fn map_row(&self, row: &PgRow) -> AppResult<User> {
+        let invalid = |e: String| AppError::Internal(format!("Corrupt user row: {}", e));
+
+        Ok(User {
+            id: row.try_get("id")?,
+            email: row.try_get("email")?,
+            username: row.try_get("username")?,
+            first_name: self.decrypt(&row.try_get::<String, _>("first_name")?)?,
+            last_name: self.decrypt(&row.try_get::<String, _>("last_name")?)?,
+            role: row.try_get::<String, _>("role")?.parse().map_err(invalid)?,
+            status: row.try_get::<String, _>("status")?.parse().map_err(invalid)?,
+            email_verified: row.try_get("email_verified")?,
+            last_login: row.try_get("last_login")?,
+            login_count: row.try_get("login_count")?,
+            failed_login_attempts: row.try_get("failed_login_attempts")?,
+            locked_until: row.try_get("locked_until")?,
+            password_hash: row.try_get("password_hash")?,
+            metadata: serde_json::from_value(row.try_get("metadata")?)
+                .map_err(|e| invalid(e.to_string()))?,
+            preferences: serde_json::from_value(row.try_get("preferences")?)
+                .map_err(|e| invalid(e.to_string()))?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+            deleted_at: row.try_get("deleted_at")?,
+            version: row.try_get("version")?,
+            region: None,
+        })
+    }
+
+    fn decrypt(&self, stored: &str) -> AppResult<String> {
+        if stored.is_empty() {
+            return Ok(String::new());
+        }
+        self.key_ring.decrypt(&EncryptedField::parse(stored)?)
+    }
+}
+
+#[async_trait]
+impl UserRepository for PostgresUserRepository {
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(db.system = "postgresql", db.operation = "users.create", db.rows = tracing::field::Empty)
+    )]
+    async fn create(&self, user: &User) -> AppResult<User> {
+        // Writes are not abandoned midway; they are only refused once the deadline has passed
+        RequestContext::check("users.create")?;
+        let mut tx = self.database.pool().begin().await?;
+        let row = self.write(&mut tx, user, true, None).await?.ok_or_else(|| not_found(user.id))?;
+        let created = self.map_row(&row)?;
+        self.outbox
+            .enqueue(&mut tx, &NewOutboxMessage::from_user_event(&UserEvent::Created { user: created.clone() })?)
+            .await?;
+        tx.commit().await?;
+        record_rows(1);
+        Ok(created)
+    }
+
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(db.system = "postgresql", db.operation = "users.find_by_id", db.rows = tracing::field::Empty)
+    )]
+    async fn find_by_id(&self, id: Uuid) -> AppResult<Option<User>> {
+        let mut query = QueryBuilder::<Postgres>::new(format!("SELECT {} FROM users WHERE id = ", USER_COLUMNS));
+        query.push_bind(id).push(" AND region IS NULL");
+        push_tenant_scope(&mut query, None);
+        let row = RequestContext::bounded("users.find", async {
+            Ok(query.build().fetch_optional(self.database.pool()).await?)
+        })
+        .await?;
+        record_rows(row.is_some() as u64);
+        row.map(|row| self.map_row(&row)).transpose()
+    }
+
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(db.system = "postgresql", db.operation = "users.find_by_ids", db.rows = tracing::field::Empty)
+    )]
+    async fn find_by_ids(&self, ids: &[Uuid]) -> AppResult<Vec<User>> {
+        let mut query = QueryBuilder::<Postgres>::new(format!("SELECT {} FROM users WHERE id = ANY(", USER_COLUMNS));
+        query.push_bind(ids).push(") AND region IS NULL");
+        push_tenant_scope(&mut query, None);
+        query.push(" ORDER BY id");
+        let rows = RequestContext::bounded("users.find_by_ids", async {
+            Ok(query.build().fetch_all(self.database.pool()).await?)
+        })
+        .await?;
+        record_rows(rows.len() as u64);
+        rows.iter().map(|row| self.map_row(row)).collect()
+    }
+
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(
+            db.system = "postgresql",
+            db.operation = "users.find_expired_lockouts",
+            db.rows = tracing::field::Empty
+        )
+    )]
+    async fn find_expired_lockouts(&self, limit: i64) -> AppResult<Vec<User>> {
+        let sql = format!(
+            "SELECT {} FROM users WHERE locked_until <= NOW() ORDER BY locked_until LIMIT $1",
+            USER_COLUMNS
+        );
+        let rows = sqlx::query(&sql).bind(limit).fetch_all(self.database.pool()).await?;
+        record_rows(rows.len() as u64);
+        rows.iter().map(|row| self.map_row(row)).collect()
+    }
+
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(
+            db.system = "postgresql",
+            db.operation = "users.find_deleted_before",
+            db.rows = tracing::field::Empty
+        )
+    )]
+    async fn find_deleted_before(&self, cutoff: DateTime<Utc>, limit: i64) -> AppResult<Vec<User>> {
+        let sql = format!(
+            "SELECT {} FROM users WHERE deleted_at < $1 AND region IS NULL ORDER BY deleted_at, id LIMIT $2",
+            USER_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(cutoff)
+            .bind(limit)
+            .fetch_all(self.database.pool())
+            .await?;
+        record_rows(rows.len() as u64);
+        rows.iter().map(|row| self.map_row(row)).collect()
+    }
+
+    async fn find_by_email(&self, email: &str) -> AppResult<Option<User>> {
+        self.fetch_one_by("email", email).await
+    }
+
+    async fn find_by_username(&self, username: &str) -> AppResult<Option<User>> {
+        self.fetch_one_by("username", username).await
+    }
+
+    async fn email_taken(&self, email: &str) -> AppResult<bool> {
+        self.taken("email", email).await
+    }
+
+    async fn username_taken(&self, username: &str) -> AppResult<bool> {
+        self.taken("username", username).await
+    }
+
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(db.system = "postgresql", db.operation = "users.update", db.rows = tracing::field::Empty)
+    )]
+    async fn update(&self, user: &User) -> AppResult<User> {
+        RequestContext::check("users.update")?;
+        let mut tx = self.database.pool().begin().await?;
+        let row = self.write(&mut tx, user, false, None).await?.ok_or_else(|| not_found(user.id))?;
+        let updated = self.map_row(&row)?;
+        self.outbox
+            .enqueue(&mut tx, &NewOutboxMessage::from_user_event(&UserEvent::Updated { user: updated.clone() })?)
+            .await?;
+        tx.commit().await?;
+        record_rows(1);
+        Ok(updated)
+    }
+
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(db.system = "postgresql", db.operation = "users.update_versioned", db.rows = tracing::field::Empty)
+    )]
+    async fn update_versioned(&self, user: &User, expected_version: i64) -> AppResult<User> {
+        RequestContext::check("users.update")?;
+        let mut tx = self.database.pool().begin().await?;
+        let Some(row) = self.write(&mut tx, user, false, Some(expected_version)).await? else {
+            let current: Option<i64> = sqlx::query_scalar("SELECT version FROM users WHERE id = $1")
+                .bind(user.id)
+                .fetch_optional(&mut *tx)
+                .await?;
+            return Err(match current {
+                Some(current) => AppError::Conflict(format!(
+                    "User {} is at version {}, not {}",
+                    user.id, current, expected_version
+                )),
+                None => not_found(user.id),
+            });
+        };
+        let updated = self.map_row(&row)?;
+        self.outbox
+            .enqueue(&mut tx, &NewOutboxMessage::from_user_event(&UserEvent::Updated { user: updated.clone() })?)
+            .await?;
+        tx.commit().await?;
+        record_rows(1);
+        Ok(updated)
+    }
+
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(db.system = "postgresql", db.operation = "users.mark_email_verified", db.rows = tracing::field::Empty)
+    )]
+    async fn mark_email_verified(&self, id: Uuid, email: &str) -> AppResult<Option<User>> {
+        RequestContext::check("users.update")?;
+        let mut tx = self.database.pool().begin().await?;
+        let sql = format!(
+            "UPDATE users SET email_verified = TRUE, updated_at = NOW(), version = version + 1 \
+             WHERE id = $1 AND email = $2 AND deleted_at IS NULL RETURNING {}",
+            USER_COLUMNS
+        );
+        let Some(row) = sqlx::query(&sql).bind(id).bind(email).fetch_optional(&mut *tx).await? else {
+            record_rows(0);
+            return Ok(None);
+        };
+        let updated = self.map_row(&row)?;
+        self.outbox
+            .enqueue(&mut tx, &NewOutboxMessage::from_user_event(&UserEvent::Updated { user: updated.clone() })?)
+            .await?;
+        tx.commit().await?;
+        record_rows(1);
+        Ok(Some(updated))
+    }
+
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(db.system = "postgresql", db.operation = "users.delete", db.rows = tracing::field::Empty)
+    )]
+    async fn delete(&self, id: Uuid) -> AppResult<()> {
+        RequestContext::check("users.delete")?;
+        let mut tx = self.database.pool().begin().await?;
+        let deleted = sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        record_rows(deleted.rows_affected());
+        if deleted.rows_affected() == 0 {
+            return Err(AppError::NotFound(format!("User {} not found", id)));
+        }
+        let event = UserEvent::Deleted {
+            user_id: id,
+            at: chrono::Utc::now(),
+        };
+        self.outbox
+            .enqueue(&mut tx, &NewOutboxMessage::from_user_event(&event)?)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(db.system = "postgresql", db.operation = "users.list", db.rows = tracing::field::Empty)
+    )]
+    async fn list(&self, filters: &UserFilters) -> AppResult<Vec<User>> {
+        let mut query =
+            QueryBuilder::<Postgres>::new(format!("SELECT {} FROM users WHERE region IS NULL", USER_COLUMNS));
+        push_filters(&mut query, filters);
+
+        push_order(&mut query, &filters.sort);
+        query
+            .push(" LIMIT ")
+            .push_bind(filters.limit.unwrap_or(DEFAULT_PAGE_SIZE));
+        query.push(" OFFSET ").push_bind(filters.offset.unwrap_or(0));
+
+        let rows = RequestContext::bounded("users.list", async {
+            Ok(query.build().fetch_all(self.database.pool()).await?)
+        })
+        .await?;
+        record_rows(rows.len() as u64);
+        rows.iter().map(|row| self.map_row(row)).collect()
+    }
+
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(db.system = "postgresql", db.operation = "users.list_page", db.rows = tracing::field::Empty)
+    )]
+    async fn list_page(&self, filters: &UserFilters, cursor: Option<&UserCursor>) -> AppResult<UserPage> {
+        check_cursor_order(filters)?;
+        let limit = filters.limit.unwrap_or(DEFAULT_PAGE_SIZE).max(0);
+        let mut query =
+            QueryBuilder::<Postgres>::new(format!("SELECT {} FROM users WHERE region IS NULL", USER_COLUMNS));
+        push_filters(&mut query, filters);
+        if let Some(cursor) = cursor {
+            query
+                .push(" AND (created_at < ")
+                .push_bind(cursor.created_at)
+                .push(" OR (created_at = ")
+                .push_bind(cursor.created_at)
+                .push(" AND id > ")
+                .push_bind(cursor.id)
+                .push("))");
+        }
+
+        // One more than the page tells whether another page follows
+        query.push(" ORDER BY created_at DESC, id");
+        query.push(" LIMIT ").push_bind(limit + 1);
+
+        let rows = RequestContext::bounded("users.list_page", async {
+            Ok(query.build().fetch_all(self.database.pool()).await?)
+        })
+        .await?;
+        record_rows(rows.len() as u64);
+        let mut users = rows.iter().map(|row| self.map_row(row)).collect::<AppResult<Vec<User>>>()?;
+        Ok(page(&mut users, limit as usize))
+    }
+
+    #[tracing::instrument(
+        name = "db.query",
+        skip_all,
+        fields(db.system = "postgresql", db.operation = "users.count", db.rows = tracing::field::Empty)
+    )]
+    async fn count(&self, filters: &UserFilters) -> AppResult<i64> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM users WHERE region IS NULL");
+        push_filters(&mut query, filters);
+        RequestContext::bounded("users.count", async {
+            Ok(query
+                .build_query_scalar::<i64>()
+                .fetch_one(self.database.pool())
+                .await?)
+        })
+        .await
+    }
+}
+
+/// Cut `users`, fetched one past the page, to `limit` and point the cursor at the last one kept
+pub(crate) fn page(users: &mut Vec<User>, limit: usize) -> UserPage {
+    let more = users.len() > limit;
+    users.truncate(limit);
+    UserPage {
+        next_cursor: users.last().filter(|_| more).map(UserCursor::after),
+        users: std::mem::take(users),
+    }
+}
+
+/// Cursors only know a position in the newest-first order
+pub(crate) fn check_cursor_order(filters: &UserFilters) -> AppResult<()> {
+    if filters.sort.iter().any(|key| *key != UserSort::NEWEST_FIRST) {
+        return Err(AppError::Validation(vec![
+            "Cursor pages are listed newest first and cannot be sorted otherwise".to_string(),
+        ]));
+    }
+    Ok(())
+}
+
+fn not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("User {} not found", id))
+}
+
+/// Attach the number of rows a statement returned or touched to its span
+fn record_rows(rows: u64) {
+    Span::current().record("db.rows", rows);
+}
+
+/// Confine a query to the tenant of the request being served, so no caller
+/// can forget to, and to `tenant_id` as well when given.
+///
+/// Users without a `tenant_users` row belong to the default tenant, as they
+/// do when joining organizations: only onboarding records one.
+fn push_tenant_scope(query: &mut QueryBuilder<'_, Postgres>, tenant_id: Option<&str>) {
+    let scope = TenantScope::current().map(|tenant| tenant.tenant_id);
+    let mut tenants: Vec<String> = scope.into_iter().chain(tenant_id.map(str::to_string)).collect();
+    tenants.dedup();
+    for tenant in tenants {
+        query
+            .push(" AND COALESCE((SELECT tenant_id FROM tenant_users WHERE tenant_users.user_id = users.id), ")
+            .push_bind(TenantContext::default().tenant_id)
+            .push(") = ")
+            .push_bind(tenant);
+    }
+}
+
+/// Names are encrypted at rest, so free-text search only covers email and username.
+///
+/// Listings are confined to the tenant like every other read.
+fn push_filters(query: &mut QueryBuilder<'_, Postgres>, filters: &UserFilters) {
+    push_tenant_scope(query, filters.tenant_id.as_deref());
+    if let Some(role) = &filters.role {
+        query.push(" AND role = ").push_bind(role.as_str());
+    }
+    if let Some(status) = &filters.status {
+        query.push(" AND status = ").push_bind(status.as_str());
+    }
+    if let Some(verified) = filters.email_verified {
+        query.push(" AND email_verified = ").push_bind(verified);
+    }
+    if let Some(after) = filters.created_after {
+        query.push(" AND created_at >= ").push_bind(after);
+    }
+    if let Some(before) = filters.created_before {
+        query.push(" AND created_at < ").push_bind(before);
+    }
+    if let Some(after) = filters.last_login_after {
+        query.push(" AND last_login >= ").push_bind(after);
+    }
+    if let Some(term) = filters.search_term.as_deref().filter(|t| !t.is_empty()) {
+        let pattern = format!("%{}%", term.replace('%', "\\%").replace('_', "\\_"));
+        query
+            .push(" AND (email ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR username ILIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+}
+
+/// Columns and directions come from fixed lists, never from the request.
+/// Text sorts bytewise under the `C` collation, so listings merged across
+/// regions order the same as each region does.
+fn push_order(query: &mut QueryBuilder<'_, Postgres>, sort: &[UserSort]) {
+    let keys = if sort.is_empty() { &[UserSort::NEWEST_FIRST][..] } else { sort };
+    query.push(" ORDER BY ");
+    for key in keys {
+        let column = match key.field {
+            UserSortField::CreatedAt => "created_at",
+            UserSortField::LastLogin => "last_login",
+            UserSortField::Username => "username COLLATE \"C\"",
+            UserSortField::Email => "email COLLATE \"C\"",
+        };
+        let direction = match key.direction {
+            SortDirection::Asc => " ASC",
+            SortDirection::Desc => " DESC",
+        };
+        query.push(column).push(direction);
+        if key.field == UserSortField::LastLogin {
+            query.push(" NULLS LAST");
+        }
+        query.push(", ");
+    }
+    query.push("id");
+}
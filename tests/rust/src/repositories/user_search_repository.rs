@@ -0,0 +1,241 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Postgres, QueryBuilder, Row};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppResult, User, UserFilters};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const PROJECTION_NAME: &str = "user_search";
+
+/// Denormalized, query-optimized view of a user
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UserSearchEntry {
+    pub user_id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub full_name: String,
+    pub role: String,
+    pub status: String,
+    pub email_verified: bool,
+    pub last_login: Option<DateTime<Utc>>,
+    pub login_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&User> for UserSearchEntry {
+    fn from(user: &User) -> Self {
+        Self {
+            user_id: user.id,
+            email: user.email.clone(),
+            username: user.username.clone(),
+            full_name: user.full_name(),
+            role: user.role.as_str().to_string(),
+            status: user.status.as_str().to_string(),
+            email_verified: user.email_verified,
+            last_login: user.last_login,
+            login_count: user.login_count,
+            created_at: user.created_at,
+        }
+    }
+}
+
+/// Where an unfinished projection rebuild left off
+#[derive(Debug, Clone, Copy)]
+pub struct RebuildCheckpoint {
+    /// Last user id projected; `None` if the rebuild had not projected anyone yet
+    pub resume_after: Option<Uuid>,
+}
+
+/// Read-side repository over the `user_search` projection table
+pub struct UserSearchRepository {
+    database: Arc<Database>,
+}
+
+impl UserSearchRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    /// Insert or replace the projection row for a user
+    pub async fn upsert(&self, entry: &UserSearchEntry) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO user_search \
+                (user_id, email, username, full_name, role, status, email_verified, \
+                 last_login, login_count, created_at, projected_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW()) \
+             ON CONFLICT (user_id) DO UPDATE SET \
+                email = EXCLUDED.email, username = EXCLUDED.username, \
+                full_name = EXCLUDED.full_name, role = EXCLUDED.role, \
+                status = EXCLUDED.status, email_verified = EXCLUDED.email_verified, \
+                last_login = EXCLUDED.last_login, login_count = EXCLUDED.login_count, \
+                projected_at = NOW()",
+        )
+        .bind(entry.user_id)
+        .bind(&entry.email)
+        .bind(&entry.username)
+        .bind(&entry.full_name)
+        .bind(&entry.role)
+        .bind(&entry.status)
+        .bind(entry.email_verified)
+        .bind(entry.last_login)
+        .bind(entry.login_count)
+        .bind(entry.created_at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Bump login statistics without needing the full user
+    pub async fn record_login(&self, user_id: Uuid, at: DateTime<Utc>) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE user_search SET last_login = $2, login_count = login_count + 1, \
+             projected_at = NOW() WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(at)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Remove a user from the read model
+    pub async fn remove(&self, user_id: Uuid) -> AppResult<()> {
+        sqlx::query("DELETE FROM user_search WHERE user_id = $1")
+            .bind(user_id)
+            .execute(self.database.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// Whether the projection has never been populated
+    /// Checkpoint of a rebuild that was started but never finished
+    pub async fn rebuild_checkpoint(&self) -> AppResult<Option<RebuildCheckpoint>> {
+        let row = sqlx::query("SELECT resume_after FROM projection_checkpoints WHERE projection = $1")
+            .bind(PROJECTION_NAME)
+            .fetch_optional(self.database.pool())
+            .await?;
+        row.map(|row| {
+            Ok(RebuildCheckpoint {
+                resume_after: row.try_get("resume_after")?,
+            })
+        })
+        .transpose()
+    }
+
+    /// Record rebuild progress; the checkpoint exists until `finish_rebuild`
+    pub async fn save_rebuild_checkpoint(&self, resume_after: Option<Uuid>) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO projection_checkpoints (projection, resume_after, updated_at) \
+             VALUES ($1, $2, NOW()) \
+             ON CONFLICT (projection) DO UPDATE SET resume_after = EXCLUDED.resume_after, updated_at = NOW()",
+        )
+        .bind(PROJECTION_NAME)
+        .bind(resume_after)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn finish_rebuild(&self) -> AppResult<()> {
+        sqlx::query("DELETE FROM projection_checkpoints WHERE projection = $1")
+            .bind(PROJECTION_NAME)
+            .execute(self.database.pool())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn is_empty(&self) -> AppResult<bool> {
+        let populated: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM user_search)")
+            .fetch_one(self.database.pool())
+            .await?;
+        Ok(!populated)
+    }
+
+    /// Search the read model using the same filters as the transactional repository
+    pub async fn search(&self, filters: &UserFilters) -> AppResult<Vec<UserSearchEntry>> {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT user_id, email, username, full_name, role, status, email_verified, \
+             last_login, login_count, created_at FROM user_search WHERE TRUE",
+        );
+        push_filters(&mut query, filters);
+
+        query.push(" ORDER BY created_at DESC, user_id");
+        query
+            .push(" LIMIT ")
+            .push_bind(filters.limit.unwrap_or(DEFAULT_PAGE_SIZE));
+        query.push(" OFFSET ").push_bind(filters.offset.unwrap_or(0));
+
+        let entries = query
+            .build_query_as::<UserSearchEntry>()
+            .fetch_all(self.database.pool())
+            .await?;
+        Ok(entries)
+    }
+
+    /// Total matches and role/status breakdown for a filter set
+    pub async fn facets(&self, filters: &UserFilters) -> AppResult<SearchFacets> {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT role, status, COUNT(*) AS matches FROM user_search WHERE TRUE",
+        );
+        push_filters(&mut query, filters);
+        query.push(" GROUP BY role, status");
+
+        let mut facets = SearchFacets::default();
+        for row in query.build().fetch_all(self.database.pool()).await? {
+            let role: String = row.try_get("role")?;
+            let status: String = row.try_get("status")?;
+            let matches = row.try_get::<i64, _>("matches")? as u64;
+
+            facets.total += matches;
+            *facets.roles.entry(role).or_insert(0) += matches;
+            *facets.statuses.entry(status).or_insert(0) += matches;
+        }
+        Ok(facets)
+    }
+}
+
+/// Match counts grouped by role and status
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchFacets {
+    pub total: u64,
+    pub roles: HashMap<String, u64>,
+    pub statuses: HashMap<String, u64>,
+}
+
+fn push_filters(query: &mut QueryBuilder<'_, Postgres>, filters: &UserFilters) {
+    if let Some(role) = &filters.role {
+        query.push(" AND role = ").push_bind(role.as_str());
+    }
+    if let Some(status) = &filters.status {
+        query.push(" AND status = ").push_bind(status.as_str());
+    }
+    if let Some(verified) = filters.email_verified {
+        query.push(" AND email_verified = ").push_bind(verified);
+    }
+    if let Some(after) = filters.created_after {
+        query.push(" AND created_at > ").push_bind(after);
+    }
+    if let Some(before) = filters.created_before {
+        query.push(" AND created_at < ").push_bind(before);
+    }
+    if let Some(after) = filters.last_login_after {
+        query.push(" AND last_login > ").push_bind(after);
+    }
+    if let Some(term) = filters.search_term.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        query
+            .push(" AND (search_document @@ plainto_tsquery('simple', ")
+            .push_bind(term.to_string())
+            .push(") OR username ILIKE ")
+            .push_bind(format!("{}%", escape_like(term)))
+            .push(")");
+    }
+}
+
+/// Escape LIKE wildcards so user input only matches literally
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
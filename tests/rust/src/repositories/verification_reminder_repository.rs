@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::AppResult;
+
+/// How many verification reminders each unverified user has been sent
+pub struct VerificationReminderRepository {
+    database: Arc<Database>,
+}
+
+impl VerificationReminderRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    /// Reminders sent to each of the users; users never reminded are left out
+    pub async fn sent(&self, user_ids: &[Uuid]) -> AppResult<HashMap<Uuid, usize>> {
+        let rows: Vec<(Uuid, i32)> =
+            sqlx::query_as("SELECT user_id, reminders_sent FROM verification_reminders WHERE user_id = ANY($1)")
+                .bind(user_ids)
+                .fetch_all(self.database.pool())
+                .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(user_id, sent)| (user_id, sent.max(0) as usize))
+            .collect())
+    }
+
+    /// Claim sending the user's `reminders_sent`th reminder. False when it,
+    /// or a later one, was already sent, by this instance or another.
+    pub async fn claim(&self, user_id: Uuid, reminders_sent: usize) -> AppResult<bool> {
+        let claimed: Option<Uuid> = sqlx::query_scalar(
+            "INSERT INTO verification_reminders (user_id, reminders_sent, last_sent_at) VALUES ($1, $2, NOW()) \
+             ON CONFLICT (user_id) DO UPDATE \
+             SET reminders_sent = EXCLUDED.reminders_sent, last_sent_at = NOW() \
+             WHERE verification_reminders.reminders_sent < EXCLUDED.reminders_sent \
+             RETURNING user_id",
+        )
+        .bind(user_id)
+        .bind(reminders_sent as i32)
+        .fetch_optional(self.database.pool())
+        .await?;
+        Ok(claimed.is_some())
+    }
+}
@@ -0,0 +1,313 @@
+pub mod user_onboarding;
+
+pub use user_onboarding::{OnboardingMembership, UserOnboardingSaga};
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::Row;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::models::{AppError, AppResult};
+use crate::utils::{new_id, Logger, Metrics};
+
+/// A single compensatable unit of work within a saga
+#[async_trait]
+pub trait SagaStep: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Perform the step, recording anything later steps need in the context
+    async fn execute(&self, context: &mut SagaContext) -> AppResult<()>;
+
+    /// Undo the step after a later step failed; defaults to nothing to undo
+    async fn compensate(&self, _context: &SagaContext) -> AppResult<()> {
+        Ok(())
+    }
+}
+
+/// Named, ordered list of steps the coordinator can start or resume
+pub struct SagaDefinition {
+    pub name: &'static str,
+    pub steps: Vec<Arc<dyn SagaStep>>,
+}
+
+/// Serializable state passed between steps and persisted after each one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SagaContext {
+    values: Map<String, Value>,
+}
+
+impl SagaContext {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn insert<T: Serialize>(&mut self, key: &str, value: &T) -> AppResult<()> {
+        let value = serde_json::to_value(value)
+            .map_err(|e| AppError::Internal(format!("Unserializable saga value {}: {}", key, e)))?;
+        self.values.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> AppResult<T> {
+        let value = self
+            .values
+            .get(key)
+            .cloned()
+            .ok_or_else(|| AppError::Internal(format!("Saga context is missing {}", key)))?;
+        serde_json::from_value(value)
+            .map_err(|e| AppError::Internal(format!("Invalid saga value {}: {}", key, e)))
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.values.remove(key);
+    }
+}
+
+/// Lifecycle of a saga instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SagaStatus {
+    Running,
+    Completed,
+    Compensating,
+    Compensated,
+    Failed,
+}
+
+impl SagaStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SagaStatus::Running => "running",
+            SagaStatus::Completed => "completed",
+            SagaStatus::Compensating => "compensating",
+            SagaStatus::Compensated => "compensated",
+            SagaStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> AppResult<Self> {
+        match value {
+            "running" => Ok(SagaStatus::Running),
+            "completed" => Ok(SagaStatus::Completed),
+            "compensating" => Ok(SagaStatus::Compensating),
+            "compensated" => Ok(SagaStatus::Compensated),
+            "failed" => Ok(SagaStatus::Failed),
+            other => Err(AppError::Internal(format!("Unknown saga status {}", other))),
+        }
+    }
+}
+
+/// Persisted progress of one saga instance.
+///
+/// While running, `completed_steps` counts steps that succeeded; while
+/// compensating, it counts steps that still need to be undone.
+#[derive(Debug, Clone)]
+pub struct SagaRecord {
+    pub id: Uuid,
+    pub name: String,
+    pub status: SagaStatus,
+    pub completed_steps: usize,
+    pub context: SagaContext,
+    pub error: Option<String>,
+}
+
+impl SagaRecord {
+    /// Convert a finished record into its context, or the error that stopped it
+    pub fn into_result(self) -> AppResult<SagaContext> {
+        match self.status {
+            SagaStatus::Completed => Ok(self.context),
+            _ => Err(AppError::Internal(format!(
+                "Saga {} ({}) ended as {}: {}",
+                self.name,
+                self.id,
+                self.status.as_str(),
+                self.error.unwrap_or_default()
+            ))),
+        }
+    }
+}
+
+/// Drives registered sagas step by step, persisting progress after each step
+pub struct SagaCoordinator {
+    database: Arc<Database>,
+    definitions: HashMap<&'static str, Arc<SagaDefinition>>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl SagaCoordinator {
+    pub fn new(database: Arc<Database>, metrics: Arc<Metrics>, logger: Arc<Logger>) -> Self {
+        Self {
+            database,
+            definitions: HashMap::new(),
+            metrics,
+            logger,
+        }
+    }
+
+    /// Make a saga available to `start` and to resumption after restarts
+    pub fn register(&mut self, definition: SagaDefinition) {
+        self.definitions.insert(definition.name, Arc::new(definition));
+    }
+
+    /// Start a new saga instance and drive it to completion or compensation
+    pub async fn start(&self, name: &str, context: SagaContext) -> AppResult<SagaRecord> {
+        let definition = self.definition(name)?;
+        let record = SagaRecord {
+            id: new_id(),
+            name: definition.name.to_string(),
+            status: SagaStatus::Running,
+            completed_steps: 0,
+            context,
+            error: None,
+        };
+
+        sqlx::query(
+            "INSERT INTO sagas (id, name, status, completed_steps, context) \
+             VALUES ($1, $2, $3, 0, $4)",
+        )
+        .bind(record.id)
+        .bind(&record.name)
+        .bind(record.status.as_str())
+        .bind(sqlx::types::Json(&record.context))
+        .execute(self.database.pool())
+        .await?;
+
+        self.drive(&definition, record).await
+    }
+
+    /// Continue every saga left running or compensating by a previous process
+    pub async fn resume_pending(&self) -> AppResult<usize> {
+        let rows = sqlx::query(
+            "SELECT id, name, status, completed_steps, context, error FROM sagas \
+             WHERE status IN ('running', 'compensating') ORDER BY created_at",
+        )
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let mut resumed = 0;
+        for row in rows {
+            let record = SagaRecord {
+                id: row.try_get("id")?,
+                name: row.try_get("name")?,
+                status: SagaStatus::parse(&row.try_get::<String, _>("status")?)?,
+                completed_steps: row.try_get::<i32, _>("completed_steps")? as usize,
+                context: row.try_get::<sqlx::types::Json<SagaContext>, _>("context")?.0,
+                error: row.try_get("error")?,
+            };
+
+            let definition = match self.definition(&record.name) {
+                Ok(definition) => definition,
+                Err(e) => {
+                    self.logger.warn(&format!("Cannot resume saga {}: {}", record.id, e));
+                    continue;
+                }
+            };
+
+            self.logger.info(&format!(
+                "Resuming saga {} ({}) at step {}",
+                record.name, record.id, record.completed_steps
+            ));
+            self.drive(&definition, record).await?;
+            resumed += 1;
+        }
+
+        Ok(resumed)
+    }
+
+    async fn drive(&self, definition: &SagaDefinition, mut record: SagaRecord) -> AppResult<SagaRecord> {
+        while record.status == SagaStatus::Running {
+            let Some(step) = definition.steps.get(record.completed_steps) else {
+                record.status = SagaStatus::Completed;
+                break;
+            };
+
+            match step.execute(&mut record.context).await {
+                Ok(()) => record.completed_steps += 1,
+                Err(e) => {
+                    self.logger.warn(&format!(
+                        "Saga {} ({}) failed at {}: {}",
+                        record.name,
+                        record.id,
+                        step.name(),
+                        e
+                    ));
+                    record.status = SagaStatus::Compensating;
+                    record.error = Some(format!("{}: {}", step.name(), e));
+                }
+            }
+            self.persist(&record).await?;
+        }
+
+        while record.status == SagaStatus::Compensating {
+            if record.completed_steps == 0 {
+                record.status = SagaStatus::Compensated;
+                break;
+            }
+
+            let step = &definition.steps[record.completed_steps - 1];
+            match step.compensate(&record.context).await {
+                Ok(()) => record.completed_steps -= 1,
+                Err(e) => {
+                    self.logger.error(&format!(
+                        "Saga {} ({}) could not compensate {}: {}",
+                        record.name,
+                        record.id,
+                        step.name(),
+                        e
+                    ));
+                    record.status = SagaStatus::Failed;
+                    record.error = Some(format!(
+                        "{}; compensation of {} failed: {}",
+                        record.error.take().unwrap_or_default(),
+                        step.name(),
+                        e
+                    ));
+                }
+            }
+            self.persist(&record).await?;
+        }
+
+        self.persist(&record).await?;
+        let _ = self
+            .metrics
+            .increment_labeled_counter(
+                "saga.finished",
+                &[("saga", record.name.as_str()), ("status", record.status.as_str())],
+            )
+            .await;
+
+        Ok(record)
+    }
+
+    async fn persist(&self, record: &SagaRecord) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE sagas SET status = $2, completed_steps = $3, context = $4, error = $5, \
+             updated_at = NOW() WHERE id = $1",
+        )
+        .bind(record.id)
+        .bind(record.status.as_str())
+        .bind(record.completed_steps as i32)
+        .bind(sqlx::types::Json(&record.context))
+        .bind(&record.error)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+
+    fn definition(&self, name: &str) -> AppResult<Arc<SagaDefinition>> {
+        self.definitions
+            .get(name)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("Saga definition {}", name)))
+    }
+}
@@ -0,0 +1,269 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::{SagaContext, SagaDefinition, SagaStep};
+use crate::config::ResidencyConfig;
+use crate::models::{AppError, AppResult, CreateUserRequest, OrgRole, OrganizationMember, TenantContext, User};
+use crate::repositories::OrganizationRepository;
+use crate::services::{NotificationService, SearchService, TenantLimitService, UserService};
+
+const REQUEST_KEY: &str = "request";
+const USER_ID_KEY: &str = "user_id";
+/// Where sagas started before contexts stopped holding the whole user keep it
+const LEGACY_USER_KEY: &str = "user";
+const TENANT_KEY: &str = "tenant";
+const MEMBERSHIP_KEY: &str = "membership";
+
+/// The organization an onboarded user joins, and as what
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnboardingMembership {
+    pub organization_id: Uuid,
+    pub role: OrgRole,
+}
+
+/// The id field of a user stored by an older saga
+#[derive(Deserialize)]
+struct StoredUser {
+    id: Uuid,
+}
+
+/// Onboarding flow for a new user: create the account, welcome them, ask them to
+/// confirm their email, index them and add them to their organization.
+///
+/// Contexts are stored for as long as the saga record is, so they hold the
+/// user's id and each step reads the user afresh. Steps are resumed by
+/// position, so new ones only ever go at the end.
+pub struct UserOnboardingSaga;
+
+impl UserOnboardingSaga {
+    pub const NAME: &'static str = "user_onboarding";
+
+    pub fn definition(
+        user_service: Arc<UserService>,
+        notification_service: Arc<NotificationService>,
+        search_service: Arc<SearchService>,
+        residency: ResidencyConfig,
+        tenant_limits: Arc<TenantLimitService>,
+        organizations: Arc<OrganizationRepository>,
+    ) -> SagaDefinition {
+        SagaDefinition {
+            name: Self::NAME,
+            steps: vec![
+                Arc::new(CreateUserStep {
+                    user_service: user_service.clone(),
+                    residency,
+                    tenant_limits,
+                }),
+                Arc::new(SendWelcomeStep {
+                    user_service: user_service.clone(),
+                    notification_service,
+                }),
+                Arc::new(SendVerificationStep {
+                    user_service: user_service.clone(),
+                }),
+                Arc::new(IndexUserStep {
+                    user_service,
+                    search_service,
+                }),
+                Arc::new(JoinOrganizationStep { organizations }),
+            ],
+        }
+    }
+
+    /// Initial context for onboarding the user described by `request` into `tenant`.
+    ///
+    /// Contexts are stored, so a password cannot be carried through; onboarded
+    /// users choose theirs through a password reset.
+    pub fn context(request: &CreateUserRequest, tenant: &TenantContext) -> AppResult<SagaContext> {
+        if request.password.is_some() {
+            return Err(AppError::Validation(vec![
+                "Onboarding does not take a password; the user sets one by resetting it".to_string(),
+            ]));
+        }
+        let mut context = SagaContext::new();
+        context.insert(REQUEST_KEY, request)?;
+        context.insert(TENANT_KEY, tenant)?;
+        Ok(context)
+    }
+
+    /// Also add the onboarded user to an organization of the tenant
+    pub fn with_membership(context: &mut SagaContext, membership: &OnboardingMembership) -> AppResult<()> {
+        context.insert(MEMBERSHIP_KEY, membership)
+    }
+
+    /// Id of the user created by a completed onboarding saga
+    pub fn user_id(context: &SagaContext) -> AppResult<Uuid> {
+        if context.contains(USER_ID_KEY) {
+            return context.get(USER_ID_KEY);
+        }
+        Ok(context.get::<StoredUser>(LEGACY_USER_KEY)?.id)
+    }
+
+    fn created(context: &SagaContext) -> bool {
+        context.contains(USER_ID_KEY) || context.contains(LEGACY_USER_KEY)
+    }
+}
+
+/// Sagas started before tenants existed have no tenant in their context
+fn tenant(context: &SagaContext) -> AppResult<TenantContext> {
+    if context.contains(TENANT_KEY) {
+        context.get(TENANT_KEY)
+    } else {
+        Ok(TenantContext::default())
+    }
+}
+
+async fn created_user(user_service: &UserService, context: &SagaContext) -> AppResult<User> {
+    let user_id = UserOnboardingSaga::user_id(context)?;
+    user_service
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("User {} not found", user_id)))
+}
+
+struct CreateUserStep {
+    user_service: Arc<UserService>,
+    residency: ResidencyConfig,
+    tenant_limits: Arc<TenantLimitService>,
+}
+
+#[async_trait]
+impl SagaStep for CreateUserStep {
+    fn name(&self) -> &'static str {
+        "create_user"
+    }
+
+    async fn execute(&self, context: &mut SagaContext) -> AppResult<()> {
+        if UserOnboardingSaga::created(context) {
+            return Ok(());
+        }
+        let mut request: CreateUserRequest = context.get(REQUEST_KEY)?;
+        let tenant = tenant(context)?;
+        if request.region.is_none() {
+            request.region = self.residency.tenant_region(&tenant).map(str::to_string);
+        }
+        self.tenant_limits.check_user_capacity(&tenant).await?;
+        let user = self.user_service.create_user(request).await?;
+        // The early check can race another sign-up; this one cannot
+        if let Err(e) = self.tenant_limits.add_user(&tenant, user.id).await {
+            self.user_service.delete_user(user.id).await?;
+            return Err(e);
+        }
+        // The request names the user as well; once they exist it is not needed
+        context.remove(REQUEST_KEY);
+        context.insert(USER_ID_KEY, &user.id)
+    }
+
+    async fn compensate(&self, context: &SagaContext) -> AppResult<()> {
+        if !UserOnboardingSaga::created(context) {
+            return Ok(());
+        }
+        self.user_service.delete_user(UserOnboardingSaga::user_id(context)?).await
+    }
+}
+
+struct SendWelcomeStep {
+    user_service: Arc<UserService>,
+    notification_service: Arc<NotificationService>,
+}
+
+#[async_trait]
+impl SagaStep for SendWelcomeStep {
+    fn name(&self) -> &'static str {
+        "send_welcome"
+    }
+
+    // A sent email cannot be recalled, so this step has no compensation
+    async fn execute(&self, context: &mut SagaContext) -> AppResult<()> {
+        let user = created_user(&self.user_service, context).await?;
+        let tenant = tenant(context)?;
+        self.notification_service
+            .send_welcome_notification(&tenant, user.id, &user.email)
+            .await
+    }
+}
+
+struct SendVerificationStep {
+    user_service: Arc<UserService>,
+}
+
+#[async_trait]
+impl SagaStep for SendVerificationStep {
+    fn name(&self) -> &'static str {
+        "send_email_verification"
+    }
+
+    // Like the welcome email, the link cannot be recalled; it expires on its own
+    async fn execute(&self, context: &mut SagaContext) -> AppResult<()> {
+        let user_id = UserOnboardingSaga::user_id(context)?;
+        self.user_service.send_email_verification(&tenant(context)?, user_id).await
+    }
+}
+
+struct IndexUserStep {
+    user_service: Arc<UserService>,
+    search_service: Arc<SearchService>,
+}
+
+#[async_trait]
+impl SagaStep for IndexUserStep {
+    fn name(&self) -> &'static str {
+        "index_in_search"
+    }
+
+    async fn execute(&self, context: &mut SagaContext) -> AppResult<()> {
+        let user = created_user(&self.user_service, context).await?;
+        self.search_service.index_user(&user).await
+    }
+
+    async fn compensate(&self, context: &SagaContext) -> AppResult<()> {
+        self.search_service.remove_user(UserOnboardingSaga::user_id(context)?).await
+    }
+}
+
+struct JoinOrganizationStep {
+    organizations: Arc<OrganizationRepository>,
+}
+
+#[async_trait]
+impl SagaStep for JoinOrganizationStep {
+    fn name(&self) -> &'static str {
+        "join_organization"
+    }
+
+    /// Nothing to do for users not onboarded into an organization
+    async fn execute(&self, context: &mut SagaContext) -> AppResult<()> {
+        if !context.contains(MEMBERSHIP_KEY) {
+            return Ok(());
+        }
+        let membership: OnboardingMembership = context.get(MEMBERSHIP_KEY)?;
+        let tenant = tenant(context)?;
+        self.organizations
+            .find(&tenant.tenant_id, membership.organization_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Organization {} not found", membership.organization_id)))?;
+        self.organizations
+            .set_member(&OrganizationMember {
+                organization_id: membership.organization_id,
+                user_id: UserOnboardingSaga::user_id(context)?,
+                role: membership.role,
+                added_by: None,
+                added_at: chrono::Utc::now(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn compensate(&self, context: &SagaContext) -> AppResult<()> {
+        if !context.contains(MEMBERSHIP_KEY) {
+            return Ok(());
+        }
+        let membership: OnboardingMembership = context.get(MEMBERSHIP_KEY)?;
+        self.organizations
+            .remove_member(membership.organization_id, UserOnboardingSaga::user_id(context)?)
+            .await?;
+        Ok(())
+    }
+}
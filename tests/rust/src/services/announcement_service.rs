@@ -0,0 +1,120 @@
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::read_only::ReadOnlyMode;
+use crate::models::{AppError, AppResult, Announcement, CreateAnnouncementRequest, TenantContext, User};
+use crate::repositories::{AnnouncementRepository, GroupRepository};
+
+/// Admin-published banners and notices, shown in-app to their audience.
+///
+/// Announcements are not fanned out as notifications: clients fetch the
+/// live ones for the signed-in user and render them as banners, so an
+/// announcement addressed to every user costs one row rather than one per
+/// recipient, and ending it takes it down everywhere at once.
+pub struct AnnouncementService {
+    announcements: Arc<dyn AnnouncementRepository>,
+    groups: Arc<dyn GroupRepository>,
+    read_only: Arc<ReadOnlyMode>,
+}
+
+impl AnnouncementService {
+    pub fn new(
+        announcements: Arc<dyn AnnouncementRepository>,
+        groups: Arc<dyn GroupRepository>,
+        read_only: Arc<ReadOnlyMode>,
+    ) -> Self {
+        Self {
+            announcements,
+            groups,
+            read_only,
+        }
+    }
+
+    pub async fn publish(
+        &self,
+        tenant: &TenantContext,
+        request: CreateAnnouncementRequest,
+        published_by: &User,
+    ) -> AppResult<Announcement> {
+        self.read_only.check()?;
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+        if let Some(group_id) = request.audience.group_id {
+            if self.groups.find_by_id(group_id).await?.is_none() {
+                return Err(AppError::Validation(vec![format!("Group {} does not exist", group_id)]));
+            }
+        }
+
+        let announcement = Announcement::new(tenant.tenant_id.clone(), request, Some(published_by.id));
+        self.announcements.create(&announcement).await?;
+        Ok(announcement)
+    }
+
+    /// Take a live or scheduled announcement down now
+    pub async fn withdraw(&self, tenant: &TenantContext, id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        self.find(tenant, id).await?;
+        if !self.announcements.end(id, Utc::now()).await? {
+            return Err(AppError::Conflict(format!("Announcement {} has already ended", id)));
+        }
+        Ok(())
+    }
+
+    /// Live announcements addressed to the user that they have not dismissed
+    pub async fn for_user(&self, tenant: &TenantContext, user: &User) -> AppResult<Vec<Announcement>> {
+        let live = self.announcements.live(&tenant.tenant_id, Utc::now()).await?;
+        if live.is_empty() {
+            return Ok(live);
+        }
+
+        let group_ids = if live.iter().any(|announcement| announcement.audience.group_id.is_some()) {
+            self.group_ids(user.id).await?
+        } else {
+            Vec::new()
+        };
+        let addressed: Vec<Announcement> = live
+            .into_iter()
+            .filter(|announcement| announcement.audience.includes(&user.role, &group_ids))
+            .collect();
+
+        let ids: Vec<Uuid> = addressed.iter().map(|announcement| announcement.id).collect();
+        let dismissed = self.announcements.dismissed(user.id, &ids).await?;
+        Ok(addressed
+            .into_iter()
+            .filter(|announcement| !dismissed.contains(&announcement.id))
+            .collect())
+    }
+
+    /// Hide an announcement from the user; dismissing twice is not an error
+    pub async fn dismiss(&self, tenant: &TenantContext, user: &User, id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        let announcement = self.find(tenant, id).await?;
+        let group_ids = match announcement.audience.group_id {
+            Some(_) => self.group_ids(user.id).await?,
+            None => Vec::new(),
+        };
+        if !announcement.audience.includes(&user.role, &group_ids) {
+            return Err(AppError::NotFound(format!("Announcement {} not found", id)));
+        }
+
+        self.announcements.dismiss(id, user.id).await?;
+        Ok(())
+    }
+
+    async fn group_ids(&self, user_id: Uuid) -> AppResult<Vec<Uuid>> {
+        let groups = self.groups.groups_for_user(user_id).await?;
+        Ok(groups.into_iter().map(|group| group.id).collect())
+    }
+
+    /// Announcements of other tenants are reported as missing
+    async fn find(&self, tenant: &TenantContext, id: Uuid) -> AppResult<Announcement> {
+        self.announcements
+            .find_by_id(id)
+            .await?
+            .filter(|announcement| announcement.tenant_id == tenant.tenant_id)
+            .ok_or_else(|| AppError::NotFound(format!("Announcement {} not found", id)))
+    }
+}
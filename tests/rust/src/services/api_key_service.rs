@@ -0,0 +1,184 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::read_only::ReadOnlyMode;
+use crate::models::{
+    ApiKey, ApiKeyContext, AppError, AppResult, CreateApiKeyRequest, IssuedApiKey, TenantContext, User,
+};
+use crate::repositories::ApiKeyRepository;
+use crate::utils::Logger;
+
+/// Marks a credential as an API key rather than a JWT
+pub const API_KEY_PREFIX: &str = "crk_";
+
+/// Characters of a key kept in the clear, including `API_KEY_PREFIX`
+const VISIBLE_PREFIX_LENGTH: usize = 12;
+
+/// `last_used_at` is only rewritten once it is this stale, so busy keys do not write on every request
+const LAST_USED_RESOLUTION_SECS: i64 = 60;
+
+/// Issues, rotates and verifies API keys for service-to-service callers.
+///
+/// Keys are 256 random bits, so like link tokens they are stored as an
+/// unsalted SHA-256 hash and looked up by it. Rotation issues a new key
+/// and leaves the old one working for a grace period, so callers can
+/// switch over without an outage.
+pub struct ApiKeyService {
+    keys: Arc<ApiKeyRepository>,
+    rotation_grace: Duration,
+    read_only: Arc<ReadOnlyMode>,
+    logger: Arc<Logger>,
+}
+
+impl ApiKeyService {
+    pub fn new(
+        keys: Arc<ApiKeyRepository>,
+        rotation_grace: Duration,
+        read_only: Arc<ReadOnlyMode>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        Self {
+            keys,
+            rotation_grace,
+            read_only,
+            logger,
+        }
+    }
+
+    pub async fn create(
+        &self,
+        tenant: &TenantContext,
+        request: CreateApiKeyRequest,
+        created_by: &User,
+    ) -> AppResult<IssuedApiKey> {
+        self.read_only.check()?;
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        let secret = new_key();
+        let mut api_key = ApiKey::new(
+            tenant.tenant_id.clone(),
+            request.name.trim().to_string(),
+            request.scope,
+            secret[..VISIBLE_PREFIX_LENGTH].to_string(),
+            hash_key(&secret),
+            created_by.id,
+        );
+        api_key.expires_at = request.expires_at;
+        self.keys.create(&api_key).await?;
+
+        self.logger.info(&format!(
+            "API key {} ({}) created by {} with {} scope",
+            api_key.id,
+            api_key.prefix,
+            created_by.id,
+            api_key.scope.as_str()
+        ));
+        Ok(IssuedApiKey { api_key, key: secret })
+    }
+
+    pub async fn list(&self, tenant: &TenantContext) -> AppResult<Vec<ApiKey>> {
+        self.keys.list(&tenant.tenant_id).await
+    }
+
+    /// Replace a key with a new one of the same name and scope; the old key expires after the grace period
+    pub async fn rotate(&self, tenant: &TenantContext, id: Uuid, rotated_by: &User) -> AppResult<IssuedApiKey> {
+        self.read_only.check()?;
+        let previous = self.find_active(tenant, id).await?;
+        let grace = chrono::Duration::from_std(self.rotation_grace)
+            .map_err(|e| AppError::Config(format!("Invalid API key rotation grace: {}", e)))?;
+
+        let secret = new_key();
+        let mut api_key = ApiKey::new(
+            previous.tenant_id.clone(),
+            previous.name.clone(),
+            previous.scope,
+            secret[..VISIBLE_PREFIX_LENGTH].to_string(),
+            hash_key(&secret),
+            rotated_by.id,
+        );
+        api_key.expires_at = previous.expires_at;
+        api_key.rotated_from = Some(previous.id);
+        self.keys.rotate(&api_key, Utc::now() + grace).await?;
+
+        self.logger.info(&format!(
+            "API key {} rotated to {} by {}",
+            previous.id, api_key.id, rotated_by.id
+        ));
+        Ok(IssuedApiKey { api_key, key: secret })
+    }
+
+    pub async fn revoke(&self, tenant: &TenantContext, id: Uuid, revoked_by: &User) -> AppResult<()> {
+        self.read_only.check()?;
+        self.find_active(tenant, id).await?;
+        if !self.keys.revoke(id).await? {
+            return Err(AppError::Conflict(format!("API key {} has already been revoked", id)));
+        }
+        self.logger.info(&format!("API key {} revoked by {}", id, revoked_by.id));
+        Ok(())
+    }
+
+    /// The caller behind a presented key, recording that the key was used
+    pub async fn authenticate(&self, key: &str) -> AppResult<ApiKeyContext> {
+        if !key.starts_with(API_KEY_PREFIX) {
+            return Err(invalid_key());
+        }
+        let now = Utc::now();
+        let api_key = self
+            .keys
+            .find_by_hash(&hash_key(key))
+            .await?
+            .filter(|api_key| api_key.is_active(now))
+            .ok_or_else(invalid_key)?;
+
+        if is_stale(api_key.last_used_at, now) {
+            // Usage tracking must not fail the request it describes
+            if let Err(e) = self.keys.touch(api_key.id, now).await {
+                self.logger.warn(&format!("Failed to record use of API key {}: {}", api_key.id, e));
+            }
+        }
+        Ok(ApiKeyContext {
+            key_id: api_key.id,
+            tenant_id: api_key.tenant_id,
+            scope: api_key.scope,
+        })
+    }
+
+    async fn find_active(&self, tenant: &TenantContext, id: Uuid) -> AppResult<ApiKey> {
+        let api_key = self
+            .keys
+            .find(&tenant.tenant_id, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("API key {} not found", id)))?;
+        if api_key.revoked_at.is_some() {
+            return Err(AppError::Conflict(format!("API key {} has already been revoked", id)));
+        }
+        Ok(api_key)
+    }
+}
+
+fn new_key() -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    format!("{}{}", API_KEY_PREFIX, URL_SAFE_NO_PAD.encode(secret))
+}
+
+fn hash_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+fn is_stale(last_used_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    last_used_at.map_or(true, |at| (now - at).num_seconds() >= LAST_USED_RESOLUTION_SECS)
+}
+
+fn invalid_key() -> AppError {
+    AppError::Unauthorized("Invalid API key".to_string())
+}
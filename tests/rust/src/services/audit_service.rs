@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use crate::models::{AppError, AppResult, AuditLog, AuditLogFilters, AuditLogPage};
+use crate::repositories::AuditLogRepository;
+use crate::utils::{Logger, Metrics};
+
+/// Records security-sensitive actions and serves them to compliance reviews.
+///
+/// Recording is best-effort: a failed write is logged and counted rather
+/// than failing the action it describes, the same trade-off the per-user
+/// audit timeline makes.
+pub struct AuditService {
+    repository: Arc<AuditLogRepository>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl AuditService {
+    pub fn new(repository: Arc<AuditLogRepository>, metrics: Arc<Metrics>, logger: Arc<Logger>) -> Self {
+        Self {
+            repository,
+            metrics,
+            logger,
+        }
+    }
+
+    pub async fn record(&self, entry: AuditLog) {
+        if let Err(e) = self.repository.record(&entry).await {
+            let _ = self.metrics.increment_counter("audit_log.write_failures").await;
+            self.logger.warn(&format!(
+                "Failed to record {} by {:?} on {:?}: {}",
+                entry.action.as_str(),
+                entry.actor_id,
+                entry.target_id,
+                e
+            ));
+        }
+    }
+
+    /// Entries matching `filters`, newest first, with the total across pages
+    pub async fn search(&self, filters: &AuditLogFilters) -> AppResult<AuditLogPage> {
+        let errors = filters.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+        let (entries, total) = tokio::try_join!(self.repository.query(filters), self.repository.count(filters))?;
+        Ok(AuditLogPage { entries, total })
+    }
+}
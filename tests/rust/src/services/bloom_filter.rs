@@ -0,0 +1,98 @@
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use super::cache_service::CacheService;
+use crate::models::AppResult;
+
+/// Probabilistic set membership stored as a Redis bitmap.
+///
+/// A negative answer is definite, a positive one only means "maybe", so
+/// callers use it to skip database lookups for values that were never
+/// added. Values cannot be removed; deleted entries simply keep answering
+/// "maybe" until the filter is rebuilt.
+pub struct BloomFilter {
+    cache: Arc<CacheService>,
+    key: String,
+    bits: u64,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// Size the filter for `expected_items` at the given false positive rate
+    pub fn new(cache: Arc<CacheService>, key: &str, expected_items: u64, false_positive_rate: f64) -> Self {
+        let items = expected_items.max(1) as f64;
+        let rate = false_positive_rate.clamp(1e-9, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-(items * rate.ln()) / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let hashes = ((bits as f64 / items) * ln2).round().clamp(1.0, 32.0) as u32;
+
+        Self {
+            cache,
+            key: key.to_string(),
+            bits,
+            hashes,
+        }
+    }
+
+    /// Whether the filter has been fully populated; until then it cannot rule anything out
+    pub async fn is_ready(&self) -> AppResult<bool> {
+        Ok(self.cache.get::<bool>(&self.ready_key()).await?.unwrap_or(false))
+    }
+
+    pub async fn mark_ready(&self) -> AppResult<()> {
+        self.cache.set(&self.ready_key(), &true, None).await
+    }
+
+    /// Drop all bits, e.g. before a rebuild
+    pub async fn clear(&self) -> AppResult<()> {
+        self.cache.delete(&self.ready_key()).await?;
+        self.cache.delete(&self.key).await
+    }
+
+    pub async fn insert(&self, value: &str) -> AppResult<()> {
+        self.insert_many(&[value]).await
+    }
+
+    pub async fn insert_many(&self, values: &[&str]) -> AppResult<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        let mut pipe = redis::pipe();
+        for value in values {
+            for offset in self.offsets(value) {
+                pipe.setbit(&self.key, offset as usize, true).ignore();
+            }
+        }
+        let mut conn = self.cache.connection();
+        let _: () = pipe.query_async(&mut conn).await?;
+        Ok(())
+    }
+
+    /// False only if `value` was definitely never inserted
+    pub async fn might_contain(&self, value: &str) -> AppResult<bool> {
+        if !self.is_ready().await? {
+            return Ok(true);
+        }
+        let mut pipe = redis::pipe();
+        for offset in self.offsets(value) {
+            pipe.getbit(&self.key, offset as usize);
+        }
+        let mut conn = self.cache.connection();
+        let bits: Vec<bool> = pipe.query_async(&mut conn).await?;
+        Ok(bits.into_iter().all(|bit| bit))
+    }
+
+    /// Bit positions via double hashing of a single SHA-256 digest
+    fn offsets(&self, value: &str) -> Vec<u64> {
+        let digest = Sha256::digest(value.to_lowercase().as_bytes());
+        let h1 = u64::from_be_bytes(digest[..8].try_into().expect("digest has 32 bytes"));
+        let h2 = u64::from_be_bytes(digest[8..16].try_into().expect("digest has 32 bytes")) | 1;
+        (0..self.hashes as u64)
+            .map(|i| h1.wrapping_add(i.wrapping_mul(h2)) % self.bits)
+            .collect()
+    }
+
+    fn ready_key(&self) -> String {
+        format!("{}:ready", self.key)
+    }
+}
@@ -0,0 +1,102 @@
+use sha1::{Digest, Sha1};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use super::bloom_filter::BloomFilter;
+use super::cache_service::CacheService;
+use crate::config::PasswordPolicy;
+use crate::models::{AppError, AppResult};
+use crate::utils::Logger;
+
+const FILTER_KEY: &str = "bloom:breached_passwords";
+/// Hashes inserted per round trip while loading the list
+const LOAD_BATCH: usize = 10_000;
+/// Length of a hex SHA-1 digest
+const HASH_LEN: usize = 40;
+
+/// Passwords known from breaches, refused wherever a password is chosen.
+///
+/// The configured list is loaded once into a bloom filter every instance
+/// shares. Entries are SHA-1 digests as haveibeenpwned publishes them, so
+/// no password is ever kept in the clear. Until a load completes nothing is
+/// refused; afterwards a false positive refuses an unbreached password at
+/// the configured error rate and the user simply picks another.
+pub struct BreachedPasswords {
+    filter: BloomFilter,
+    list: Option<String>,
+    logger: Arc<Logger>,
+}
+
+impl BreachedPasswords {
+    pub fn new(cache: Arc<CacheService>, policy: &PasswordPolicy, logger: Arc<Logger>) -> Self {
+        Self {
+            filter: BloomFilter::new(
+                cache,
+                FILTER_KEY,
+                policy.breached_filter_capacity,
+                policy.breached_filter_error_rate,
+            ),
+            list: policy.breached_list.clone(),
+            logger,
+        }
+    }
+
+    /// Load the configured list unless another instance already has, returning how many hashes were added.
+    ///
+    /// An interrupted load leaves the filter unready, so the next one starts over.
+    pub async fn load(&self) -> AppResult<usize> {
+        let Some(path) = &self.list else {
+            return Ok(0);
+        };
+        if self.filter.is_ready().await? {
+            return Ok(0);
+        }
+        let unreadable = |e: std::io::Error| {
+            AppError::Config(format!("Cannot read breached password list {}: {}", path, e))
+        };
+        let file = tokio::fs::File::open(path).await.map_err(unreadable)?;
+        let mut lines = BufReader::new(file).lines();
+        let mut batch = Vec::with_capacity(LOAD_BATCH);
+        let mut total = 0;
+        while let Some(line) = lines.next_line().await.map_err(unreadable)? {
+            let hash = line.split(':').next().unwrap_or_default().trim();
+            if hash.len() != HASH_LEN || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                continue;
+            }
+            batch.push(hash.to_string());
+            if batch.len() == LOAD_BATCH {
+                total += self.insert(&mut batch).await?;
+            }
+        }
+        total += self.insert(&mut batch).await?;
+
+        self.filter.mark_ready().await?;
+        self.logger
+            .info(&format!("Loaded {} breached password hashes from {}", total, path));
+        Ok(total)
+    }
+
+    /// Whether the password is probably in the list; always false before the list is loaded
+    pub async fn contains(&self, password: &str) -> AppResult<bool> {
+        if self.list.is_none() || !self.filter.is_ready().await? {
+            return Ok(false);
+        }
+        self.filter.might_contain(&sha1_hex(password)).await
+    }
+
+    async fn insert(&self, batch: &mut Vec<String>) -> AppResult<usize> {
+        let hashes: Vec<&str> = batch.iter().map(String::as_str).collect();
+        self.filter.insert_many(&hashes).await?;
+        let inserted = batch.len();
+        batch.clear();
+        Ok(inserted)
+    }
+}
+
+/// The digest as the list spells it; the filter ignores case
+fn sha1_hex(password: &str) -> String {
+    Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect()
+}
@@ -0,0 +1,95 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::config::CacheConfig;
+use crate::models::{AppError, AppResult};
+
+/// Kinds of entity the cache holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheEntity {
+    User,
+    Template,
+    /// Resolved user segments, e.g. audience lists for targeting
+    Segment,
+}
+
+impl CacheEntity {
+    /// Key prefix shared by every entry of the entity
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            CacheEntity::User => "user",
+            CacheEntity::Template => "template",
+            CacheEntity::Segment => "segment",
+        }
+    }
+}
+
+/// Wire format of cached values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheCodec {
+    Json,
+    /// Compact binary encoding for large or frequently read entities
+    MessagePack,
+}
+
+/// TTL and encoding applied to every cache entry of one entity type
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    pub entity: CacheEntity,
+    pub ttl: Duration,
+    pub codec: CacheCodec,
+}
+
+impl CachePolicy {
+    /// A zero TTL turns caching off for the entity
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    pub fn key(&self, id: &str) -> String {
+        format!("{}:{}", self.entity.prefix(), id)
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> AppResult<Vec<u8>> {
+        let unserializable = |e: String| AppError::Cache(format!("Unserializable {:?} cache value: {}", self.entity, e));
+        match self.codec {
+            CacheCodec::Json => serde_json::to_vec(value).map_err(|e| unserializable(e.to_string())),
+            CacheCodec::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| unserializable(e.to_string())),
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, raw: &[u8]) -> AppResult<T> {
+        let corrupt = |e: String| AppError::Cache(format!("Corrupt {:?} cache entry: {}", self.entity, e));
+        match self.codec {
+            CacheCodec::Json => serde_json::from_slice(raw).map_err(|e| corrupt(e.to_string())),
+            CacheCodec::MessagePack => rmp_serde::from_slice(raw).map_err(|e| corrupt(e.to_string())),
+        }
+    }
+}
+
+/// Registry of cache policies, one per entity type
+#[derive(Debug, Clone)]
+pub struct CachePolicies {
+    policies: HashMap<CacheEntity, CachePolicy>,
+}
+
+impl CachePolicies {
+    pub fn from_config(config: &CacheConfig) -> Self {
+        let policies = [
+            (CacheEntity::User, config.user_ttl, CacheCodec::MessagePack),
+            (CacheEntity::Template, config.template_ttl, CacheCodec::Json),
+            (CacheEntity::Segment, config.segment_ttl, CacheCodec::MessagePack),
+        ]
+        .into_iter()
+        .map(|(entity, ttl, codec)| (entity, CachePolicy { entity, ttl, codec }))
+        .collect();
+
+        Self { policies }
+    }
+
+    pub fn get(&self, entity: CacheEntity) -> CachePolicy {
+        self.policies[&entity].clone()
+    }
+}
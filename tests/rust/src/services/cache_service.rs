@@ -0,0 +1,677 @@
+use redis::aio::ConnectionManager;
+use redis::streams::{
+    StreamClaimReply, StreamId, StreamMaxlen, StreamPendingCountReply, StreamPendingReply, StreamRangeReply,
+    StreamReadOptions, StreamReadReply,
+};
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::Span;
+
+use super::cache_policy::CachePolicy;
+use crate::models::{AppError, AppResult};
+use crate::utils::{FrequencySketch, HotKey};
+
+/// Sketch dimensions: 16k counters keep overcounting within about 0.07% of
+/// total reads for 98% of keys
+const SKETCH_WIDTH: usize = 4096;
+const SKETCH_DEPTH: usize = 4;
+const HOT_KEY_CAPACITY: usize = 50;
+
+impl From<redis::RedisError> for AppError {
+    fn from(error: redis::RedisError) -> Self {
+        AppError::Cache(error.to_string())
+    }
+}
+
+/// Field of a stream entry holding its JSON payload
+const STREAM_PAYLOAD_FIELD: &str = "payload";
+
+/// An entry read from a stream through a consumer group.
+///
+/// Entries stay pending for the group until acknowledged, so a consumer that
+/// crashes before `stream_ack` leaves them to be claimed by another.
+#[derive(Debug, Clone)]
+pub struct StreamEntry<T> {
+    pub id: String,
+    pub payload: T,
+    /// How many times the entry has been handed to a consumer, including this one
+    pub deliveries: u64,
+}
+
+/// Refills a token bucket stored as a hash and takes one token if there is
+/// one. Returns whether a token was taken, the whole tokens left and how
+/// many milliseconds until the next token when none was.
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local burst = tonumber(ARGV[1])
+local per_ms = tonumber(ARGV[2])
+local now = tonumber(ARGV[3])
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'at')
+local tokens = tonumber(bucket[1]) or burst
+local at = tonumber(bucket[2]) or now
+tokens = math.min(burst, tokens + math.max(0, now - at) * per_ms)
+local taken = 0
+local wait = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    taken = 1
+else
+    wait = math.ceil((1 - tokens) / per_ms)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'at', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(burst / per_ms))
+return {taken, math.floor(tokens), wait}
+"#;
+
+/// Outcome of taking a token from a bucket
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBucket {
+    pub taken: bool,
+    pub remaining: u32,
+    /// Until another token is available; zero when one was taken
+    pub retry_after: Duration,
+}
+
+/// Redis-backed cache and shared counter store
+pub struct CacheService {
+    connection: ConnectionManager,
+    /// Read frequencies of cache keys seen by this instance
+    reads: Mutex<FrequencySketch>,
+    /// Hashed once; sent by digest after the first call
+    token_bucket: redis::Script,
+}
+
+/// Snapshot of the hottest keys read through this instance
+#[derive(Debug, Clone, Serialize)]
+pub struct HotKeyReport {
+    pub total_reads: u64,
+    pub keys: Vec<HotKey>,
+}
+
+impl CacheService {
+    /// Connect to Redis; the connection manager reconnects transparently
+    pub async fn new(redis_url: &str) -> AppResult<Self> {
+        let client = redis::Client::open(redis_url)?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(Self {
+            connection,
+            reads: Mutex::new(FrequencySketch::new(SKETCH_WIDTH, SKETCH_DEPTH, HOT_KEY_CAPACITY)),
+            token_bucket: redis::Script::new(TOKEN_BUCKET_SCRIPT),
+        })
+    }
+
+    pub async fn health_check(&self) -> AppResult<()> {
+        let mut conn = self.connection();
+        let _: String = redis::cmd("PING").query_async(&mut conn).await?;
+        Ok(())
+    }
+
+    pub async fn close(&self) -> AppResult<()> {
+        // Connections are released when the manager is dropped
+        Ok(())
+    }
+
+    /// Fetch and deserialize a JSON value
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "get", cache.key = key_namespace(key), cache.hit = tracing::field::Empty)
+    )]
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> AppResult<Option<T>> {
+        self.record_read(key);
+        let mut conn = self.connection();
+        let raw: Option<String> = conn.get(key).await?;
+        record_hit(raw.is_some());
+        raw.map(|raw| {
+            serde_json::from_str(&raw)
+                .map_err(|e| AppError::Cache(format!("Corrupt cache entry {}: {}", key, e)))
+        })
+        .transpose()
+    }
+
+    /// Fetch a JSON value and remove it in the same command, so only one caller ever gets it
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "getdel", cache.key = key_namespace(key), cache.hit = tracing::field::Empty)
+    )]
+    pub async fn take<T: DeserializeOwned>(&self, key: &str) -> AppResult<Option<T>> {
+        self.record_read(key);
+        let mut conn = self.connection();
+        let raw: Option<String> = redis::cmd("GETDEL").arg(key).query_async(&mut conn).await?;
+        record_hit(raw.is_some());
+        raw.map(|raw| {
+            serde_json::from_str(&raw)
+                .map_err(|e| AppError::Cache(format!("Corrupt cache entry {}: {}", key, e)))
+        })
+        .transpose()
+    }
+
+    /// Serialize and store a JSON value with an optional expiry
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "set", cache.key = key_namespace(key))
+    )]
+    pub async fn set<T: Serialize>(&self, key: &str, value: &T, ttl: Option<Duration>) -> AppResult<()> {
+        let raw = serde_json::to_string(value)
+            .map_err(|e| AppError::Cache(format!("Unserializable cache value {}: {}", key, e)))?;
+        let mut conn = self.connection();
+        let _: () = match ttl {
+            Some(ttl) => conn.set_ex(key, raw, ttl.as_secs().max(1)).await?,
+            None => conn.set(key, raw).await?,
+        };
+        Ok(())
+    }
+
+    /// Read an entity cached under `policy`
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "get", cache.key = policy.entity.prefix(), cache.hit = tracing::field::Empty)
+    )]
+    pub async fn fetch<T: DeserializeOwned>(&self, policy: &CachePolicy, id: &str) -> AppResult<Option<T>> {
+        if !policy.is_enabled() {
+            return Ok(None);
+        }
+        let key = policy.key(id);
+        self.record_read(&key);
+        let mut conn = self.connection();
+        let raw: Option<Vec<u8>> = conn.get(key).await?;
+        record_hit(raw.is_some());
+        raw.map(|raw| policy.decode(&raw)).transpose()
+    }
+
+    /// Cache an entity with the TTL and codec of `policy`
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "set", cache.key = policy.entity.prefix())
+    )]
+    pub async fn store<T: Serialize>(&self, policy: &CachePolicy, id: &str, value: &T) -> AppResult<()> {
+        if !policy.is_enabled() {
+            return Ok(());
+        }
+        let raw = policy.encode(value)?;
+        let mut conn = self.connection();
+        let _: () = conn.set_ex(policy.key(id), raw, policy.ttl.as_secs().max(1)).await?;
+        Ok(())
+    }
+
+    pub async fn evict(&self, policy: &CachePolicy, id: &str) -> AppResult<()> {
+        self.delete(&policy.key(id)).await
+    }
+
+    /// Like `fetch`, but not counted as a read, so inspecting the cache leaves hot keys alone
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "get", cache.key = policy.entity.prefix(), cache.hit = tracing::field::Empty)
+    )]
+    pub async fn peek<T: DeserializeOwned>(&self, policy: &CachePolicy, id: &str) -> AppResult<Option<T>> {
+        let mut conn = self.connection();
+        let raw: Option<Vec<u8>> = conn.get(policy.key(id)).await?;
+        record_hit(raw.is_some());
+        raw.map(|raw| policy.decode(&raw)).transpose()
+    }
+
+    /// One `SCAN` step over the entries of `policy`, returning the next
+    /// cursor, zero once the keyspace has been walked, and the ids found
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "scan", cache.key = policy.entity.prefix())
+    )]
+    pub async fn scan_ids(&self, policy: &CachePolicy, cursor: u64, count: usize) -> AppResult<(u64, Vec<String>)> {
+        let mut conn = self.connection();
+        let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg(policy.key("*"))
+            .arg("COUNT")
+            .arg(count.max(1))
+            .query_async(&mut conn)
+            .await?;
+        let prefix = policy.key("");
+        let ids = keys
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&prefix).map(str::to_string))
+            .collect();
+        Ok((next, ids))
+    }
+
+    /// Whether each key exists, in the order given
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "exists", cache.key = keys.first().map_or("", |key| key_namespace(key)))
+    )]
+    pub async fn exists_many(&self, keys: &[String]) -> AppResult<Vec<bool>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut conn = self.connection();
+        let mut pipe = redis::pipe();
+        for key in keys {
+            pipe.exists(key);
+        }
+        let present: Vec<bool> = pipe.query_async(&mut conn).await?;
+        Ok(present)
+    }
+
+    /// Store a JSON value only if nothing is stored under `key` yet; false when something was
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "set_nx", cache.key = key_namespace(key))
+    )]
+    pub async fn set_if_absent<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) -> AppResult<bool> {
+        let raw = serde_json::to_string(value)
+            .map_err(|e| AppError::Cache(format!("Unserializable cache value {}: {}", key, e)))?;
+        let mut conn = self.connection();
+        let stored: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(raw)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl.as_secs().max(1))
+            .query_async(&mut conn)
+            .await?;
+        Ok(stored.is_some())
+    }
+
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "del", cache.key = key_namespace(key))
+    )]
+    pub async fn delete(&self, key: &str) -> AppResult<()> {
+        let mut conn = self.connection();
+        let _: () = conn.del(key).await?;
+        Ok(())
+    }
+
+    /// Atomically increment a counter, setting its expiry only when first created
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "incr", cache.key = key_namespace(key))
+    )]
+    pub async fn increment(&self, key: &str, by: i64, ttl: Option<Duration>) -> AppResult<i64> {
+        let mut conn = self.connection();
+        let mut pipe = redis::pipe();
+        pipe.atomic().incr(key, by);
+        if let Some(ttl) = ttl {
+            pipe.cmd("EXPIRE").arg(key).arg(ttl.as_secs().max(1)).arg("NX").ignore();
+        }
+        let (value,): (i64,) = pipe.query_async(&mut conn).await?;
+        Ok(value)
+    }
+
+    /// Take one token from the bucket at `key`, holding up to `burst` tokens
+    /// and refilling `per_second`. Buckets left alone until full again expire.
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "token_bucket", cache.key = key_namespace(key))
+    )]
+    pub async fn take_token(&self, key: &str, burst: u32, per_second: f64) -> AppResult<TokenBucket> {
+        let mut conn = self.connection();
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let (taken, remaining, wait_ms): (i64, i64, i64) = self
+            .token_bucket
+            .key(key)
+            .arg(burst)
+            .arg(per_second / 1000.0)
+            .arg(now_ms)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(TokenBucket {
+            taken: taken == 1,
+            remaining: remaining.max(0) as u32,
+            retry_after: Duration::from_millis(wait_ms.max(0) as u64),
+        })
+    }
+
+    /// Read a counter without modifying it
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "get", cache.key = key_namespace(key), cache.hit = tracing::field::Empty)
+    )]
+    pub async fn get_counter(&self, key: &str) -> AppResult<i64> {
+        let mut conn = self.connection();
+        let value: Option<i64> = conn.get(key).await?;
+        record_hit(value.is_some());
+        Ok(value.unwrap_or(0))
+    }
+
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "sadd", cache.key = key_namespace(key))
+    )]
+    pub async fn add_to_set(&self, key: &str, member: &str) -> AppResult<()> {
+        let mut conn = self.connection();
+        let _: () = conn.sadd(key, member).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "srem", cache.key = key_namespace(key))
+    )]
+    pub async fn remove_from_set(&self, key: &str, members: &[String]) -> AppResult<()> {
+        if members.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.connection();
+        let _: () = conn.srem(key, members).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(
+            cache.operation = "smembers",
+            cache.key = key_namespace(key),
+            cache.entries = tracing::field::Empty
+        )
+    )]
+    pub async fn set_members(&self, key: &str) -> AppResult<Vec<String>> {
+        let mut conn = self.connection();
+        let members: Vec<String> = conn.smembers(key).await?;
+        Span::current().record("cache.entries", members.len());
+        Ok(members)
+    }
+
+    /// Remove and return up to `count` random members of a set
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(
+            cache.operation = "spop",
+            cache.key = key_namespace(key),
+            cache.entries = tracing::field::Empty
+        )
+    )]
+    pub async fn pop_from_set(&self, key: &str, count: usize) -> AppResult<Vec<String>> {
+        let mut conn = self.connection();
+        let members: Vec<String> = redis::cmd("SPOP")
+            .arg(key)
+            .arg(count)
+            .query_async(&mut conn)
+            .await?;
+        Span::current().record("cache.entries", members.len());
+        Ok(members)
+    }
+
+    /// Add `by` to the score of `member` in the sorted set at `key`, which expires `ttl` after its first write
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "zincrby", cache.key = key_namespace(key))
+    )]
+    pub async fn increment_score(&self, key: &str, member: &str, by: f64, ttl: Option<Duration>) -> AppResult<f64> {
+        let mut conn = self.connection();
+        let mut pipe = redis::pipe();
+        pipe.atomic().zincr(key, member, by);
+        if let Some(ttl) = ttl {
+            pipe.cmd("EXPIRE").arg(key).arg(ttl.as_secs().max(1)).arg("NX").ignore();
+        }
+        let (score,): (f64,) = pipe.query_async(&mut conn).await?;
+        Ok(score)
+    }
+
+    /// The `limit` highest-scoring members across the sorted sets at `keys`, their scores summed
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(
+            cache.operation = "zunion",
+            cache.key = keys.first().map(|key| key_namespace(key)),
+            cache.entries = tracing::field::Empty
+        )
+    )]
+    pub async fn top_scores(&self, keys: &[String], limit: usize) -> AppResult<Vec<(String, f64)>> {
+        if keys.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        // Summed into a scratch set, read and dropped in one transaction
+        let scratch = format!("zunion:{}", uuid::Uuid::new_v4());
+        let mut conn = self.connection();
+        let (top,): (Vec<(String, f64)>,) = redis::pipe()
+            .atomic()
+            .cmd("ZUNIONSTORE")
+            .arg(&scratch)
+            .arg(keys.len())
+            .arg(keys)
+            .ignore()
+            .cmd("ZREVRANGE")
+            .arg(&scratch)
+            .arg(0)
+            .arg(limit - 1)
+            .arg("WITHSCORES")
+            .cmd("DEL")
+            .arg(&scratch)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        Span::current().record("cache.entries", top.len());
+        Ok(top)
+    }
+
+    /// Append a JSON payload to a stream, trimming it to roughly `max_len` entries
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "xadd", cache.key = key_namespace(stream))
+    )]
+    pub async fn stream_add<T: Serialize>(&self, stream: &str, payload: &T, max_len: Option<usize>) -> AppResult<String> {
+        let raw = serde_json::to_string(payload)
+            .map_err(|e| AppError::Cache(format!("Unserializable stream entry for {}: {}", stream, e)))?;
+        let fields = [(STREAM_PAYLOAD_FIELD, raw)];
+        let mut conn = self.connection();
+        let id: String = match max_len {
+            Some(max_len) => conn.xadd_maxlen(stream, StreamMaxlen::Approx(max_len), "*", &fields).await?,
+            None => conn.xadd(stream, "*", &fields).await?,
+        };
+        Ok(id)
+    }
+
+    /// The newest `count` payloads of a stream, newest first; entries that cannot be decoded are skipped
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(
+            cache.operation = "xrevrange",
+            cache.key = key_namespace(stream),
+            cache.entries = tracing::field::Empty
+        )
+    )]
+    pub async fn stream_recent<T: DeserializeOwned>(&self, stream: &str, count: usize) -> AppResult<Vec<T>> {
+        let mut conn = self.connection();
+        let reply: StreamRangeReply = conn.xrevrange_count(stream, "+", "-", count).await?;
+        let entries: Vec<T> = reply
+            .ids
+            .into_iter()
+            .filter_map(|id| id.get::<String>(STREAM_PAYLOAD_FIELD))
+            .filter_map(|raw| serde_json::from_str(&raw).ok())
+            .collect();
+        Span::current().record("cache.entries", entries.len());
+        Ok(entries)
+    }
+
+    /// Create a consumer group reading the stream from its beginning; existing groups are left as they are
+    pub async fn create_consumer_group(&self, stream: &str, group: &str) -> AppResult<()> {
+        let mut conn = self.connection();
+        let created: redis::RedisResult<()> = conn.xgroup_create_mkstream(stream, group, "0").await;
+        match created {
+            Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+            other => Ok(other?),
+        }
+    }
+
+    /// Read entries never delivered to the group, waiting up to `block` for new ones
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(
+            cache.operation = "xreadgroup",
+            cache.key = key_namespace(stream),
+            cache.entries = tracing::field::Empty
+        )
+    )]
+    pub async fn stream_read_group<T: DeserializeOwned>(
+        &self,
+        stream: &str,
+        group: &str,
+        consumer: &str,
+        count: usize,
+        block: Duration,
+    ) -> AppResult<Vec<StreamEntry<T>>> {
+        let options = StreamReadOptions::default()
+            .group(group, consumer)
+            .count(count)
+            .block(block.as_millis() as usize);
+        let mut conn = self.connection();
+        let reply: StreamReadReply = conn.xread_options(&[stream], &[">"], &options).await?;
+
+        let ids = reply.keys.into_iter().flat_map(|key| key.ids).map(|id| (id, 1));
+        let entries = self.decode_entries(stream, group, ids).await?;
+        Span::current().record("cache.entries", entries.len());
+        Ok(entries)
+    }
+
+    /// Acknowledge processed entries so they leave the group's pending list
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(cache.operation = "xack", cache.key = key_namespace(stream))
+    )]
+    pub async fn stream_ack(&self, stream: &str, group: &str, ids: &[String]) -> AppResult<u64> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let mut conn = self.connection();
+        let acked: u64 = conn.xack(stream, group, ids).await?;
+        Ok(acked)
+    }
+
+    /// Take over entries another consumer has held unacknowledged for at least `min_idle`
+    #[tracing::instrument(
+        name = "cache.command",
+        skip_all,
+        fields(
+            cache.operation = "xclaim",
+            cache.key = key_namespace(stream),
+            cache.entries = tracing::field::Empty
+        )
+    )]
+    pub async fn stream_claim_pending<T: DeserializeOwned>(
+        &self,
+        stream: &str,
+        group: &str,
+        consumer: &str,
+        min_idle: Duration,
+        count: usize,
+    ) -> AppResult<Vec<StreamEntry<T>>> {
+        let mut conn = self.connection();
+        let pending: StreamPendingCountReply = conn.xpending_count(stream, group, "-", "+", count).await?;
+        let min_idle_ms = min_idle.as_millis() as usize;
+        let stale: Vec<_> = pending
+            .ids
+            .into_iter()
+            .filter(|entry| entry.last_delivered_ms >= min_idle_ms)
+            .collect();
+        if stale.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let stale_ids: Vec<&str> = stale.iter().map(|entry| entry.id.as_str()).collect();
+        // XCLAIM re-checks idle time, so entries another consumer just claimed are skipped
+        let claimed: StreamClaimReply = conn.xclaim(stream, group, consumer, min_idle_ms, &stale_ids).await?;
+
+        let ids = claimed.ids.into_iter().map(|id| {
+            let previous = stale
+                .iter()
+                .find(|entry| entry.id == id.id)
+                .map_or(0, |entry| entry.times_delivered as u64);
+            (id, previous + 1)
+        });
+        let entries = self.decode_entries(stream, group, ids).await?;
+        Span::current().record("cache.entries", entries.len());
+        Ok(entries)
+    }
+
+    /// Number of entries delivered to the group but not yet acknowledged
+    pub async fn stream_pending_count(&self, stream: &str, group: &str) -> AppResult<usize> {
+        let mut conn = self.connection();
+        let reply: StreamPendingReply = conn.xpending(stream, group).await?;
+        Ok(reply.count())
+    }
+
+    /// Entries whose payload cannot be decoded are acknowledged and dropped so they cannot wedge the group
+    async fn decode_entries<T: DeserializeOwned>(
+        &self,
+        stream: &str,
+        group: &str,
+        ids: impl Iterator<Item = (StreamId, u64)>,
+    ) -> AppResult<Vec<StreamEntry<T>>> {
+        let mut entries = Vec::new();
+        let mut corrupt = Vec::new();
+        for (id, deliveries) in ids {
+            let payload = id
+                .get::<String>(STREAM_PAYLOAD_FIELD)
+                .and_then(|raw| serde_json::from_str(&raw).ok());
+            match payload {
+                Some(payload) => entries.push(StreamEntry {
+                    id: id.id,
+                    payload,
+                    deliveries,
+                }),
+                None => corrupt.push(id.id),
+            }
+        }
+
+        if !corrupt.is_empty() {
+            tracing::warn!("Dropping {} undecodable entries from stream {}", corrupt.len(), stream);
+            self.stream_ack(stream, group, &corrupt).await?;
+        }
+        Ok(entries)
+    }
+
+    /// Most frequently read keys, as candidates for pinning in a local cache
+    pub fn hot_keys(&self, limit: usize) -> HotKeyReport {
+        let reads = self.reads.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        HotKeyReport {
+            total_reads: reads.recorded(),
+            keys: reads.top(limit),
+        }
+    }
+
+    fn record_read(&self, key: &str) {
+        self.reads
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .record(key);
+    }
+
+    /// Clone of the underlying connection for commands not wrapped here
+    pub fn connection(&self) -> ConnectionManager {
+        self.connection.clone()
+    }
+}
+
+/// Keys embed ids and emails, so spans only carry the part before the first `:`
+fn key_namespace(key: &str) -> &str {
+    key.split(':').next().unwrap_or(key)
+}
+
+fn record_hit(hit: bool) {
+    Span::current().record("cache.hit", hit);
+}
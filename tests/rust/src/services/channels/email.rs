@@ -0,0 +1,120 @@
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::tracking::EmailTracker;
+
+use crate::config::NotificationConfig;
+use crate::models::{AppError, AppResult};
+
+/// File attached to an outgoing email
+#[derive(Debug, Clone)]
+pub struct EmailAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
+
+/// A fully rendered email ready for delivery
+#[derive(Debug, Clone, Default)]
+pub struct EmailMessage {
+    /// Sender mailbox overriding the configured one, e.g. for a tenant's branding
+    pub from: Option<String>,
+    pub to: Vec<String>,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: Option<String>,
+    pub attachments: Vec<EmailAttachment>,
+    /// Notification to attribute opens and clicks to; only HTML bodies are tracked
+    pub track_as: Option<Uuid>,
+}
+
+/// SMTP delivery channel for notifications
+pub struct EmailChannel {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    tracker: Option<Arc<EmailTracker>>,
+}
+
+impl EmailChannel {
+    pub fn new(config: &NotificationConfig, tracker: Option<Arc<EmailTracker>>) -> AppResult<Self> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+            .map_err(|e| AppError::Config(format!("Invalid SMTP host: {}", e)))?
+            .port(config.smtp_port);
+
+        if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        let from = format!("{} <{}>", config.from_name, config.from_address)
+            .parse()
+            .map_err(|e| AppError::Config(format!("Invalid sender address: {}", e)))?;
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+            tracker,
+        })
+    }
+
+    /// Open an SMTP session, going as far as the greeting and STARTTLS, without sending anything
+    pub async fn test_connection(&self) -> AppResult<()> {
+        match self.transport.test_connection().await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(AppError::Internal("SMTP server refused the connection".to_string())),
+            Err(e) => Err(AppError::Internal(format!("SMTP handshake failed: {}", e))),
+        }
+    }
+
+    /// Deliver a message to all of its recipients
+    pub async fn send(&self, message: EmailMessage) -> AppResult<()> {
+        if message.to.is_empty() {
+            return Err(AppError::Validation(vec!["Email has no recipients".to_string()]));
+        }
+
+        let from = match &message.from {
+            Some(sender) => sender
+                .parse()
+                .map_err(|_| AppError::Validation(vec![format!("Invalid sender address: {}", sender)]))?,
+            None => self.from.clone(),
+        };
+        let mut builder = Message::builder().from(from).subject(message.subject);
+        for recipient in &message.to {
+            let mailbox: Mailbox = recipient.parse().map_err(|_| {
+                AppError::Validation(vec![format!("Invalid recipient address: {}", recipient)])
+            })?;
+            builder = builder.to(mailbox);
+        }
+
+        let html_body = match (&self.tracker, message.track_as) {
+            (Some(tracker), Some(notification_id)) => message
+                .html_body
+                .map(|html| tracker.instrument(notification_id, &html)),
+            _ => message.html_body,
+        };
+        let body = match html_body {
+            Some(html) => MultiPart::alternative_plain_html(message.text_body, html),
+            None => MultiPart::mixed().singlepart(SinglePart::plain(message.text_body)),
+        };
+        let mut content = MultiPart::mixed().multipart(body);
+        for attachment in message.attachments {
+            let content_type = ContentType::parse(&attachment.content_type)
+                .map_err(|_| AppError::Internal(format!("Bad content type {}", attachment.content_type)))?;
+            content = content
+                .singlepart(Attachment::new(attachment.filename).body(attachment.content, content_type));
+        }
+
+        let email = builder
+            .multipart(content)
+            .map_err(|e| AppError::Internal(format!("Failed to build email: {}", e)))?;
+
+        self.transport
+            .send(email)
+            .await
+            .map_err(|e| AppError::Internal(format!("SMTP delivery failed: {}", e)))?;
+        Ok(())
+    }
+}
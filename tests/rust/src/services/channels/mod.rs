@@ -0,0 +1,7 @@
+pub mod email;
+pub mod tracking;
+pub mod probes;
+
+pub use email::{EmailAttachment, EmailChannel, EmailMessage};
+pub use tracking::EmailTracker;
+pub use probes::HttpProbe;
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use reqwest::{Client, Method};
+use std::time::Duration;
+
+use super::email::EmailChannel;
+use crate::models::{AppError, AppResult};
+use crate::services::health_registry::HealthProbe;
+
+/// Opens an SMTP session and says hello without sending anything
+#[async_trait]
+impl HealthProbe for EmailChannel {
+    async fn probe(&self) -> AppResult<()> {
+        self.test_connection().await
+    }
+}
+
+/// Sends `HEAD` to a delivery provider's API or a webhook endpoint.
+///
+/// Only reachability is checked: any answer short of a server error counts
+/// as healthy, since endpoints commonly refuse unauthenticated `HEAD`
+/// requests with a 401 or 405.
+pub struct HttpProbe {
+    url: String,
+    client: Client,
+}
+
+impl HttpProbe {
+    pub fn new(url: &str, timeout: Duration) -> AppResult<Self> {
+        let client = Client::builder()
+            .timeout(timeout)
+            .user_agent(concat!("crawler-probe/", env!("CARGO_PKG_VERSION")))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| AppError::Config(format!("Invalid probe client settings: {}", e)))?;
+        Ok(Self {
+            url: url.to_string(),
+            client,
+        })
+    }
+}
+
+#[async_trait]
+impl HealthProbe for HttpProbe {
+    async fn probe(&self) -> AppResult<()> {
+        let response = self
+            .client
+            .request(Method::HEAD, &self.url)
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("{} is unreachable: {}", self.url, e)))?;
+        if response.status().is_server_error() {
+            return Err(AppError::Internal(format!("{} answered {}", self.url, response.status())));
+        }
+        Ok(())
+    }
+}
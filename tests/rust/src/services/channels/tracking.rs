@@ -0,0 +1,129 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::config::NotificationConfig;
+use crate::models::{AppError, AppResult};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Truncated MAC length; enough to make guessing a valid link impractical
+const SIGNATURE_LEN: usize = 16;
+const HREF: &str = "href=\"";
+
+/// Builds signed open-pixel and click-redirect URLs for outgoing email.
+///
+/// Every URL carries a MAC over the notification id (and link target), so
+/// the ingest endpoints neither record forged engagement nor act as an open
+/// redirect for arbitrary targets.
+pub struct EmailTracker {
+    base_url: String,
+    secret: Vec<u8>,
+}
+
+impl EmailTracker {
+    /// Tracking is enabled by configuring a public base URL; it then requires a secret
+    pub fn from_config(config: &NotificationConfig) -> AppResult<Option<Self>> {
+        let Some(base_url) = &config.tracking_base_url else {
+            return Ok(None);
+        };
+        let secret = config.tracking_secret.as_ref().ok_or_else(|| {
+            AppError::Config("NOTIFICATION_TRACKING_SECRET is required when tracking is enabled".to_string())
+        })?;
+
+        Ok(Some(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            secret: secret.as_bytes().to_vec(),
+        }))
+    }
+
+    pub fn pixel_url(&self, notification_id: Uuid) -> String {
+        let signature = self.sign(&open_payload(notification_id));
+        format!("{}/t/open/{}/{}", self.base_url, notification_id, signature)
+    }
+
+    pub fn link_url(&self, notification_id: Uuid, target: &str) -> String {
+        let signature = self.sign(&click_payload(notification_id, target));
+        format!(
+            "{}/t/click/{}/{}/{}",
+            self.base_url,
+            notification_id,
+            URL_SAFE_NO_PAD.encode(target),
+            signature
+        )
+    }
+
+    /// Route every absolute link in `html` through the click redirect and append the open pixel
+    pub fn instrument(&self, notification_id: Uuid, html: &str) -> String {
+        let mut out = String::with_capacity(html.len() + 512);
+        let mut rest = html;
+
+        while let Some(start) = rest.find(HREF) {
+            let (before, after) = rest.split_at(start + HREF.len());
+            out.push_str(before);
+            let Some(end) = after.find('"') else {
+                rest = after;
+                break;
+            };
+            let target = &after[..end];
+            if target.starts_with("https://") || target.starts_with("http://") {
+                out.push_str(&self.link_url(notification_id, &target.replace("&amp;", "&")));
+            } else {
+                out.push_str(target);
+            }
+            rest = &after[end..];
+        }
+        out.push_str(rest);
+
+        let pixel = format!(
+            "<img src=\"{}\" width=\"1\" height=\"1\" alt=\"\" style=\"display:none\">",
+            self.pixel_url(notification_id)
+        );
+        // ASCII lowercasing keeps byte offsets, so the index is valid in `out`
+        match out.to_ascii_lowercase().rfind("</body>") {
+            Some(index) => out.insert_str(index, &pixel),
+            None => out.push_str(&pixel),
+        }
+        out
+    }
+
+    pub fn verify_open(&self, notification_id: Uuid, signature: &str) -> bool {
+        self.verify(&open_payload(notification_id), signature)
+    }
+
+    /// Decode and authenticate a click link's target
+    pub fn verify_click(&self, notification_id: Uuid, encoded_target: &str, signature: &str) -> Option<String> {
+        let target = String::from_utf8(URL_SAFE_NO_PAD.decode(encoded_target).ok()?).ok()?;
+        self.verify(&click_payload(notification_id, &target), signature)
+            .then_some(target)
+    }
+
+    fn sign(&self, payload: &str) -> String {
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+        URL_SAFE_NO_PAD.encode(&mac.finalize().into_bytes()[..SIGNATURE_LEN])
+    }
+
+    fn verify(&self, payload: &str, signature: &str) -> bool {
+        let Ok(signature) = URL_SAFE_NO_PAD.decode(signature) else {
+            return false;
+        };
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+        signature.len() == SIGNATURE_LEN && mac.verify_truncated_left(&signature).is_ok()
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length")
+    }
+}
+
+fn open_payload(notification_id: Uuid) -> String {
+    format!("open:{}", notification_id)
+}
+
+fn click_payload(notification_id: Uuid, target: &str) -> String {
+    format!("click:{}:{}", notification_id, target)
+}
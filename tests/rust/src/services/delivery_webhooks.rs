@@ -0,0 +1,219 @@
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::notification_service::NotificationService;
+use super::read_only::ReadOnlyMode;
+use crate::config::NotificationConfig;
+use crate::models::{AppError, AppResult, CallbackFilters, CallbackOutcome, DeliveryEvent, ProviderCallback};
+use crate::repositories::ProviderCallbackRepository;
+use crate::utils::Logger;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header carrying the base64 HMAC-SHA256 of the raw body under the provider's secret
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+
+/// Headers stored with a callback; anything else, credentials included, is dropped
+const KEPT_HEADERS: [&str; 4] = ["content-type", "user-agent", "x-request-id", SIGNATURE_HEADER];
+
+const MAX_CALLBACK_PAGE: i64 = 200;
+
+/// Where delivery status callbacks from email providers go
+#[async_trait]
+pub trait ProviderCallbacks: Send + Sync {
+    /// Store a callback and, when its signature verifies, apply it
+    async fn receive(&self, provider: &str, headers: &HeaderMap, body: &[u8]) -> AppResult<()>;
+
+    /// Run a stored callback through the status pipeline again
+    async fn replay(&self, id: Uuid, force: bool) -> AppResult<ProviderCallback>;
+
+    async fn get(&self, id: Uuid) -> AppResult<ProviderCallback>;
+
+    async fn list(&self, filters: &CallbackFilters) -> AppResult<Vec<ProviderCallback>>;
+}
+
+/// Stands in for delivery webhooks when they are switched off; callbacks
+/// are acknowledged so providers stop retrying, and nothing is stored
+pub struct NoopProviderCallbacks;
+
+#[async_trait]
+impl ProviderCallbacks for NoopProviderCallbacks {
+    async fn receive(&self, _provider: &str, _headers: &HeaderMap, _body: &[u8]) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn replay(&self, id: Uuid, _force: bool) -> AppResult<ProviderCallback> {
+        self.get(id).await
+    }
+
+    async fn get(&self, id: Uuid) -> AppResult<ProviderCallback> {
+        Err(AppError::NotFound(format!("Provider callback {} not found", id)))
+    }
+
+    async fn list(&self, _filters: &CallbackFilters) -> AppResult<Vec<ProviderCallback>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Receives delivery status callbacks from email providers.
+///
+/// Every callback is stored as it arrived before anything else happens,
+/// so a payload the pipeline choked on can be looked at and replayed once
+/// the problem is fixed. Callbacks whose signature does not verify are
+/// stored flagged and are not applied; an administrator may replay them
+/// after correcting a provider's secret, or force them through.
+pub struct DeliveryWebhooks {
+    callbacks: Arc<ProviderCallbackRepository>,
+    notifications: Arc<NotificationService>,
+    secrets: HashMap<String, Vec<u8>>,
+    read_only: Arc<ReadOnlyMode>,
+    logger: Arc<Logger>,
+}
+
+impl DeliveryWebhooks {
+    pub fn new(
+        config: &NotificationConfig,
+        callbacks: Arc<ProviderCallbackRepository>,
+        notifications: Arc<NotificationService>,
+        read_only: Arc<ReadOnlyMode>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        let secrets = config
+            .webhook_secrets
+            .iter()
+            .map(|(provider, secret)| (provider.clone(), secret.as_bytes().to_vec()))
+            .collect();
+        Self {
+            callbacks,
+            notifications,
+            secrets,
+            read_only,
+            logger,
+        }
+    }
+
+    async fn process(&self, mut callback: ProviderCallback, replayed: bool) -> AppResult<ProviderCallback> {
+        let event: Result<DeliveryEvent, _> = serde_json::from_str(&callback.payload);
+        match event {
+            Ok(event) => {
+                callback.notification_id = Some(event.notification_id);
+                match self.notifications.apply_delivery_event(&event).await {
+                    Ok(true) => {
+                        callback.outcome = CallbackOutcome::Applied;
+                        callback.error = None;
+                    }
+                    Ok(false) => {
+                        callback.outcome = CallbackOutcome::Ignored;
+                        callback.error = None;
+                    }
+                    Err(e) => {
+                        callback.outcome = CallbackOutcome::Failed;
+                        callback.error = Some(e.to_string());
+                    }
+                }
+            }
+            Err(e) => {
+                callback.outcome = CallbackOutcome::Failed;
+                callback.error = Some(format!("Unreadable payload: {}", e));
+            }
+        }
+        if let Some(error) = &callback.error {
+            self.logger
+                .warn(&format!("Callback {} from {} failed: {}", callback.id, callback.provider, error));
+        }
+        self.callbacks.record_outcome(&callback, replayed).await
+    }
+
+    /// Unknown providers have no secret, so nothing they send verifies
+    fn verify(&self, provider: &str, body: &[u8], signature: Option<&str>) -> bool {
+        let (Some(secret), Some(signature)) = (self.secrets.get(provider), signature) else {
+            return false;
+        };
+        let Ok(signature) = STANDARD.decode(signature.trim().trim_start_matches("sha256=")) else {
+            return false;
+        };
+        let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+        mac.update(body);
+        mac.verify_slice(&signature).is_ok()
+    }
+}
+
+#[async_trait]
+impl ProviderCallbacks for DeliveryWebhooks {
+    async fn receive(&self, provider: &str, headers: &HeaderMap, body: &[u8]) -> AppResult<()> {
+        self.read_only.check()?;
+        let signature = headers.get(SIGNATURE_HEADER).and_then(|value| value.to_str().ok());
+        let signature_valid = self.verify(provider, body, signature);
+        let callback = ProviderCallback::new(
+            provider.to_string(),
+            kept_headers(headers),
+            String::from_utf8_lossy(body).into_owned(),
+            signature_valid,
+        );
+        self.callbacks.create(&callback).await?;
+
+        if !signature_valid {
+            self.logger.warn(&format!(
+                "Callback {} from {} failed signature verification; stored without applying it",
+                callback.id, provider
+            ));
+            return Err(AppError::Unauthorized("Invalid webhook signature".to_string()));
+        }
+        self.process(callback, false).await?;
+        Ok(())
+    }
+
+    /// The signature is checked against the current secret, so fixing a
+    /// misconfigured secret makes earlier callbacks replayable; `force`
+    /// applies one regardless.
+    async fn replay(&self, id: Uuid, force: bool) -> AppResult<ProviderCallback> {
+        self.read_only.check()?;
+        let mut callback = self.get(id).await?;
+        let signature = callback.headers.get(SIGNATURE_HEADER).and_then(|value| value.as_str());
+        callback.signature_valid = self.verify(&callback.provider, callback.payload.as_bytes(), signature);
+        if !callback.signature_valid && !force {
+            return Err(AppError::Validation(vec![format!(
+                "Callback {} still fails signature verification; replay with force to apply it anyway",
+                id
+            )]));
+        }
+        self.logger.info(&format!("Replaying callback {} from {}", id, callback.provider));
+        self.process(callback, true).await
+    }
+
+    async fn get(&self, id: Uuid) -> AppResult<ProviderCallback> {
+        self.callbacks
+            .find(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Provider callback {} not found", id)))
+    }
+
+    async fn list(&self, filters: &CallbackFilters) -> AppResult<Vec<ProviderCallback>> {
+        if filters.limit.is_some_and(|limit| !(1..=MAX_CALLBACK_PAGE).contains(&limit)) {
+            return Err(AppError::Validation(vec![format!(
+                "limit must be between 1 and {}",
+                MAX_CALLBACK_PAGE
+            )]));
+        }
+        self.callbacks.list(filters).await
+    }
+}
+
+fn kept_headers(headers: &HeaderMap) -> serde_json::Value {
+    let kept: serde_json::Map<String, serde_json::Value> = KEPT_HEADERS
+        .iter()
+        .filter_map(|name| {
+            let value = headers.get(*name)?.to_str().ok()?;
+            Some((name.to_string(), json!(value)))
+        })
+        .collect();
+    serde_json::Value::Object(kept)
+}
@@ -0,0 +1,26 @@
+use tokio::sync::broadcast;
+
+use crate::models::UserEvent;
+
+/// In-process fan-out of user events to projections and other subscribers
+pub struct EventBus {
+    sender: broadcast::Sender<UserEvent>,
+}
+
+impl EventBus {
+    /// Create a bus buffering up to `capacity` events per slow subscriber
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Publish an event; it is dropped silently when nobody is subscribed
+    pub fn publish(&self, event: UserEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribe to all events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<UserEvent> {
+        self.sender.subscribe()
+    }
+}
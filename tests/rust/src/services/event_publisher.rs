@@ -0,0 +1,138 @@
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::event_bus::EventBus;
+use crate::config::OutboxConfig;
+use crate::models::{AppError, AppResult, OutboxMessage, UserEvent, UserEventPayload};
+use crate::repositories::UserRepository;
+
+/// Destination for messages relayed out of the transactional outbox
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    /// Publish a message; must be safe to call again with the same message
+    async fn publish(&self, message: &OutboxMessage) -> AppResult<()>;
+}
+
+/// Publishes outbox messages to Kafka with an idempotent producer.
+///
+/// The outbox idempotency key is used as the record key and
+/// `idempotency-key` header so consumers can drop redeliveries that
+/// happen when the relay crashes between publishing and marking a row.
+pub struct KafkaPublisher {
+    producer: FutureProducer,
+    topic: String,
+    timeout: Duration,
+}
+
+impl KafkaPublisher {
+    pub fn new(config: &OutboxConfig) -> AppResult<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", &config.kafka_brokers)
+            .set("enable.idempotence", "true")
+            .set("acks", "all")
+            .set("message.timeout.ms", config.publish_timeout.as_millis().to_string())
+            .create()
+            .map_err(|e| AppError::Config(format!("Invalid Kafka producer settings: {}", e)))?;
+
+        Ok(Self {
+            producer,
+            topic: config.kafka_topic.clone(),
+            timeout: config.publish_timeout,
+        })
+    }
+}
+
+#[async_trait]
+impl EventPublisher for KafkaPublisher {
+    async fn publish(&self, message: &OutboxMessage) -> AppResult<()> {
+        let key = message.idempotency_key.to_string();
+        let payload = message.payload.to_string();
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: "idempotency-key",
+                value: Some(key.as_str()),
+            })
+            .insert(Header {
+                key: "event-type",
+                value: Some(message.event_type.as_str()),
+            });
+
+        let record = FutureRecord::to(&self.topic)
+            .key(&key)
+            .payload(&payload)
+            .headers(headers);
+
+        self.producer
+            .send(record, self.timeout)
+            .await
+            .map(|_| ())
+            .map_err(|(e, _)| AppError::Internal(format!("Kafka publish failed: {}", e)))
+    }
+}
+
+/// Publishes outbox messages to several destinations in order.
+///
+/// A failure stops at that destination, and the relay retries the message
+/// on every one of them, which each tolerates by the `EventPublisher` contract.
+pub struct FanOutPublisher {
+    publishers: Vec<Arc<dyn EventPublisher>>,
+}
+
+impl FanOutPublisher {
+    pub fn new(publishers: Vec<Arc<dyn EventPublisher>>) -> Self {
+        Self { publishers }
+    }
+}
+
+#[async_trait]
+impl EventPublisher for FanOutPublisher {
+    async fn publish(&self, message: &OutboxMessage) -> AppResult<()> {
+        for publisher in &self.publishers {
+            publisher.publish(message).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Publishes outbox messages to the in-process event bus.
+///
+/// Messages name the user without their personal data, so created and
+/// updated users are read back from storage before subscribers see them.
+/// A user gone by then is skipped; the deletion follows in its own message.
+pub struct EventBusPublisher {
+    event_bus: Arc<EventBus>,
+    users: Arc<dyn UserRepository>,
+}
+
+impl EventBusPublisher {
+    pub fn new(event_bus: Arc<EventBus>, users: Arc<dyn UserRepository>) -> Self {
+        Self { event_bus, users }
+    }
+}
+
+#[async_trait]
+impl EventPublisher for EventBusPublisher {
+    async fn publish(&self, message: &OutboxMessage) -> AppResult<()> {
+        let payload: UserEventPayload = serde_json::from_value(message.payload.clone()).map_err(|e| {
+            AppError::Internal(format!("Outbox message {} is not a user event: {}", message.id, e))
+        })?;
+        let event = match payload {
+            UserEventPayload::Created { user_id, .. } => match self.users.find_by_id(user_id).await? {
+                Some(user) => UserEvent::Created { user },
+                None => return Ok(()),
+            },
+            UserEventPayload::Updated { user_id, .. } => match self.users.find_by_id(user_id).await? {
+                Some(user) => UserEvent::Updated { user },
+                None => return Ok(()),
+            },
+            UserEventPayload::LoggedIn { user_id, at } => UserEvent::LoggedIn { user_id, at },
+            UserEventPayload::Deleted { user_id, at } => UserEvent::Deleted { user_id, at },
+        };
+        self.event_bus.publish(event);
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::notification_service::NotificationService;
+use super::policy_engine::PolicyEngine;
+use super::read_only::ReadOnlyMode;
+use super::report_service::{ReportKind, ReportService};
+use super::storage_service::StorageService;
+use super::user_service::UserService;
+use crate::config::ExportConfig;
+use crate::models::{
+    AppError, AppResult, ExportJob, ExportJobView, ExportKind, ExportStatus, TenantContext, User,
+};
+use crate::repositories::ExportJobRepository;
+use crate::utils::{Logger, Metrics, UrlSigner};
+
+const LIST_LIMIT: i64 = 50;
+
+/// A generated export file
+struct Artifact {
+    filename: String,
+    content_type: String,
+    content: Vec<u8>,
+}
+
+/// Runs exports as background jobs instead of inside the request.
+///
+/// A request only queues a job; a runner on any instance claims it,
+/// writes the file to storage and emails the requester a signed download
+/// link valid until the file expires. Expired files are deleted and their
+/// jobs kept as a record.
+pub struct ExportService {
+    repository: Arc<ExportJobRepository>,
+    storage: Arc<StorageService>,
+    users: Arc<UserService>,
+    reports: Arc<ReportService>,
+    notifications: Arc<NotificationService>,
+    policies: Arc<PolicyEngine>,
+    signer: Arc<UrlSigner>,
+    read_only: Arc<ReadOnlyMode>,
+    config: ExportConfig,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl ExportService {
+    pub fn new(
+        repository: Arc<ExportJobRepository>,
+        storage: Arc<StorageService>,
+        users: Arc<UserService>,
+        reports: Arc<ReportService>,
+        notifications: Arc<NotificationService>,
+        policies: Arc<PolicyEngine>,
+        signer: Arc<UrlSigner>,
+        read_only: Arc<ReadOnlyMode>,
+        config: ExportConfig,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        Self {
+            repository,
+            storage,
+            users,
+            reports,
+            notifications,
+            policies,
+            signer,
+            read_only,
+            config,
+            metrics,
+            logger,
+        }
+    }
+
+    pub fn config(&self) -> &ExportConfig {
+        &self.config
+    }
+
+    /// Queue an export; anyone may export their own data, reports need `reports:export`
+    pub async fn request(&self, tenant: &TenantContext, actor: &User, kind: ExportKind) -> AppResult<ExportJob> {
+        self.read_only.check()?;
+        if report_kind(kind).is_some() {
+            self.policies.require(actor, "reports:export", None)?;
+        }
+        let pending = self.repository.count_pending(actor.id).await?;
+        if pending >= self.config.max_pending_per_user {
+            return Err(AppError::Conflict(format!(
+                "{} exports are already in progress; wait for one to finish",
+                pending
+            )));
+        }
+
+        let job = ExportJob::new(actor.id, &tenant.tenant_id, kind);
+        self.repository.create(&job).await?;
+        self.metrics
+            .increment_labeled_counter("exports.requested", &[("kind", kind.as_str())])
+            .await?;
+        self.logger
+            .info(&format!("Queued {} export {} for user {}", kind.as_str(), job.id, actor.id));
+        Ok(job)
+    }
+
+    pub async fn list(&self, actor: &User) -> AppResult<Vec<ExportJobView>> {
+        let jobs = self.repository.for_user(actor.id, LIST_LIMIT).await?;
+        Ok(jobs.into_iter().map(|job| self.view(job)).collect())
+    }
+
+    /// Only the requester sees a job; anyone else is told it does not exist
+    pub async fn get(&self, actor: &User, id: Uuid) -> AppResult<ExportJobView> {
+        match self.repository.find(id).await? {
+            Some(job) if job.requested_by == actor.id => Ok(self.view(job)),
+            _ => Err(not_found(id)),
+        }
+    }
+
+    /// The file of a job reached through its signed link
+    pub async fn download(&self, id: Uuid) -> AppResult<(ExportJob, Vec<u8>)> {
+        let job = self.repository.find(id).await?.ok_or_else(|| not_found(id))?;
+        let downloadable = job.status == ExportStatus::Completed && job.expires_at.is_some_and(|at| at > Utc::now());
+        let Some(key) = job.artifact_key.as_deref().filter(|_| downloadable) else {
+            return Err(AppError::NotFound(format!("Export {} is not available for download", id)));
+        };
+        let content = self
+            .storage
+            .get(key)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Export {} is not available for download", id)))?;
+        self.metrics
+            .increment_labeled_counter("exports.downloaded", &[("kind", job.kind.as_str())])
+            .await?;
+        Ok((job, content))
+    }
+
+    /// Claim and run one queued export, returning it, or None when the queue is empty
+    pub async fn run_next(&self) -> AppResult<Option<ExportJob>> {
+        // Queued exports wait for the end of maintenance rather than fail every run
+        if self.read_only.is_read_only() {
+            return Ok(None);
+        }
+        let stale = chrono::Duration::from_std(self.config.stale_after)
+            .map_err(|e| AppError::Config(format!("Invalid export stale timeout: {}", e)))?;
+        let stale_before = Utc::now() - stale;
+        let abandoned = self.repository.abandon_stale(stale_before, self.config.max_attempts).await?;
+        if abandoned > 0 {
+            self.metrics.add_to_counter("exports.failed", abandoned).await?;
+            self.logger
+                .warn(&format!("Gave up on {} exports interrupted too many times", abandoned));
+        }
+
+        let Some(mut job) = self.repository.claim_next(stale_before, self.config.max_attempts).await? else {
+            return Ok(None);
+        };
+        match self.generate(&job).await {
+            Ok(artifact) => {
+                self.finish(&mut job, artifact).await?;
+                self.notify(&job).await;
+            }
+            Err(e) => {
+                self.logger
+                    .error(&format!("Export {} of kind {} failed: {}", job.id, job.kind.as_str(), e));
+                self.repository.fail(job.id, &e.to_string()).await?;
+                self.metrics
+                    .increment_labeled_counter("exports.failed", &[("kind", job.kind.as_str())])
+                    .await?;
+                job.status = ExportStatus::Failed;
+                job.error = Some(e.to_string());
+            }
+        }
+        Ok(Some(job))
+    }
+
+    /// Delete the files of exports past their retention, returning how many
+    pub async fn expire_artifacts(&self, limit: i64) -> AppResult<usize> {
+        if self.read_only.is_read_only() {
+            return Ok(0);
+        }
+        let jobs = self.repository.expired(limit).await?;
+        for job in &jobs {
+            if let Some(key) = &job.artifact_key {
+                self.storage.delete(key).await?;
+            }
+            self.repository.mark_expired(job.id).await?;
+        }
+        if !jobs.is_empty() {
+            self.metrics.add_to_counter("exports.expired", jobs.len() as u64).await?;
+        }
+        Ok(jobs.len())
+    }
+
+    async fn generate(&self, job: &ExportJob) -> AppResult<Artifact> {
+        match report_kind(job.kind) {
+            Some(kind) => {
+                let report = self.reports.generate(kind).await?;
+                let attachment = self.reports.render(&report)?;
+                Ok(Artifact {
+                    filename: attachment.filename,
+                    content_type: attachment.content_type,
+                    content: attachment.content,
+                })
+            }
+            None => {
+                let export = self.users.export_user_data(job.requested_by).await?;
+                let content = serde_json::to_vec_pretty(&export)
+                    .map_err(|e| AppError::Internal(format!("Unserializable user data export: {}", e)))?;
+                Ok(Artifact {
+                    filename: format!("user_data_{}.json", Utc::now().format("%Y-%m-%d")),
+                    content_type: "application/json".to_string(),
+                    content,
+                })
+            }
+        }
+    }
+
+    async fn finish(&self, job: &mut ExportJob, artifact: Artifact) -> AppResult<()> {
+        let retention = chrono::Duration::from_std(self.config.retention)
+            .map_err(|e| AppError::Config(format!("Invalid export retention: {}", e)))?;
+        let key = format!("exports/{}/{}", job.id, artifact.filename);
+        self.storage.put(&key, &artifact.content).await?;
+
+        let now = Utc::now();
+        job.status = ExportStatus::Completed;
+        job.artifact_key = Some(key);
+        job.size_bytes = Some(artifact.content.len() as i64);
+        job.filename = Some(artifact.filename);
+        job.content_type = Some(artifact.content_type);
+        job.completed_at = Some(now);
+        job.expires_at = Some(now + retention);
+        self.repository.complete(job).await?;
+
+        self.metrics
+            .increment_labeled_counter("exports.completed", &[("kind", job.kind.as_str())])
+            .await?;
+        self.logger.info(&format!(
+            "Export {} finished with {} bytes",
+            job.id,
+            job.size_bytes.unwrap_or_default()
+        ));
+        Ok(())
+    }
+
+    /// The export stays downloadable from the API when the email cannot be sent
+    async fn notify(&self, job: &ExportJob) {
+        let Some(link) = self.download_url(job) else {
+            return;
+        };
+        let sent = async {
+            let tenant = TenantContext::new(&job.tenant_id)?;
+            let user = self
+                .users
+                .get_user_by_id(job.requested_by)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("User {} not found", job.requested_by)))?;
+            let name = job.kind.as_str().replace('_', " ");
+            self.notifications
+                .send_export_ready(&tenant, &user, &name, &link, self.config.retention)
+                .await
+        };
+        if let Err(e) = sent.await {
+            self.logger
+                .warn(&format!("Could not notify user {} of export {}: {}", job.requested_by, job.id, e));
+        }
+    }
+
+    /// Signed link to a completed export, valid until it expires
+    fn download_url(&self, job: &ExportJob) -> Option<String> {
+        let expires_at = job.expires_at.filter(|_| job.status == ExportStatus::Completed)?;
+        Some(self.signer.sign_until(&format!("/exports/{}/download", job.id), expires_at))
+    }
+
+    fn view(&self, job: ExportJob) -> ExportJobView {
+        let download_url = self.download_url(&job).filter(|_| job.expires_at.is_some_and(|at| at > Utc::now()));
+        ExportJobView { job, download_url }
+    }
+}
+
+fn report_kind(kind: ExportKind) -> Option<ReportKind> {
+    match kind {
+        ExportKind::UserData => None,
+        ExportKind::NewSignups => Some(ReportKind::NewSignups),
+        ExportKind::NotificationDelivery => Some(ReportKind::NotificationDelivery),
+        ExportKind::LockedAccounts => Some(ReportKind::LockedAccounts),
+    }
+}
+
+fn not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Export {} not found", id))
+}
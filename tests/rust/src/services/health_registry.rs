@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use super::cache_service::CacheService;
+use crate::database::Database;
+use crate::models::AppResult;
+
+/// A synthetic check of one component; an error says what is wrong with it
+#[async_trait]
+pub trait HealthProbe: Send + Sync {
+    async fn probe(&self) -> AppResult<()>;
+}
+
+#[async_trait]
+impl HealthProbe for Database {
+    async fn probe(&self) -> AppResult<()> {
+        self.ping().await
+    }
+}
+
+#[async_trait]
+impl HealthProbe for CacheService {
+    async fn probe(&self) -> AppResult<()> {
+        self.health_check().await
+    }
+}
+
+/// The components the status service probes, in the order the status page lists them.
+///
+/// Components register at startup; registering a name again replaces its probe.
+#[derive(Default)]
+pub struct HealthRegistry {
+    probes: Vec<(String, Arc<dyn HealthProbe>)>,
+}
+
+impl HealthRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, component: &str, probe: Arc<dyn HealthProbe>) -> &mut Self {
+        match self.probes.iter_mut().find(|(name, _)| name == component) {
+            Some((_, existing)) => *existing = probe,
+            None => self.probes.push((component.to_string(), probe)),
+        }
+        self
+    }
+
+    pub fn components(&self) -> impl Iterator<Item = &str> {
+        self.probes.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn probes(&self) -> &[(String, Arc<dyn HealthProbe>)] {
+        &self.probes
+    }
+}
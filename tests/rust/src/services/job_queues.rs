@@ -0,0 +1,304 @@
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::cache_service::CacheService;
+use super::read_only::ReadOnlyMode;
+use crate::config::ExportConfig;
+use crate::models::{
+    AppError, AppResult, ExportJob, ExportStatus, JobFilters, JobQueue, JobQueueSummary, JobState, OutboxMessage,
+    QueuePause, QueueRun, QueuedJob,
+};
+use crate::models::job_queue::MAX_JOB_PAGE;
+use crate::repositories::{ExportJobRepository, OutboxRepository};
+use crate::utils::Logger;
+
+const DEFAULT_JOB_PAGE: i64 = 50;
+/// Run records outlive a few missed runs, then the queue shows as not heard from
+const RUN_RECORD_INTERVALS: u32 = 10;
+
+/// Operator view of the background work queues.
+///
+/// Jobs are read straight from the tables the runners claim them from.
+/// Pausing a queue is a flag in the cache, so every instance's runners
+/// see it on their next run and leave the queue alone until it is
+/// resumed; work already claimed finishes. Runners record each run, which
+/// is where next-run times come from. Outbox messages are listed from the
+/// primary region's outbox; pausing it holds every region's relay.
+pub struct JobQueues {
+    exports: Arc<ExportJobRepository>,
+    outbox: Arc<OutboxRepository>,
+    cache: Arc<CacheService>,
+    read_only: Arc<ReadOnlyMode>,
+    export_config: ExportConfig,
+    logger: Arc<Logger>,
+}
+
+impl JobQueues {
+    pub fn new(
+        exports: Arc<ExportJobRepository>,
+        outbox: Arc<OutboxRepository>,
+        cache: Arc<CacheService>,
+        read_only: Arc<ReadOnlyMode>,
+        export_config: &ExportConfig,
+        logger: Arc<Logger>,
+    ) -> Self {
+        Self {
+            exports,
+            outbox,
+            cache,
+            read_only,
+            export_config: export_config.clone(),
+            logger,
+        }
+    }
+
+    pub async fn summaries(&self) -> AppResult<Vec<JobQueueSummary>> {
+        let mut summaries = Vec::with_capacity(JobQueue::ALL.len());
+        for queue in JobQueue::ALL {
+            summaries.push(self.summary(queue).await?);
+        }
+        Ok(summaries)
+    }
+
+    pub async fn summary(&self, queue: JobQueue) -> AppResult<JobQueueSummary> {
+        let (queued, running, failed, oldest_queued_at) = match queue {
+            JobQueue::Exports => self.exports.queue_counts().await?,
+            JobQueue::Outbox => {
+                let lag = self.outbox.lag().await?;
+                let (untried, failed) = self.outbox.failure_counts().await?;
+                // Messages are claimed inside the relay's transaction, so none is ever seen running
+                (untried, 0, failed, lag.oldest_pending_at)
+            }
+        };
+        Ok(JobQueueSummary {
+            queue,
+            queued,
+            running,
+            failed,
+            oldest_queued_at,
+            paused: self.pause(queue).await?,
+            last_run: self.last_run(queue).await?,
+        })
+    }
+
+    /// Jobs of a queue in the state asked for, or all that are not done, head of the queue first
+    pub async fn list(&self, queue: JobQueue, filters: &JobFilters) -> AppResult<Vec<QueuedJob>> {
+        let limit = filters.limit.unwrap_or(DEFAULT_JOB_PAGE);
+        if !(1..=MAX_JOB_PAGE).contains(&limit) {
+            return Err(AppError::Validation(vec![format!(
+                "limit must be between 1 and {}",
+                MAX_JOB_PAGE
+            )]));
+        }
+        let next_run = self.next_run(queue).await?;
+        match queue {
+            JobQueue::Exports => {
+                let statuses = match filters.state {
+                    Some(JobState::Queued) => vec![ExportStatus::Pending],
+                    Some(JobState::Running) => vec![ExportStatus::Running],
+                    Some(JobState::Failed) => vec![ExportStatus::Failed],
+                    Some(JobState::Cancelled) => vec![ExportStatus::Cancelled],
+                    None => vec![ExportStatus::Pending, ExportStatus::Running, ExportStatus::Failed],
+                };
+                let jobs = self.exports.with_status(&statuses, limit).await?;
+                Ok(jobs.iter().map(|job| self.export_job(job, next_run)).collect())
+            }
+            JobQueue::Outbox => {
+                let failed = match filters.state {
+                    Some(JobState::Queued) => Some(false),
+                    Some(JobState::Failed) => Some(true),
+                    Some(JobState::Running | JobState::Cancelled) => return Ok(Vec::new()),
+                    None => None,
+                };
+                let messages = self.outbox.unpublished(failed, limit).await?;
+                Ok(messages.iter().map(|message| outbox_job(message, next_run)).collect())
+            }
+        }
+    }
+
+    /// Queue a failed export again with a fresh set of attempts
+    pub async fn retry(&self, queue: JobQueue, id: &str, actor: Uuid) -> AppResult<QueuedJob> {
+        self.read_only.check()?;
+        let id = match queue {
+            JobQueue::Exports => export_id(id)?,
+            JobQueue::Outbox => {
+                return Err(AppError::Conflict(
+                    "Outbox messages are retried on every relay run until published".to_string(),
+                ))
+            }
+        };
+        let Some(job) = self.exports.requeue_failed(id).await? else {
+            return Err(self.not_in_state(id, "failed").await);
+        };
+        self.logger
+            .info(&format!("Export {} queued again by {}", id, actor));
+        Ok(self.export_job(&job, self.next_run(queue).await?))
+    }
+
+    /// Take an export off the queue before it runs
+    pub async fn cancel(&self, queue: JobQueue, id: &str, actor: Uuid) -> AppResult<QueuedJob> {
+        self.read_only.check()?;
+        let id = match queue {
+            JobQueue::Exports => export_id(id)?,
+            // Consumers rely on every event arriving, in order
+            JobQueue::Outbox => {
+                return Err(AppError::Conflict("Outbox messages cannot be cancelled".to_string()))
+            }
+        };
+        let Some(job) = self.exports.cancel_pending(id).await? else {
+            return Err(self.not_in_state(id, "queued").await);
+        };
+        self.logger
+            .warn(&format!("Export {} cancelled by {}", id, actor));
+        Ok(self.export_job(&job, None))
+    }
+
+    /// Hold a queue's runners on every instance; pausing a paused queue keeps the original pause
+    pub async fn pause_queue(&self, queue: JobQueue, reason: &str, actor: Uuid) -> AppResult<JobQueueSummary> {
+        if self.pause(queue).await?.is_none() {
+            let pause = QueuePause {
+                reason: reason.to_string(),
+                since: Utc::now(),
+                paused_by: actor,
+            };
+            self.cache.set(&pause_key(queue), &pause, None).await?;
+            self.logger
+                .warn(&format!("Job queue {} paused by {}: {}", queue.as_str(), actor, reason));
+        }
+        self.summary(queue).await
+    }
+
+    pub async fn resume_queue(&self, queue: JobQueue, actor: Uuid) -> AppResult<JobQueueSummary> {
+        if self.pause(queue).await?.is_some() {
+            self.cache.delete(&pause_key(queue)).await?;
+            self.logger
+                .warn(&format!("Job queue {} resumed by {}", queue.as_str(), actor));
+        }
+        self.summary(queue).await
+    }
+
+    /// Whether runners should leave the queue alone; a cache failure lets them run rather than stall the queue
+    pub async fn is_paused(&self, queue: JobQueue) -> bool {
+        match self.pause(queue).await {
+            Ok(pause) => pause.is_some(),
+            Err(e) => {
+                self.logger
+                    .warn(&format!("Failed to read pause of job queue {}: {}", queue.as_str(), e));
+                false
+            }
+        }
+    }
+
+    /// Note that a runner of the queue ran and is due again after `interval`
+    pub async fn record_run(&self, queue: JobQueue, interval: Duration) {
+        let now = Utc::now();
+        let run = QueueRun {
+            last_run_at: now,
+            next_run_at: now + chrono::Duration::from_std(interval).unwrap_or_else(|_| chrono::Duration::zero()),
+        };
+        let ttl = (interval * RUN_RECORD_INTERVALS).max(Duration::from_secs(60));
+        if let Err(e) = self.cache.set(&run_key(queue), &run, Some(ttl)).await {
+            self.logger
+                .warn(&format!("Failed to record run of job queue {}: {}", queue.as_str(), e));
+        }
+    }
+
+    async fn pause(&self, queue: JobQueue) -> AppResult<Option<QueuePause>> {
+        self.cache.get(&pause_key(queue)).await
+    }
+
+    async fn last_run(&self, queue: JobQueue) -> AppResult<Option<QueueRun>> {
+        self.cache.get(&run_key(queue)).await
+    }
+
+    /// When waiting jobs of the queue are next picked up; None while paused
+    async fn next_run(&self, queue: JobQueue) -> AppResult<Option<DateTime<Utc>>> {
+        if self.pause(queue).await?.is_some() {
+            return Ok(None);
+        }
+        Ok(self.last_run(queue).await?.map(|run| run.next_run_at))
+    }
+
+    fn export_job(&self, job: &ExportJob, next_run: Option<DateTime<Utc>>) -> QueuedJob {
+        let state = match job.status {
+            ExportStatus::Running => JobState::Running,
+            ExportStatus::Failed => JobState::Failed,
+            ExportStatus::Cancelled => JobState::Cancelled,
+            _ => JobState::Queued,
+        };
+        let next_run_at = match job.status {
+            ExportStatus::Pending => next_run,
+            // A runner that died with its instance leaves the job to be claimed once stale
+            ExportStatus::Running if job.attempts < self.export_config.max_attempts => job
+                .started_at
+                .zip(chrono::Duration::from_std(self.export_config.stale_after).ok())
+                .map(|(started, stale)| started + stale),
+            _ => None,
+        };
+        QueuedJob {
+            queue: JobQueue::Exports,
+            id: job.id.to_string(),
+            state,
+            summary: json!({
+                "kind": job.kind.as_str(),
+                "status": job.status.as_str(),
+                "requested_by": job.requested_by,
+                "tenant_id": job.tenant_id,
+            }),
+            attempts: job.attempts,
+            max_attempts: Some(self.export_config.max_attempts),
+            last_error: job.error.clone(),
+            created_at: job.created_at,
+            started_at: job.started_at,
+            next_run_at,
+        }
+    }
+
+    async fn not_in_state(&self, id: Uuid, state: &str) -> AppError {
+        match self.exports.find(id).await {
+            Ok(Some(_)) => AppError::Conflict(format!("Export {} is not {}", id, state)),
+            Ok(None) => AppError::NotFound(format!("Export {} not found", id)),
+            Err(e) => e,
+        }
+    }
+}
+
+/// Outbox payloads carry user data, so only what the event is about is shown
+fn outbox_job(message: &OutboxMessage, next_run: Option<DateTime<Utc>>) -> QueuedJob {
+    QueuedJob {
+        queue: JobQueue::Outbox,
+        id: message.id.to_string(),
+        state: if message.last_error.is_some() {
+            JobState::Failed
+        } else {
+            JobState::Queued
+        },
+        summary: json!({
+            "aggregate_type": message.aggregate_type,
+            "aggregate_id": message.aggregate_id,
+            "event_type": message.event_type,
+        }),
+        attempts: message.attempts,
+        max_attempts: None,
+        last_error: message.last_error.clone(),
+        created_at: message.created_at,
+        started_at: None,
+        next_run_at: next_run,
+    }
+}
+
+fn export_id(id: &str) -> AppResult<Uuid> {
+    id.parse()
+        .map_err(|_| AppError::Validation(vec![format!("Invalid export id: {}", id)]))
+}
+
+fn pause_key(queue: JobQueue) -> String {
+    format!("jobs:paused:{}", queue.as_str())
+}
+
+fn run_key(queue: JobQueue) -> String {
+    format!("jobs:last_run:{}", queue.as_str())
+}
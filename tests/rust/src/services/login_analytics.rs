@@ -0,0 +1,175 @@
+use chrono::{DateTime, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::cache_service::CacheService;
+use crate::config::LockoutPolicy;
+use crate::models::{AppResult, ClientInfo, LoginFailureReason};
+
+const FAILURE_STREAM: &str = "security:login_failures";
+/// Shown for failures whose client address is not known
+const UNKNOWN_ADDRESS: &str = "unknown";
+
+/// A refused sign-in as kept in the security event stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginFailure {
+    pub at: DateTime<Utc>,
+    /// The normalized email tried, whether or not an account has it
+    pub email: String,
+    pub user_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: String,
+    pub reason: LoginFailureReason,
+}
+
+/// A client address or account and how many failed sign-ins it had
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedSource {
+    pub key: String,
+    pub failures: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HourlyLoginFailures {
+    pub hour: DateTime<Utc>,
+    pub failures: u64,
+    pub lockouts: u64,
+}
+
+/// Failed sign-ins over the last `hours`, for spotting credential stuffing
+#[derive(Debug, Clone, Serialize)]
+pub struct LoginThrottlingReport {
+    pub hours: u32,
+    pub failures: u64,
+    pub lockouts: u64,
+    pub top_addresses: Vec<RankedSource>,
+    pub top_accounts: Vec<RankedSource>,
+    /// Oldest hour first
+    pub timeline: Vec<HourlyLoginFailures>,
+    /// Newest first
+    pub recent: Vec<LoginFailure>,
+}
+
+/// Failed sign-ins aggregated for operators.
+///
+/// Each failure is appended to a capped security event stream and counted
+/// into hourly buckets in the cache: a counter of failures and lockouts, and
+/// sorted sets of client addresses and targeted emails. Reports sum the
+/// buckets of the hours asked for, so nothing older than the retention is
+/// kept or read. Recording never fails a sign-in; callers log the error.
+pub struct LoginAnalytics {
+    cache: Arc<CacheService>,
+    retention: Duration,
+    stream_length: usize,
+}
+
+impl LoginAnalytics {
+    pub fn new(cache: Arc<CacheService>, policy: &LockoutPolicy) -> Self {
+        Self {
+            cache,
+            retention: policy.analytics_retention,
+            stream_length: policy.failure_stream_length,
+        }
+    }
+
+    pub async fn record_failure(
+        &self,
+        email: &str,
+        user_id: Option<Uuid>,
+        client: &ClientInfo,
+        reason: LoginFailureReason,
+    ) -> AppResult<()> {
+        let failure = LoginFailure {
+            at: Utc::now(),
+            email: email.to_string(),
+            user_id,
+            ip_address: client.ip_address.clone(),
+            user_agent: client.user_agent.clone(),
+            reason,
+        };
+        let hour = hour_of(failure.at);
+        let ttl = Some(self.bucket_ttl());
+        self.cache
+            .stream_add(FAILURE_STREAM, &failure, Some(self.stream_length))
+            .await?;
+        self.cache.increment(&bucket_key("count", hour), 1, ttl).await?;
+        let address = failure.ip_address.as_deref().unwrap_or(UNKNOWN_ADDRESS);
+        self.cache
+            .increment_score(&bucket_key("addresses", hour), address, 1.0, ttl)
+            .await?;
+        self.cache
+            .increment_score(&bucket_key("accounts", hour), &failure.email, 1.0, ttl)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn record_lockout(&self) -> AppResult<()> {
+        let hour = hour_of(Utc::now());
+        self.cache
+            .increment(&bucket_key("lockouts", hour), 1, Some(self.bucket_ttl()))
+            .await?;
+        Ok(())
+    }
+
+    /// Totals, the `limit` busiest addresses and accounts, and the hourly timeline, capped at the retention
+    pub async fn report(&self, hours: u32, limit: usize) -> AppResult<LoginThrottlingReport> {
+        let retained = (self.retention.as_secs() / 3600).max(1) as u32;
+        let hours = hours.clamp(1, retained);
+        let now = hour_of(Utc::now());
+        let buckets: Vec<DateTime<Utc>> = (0..hours as i64)
+            .rev()
+            .map(|back| now - chrono::Duration::hours(back))
+            .collect();
+
+        let mut timeline = Vec::with_capacity(buckets.len());
+        for hour in &buckets {
+            timeline.push(HourlyLoginFailures {
+                hour: *hour,
+                failures: self.cache.get_counter(&bucket_key("count", *hour)).await?.max(0) as u64,
+                lockouts: self.cache.get_counter(&bucket_key("lockouts", *hour)).await?.max(0) as u64,
+            });
+        }
+        let keys = |kind: &str| -> Vec<String> { buckets.iter().map(|hour| bucket_key(kind, *hour)).collect() };
+        let ranked = |top: Vec<(String, f64)>| -> Vec<RankedSource> {
+            top.into_iter()
+                .map(|(key, score)| RankedSource {
+                    key,
+                    failures: score.max(0.0) as u64,
+                })
+                .collect()
+        };
+        let since = buckets.first().copied().unwrap_or(now);
+        let recent = self
+            .cache
+            .stream_recent::<LoginFailure>(FAILURE_STREAM, limit)
+            .await?
+            .into_iter()
+            .filter(|failure| failure.at >= since)
+            .collect();
+
+        Ok(LoginThrottlingReport {
+            hours,
+            failures: timeline.iter().map(|hour| hour.failures).sum(),
+            lockouts: timeline.iter().map(|hour| hour.lockouts).sum(),
+            top_addresses: ranked(self.cache.top_scores(&keys("addresses"), limit).await?),
+            top_accounts: ranked(self.cache.top_scores(&keys("accounts"), limit).await?),
+            timeline,
+            recent,
+        })
+    }
+
+    /// Buckets outlive the retention by an hour so the oldest hour reported is still whole
+    fn bucket_ttl(&self) -> Duration {
+        self.retention + Duration::from_secs(3600)
+    }
+}
+
+fn hour_of(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.duration_trunc(chrono::Duration::hours(1)).unwrap_or(at)
+}
+
+fn bucket_key(kind: &str, hour: DateTime<Utc>) -> String {
+    format!("login_failures:{}:{}", kind, hour.format("%Y%m%d%H"))
+}
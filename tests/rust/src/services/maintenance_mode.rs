@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use super::cache_service::CacheService;
+use super::mode_switch::ModeSwitch;
+use crate::config::MaintenanceConfig;
+use crate::models::{AppError, AppResult};
+use crate::utils::{Logger, Metrics};
+
+const MAINTENANCE_KEY: &str = "maintenance:mode";
+
+/// Why and since when the application has been in maintenance mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceState {
+    pub reason: String,
+    pub since: DateTime<Utc>,
+    /// None when the mode was switched on by configuration
+    pub enabled_by: Option<Uuid>,
+    /// Whether reads are still served to everyone
+    pub allow_reads: bool,
+}
+
+/// A switch closing the API to everyone but admins, for deploys and
+/// incidents where even reads may be unsafe.
+///
+/// Unlike read-only mode it is enforced by the `maintenance` middleware
+/// layer rather than inside services, so admins can keep working to verify
+/// and repair. Like read-only mode it is a `ModeSwitch`, reaching every
+/// instance within the sync interval.
+pub struct MaintenanceMode {
+    switch: Arc<ModeSwitch<MaintenanceState>>,
+    logger: Arc<Logger>,
+}
+
+impl MaintenanceMode {
+    /// Start in maintenance mode when configured to, otherwise as the other instances are
+    pub async fn load(
+        cache: Arc<CacheService>,
+        config: &MaintenanceConfig,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> AppResult<Self> {
+        let mode = Self {
+            switch: Arc::new(ModeSwitch::new(
+                "maintenance mode",
+                MAINTENANCE_KEY,
+                "maintenance.enabled",
+                cache,
+                config.sync_interval,
+                metrics,
+                logger.clone(),
+            )),
+            logger,
+        };
+        if config.maintenance_mode {
+            mode.enable(&config.maintenance_reason, config.maintenance_allow_reads, None)
+                .await?;
+        } else {
+            mode.switch.refresh().await?;
+        }
+        Ok(mode)
+    }
+
+    /// Refuse a request from a caller that is not an admin; reads pass when the mode allows them
+    pub fn check(&self, is_read: bool) -> AppResult<()> {
+        match self.status() {
+            Some(state) if !(is_read && state.allow_reads) => Err(AppError::Maintenance(state.reason)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn status(&self) -> Option<MaintenanceState> {
+        self.switch.status()
+    }
+
+    /// Switch maintenance mode on everywhere, or change whether reads are served while it is on
+    pub async fn enable(&self, reason: &str, allow_reads: bool, actor: Option<Uuid>) -> AppResult<MaintenanceState> {
+        let state = MaintenanceState {
+            reason: reason.to_string(),
+            since: self.status().map_or_else(Utc::now, |current| current.since),
+            enabled_by: actor,
+            allow_reads,
+        };
+        self.switch.switch_on(state.clone()).await?;
+        self.logger.warn(&format!(
+            "Maintenance mode switched on by {:?} ({}): {}",
+            actor,
+            if allow_reads { "reads served" } else { "reads refused" },
+            state.reason
+        ));
+        Ok(state)
+    }
+
+    /// Switch maintenance mode off everywhere
+    pub async fn disable(&self, actor: Option<Uuid>) -> AppResult<()> {
+        if self.switch.switch_off().await? {
+            self.logger.warn(&format!("Maintenance mode switched off by {:?}", actor));
+        }
+        Ok(())
+    }
+
+    /// Pick up the mode as switched by any instance
+    pub async fn refresh(&self) -> AppResult<()> {
+        self.switch.refresh().await
+    }
+
+    pub fn spawn_sync(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        self.switch.clone().spawn_sync(shutdown)
+    }
+}
@@ -0,0 +1,80 @@
+pub mod user_service;
+pub mod notification_service;
+pub mod notification_dispatcher;
+pub mod notification_budget;
+pub mod cache_service;
+pub mod bloom_filter;
+pub mod breached_passwords;
+pub mod second_factor;
+pub mod cache_policy;
+pub mod event_bus;
+pub mod search_service;
+pub mod event_publisher;
+pub mod quota_service;
+pub mod channels;
+pub mod report_service;
+pub mod presence_service;
+pub mod announcement_service;
+pub mod oauth_service;
+pub mod saml;
+pub mod session_service;
+pub mod api_key_service;
+pub mod audit_service;
+pub mod token_revocation;
+pub mod delivery_webhooks;
+pub mod policy_engine;
+pub mod webauthn;
+pub mod storage_service;
+pub mod export_service;
+pub mod status_service;
+pub mod health_registry;
+pub mod secrets;
+pub mod tenant_limit_service;
+pub mod mode_switch;
+pub mod read_only;
+pub mod maintenance_mode;
+pub mod login_analytics;
+pub mod service_account_service;
+pub mod job_queues;
+pub mod trusted_devices;
+pub mod organization_service;
+
+pub use user_service::UserService;
+pub use notification_dispatcher::NotificationDispatcher;
+pub use notification_budget::{ChannelBudgetUsage, NotificationBudget};
+pub use notification_service::NotificationService;
+pub use cache_service::{CacheService, HotKeyReport, StreamEntry, TokenBucket};
+pub use bloom_filter::BloomFilter;
+pub use breached_passwords::BreachedPasswords;
+pub use second_factor::SecondFactors;
+pub use cache_policy::{CacheCodec, CacheEntity, CachePolicies, CachePolicy};
+pub use event_bus::EventBus;
+pub use search_service::{NoopSearchIndexer, SearchBackend, SearchIndexer, SearchService, UserSearchResults};
+pub use event_publisher::{EventBusPublisher, EventPublisher, FanOutPublisher, KafkaPublisher};
+pub use quota_service::{QuotaDecision, QuotaService, QuotaSubject, UsageReport};
+pub use channels::{EmailChannel, EmailTracker, HttpProbe};
+pub use report_service::{Report, ReportKind, ReportService};
+pub use presence_service::PresenceService;
+pub use announcement_service::AnnouncementService;
+pub use oauth_service::OAuthService;
+pub use saml::SamlService;
+pub use session_service::SessionService;
+pub use api_key_service::ApiKeyService;
+pub use audit_service::AuditService;
+pub use token_revocation::TokenRevocations;
+pub use delivery_webhooks::{DeliveryWebhooks, NoopProviderCallbacks, ProviderCallbacks};
+pub use policy_engine::PolicyEngine;
+pub use webauthn::Passkeys;
+pub use storage_service::StorageService;
+pub use export_service::ExportService;
+pub use status_service::StatusService;
+pub use health_registry::{HealthProbe, HealthRegistry};
+pub use secrets::{SecretProvider, SecretStore};
+pub use tenant_limit_service::{TenantLimitService, TenantUsage};
+pub use read_only::{ReadOnlyMode, ReadOnlyState};
+pub use maintenance_mode::{MaintenanceMode, MaintenanceState};
+pub use service_account_service::ServiceAccountService;
+pub use job_queues::JobQueues;
+pub use trusted_devices::TrustedDevices;
+pub use organization_service::OrganizationService;
+pub use login_analytics::{LoginAnalytics, LoginFailure, LoginThrottlingReport};
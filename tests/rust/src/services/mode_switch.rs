@@ -0,0 +1,105 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::cache_service::CacheService;
+use crate::models::AppResult;
+use crate::utils::{Logger, Metrics};
+
+/// An application-wide mode switched on and off for every instance at once.
+///
+/// While on, the mode's state is kept in the cache under its key, so
+/// switching it on one instance reaches the others within the sync
+/// interval. Checks read the local copy and never wait on the cache.
+/// Whether the mode is on is published as a 0/1 gauge.
+pub struct ModeSwitch<S> {
+    /// Names the mode in logs
+    name: &'static str,
+    key: &'static str,
+    gauge: &'static str,
+    state: RwLock<Option<S>>,
+    cache: Arc<CacheService>,
+    sync_interval: Duration,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl<S> ModeSwitch<S>
+where
+    S: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    pub fn new(
+        name: &'static str,
+        key: &'static str,
+        gauge: &'static str,
+        cache: Arc<CacheService>,
+        sync_interval: Duration,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        Self {
+            name,
+            key,
+            gauge,
+            state: RwLock::new(None),
+            cache,
+            sync_interval,
+            metrics,
+            logger,
+        }
+    }
+
+    pub fn status(&self) -> Option<S> {
+        self.state.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Switch the mode on everywhere, or replace its state while on
+    pub async fn switch_on(&self, state: S) -> AppResult<()> {
+        self.cache.set(self.key, &state, None).await?;
+        self.apply(Some(state)).await;
+        Ok(())
+    }
+
+    /// Switch the mode off everywhere, returning whether it was on here
+    pub async fn switch_off(&self) -> AppResult<bool> {
+        self.cache.delete(self.key).await?;
+        let was_on = self.status().is_some();
+        self.apply(None).await;
+        Ok(was_on)
+    }
+
+    /// Pick up the mode as switched by any instance
+    pub async fn refresh(&self) -> AppResult<()> {
+        let state = self.cache.get::<S>(self.key).await?;
+        self.apply(state).await;
+        Ok(())
+    }
+
+    /// Keep the local copy in step with the cache; on a failed read the mode stays as it was
+    pub fn spawn_sync(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        let interval = self.sync_interval.max(Duration::from_secs(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes at once, and the mode was just loaded
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.refresh().await {
+                    self.logger.error(&format!("Failed to refresh {}: {}", self.name, e));
+                }
+            }
+        })
+    }
+
+    async fn apply(&self, state: Option<S>) {
+        let on = state.is_some();
+        *self.state.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = state;
+        let _ = self.metrics.set_gauge(self.gauge, if on { 1.0 } else { 0.0 }).await;
+    }
+}
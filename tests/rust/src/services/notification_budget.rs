@@ -0,0 +1,168 @@
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::cache_service::CacheService;
+use crate::config::{ChannelBudget, NotificationBudgets};
+use crate::models::{AppResult, NotificationChannel, NotificationType};
+use crate::utils::Metrics;
+
+/// Costs are counted in millionths of the currency so the counters stay integers
+const MICROS: f64 = 1_000_000.0;
+/// Counters outlive their month by this long for reporting
+const COUNTER_GRACE: Duration = Duration::from_secs(2 * 86_400);
+
+/// What a channel has used of its budget this month
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelBudgetUsage {
+    pub channel: NotificationChannel,
+    /// `YYYY-MM`, in UTC
+    pub month: String,
+    pub messages: u64,
+    pub max_messages: Option<u64>,
+    pub cost: f64,
+    pub max_cost: Option<f64>,
+    pub currency: String,
+    /// Share of the tighter of the two limits used, in percent
+    pub used_percent: f64,
+    pub spent: bool,
+    pub resets_at: DateTime<Utc>,
+}
+
+/// Monthly message and cost budgets per notification channel.
+///
+/// The dispatcher checks a notification against its channel's budget before
+/// sending it and counts it once sent; the counts are kept in the cache per
+/// calendar month, so every instance draws on the same budget. Counting
+/// returns the warning thresholds the send took the budget past, each
+/// exactly once, since increments are atomic.
+pub struct NotificationBudget {
+    config: NotificationBudgets,
+    cache: Arc<CacheService>,
+    metrics: Arc<Metrics>,
+}
+
+impl NotificationBudget {
+    pub fn new(config: &NotificationBudgets, cache: Arc<CacheService>, metrics: Arc<Metrics>) -> Self {
+        Self {
+            config: config.clone(),
+            cache,
+            metrics,
+        }
+    }
+
+    /// False once the channel's budget is spent, for the types configured to stop then
+    pub async fn allows(&self, channel: NotificationChannel, notification_type: NotificationType) -> AppResult<bool> {
+        if !self.config.hard_stop.contains(&notification_type) {
+            return Ok(true);
+        }
+        let Some(budget) = self.config.for_channel(channel) else {
+            return Ok(true);
+        };
+        let month = month_of(Utc::now());
+        let messages = self.cache.get_counter(&messages_key(channel, &month)).await?;
+        let cost = self.cache.get_counter(&cost_key(channel, &month)).await?;
+        Ok(used(budget, messages, cost) < 1.0)
+    }
+
+    /// Count a sent message, returning the warning percentages it took the budget past
+    pub async fn record_sent(&self, channel: NotificationChannel) -> AppResult<Vec<u32>> {
+        let Some(budget) = self.config.for_channel(channel) else {
+            return Ok(Vec::new());
+        };
+        let now = Utc::now();
+        let month = month_of(now);
+        let ttl = Some((next_month(now) - now).to_std().unwrap_or_default() + COUNTER_GRACE);
+        let price = (budget.cost_per_message * MICROS).round() as i64;
+
+        let messages = self.cache.increment(&messages_key(channel, &month), 1, ttl).await?;
+        let cost = if price > 0 {
+            self.cache.increment(&cost_key(channel, &month), price, ttl).await?
+        } else {
+            0
+        };
+
+        let before = used(budget, messages - 1, cost - price) * 100.0;
+        let after = used(budget, messages, cost) * 100.0;
+        let _ = self
+            .metrics
+            .set_labeled_gauge("notifications.budget_used_percent", &[("channel", channel.as_str())], after)
+            .await;
+        Ok(self
+            .config
+            .warning_thresholds
+            .iter()
+            .copied()
+            .filter(|threshold| before < *threshold as f64 && after >= *threshold as f64)
+            .collect())
+    }
+
+    /// This month's usage of every channel with a budget
+    pub async fn usage(&self) -> AppResult<Vec<ChannelBudgetUsage>> {
+        let now = Utc::now();
+        let month = month_of(now);
+        let mut usage = Vec::with_capacity(self.config.channels.len());
+        for (channel, budget) in &self.config.channels {
+            let messages = self.cache.get_counter(&messages_key(*channel, &month)).await?;
+            let cost = self.cache.get_counter(&cost_key(*channel, &month)).await?;
+            let used = used(budget, messages, cost);
+            usage.push(ChannelBudgetUsage {
+                channel: *channel,
+                month: month.clone(),
+                messages: messages.max(0) as u64,
+                max_messages: budget.max_messages,
+                cost: cost.max(0) as f64 / MICROS,
+                max_cost: budget.max_cost,
+                currency: self.config.currency.clone(),
+                used_percent: used * 100.0,
+                spent: used >= 1.0,
+                resets_at: next_month(now),
+            });
+        }
+        usage.sort_by_key(|usage| usage.channel.as_str());
+        Ok(usage)
+    }
+}
+
+/// Share of the tighter limit used; 0 for a channel whose budget sets no limit
+fn used(budget: &ChannelBudget, messages: i64, cost_micros: i64) -> f64 {
+    let by_messages = budget
+        .max_messages
+        .map_or(0.0, |max| share(messages.max(0) as f64, max as f64));
+    let by_cost = budget
+        .max_cost
+        .map_or(0.0, |max| share(cost_micros.max(0) as f64 / MICROS, max));
+    by_messages.max(by_cost)
+}
+
+/// A zero limit is spent from the start
+fn share(used: f64, limit: f64) -> f64 {
+    if limit <= 0.0 {
+        1.0
+    } else {
+        used / limit
+    }
+}
+
+fn month_of(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m").to_string()
+}
+
+fn next_month(at: DateTime<Utc>) -> DateTime<Utc> {
+    let (year, month) = if at.month() == 12 {
+        (at.year() + 1, 1)
+    } else {
+        (at.year(), at.month() + 1)
+    };
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(at.date_naive());
+    Utc.from_utc_datetime(&first.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+fn messages_key(channel: NotificationChannel, month: &str) -> String {
+    format!("notification_budget:{}:{}:messages", channel.as_str(), month)
+}
+
+fn cost_key(channel: NotificationChannel, month: &str) -> String {
+    format!("notification_budget:{}:{}:cost", channel.as_str(), month)
+}
@@ -0,0 +1,376 @@
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{mpsc, Mutex, Semaphore};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::channels::{EmailChannel, EmailMessage};
+use super::notification_budget::NotificationBudget;
+use crate::config::NotificationConfig;
+use crate::models::{
+    AppError, AppResult, Notification, NotificationChannel, NotificationStatus, TenantContext, UserFilters, UserRole,
+    UserStatus,
+};
+use crate::repositories::{BroadcastRepository, NotificationRepository, SuppressionRepository, UserRepository};
+use crate::utils::{Logger, Metrics};
+
+const RECOVERY_LIMIT: i64 = 10_000;
+
+/// Queue of stored notifications drained by a pool of delivery workers.
+///
+/// The queue is bounded and each channel has its own concurrency cap, so a
+/// slow provider holds on to its permits, workers wait for them, the queue
+/// fills up and `enqueue` starts waiting: producers are slowed down instead
+/// of piling up unbounded work in memory. Sends are counted against the
+/// channels' monthly budgets, and admins are emailed as a budget passes its
+/// warning thresholds.
+pub struct NotificationDispatcher {
+    sender: mpsc::Sender<Notification>,
+    receiver: Mutex<Option<mpsc::Receiver<Notification>>>,
+    workers: usize,
+    /// Anything pending from before this instant was queued by a previous process
+    created_at: chrono::DateTime<chrono::Utc>,
+    email_permits: Arc<Semaphore>,
+    in_app_permits: Arc<Semaphore>,
+    repository: Arc<dyn NotificationRepository>,
+    broadcasts: Arc<BroadcastRepository>,
+    suppressions: Arc<SuppressionRepository>,
+    budget: Arc<NotificationBudget>,
+    /// Admins to warn about budgets
+    users: Arc<dyn UserRepository>,
+    email: Arc<EmailChannel>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl NotificationDispatcher {
+    pub fn new(
+        config: &NotificationConfig,
+        repository: Arc<dyn NotificationRepository>,
+        broadcasts: Arc<BroadcastRepository>,
+        suppressions: Arc<SuppressionRepository>,
+        budget: Arc<NotificationBudget>,
+        users: Arc<dyn UserRepository>,
+        email: Arc<EmailChannel>,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(config.dispatch_queue_capacity.max(1));
+
+        Self {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            workers: config.dispatch_workers.max(1),
+            created_at: chrono::Utc::now(),
+            email_permits: Arc::new(Semaphore::new(config.email_concurrency.max(1))),
+            in_app_permits: Arc::new(Semaphore::new(config.in_app_concurrency.max(1))),
+            repository,
+            broadcasts,
+            suppressions,
+            budget,
+            users,
+            email,
+            metrics,
+            logger,
+        }
+    }
+
+    /// Queue a stored notification for delivery, waiting while the queue is full
+    pub async fn enqueue(&self, notification: Notification) -> AppResult<()> {
+        self.sender
+            .send(notification)
+            .await
+            .map_err(|_| AppError::Internal("Notification dispatcher has shut down".to_string()))?;
+        self.report_depth().await;
+        Ok(())
+    }
+
+    /// Start the worker pool, first re-queueing notifications left pending by a previous run.
+    ///
+    /// On shutdown workers finish the delivery in hand and stop; whatever is
+    /// still queued stays pending in the database and is re-queued on the next start.
+    pub async fn spawn_workers(self: Arc<Self>, shutdown: CancellationToken) -> AppResult<Vec<JoinHandle<()>>> {
+        let receiver = self
+            .receiver
+            .lock()
+            .await
+            .take()
+            .ok_or_else(|| AppError::Internal("Notification workers already started".to_string()))?;
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let mut handles = Vec::with_capacity(self.workers + 1);
+        for worker in 0..self.workers {
+            let dispatcher = self.clone();
+            let receiver = receiver.clone();
+            let shutdown = shutdown.clone();
+            handles.push(tokio::spawn(async move {
+                dispatcher.work(worker, receiver, shutdown).await;
+            }));
+        }
+
+        // Recovery enqueues through the bounded queue, so it must run alongside the workers
+        let dispatcher = self.clone();
+        handles.push(tokio::spawn(async move {
+            let recovered = tokio::select! {
+                _ = shutdown.cancelled() => return,
+                recovered = dispatcher.requeue_pending() => recovered,
+            };
+            if let Err(e) = recovered {
+                dispatcher
+                    .logger
+                    .error(&format!("Failed to re-queue pending notifications: {}", e));
+            }
+        }));
+
+        self.logger
+            .info(&format!("Started {} notification dispatch workers", self.workers));
+        Ok(handles)
+    }
+
+    async fn work(
+        &self,
+        worker: usize,
+        receiver: Arc<Mutex<mpsc::Receiver<Notification>>>,
+        shutdown: CancellationToken,
+    ) {
+        loop {
+            // Hold the lock only while waiting for the next item
+            let next = tokio::select! {
+                _ = shutdown.cancelled() => {
+                    self.logger
+                        .debug(&format!("Notification worker {} stopping: shutting down", worker));
+                    return;
+                }
+                next = async { receiver.lock().await.recv().await } => next,
+            };
+            let Some(mut notification) = next else {
+                self.logger
+                    .debug(&format!("Notification worker {} stopping: queue closed", worker));
+                return;
+            };
+            self.report_depth().await;
+
+            if let Err(e) = self.dispatch(&mut notification).await {
+                self.logger.warn(&format!(
+                    "Delivery of notification {} failed: {}",
+                    notification.id, e
+                ));
+            }
+        }
+    }
+
+    /// Deliver one notification under its channel's concurrency cap and record the outcome
+    async fn dispatch(&self, notification: &mut Notification) -> AppResult<()> {
+        if !self.still_wanted(notification).await? {
+            return Ok(());
+        }
+        if !self.budget.allows(notification.channel, notification.notification_type).await? {
+            let channel = notification.channel.as_str();
+            notification.mark_cancelled(&format!("Monthly {} budget spent", channel));
+            self.repository.update_status(notification).await?;
+            self.metrics
+                .increment_labeled_counter("notifications.budget_stopped", &[("channel", channel)])
+                .await?;
+            return Ok(());
+        }
+
+        let permits = match notification.channel {
+            NotificationChannel::Email => &self.email_permits,
+            NotificationChannel::InApp => &self.in_app_permits,
+        };
+        let _permit = permits
+            .acquire()
+            .await
+            .map_err(|_| AppError::Internal("Channel permits closed".to_string()))?;
+
+        let started = Instant::now();
+        let result = match notification.channel {
+            NotificationChannel::Email => {
+                self.email
+                    .send(EmailMessage {
+                        from: notification.sender.clone(),
+                        to: vec![notification.recipient.clone()],
+                        subject: notification.title.clone(),
+                        text_body: notification.message.clone(),
+                        html_body: notification.html_message.clone(),
+                        attachments: Vec::new(),
+                        track_as: Some(notification.id),
+                    })
+                    .await
+            }
+            // In-app notifications are delivered by being stored
+            NotificationChannel::InApp => Ok(()),
+        };
+
+        let channel = notification.channel.as_str();
+        self.metrics
+            .record_labeled_duration("notifications.dispatch", &[("channel", channel)], started.elapsed())
+            .await?;
+
+        match &result {
+            Ok(()) => {
+                notification.mark_sent();
+                if notification.channel == NotificationChannel::InApp {
+                    notification.status = NotificationStatus::Delivered;
+                }
+                self.metrics
+                    .increment_labeled_counter("notifications.sent", &[("channel", channel)])
+                    .await?;
+                self.count_against_budget(notification.channel).await;
+            }
+            Err(e) => {
+                notification.mark_failed(&e.to_string());
+                self.metrics
+                    .increment_labeled_counter("notifications.failed", &[("channel", channel)])
+                    .await?;
+            }
+        }
+        self.repository.update_status(notification).await?;
+        result
+    }
+
+    /// Whether a queued notification should still go out.
+    ///
+    /// It may have been cancelled while queued, belong to a broadcast that
+    /// has since been stopped, or be addressed to a suppressed recipient;
+    /// the latter two are recorded as cancelled here.
+    async fn still_wanted(&self, notification: &mut Notification) -> AppResult<bool> {
+        let current = self.repository.find_by_id(notification.id).await?;
+        if current.map_or(true, |current| current.status != NotificationStatus::Pending) {
+            return Ok(false);
+        }
+
+        let reason = match notification.broadcast_id() {
+            Some(broadcast_id) if self.broadcasts.is_cancelled(broadcast_id).await? => "Broadcast cancelled",
+            _ => {
+                let tenant_id = notification
+                    .tenant_id
+                    .clone()
+                    .unwrap_or_else(|| TenantContext::default().tenant_id);
+                if !self
+                    .suppressions
+                    .is_suppressed(&tenant_id, notification.user_id, &notification.recipient)
+                    .await?
+                {
+                    return Ok(true);
+                }
+                "Recipient suppressed"
+            }
+        };
+        notification.mark_cancelled(reason);
+        self.repository.update_status(notification).await?;
+        self.metrics
+            .increment_labeled_counter("notifications.cancelled", &[("channel", notification.channel.as_str())])
+            .await?;
+        Ok(false)
+    }
+
+    /// Count a sent notification against its channel's budget; failing to must not fail the delivery
+    async fn count_against_budget(&self, channel: NotificationChannel) {
+        let crossed = match self.budget.record_sent(channel).await {
+            Ok(crossed) => crossed,
+            Err(e) => {
+                self.logger
+                    .warn(&format!("Failed to count a {} notification against its budget: {}", channel.as_str(), e));
+                return;
+            }
+        };
+        for threshold in crossed {
+            if let Err(e) = self.warn_admins(channel, threshold).await {
+                self.logger.error(&format!(
+                    "Failed to warn admins that the {} budget passed {}%: {}",
+                    channel.as_str(),
+                    threshold,
+                    e
+                ));
+            }
+        }
+    }
+
+    /// Emailed directly rather than queued, so the warning neither waits behind nor counts as the traffic it is about
+    async fn warn_admins(&self, channel: NotificationChannel, threshold: u32) -> AppResult<()> {
+        let mut recipients = Vec::new();
+        for role in [UserRole::Admin, UserRole::SuperAdmin] {
+            let filters = UserFilters::new().with_role(role).with_status(UserStatus::Active);
+            recipients.extend(self.users.list(&filters).await?.into_iter().map(|user| user.email));
+        }
+        self.logger.warn(&format!(
+            "Monthly {} notification budget passed {}%",
+            channel.as_str(),
+            threshold
+        ));
+        if recipients.is_empty() {
+            return Ok(());
+        }
+
+        let usage = self.budget.usage().await?;
+        let summary = usage
+            .iter()
+            .find(|usage| usage.channel == channel)
+            .map(|usage| {
+                let mut lines = vec![format!("Messages sent: {}", usage.messages)];
+                if let Some(max) = usage.max_messages {
+                    lines.push(format!("Message budget: {}", max));
+                }
+                if let Some(max) = usage.max_cost {
+                    lines.push(format!("Spend: {:.2} of {:.2} {}", usage.cost, max, usage.currency));
+                }
+                lines.push(format!("Resets at: {}", usage.resets_at.to_rfc3339()));
+                lines.join("\n")
+            })
+            .unwrap_or_default();
+        self.email
+            .send(EmailMessage {
+                from: None,
+                to: recipients,
+                subject: format!("{} notifications have used {}% of this month's budget", channel.as_str(), threshold),
+                text_body: format!(
+                    "The monthly budget for {} notifications has passed {}%.\n\n{}\n",
+                    channel.as_str(),
+                    threshold,
+                    summary
+                ),
+                html_body: None,
+                attachments: Vec::new(),
+                track_as: None,
+            })
+            .await?;
+        self.metrics
+            .increment_labeled_counter("notifications.budget_warnings", &[("channel", channel.as_str())])
+            .await?;
+        Ok(())
+    }
+
+    /// Only rows older than this dispatcher are recovered; newer pending rows are already queued
+    async fn requeue_pending(&self) -> AppResult<()> {
+        let pending = self
+            .repository
+            .pending_before(self.created_at, RECOVERY_LIMIT)
+            .await?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let count = pending.len();
+        if count as i64 == RECOVERY_LIMIT {
+            self.logger.warn(&format!(
+                "Recovery limit reached; pending notifications beyond the first {} stay queued in the database",
+                RECOVERY_LIMIT
+            ));
+        }
+        for notification in pending {
+            self.enqueue(notification).await?;
+        }
+        self.logger
+            .info(&format!("Re-queued {} pending notifications", count));
+        Ok(())
+    }
+
+    async fn report_depth(&self) {
+        let depth = self.sender.max_capacity() - self.sender.capacity();
+        let _ = self
+            .metrics
+            .set_gauge("notifications.queue_depth", depth as f64)
+            .await;
+    }
+}
@@ -0,0 +1,787 @@
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use super::channels::{EmailChannel, EmailMessage};
+use super::notification_dispatcher::NotificationDispatcher;
+use super::presence_service::PresenceService;
+use super::read_only::ReadOnlyMode;
+use super::tenant_limit_service::TenantLimitService;
+use crate::config::{ChannelRouting, NotificationConfig};
+use crate::models::{
+    AppError, AppResult, Broadcast, BroadcastMessage, BroadcastStatus, BroadcastSummary, BroadcastTarget, ClientInfo,
+    CreateSuppressionRequest, DeliveryEvent, DeliveryEventKind, EngagementEvent, Notification, NotificationChannel,
+    NotificationFilters, NotificationPriority, NotificationStatus, NotificationTemplate, NotificationType, Suppression,
+    TemplateEngagement, TemplatePreview, TemplateRevision, TenantBranding, TenantContext, User,
+};
+use crate::repositories::{
+    BroadcastRepository, GroupRepository, NotificationRepository, SuppressionRepository, TemplateRepository,
+    TenantBrandingRepository, UserRepository,
+};
+use crate::utils::{Locale, Logger, RequestContext};
+
+const WELCOME_TEMPLATE: &str = "welcome";
+const PASSWORD_RESET_TEMPLATE: &str = "password_reset";
+const EMAIL_VERIFICATION_TEMPLATE: &str = "email_verification";
+const EXPORT_READY_TEMPLATE: &str = "export_ready";
+const NEW_SIGN_IN_TEMPLATE: &str = "new_sign_in";
+
+/// Creates and stores notifications and queues them for delivery
+pub struct NotificationService {
+    repository: Arc<dyn NotificationRepository>,
+    users: Arc<dyn UserRepository>,
+    groups: Arc<dyn GroupRepository>,
+    templates: Arc<dyn TemplateRepository>,
+    branding: Arc<dyn TenantBrandingRepository>,
+    broadcasts: Arc<BroadcastRepository>,
+    suppressions: Arc<SuppressionRepository>,
+    /// Deployment-wide branding that tenant overrides fall back to
+    default_branding: TenantBranding,
+    dispatcher: Arc<NotificationDispatcher>,
+    email: Arc<EmailChannel>,
+    /// Online users get in-app delivery instead of email
+    presence: Arc<PresenceService>,
+    /// Tenants' daily notification allowances
+    limits: Arc<TenantLimitService>,
+    read_only: Arc<ReadOnlyMode>,
+    routing: ChannelRouting,
+    broadcast_batch_size: i64,
+    /// Stops broadcasts at the next batch boundary
+    shutdown: CancellationToken,
+    logger: Arc<Logger>,
+}
+
+impl NotificationService {
+    pub async fn new(
+        config: &NotificationConfig,
+        repository: Arc<dyn NotificationRepository>,
+        users: Arc<dyn UserRepository>,
+        groups: Arc<dyn GroupRepository>,
+        templates: Arc<dyn TemplateRepository>,
+        branding: Arc<dyn TenantBrandingRepository>,
+        broadcasts: Arc<BroadcastRepository>,
+        suppressions: Arc<SuppressionRepository>,
+        dispatcher: Arc<NotificationDispatcher>,
+        email: Arc<EmailChannel>,
+        presence: Arc<PresenceService>,
+        limits: Arc<TenantLimitService>,
+        read_only: Arc<ReadOnlyMode>,
+        shutdown: CancellationToken,
+        logger: Arc<Logger>,
+    ) -> AppResult<Self> {
+        let default_branding = TenantBranding {
+            tenant_id: TenantContext::default().tenant_id,
+            sender_address: Some(config.from_address.clone()),
+            sender_name: Some(config.from_name.clone()),
+            logo_url: config.brand_logo_url.clone(),
+            primary_color: config.brand_primary_color.clone(),
+            accent_color: config.brand_accent_color.clone(),
+            footer: config.brand_footer.clone(),
+            updated_at: None,
+        };
+
+        Ok(Self {
+            repository,
+            users,
+            groups,
+            templates,
+            branding,
+            broadcasts,
+            suppressions,
+            default_branding,
+            dispatcher,
+            email,
+            presence,
+            limits,
+            read_only,
+            routing: config.routing.clone(),
+            broadcast_batch_size: config.broadcast_batch_size.max(1),
+            shutdown,
+            logger,
+        })
+    }
+
+    pub async fn initialize(&self) -> AppResult<()> {
+        self.logger.info("Notification service initialized");
+        Ok(())
+    }
+
+    pub async fn shutdown(&self) -> AppResult<()> {
+        self.logger.info("Notification service shut down");
+        Ok(())
+    }
+
+    pub async fn send_welcome_notification(
+        &self,
+        tenant: &TenantContext,
+        user_id: Uuid,
+        email: &str,
+    ) -> AppResult<()> {
+        self.read_only.check()?;
+        RequestContext::check("send_welcome_notification")?;
+        let user = self
+            .users
+            .find_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("User {} not found", user_id)))?;
+        let branding = self.tenant_branding(tenant).await?;
+        let template = self.template(WELCOME_TEMPLATE).await?;
+        let Some(channel) = self.delivery_channel(&user, template.notification_type, NotificationPriority::Normal, false)
+        else {
+            self.logger
+                .debug(&format!("No deliverable channel for welcome notification to {}", user_id));
+            return Ok(());
+        };
+        let rendered = template.render(channel, &branded(&branding, user_params(&user)))?;
+
+        let mut notification = Notification::new(
+            user_id,
+            template.notification_type,
+            channel,
+            email.to_string(),
+            rendered.subject,
+            rendered.body,
+        );
+        notification.html_message = rendered.html_body;
+        brand(&mut notification, &branding);
+        notification.template_key = Some(template.key.clone());
+        notification.template_version = Some(template.version);
+        // Part of onboarding, so it counts towards the tenant's allowance but is never refused
+        self.limits.count_notification(tenant).await?;
+        self.repository.create(&notification).await?;
+        self.dispatcher.enqueue(notification).await
+    }
+
+    /// Email a password reset link
+    pub async fn send_password_reset(
+        &self,
+        tenant: &TenantContext,
+        user: &User,
+        reset_link: &str,
+        expires_in: std::time::Duration,
+    ) -> AppResult<()> {
+        self.read_only.check()?;
+        let params = [
+            ("reset_link", reset_link.to_string()),
+            ("expires_minutes", minutes(expires_in)),
+            ("expires_at", expires_at(user, expires_in)),
+        ];
+        self.send_account_email(tenant, user, PASSWORD_RESET_TEMPLATE, &params).await
+    }
+
+    /// Email a link confirming the user's current address
+    pub async fn send_email_verification(
+        &self,
+        tenant: &TenantContext,
+        user: &User,
+        verification_link: &str,
+        expires_in: std::time::Duration,
+    ) -> AppResult<()> {
+        self.read_only.check()?;
+        let params = [
+            ("verification_link", verification_link.to_string()),
+            ("expires_minutes", minutes(expires_in)),
+            ("expires_at", expires_at(user, expires_in)),
+        ];
+        self.send_account_email(tenant, user, EMAIL_VERIFICATION_TEMPLATE, &params).await
+    }
+
+    /// Remind a user to confirm their address with a fresh link, using the
+    /// campaign's template for how many reminders they have had
+    pub async fn send_verification_reminder(
+        &self,
+        tenant: &TenantContext,
+        user: &User,
+        template_key: &str,
+        verification_link: &str,
+        expires_in: std::time::Duration,
+        suspends_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()> {
+        self.read_only.check()?;
+        let locale = Locale::for_preferences(&user.preferences);
+        let params = [
+            ("verification_link", verification_link.to_string()),
+            ("expires_minutes", minutes(expires_in)),
+            ("expires_at", expires_at(user, expires_in)),
+            ("suspends_at", suspends_at.map_or_else(String::new, |at| locale.date(at))),
+        ];
+        self.send_account_email(tenant, user, template_key, &params).await
+    }
+
+    /// Email the link to a finished export; by email like account emails,
+    /// since the link alone grants the download
+    pub async fn send_export_ready(
+        &self,
+        tenant: &TenantContext,
+        user: &User,
+        export_kind: &str,
+        download_link: &str,
+        expires_in: std::time::Duration,
+    ) -> AppResult<()> {
+        self.read_only.check()?;
+        let params = [
+            ("export_kind", export_kind.to_string()),
+            ("download_link", download_link.to_string()),
+            ("expires_hours", (expires_in.as_secs() / 3600).max(1).to_string()),
+            ("expires_at", expires_at(user, expires_in)),
+        ];
+        self.send_account_email(tenant, user, EXPORT_READY_TEMPLATE, &params).await
+    }
+
+    /// Tell the user their account was signed in to from a client it had not been before
+    pub async fn send_new_sign_in(&self, tenant: &TenantContext, user: &User, client: &ClientInfo) -> AppResult<()> {
+        self.read_only.check()?;
+        let params = [
+            ("browser", format!("{} on {}", client.browser(), client.os())),
+            ("ip_address", client.ip_address.clone().unwrap_or_else(|| "an unknown address".to_string())),
+            ("country", client.country.clone().unwrap_or_else(|| "unknown location".to_string())),
+        ];
+        self.send_account_email(tenant, user, NEW_SIGN_IN_TEMPLATE, &params).await
+    }
+
+    /// Send an account security email.
+    ///
+    /// Always sent by email, whatever the user's channel preferences: the
+    /// mailbox is what proves the request came from the account owner.
+    async fn send_account_email(
+        &self,
+        tenant: &TenantContext,
+        user: &User,
+        template_key: &str,
+        extra: &[(&str, String)],
+    ) -> AppResult<()> {
+        let branding = self.tenant_branding(tenant).await?;
+        let template = self.template(template_key).await?;
+        let mut params = user_params(user);
+        params.extend(extra.iter().map(|(key, value)| (key.to_string(), value.clone())));
+        let rendered = template.render(NotificationChannel::Email, &branded(&branding, params))?;
+
+        let mut notification = Notification::new(
+            user.id,
+            template.notification_type,
+            NotificationChannel::Email,
+            user.email.clone(),
+            rendered.subject,
+            rendered.body,
+        );
+        notification.html_message = rendered.html_body;
+        brand(&mut notification, &branding);
+        notification.template_key = Some(template.key.clone());
+        notification.template_version = Some(template.version);
+        // Refusing these would lock users out of their accounts, so they are only counted
+        self.limits.count_notification(tenant).await?;
+        self.repository.create(&notification).await?;
+        self.dispatcher.enqueue(notification).await
+    }
+
+    /// Render a template for every channel with sample parameters, without sending anything
+    pub async fn preview(
+        &self,
+        tenant: &TenantContext,
+        template_key: &str,
+        sample_params: &HashMap<String, String>,
+    ) -> AppResult<TemplatePreview> {
+        let template = self.template(template_key).await?;
+        let params = branded(&self.tenant_branding(tenant).await?, sample_params.clone());
+        let renderings = NotificationTemplate::CHANNELS
+            .iter()
+            .map(|channel| template.render(*channel, &params))
+            .collect::<AppResult<Vec<_>>>()?;
+
+        Ok(TemplatePreview {
+            template_key: template.key,
+            renderings,
+        })
+    }
+
+    /// Email a rendered template to an arbitrary address.
+    ///
+    /// Test sends bypass the queue and are not stored, so they never show up
+    /// in a user's notifications or in delivery reports.
+    pub async fn test_send(
+        &self,
+        tenant: &TenantContext,
+        template_key: &str,
+        sample_params: &HashMap<String, String>,
+        address: &str,
+    ) -> AppResult<()> {
+        self.read_only.check()?;
+        if !address.contains('@') {
+            return Err(AppError::Validation(vec!["Invalid email address".to_string()]));
+        }
+
+        let branding = self.tenant_branding(tenant).await?;
+        let template = self.template(template_key).await?;
+        let rendered = template.render(NotificationChannel::Email, &branded(&branding, sample_params.clone()))?;
+        self.email
+            .send(EmailMessage {
+                from: branding.sender(),
+                to: vec![address.to_string()],
+                subject: format!("[Test] {}", rendered.subject),
+                text_body: rendered.body,
+                html_body: rendered.html_body,
+                attachments: Vec::new(),
+                track_as: None,
+            })
+            .await?;
+
+        self.logger
+            .info(&format!("Sent test of template {} to {}", template_key, address));
+        Ok(())
+    }
+
+    /// Send the same message to every user in the target.
+    ///
+    /// Recipients are resolved a page at a time in user id order so
+    /// arbitrarily large groups never have to be held in memory at once.
+    /// The cursor is persisted after each page, so a broadcast stopped by
+    /// shutdown is marked interrupted and picked up by `resume_broadcasts`
+    /// without messaging anyone twice. Delivery happens on the dispatcher's
+    /// workers, which slow the broadcast down when providers lag. Every
+    /// recipient counts towards the tenant's daily notification allowance,
+    /// and the broadcast fails once it is spent.
+    pub async fn broadcast(
+        &self,
+        tenant: &TenantContext,
+        target: BroadcastTarget,
+        message: BroadcastMessage,
+    ) -> AppResult<Broadcast> {
+        self.read_only.check()?;
+        let target = match target {
+            BroadcastTarget::Users(mut ids) => {
+                ids.sort_unstable();
+                ids.dedup();
+                BroadcastTarget::Users(ids)
+            }
+            group => group,
+        };
+        let mut broadcast = Broadcast::new(tenant.tenant_id.clone(), target, message);
+        self.broadcasts.create(&broadcast).await?;
+        self.run_broadcast(tenant, &mut broadcast).await?;
+        Ok(broadcast)
+    }
+
+    /// Continue broadcasts a previous shutdown stopped at a page boundary.
+    ///
+    /// Enqueues through the dispatcher, so it must run once its workers are started.
+    pub async fn resume_broadcasts(&self) -> AppResult<usize> {
+        let mut resumed = 0;
+        for mut broadcast in self.broadcasts.interrupted().await? {
+            if !self.broadcasts.claim_interrupted(broadcast.id).await? {
+                continue;
+            }
+            broadcast.status = BroadcastStatus::Running;
+            let tenant = TenantContext {
+                tenant_id: broadcast.tenant_id.clone(),
+            };
+            self.run_broadcast(&tenant, &mut broadcast).await?;
+            resumed += 1;
+        }
+        if resumed > 0 {
+            self.logger
+                .info(&format!("Resumed {} interrupted broadcasts", resumed));
+        }
+        Ok(resumed)
+    }
+
+    /// Withdraw the tenant's queued notifications matching `filters`.
+    ///
+    /// Meant for incidents such as a bad broadcast: naming one also stops it
+    /// from queueing further recipients. Notifications already handed to a
+    /// provider cannot be recalled; anything still pending is skipped by the
+    /// dispatcher, which re-reads the status before delivering.
+    pub async fn cancel_pending(
+        &self,
+        tenant: &TenantContext,
+        filters: NotificationFilters,
+        cancelled_by: &User,
+    ) -> AppResult<u64> {
+        self.read_only.check()?;
+        let errors = filters.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        // Stop the broadcast first so it does not queue more behind the cancellation
+        if let Some(broadcast_id) = filters.broadcast_id {
+            if self.broadcasts.cancel(&tenant.tenant_id, broadcast_id).await? {
+                self.logger
+                    .info(&format!("Broadcast {} cancelled by {}", broadcast_id, cancelled_by.id));
+            }
+        }
+        let reason = format!("Cancelled by {}", cancelled_by.id);
+        let cancelled = self.repository.cancel_pending(&tenant.tenant_id, &filters, &reason).await?;
+        self.logger.info(&format!(
+            "{} pending notifications cancelled by {}",
+            cancelled, cancelled_by.id
+        ));
+        Ok(cancelled)
+    }
+
+    /// Never contact a user or address again, including notifications already queued
+    pub async fn suppress(
+        &self,
+        tenant: &TenantContext,
+        request: CreateSuppressionRequest,
+        created_by: &User,
+    ) -> AppResult<Suppression> {
+        self.read_only.check()?;
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+        let suppression = Suppression::new(tenant.tenant_id.clone(), request, Some(created_by.id));
+        self.suppressions.create(&suppression).await?;
+        Ok(suppression)
+    }
+
+    pub async fn unsuppress(&self, tenant: &TenantContext, id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        if !self.suppressions.delete(&tenant.tenant_id, id).await? {
+            return Err(AppError::NotFound(format!("Suppression {} not found", id)));
+        }
+        Ok(())
+    }
+
+    pub async fn suppressions(&self, tenant: &TenantContext) -> AppResult<Vec<Suppression>> {
+        self.suppressions.list(&tenant.tenant_id).await
+    }
+
+    /// Record an open or click reported by the tracking endpoints
+    pub async fn record_engagement(&self, notification_id: Uuid, event: EngagementEvent) -> AppResult<()> {
+        self.read_only.check()?;
+        if !self.repository.record_engagement(notification_id, event).await? {
+            // Notifications can be erased with their user while emails are still being read
+            self.logger.debug(&format!(
+                "Ignoring {:?} for unknown notification {}",
+                event, notification_id
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply a provider's delivery report to the notification's status.
+    ///
+    /// False when the notification is gone or already in a final state, so
+    /// replaying a report, or one arriving out of order, changes nothing.
+    pub async fn apply_delivery_event(&self, event: &DeliveryEvent) -> AppResult<bool> {
+        self.read_only.check()?;
+        let Some(mut notification) = self.repository.find_by_id(event.notification_id).await? else {
+            return Ok(false);
+        };
+        if notification.status.is_final() {
+            return Ok(false);
+        }
+        match event.event {
+            DeliveryEventKind::Delivered if notification.status == NotificationStatus::Delivered => {
+                return Ok(false);
+            }
+            DeliveryEventKind::Delivered => notification.mark_delivered(),
+            DeliveryEventKind::Bounced => notification.mark_failed(event.reason.as_deref().unwrap_or("bounced")),
+            DeliveryEventKind::Failed => notification.mark_failed(event.reason.as_deref().unwrap_or("failed")),
+        }
+        self.repository.update_status(&notification).await?;
+        Ok(true)
+    }
+
+    /// Open and click rates per template revision for emails created since `since`
+    pub async fn template_engagement(&self, since: DateTime<Utc>) -> AppResult<Vec<TemplateEngagement>> {
+        self.repository.engagement_by_template(since).await
+    }
+
+    /// Branding for a tenant with its overrides applied over the defaults
+    pub async fn tenant_branding(&self, tenant: &TenantContext) -> AppResult<TenantBranding> {
+        let overrides = self.branding.find(&tenant.tenant_id).await?.unwrap_or_else(|| TenantBranding {
+            tenant_id: tenant.tenant_id.clone(),
+            ..TenantBranding::default()
+        });
+        Ok(overrides.with_fallback(&self.default_branding))
+    }
+
+    /// Replace a tenant's branding overrides; unset fields use the defaults
+    pub async fn update_branding(
+        &self,
+        tenant: &TenantContext,
+        mut branding: TenantBranding,
+    ) -> AppResult<TenantBranding> {
+        self.read_only.check()?;
+        let errors = branding.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        branding.tenant_id = tenant.tenant_id.clone();
+        let saved = self.branding.upsert(&branding).await?;
+        self.logger
+            .info(&format!("Updated notification branding for tenant {}", tenant.tenant_id));
+        Ok(saved)
+    }
+
+    /// Save edited template content as a new revision
+    pub async fn update_template(
+        &self,
+        template: NotificationTemplate,
+        author_id: Uuid,
+    ) -> AppResult<NotificationTemplate> {
+        self.read_only.check()?;
+        let saved = self
+            .templates
+            .save_revision(&template, Some(author_id), None)
+            .await?;
+        self.logger.info(&format!(
+            "Template {} updated to v{} by {}",
+            saved.key, saved.version, author_id
+        ));
+        Ok(saved)
+    }
+
+    /// Revision history of a template, newest first
+    pub async fn template_history(&self, template_key: &str) -> AppResult<Vec<TemplateRevision>> {
+        self.template(template_key).await?;
+        self.templates.revisions(template_key).await
+    }
+
+    /// Restore the content of an earlier revision.
+    ///
+    /// The restored content is written as a new revision rather than moving
+    /// the current pointer back, so the bad revision stays in the history.
+    pub async fn rollback_template(
+        &self,
+        template_key: &str,
+        version: i32,
+        author_id: Uuid,
+    ) -> AppResult<NotificationTemplate> {
+        self.read_only.check()?;
+        let revision = self
+            .templates
+            .revision(template_key, version)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Template {} has no revision v{}", template_key, version))
+            })?;
+
+        let saved = self
+            .templates
+            .save_revision(&revision.template, Some(author_id), Some(version))
+            .await?;
+        self.logger.info(&format!(
+            "Template {} rolled back to v{} content as v{} by {}",
+            saved.key, version, saved.version, author_id
+        ));
+        Ok(saved)
+    }
+
+    /// Process pages from the broadcast's cursor until done, failed, interrupted or cancelled
+    async fn run_broadcast(&self, tenant: &TenantContext, broadcast: &mut Broadcast) -> AppResult<()> {
+        let result = self.broadcast_pages(tenant, broadcast).await;
+        match &result {
+            Ok(status) => {
+                broadcast.status = *status;
+                if *status != BroadcastStatus::Interrupted {
+                    broadcast.completed_at = Some(Utc::now());
+                }
+            }
+            Err(e) => {
+                broadcast.status = BroadcastStatus::Failed;
+                broadcast.error = Some(e.to_string());
+            }
+        }
+        self.broadcasts.save_progress(broadcast).await?;
+
+        let summary = &broadcast.summary;
+        self.logger.info(&format!(
+            "Broadcast '{}' {}: {} targeted, {} queued, {} skipped",
+            broadcast.message.title,
+            broadcast.status.as_str(),
+            summary.targeted,
+            summary.queued,
+            summary.skipped
+        ));
+        result.map(|_| ())
+    }
+
+    /// Interrupted if shutdown stopped the broadcast before its last page,
+    /// cancelled if an administrator did
+    async fn broadcast_pages(
+        &self,
+        tenant: &TenantContext,
+        broadcast: &mut Broadcast,
+    ) -> AppResult<BroadcastStatus> {
+        let branding = self.tenant_branding(tenant).await?;
+        loop {
+            if self.shutdown.is_cancelled() {
+                return Ok(BroadcastStatus::Interrupted);
+            }
+            if self.broadcasts.is_cancelled(broadcast.id).await? {
+                return Ok(BroadcastStatus::Cancelled);
+            }
+
+            let ids: Vec<Uuid> = match &broadcast.target {
+                BroadcastTarget::Users(ids) => ids
+                    .iter()
+                    .copied()
+                    .filter(|id| broadcast.cursor.map_or(true, |after| *id > after))
+                    .take(self.broadcast_batch_size as usize)
+                    .collect(),
+                BroadcastTarget::Group(group_id) => self
+                    .groups
+                    .member_page(*group_id, broadcast.cursor, self.broadcast_batch_size)
+                    .await?
+                    .iter()
+                    .map(|m| m.user_id)
+                    .collect(),
+            };
+            let Some(last) = ids.last().copied() else {
+                return Ok(BroadcastStatus::Completed);
+            };
+
+            let users = self.users.find_by_ids(&ids).await?;
+            broadcast.summary.unresolved(ids.len() - users.len());
+            self.broadcast_batch(tenant, &users, broadcast.id, &broadcast.message, &branding, &mut broadcast.summary)
+                .await?;
+            broadcast.cursor = Some(last);
+            self.broadcasts.save_progress(broadcast).await?;
+
+            if (ids.len() as i64) < self.broadcast_batch_size {
+                return Ok(BroadcastStatus::Completed);
+            }
+        }
+    }
+
+    async fn template(&self, key: &str) -> AppResult<NotificationTemplate> {
+        self.templates
+            .find(key)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Template {} not found", key)))
+    }
+
+    async fn broadcast_batch(
+        &self,
+        tenant: &TenantContext,
+        users: &[User],
+        broadcast_id: Uuid,
+        message: &BroadcastMessage,
+        branding: &TenantBranding,
+        summary: &mut BroadcastSummary,
+    ) -> AppResult<()> {
+        // Recipients already queued stay queued, also when the tenant's daily
+        // allowance runs out partway; the rest of the broadcast is marked failed
+        RequestContext::check("broadcast")?;
+        let online = self.online_recipients(users).await;
+        for user in users {
+            summary.targeted += 1;
+            // Inactive covers accounts awaiting deletion
+            if !user.status.is_active() || user.deleted_at.is_some() || !user.preferences.notifications_enabled {
+                summary.skipped += 1;
+                continue;
+            }
+
+            let Some(channel) = self.delivery_channel(
+                user,
+                message.notification_type,
+                message.priority,
+                online.contains(&user.id),
+            ) else {
+                summary.skipped += 1;
+                continue;
+            };
+            let mut notification = Notification::new(
+                user.id,
+                message.notification_type,
+                channel,
+                user.email.clone(),
+                message.title.clone(),
+                message.message.clone(),
+            );
+            brand(&mut notification, branding);
+            notification
+                .metadata
+                .insert("broadcast_id".to_string(), serde_json::json!(broadcast_id));
+            self.limits.reserve_notification(tenant).await?;
+            self.repository.create(&notification).await?;
+            self.dispatcher.enqueue(notification).await?;
+            summary.queued += 1;
+        }
+        Ok(())
+    }
+
+    /// First channel on the user's route they can be reached on.
+    ///
+    /// The user's own route for the type replaces the configured one, email
+    /// is dropped for users who opted out of it, and users who are online
+    /// get in-app delivery whenever their route allows it.
+    fn delivery_channel(
+        &self,
+        user: &User,
+        notification_type: NotificationType,
+        priority: NotificationPriority,
+        online: bool,
+    ) -> Option<NotificationChannel> {
+        let route = user
+            .preferences
+            .channel_routing
+            .get(&notification_type)
+            .map(Vec::as_slice)
+            .unwrap_or_else(|| self.routing.route(notification_type, priority));
+        if online && route.contains(&NotificationChannel::InApp) {
+            return Some(NotificationChannel::InApp);
+        }
+        route.iter().copied().find(|channel| match channel {
+            NotificationChannel::Email => user.preferences.email_notifications,
+            NotificationChannel::InApp => true,
+        })
+    }
+
+    /// Presence is only a delivery hint, so a lookup failure sends everyone by preference
+    async fn online_recipients(&self, users: &[User]) -> HashSet<Uuid> {
+        let ids: Vec<Uuid> = users.iter().map(|user| user.id).collect();
+        match self.presence.online_among(&ids).await {
+            Ok(online) => online,
+            Err(e) => {
+                tracing::warn!("Presence lookup failed, delivering by preference: {}", e);
+                HashSet::new()
+            }
+        }
+    }
+}
+
+/// Template parameters with the tenant's branding added; callers' values win on conflicts
+fn branded(branding: &TenantBranding, params: HashMap<String, String>) -> HashMap<String, String> {
+    let mut merged = branding.template_params();
+    merged.extend(params);
+    merged
+}
+
+/// Record which tenant's identity a notification goes out under
+fn brand(notification: &mut Notification, branding: &TenantBranding) {
+    notification.tenant_id = Some(branding.tenant_id.clone());
+    notification.sender = branding.sender();
+}
+
+/// Whole minutes for link expiry notices, never shown as zero
+fn minutes(duration: std::time::Duration) -> String {
+    (duration.as_secs() / 60).max(1).to_string()
+}
+
+/// When a link sent now stops working, in the recipient's own timezone
+fn expires_at(user: &User, expires_in: std::time::Duration) -> String {
+    let expires_in = chrono::Duration::from_std(expires_in).unwrap_or_else(|_| chrono::Duration::days(365));
+    Locale::for_preferences(&user.preferences).date_time(Utc::now() + expires_in)
+}
+
+/// Parameters every user-addressed template can rely on; times are shown
+/// in the recipient's timezone and language
+fn user_params(user: &User) -> HashMap<String, String> {
+    let locale = Locale::for_preferences(&user.preferences);
+    let now = Utc::now();
+    HashMap::from([
+        ("first_name".to_string(), user.first_name.clone()),
+        ("last_name".to_string(), user.last_name.clone()),
+        ("username".to_string(), user.username.clone()),
+        ("email".to_string(), user.email.clone()),
+        ("sent_date".to_string(), locale.date(now)),
+        ("sent_at".to_string(), locale.date_time(now)),
+    ])
+}
@@ -0,0 +1,365 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+use reqwest::{Client, RequestBuilder, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::cache_service::CacheService;
+use super::user_service::UserService;
+use crate::config::{OAuthConfig, OAuthProviderConfig, OAuthProviderKind};
+use crate::models::{AppError, AppResult, ExternalIdentity, User, UserRole};
+use crate::repositories::OAuthIdentityRepository;
+use crate::utils::Logger;
+
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(10);
+const OIDC_SCOPES: &str = "openid email profile";
+const GITHUB_SCOPES: &str = "read:user user:email";
+const GITHUB_API: &str = "https://api.github.com";
+
+/// Endpoints of one configured provider
+struct Provider {
+    config: OAuthProviderConfig,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    /// OIDC userinfo endpoint; GitHub identities come from its REST API instead
+    userinfo_endpoint: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DiscoveryDocument {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: String,
+}
+
+/// What the callback needs to finish a sign-in started by `authorization_url`
+#[derive(Serialize, Deserialize)]
+struct PendingSignIn {
+    provider: String,
+    code_verifier: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Sign-in through external identity providers with the authorization-code flow.
+///
+/// Each sign-in carries a single-use `state` kept in the cache together with
+/// its PKCE verifier, so the redirect back may land on any instance and a
+/// replayed callback is refused. The code is exchanged over the back channel
+/// and the identity read from the provider's userinfo API with the resulting
+/// access token. Identities are linked to users on first use: to the account
+/// registered under the same verified email, or to a newly provisioned one.
+pub struct OAuthService {
+    providers: HashMap<String, Provider>,
+    identities: Arc<dyn OAuthIdentityRepository>,
+    users: Arc<UserService>,
+    cache: Arc<CacheService>,
+    client: Client,
+    /// Base the provider callback URLs are built on
+    public_base_url: String,
+    auto_provision: bool,
+    default_role: UserRole,
+    state_ttl: Duration,
+    logger: Arc<Logger>,
+}
+
+impl OAuthService {
+    /// None when no provider is configured. OIDC discovery documents are
+    /// fetched here, so a misconfigured issuer fails startup.
+    pub async fn new(
+        config: &OAuthConfig,
+        public_base_url: &str,
+        identities: Arc<dyn OAuthIdentityRepository>,
+        users: Arc<UserService>,
+        cache: Arc<CacheService>,
+        logger: Arc<Logger>,
+    ) -> AppResult<Option<Self>> {
+        if config.providers.is_empty() {
+            return Ok(None);
+        }
+        let client = Client::builder()
+            .timeout(PROVIDER_TIMEOUT)
+            .user_agent(concat!("crawler/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| AppError::Config(format!("Invalid OAuth client settings: {}", e)))?;
+
+        let mut providers = HashMap::new();
+        for provider in &config.providers {
+            let resolved = match &provider.kind {
+                OAuthProviderKind::Oidc { issuer } => {
+                    let discovery: DiscoveryDocument = client
+                        .get(format!("{}/.well-known/openid-configuration", issuer))
+                        .send()
+                        .await
+                        .and_then(|response| response.error_for_status())
+                        .map_err(|e| AppError::Config(format!("OIDC discovery for {} failed: {}", issuer, e)))?
+                        .json()
+                        .await
+                        .map_err(|e| AppError::Config(format!("Invalid OIDC discovery document of {}: {}", issuer, e)))?;
+                    Provider {
+                        config: provider.clone(),
+                        authorization_endpoint: discovery.authorization_endpoint,
+                        token_endpoint: discovery.token_endpoint,
+                        userinfo_endpoint: Some(discovery.userinfo_endpoint),
+                    }
+                }
+                OAuthProviderKind::GitHub => Provider {
+                    config: provider.clone(),
+                    authorization_endpoint: "https://github.com/login/oauth/authorize".to_string(),
+                    token_endpoint: "https://github.com/login/oauth/access_token".to_string(),
+                    userinfo_endpoint: None,
+                },
+            };
+            providers.insert(provider.name.clone(), resolved);
+        }
+
+        Ok(Some(Self {
+            providers,
+            identities,
+            users,
+            cache,
+            client,
+            public_base_url: public_base_url.trim_end_matches('/').to_string(),
+            auto_provision: config.auto_provision,
+            default_role: config.default_role.clone(),
+            state_ttl: config.state_ttl,
+            logger,
+        }))
+    }
+
+    /// Names of the providers users can sign in with
+    pub fn providers(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.providers.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// URL of the provider's consent page to send the browser to
+    pub async fn authorization_url(&self, provider_name: &str) -> AppResult<String> {
+        let provider = self.provider(provider_name)?;
+        let state = random_token();
+        let code_verifier = random_token();
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let pending = PendingSignIn {
+            provider: provider_name.to_string(),
+            code_verifier,
+        };
+        self.cache.set(&state_key(&state), &pending, Some(self.state_ttl)).await?;
+
+        let scopes = match provider.config.kind {
+            OAuthProviderKind::Oidc { .. } => OIDC_SCOPES,
+            OAuthProviderKind::GitHub => GITHUB_SCOPES,
+        };
+        let url = Url::parse_with_params(
+            &provider.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", provider.config.client_id.as_str()),
+                ("redirect_uri", self.redirect_uri(provider_name).as_str()),
+                ("scope", scopes),
+                ("state", state.as_str()),
+                ("code_challenge", code_challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| AppError::Config(format!("Invalid authorization endpoint of {}: {}", provider_name, e)))?;
+        Ok(url.into())
+    }
+
+    /// The user a provider's redirect signs in, for `UserService::external_login` to finish.
+    ///
+    /// Email matching and provisioning only happen for emails the provider
+    /// has verified; otherwise anyone able to register that address at the
+    /// provider could take over the local account.
+    pub async fn complete(&self, provider_name: &str, code: &str, state: &str) -> AppResult<User> {
+        let pending = self.take_pending(state).await?;
+        if pending.provider != provider_name {
+            return Err(invalid_state());
+        }
+        let provider = self.provider(provider_name)?;
+
+        let access_token = self.exchange_code(provider, code, &pending.code_verifier).await?;
+        let identity = match provider.config.kind {
+            OAuthProviderKind::Oidc { .. } => self.oidc_identity(provider, &access_token).await?,
+            OAuthProviderKind::GitHub => self.github_identity(provider, &access_token).await?,
+        };
+
+        self.resolve_user(&identity).await
+    }
+
+    async fn resolve_user(&self, identity: &ExternalIdentity) -> AppResult<User> {
+        if let Some(user_id) = self.identities.linked_user(&identity.provider, &identity.subject).await? {
+            return self
+                .users
+                .get_user_by_id(user_id)
+                .await?
+                .ok_or_else(|| AppError::Unauthorized("Linked account no longer exists".to_string()));
+        }
+
+        let Some(email) = identity.email.as_deref().filter(|_| identity.email_verified) else {
+            return Err(AppError::Unauthorized("Provider did not report a verified email".to_string()));
+        };
+        let user = match self.users.get_user_by_email(email).await? {
+            Some(user) => user,
+            None if self.auto_provision => {
+                self.users
+                    .provision_external_user(identity, self.default_role.clone())
+                    .await?
+            }
+            None => return Err(AppError::Unauthorized(format!("No account is registered for {}", email))),
+        };
+
+        if self.identities.link(identity, user.id).await? {
+            self.logger.info(&format!(
+                "Linked {} identity {} to user {}",
+                identity.provider, identity.subject, user.id
+            ));
+        }
+        Ok(user)
+    }
+
+    async fn exchange_code(&self, provider: &Provider, code: &str, code_verifier: &str) -> AppResult<String> {
+        let redirect_uri = self.redirect_uri(&provider.config.name);
+        let request = self.client.post(&provider.token_endpoint).form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("client_id", provider.config.client_id.as_str()),
+            ("client_secret", provider.config.client_secret.as_str()),
+            ("code_verifier", code_verifier),
+        ]);
+        // Providers report a bad code with an error body, with or without an error status
+        let response: TokenResponse = send(request, "token exchange", true).await?;
+        match (response.access_token, response.error) {
+            (Some(access_token), None) => Ok(access_token),
+            (_, error) => {
+                let reason = response.error_description.or(error).unwrap_or_default();
+                Err(AppError::Unauthorized(format!("Provider rejected the sign-in: {}", reason)))
+            }
+        }
+    }
+
+    async fn oidc_identity(&self, provider: &Provider, access_token: &str) -> AppResult<ExternalIdentity> {
+        let endpoint = provider.userinfo_endpoint.as_deref().unwrap_or_default();
+        let claims: Value = send(self.client.get(endpoint).bearer_auth(access_token), "userinfo", false).await?;
+        let text = |claim: &str| claims[claim].as_str().map(str::to_string);
+
+        Ok(ExternalIdentity {
+            provider: provider.config.name.clone(),
+            subject: text("sub").ok_or_else(|| AppError::Unauthorized("Provider returned no subject".to_string()))?,
+            email: text("email"),
+            // Some providers send the flag as a string
+            email_verified: claims["email_verified"].as_bool().unwrap_or(claims["email_verified"] == "true"),
+            first_name: text("given_name"),
+            last_name: text("family_name"),
+            username: text("preferred_username"),
+        })
+    }
+
+    async fn github_identity(&self, provider: &Provider, access_token: &str) -> AppResult<ExternalIdentity> {
+        let profile: Value = send(
+            github(self.client.get(format!("{}/user", GITHUB_API)), access_token),
+            "GitHub profile",
+            false,
+        )
+        .await?;
+        // The profile email is optional and unverified; the emails API says which are verified
+        let emails: Vec<Value> = send(
+            github(self.client.get(format!("{}/user/emails", GITHUB_API)), access_token),
+            "GitHub emails",
+            false,
+        )
+        .await?;
+        let primary = emails
+            .iter()
+            .find(|email| email["primary"].as_bool().unwrap_or(false) && email["verified"].as_bool().unwrap_or(false));
+
+        let subject = profile["id"]
+            .as_u64()
+            .map(|id| id.to_string())
+            .ok_or_else(|| AppError::Unauthorized("Provider returned no subject".to_string()))?;
+        let (first_name, last_name) = match profile["name"].as_str().map(str::trim) {
+            Some(name) if !name.is_empty() => match name.rsplit_once(' ') {
+                Some((first, last)) => (Some(first.to_string()), Some(last.to_string())),
+                None => (Some(name.to_string()), None),
+            },
+            _ => (None, None),
+        };
+
+        Ok(ExternalIdentity {
+            provider: provider.config.name.clone(),
+            subject,
+            email: primary.and_then(|email| email["email"].as_str()).map(str::to_string),
+            email_verified: primary.is_some(),
+            first_name,
+            last_name,
+            username: profile["login"].as_str().map(str::to_string),
+        })
+    }
+
+    fn provider(&self, name: &str) -> AppResult<&Provider> {
+        self.providers
+            .get(name)
+            .ok_or_else(|| AppError::NotFound(format!("Sign-in provider {} is not configured", name)))
+    }
+
+    fn redirect_uri(&self, provider_name: &str) -> String {
+        format!("{}/auth/oauth/{}/callback", self.public_base_url, provider_name)
+    }
+
+    /// States are single-use, so a replayed callback finds nothing
+    async fn take_pending(&self, state: &str) -> AppResult<PendingSignIn> {
+        self.cache.take(&state_key(state)).await?.ok_or_else(invalid_state)
+    }
+}
+
+async fn send<T: DeserializeOwned>(request: RequestBuilder, call: &str, allow_error_status: bool) -> AppResult<T> {
+    let response = request
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .map_err(|e| AppError::Internal(format!("Provider {} failed: {}", call, e)))?;
+    if !allow_error_status && !response.status().is_success() {
+        return Err(AppError::Unauthorized(format!(
+            "Provider {} returned {}",
+            call,
+            response.status()
+        )));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| AppError::Internal(format!("Unreadable provider {} response: {}", call, e)))
+}
+
+fn github(request: RequestBuilder, access_token: &str) -> RequestBuilder {
+    request
+        .bearer_auth(access_token)
+        .header("X-GitHub-Api-Version", "2022-11-28")
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn state_key(state: &str) -> String {
+    format!("oauth:{}", state)
+}
+
+fn invalid_state() -> AppError {
+    AppError::Unauthorized("Unknown or expired sign-in state".to_string())
+}
@@ -0,0 +1,254 @@
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::read_only::ReadOnlyMode;
+use super::user_service::UserService;
+use crate::models::{
+    AppError, AppResult, AuditAction, AuditEvent, CreateOrganizationRequest, OrgRole, Organization,
+    OrganizationMember, TenantContext, User, UserRole,
+};
+use crate::repositories::{AuditRepository, OrganizationRepository, TenantUserRepository};
+use crate::utils::Logger;
+
+/// Creates a tenant's organizations and manages who belongs to them.
+///
+/// Organizations are scoped by tenant like service accounts: one of another
+/// tenant is reported as not found, and only users onboarded into the
+/// tenant can join. What an org role lets its holder do to other members'
+/// accounts is decided by the `PolicyEngine` through `UserService`.
+pub struct OrganizationService {
+    organizations: Arc<OrganizationRepository>,
+    tenant_users: Arc<TenantUserRepository>,
+    users: Arc<UserService>,
+    audit: Arc<AuditRepository>,
+    read_only: Arc<ReadOnlyMode>,
+    logger: Arc<Logger>,
+}
+
+impl OrganizationService {
+    pub fn new(
+        organizations: Arc<OrganizationRepository>,
+        tenant_users: Arc<TenantUserRepository>,
+        users: Arc<UserService>,
+        audit: Arc<AuditRepository>,
+        read_only: Arc<ReadOnlyMode>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        Self {
+            organizations,
+            tenant_users,
+            users,
+            audit,
+            read_only,
+            logger,
+        }
+    }
+
+    /// Create an organization; needs the admin permission, as does appointing its first org admin
+    pub async fn create(
+        &self,
+        tenant: &TenantContext,
+        actor: &User,
+        request: CreateOrganizationRequest,
+    ) -> AppResult<Organization> {
+        self.read_only.check()?;
+        self.require_admin(actor)?;
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        let name = request.name.trim().to_string();
+        let organization = self
+            .organizations
+            .create(&Organization::new(tenant.tenant_id.clone(), name.clone(), Some(actor.id)))
+            .await?
+            .ok_or_else(|| AppError::Conflict(format!("Organization {} already exists", name)))?;
+        self.logger.info(&format!(
+            "Created organization {} ({}) in tenant {}",
+            organization.id, organization.name, tenant.tenant_id
+        ));
+        Ok(organization)
+    }
+
+    /// The tenant's organizations; admins only
+    pub async fn list(&self, tenant: &TenantContext, actor: &User) -> AppResult<Vec<Organization>> {
+        self.require_admin(actor)?;
+        self.organizations.list(&tenant.tenant_id).await
+    }
+
+    /// Members of an organization; visible to admins and to its moderators and org admins
+    pub async fn members(
+        &self,
+        tenant: &TenantContext,
+        actor: &User,
+        organization_id: Uuid,
+    ) -> AppResult<Vec<OrganizationMember>> {
+        self.require_organization(tenant, organization_id).await?;
+        if !self.is_admin(actor) {
+            let role = self.organizations.member_role(organization_id, actor.id).await?;
+            if !role.is_some_and(|role| role.level() >= OrgRole::OrgModerator.level()) {
+                return Err(AppError::Forbidden("Cannot view this organization's members".to_string()));
+            }
+        }
+        self.organizations.members(organization_id).await
+    }
+
+    /// Add a user to an organization, or change the role they hold there.
+    ///
+    /// Admins may add anyone in the tenant and set any role. Org admins may
+    /// only re-role plain users who are already members ranked below them,
+    /// and only to roles below their own: bringing in an outsider would give
+    /// them standing over an account nobody in the organization vouched for.
+    pub async fn set_member(
+        &self,
+        tenant: &TenantContext,
+        actor: &User,
+        organization_id: Uuid,
+        user_id: Uuid,
+        role: OrgRole,
+    ) -> AppResult<OrganizationMember> {
+        self.read_only.check()?;
+        self.require_organization(tenant, organization_id).await?;
+        let user = self.require_user(user_id).await?;
+        if user.deleted_at.is_some() {
+            return Err(AppError::Validation(vec![format!(
+                "User {} is deleted and cannot join organizations",
+                user_id
+            )]));
+        }
+        // Accounts from before tenants existed belong to the default tenant
+        let user_tenant = self
+            .tenant_users
+            .tenant_of(user_id)
+            .await?
+            .unwrap_or_else(|| TenantContext::default().tenant_id);
+        if user_tenant != tenant.tenant_id {
+            return Err(AppError::Validation(vec![format!(
+                "User {} belongs to another tenant and cannot join its organizations",
+                user_id
+            )]));
+        }
+        let current = self.organizations.member_role(organization_id, user_id).await?;
+        self.check_manager(actor, organization_id, &user, current, Some(role)).await?;
+
+        let member = self
+            .organizations
+            .set_member(&OrganizationMember {
+                organization_id,
+                user_id,
+                role,
+                added_by: Some(actor.id),
+                added_at: chrono::Utc::now(),
+            })
+            .await?;
+        self.record_audit(
+            user_id,
+            actor.id,
+            json!({
+                "organization_id": organization_id,
+                "from": current.map(|role| role.as_str()),
+                "to": role.as_str(),
+            }),
+        )
+        .await;
+        Ok(member)
+    }
+
+    /// Take a user out of an organization; allowed to whoever could have given them their role
+    pub async fn remove_member(
+        &self,
+        tenant: &TenantContext,
+        actor: &User,
+        organization_id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<()> {
+        self.read_only.check()?;
+        self.require_organization(tenant, organization_id).await?;
+        let user = self.require_user(user_id).await?;
+        let current = self
+            .organizations
+            .member_role(organization_id, user_id)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound(format!("User {} is not a member of organization {}", user_id, organization_id))
+            })?;
+        self.check_manager(actor, organization_id, &user, Some(current), None).await?;
+
+        self.organizations.remove_member(organization_id, user_id).await?;
+        self.record_audit(
+            user_id,
+            actor.id,
+            json!({ "organization_id": organization_id, "from": current.as_str(), "to": null }),
+        )
+        .await;
+        Ok(())
+    }
+
+    fn is_admin(&self, actor: &User) -> bool {
+        self.users.policies().allows(actor, "admin", None)
+    }
+
+    fn require_admin(&self, actor: &User) -> AppResult<()> {
+        if !self.is_admin(actor) {
+            return Err(AppError::Forbidden("Admin permission required".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Organizations of other tenants are not found rather than forbidden
+    async fn require_organization(&self, tenant: &TenantContext, id: Uuid) -> AppResult<Organization> {
+        self.organizations
+            .find(&tenant.tenant_id, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Organization {} not found", id)))
+    }
+
+    async fn require_user(&self, id: Uuid) -> AppResult<User> {
+        self.users
+            .get_user_by_id(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("User {} not found", id)))
+    }
+
+    /// Whether `actor` may move `user` from the `current` to the `next` role in an organization
+    async fn check_manager(
+        &self,
+        actor: &User,
+        organization_id: Uuid,
+        user: &User,
+        current: Option<OrgRole>,
+        next: Option<OrgRole>,
+    ) -> AppResult<()> {
+        if self.is_admin(actor) {
+            return Ok(());
+        }
+        let Some(actor_role) = self.organizations.member_role(organization_id, actor.id).await? else {
+            return Err(AppError::Forbidden("Cannot manage this organization's members".to_string()));
+        };
+        let Some(current) = current else {
+            return Err(AppError::Forbidden("Admin permission required to add organization members".to_string()));
+        };
+        if actor_role != OrgRole::OrgAdmin || user.role != UserRole::User || !actor_role.can_manage(&current) {
+            return Err(AppError::Forbidden("Cannot manage this member".to_string()));
+        }
+        if let Some(role) = next.filter(|role| !actor_role.can_manage(role)) {
+            return Err(AppError::Forbidden(format!("Cannot grant the {} organization role", role.as_str())));
+        }
+        Ok(())
+    }
+
+    /// Audit failures are logged rather than failing the change they describe
+    async fn record_audit(&self, user_id: Uuid, actor_id: Uuid, details: serde_json::Value) {
+        let event = AuditEvent::new(user_id, Some(actor_id), AuditAction::OrganizationRoleChanged, details);
+        if let Err(e) = self.audit.record(&event).await {
+            self.logger.warn(&format!(
+                "Failed to record {} for user {}: {}",
+                AuditAction::OrganizationRoleChanged.as_str(),
+                user_id,
+                e
+            ));
+        }
+    }
+}
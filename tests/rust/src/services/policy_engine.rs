@@ -0,0 +1,184 @@
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::AuthorizationConfig;
+use crate::models::{AppError, AppResult, PolicyCondition, PolicyEffect, PolicyResource, PolicyRule, User, UserRole};
+use crate::repositories::PolicyRepository;
+use crate::utils::Logger;
+
+/// Role permissions granted without any configuration; the permissions
+/// each role had before policies were configurable, plus the rules behind
+/// the user endpoints
+fn builtin_rules() -> Vec<PolicyRule> {
+    let roles: [(&str, &[&str]); 4] = [
+        ("user", &["read"]),
+        ("moderator", &["read", "write", "moderate"]),
+        ("admin", &["read", "write", "moderate", "admin", "delete", "users:*", "reports:*"]),
+        ("superadmin", &["read", "write", "moderate", "admin", "delete", "super_admin", "users:*", "reports:*"]),
+    ];
+    let mut rules: Vec<PolicyRule> = roles
+        .into_iter()
+        .flat_map(|(role, permissions)| permissions.iter().map(move |permission| PolicyRule::allow(role, permission)))
+        .collect();
+    rules.push(PolicyRule::allow("*", "users:read").on("user", PolicyCondition::Owner));
+    rules.push(PolicyRule::allow("*", "users:update").on("user", PolicyCondition::Owner));
+    // Organization admins and moderators reach the members they outrank there, whatever their global role
+    rules.push(PolicyRule::allow("*", "users:read").on("user", PolicyCondition::OrgModerator));
+    rules.push(PolicyRule::allow("*", "users:update").on("user", PolicyCondition::OrgAdmin));
+    rules.push(PolicyRule::allow("*", "users:manage").on("user", PolicyCondition::OrgAdmin));
+    rules
+}
+
+/// Decides what roles may do, from rules that can change without a deploy.
+///
+/// Rules come from the built-in set, the configured policy file and the
+/// `authz_policies` table, and are re-read periodically. A permission is
+/// granted when some rule allows it and none denies it; resource-level
+/// rules only count for checks made against a resource of their kind.
+pub struct PolicyEngine {
+    rules: RwLock<Arc<Vec<PolicyRule>>>,
+    /// Digest of the rules in force, the same on every instance holding the same rules
+    fingerprint: RwLock<String>,
+    repository: Option<Arc<PolicyRepository>>,
+    config: AuthorizationConfig,
+    logger: Arc<Logger>,
+}
+
+impl PolicyEngine {
+    /// Load every rule; an invalid rule fails startup rather than being skipped
+    pub async fn load(
+        config: &AuthorizationConfig,
+        repository: Option<Arc<PolicyRepository>>,
+        logger: Arc<Logger>,
+    ) -> AppResult<Self> {
+        let engine = Self {
+            rules: RwLock::new(Arc::new(Vec::new())),
+            fingerprint: RwLock::new(String::new()),
+            repository,
+            config: config.clone(),
+            logger,
+        };
+        engine.reload().await?;
+        Ok(engine)
+    }
+
+    /// Re-read the policy file and database, returning how many rules are in force.
+    /// On failure the rules loaded before stay in force.
+    pub async fn reload(&self) -> AppResult<usize> {
+        let mut rules = if self.config.builtin_policies {
+            builtin_rules()
+        } else {
+            Vec::new()
+        };
+        if let Some(path) = &self.config.policy_file {
+            let raw = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| AppError::Config(format!("Cannot read policy file {}: {}", path, e)))?;
+            let configured: Vec<PolicyRule> = serde_json::from_str(&raw)
+                .map_err(|e| AppError::Config(format!("Invalid policy file {}: {}", path, e)))?;
+            rules.extend(configured);
+        }
+        if let Some(repository) = &self.repository {
+            rules.extend(repository.all().await?);
+        }
+
+        let errors: Vec<String> = rules.iter().flat_map(PolicyRule::validate).collect();
+        if !errors.is_empty() {
+            return Err(AppError::Config(errors.join("; ")));
+        }
+        let count = rules.len();
+        let encoded = serde_json::to_vec(&rules)
+            .map_err(|e| AppError::Internal(format!("Unserializable policy rules: {}", e)))?;
+        let digest = Sha256::digest(&encoded);
+        let fingerprint: String = digest[..8].iter().map(|byte| format!("{:02x}", byte)).collect();
+        *self.rules.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(rules);
+        *self.fingerprint.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = fingerprint;
+        Ok(count)
+    }
+
+    /// Whether `actor` may exercise `permission`, on `resource` when given.
+    /// Accounts that cannot sign in hold no permissions.
+    pub fn allows(&self, actor: &User, permission: &str, resource: Option<&PolicyResource>) -> bool {
+        if !actor.can_authenticate() {
+            return false;
+        }
+        self.decide(|rule| rule.applies(actor, permission, resource))
+    }
+
+    /// Whether `role` holds `permission` outright, without any resource-level rule
+    pub fn role_allows(&self, role: &UserRole, permission: &str) -> bool {
+        self.decide(|rule| rule.resource.is_none() && rule.covers(role, permission))
+    }
+
+    /// Permission patterns `role` is granted and withheld outright, sorted.
+    /// Granted patterns a deny takes in entirely are left out.
+    pub fn role_permissions(&self, role: &UserRole) -> (Vec<String>, Vec<String>) {
+        let rules = self.rules();
+        let outright = || rules.iter().filter(|rule| rule.resource.is_none() && rule.is_for(role));
+        let denied: BTreeSet<String> = outright()
+            .filter(|rule| rule.effect == PolicyEffect::Deny)
+            .map(|rule| rule.permission.clone())
+            .collect();
+        let allowed: BTreeSet<String> = outright()
+            .filter(|rule| rule.effect == PolicyEffect::Allow)
+            .filter(|rule| {
+                !outright().any(|deny| deny.effect == PolicyEffect::Deny && deny.includes(&rule.permission))
+            })
+            .map(|rule| rule.permission.clone())
+            .collect();
+        (allowed.into_iter().collect(), denied.into_iter().collect())
+    }
+
+    /// Changes whenever the rules in force do, for keying what is derived from them
+    pub fn fingerprint(&self) -> String {
+        self.fingerprint.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// `AppError::Forbidden` unless `actor` may exercise `permission`
+    pub fn require(&self, actor: &User, permission: &str, resource: Option<&PolicyResource>) -> AppResult<()> {
+        if self.allows(actor, permission, resource) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!("Missing the {} permission", permission)))
+        }
+    }
+
+    /// Rules currently in force, for inspection
+    pub fn rules(&self) -> Arc<Vec<PolicyRule>> {
+        self.rules.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    pub fn spawn_reload(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        let interval = self.config.reload_interval.max(Duration::from_secs(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes at once, and the rules were just loaded
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.reload().await {
+                    self.logger.error(&format!("Failed to reload authorization policies: {}", e));
+                }
+            }
+        })
+    }
+
+    fn decide(&self, applies: impl Fn(&PolicyRule) -> bool) -> bool {
+        let rules = self.rules();
+        let mut allowed = false;
+        for rule in rules.iter().filter(|rule| applies(rule)) {
+            match rule.effect {
+                PolicyEffect::Deny => return false,
+                PolicyEffect::Allow => allowed = true,
+            }
+        }
+        allowed
+    }
+}
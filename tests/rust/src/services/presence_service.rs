@@ -0,0 +1,59 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::cache_service::CacheService;
+use crate::models::AppResult;
+
+/// Which users are online right now, from client heartbeats.
+///
+/// Each heartbeat refreshes a Redis key with a short TTL, so a user whose
+/// client stops sending them is considered offline once it expires; no
+/// sweeper is needed and every instance sees the same answer.
+pub struct PresenceService {
+    cache: Arc<CacheService>,
+    ttl: Duration,
+}
+
+impl PresenceService {
+    pub fn new(cache: Arc<CacheService>, ttl: Duration) -> Self {
+        Self { cache, ttl }
+    }
+
+    /// How long a user counts as online after their last heartbeat
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub async fn heartbeat(&self, user_id: Uuid) -> AppResult<()> {
+        self.cache
+            .set(&presence_key(user_id), &chrono::Utc::now(), Some(self.ttl))
+            .await
+    }
+
+    /// Mark a user offline immediately, e.g. on sign-out
+    pub async fn go_offline(&self, user_id: Uuid) -> AppResult<()> {
+        self.cache.delete(&presence_key(user_id)).await
+    }
+
+    pub async fn is_online(&self, user_id: Uuid) -> AppResult<bool> {
+        Ok(!self.online_among(&[user_id]).await?.is_empty())
+    }
+
+    /// The subset of `user_ids` currently online
+    pub async fn online_among(&self, user_ids: &[Uuid]) -> AppResult<HashSet<Uuid>> {
+        let keys: Vec<String> = user_ids.iter().map(|id| presence_key(*id)).collect();
+        let present = self.cache.exists_many(&keys).await?;
+        Ok(user_ids
+            .iter()
+            .zip(present)
+            .filter(|(_, online)| *online)
+            .map(|(id, _)| *id)
+            .collect())
+    }
+}
+
+fn presence_key(user_id: Uuid) -> String {
+    format!("presence:{}", user_id)
+}
@@ -0,0 +1,236 @@
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use super::cache_service::CacheService;
+use crate::config::QuotaConfig;
+use crate::database::Database;
+use crate::models::{AppError, AppResult};
+use crate::utils::{Logger, Metrics};
+
+/// Counters outlive their billing period by this long so late flushes still see them
+const COUNTER_GRACE_DAYS: u64 = 7;
+const FLUSH_BATCH: usize = 500;
+
+/// Caller an API call is metered against
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum QuotaSubject {
+    User(Uuid),
+    ApiKey(String),
+}
+
+impl QuotaSubject {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            QuotaSubject::User(_) => "user",
+            QuotaSubject::ApiKey(_) => "api_key",
+        }
+    }
+
+    pub fn id(&self) -> String {
+        match self {
+            QuotaSubject::User(id) => id.to_string(),
+            QuotaSubject::ApiKey(key_id) => key_id.clone(),
+        }
+    }
+
+    fn parse(member: &str) -> Option<Self> {
+        match member.split_once(':')? {
+            ("user", id) => id.parse().ok().map(QuotaSubject::User),
+            ("api_key", id) => Some(QuotaSubject::ApiKey(id.to_string())),
+            _ => None,
+        }
+    }
+
+    fn member(&self) -> String {
+        format!("{}:{}", self.kind(), self.id())
+    }
+}
+
+/// Outcome of metering a single call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaDecision {
+    Allowed { used: u64, limit: u64 },
+    Exceeded { limit: u64, resets_at: DateTime<Utc> },
+}
+
+/// Usage of a subject within the current billing period
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub subject: QuotaSubject,
+    pub period_start: NaiveDate,
+    pub resets_at: DateTime<Utc>,
+    pub used: u64,
+    pub limit: u64,
+    pub remaining: u64,
+}
+
+/// Meters API calls per user and API key over monthly billing periods.
+///
+/// Counters live in Redis for cheap per-request increments and are
+/// periodically flushed to the `api_usage` table for billing.
+pub struct QuotaService {
+    cache: Arc<CacheService>,
+    database: Arc<Database>,
+    config: QuotaConfig,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl QuotaService {
+    pub fn new(
+        cache: Arc<CacheService>,
+        database: Arc<Database>,
+        config: QuotaConfig,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        Self {
+            cache,
+            database,
+            config,
+            metrics,
+            logger,
+        }
+    }
+
+    /// Count a call against the subject's quota, refusing it once the limit is reached
+    pub async fn record_call(&self, subject: &QuotaSubject) -> AppResult<QuotaDecision> {
+        let now = Utc::now();
+        let period = period_start(now);
+        let resets_at = period_end(period);
+        let limit = self.limit_for(subject);
+        let key = counter_key(period, subject);
+
+        let ttl = (resets_at - now).to_std().unwrap_or_default()
+            + std::time::Duration::from_secs(COUNTER_GRACE_DAYS * 86_400);
+        let used = self.cache.increment(&key, 1, Some(ttl)).await?;
+
+        if used as u64 > limit {
+            // Rejected calls are not billable, so give the increment back
+            self.cache.increment(&key, -1, None).await?;
+            self.metrics
+                .increment_labeled_counter("quota.exceeded", &[("subject", subject.kind())])
+                .await?;
+            return Ok(QuotaDecision::Exceeded { limit, resets_at });
+        }
+
+        self.cache
+            .add_to_set(&dirty_key(period), &subject.member())
+            .await?;
+        Ok(QuotaDecision::Allowed {
+            used: used as u64,
+            limit,
+        })
+    }
+
+    /// Current period usage for a subject
+    pub async fn usage(&self, subject: &QuotaSubject) -> AppResult<UsageReport> {
+        let period = period_start(Utc::now());
+        let limit = self.limit_for(subject);
+        let used = self.cache.get_counter(&counter_key(period, subject)).await?.max(0) as u64;
+
+        Ok(UsageReport {
+            subject: subject.clone(),
+            period_start: period,
+            resets_at: period_end(period),
+            used,
+            limit,
+            remaining: limit.saturating_sub(used),
+        })
+    }
+
+    /// Persist counters touched since the last flush, returning how many were written
+    pub async fn flush(&self) -> AppResult<usize> {
+        let current = period_start(Utc::now());
+        let previous = current
+            .checked_sub_months(Months::new(1))
+            .ok_or_else(|| AppError::Internal("Billing period underflow".to_string()))?;
+
+        let mut flushed = 0;
+        for period in [previous, current] {
+            loop {
+                let members = self.cache.pop_from_set(&dirty_key(period), FLUSH_BATCH).await?;
+                if members.is_empty() {
+                    break;
+                }
+                for subject in members.iter().filter_map(|m| QuotaSubject::parse(m)) {
+                    let count = self.cache.get_counter(&counter_key(period, &subject)).await?;
+                    self.persist(period, &subject, count).await?;
+                    flushed += 1;
+                }
+            }
+        }
+
+        self.metrics
+            .add_to_counter("quota.flushed", flushed as u64)
+            .await?;
+        Ok(flushed)
+    }
+
+    /// Flush counters on the configured interval, and once more on shutdown
+    /// so calls since the last tick are not lost
+    pub fn spawn_flush(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.flush_interval);
+            loop {
+                let stopping = tokio::select! {
+                    _ = shutdown.cancelled() => true,
+                    _ = ticker.tick() => false,
+                };
+                if let Err(e) = self.flush().await {
+                    self.logger.error(&format!("Failed to flush API usage: {}", e));
+                }
+                if stopping {
+                    break;
+                }
+            }
+        })
+    }
+
+    fn limit_for(&self, subject: &QuotaSubject) -> u64 {
+        match subject {
+            QuotaSubject::User(_) => self.config.user_monthly_limit,
+            QuotaSubject::ApiKey(_) => self.config.api_key_monthly_limit,
+        }
+    }
+
+    async fn persist(&self, period: NaiveDate, subject: &QuotaSubject, count: i64) -> AppResult<()> {
+        // Redis holds absolute counts, so keeping the larger value makes repeat flushes harmless
+        sqlx::query(
+            "INSERT INTO api_usage (subject_type, subject_id, period_start, call_count) \
+             VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (subject_type, subject_id, period_start) DO UPDATE SET \
+                call_count = GREATEST(api_usage.call_count, EXCLUDED.call_count), \
+                updated_at = NOW()",
+        )
+        .bind(subject.kind())
+        .bind(subject.id())
+        .bind(period)
+        .bind(count)
+        .execute(self.database.pool())
+        .await?;
+        Ok(())
+    }
+}
+
+fn period_start(now: DateTime<Utc>) -> NaiveDate {
+    NaiveDate::from_ymd_opt(now.year(), now.month(), 1).unwrap_or_else(|| now.date_naive())
+}
+
+fn period_end(start: NaiveDate) -> DateTime<Utc> {
+    let next = start.checked_add_months(Months::new(1)).unwrap_or(start);
+    Utc.from_utc_datetime(&next.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+fn counter_key(period: NaiveDate, subject: &QuotaSubject) -> String {
+    format!("quota:{}:{}", period.format("%Y-%m"), subject.member())
+}
+
+fn dirty_key(period: NaiveDate) -> String {
+    format!("quota:{}:dirty", period.format("%Y-%m"))
+}
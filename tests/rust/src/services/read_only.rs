@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use super::cache_service::CacheService;
+use super::mode_switch::ModeSwitch;
+use crate::config::MaintenanceConfig;
+use crate::models::{AppError, AppResult};
+use crate::utils::{Logger, Metrics};
+
+const READ_ONLY_KEY: &str = "maintenance:read_only";
+
+/// Why and since when the application has been read-only
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadOnlyState {
+    pub reason: String,
+    pub since: DateTime<Utc>,
+    /// None when the mode was switched on by configuration
+    pub enabled_by: Option<Uuid>,
+}
+
+/// A switch refusing writes while the database is under maintenance.
+///
+/// Services check it at the top of every mutating method; signing in,
+/// refreshing tokens and signing out keep working so users can still read.
+/// Like maintenance mode it is a `ModeSwitch`, reaching every instance
+/// within the sync interval.
+pub struct ReadOnlyMode {
+    switch: Arc<ModeSwitch<ReadOnlyState>>,
+    logger: Arc<Logger>,
+}
+
+impl ReadOnlyMode {
+    /// Start read-only when configured to, otherwise as the other instances are
+    pub async fn load(
+        cache: Arc<CacheService>,
+        config: &MaintenanceConfig,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> AppResult<Self> {
+        let mode = Self {
+            switch: Arc::new(ModeSwitch::new(
+                "read-only mode",
+                READ_ONLY_KEY,
+                "maintenance.read_only",
+                cache,
+                config.sync_interval,
+                metrics,
+                logger.clone(),
+            )),
+            logger,
+        };
+        if config.read_only {
+            mode.enable(&config.read_only_reason, None).await?;
+        } else {
+            mode.switch.refresh().await?;
+        }
+        Ok(mode)
+    }
+
+    /// Refuse a write while read-only
+    pub fn check(&self) -> AppResult<()> {
+        match self.status() {
+            Some(state) => Err(AppError::ReadOnly(state.reason)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.status().is_some()
+    }
+
+    pub fn status(&self) -> Option<ReadOnlyState> {
+        self.switch.status()
+    }
+
+    /// Switch read-only mode on everywhere; already being read-only keeps the original start
+    pub async fn enable(&self, reason: &str, actor: Option<Uuid>) -> AppResult<ReadOnlyState> {
+        if let Some(current) = self.status() {
+            return Ok(current);
+        }
+        let state = ReadOnlyState {
+            reason: reason.to_string(),
+            since: Utc::now(),
+            enabled_by: actor,
+        };
+        self.switch.switch_on(state.clone()).await?;
+        self.logger
+            .warn(&format!("Read-only mode switched on by {:?}: {}", actor, state.reason));
+        Ok(state)
+    }
+
+    /// Switch read-only mode off everywhere
+    pub async fn disable(&self, actor: Option<Uuid>) -> AppResult<()> {
+        if self.switch.switch_off().await? {
+            self.logger.warn(&format!("Read-only mode switched off by {:?}", actor));
+        }
+        Ok(())
+    }
+
+    /// Pick up the mode as switched by any instance
+    pub async fn refresh(&self) -> AppResult<()> {
+        self.switch.refresh().await
+    }
+
+    pub fn spawn_sync(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        self.switch.clone().spawn_sync(shutdown)
+    }
+}
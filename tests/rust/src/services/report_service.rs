@@ -0,0 +1,373 @@
+use chrono::{DateTime, Duration, Utc};
+use cron::Schedule;
+use sqlx::Row;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::cache_service::CacheService;
+use super::channels::{EmailAttachment, EmailChannel, EmailMessage};
+use crate::config::{ReportConfig, ReportFormat};
+use crate::database::Database;
+use crate::models::{AppError, AppResult};
+use crate::utils::{pdf, Logger, Metrics};
+
+/// How long a replica's claim on a fire time is kept; well past any clock skew between replicas
+const FIRE_CLAIM_TTL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// Kinds of periodic reports sent to admins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportKind {
+    NewSignups,
+    NotificationDelivery,
+    LockedAccounts,
+}
+
+impl ReportKind {
+    pub const ALL: [ReportKind; 3] = [
+        ReportKind::NewSignups,
+        ReportKind::NotificationDelivery,
+        ReportKind::LockedAccounts,
+    ];
+
+    pub fn slug(&self) -> &'static str {
+        match self {
+            ReportKind::NewSignups => "new_signups",
+            ReportKind::NotificationDelivery => "notification_delivery",
+            ReportKind::LockedAccounts => "locked_accounts",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ReportKind::NewSignups => "New signups",
+            ReportKind::NotificationDelivery => "Notification delivery rates",
+            ReportKind::LockedAccounts => "Locked accounts",
+        }
+    }
+}
+
+/// Tabular report data, independent of the output format
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub kind: ReportKind,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Generates periodic admin reports and emails them on a cron schedule.
+///
+/// Every replica runs the schedule, so each report's fire time is claimed
+/// in the cache first and only the replica winning the claim sends it.
+pub struct ReportService {
+    database: Arc<Database>,
+    email: Arc<EmailChannel>,
+    cache: Arc<CacheService>,
+    config: ReportConfig,
+    schedule: Schedule,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl ReportService {
+    pub fn new(
+        database: Arc<Database>,
+        email: Arc<EmailChannel>,
+        cache: Arc<CacheService>,
+        config: ReportConfig,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> AppResult<Self> {
+        let schedule = Schedule::from_str(&config.schedule).map_err(|e| {
+            AppError::Config(format!("Invalid REPORT_SCHEDULE {}: {}", config.schedule, e))
+        })?;
+
+        Ok(Self {
+            database,
+            email,
+            cache,
+            config,
+            schedule,
+            metrics,
+            logger,
+        })
+    }
+
+    /// Build a report covering the configured window ending now
+    pub async fn generate(&self, kind: ReportKind) -> AppResult<Report> {
+        let window_end = Utc::now();
+        let window_start = window_end - Duration::days(self.config.window_days);
+
+        let (headers, rows) = match kind {
+            ReportKind::NewSignups => self.new_signups(window_start, window_end).await?,
+            ReportKind::NotificationDelivery => {
+                self.notification_delivery(window_start, window_end).await?
+            }
+            ReportKind::LockedAccounts => self.locked_accounts().await?,
+        };
+
+        Ok(Report {
+            kind,
+            window_start,
+            window_end,
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows,
+        })
+    }
+
+    /// Render a report in the configured format as an email attachment
+    #[tracing::instrument(name = "render", skip_all)]
+    pub fn render(&self, report: &Report) -> AppResult<EmailAttachment> {
+        let date = report.window_end.format("%Y-%m-%d");
+
+        match self.config.format {
+            ReportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                let csv_error = |e: csv::Error| AppError::Internal(format!("CSV rendering failed: {}", e));
+                writer.write_record(&report.headers).map_err(csv_error)?;
+                for row in &report.rows {
+                    writer.write_record(row).map_err(csv_error)?;
+                }
+                let content = writer
+                    .into_inner()
+                    .map_err(|e| AppError::Internal(format!("CSV rendering failed: {}", e)))?;
+
+                Ok(EmailAttachment {
+                    filename: format!("{}_{}.csv", report.kind.slug(), date),
+                    content_type: "text/csv".to_string(),
+                    content,
+                })
+            }
+            ReportFormat::Pdf => {
+                let mut lines = vec![
+                    format!(
+                        "Period: {} to {}",
+                        report.window_start.format("%Y-%m-%d %H:%M UTC"),
+                        report.window_end.format("%Y-%m-%d %H:%M UTC")
+                    ),
+                    String::new(),
+                    report.headers.join(" | "),
+                ];
+                lines.extend(report.rows.iter().map(|row| row.join(" | ")));
+
+                Ok(EmailAttachment {
+                    filename: format!("{}_{}.pdf", report.kind.slug(), date),
+                    content_type: "application/pdf".to_string(),
+                    content: pdf::render_text(report.kind.title(), &lines),
+                })
+            }
+        }
+    }
+
+    /// Generate every report and email them to all active admins
+    pub async fn deliver(&self) -> AppResult<usize> {
+        self.deliver_kinds(&ReportKind::ALL).await
+    }
+
+    /// Email the reports of `kinds` to all active admins
+    async fn deliver_kinds(&self, kinds: &[ReportKind]) -> AppResult<usize> {
+        let recipients = self.admin_recipients().await?;
+        if recipients.is_empty() {
+            self.logger.warn("No active admins to receive scheduled reports");
+            return Ok(0);
+        }
+
+        let mut attachments = Vec::new();
+        let mut summary = Vec::new();
+        for &kind in kinds {
+            let report = self.generate(kind).await?;
+            summary.push(format!("- {}: {} rows", kind.title(), report.rows.len()));
+            attachments.push(self.render(&report)?);
+        }
+
+        let count = recipients.len();
+        self.email
+            .send(EmailMessage {
+                from: None,
+                to: recipients,
+                subject: format!("Scheduled reports for {}", Utc::now().format("%Y-%m-%d")),
+                text_body: format!(
+                    "Reports covering the last {} days are attached.\n\n{}\n",
+                    self.config.window_days,
+                    summary.join("\n")
+                ),
+                html_body: None,
+                attachments,
+                track_as: None,
+            })
+            .await?;
+
+        self.metrics.increment_counter("reports.delivered").await?;
+        self.logger
+            .info(&format!("Delivered scheduled reports to {} admins", count));
+        Ok(count)
+    }
+
+    /// Deliver reports at each cron fire time until shutdown
+    pub fn spawn_scheduler(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(next) = self.schedule.upcoming(Utc).next() {
+                let wait = (next - Utc::now()).to_std().unwrap_or_default();
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = tokio::time::sleep(wait) => {}
+                }
+
+                let delivered = match self.claim_fire(next).await {
+                    Ok(kinds) if kinds.is_empty() => continue,
+                    Ok(kinds) => {
+                        let delivered = self.deliver_kinds(&kinds).await;
+                        // A replica firing a little later may still send what this one could not
+                        if !matches!(delivered, Ok(count) if count > 0) {
+                            self.release_fire(next, &kinds).await;
+                        }
+                        delivered
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = delivered {
+                    self.logger.error(&format!("Scheduled report delivery failed: {}", e));
+                    let _ = self.metrics.increment_counter("reports.failed").await;
+                }
+            }
+        })
+    }
+
+    /// The reports this replica won the `fire_time` delivery of; the others are another replica's to send.
+    /// Failing partway gives back what was already claimed
+    async fn claim_fire(&self, fire_time: DateTime<Utc>) -> AppResult<Vec<ReportKind>> {
+        let mut claimed = Vec::new();
+        for kind in ReportKind::ALL {
+            match self.cache.set_if_absent(&fire_key(kind, fire_time), &true, FIRE_CLAIM_TTL).await {
+                Ok(true) => claimed.push(kind),
+                Ok(false) => {}
+                Err(e) => {
+                    self.release_fire(fire_time, &claimed).await;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(claimed)
+    }
+
+    /// Drop this replica's claims on `fire_time`; one left behind only expires with `FIRE_CLAIM_TTL`
+    async fn release_fire(&self, fire_time: DateTime<Utc>, kinds: &[ReportKind]) {
+        for &kind in kinds {
+            if let Err(e) = self.cache.delete(&fire_key(kind, fire_time)).await {
+                self.logger
+                    .warn(&format!("Failed to release {} report claim: {}", kind.slug(), e));
+            }
+        }
+    }
+
+    async fn new_signups(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> AppResult<(Vec<&'static str>, Vec<Vec<String>>)> {
+        let rows = sqlx::query(
+            "SELECT DATE(created_at) AS day, role, COUNT(*) AS signups FROM users \
+             WHERE created_at >= $1 AND created_at < $2 \
+             GROUP BY day, role ORDER BY day, role",
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let mut table = Vec::with_capacity(rows.len());
+        for row in rows {
+            table.push(vec![
+                row.try_get::<chrono::NaiveDate, _>("day")?.to_string(),
+                row.try_get::<String, _>("role")?,
+                row.try_get::<i64, _>("signups")?.to_string(),
+            ]);
+        }
+        Ok((vec!["date", "role", "signups"], table))
+    }
+
+    async fn notification_delivery(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> AppResult<(Vec<&'static str>, Vec<Vec<String>>)> {
+        let rows = sqlx::query(
+            "SELECT notification_type, COUNT(*) AS total, \
+                    COUNT(*) FILTER (WHERE status IN ('sent', 'delivered', 'read')) AS delivered, \
+                    COUNT(*) FILTER (WHERE status = 'failed') AS failed \
+             FROM notifications WHERE created_at >= $1 AND created_at < $2 \
+             GROUP BY notification_type ORDER BY notification_type",
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let mut table = Vec::with_capacity(rows.len());
+        for row in rows {
+            let total: i64 = row.try_get("total")?;
+            let delivered: i64 = row.try_get("delivered")?;
+            let rate = if total > 0 {
+                delivered as f64 * 100.0 / total as f64
+            } else {
+                0.0
+            };
+            table.push(vec![
+                row.try_get::<String, _>("notification_type")?,
+                total.to_string(),
+                delivered.to_string(),
+                row.try_get::<i64, _>("failed")?.to_string(),
+                format!("{:.1}%", rate),
+            ]);
+        }
+        Ok((
+            vec!["notification_type", "total", "delivered", "failed", "delivery_rate"],
+            table,
+        ))
+    }
+
+    async fn locked_accounts(&self) -> AppResult<(Vec<&'static str>, Vec<Vec<String>>)> {
+        let rows = sqlx::query(
+            "SELECT id, username, email, failed_login_attempts, locked_until, last_login FROM users \
+             WHERE locked_until > NOW() AND deleted_at IS NULL \
+             ORDER BY locked_until DESC, username",
+        )
+        .fetch_all(self.database.pool())
+        .await?;
+
+        let mut table = Vec::with_capacity(rows.len());
+        for row in rows {
+            let last_login: Option<DateTime<Utc>> = row.try_get("last_login")?;
+            table.push(vec![
+                row.try_get::<uuid::Uuid, _>("id")?.to_string(),
+                row.try_get::<String, _>("username")?,
+                row.try_get::<String, _>("email")?,
+                row.try_get::<i32, _>("failed_login_attempts")?.to_string(),
+                row.try_get::<DateTime<Utc>, _>("locked_until")?.to_rfc3339(),
+                last_login.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            ]);
+        }
+        Ok((
+            vec!["user_id", "username", "email", "failed_attempts", "locked_until", "last_login"],
+            table,
+        ))
+    }
+
+    async fn admin_recipients(&self) -> AppResult<Vec<String>> {
+        let emails = sqlx::query_scalar(
+            "SELECT email FROM users WHERE role IN ('admin', 'superadmin') \
+             AND status = 'active' AND deleted_at IS NULL AND region IS NULL ORDER BY email",
+        )
+        .fetch_all(self.database.pool())
+        .await?;
+        Ok(emails)
+    }
+}
+
+/// Key of the claim on delivering `kind` at `fire_time`, shared by every replica
+fn fire_key(kind: ReportKind, fire_time: DateTime<Utc>) -> String {
+    format!("report:{}:{}", kind.slug(), fire_time.timestamp())
+}
@@ -0,0 +1,314 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+use super::xml::{self, Element};
+use crate::models::{AppError, AppResult};
+
+pub const PROTOCOL_NS: &str = "urn:oasis:names:tc:SAML:2.0:protocol";
+pub const ASSERTION_NS: &str = "urn:oasis:names:tc:SAML:2.0:assertion";
+const DSIG_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+const EXC_C14N_NS: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+
+const STATUS_SUCCESS: &str = "urn:oasis:names:tc:SAML:2.0:status:Success";
+const BEARER: &str = "urn:oasis:names:tc:SAML:2.0:cm:bearer";
+pub const EMAIL_NAME_ID: &str = "urn:oasis:names:tc:SAML:1.1:nameid-format:emailAddress";
+const EXC_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+const ENVELOPED_SIGNATURE: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+const SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+
+/// What a response has to match to be accepted
+pub struct Expected<'a> {
+    pub issuer: &'a str,
+    pub audience: &'a str,
+    /// Our assertion consumer service URL
+    pub recipient: &'a str,
+    pub key: &'a RsaPublicKey,
+    pub clock_skew: Duration,
+}
+
+/// The parts of a validated assertion sign-in needs
+#[derive(Debug)]
+pub struct Assertion {
+    pub id: String,
+    /// Id of the authentication request answered; None for unsolicited responses
+    pub in_response_to: Option<String>,
+    pub name_id: String,
+    pub name_id_format: Option<String>,
+    pub attributes: HashMap<String, Vec<String>>,
+}
+
+fn rejected(reason: impl Into<String>) -> AppError {
+    AppError::Unauthorized(format!("SAML response rejected: {}", reason.into()))
+}
+
+/// Validate a decoded `SAMLResponse` and read its assertion.
+///
+/// Exactly one plain assertion is accepted, signed itself or through the
+/// response around it, and only what that signature covers is read. IDs
+/// must be unique in the document, so the signed element cannot be swapped
+/// for an unsigned one of the same ID.
+pub fn validate(document: &str, expected: &Expected<'_>, now: DateTime<Utc>) -> AppResult<Assertion> {
+    let response = xml::parse(document)?;
+    if !response.is(PROTOCOL_NS, "Response") {
+        return Err(rejected("not a SAML response"));
+    }
+
+    let mut ids = HashSet::new();
+    for element in response.descendants() {
+        if let Some(id) = element.attribute("ID") {
+            if !ids.insert(id) {
+                return Err(rejected(format!("duplicate ID {}", id)));
+            }
+        }
+    }
+
+    let status = response
+        .child(PROTOCOL_NS, "Status")
+        .and_then(|status| status.child(PROTOCOL_NS, "StatusCode"))
+        .and_then(|code| code.attribute("Value"))
+        .unwrap_or_default();
+    if status != STATUS_SUCCESS {
+        return Err(AppError::Unauthorized(format!(
+            "Identity provider did not complete the sign-in: {}",
+            status
+        )));
+    }
+    if let Some(destination) = response.attribute("Destination") {
+        if destination != expected.recipient {
+            return Err(rejected(format!("sent to {}", destination)));
+        }
+    }
+    if response.child(ASSERTION_NS, "EncryptedAssertion").is_some() {
+        return Err(rejected("encrypted assertions are not supported"));
+    }
+    let mut assertions = response.children_named(ASSERTION_NS, "Assertion");
+    let (Some(assertion), None) = (assertions.next(), assertions.next()) else {
+        return Err(rejected("exactly one assertion is required"));
+    };
+
+    match assertion.child(DSIG_NS, "Signature") {
+        Some(signature) => verify_signature(assertion, signature, expected.key)?,
+        None => {
+            let signature = response
+                .child(DSIG_NS, "Signature")
+                .ok_or_else(|| rejected("neither the response nor its assertion is signed"))?;
+            verify_signature(&response, signature, expected.key)?;
+        }
+    }
+
+    let issuer = assertion.child(ASSERTION_NS, "Issuer").map(Element::text);
+    if issuer.as_deref() != Some(expected.issuer) {
+        return Err(rejected(format!("unexpected issuer {}", issuer.unwrap_or_default())));
+    }
+    check_conditions(assertion, expected, now)?;
+    let (name_id, name_id_format, in_response_to) = check_subject(assertion, expected, now)?;
+
+    let mut attributes: HashMap<String, Vec<String>> = HashMap::new();
+    for statement in assertion.children_named(ASSERTION_NS, "AttributeStatement") {
+        for attribute in statement.children_named(ASSERTION_NS, "Attribute") {
+            let Some(name) = attribute.attribute("Name") else {
+                continue;
+            };
+            attributes.entry(name.to_string()).or_default().extend(
+                attribute
+                    .children_named(ASSERTION_NS, "AttributeValue")
+                    .map(Element::text)
+                    .filter(|value| !value.is_empty()),
+            );
+        }
+    }
+
+    Ok(Assertion {
+        id: assertion
+            .attribute("ID")
+            .ok_or_else(|| rejected("assertion has no ID"))?
+            .to_string(),
+        in_response_to,
+        name_id,
+        name_id_format,
+        attributes,
+    })
+}
+
+/// Check an enveloped signature over `signed`: its single reference, digest and RSA-SHA256 signature value
+fn verify_signature(signed: &Element, signature: &Element, key: &RsaPublicKey) -> AppResult<()> {
+    let signed_info = signature
+        .child(DSIG_NS, "SignedInfo")
+        .ok_or_else(|| rejected("signature has no SignedInfo"))?;
+    let canonicalization = signed_info
+        .child(DSIG_NS, "CanonicalizationMethod")
+        .ok_or_else(|| rejected("signature has no canonicalization method"))?;
+    if canonicalization.attribute("Algorithm") != Some(EXC_C14N) {
+        return Err(rejected("only exclusive canonicalization is supported"));
+    }
+    let method = signed_info
+        .child(DSIG_NS, "SignatureMethod")
+        .and_then(|method| method.attribute("Algorithm"));
+    if method != Some(RSA_SHA256) {
+        return Err(rejected("only RSA-SHA256 signatures are accepted"));
+    }
+
+    let mut references = signed_info.children_named(DSIG_NS, "Reference");
+    let (Some(reference), None) = (references.next(), references.next()) else {
+        return Err(rejected("signature must have exactly one reference"));
+    };
+    let id = signed.attribute("ID").ok_or_else(|| rejected("signed element has no ID"))?;
+    if reference.attribute("URI") != Some(format!("#{}", id).as_str()) {
+        return Err(rejected("signature does not cover the signed element"));
+    }
+
+    let mut enveloped = false;
+    let mut prefixes = Vec::new();
+    for transform in reference
+        .child(DSIG_NS, "Transforms")
+        .into_iter()
+        .flat_map(|transforms| transforms.children_named(DSIG_NS, "Transform"))
+    {
+        match transform.attribute("Algorithm") {
+            Some(ENVELOPED_SIGNATURE) => enveloped = true,
+            Some(EXC_C14N) => prefixes = inclusive_prefixes(transform),
+            other => {
+                return Err(rejected(format!("unsupported transform {}", other.unwrap_or_default())));
+            }
+        }
+    }
+    if !enveloped {
+        return Err(rejected("signature must be enveloped"));
+    }
+    let digest_method = reference
+        .child(DSIG_NS, "DigestMethod")
+        .and_then(|method| method.attribute("Algorithm"));
+    if digest_method != Some(SHA256) {
+        return Err(rejected("only SHA-256 digests are accepted"));
+    }
+    let digest_value = reference
+        .child(DSIG_NS, "DigestValue")
+        .map(|value| decode_base64(&value.text()))
+        .transpose()?
+        .ok_or_else(|| rejected("signature has no digest"))?;
+    let digest = Sha256::digest(signed.canonicalize(Some(signature), &prefixes).as_bytes());
+    if digest.as_slice() != digest_value.as_slice() {
+        return Err(rejected("digest does not match the signed content"));
+    }
+
+    let value = signature
+        .child(DSIG_NS, "SignatureValue")
+        .map(|value| decode_base64(&value.text()))
+        .transpose()?
+        .ok_or_else(|| rejected("signature has no value"))?;
+    let value = Signature::try_from(value.as_slice()).map_err(|_| rejected("malformed signature value"))?;
+    let canonical_info = signed_info.canonicalize(None, &inclusive_prefixes(canonicalization));
+    VerifyingKey::<Sha256>::new(key.clone())
+        .verify(canonical_info.as_bytes(), &value)
+        .map_err(|_| rejected("signature does not verify against the provider's certificate"))
+}
+
+fn inclusive_prefixes(method: &Element) -> Vec<String> {
+    method
+        .child(EXC_C14N_NS, "InclusiveNamespaces")
+        .and_then(|namespaces| namespaces.attribute("PrefixList"))
+        .map(|list| list.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn check_conditions(assertion: &Element, expected: &Expected<'_>, now: DateTime<Utc>) -> AppResult<()> {
+    let conditions = assertion
+        .child(ASSERTION_NS, "Conditions")
+        .ok_or_else(|| rejected("assertion has no conditions"))?;
+    if let Some(not_before) = timestamp(conditions, "NotBefore")? {
+        if now + expected.clock_skew < not_before {
+            return Err(rejected("assertion is not yet valid"));
+        }
+    }
+    if let Some(not_on_or_after) = timestamp(conditions, "NotOnOrAfter")? {
+        if now - expected.clock_skew >= not_on_or_after {
+            return Err(rejected("assertion has expired"));
+        }
+    }
+    // Every audience restriction must name us, and there has to be at least one
+    let mut restrictions = conditions.children_named(ASSERTION_NS, "AudienceRestriction").peekable();
+    if restrictions.peek().is_none() {
+        return Err(rejected("assertion is not restricted to an audience"));
+    }
+    for restriction in restrictions {
+        if !restriction
+            .children_named(ASSERTION_NS, "Audience")
+            .any(|audience| audience.text() == expected.audience)
+        {
+            return Err(rejected("assertion is meant for another service provider"));
+        }
+    }
+    Ok(())
+}
+
+/// NameID, its format and the request answered, from a bearer confirmation meant for us
+fn check_subject(
+    assertion: &Element,
+    expected: &Expected<'_>,
+    now: DateTime<Utc>,
+) -> AppResult<(String, Option<String>, Option<String>)> {
+    let subject = assertion
+        .child(ASSERTION_NS, "Subject")
+        .ok_or_else(|| rejected("assertion has no subject"))?;
+    let name_id = subject
+        .child(ASSERTION_NS, "NameID")
+        .ok_or_else(|| rejected("subject has no NameID"))?;
+    if name_id.text().is_empty() {
+        return Err(rejected("subject has an empty NameID"));
+    }
+
+    let mut in_response_to = None;
+    let mut confirmed = false;
+    for confirmation in subject.children_named(ASSERTION_NS, "SubjectConfirmation") {
+        if confirmation.attribute("Method") != Some(BEARER) {
+            continue;
+        }
+        let Some(data) = confirmation.child(ASSERTION_NS, "SubjectConfirmationData") else {
+            continue;
+        };
+        if data.attribute("Recipient") != Some(expected.recipient) {
+            continue;
+        }
+        match timestamp(data, "NotOnOrAfter")? {
+            Some(not_on_or_after) if now - expected.clock_skew < not_on_or_after => {}
+            _ => continue,
+        }
+        in_response_to = data.attribute("InResponseTo").map(str::to_string);
+        confirmed = true;
+        break;
+    }
+    if !confirmed {
+        return Err(rejected("no valid bearer confirmation for this service provider"));
+    }
+    Ok((
+        name_id.text(),
+        name_id.attribute("Format").map(str::to_string),
+        in_response_to,
+    ))
+}
+
+fn timestamp(element: &Element, attribute: &str) -> AppResult<Option<DateTime<Utc>>> {
+    element
+        .attribute(attribute)
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|_| rejected(format!("invalid {} {}", attribute, value)))
+        })
+        .transpose()
+}
+
+/// Base64 as found in XML, which may be wrapped over several lines
+pub fn decode_base64(value: &str) -> AppResult<Vec<u8>> {
+    let compact: String = value.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    STANDARD
+        .decode(compact)
+        .map_err(|_| rejected("invalid base64 content"))
+}
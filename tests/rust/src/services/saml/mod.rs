@@ -0,0 +1,283 @@
+pub mod assertion;
+pub mod xml;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use reqwest::Url;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::RsaPublicKey;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use x509_cert::der::{DecodePem, Encode};
+use uuid::Uuid;
+use x509_cert::Certificate;
+
+use self::assertion::{Assertion, Expected, ASSERTION_NS, EMAIL_NAME_ID, PROTOCOL_NS};
+use self::xml::escape;
+use super::cache_service::CacheService;
+use super::user_service::UserService;
+use crate::config::{SamlAttributeMap, SamlConfig, SamlIdentityProvider};
+use crate::models::{AppError, AppResult, ExternalIdentity, User, UserRole};
+use crate::repositories::OAuthIdentityRepository;
+use crate::utils::Logger;
+
+/// Provider name SAML identities are linked under
+const PROVIDER: &str = "saml";
+const METADATA_NS: &str = "urn:oasis:names:tc:SAML:2.0:metadata";
+const POST_BINDING: &str = "urn:oasis:names:tc:SAML:2.0:bindings:HTTP-POST";
+
+/// Service-provider-initiated SAML 2.0 sign-in.
+///
+/// Sign-in starts with an AuthnRequest sent to the identity provider with
+/// the HTTP-Redirect binding; its id is kept in the cache, single-use, and
+/// only responses answering a pending request are accepted, so unsolicited
+/// and replayed responses are refused. Responses come back to the assertion
+/// consumer service with the HTTP-POST binding and must carry an assertion
+/// signed with the provider's certificate. Only emails in the allowed
+/// domains sign in: to the account linked to the NameID, to the account
+/// registered under the email, or to one provisioned just in time.
+pub struct SamlService {
+    idp: SamlIdentityProvider,
+    key: RsaPublicKey,
+    sp_entity_id: String,
+    acs_url: String,
+    allowed_domains: Vec<String>,
+    jit_provisioning: bool,
+    default_role: UserRole,
+    attributes: SamlAttributeMap,
+    request_ttl: Duration,
+    clock_skew: Duration,
+    identities: Arc<dyn OAuthIdentityRepository>,
+    users: Arc<UserService>,
+    cache: Arc<CacheService>,
+    logger: Arc<Logger>,
+}
+
+impl SamlService {
+    /// None unless an identity provider is configured. Its certificate is
+    /// read here, so a missing or unreadable one fails startup.
+    pub fn from_config(
+        config: &SamlConfig,
+        public_base_url: &str,
+        identities: Arc<dyn OAuthIdentityRepository>,
+        users: Arc<UserService>,
+        cache: Arc<CacheService>,
+        logger: Arc<Logger>,
+    ) -> AppResult<Option<Self>> {
+        let Some(idp) = &config.idp else {
+            return Ok(None);
+        };
+        let base = public_base_url.trim_end_matches('/');
+        Ok(Some(Self {
+            key: certificate_key(&idp.certificate_file)?,
+            idp: idp.clone(),
+            sp_entity_id: config
+                .sp_entity_id
+                .clone()
+                .unwrap_or_else(|| format!("{}/auth/saml/metadata", base)),
+            acs_url: format!("{}/auth/saml/acs", base),
+            allowed_domains: config.allowed_domains.clone(),
+            jit_provisioning: config.jit_provisioning,
+            default_role: config.default_role.clone(),
+            attributes: config.attributes.clone(),
+            request_ttl: config.request_ttl,
+            clock_skew: config.clock_skew,
+            identities,
+            users,
+            cache,
+            logger,
+        }))
+    }
+
+    /// URL of the provider's sign-in page carrying a new authentication request
+    pub async fn login_url(&self) -> AppResult<String> {
+        // XML ids must not start with a digit
+        let id = format!("_{}", Uuid::new_v4().simple());
+        let now = Utc::now();
+        // Kept under its id until answered, holding when it was sent
+        self.cache.set(&request_key(&id), &now, Some(self.request_ttl)).await?;
+
+        let request = format!(
+            "<samlp:AuthnRequest xmlns:samlp=\"{}\" xmlns:saml=\"{}\" ID=\"{}\" Version=\"2.0\" \
+             IssueInstant=\"{}\" Destination=\"{}\" AssertionConsumerServiceURL=\"{}\" ProtocolBinding=\"{}\">\
+             <saml:Issuer>{}</saml:Issuer>\
+             <samlp:NameIDPolicy Format=\"{}\" AllowCreate=\"true\"/>\
+             </samlp:AuthnRequest>",
+            PROTOCOL_NS,
+            ASSERTION_NS,
+            id,
+            now.to_rfc3339_opts(SecondsFormat::Secs, true),
+            escape(&self.idp.sso_url),
+            escape(&self.acs_url),
+            POST_BINDING,
+            escape(&self.sp_entity_id),
+            EMAIL_NAME_ID,
+        );
+        // The redirect binding carries the request raw-deflated, then base64 encoded
+        let failed = |e: std::io::Error| AppError::Internal(format!("Failed to encode SAML request: {}", e));
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(request.as_bytes()).map_err(failed)?;
+        let encoded = STANDARD.encode(encoder.finish().map_err(failed)?);
+        let url = Url::parse_with_params(&self.idp.sso_url, &[("SAMLRequest", encoded.as_str())])
+            .map_err(|e| AppError::Config(format!("Invalid SAML_IDP_SSO_URL: {}", e)))?;
+        Ok(url.into())
+    }
+
+    /// The user a `SAMLResponse` posted to the assertion consumer service
+    /// signs in, for `UserService::external_login` to finish
+    pub async fn complete(&self, saml_response: &str) -> AppResult<User> {
+        let document = String::from_utf8(assertion::decode_base64(saml_response)?)
+            .map_err(|_| AppError::Unauthorized("SAML response is not UTF-8".to_string()))?;
+        let expected = Expected {
+            issuer: &self.idp.entity_id,
+            audience: &self.sp_entity_id,
+            recipient: &self.acs_url,
+            key: &self.key,
+            clock_skew: chrono::Duration::from_std(self.clock_skew)
+                .map_err(|e| AppError::Config(format!("Invalid SAML clock skew: {}", e)))?,
+        };
+        let assertion = assertion::validate(&document, &expected, Utc::now())?;
+
+        let request_id = assertion
+            .in_response_to
+            .as_deref()
+            .ok_or_else(|| AppError::Unauthorized("Unsolicited SAML responses are not accepted".to_string()))?;
+        self.take_pending(request_id).await?;
+        // Assertions are single-use too, for as long as they could still validate
+        let first_use = self
+            .cache
+            .set_if_absent(&assertion_key(&assertion.id), &true, self.request_ttl + self.clock_skew)
+            .await?;
+        if !first_use {
+            return Err(AppError::Unauthorized("SAML assertion was already used".to_string()));
+        }
+
+        let identity = self.identity(&assertion)?;
+        self.resolve_user(&identity).await
+    }
+
+    /// Service provider metadata for registering this service with the identity provider
+    pub fn metadata(&self) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <md:EntityDescriptor xmlns:md=\"{}\" entityID=\"{}\">\
+             <md:SPSSODescriptor AuthnRequestsSigned=\"false\" WantAssertionsSigned=\"true\" \
+             protocolSupportEnumeration=\"{}\">\
+             <md:NameIDFormat>{}</md:NameIDFormat>\
+             <md:AssertionConsumerService Binding=\"{}\" Location=\"{}\" index=\"0\" isDefault=\"true\"/>\
+             </md:SPSSODescriptor>\
+             </md:EntityDescriptor>",
+            METADATA_NS,
+            escape(&self.sp_entity_id),
+            PROTOCOL_NS,
+            EMAIL_NAME_ID,
+            POST_BINDING,
+            escape(&self.acs_url),
+        )
+    }
+
+    /// User fields from the mapped attributes, refused unless the email is in an allowed domain
+    fn identity(&self, assertion: &Assertion) -> AppResult<ExternalIdentity> {
+        let attribute = |name: &str| {
+            assertion
+                .attributes
+                .get(name)
+                .and_then(|values| values.first())
+                .cloned()
+        };
+        let email = attribute(&self.attributes.email)
+            .or_else(|| {
+                (assertion.name_id_format.as_deref() == Some(EMAIL_NAME_ID)).then(|| assertion.name_id.clone())
+            })
+            .map(|email| email.trim().to_lowercase())
+            .ok_or_else(|| AppError::Unauthorized("Identity provider did not send an email".to_string()))?;
+        let domain = email.rsplit_once('@').map(|(_, domain)| domain).unwrap_or_default();
+        if !self.allowed_domains.iter().any(|allowed| allowed == domain) {
+            self.logger.warn(&format!(
+                "Refused SAML sign-in of {} outside the allowed domains",
+                assertion.name_id
+            ));
+            return Err(AppError::Forbidden(format!(
+                "Accounts of {} cannot sign in with single sign-on",
+                domain
+            )));
+        }
+
+        Ok(ExternalIdentity {
+            provider: PROVIDER.to_string(),
+            subject: assertion.name_id.clone(),
+            email: Some(email),
+            // The provider is trusted for the allowed domains, which is what vouching for the email means here
+            email_verified: true,
+            first_name: attribute(&self.attributes.first_name),
+            last_name: attribute(&self.attributes.last_name),
+            username: attribute(&self.attributes.username),
+        })
+    }
+
+    async fn resolve_user(&self, identity: &ExternalIdentity) -> AppResult<User> {
+        if let Some(user_id) = self.identities.linked_user(&identity.provider, &identity.subject).await? {
+            return self
+                .users
+                .get_user_by_id(user_id)
+                .await?
+                .ok_or_else(|| AppError::Unauthorized("Linked account no longer exists".to_string()));
+        }
+
+        let email = identity.email.as_deref().unwrap_or_default();
+        let user = match self.users.get_user_by_email(email).await? {
+            Some(user) => user,
+            None if self.jit_provisioning => {
+                let user = self
+                    .users
+                    .provision_external_user(identity, self.default_role.clone())
+                    .await?;
+                self.logger
+                    .info(&format!("Provisioned user {} from SAML sign-in", user.id));
+                user
+            }
+            None => return Err(AppError::Unauthorized(format!("No account is registered for {}", email))),
+        };
+
+        if self.identities.link(identity, user.id).await? {
+            self.logger.info(&format!(
+                "Linked SAML identity {} to user {}",
+                identity.subject, user.id
+            ));
+        }
+        Ok(user)
+    }
+
+    /// Request ids are single-use, so a replayed response finds nothing
+    async fn take_pending(&self, request_id: &str) -> AppResult<DateTime<Utc>> {
+        self.cache
+            .take(&request_key(request_id))
+            .await?
+            .ok_or_else(|| AppError::Unauthorized("Unknown or expired SAML request".to_string()))
+    }
+}
+
+/// Public key of the provider's signing certificate
+fn certificate_key(path: &str) -> AppResult<RsaPublicKey> {
+    let invalid = |e: String| AppError::Config(format!("Invalid SAML certificate {}: {}", path, e));
+    let pem = std::fs::read(path).map_err(|e| invalid(e.to_string()))?;
+    let certificate = Certificate::from_pem(&pem).map_err(|e| invalid(e.to_string()))?;
+    let key_info = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|e| invalid(e.to_string()))?;
+    RsaPublicKey::from_public_key_der(&key_info).map_err(|e| invalid(format!("not an RSA key: {}", e)))
+}
+
+fn request_key(id: &str) -> String {
+    format!("saml:request:{}", id)
+}
+
+fn assertion_key(id: &str) -> String {
+    format!("saml:assertion:{}", id)
+}
@@ -0,0 +1,334 @@
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::models::{AppError, AppResult};
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// An element of a parsed document, prefixes kept as written.
+///
+/// Canonicalization has to reproduce the provider's prefixes and namespace
+/// declarations, so names are not resolved at parse time; every element
+/// keeps the namespaces in scope at it instead.
+#[derive(Debug)]
+pub struct Element {
+    prefix: Option<String>,
+    pub name: String,
+    /// Attributes other than namespace declarations, as (prefix, local name, value)
+    attributes: Vec<(Option<String>, String, String)>,
+    /// Namespaces in scope by prefix, "" for the default namespace
+    scope: BTreeMap<String, String>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Debug)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+fn malformed(reason: impl std::fmt::Display) -> AppError {
+    AppError::Unauthorized(format!("Malformed SAML message: {}", reason))
+}
+
+/// Parse a document into its root element.
+///
+/// Document type declarations are refused outright, which rules out entity
+/// expansion attacks. Comments and processing instructions are dropped;
+/// a signature over content containing them then fails to verify.
+pub fn parse(document: &str) -> AppResult<Element> {
+    let mut reader = Reader::from_str(document);
+    let mut open: Vec<Element> = Vec::new();
+    let mut root = None;
+    loop {
+        match reader.read_event().map_err(malformed)? {
+            Event::Start(start) => {
+                let element = start_element(&start, open.last().map(|parent| &parent.scope))?;
+                open.push(element);
+            }
+            Event::Empty(start) => {
+                let element = start_element(&start, open.last().map(|parent| &parent.scope))?;
+                close(element, &mut open, &mut root)?;
+            }
+            Event::End(_) => {
+                let element = open.pop().ok_or_else(|| malformed("unbalanced end tag"))?;
+                close(element, &mut open, &mut root)?;
+            }
+            Event::Text(text) => {
+                let raw = std::str::from_utf8(&text).map_err(malformed)?;
+                let text = unescape(&normalize_newlines(raw)).map_err(malformed)?.into_owned();
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(Node::Text(text)),
+                    None if text.trim().is_empty() => {}
+                    None => return Err(malformed("text outside the root element")),
+                }
+            }
+            Event::CData(data) => {
+                let text = std::str::from_utf8(&data).map_err(malformed)?;
+                let parent = open.last_mut().ok_or_else(|| malformed("text outside the root element"))?;
+                parent.children.push(Node::Text(normalize_newlines(text)));
+            }
+            Event::DocType(_) => return Err(malformed("document type declarations are not accepted")),
+            Event::Comment(_) | Event::PI(_) | Event::Decl(_) => {}
+            Event::Eof => break,
+        }
+    }
+    if !open.is_empty() {
+        return Err(malformed("unclosed element"));
+    }
+    root.ok_or_else(|| malformed("empty document"))
+}
+
+fn start_element(start: &BytesStart, parent_scope: Option<&BTreeMap<String, String>>) -> AppResult<Element> {
+    let (prefix, name) = split_name(std::str::from_utf8(start.name().as_ref()).map_err(malformed)?);
+    let mut scope = parent_scope.cloned().unwrap_or_default();
+    let mut attributes = Vec::new();
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(malformed)?;
+        let key = std::str::from_utf8(attribute.key.as_ref()).map_err(malformed)?;
+        // Literal whitespace in attribute values is normalized to spaces, character references are not
+        let raw = std::str::from_utf8(&attribute.value).map_err(malformed)?;
+        let raw = raw.replace(['\t', '\n', '\r'], " ");
+        let value = unescape(&raw).map_err(malformed)?.into_owned();
+        match split_name(key) {
+            (None, local) if local == "xmlns" => {
+                scope.insert(String::new(), value);
+            }
+            (Some(prefix), local) if prefix == "xmlns" => {
+                scope.insert(local, value);
+            }
+            (prefix, local) => attributes.push((prefix, local, value)),
+        }
+    }
+    Ok(Element {
+        prefix,
+        name,
+        attributes,
+        scope,
+        children: Vec::new(),
+    })
+}
+
+fn close(element: Element, open: &mut [Element], root: &mut Option<Element>) -> AppResult<()> {
+    match open.last_mut() {
+        Some(parent) => parent.children.push(Node::Element(element)),
+        None if root.is_none() => *root = Some(element),
+        None => return Err(malformed("more than one root element")),
+    }
+    Ok(())
+}
+
+fn split_name(qualified: &str) -> (Option<String>, String) {
+    match qualified.split_once(':') {
+        Some((prefix, local)) => (Some(prefix.to_string()), local.to_string()),
+        None => (None, qualified.to_string()),
+    }
+}
+
+fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+impl Element {
+    /// Namespace the element's name is in
+    pub fn namespace(&self) -> Option<&str> {
+        self.scope
+            .get(self.prefix.as_deref().unwrap_or_default())
+            .map(String::as_str)
+            .filter(|uri| !uri.is_empty())
+    }
+
+    pub fn is(&self, namespace: &str, name: &str) -> bool {
+        self.name == name && self.namespace() == Some(namespace)
+    }
+
+    /// Value of an attribute without a prefix
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(prefix, local, _)| prefix.is_none() && local == name)
+            .map(|(_, _, value)| value.as_str())
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    pub fn children_named<'a>(&'a self, namespace: &'a str, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.elements().filter(move |element| element.is(namespace, name))
+    }
+
+    pub fn child(&self, namespace: &str, name: &str) -> Option<&Element> {
+        self.children_named(namespace, name).next()
+    }
+
+    /// Text content of the element's own text children, trimmed
+    pub fn text(&self) -> String {
+        let text: String = self
+            .children
+            .iter()
+            .filter_map(|child| match child {
+                Node::Text(text) => Some(text.as_str()),
+                Node::Element(_) => None,
+            })
+            .collect();
+        text.trim().to_string()
+    }
+
+    /// Every element of the subtree, this one included
+    pub fn descendants(&self) -> Vec<&Element> {
+        let mut found = vec![self];
+        let mut index = 0;
+        while index < found.len() {
+            found.extend(found[index].elements());
+            index += 1;
+        }
+        found
+    }
+
+    /// Exclusive XML canonicalization (without comments) of the subtree.
+    ///
+    /// `omit` is left out of the output, which is how the enveloped
+    /// signature transform drops the signature from what it signs.
+    /// `inclusive` lists prefixes rendered wherever they are in scope, as
+    /// the InclusiveNamespaces PrefixList asks; `#default` is the default
+    /// namespace.
+    pub fn canonicalize(&self, omit: Option<&Element>, inclusive: &[String]) -> String {
+        let inclusive: Vec<&str> = inclusive
+            .iter()
+            .map(|prefix| if prefix == "#default" { "" } else { prefix.as_str() })
+            .collect();
+        let mut out = String::new();
+        self.write_canonical(&mut out, &BTreeMap::new(), omit, &inclusive);
+        out
+    }
+
+    fn write_canonical(
+        &self,
+        out: &mut String,
+        rendered: &BTreeMap<String, String>,
+        omit: Option<&Element>,
+        inclusive: &[&str],
+    ) {
+        // Only namespaces the element visibly uses are declared, and only where the output has not already
+        let mut used = BTreeSet::new();
+        used.insert(self.prefix.clone().unwrap_or_default());
+        for (prefix, _, _) in &self.attributes {
+            if let Some(prefix) = prefix.as_ref().filter(|prefix| *prefix != "xml") {
+                used.insert(prefix.clone());
+            }
+        }
+        for prefix in inclusive {
+            if self.scope.contains_key(*prefix) {
+                used.insert(prefix.to_string());
+            }
+        }
+        let mut in_output = rendered.clone();
+        let mut declarations = Vec::new();
+        for prefix in used {
+            let uri = self.scope.get(&prefix).cloned().unwrap_or_default();
+            let unchanged = match rendered.get(&prefix) {
+                Some(current) => *current == uri,
+                // An empty default namespace only needs declaring to undo an inherited one
+                None => prefix.is_empty() && uri.is_empty(),
+            };
+            if !unchanged {
+                declarations.push((prefix.clone(), uri.clone()));
+                in_output.insert(prefix, uri);
+            }
+        }
+
+        let name = self.qualified_name();
+        out.push('<');
+        out.push_str(&name);
+        for (prefix, uri) in &declarations {
+            if prefix.is_empty() {
+                out.push_str(" xmlns=\"");
+            } else {
+                out.push_str(" xmlns:");
+                out.push_str(prefix);
+                out.push_str("=\"");
+            }
+            out.push_str(&escape_attribute(uri));
+            out.push('"');
+        }
+        let mut attributes: Vec<(&str, String, &str)> = self
+            .attributes
+            .iter()
+            .map(|(prefix, local, value)| {
+                let namespace = match prefix.as_deref() {
+                    None => "",
+                    Some("xml") => XML_NAMESPACE,
+                    Some(prefix) => self.scope.get(prefix).map(String::as_str).unwrap_or_default(),
+                };
+                let name = match prefix {
+                    Some(prefix) => format!("{}:{}", prefix, local),
+                    None => local.clone(),
+                };
+                (namespace, name, value.as_str())
+            })
+            .collect();
+        attributes.sort_by(|a, b| {
+            let local = |name: &str| name.rsplit(':').next().unwrap_or_default().to_string();
+            (a.0, local(&a.1)).cmp(&(b.0, local(&b.1)))
+        });
+        for (_, name, value) in attributes {
+            out.push(' ');
+            out.push_str(&name);
+            out.push_str("=\"");
+            out.push_str(&escape_attribute(value));
+            out.push('"');
+        }
+        out.push('>');
+
+        for child in &self.children {
+            match child {
+                Node::Text(text) => out.push_str(&escape_text(text)),
+                Node::Element(element) if omit.is_some_and(|omit| std::ptr::eq(omit, element)) => {}
+                Node::Element(element) => element.write_canonical(out, &in_output, omit, inclusive),
+            }
+        }
+        out.push_str("</");
+        out.push_str(&name);
+        out.push('>');
+    }
+
+    fn qualified_name(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{}:{}", prefix, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\r', "&#xD;")
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;")
+        .replace('\t', "&#x9;")
+        .replace('\n', "&#xA;")
+        .replace('\r', "&#xD;")
+}
+
+/// Escape a value for a document this service writes
+pub fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,338 @@
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::config::SearchConfig;
+use crate::models::{AppError, AppResult, User, UserEvent, UserFilters};
+use crate::repositories::{SearchFacets, UserSearchEntry, UserSearchRepository};
+use crate::utils::{Logger, Metrics};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// Which backend answered a search request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchBackend {
+    Elasticsearch,
+    Postgres,
+}
+
+/// Search hits together with role/status facets
+#[derive(Debug, Clone, Serialize)]
+pub struct UserSearchResults {
+    pub hits: Vec<UserSearchEntry>,
+    pub facets: SearchFacets,
+    pub backend: SearchBackend,
+}
+
+/// Keeps a search index in step with user mutation events
+pub trait SearchIndexer: Send + Sync {
+    fn spawn_indexer(
+        self: Arc<Self>,
+        events: broadcast::Receiver<UserEvent>,
+        shutdown: CancellationToken,
+    ) -> JoinHandle<()>;
+}
+
+/// Stands in for the cluster indexer when search indexing is switched off
+pub struct NoopSearchIndexer;
+
+impl SearchIndexer for NoopSearchIndexer {
+    /// The receiver is dropped at once, so the event bus never waits on it
+    fn spawn_indexer(
+        self: Arc<Self>,
+        events: broadcast::Receiver<UserEvent>,
+        _shutdown: CancellationToken,
+    ) -> JoinHandle<()> {
+        drop(events);
+        tokio::spawn(async {})
+    }
+}
+
+/// Full-text user search backed by Elasticsearch/OpenSearch.
+///
+/// Users are indexed from mutation events; queries fall back to the
+/// Postgres read model whenever the cluster is unconfigured or failing.
+pub struct SearchService {
+    client: Client,
+    config: SearchConfig,
+    fallback: Arc<UserSearchRepository>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl SearchService {
+    pub fn new(
+        config: SearchConfig,
+        fallback: Arc<UserSearchRepository>,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> AppResult<Self> {
+        let client = Client::builder()
+            .timeout(config.request_timeout)
+            .build()
+            .map_err(|e| AppError::Config(format!("Invalid search client settings: {}", e)))?;
+
+        Ok(Self {
+            client,
+            config,
+            fallback,
+            metrics,
+            logger,
+        })
+    }
+
+    /// Create the users index with explicit mappings if it does not exist yet
+    pub async fn ensure_index(&self) -> AppResult<()> {
+        let Some(url) = self.index_url() else {
+            return Ok(());
+        };
+
+        let exists = self.send(self.client.head(&url)).await?;
+        if exists.status() != StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+
+        let mappings = json!({
+            "mappings": {
+                "properties": {
+                    "user_id": { "type": "keyword" },
+                    "email": { "type": "text", "fields": { "raw": { "type": "keyword" } } },
+                    "username": { "type": "text", "fields": { "raw": { "type": "keyword" } } },
+                    "full_name": { "type": "text" },
+                    "role": { "type": "keyword" },
+                    "status": { "type": "keyword" },
+                    "email_verified": { "type": "boolean" },
+                    "last_login": { "type": "date" },
+                    "login_count": { "type": "long" },
+                    "created_at": { "type": "date" }
+                }
+            }
+        });
+        self.send_ok(self.client.put(&url).json(&mappings)).await?;
+        self.logger
+            .info(&format!("Created search index {}", self.config.users_index));
+        Ok(())
+    }
+
+    /// Index or replace a user's document
+    pub async fn index_user(&self, user: &User) -> AppResult<()> {
+        let Some(url) = self.index_url() else {
+            return Ok(());
+        };
+        let document = UserSearchEntry::from(user);
+        self.send_ok(self.client.put(format!("{}/_doc/{}", url, user.id)).json(&document))
+            .await
+    }
+
+    /// Remove a user's document; missing documents are not an error
+    pub async fn remove_user(&self, user_id: Uuid) -> AppResult<()> {
+        let Some(url) = self.index_url() else {
+            return Ok(());
+        };
+        let response = self
+            .send(self.client.delete(format!("{}/_doc/{}", url, user_id)))
+            .await?;
+        match response.status() {
+            status if status.is_success() || status == StatusCode::NOT_FOUND => Ok(()),
+            status => Err(AppError::Internal(format!("Search cluster returned {}", status))),
+        }
+    }
+
+    /// Typo-tolerant search with facets, falling back to Postgres on failure
+    pub async fn search(&self, filters: &UserFilters) -> AppResult<UserSearchResults> {
+        if self.config.url.is_some() {
+            match self.search_cluster(filters).await {
+                Ok(results) => return Ok(results),
+                Err(e) => {
+                    self.logger
+                        .warn(&format!("Search cluster unavailable, using Postgres: {}", e));
+                    let _ = self.metrics.increment_counter("search.fallback").await;
+                }
+            }
+        }
+
+        Ok(UserSearchResults {
+            hits: self.fallback.search(filters).await?,
+            facets: self.fallback.facets(filters).await?,
+            backend: SearchBackend::Postgres,
+        })
+    }
+
+    async fn search_cluster(&self, filters: &UserFilters) -> AppResult<UserSearchResults> {
+        let url = self
+            .index_url()
+            .ok_or_else(|| AppError::Config("Search cluster is not configured".to_string()))?;
+
+        let response = self
+            .send_ok(self.client.post(format!("{}/_search", url)).json(&build_query(filters)))
+            .await?;
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| AppError::Internal(format!("Invalid search response: {}", e)))?;
+
+        let hits = body["hits"]["hits"]
+            .as_array()
+            .map(|hits| {
+                hits.iter()
+                    .filter_map(|hit| serde_json::from_value(hit["_source"].clone()).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(UserSearchResults {
+            hits,
+            facets: SearchFacets {
+                total: body["hits"]["total"]["value"].as_u64().unwrap_or(0),
+                roles: buckets(&body["aggregations"]["roles"]),
+                statuses: buckets(&body["aggregations"]["statuses"]),
+            },
+            backend: SearchBackend::Elasticsearch,
+        })
+    }
+
+    fn index_url(&self) -> Option<String> {
+        self.config
+            .url
+            .as_ref()
+            .map(|url| format!("{}/{}", url, self.config.users_index))
+    }
+
+    async fn send(&self, request: RequestBuilder) -> AppResult<reqwest::Response> {
+        let request = match &self.config.username {
+            Some(username) => request.basic_auth(username, self.config.password.as_ref()),
+            None => request,
+        };
+        request
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("Search request failed: {}", e)))
+    }
+
+    async fn send_ok(&self, request: RequestBuilder) -> AppResult<reqwest::Response> {
+        let response = self.send(request).await?;
+        if response.status().is_success() {
+            Ok(response)
+        } else {
+            Err(AppError::Internal(format!(
+                "Search cluster returned {}",
+                response.status()
+            )))
+        }
+    }
+}
+
+impl SearchIndexer for SearchService {
+    /// Index users as mutation events arrive
+    fn spawn_indexer(
+        self: Arc<Self>,
+        mut events: broadcast::Receiver<UserEvent>,
+        shutdown: CancellationToken,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            if let Err(e) = self.ensure_index().await {
+                self.logger.warn(&format!("Could not prepare search index: {}", e));
+            }
+
+            loop {
+                let received = tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    received = events.recv() => received,
+                };
+                let result = match received {
+                    Ok(UserEvent::Created { user }) | Ok(UserEvent::Updated { user }) => {
+                        if user.deleted_at.is_some() {
+                            self.remove_user(user.id).await
+                        } else {
+                            self.index_user(&user).await
+                        }
+                    }
+                    Ok(UserEvent::Deleted { user_id, .. }) => self.remove_user(user_id).await,
+                    Ok(UserEvent::LoggedIn { .. }) => Ok(()),
+                    Err(RecvError::Lagged(missed)) => {
+                        self.logger
+                            .warn(&format!("Search indexer missed {} user events", missed));
+                        let _ = self.metrics.add_to_counter("search.index.missed", missed).await;
+                        Ok(())
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                if let Err(e) = result {
+                    self.logger.error(&format!("Failed to update search index: {}", e));
+                    let _ = self.metrics.increment_counter("search.index.errors").await;
+                }
+            }
+        })
+    }
+}
+
+/// Translate user filters into an Elasticsearch query with facet aggregations
+fn build_query(filters: &UserFilters) -> Value {
+    let mut filter_clauses = Vec::new();
+
+    if let Some(role) = &filters.role {
+        filter_clauses.push(json!({ "term": { "role": role.as_str() } }));
+    }
+    if let Some(status) = &filters.status {
+        filter_clauses.push(json!({ "term": { "status": status.as_str() } }));
+    }
+    if let Some(verified) = filters.email_verified {
+        filter_clauses.push(json!({ "term": { "email_verified": verified } }));
+    }
+    if filters.created_after.is_some() || filters.created_before.is_some() {
+        filter_clauses.push(json!({
+            "range": { "created_at": { "gt": filters.created_after, "lt": filters.created_before } }
+        }));
+    }
+    if let Some(after) = filters.last_login_after {
+        filter_clauses.push(json!({ "range": { "last_login": { "gt": after } } }));
+    }
+
+    let term = filters.search_term.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    let must = match term {
+        Some(term) => json!([{
+            "multi_match": {
+                "query": term,
+                "fields": ["username^3", "email^2", "full_name"],
+                "fuzziness": "AUTO"
+            }
+        }]),
+        None => json!([{ "match_all": {} }]),
+    };
+    let sort = match term {
+        Some(_) => json!(["_score", { "created_at": "desc" }]),
+        None => json!([{ "created_at": "desc" }]),
+    };
+
+    json!({
+        "from": filters.offset.unwrap_or(0),
+        "size": filters.limit.unwrap_or(DEFAULT_PAGE_SIZE),
+        "track_total_hits": true,
+        "query": { "bool": { "must": must, "filter": filter_clauses } },
+        "sort": sort,
+        "aggs": {
+            "roles": { "terms": { "field": "role" } },
+            "statuses": { "terms": { "field": "status" } }
+        }
+    })
+}
+
+fn buckets(aggregation: &Value) -> HashMap<String, u64> {
+    aggregation["buckets"]
+        .as_array()
+        .map(|buckets| {
+            buckets
+                .iter()
+                .filter_map(|b| Some((b["key"].as_str()?.to_string(), b["doc_count"].as_u64()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,374 @@
+use chrono::Utc;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, CredentialID, Passkey, PasskeyAuthentication, PasskeyRegistration, PublicKeyCredential,
+    RegisterPublicKeyCredential, RequestChallengeResponse, Webauthn,
+};
+
+use super::cache_service::CacheService;
+use super::webauthn::relying_party;
+use crate::config::AccountConfig;
+use crate::models::{
+    AppError, AppResult, Authenticator, SecondFactorSummary, TotpEnrollment, User, WebAuthnChallenge,
+};
+use crate::repositories::SecondFactorRepository;
+use crate::utils::{new_id, EncryptedField, KeyRing};
+
+/// Unambiguous characters for backup codes: no 0/O or 1/I/L
+const BACKUP_CODE_ALPHABET: &[u8] = b"23456789ABCDEFGHJKMNPQRSTUVWXYZ";
+const BACKUP_CODE_LENGTH: usize = 10;
+
+/// RFC 6238 defaults, which every authenticator app supports
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP_SECS: u64 = 30;
+
+/// Ceremony state kept between the begin and finish halves of a WebAuthn exchange
+#[derive(Serialize, Deserialize)]
+enum Ceremony {
+    Registration {
+        user_id: Uuid,
+        name: String,
+        state: PasskeyRegistration,
+    },
+    Assertion {
+        user_id: Uuid,
+        state: PasskeyAuthentication,
+    },
+}
+
+/// WebAuthn authenticators, TOTP apps and one-time backup codes as second factors.
+///
+/// Ceremony state lives in the cache for the challenge lifetime, so the
+/// begin and finish requests of a ceremony may land on different instances.
+/// TOTP secrets must be readable to check codes, so they are encrypted with
+/// the PII key ring rather than hashed.
+pub struct SecondFactors {
+    repository: Arc<dyn SecondFactorRepository>,
+    cache: Arc<CacheService>,
+    key_ring: Arc<KeyRing>,
+    webauthn: Webauthn,
+    challenge_ttl: Duration,
+    backup_code_count: usize,
+    totp_issuer: String,
+    totp_skew_steps: u8,
+}
+
+impl SecondFactors {
+    pub fn new(
+        repository: Arc<dyn SecondFactorRepository>,
+        cache: Arc<CacheService>,
+        key_ring: Arc<KeyRing>,
+        config: &AccountConfig,
+    ) -> AppResult<Self> {
+        Ok(Self {
+            repository,
+            cache,
+            key_ring,
+            webauthn: relying_party(config)?,
+            challenge_ttl: config.second_factor_challenge_ttl,
+            backup_code_count: config.backup_code_count.max(1),
+            totp_issuer: config.totp_issuer.clone(),
+            totp_skew_steps: config.totp_skew_steps,
+        })
+    }
+
+    pub async fn summary(&self, user_id: Uuid) -> AppResult<SecondFactorSummary> {
+        Ok(SecondFactorSummary {
+            authenticators: self.repository.authenticators(user_id).await?,
+            backup_codes_remaining: self.repository.remaining_backup_codes(user_id).await?,
+            totp_enabled: self
+                .repository
+                .totp_credential(user_id)
+                .await?
+                .is_some_and(|credential| credential.confirmed_at.is_some()),
+        })
+    }
+
+    /// Start registering an authenticator; already registered ones are excluded
+    pub async fn begin_registration(
+        &self,
+        user: &User,
+        name: &str,
+    ) -> AppResult<WebAuthnChallenge<CreationChallengeResponse>> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::Validation(vec!["Authenticator name is required".to_string()]));
+        }
+        let existing = self.passkeys(user.id).await?;
+        let exclude: Vec<CredentialID> = existing.iter().map(|(_, passkey)| passkey.cred_id().clone()).collect();
+
+        let (options, state) = self
+            .webauthn
+            .start_passkey_registration(user.id, &user.email, &user.username, Some(exclude))
+            .map_err(rejected)?;
+        let ceremony_id = self
+            .save_ceremony(&Ceremony::Registration {
+                user_id: user.id,
+                name: name.to_string(),
+                state,
+            })
+            .await?;
+        Ok(WebAuthnChallenge { ceremony_id, options })
+    }
+
+    pub async fn finish_registration(
+        &self,
+        user_id: Uuid,
+        ceremony_id: Uuid,
+        response: &RegisterPublicKeyCredential,
+    ) -> AppResult<Authenticator> {
+        let Ceremony::Registration { user_id: owner, name, state } = self.take_ceremony(ceremony_id).await? else {
+            return Err(unknown_ceremony());
+        };
+        if owner != user_id {
+            return Err(unknown_ceremony());
+        }
+
+        let passkey = self
+            .webauthn
+            .finish_passkey_registration(response, &state)
+            .map_err(rejected)?;
+        let authenticator = Authenticator {
+            id: new_id(),
+            user_id,
+            name,
+            credential_id: passkey.cred_id().to_string(),
+            credential: encode_passkey(&passkey)?,
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+        self.repository.create_authenticator(&authenticator).await?;
+        Ok(authenticator)
+    }
+
+    /// Challenge the user to prove possession of one of their authenticators
+    pub async fn begin_assertion(&self, user_id: Uuid) -> AppResult<WebAuthnChallenge<RequestChallengeResponse>> {
+        let passkeys: Vec<Passkey> = self.passkeys(user_id).await?.into_iter().map(|(_, p)| p).collect();
+        if passkeys.is_empty() {
+            return Err(AppError::NotFound("No authenticators registered".to_string()));
+        }
+
+        let (options, state) = self
+            .webauthn
+            .start_passkey_authentication(&passkeys)
+            .map_err(rejected)?;
+        let ceremony_id = self.save_ceremony(&Ceremony::Assertion { user_id, state }).await?;
+        Ok(WebAuthnChallenge { ceremony_id, options })
+    }
+
+    /// Verify an assertion and record the authenticator's new signature counter
+    pub async fn finish_assertion(
+        &self,
+        user_id: Uuid,
+        ceremony_id: Uuid,
+        response: &PublicKeyCredential,
+    ) -> AppResult<Authenticator> {
+        let Ceremony::Assertion { user_id: owner, state } = self.take_ceremony(ceremony_id).await? else {
+            return Err(unknown_ceremony());
+        };
+        if owner != user_id {
+            return Err(unknown_ceremony());
+        }
+
+        let result = self
+            .webauthn
+            .finish_passkey_authentication(response, &state)
+            .map_err(rejected)?;
+        let (mut authenticator, mut passkey) = self
+            .passkeys(user_id)
+            .await?
+            .into_iter()
+            .find(|(_, passkey)| passkey.cred_id() == result.cred_id())
+            .ok_or_else(|| AppError::Unauthorized("Authenticator has been removed".to_string()))?;
+
+        passkey.update_credential(&result);
+        authenticator.credential = encode_passkey(&passkey)?;
+        authenticator.last_used_at = Some(Utc::now());
+        self.repository
+            .update_credential(authenticator.id, &authenticator.credential)
+            .await?;
+        Ok(authenticator)
+    }
+
+    pub async fn remove_authenticator(&self, user_id: Uuid, authenticator_id: Uuid) -> AppResult<()> {
+        if !self.repository.delete_authenticator(user_id, authenticator_id).await? {
+            return Err(AppError::NotFound(format!("Authenticator {} not found", authenticator_id)));
+        }
+        Ok(())
+    }
+
+    /// Replace the user's backup codes; the plaintext codes are only ever returned here
+    pub async fn generate_backup_codes(&self, user_id: Uuid) -> AppResult<Vec<String>> {
+        let mut rng = rand::thread_rng();
+        let codes: Vec<String> = (0..self.backup_code_count)
+            .map(|_| {
+                let code: String = (0..BACKUP_CODE_LENGTH)
+                    .map(|_| BACKUP_CODE_ALPHABET[rng.gen_range(0..BACKUP_CODE_ALPHABET.len())] as char)
+                    .collect();
+                format!("{}-{}", &code[..BACKUP_CODE_LENGTH / 2], &code[BACKUP_CODE_LENGTH / 2..])
+            })
+            .collect();
+        let hashes: Vec<String> = codes.iter().map(|code| hash_backup_code(code)).collect();
+        self.repository.replace_backup_codes(user_id, &hashes).await?;
+        Ok(codes)
+    }
+
+    /// Spend a backup code; each code works once
+    pub async fn redeem_backup_code(&self, user_id: Uuid, code: &str) -> AppResult<bool> {
+        self.repository
+            .consume_backup_code(user_id, &hash_backup_code(code))
+            .await
+    }
+
+    /// Generate a TOTP secret for the user's authenticator app.
+    ///
+    /// The secret stays pending until `confirm_totp` sees a matching code,
+    /// so an abandoned setup never locks anyone out; starting over replaces
+    /// a pending secret.
+    pub async fn begin_totp_enrollment(&self, user: &User) -> AppResult<TotpEnrollment> {
+        let secret = Secret::generate_secret()
+            .to_bytes()
+            .map_err(|e| AppError::Internal(format!("TOTP secret generation failed: {:?}", e)))?;
+        let totp = self.totp(secret, &user.email)?;
+        let encoded = totp.get_secret_base32();
+
+        let stored = self.key_ring.encrypt(&encoded)?.encode();
+        if !self.repository.save_pending_totp(user.id, &stored).await? {
+            return Err(AppError::Conflict("An authenticator app is already set up".to_string()));
+        }
+        Ok(TotpEnrollment {
+            secret: encoded,
+            provisioning_uri: totp.get_url(),
+            qr_code: totp
+                .get_qr_base64()
+                .map_err(|e| AppError::Internal(format!("QR code generation failed: {}", e)))?,
+        })
+    }
+
+    /// Activate a pending TOTP secret once the app produces a matching code
+    pub async fn confirm_totp(&self, user: &User, code: &str) -> AppResult<bool> {
+        let Some(credential) = self.repository.totp_credential(user.id).await? else {
+            return Err(AppError::NotFound("No authenticator app setup in progress".to_string()));
+        };
+        if credential.confirmed_at.is_some() {
+            return Err(AppError::Conflict("An authenticator app is already set up".to_string()));
+        }
+        match self.matching_step(&credential.secret, &user.email, code)? {
+            Some(step) => self.repository.confirm_totp(user.id, step).await,
+            None => Ok(false),
+        }
+    }
+
+    /// Check a code from the user's authenticator app; each code works once
+    pub async fn verify_totp(&self, user: &User, code: &str) -> AppResult<bool> {
+        let Some(credential) = self.repository.totp_credential(user.id).await? else {
+            return Ok(false);
+        };
+        if credential.confirmed_at.is_none() {
+            return Ok(false);
+        }
+        match self.matching_step(&credential.secret, &user.email, code)? {
+            Some(step) => self.repository.use_totp_step(user.id, step).await,
+            None => Ok(false),
+        }
+    }
+
+    pub async fn remove_totp(&self, user_id: Uuid) -> AppResult<()> {
+        if !self.repository.delete_totp(user_id).await? {
+            return Err(AppError::NotFound("No authenticator app is set up".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Time step within the allowed skew whose code matches, if any
+    fn matching_step(&self, stored_secret: &str, account: &str, code: &str) -> AppResult<Option<i64>> {
+        let encoded = self.key_ring.decrypt(&EncryptedField::parse(stored_secret)?)?;
+        let secret = Secret::Encoded(encoded)
+            .to_bytes()
+            .map_err(|e| AppError::Internal(format!("Corrupt TOTP secret: {:?}", e)))?;
+        let totp = self.totp(secret, account)?;
+
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        let current = (Utc::now().timestamp() as u64 / TOTP_STEP_SECS) as i64;
+        let skew = self.totp_skew_steps as i64;
+        Ok((current - skew..=current + skew)
+            .filter(|step| *step >= 0)
+            .find(|step| totp.check(&code, *step as u64 * TOTP_STEP_SECS)))
+    }
+
+    fn totp(&self, secret: Vec<u8>, account: &str) -> AppResult<TOTP> {
+        // Skew is applied by `matching_step`, which needs to know the matching step
+        TOTP::new(
+            Algorithm::SHA1,
+            TOTP_DIGITS,
+            0,
+            TOTP_STEP_SECS,
+            secret,
+            Some(self.totp_issuer.clone()),
+            account.to_string(),
+        )
+        .map_err(|e| AppError::Internal(format!("Invalid TOTP parameters: {:?}", e)))
+    }
+
+    async fn passkeys(&self, user_id: Uuid) -> AppResult<Vec<(Authenticator, Passkey)>> {
+        self.repository
+            .authenticators(user_id)
+            .await?
+            .into_iter()
+            .map(|authenticator| {
+                let passkey = serde_json::from_value(authenticator.credential.clone()).map_err(|e| {
+                    AppError::Internal(format!("Corrupt credential of authenticator {}: {}", authenticator.id, e))
+                })?;
+                Ok((authenticator, passkey))
+            })
+            .collect()
+    }
+
+    async fn save_ceremony(&self, ceremony: &Ceremony) -> AppResult<Uuid> {
+        let ceremony_id = Uuid::new_v4();
+        self.cache
+            .set(&ceremony_key(ceremony_id), ceremony, Some(self.challenge_ttl))
+            .await?;
+        Ok(ceremony_id)
+    }
+
+    /// Ceremonies are single-use, so a replayed response finds nothing
+    async fn take_ceremony(&self, ceremony_id: Uuid) -> AppResult<Ceremony> {
+        let key = ceremony_key(ceremony_id);
+        let ceremony = self.cache.get(&key).await?.ok_or_else(unknown_ceremony)?;
+        self.cache.delete(&key).await?;
+        Ok(ceremony)
+    }
+}
+
+fn ceremony_key(ceremony_id: Uuid) -> String {
+    format!("webauthn:{}", ceremony_id)
+}
+
+/// Codes are random and high-entropy, so a fast hash is enough; case and separators are ignored
+fn hash_backup_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+fn encode_passkey(passkey: &Passkey) -> AppResult<serde_json::Value> {
+    serde_json::to_value(passkey).map_err(|e| AppError::Internal(format!("Unserializable credential: {}", e)))
+}
+
+fn unknown_ceremony() -> AppError {
+    AppError::Unauthorized("Unknown or expired WebAuthn challenge".to_string())
+}
+
+fn rejected(error: webauthn_rs::prelude::WebauthnError) -> AppError {
+    AppError::Unauthorized(format!("WebAuthn verification failed: {}", error))
+}
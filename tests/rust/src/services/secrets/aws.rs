@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use aws_sdk_secretsmanager::Client;
+use serde_json::Value;
+
+use super::SecretProvider;
+use crate::config::SecretReference;
+use crate::models::{AppError, AppResult};
+
+/// Secrets in AWS Secrets Manager, with credentials and region found the
+/// usual AWS way: environment, profile or instance role
+pub struct AwsSecretsManagerProvider {
+    client: Client,
+}
+
+impl AwsSecretsManagerProvider {
+    pub async fn load() -> Self {
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        Self {
+            client: Client::new(&config),
+        }
+    }
+}
+
+#[async_trait]
+impl SecretProvider for AwsSecretsManagerProvider {
+    fn scheme(&self) -> &'static str {
+        "aws-sm"
+    }
+
+    /// The secret string itself, or one key of it when it is a JSON object
+    async fn fetch(&self, reference: &SecretReference) -> AppResult<String> {
+        let SecretReference::AwsSecretsManager { secret_id, field } = reference else {
+            return Err(AppError::Internal(format!("Not an AWS Secrets Manager secret: {:?}", reference)));
+        };
+        let failed = |e: String| AppError::Config(format!("AWS secret {} unavailable: {}", secret_id, e));
+
+        let output = self
+            .client
+            .get_secret_value()
+            .secret_id(secret_id)
+            .send()
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+        let secret = output
+            .secret_string()
+            .ok_or_else(|| failed("secret has no string value".to_string()))?;
+
+        let Some(field) = field else {
+            return Ok(secret.to_string());
+        };
+        let parsed: Value = serde_json::from_str(secret).map_err(|e| failed(format!("not a JSON secret: {}", e)))?;
+        match &parsed[field.as_str()] {
+            Value::String(value) => Ok(value.clone()),
+            Value::Null => Err(failed(format!("no key {}", field))),
+            other => Ok(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+use super::SecretProvider;
+use crate::config::{SecretReference, SecretsConfig};
+use crate::models::{AppError, AppResult};
+
+/// Secrets kept in files, such as Docker or Kubernetes mounted secrets
+pub struct FileSecretProvider {
+    secrets_dir: PathBuf,
+}
+
+impl FileSecretProvider {
+    pub fn new(config: &SecretsConfig) -> Self {
+        Self {
+            secrets_dir: config.secrets_dir.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl SecretProvider for FileSecretProvider {
+    fn scheme(&self) -> &'static str {
+        "file"
+    }
+
+    /// Relative paths are read from the secrets directory; a trailing newline is not part of the secret
+    async fn fetch(&self, reference: &SecretReference) -> AppResult<String> {
+        let SecretReference::File { path } = reference else {
+            return Err(AppError::Internal(format!("Not a file secret: {:?}", reference)));
+        };
+        let path = self.secrets_dir.join(path);
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| AppError::Config(format!("Cannot read secret file {}: {}", path.display(), e)))?;
+        Ok(content.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
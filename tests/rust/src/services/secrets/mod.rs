@@ -0,0 +1,97 @@
+pub mod aws;
+pub mod file;
+pub mod vault;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::config::{publish_secrets, SecretReference, SecretsConfig};
+use crate::models::{AppError, AppResult};
+
+pub use aws::AwsSecretsManagerProvider;
+pub use file::FileSecretProvider;
+pub use vault::VaultSecretProvider;
+
+/// A backend secrets are fetched from
+#[async_trait]
+pub trait SecretProvider: Send + Sync {
+    /// The reference scheme this provider answers, e.g. `vault`
+    fn scheme(&self) -> &'static str;
+
+    async fn fetch(&self, reference: &SecretReference) -> AppResult<String>;
+}
+
+/// Secrets that configuration variables refer to, fetched from their providers.
+///
+/// Loading publishes the fetched values so `AppConfig::from_env` reads them
+/// in place of the references. Refreshing fetches every secret again and
+/// reports which changed, leaving whoever uses them to apply the change.
+pub struct SecretStore {
+    references: Vec<(String, SecretReference)>,
+    providers: HashMap<&'static str, Box<dyn SecretProvider>>,
+    values: RwLock<HashMap<String, String>>,
+    refresh_interval: Duration,
+}
+
+impl SecretStore {
+    /// None when no variable holds a reference; fails when any secret cannot be fetched
+    pub async fn load(config: &SecretsConfig) -> AppResult<Option<Self>> {
+        if config.references.is_empty() {
+            return Ok(None);
+        }
+
+        let mut providers: Vec<Box<dyn SecretProvider>> = vec![Box::new(FileSecretProvider::new(config))];
+        if config.uses("vault") {
+            providers.push(Box::new(VaultSecretProvider::new(config)?));
+        }
+        if config.uses("aws-sm") {
+            providers.push(Box::new(AwsSecretsManagerProvider::load().await));
+        }
+
+        let store = Self {
+            references: config.references.clone(),
+            providers: providers.into_iter().map(|provider| (provider.scheme(), provider)).collect(),
+            values: RwLock::new(HashMap::new()),
+            refresh_interval: config.refresh_interval,
+        };
+        store.refresh().await?;
+        Ok(Some(store))
+    }
+
+    pub fn refresh_interval(&self) -> Duration {
+        self.refresh_interval
+    }
+
+    pub fn get(&self, var: &str) -> Option<String> {
+        self.values.read().unwrap_or_else(|poisoned| poisoned.into_inner()).get(var).cloned()
+    }
+
+    /// Fetch every secret again, returning the variables whose value changed.
+    ///
+    /// All secrets are fetched before any is published, so a failure leaves
+    /// the previous values in place.
+    pub async fn refresh(&self) -> AppResult<Vec<String>> {
+        let mut fetched = HashMap::new();
+        for (var, reference) in &self.references {
+            let provider = self.providers.get(reference.scheme()).ok_or_else(|| {
+                AppError::Config(format!("No secret provider for {} references", reference.scheme()))
+            })?;
+            let value = provider.fetch(reference).await.map_err(|e| {
+                AppError::Config(format!("Cannot fetch secret for {}: {}", var, e))
+            })?;
+            fetched.insert(var.clone(), value);
+        }
+
+        let mut values = self.values.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let changed: Vec<String> = fetched
+            .iter()
+            .filter(|(var, value)| values.get(*var) != Some(*value))
+            .map(|(var, _)| var.clone())
+            .collect();
+        publish_secrets(&fetched);
+        *values = fetched;
+        Ok(changed)
+    }
+}
@@ -0,0 +1,75 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+use std::time::Duration;
+
+use super::SecretProvider;
+use crate::config::{SecretReference, SecretsConfig};
+use crate::models::{AppError, AppResult};
+
+const VAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Secrets in a HashiCorp Vault KV version 2 engine, read with a token
+pub struct VaultSecretProvider {
+    client: Client,
+    addr: String,
+    token: String,
+    namespace: Option<String>,
+}
+
+impl VaultSecretProvider {
+    pub fn new(config: &SecretsConfig) -> AppResult<Self> {
+        let addr = config
+            .vault_addr
+            .clone()
+            .ok_or_else(|| AppError::Config("VAULT_ADDR must be set to use vault: secrets".to_string()))?;
+        let token = config.vault_token.clone().ok_or_else(|| {
+            AppError::Config("VAULT_TOKEN or VAULT_TOKEN_FILE must be set to use vault: secrets".to_string())
+        })?;
+        let client = Client::builder()
+            .timeout(VAULT_TIMEOUT)
+            .build()
+            .map_err(|e| AppError::Config(format!("Invalid Vault client settings: {}", e)))?;
+
+        Ok(Self {
+            client,
+            addr: addr.trim_end_matches('/').to_string(),
+            token,
+            namespace: config.vault_namespace.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl SecretProvider for VaultSecretProvider {
+    fn scheme(&self) -> &'static str {
+        "vault"
+    }
+
+    /// `path` is the secret's API path below `/v1/`, e.g. `secret/data/crawler`
+    async fn fetch(&self, reference: &SecretReference) -> AppResult<String> {
+        let SecretReference::Vault { path, field } = reference else {
+            return Err(AppError::Internal(format!("Not a Vault secret: {:?}", reference)));
+        };
+        let failed = |e: String| AppError::Config(format!("Vault secret {} unavailable: {}", path, e));
+
+        let mut request = self
+            .client
+            .get(format!("{}/v1/{}", self.addr, path))
+            .header("X-Vault-Token", &self.token);
+        if let Some(namespace) = &self.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+        let response = request.send().await.map_err(|e| failed(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(failed(format!("status {}", response.status())));
+        }
+        let body: Value = response.json().await.map_err(|e| failed(e.to_string()))?;
+
+        match &body["data"]["data"][field.as_str()] {
+            Value::String(value) => Ok(value.clone()),
+            Value::Null => Err(failed(format!("no field {}", field))),
+            other => Ok(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::read_only::ReadOnlyMode;
+use crate::models::{
+    AppError, AppResult, CreateServiceAccountRequest, IssuedServiceAccount, ServiceAccount, TenantContext, User,
+};
+use crate::repositories::ServiceAccountRepository;
+use crate::utils::Logger;
+
+/// Marks a credential as a service account secret
+const CLIENT_SECRET_PREFIX: &str = "crs_";
+
+/// Creates service accounts and checks their client credentials.
+///
+/// Secrets are 256 random bits, stored as an unsalted SHA-256 hash like API
+/// keys. Tokens are signed by `AuthMiddleware` once the credentials check
+/// out; this service only decides who the caller is.
+pub struct ServiceAccountService {
+    accounts: Arc<ServiceAccountRepository>,
+    read_only: Arc<ReadOnlyMode>,
+    logger: Arc<Logger>,
+}
+
+impl ServiceAccountService {
+    pub fn new(accounts: Arc<ServiceAccountRepository>, read_only: Arc<ReadOnlyMode>, logger: Arc<Logger>) -> Self {
+        Self {
+            accounts,
+            read_only,
+            logger,
+        }
+    }
+
+    pub async fn create(
+        &self,
+        tenant: &TenantContext,
+        request: CreateServiceAccountRequest,
+        created_by: &User,
+    ) -> AppResult<IssuedServiceAccount> {
+        self.read_only.check()?;
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        let secret = new_secret();
+        let account = ServiceAccount::new(
+            tenant.tenant_id.clone(),
+            request.name.trim().to_string(),
+            request.scopes,
+            hash_secret(&secret),
+            created_by.id,
+        );
+        self.accounts.create(&account).await?;
+
+        let scopes: Vec<&str> = account.scopes.iter().map(|scope| scope.as_str()).collect();
+        self.logger.info(&format!(
+            "Service account {} ({}) created by {} with scopes {}",
+            account.id,
+            account.name,
+            created_by.id,
+            scopes.join(" ")
+        ));
+        Ok(IssuedServiceAccount {
+            account,
+            client_secret: secret,
+        })
+    }
+
+    pub async fn list(&self, tenant: &TenantContext) -> AppResult<Vec<ServiceAccount>> {
+        self.accounts.list(&tenant.tenant_id).await
+    }
+
+    /// Stop the account getting tokens; tokens already issued run out on their own
+    pub async fn disable(&self, tenant: &TenantContext, id: Uuid, disabled_by: &User) -> AppResult<()> {
+        self.read_only.check()?;
+        self.accounts
+            .find(&tenant.tenant_id, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Service account {} not found", id)))?;
+        if !self.accounts.disable(id).await? {
+            return Err(AppError::Conflict(format!("Service account {} is already disabled", id)));
+        }
+        self.logger.info(&format!("Service account {} disabled by {}", id, disabled_by.id));
+        Ok(())
+    }
+
+    /// The active account behind client credentials presented in `tenant`
+    pub async fn authenticate(
+        &self,
+        tenant: &TenantContext,
+        client_id: Uuid,
+        secret: &str,
+    ) -> AppResult<ServiceAccount> {
+        if !secret.starts_with(CLIENT_SECRET_PREFIX) {
+            return Err(invalid_credentials());
+        }
+        let account = self
+            .accounts
+            .get(client_id)
+            .await?
+            .filter(|account| account.is_active() && account.tenant_id == tenant.tenant_id)
+            .filter(|account| account.secret_hash == hash_secret(secret))
+            .ok_or_else(invalid_credentials)?;
+
+        // Usage tracking must not fail the sign-in it describes
+        if let Err(e) = self.accounts.touch(account.id).await {
+            self.logger
+                .warn(&format!("Failed to record use of service account {}: {}", account.id, e));
+        }
+        Ok(account)
+    }
+}
+
+fn new_secret() -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    format!("{}{}", CLIENT_SECRET_PREFIX, URL_SAFE_NO_PAD.encode(secret))
+}
+
+fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+fn invalid_credentials() -> AppError {
+    AppError::Unauthorized("Invalid client credentials".to_string())
+}
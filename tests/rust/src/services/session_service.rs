@@ -0,0 +1,138 @@
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::cache_service::CacheService;
+use crate::models::{AppResult, Session};
+
+/// Live sign-in sessions, kept in Redis.
+///
+/// Each session is stored under its own key expiring with the session, and
+/// indexed in a per-user set so a user's sessions can be listed and revoked
+/// together. Revoking deletes the key, so every instance stops accepting the
+/// session at once. Expired ids left in the index are pruned when listed.
+/// A session with an idle timeout ends that long after its last refresh.
+pub struct SessionService {
+    cache: Arc<CacheService>,
+}
+
+impl SessionService {
+    pub fn new(cache: Arc<CacheService>) -> Self {
+        Self { cache }
+    }
+
+    pub async fn create(&self, session: &Session) -> AppResult<()> {
+        self.cache
+            .set(&session_key(session.id), session, Some(remaining(session)))
+            .await?;
+        self.cache
+            .add_to_set(&user_sessions_key(session.user_id), &session.id.to_string())
+            .await
+    }
+
+    /// The session if it is still live
+    pub async fn get(&self, id: Uuid) -> AppResult<Option<Session>> {
+        let session: Option<Session> = self.cache.get(&session_key(id)).await?;
+        Ok(session.filter(Session::is_active))
+    }
+
+    /// Record activity on a live session, moving it to `ip_address` when given
+    pub async fn refresh(&self, id: Uuid, ip_address: Option<&str>) -> AppResult<Option<Session>> {
+        let Some(mut session) = self.get(id).await? else {
+            return Ok(None);
+        };
+        session.last_active_at = Utc::now();
+        if let Some(ip_address) = ip_address {
+            session.ip_address = Some(ip_address.to_string());
+        }
+        self.cache
+            .set(&session_key(id), &session, Some(remaining(&session)))
+            .await?;
+        Ok(Some(session))
+    }
+
+    /// End the least recently active of a user's sessions beyond the first
+    /// `keep`, returning the ones ended
+    pub async fn trim(&self, user_id: Uuid, keep: usize) -> AppResult<Vec<Session>> {
+        let sessions = self.list(user_id).await?;
+        let ended: Vec<Session> = sessions.into_iter().skip(keep).collect();
+        self.remove(&ended).await?;
+        Ok(ended)
+    }
+
+    /// A user's live sessions, most recently active first
+    pub async fn list(&self, user_id: Uuid) -> AppResult<Vec<Session>> {
+        let index = user_sessions_key(user_id);
+        let mut sessions = Vec::new();
+        let mut stale = Vec::new();
+        for member in self.cache.set_members(&index).await? {
+            let session = match member.parse() {
+                Ok(id) => self.get(id).await?,
+                Err(_) => None,
+            };
+            match session {
+                Some(session) if session.user_id == user_id => sessions.push(session),
+                _ => stale.push(member),
+            }
+        }
+        self.cache.remove_from_set(&index, &stale).await?;
+
+        sessions.sort_by(|a, b| b.last_active_at.cmp(&a.last_active_at));
+        Ok(sessions)
+    }
+
+    /// End one session; false when the user has no such live session
+    pub async fn revoke(&self, user_id: Uuid, id: Uuid) -> AppResult<bool> {
+        match self.get(id).await? {
+            Some(session) if session.user_id == user_id => {
+                self.remove(&[session]).await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// End every session opened on a device, returning how many there were
+    pub async fn revoke_device(&self, user_id: Uuid, device_id: Uuid) -> AppResult<usize> {
+        let sessions: Vec<Session> = self
+            .list(user_id)
+            .await?
+            .into_iter()
+            .filter(|session| session.device_id == device_id)
+            .collect();
+        self.remove(&sessions).await?;
+        Ok(sessions.len())
+    }
+
+    /// End all of a user's sessions, returning how many there were
+    pub async fn revoke_all(&self, user_id: Uuid) -> AppResult<usize> {
+        let sessions = self.list(user_id).await?;
+        self.remove(&sessions).await?;
+        self.cache.delete(&user_sessions_key(user_id)).await?;
+        Ok(sessions.len())
+    }
+
+    async fn remove(&self, sessions: &[Session]) -> AppResult<()> {
+        for session in sessions {
+            self.cache.delete(&session_key(session.id)).await?;
+            self.cache
+                .remove_from_set(&user_sessions_key(session.user_id), &[session.id.to_string()])
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Time until the session ends, so its key expires with it
+fn remaining(session: &Session) -> Duration {
+    (session.ends_at() - Utc::now()).to_std().unwrap_or_default()
+}
+
+fn session_key(id: Uuid) -> String {
+    format!("session:{}", id)
+}
+
+fn user_sessions_key(user_id: Uuid) -> String {
+    format!("user_sessions:{}", user_id)
+}
@@ -0,0 +1,270 @@
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::health_registry::{HealthProbe, HealthRegistry};
+use crate::config::StatusConfig;
+use crate::models::{
+    AppError, AppResult, ComponentStatus, DailyUptime, HealthSample, Incident, OverallStatus, StatusPage,
+};
+use crate::repositories::{DailyCheckCounts, HealthCheckRepository, HealthTransition};
+use crate::utils::{Logger, Metrics};
+
+/// Checks kept in memory while they cannot be stored; the oldest are dropped first
+const MAX_UNSAVED: usize = 1000;
+const MAX_INCIDENTS: usize = 50;
+
+/// Probes the components registered with the `HealthRegistry` and
+/// summarizes their history for a public status page.
+///
+/// Every instance probes on its own and stores the results, so uptime is
+/// the share of healthy checks across instances and an incident runs from
+/// a component's first failed check to its next healthy one. Checks made
+/// while the database is down are kept until they can be stored, and the
+/// page falls back to the last one built, updated with this instance's
+/// latest probes, when history cannot be read.
+pub struct StatusService {
+    repository: Arc<HealthCheckRepository>,
+    registry: Arc<HealthRegistry>,
+    config: StatusConfig,
+    unsaved: Mutex<Vec<HealthSample>>,
+    latest: RwLock<Vec<HealthSample>>,
+    page: RwLock<Option<(Instant, Arc<StatusPage>)>>,
+    metrics: Arc<Metrics>,
+    logger: Arc<Logger>,
+}
+
+impl StatusService {
+    pub fn new(
+        repository: Arc<HealthCheckRepository>,
+        registry: Arc<HealthRegistry>,
+        config: StatusConfig,
+        metrics: Arc<Metrics>,
+        logger: Arc<Logger>,
+    ) -> Self {
+        Self {
+            repository,
+            registry,
+            config,
+            unsaved: Mutex::new(Vec::new()),
+            latest: RwLock::new(Vec::new()),
+            page: RwLock::new(None),
+            metrics,
+            logger,
+        }
+    }
+
+    /// Probe every component once, all at the same time, and store the results
+    pub async fn probe(&self) -> AppResult<Vec<HealthSample>> {
+        let probes = self.registry.probes();
+        let samples = join_all(probes.iter().map(|(component, probe)| self.check(component, probe.as_ref()))).await;
+        for sample in samples.iter().filter(|sample| !sample.healthy) {
+            self.metrics
+                .increment_labeled_counter("status.probe_failed", &[("component", sample.component.as_str())])
+                .await?;
+        }
+        *self.latest.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = samples.clone();
+
+        let pending = {
+            let mut unsaved = self.unsaved.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            unsaved.extend(samples.iter().cloned());
+            std::mem::take(&mut *unsaved)
+        };
+        if let Err(e) = self.repository.record(&pending).await {
+            let mut unsaved = self.unsaved.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let mut kept = pending;
+            kept.append(&mut unsaved);
+            let excess = kept.len().saturating_sub(MAX_UNSAVED);
+            kept.drain(..excess);
+            *unsaved = kept;
+            return Err(e);
+        }
+
+        self.repository.prune(self.history_start()).await?;
+        Ok(samples)
+    }
+
+    /// The status page, rebuilt at most once per `page_ttl`
+    pub async fn page(&self) -> Arc<StatusPage> {
+        let cached = self.page.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        if let Some((built, page)) = &cached {
+            if built.elapsed() < self.config.page_ttl {
+                return page.clone();
+            }
+        }
+
+        match self.build().await {
+            Ok(page) => {
+                let page = Arc::new(page);
+                *self.page.write().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+                    Some((Instant::now(), page.clone()));
+                page
+            }
+            Err(e) => {
+                self.logger.warn(&format!("Serving status page without fresh history: {}", e));
+                Arc::new(self.fallback(cached.map(|(_, page)| page)))
+            }
+        }
+    }
+
+    /// Probe until shutdown
+    pub fn spawn_probes(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.probe_interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.probe().await {
+                    self.logger.warn(&format!("Failed to store health checks: {}", e));
+                }
+            }
+        })
+    }
+
+    async fn check(&self, component: &str, probe: &dyn HealthProbe) -> HealthSample {
+        let started = Instant::now();
+        let outcome = match tokio::time::timeout(self.config.probe_timeout, probe.probe()).await {
+            Ok(outcome) => outcome,
+            Err(_) => Err(AppError::Internal(format!(
+                "No answer within {}ms",
+                self.config.probe_timeout.as_millis()
+            ))),
+        };
+        HealthSample {
+            component: component.to_string(),
+            healthy: outcome.is_ok(),
+            latency_ms: started.elapsed().as_millis() as i64,
+            error: outcome.err().map(|e| e.to_string()),
+            checked_at: Utc::now(),
+        }
+    }
+
+    async fn build(&self) -> AppResult<StatusPage> {
+        let since = self.history_start();
+        let counts = self.repository.daily_counts(since).await?;
+        let transitions = self.repository.transitions(since).await?;
+        let latest = self.repository.latest().await?;
+
+        let components = self
+            .registry
+            .components()
+            .map(|name| {
+                let last = latest.iter().find(|check| check.component == name);
+                component_status(name, &counts, last)
+            })
+            .collect();
+        let mut incidents: Vec<Incident> = self
+            .registry
+            .components()
+            .flat_map(|name| incidents(name, &transitions))
+            .collect();
+        incidents.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        incidents.truncate(MAX_INCIDENTS);
+
+        Ok(self.summarize(components, incidents))
+    }
+
+    /// The last page built, or an empty one, with this instance's latest probes applied
+    fn fallback(&self, stale: Option<Arc<StatusPage>>) -> StatusPage {
+        let (mut components, incidents) = match stale {
+            Some(page) => (page.components.clone(), page.incidents.clone()),
+            None => (
+                self.registry
+                    .components()
+                    .map(|name| component_status(name, &[], None))
+                    .collect(),
+                Vec::new(),
+            ),
+        };
+        let latest = self.latest.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        for component in &mut components {
+            if let Some(sample) = latest.iter().find(|sample| sample.component == component.name) {
+                component.operational = sample.healthy;
+                component.last_checked_at = Some(sample.checked_at);
+            }
+        }
+        self.summarize(components, incidents)
+    }
+
+    fn summarize(&self, components: Vec<ComponentStatus>, incidents: Vec<Incident>) -> StatusPage {
+        let down = components.iter().filter(|component| !component.operational).count();
+        let status = match down {
+            0 => OverallStatus::Operational,
+            down if down == components.len() => OverallStatus::MajorOutage,
+            _ => OverallStatus::PartialOutage,
+        };
+        StatusPage {
+            status,
+            components,
+            incidents,
+            history_days: self.config.history_days,
+            generated_at: Utc::now(),
+        }
+    }
+
+    fn history_start(&self) -> DateTime<Utc> {
+        Utc::now() - chrono::Duration::days(self.config.history_days)
+    }
+}
+
+/// A component never checked counts as operational with full uptime
+fn component_status(name: &str, counts: &[DailyCheckCounts], last: Option<&HealthTransition>) -> ComponentStatus {
+    let days: Vec<&DailyCheckCounts> = counts.iter().filter(|day| day.component == name).collect();
+    let healthy: i64 = days.iter().map(|day| day.healthy).sum();
+    let total: i64 = days.iter().map(|day| day.total).sum();
+
+    ComponentStatus {
+        name: name.to_string(),
+        operational: last.map_or(true, |check| check.healthy),
+        last_checked_at: last.map(|check| check.checked_at),
+        uptime_percent: percent(healthy, total),
+        daily: days
+            .iter()
+            .map(|day| DailyUptime {
+                date: day.day,
+                uptime_percent: percent(day.healthy, day.total),
+            })
+            .collect(),
+    }
+}
+
+/// Incident windows of a component from its health transitions, oldest first
+fn incidents(name: &str, transitions: &[HealthTransition]) -> Vec<Incident> {
+    let mut incidents = Vec::new();
+    let mut started: Option<DateTime<Utc>> = None;
+    for transition in transitions.iter().filter(|transition| transition.component == name) {
+        match (transition.healthy, started) {
+            (false, None) => started = Some(transition.checked_at),
+            (true, Some(started_at)) => {
+                incidents.push(incident(name, started_at, Some(transition.checked_at)));
+                started = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(started_at) = started {
+        incidents.push(incident(name, started_at, None));
+    }
+    incidents
+}
+
+fn incident(name: &str, started_at: DateTime<Utc>, resolved_at: Option<DateTime<Utc>>) -> Incident {
+    Incident {
+        component: name.to_string(),
+        started_at,
+        resolved_at,
+        duration_secs: (resolved_at.unwrap_or_else(Utc::now) - started_at).num_seconds(),
+    }
+}
+
+fn percent(healthy: i64, total: i64) -> f64 {
+    if total == 0 {
+        return 100.0;
+    }
+    (healthy as f64 * 100_000.0 / total as f64).round() / 1000.0
+}
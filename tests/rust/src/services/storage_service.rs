@@ -0,0 +1,73 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::config::StorageConfig;
+use crate::models::{AppError, AppResult};
+
+/// Keeps generated files under a directory, addressed by slash-separated keys.
+///
+/// Writes go to a temporary file that is renamed into place, so a reader
+/// never sees a partial file. Keys are checked so none can reach outside
+/// the root.
+pub struct StorageService {
+    root: PathBuf,
+}
+
+impl StorageService {
+    pub fn new(config: &StorageConfig) -> Self {
+        Self {
+            root: config.root.clone(),
+        }
+    }
+
+    pub async fn put(&self, key: &str, content: &[u8]) -> AppResult<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| failed(key, e))?;
+        }
+        let staging = path.with_extension(format!("{}.tmp", Uuid::new_v4().simple()));
+        tokio::fs::write(&staging, content).await.map_err(|e| failed(key, e))?;
+        if let Err(e) = tokio::fs::rename(&staging, &path).await {
+            let _ = tokio::fs::remove_file(&staging).await;
+            return Err(failed(key, e));
+        }
+        Ok(())
+    }
+
+    pub async fn get(&self, key: &str) -> AppResult<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(failed(key, e)),
+        }
+    }
+
+    /// False when there was nothing stored under the key
+    pub async fn delete(&self, key: &str) -> AppResult<bool> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(failed(key, e)),
+        }
+    }
+
+    fn path(&self, key: &str) -> AppResult<PathBuf> {
+        let valid = !key.is_empty()
+            && key.split('/').all(|segment| {
+                !segment.is_empty()
+                    && !segment.starts_with('.')
+                    && segment
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            });
+        if !valid {
+            return Err(AppError::Internal(format!("Invalid storage key: {}", key)));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+fn failed(key: &str, error: std::io::Error) -> AppError {
+    AppError::Internal(format!("Storage operation on {} failed: {}", key, error))
+}
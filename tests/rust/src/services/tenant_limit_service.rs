@@ -0,0 +1,182 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::cache_service::{CacheService, TokenBucket};
+use crate::config::{RateLimit, TenantLimits, TenantLimitsConfig};
+use crate::models::{AppError, AppResult, TenantContext};
+use crate::repositories::TenantUserRepository;
+use crate::utils::Metrics;
+
+/// Daily notification counters outlive their day by this long for reporting
+const NOTIFICATION_COUNTER_GRACE: Duration = Duration::from_secs(2 * 86_400);
+
+/// What a tenant has used of its limits
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantUsage {
+    pub tenant_id: String,
+    pub notifications_today: u64,
+    pub notifications_per_day: Option<u64>,
+    pub users: u64,
+    pub max_users: Option<u64>,
+    pub requests_per_window: Option<u32>,
+    pub window_secs: Option<u32>,
+}
+
+/// Limits shared by everyone acting on behalf of one tenant.
+///
+/// The request rate is a token bucket per tenant, taken from by the rate
+/// limiting middleware; notification counts are per UTC day and kept in the
+/// cache like API quotas; user counts come from the accounts onboarded into
+/// the tenant. Every refusal is counted per tenant.
+pub struct TenantLimitService {
+    config: TenantLimitsConfig,
+    cache: Arc<CacheService>,
+    users: Arc<TenantUserRepository>,
+    metrics: Arc<Metrics>,
+}
+
+impl TenantLimitService {
+    pub fn new(
+        config: TenantLimitsConfig,
+        cache: Arc<CacheService>,
+        users: Arc<TenantUserRepository>,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        Self {
+            config,
+            cache,
+            users,
+            metrics,
+        }
+    }
+
+    pub fn limits(&self, tenant: &TenantContext) -> TenantLimits {
+        self.config.for_tenant(tenant)
+    }
+
+    /// Whether any tenant is held to a request rate, so the middleware can skip the bucket otherwise
+    pub fn limits_requests(&self) -> bool {
+        self.config.limits_requests()
+    }
+
+    /// Take a token from the tenant's request bucket; None when its rate is unlimited
+    pub async fn take_request(&self, tenant: &TenantContext) -> AppResult<Option<(RateLimit, TokenBucket)>> {
+        let Some(limit) = self.limits(tenant).requests else {
+            return Ok(None);
+        };
+        let key = format!("ratelimit:tenant:{}", tenant.tenant_id);
+        let bucket = self.cache.take_token(&key, limit.burst, limit.refill_rate()).await?;
+        if !bucket.taken {
+            self.refused(tenant, "requests").await;
+            return Err(AppError::RateLimited {
+                retry_after: bucket.retry_after,
+            });
+        }
+        Ok(Some((limit, bucket)))
+    }
+
+    /// Count a notification against the tenant's daily allowance, refusing it once spent
+    pub async fn reserve_notification(&self, tenant: &TenantContext) -> AppResult<()> {
+        let now = Utc::now();
+        let key = notification_key(tenant, now.date_naive());
+        let sent = self.count(&key, now).await?;
+        if let Some(limit) = self.limits(tenant).notifications_per_day {
+            if sent > limit {
+                // Refused notifications are not sent, so give the increment back
+                self.cache.increment(&key, -1, None).await?;
+                self.refused(tenant, "notifications").await;
+                return Err(AppError::QuotaExceeded {
+                    limit,
+                    resets_at: next_day(now.date_naive()),
+                });
+            }
+        }
+        self.sent(tenant).await;
+        Ok(())
+    }
+
+    /// Count a notification that is sent regardless of the allowance, such as an account security email
+    pub async fn count_notification(&self, tenant: &TenantContext) -> AppResult<()> {
+        let now = Utc::now();
+        self.count(&notification_key(tenant, now.date_naive()), now).await?;
+        self.sent(tenant).await;
+        Ok(())
+    }
+
+    /// Refuse early when the tenant has no room for another user
+    pub async fn check_user_capacity(&self, tenant: &TenantContext) -> AppResult<()> {
+        let Some(max_users) = self.limits(tenant).max_users else {
+            return Ok(());
+        };
+        if self.users.count(&tenant.tenant_id).await? as u64 >= max_users {
+            return Err(self.user_limit_reached(tenant, max_users).await);
+        }
+        Ok(())
+    }
+
+    /// Record the user as the tenant's, refusing when that would take it past its maximum
+    pub async fn add_user(&self, tenant: &TenantContext, user_id: Uuid) -> AppResult<()> {
+        let max_users = self.limits(tenant).max_users;
+        if !self.users.add(&tenant.tenant_id, user_id, max_users).await? {
+            return Err(self.user_limit_reached(tenant, max_users.unwrap_or_default()).await);
+        }
+        Ok(())
+    }
+
+    pub async fn usage(&self, tenant: &TenantContext) -> AppResult<TenantUsage> {
+        let limits = self.limits(tenant);
+        let today = notification_key(tenant, Utc::now().date_naive());
+        Ok(TenantUsage {
+            tenant_id: tenant.tenant_id.clone(),
+            notifications_today: self.cache.get_counter(&today).await?.max(0) as u64,
+            notifications_per_day: limits.notifications_per_day,
+            users: self.users.count(&tenant.tenant_id).await?.max(0) as u64,
+            max_users: limits.max_users,
+            requests_per_window: limits.requests.map(|limit| limit.burst),
+            window_secs: limits.requests.map(|limit| limit.window_secs),
+        })
+    }
+
+    async fn count(&self, key: &str, now: DateTime<Utc>) -> AppResult<u64> {
+        let ttl = (next_day(now.date_naive()) - now).to_std().unwrap_or_default() + NOTIFICATION_COUNTER_GRACE;
+        let sent = self.cache.increment(key, 1, Some(ttl)).await?;
+        Ok(sent.max(0) as u64)
+    }
+
+    async fn sent(&self, tenant: &TenantContext) {
+        let _ = self
+            .metrics
+            .increment_labeled_counter("tenant.notifications", &[("tenant", tenant.tenant_id.as_str())])
+            .await;
+    }
+
+    async fn user_limit_reached(&self, tenant: &TenantContext, max_users: u64) -> AppError {
+        self.refused(tenant, "users").await;
+        AppError::Forbidden(format!(
+            "Tenant {} has reached its limit of {} users",
+            tenant.tenant_id, max_users
+        ))
+    }
+
+    async fn refused(&self, tenant: &TenantContext, limit: &str) {
+        let _ = self
+            .metrics
+            .increment_labeled_counter(
+                "tenant.limit_exceeded",
+                &[("tenant", tenant.tenant_id.as_str()), ("limit", limit)],
+            )
+            .await;
+    }
+}
+
+fn notification_key(tenant: &TenantContext, day: NaiveDate) -> String {
+    format!("tenant_notifications:{}:{}", tenant.tenant_id, day.format("%Y-%m-%d"))
+}
+
+fn next_day(day: NaiveDate) -> DateTime<Utc> {
+    let next = day.succ_opt().unwrap_or(day);
+    Utc.from_utc_datetime(&next.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::cache_service::CacheService;
+use crate::models::AppResult;
+
+/// Used refresh tokens and revoked token families, kept in Redis.
+///
+/// A refresh token may be traded in once. Its id is remembered until the
+/// token would have expired anyway, so presenting it again is recognised
+/// as reuse, which means it was copied: the whole family of tokens
+/// descending from the same sign-in is then revoked for as long as any of
+/// them could still be valid.
+pub struct TokenRevocations {
+    cache: Arc<CacheService>,
+}
+
+impl TokenRevocations {
+    pub fn new(cache: Arc<CacheService>) -> Self {
+        Self { cache }
+    }
+
+    /// Mark a refresh token used; false when it had been used before
+    pub async fn mark_used(&self, token_id: Uuid, expires_at: DateTime<Utc>) -> AppResult<bool> {
+        let ttl = (expires_at - Utc::now()).to_std().unwrap_or(Duration::from_secs(1));
+        self.cache.set_if_absent(&used_key(token_id), &true, ttl).await
+    }
+
+    /// Refuse every token of `family_id` for the next `ttl`
+    pub async fn revoke_family(&self, family_id: Uuid, ttl: Duration) -> AppResult<()> {
+        self.cache.set(&family_key(family_id), &true, Some(ttl)).await
+    }
+
+    pub async fn is_family_revoked(&self, family_id: Uuid) -> AppResult<bool> {
+        Ok(self.cache.get::<bool>(&family_key(family_id)).await?.is_some())
+    }
+}
+
+fn used_key(token_id: Uuid) -> String {
+    format!("auth:refresh:used:{}", token_id)
+}
+
+fn family_key(family_id: Uuid) -> String {
+    format!("auth:refresh:revoked:{}", family_id)
+}
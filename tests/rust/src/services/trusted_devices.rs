@@ -0,0 +1,139 @@
+use axum::http::{header, HeaderMap};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::AuthConfig;
+use crate::models::{AppError, AppResult, ClientInfo, IssuedTrustedDevice, TrustedDevice};
+use crate::repositories::TrustedDeviceRepository;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const TRUSTED_DEVICE_COOKIE: &str = "trusted_device";
+/// Only the auth routes read the cookie
+const COOKIE_PATH: &str = "/auth";
+
+/// Browsers a user trusts to sign in with their password alone.
+///
+/// Trusting a device stores it and hands the browser a cookie of the
+/// device id and a MAC over the id and the user's id, so a cookie can
+/// neither be forged nor moved to another account. A sign-in presenting
+/// the cookie skips the second factor while its device is unrevoked and
+/// unexpired; every other sign-in still needs one. Rotating the secret
+/// forgets every trusted device at once.
+pub struct TrustedDevices {
+    repository: Arc<TrustedDeviceRepository>,
+    secret: Vec<u8>,
+    ttl: Duration,
+}
+
+impl TrustedDevices {
+    /// None unless a trusted device secret is configured
+    pub fn from_config(repository: Arc<TrustedDeviceRepository>, config: &AuthConfig) -> Option<Self> {
+        config.trusted_device_secret.as_ref().map(|secret| Self {
+            repository,
+            secret: secret.as_bytes().to_vec(),
+            ttl: config.trusted_device_ttl,
+        })
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> AppResult<Vec<TrustedDevice>> {
+        self.repository.active_for_user(user_id).await
+    }
+
+    pub async fn trust(
+        &self,
+        user_id: Uuid,
+        name: Option<String>,
+        client: &ClientInfo,
+    ) -> AppResult<IssuedTrustedDevice> {
+        let lifetime = chrono::Duration::from_std(self.ttl)
+            .map_err(|e| AppError::Config(format!("Invalid trusted device lifetime: {}", e)))?;
+        let device = TrustedDevice::new(user_id, name, client, lifetime);
+        self.repository.create(&device).await?;
+        let token = format!("{}.{}", device.id, self.mac(device.id, user_id));
+        Ok(IssuedTrustedDevice { device, token })
+    }
+
+    pub async fn rename(&self, user_id: Uuid, device_id: Uuid, name: &str) -> AppResult<TrustedDevice> {
+        self.repository
+            .rename(user_id, device_id, name)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Trusted device {} not found", device_id)))
+    }
+
+    pub async fn revoke(&self, user_id: Uuid, device_id: Uuid) -> AppResult<()> {
+        if !self.repository.revoke(user_id, device_id).await? {
+            return Err(AppError::NotFound(format!("Trusted device {} not found", device_id)));
+        }
+        Ok(())
+    }
+
+    pub async fn revoke_all(&self, user_id: Uuid) -> AppResult<u64> {
+        self.repository.revoke_all(user_id).await
+    }
+
+    /// The active device a cookie value names, when it was issued to `user_id`
+    pub async fn recognize(&self, user_id: Uuid, token: &str) -> AppResult<Option<TrustedDevice>> {
+        let Some((device_id, signature)) = token.split_once('.') else {
+            return Ok(None);
+        };
+        let Ok(device_id) = device_id.parse::<Uuid>() else {
+            return Ok(None);
+        };
+        let Ok(signature) = URL_SAFE_NO_PAD.decode(signature) else {
+            return Ok(None);
+        };
+        let mut mac = self.hmac();
+        mac.update(signed_payload(device_id, user_id).as_bytes());
+        if mac.verify_slice(&signature).is_err() {
+            return Ok(None);
+        }
+        self.repository.find_active(user_id, device_id).await
+    }
+
+    pub async fn record_use(&self, device_id: Uuid) -> AppResult<()> {
+        self.repository.record_use(device_id).await
+    }
+
+    /// `Set-Cookie` value for a newly trusted device, kept from page scripts
+    pub fn cookie(&self, issued: &IssuedTrustedDevice) -> String {
+        format!(
+            "{}={}; Path={}; HttpOnly; Secure; SameSite=Strict; Max-Age={}",
+            TRUSTED_DEVICE_COOKIE,
+            issued.token,
+            COOKIE_PATH,
+            self.ttl.as_secs()
+        )
+    }
+
+    /// The trusted device cookie a request carries
+    pub fn presented(headers: &HeaderMap) -> Option<&str> {
+        headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|cookies| cookies.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(name, _)| *name == TRUSTED_DEVICE_COOKIE)
+            .map(|(_, value)| value)
+    }
+
+    fn mac(&self, device_id: Uuid, user_id: Uuid) -> String {
+        let mut mac = self.hmac();
+        mac.update(signed_payload(device_id, user_id).as_bytes());
+        URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+    }
+
+    fn hmac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length")
+    }
+}
+
+fn signed_payload(device_id: Uuid, user_id: Uuid) -> String {
+    format!("{}:{}", device_id, user_id)
+}
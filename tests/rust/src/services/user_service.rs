@@ -0,0 +1,2552 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::{Rng, RngCore};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+use webauthn_rs::prelude::{CreationChallengeResponse, RegisterPublicKeyCredential, RequestChallengeResponse};
+
+use super::audit_service::AuditService;
+use super::bloom_filter::BloomFilter;
+use super::breached_passwords::BreachedPasswords;
+use super::cache_service::CacheService;
+use super::notification_service::NotificationService;
+use super::login_analytics::LoginAnalytics;
+use super::policy_engine::PolicyEngine;
+use super::read_only::ReadOnlyMode;
+use super::second_factor::SecondFactors;
+use super::session_service::SessionService;
+use super::trusted_devices::TrustedDevices;
+use crate::config::AccountConfig;
+use crate::models::{
+    AccountDeletion, AccountDeletionRequest, ActivityEntry, ActivityPage, AppError, AppResult, AuditAction,
+    AuditEvent, AuditLog, AuditLogAction, AuthContext, BulkAction, BulkOperation, BulkOperationItem, BulkPreview, BulkStatus, ClientInfo, CreateGroupRequest, CreateUserRequest,
+    Authenticator, Device, ExternalIdentity, Group, GroupMembership, SecondFactorProof, SecondFactorSummary, Session,
+    ExternalLogin, TenantContext,
+    CreateRoleRequest, RoleGrant, RoleRequest, RoleRequestDecision, RoleRequestStatus, SettingsRegistry, PolicyResource,
+    TotpEnrollment, User, UpdateUserRequest, UserDataExport, UserFilters, UserRole, UserStatus, WebAuthnChallenge,
+    LoginAttempt, LoginFailureReason, LoginHistoryFilters, ServiceContext, ServiceScope,
+    EffectivePermissions, IssuedTrustedDevice, TrustDeviceRequest, TrustedDevice,
+    OrgRole, UserCursor, UserPage,
+};
+use crate::models::trusted_device::device_name;
+use crate::repositories::{
+    AccountDeletionRepository, AuditRepository, BulkOperationRepository, EmailVerificationRepository, GroupRepository,
+    NotificationRepository, PasswordHistoryRepository, PasswordResetRepository, SecondFactorRepository, SessionRepository, UserRepository,
+    LoginHistoryRepository, OrganizationRepository, RoleGrantRepository, RoleRequestRepository,
+};
+use crate::utils::{KeyRing, Logger, Metrics, PasswordHashing, PasswordVerification, RequestContext, TenantScope};
+
+const EXPORT_NOTIFICATION_LIMIT: i64 = 10_000;
+const BULK_BATCH_SIZE: i64 = 200;
+const BULK_PREVIEW_SAMPLE: i64 = 20;
+const IDENTITY_FILTER_PAGE: i64 = 1000;
+/// Usernames tried for a provisioned account before giving up
+const USERNAME_ATTEMPTS: usize = 5;
+
+/// User account management and group membership
+pub struct UserService {
+    repository: Arc<dyn UserRepository>,
+    groups: Arc<dyn GroupRepository>,
+    deletions: Arc<AccountDeletionRepository>,
+    notifications: Arc<dyn NotificationRepository>,
+    /// Sends account emails such as reset links
+    notifier: Arc<NotificationService>,
+    sessions: Arc<dyn SessionRepository>,
+    /// Live sessions; Postgres keeps the device history behind them
+    live_sessions: Arc<SessionService>,
+    bulk: Arc<BulkOperationRepository>,
+    passwords: Arc<PasswordHistoryRepository>,
+    resets: Arc<PasswordResetRepository>,
+    verifications: Arc<EmailVerificationRepository>,
+    audit: Arc<AuditRepository>,
+    /// Compliance log of security-sensitive actions, kept after accounts are gone
+    audit_log: Arc<AuditService>,
+    role_requests: Arc<RoleRequestRepository>,
+    /// Temporary roles raising users' effective role until they expire
+    role_grants: Arc<RoleGrantRepository>,
+    /// Organization roles, delegating the management of members to org admins
+    organizations: Arc<OrganizationRepository>,
+    /// What each role may do to which users
+    policies: Arc<PolicyEngine>,
+    hashing: PasswordHashing,
+    /// Known `custom_settings` keys and the values they accept
+    settings: SettingsRegistry,
+    second_factors: SecondFactors,
+    /// Registered emails and usernames, so negative existence checks skip Postgres
+    emails: BloomFilter,
+    usernames: BloomFilter,
+    /// Passwords known from breaches, refused when chosen
+    breached: BreachedPasswords,
+    /// Counts verification re-sends and keeps effective permissions, per user
+    cache: Arc<CacheService>,
+    /// Refuses changes other than signing in and out during maintenance
+    read_only: Arc<ReadOnlyMode>,
+    /// Failed sign-ins by client address and account, for spotting credential stuffing
+    login_analytics: Arc<LoginAnalytics>,
+    /// Every sign-in attempt, for users' history and sign-in decisions
+    login_history: Arc<LoginHistoryRepository>,
+    /// Devices whose sign-ins skip the second factor; None when no cookie secret is configured
+    trusted_devices: Option<Arc<TrustedDevices>>,
+    metrics: Arc<Metrics>,
+    config: AccountConfig,
+    /// Stops bulk operations at the next batch boundary
+    shutdown: CancellationToken,
+    logger: Arc<Logger>,
+}
+
+impl UserService {
+    pub async fn new(
+        repository: Arc<dyn UserRepository>,
+        groups: Arc<dyn GroupRepository>,
+        deletions: Arc<AccountDeletionRepository>,
+        notifications: Arc<dyn NotificationRepository>,
+        notifier: Arc<NotificationService>,
+        sessions: Arc<dyn SessionRepository>,
+        live_sessions: Arc<SessionService>,
+        bulk: Arc<BulkOperationRepository>,
+        passwords: Arc<PasswordHistoryRepository>,
+        resets: Arc<PasswordResetRepository>,
+        verifications: Arc<EmailVerificationRepository>,
+        audit: Arc<AuditRepository>,
+        audit_log: Arc<AuditService>,
+        role_requests: Arc<RoleRequestRepository>,
+        role_grants: Arc<RoleGrantRepository>,
+        organizations: Arc<OrganizationRepository>,
+        policies: Arc<PolicyEngine>,
+        second_factors: Arc<dyn SecondFactorRepository>,
+        key_ring: Arc<KeyRing>,
+        cache: Arc<CacheService>,
+        read_only: Arc<ReadOnlyMode>,
+        login_analytics: Arc<LoginAnalytics>,
+        login_history: Arc<LoginHistoryRepository>,
+        trusted_devices: Option<Arc<TrustedDevices>>,
+        metrics: Arc<Metrics>,
+        config: AccountConfig,
+        shutdown: CancellationToken,
+        logger: Arc<Logger>,
+    ) -> AppResult<Self> {
+        let capacity = config.identity_filter_capacity;
+        let error_rate = config.identity_filter_error_rate;
+        Ok(Self {
+            repository,
+            groups,
+            deletions,
+            notifications,
+            notifier,
+            sessions,
+            live_sessions,
+            bulk,
+            passwords,
+            resets,
+            verifications,
+            audit,
+            audit_log,
+            role_requests,
+            role_grants,
+            organizations,
+            policies,
+            hashing: PasswordHashing::new(config.password_policy.algorithm),
+            settings: SettingsRegistry::standard(),
+            second_factors: SecondFactors::new(second_factors, cache.clone(), key_ring, &config)?,
+            emails: BloomFilter::new(cache.clone(), "bloom:user_emails", capacity, error_rate),
+            usernames: BloomFilter::new(cache.clone(), "bloom:usernames", capacity, error_rate),
+            breached: BreachedPasswords::new(cache.clone(), &config.password_policy, logger.clone()),
+            cache,
+            read_only,
+            login_analytics,
+            login_history,
+            trusted_devices,
+            metrics,
+            config,
+            shutdown,
+            logger,
+        })
+    }
+
+    pub async fn initialize(&self) -> AppResult<()> {
+        // Another instance may already have built the filters
+        match self.emails.is_ready().await {
+            Ok(true) => {}
+            Ok(false) => self.rebuild_identity_filters().await?,
+            Err(e) => self
+                .logger
+                .warn(&format!("Identity filters unavailable, existence checks use the database: {}", e)),
+        }
+        self.logger.info("User service initialized");
+        Ok(())
+    }
+
+    /// Continue bulk operations a previous shutdown stopped at a batch boundary.
+    ///
+    /// Can take as long as the operations themselves, so it is run as a
+    /// background task rather than during initialization.
+    pub async fn resume_bulk_operations(&self) -> AppResult<usize> {
+        let mut resumed = 0;
+        for mut operation in self.bulk.interrupted().await? {
+            if !self.bulk.claim_interrupted(operation.id).await? {
+                continue;
+            }
+            // The actor is re-checked, so a demoted admin's operation fails instead of resuming
+            operation.status = BulkStatus::Running;
+            let result = match self.require_bulk_actor(operation.requested_by).await {
+                Ok(actor) => self.apply_bulk(&mut operation, &actor).await,
+                Err(e) => Err(e),
+            };
+            self.finish_bulk(&mut operation, result).await?;
+            resumed += 1;
+        }
+        if resumed > 0 {
+            self.logger
+                .info(&format!("Resumed {} interrupted bulk operations", resumed));
+        }
+        Ok(resumed)
+    }
+
+    /// Load the configured breached password list, returning how many entries were added
+    pub async fn load_breached_passwords(&self) -> AppResult<usize> {
+        self.breached.load().await
+    }
+
+    /// Repopulate the email and username filters from every stored user
+    pub async fn rebuild_identity_filters(&self) -> AppResult<()> {
+        self.emails.clear().await?;
+        self.usernames.clear().await?;
+
+        let filters = UserFilters::new().with_limit(IDENTITY_FILTER_PAGE);
+        let mut cursor = None;
+        let mut total = 0;
+        loop {
+            let page = self.repository.list_page(&filters, cursor.as_ref()).await?;
+            let emails: Vec<&str> = page.users.iter().map(|u| u.email.as_str()).collect();
+            let usernames: Vec<&str> = page.users.iter().map(|u| u.username.as_str()).collect();
+            self.emails.insert_many(&emails).await?;
+            self.usernames.insert_many(&usernames).await?;
+
+            total += page.users.len();
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        self.emails.mark_ready().await?;
+        self.usernames.mark_ready().await?;
+        self.logger
+            .info(&format!("Rebuilt identity filters from {} users", total));
+        Ok(())
+    }
+
+    /// Whether an account with this email exists in any tenant; most unknown emails are answered without a query
+    pub async fn email_exists(&self, email: &str) -> AppResult<bool> {
+        let email = self.config.email_policy.normalize(email);
+        if !self.filter_might_contain(&self.emails, &email).await {
+            return Ok(false);
+        }
+        self.repository.email_taken(&email).await
+    }
+
+    pub async fn username_exists(&self, username: &str) -> AppResult<bool> {
+        if !self.filter_might_contain(&self.usernames, username).await {
+            return Ok(false);
+        }
+        self.repository.username_taken(username).await
+    }
+
+    pub async fn shutdown(&self) -> AppResult<()> {
+        self.logger.info("User service shut down");
+        Ok(())
+    }
+
+    /// Create a user after validating the request and checking uniqueness
+    pub async fn create_user(&self, mut request: CreateUserRequest) -> AppResult<User> {
+        self.read_only.check()?;
+        RequestContext::check("create_user")?;
+        request.email = self.config.email_policy.normalize(&request.email);
+        let mut errors = request.validate();
+        errors.extend(self.config.email_policy.validate(&request.email));
+        errors.extend(self.config.username_policy.validate(&request.username));
+        if let Some(password) = &request.password {
+            errors.extend(self.password_problems(password).await);
+        }
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        if self.email_exists(&request.email).await? {
+            return Err(AppError::Conflict(format!("Email {} is already registered", request.email)));
+        }
+        if self.username_exists(&request.username).await? {
+            return Err(AppError::Conflict(format!("Username {} is taken", request.username)));
+        }
+
+        let password_hash = match request.password.take() {
+            Some(password) => {
+                let hashing = self.hashing.clone();
+                blocking(move || hashing.hash(&password)).await??
+            }
+            None => String::new(),
+        };
+        let mut user = User::new(
+            request.email,
+            request.username,
+            request.first_name,
+            request.last_name,
+            password_hash,
+        );
+        user.role = request.role;
+        user.region = request.region;
+
+        let user = self.repository.create(&user).await?;
+        let depth = self.config.password_policy.history_depth;
+        if depth > 0 && !user.password_hash.is_empty() {
+            self.passwords.record(user.id, &user.password_hash, depth).await?;
+        }
+        self.remember_identity(&user).await;
+        self.audit_log
+            .record(AuditLog::new(None, AuditLogAction::UserCreated, Some(user.id)).with_after(&user))
+            .await;
+
+        self.logger.info(&format!("Created user {} ({})", user.id, user.username));
+        Ok(user)
+    }
+
+    /// Known settings, for reading a user's `custom_settings` by typed key
+    pub fn settings(&self) -> &SettingsRegistry {
+        &self.settings
+    }
+
+    /// Authorization rules, for checks made by the API layer
+    pub fn policies(&self) -> &Arc<PolicyEngine> {
+        &self.policies
+    }
+
+    /// `user` as checked against by `actor`, with the standing the actor has
+    /// over them through organizations. Org roles never reach staff, so it
+    /// is looked up for plain users only.
+    pub async fn user_resource(&self, actor: &User, user: &User) -> AppResult<PolicyResource> {
+        let resource = PolicyResource::user(user);
+        if actor.id == user.id || user.role != UserRole::User {
+            return Ok(resource);
+        }
+        Ok(resource.with_org_role(self.organizations.standing(actor.id, user.id).await?))
+    }
+
+    /// Look up a user; the repository serves cached copies when it is wrapped in a cache
+    pub async fn get_user_by_id(&self, id: Uuid) -> AppResult<Option<User>> {
+        self.repository.find_by_id(id).await
+    }
+
+    /// Apply an edit by `actor`, provided nobody has changed the user since `expected_version`.
+    ///
+    /// Editing needs the `users:update` permission on the user, which users
+    /// hold for their own profile; changing a role or status needs
+    /// `users:manage` and a role above both the user's current and new role. A stale version fails with
+    /// `AppError::Conflict` and changes nothing.
+    pub async fn update_user(
+        &self,
+        actor: &User,
+        id: Uuid,
+        request: UpdateUserRequest,
+        expected_version: i64,
+    ) -> AppResult<User> {
+        self.read_only.check()?;
+        RequestContext::check("update_user")?;
+        let mut user = self.require_user(id).await?;
+        let before = user.clone();
+        let edited: Vec<&str> = [
+            ("email", request.email.is_some()),
+            ("username", request.username.is_some()),
+            ("first_name", request.first_name.is_some()),
+            ("last_name", request.last_name.is_some()),
+            ("preferences", request.preferences.is_some()),
+            ("metadata", request.metadata.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(field, present)| present.then_some(field))
+        .collect();
+        let resource = self.user_resource(actor, &before).await?;
+        if !self.policies.allows(actor, "users:update", Some(&resource)) {
+            return Err(AppError::Forbidden("Cannot edit another user".to_string()));
+        }
+        if user.version != expected_version {
+            return Err(AppError::Conflict(format!(
+                "User {} is at version {}, not {}",
+                id, user.version, expected_version
+            )));
+        }
+
+        let mut errors = Vec::new();
+        if let Some(email) = request.email {
+            let email = self.config.email_policy.normalize(&email);
+            if email != user.email {
+                errors.extend(self.config.email_policy.validate(&email));
+                if self.email_exists(&email).await? {
+                    return Err(AppError::Conflict(format!("Email {} is already registered", email)));
+                }
+                user.email = email;
+                user.email_verified = false;
+            }
+        }
+        if let Some(username) = request.username {
+            if username != user.username {
+                errors.extend(self.config.username_policy.validate(&username));
+                if self.username_exists(&username).await? {
+                    return Err(AppError::Conflict(format!("Username {} is taken", username)));
+                }
+                user.username = username;
+            }
+        }
+        if let Some(first_name) = request.first_name {
+            if first_name.trim().is_empty() {
+                errors.push("First name is required".to_string());
+            }
+            user.first_name = first_name;
+        }
+        if let Some(last_name) = request.last_name {
+            if last_name.trim().is_empty() {
+                errors.push("Last name is required".to_string());
+            }
+            user.last_name = last_name;
+        }
+        if request.role.is_some() || request.status.is_some() {
+            // Organization admins may change the status of their members, never their role
+            let outranks = actor.role.can_manage(&user.role)
+                || (request.role.is_none() && resource.org_role == Some(OrgRole::OrgAdmin));
+            if !self.policies.allows(actor, "users:manage", Some(&resource)) || !outranks {
+                return Err(AppError::Forbidden("Cannot change the role or status of this user".to_string()));
+            }
+            if let Some(role) = request.role {
+                if !actor.role.can_manage(&role) {
+                    return Err(AppError::Forbidden(format!("Cannot grant the {} role", role.as_str())));
+                }
+                user.role = role;
+            }
+            if let Some(status) = request.status {
+                user.status = status;
+            }
+        }
+        if let Some(mut preferences) = request.preferences {
+            self.settings.migrate(&mut preferences);
+            errors.extend(self.settings.validate_update(&user.preferences, &preferences));
+            // Only enrolling or removing an authenticator app turns two-factor sign-in on or off
+            preferences.two_factor_enabled = user.preferences.two_factor_enabled;
+            user.preferences = preferences;
+        }
+        if let Some(metadata) = request.metadata {
+            user.metadata = metadata;
+        }
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        user.touch();
+        let user = self.repository.update_versioned(&user, expected_version).await?;
+        self.remember_identity(&user).await;
+        self.forget_permissions(user.id).await;
+        if !edited.is_empty() {
+            self.record_audit(id, Some(actor.id), AuditAction::ProfileUpdated, json!({ "fields": edited }))
+                .await;
+        }
+        if user.role != before.role {
+            let details = json!({ "from": before.role.as_str(), "to": user.role.as_str() });
+            self.record_audit(id, Some(actor.id), AuditAction::RoleChanged, details).await;
+        }
+        if user.status != before.status {
+            let details = json!({ "from": before.status.as_str(), "to": user.status.as_str() });
+            self.record_audit(id, Some(actor.id), AuditAction::StatusChanged, details).await;
+        }
+        self.log_access_change(Some(actor.id), &before, &user, json!({})).await;
+        self.logger.info(&format!(
+            "User {} edited by {} (version {})",
+            user.id, actor.id, user.version
+        ));
+        Ok(user)
+    }
+
+    /// Ask for a higher role for `actor` or, when they manage the user's role, for someone else
+    pub async fn request_role(&self, actor: &User, request: CreateRoleRequest) -> AppResult<RoleRequest> {
+        self.read_only.check()?;
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+        let user_id = request.user_id.unwrap_or(actor.id);
+        let user = self.require_user(user_id).await?;
+        if user_id != actor.id && !actor.role.can_manage(&user.role) {
+            return Err(AppError::Forbidden("Cannot request a role for this user".to_string()));
+        }
+        if request.role.level() <= user.role.level() {
+            return Err(AppError::Validation(vec![format!(
+                "Requested role must be above the current {} role",
+                user.role.as_str()
+            )]));
+        }
+        if self.role_requests.pending_for_user(user_id).await?.is_some() {
+            return Err(AppError::Conflict(format!("User {} already has a pending role request", user_id)));
+        }
+
+        let role_request = RoleRequest::new(user_id, actor.id, user.role.clone(), request);
+        self.role_requests.create(&role_request).await?;
+        let entry = AuditLog::new(Some(actor.id), AuditLogAction::RoleRequested, Some(user_id))
+            .with_before(&user)
+            .with_context(json!({
+                "request_id": role_request.id,
+                "requested_role": role_request.requested_role.as_str(),
+                "rationale": role_request.rationale,
+            }));
+        self.audit_log.record(entry).await;
+        self.logger.info(&format!(
+            "User {} requested the {} role for {}",
+            actor.id,
+            role_request.requested_role.as_str(),
+            user_id
+        ));
+        Ok(role_request)
+    }
+
+    /// Approve or deny a pending role request; approving changes the user's role at once
+    pub async fn decide_role_request(
+        &self,
+        actor: &User,
+        id: Uuid,
+        decision: RoleRequestDecision,
+    ) -> AppResult<RoleRequest> {
+        self.read_only.check()?;
+        let errors = decision.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+        let request = self
+            .role_requests
+            .find(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Role request {} not found", id)))?;
+        if request.status != RoleRequestStatus::Pending {
+            return Err(AppError::Conflict(format!("Role request {} was already {}", id, request.status.as_str())));
+        }
+        if actor.id == request.requested_by || actor.id == request.user_id {
+            return Err(AppError::Forbidden("Cannot decide a role request you are part of".to_string()));
+        }
+        let mut user = self.require_user(request.user_id).await?;
+        if !actor.role.can_manage(&user.role) || !actor.role.can_manage(&request.requested_role) {
+            return Err(AppError::Forbidden(format!(
+                "Cannot grant the {} role",
+                request.requested_role.as_str()
+            )));
+        }
+        if decision.approve && user.role != request.current_role {
+            return Err(AppError::Conflict(format!(
+                "User {} changed role since the request was made",
+                user.id
+            )));
+        }
+
+        let status = if decision.approve { RoleRequestStatus::Approved } else { RoleRequestStatus::Denied };
+        let note = decision.note.as_deref().map(str::trim).filter(|note| !note.is_empty());
+        let decided = self
+            .role_requests
+            .decide(id, status, actor.id, note)
+            .await?
+            .ok_or_else(|| AppError::Conflict(format!("Role request {} was decided meanwhile", id)))?;
+        let context = json!({
+            "request_id": decided.id,
+            "requested_by": decided.requested_by,
+            "rationale": decided.rationale,
+            "note": decided.decision_note,
+        });
+
+        if decision.approve {
+            let before = user.clone();
+            user.role = decided.requested_role.clone();
+            user.touch();
+            let user = self.save(&user).await?;
+            let details = json!({ "from": before.role.as_str(), "to": user.role.as_str(), "request_id": decided.id });
+            self.record_audit(user.id, Some(actor.id), AuditAction::RoleChanged, details).await;
+            self.log_access_change(Some(actor.id), &before, &user, context).await;
+        } else {
+            let entry = AuditLog::new(Some(actor.id), AuditLogAction::RoleRequestDenied, Some(user.id))
+                .with_before(&user)
+                .with_context(context);
+            self.audit_log.record(entry).await;
+        }
+        self.logger.info(&format!(
+            "Role request {} {} by {}",
+            decided.id,
+            decided.status.as_str(),
+            actor.id
+        ));
+        Ok(decided)
+    }
+
+    /// Pending requests `actor` could decide, oldest first
+    pub async fn pending_role_requests(&self, actor: &User, limit: i64) -> AppResult<Vec<RoleRequest>> {
+        let pending = self.role_requests.pending(limit).await?;
+        Ok(pending
+            .into_iter()
+            .filter(|request| actor.id != request.requested_by && actor.id != request.user_id)
+            .filter(|request| {
+                actor.role.can_manage(&request.current_role) && actor.role.can_manage(&request.requested_role)
+            })
+            .collect())
+    }
+
+    /// A user's role requests, newest first; visible to the user and to those managing their role
+    pub async fn role_requests_for(&self, actor: &User, user_id: Uuid) -> AppResult<Vec<RoleRequest>> {
+        if actor.id != user_id {
+            let user = self.require_user(user_id).await?;
+            if !actor.role.can_manage(&user.role) {
+                return Err(AppError::Forbidden("Cannot view this user's role requests".to_string()));
+            }
+        }
+        self.role_requests.for_user(user_id).await
+    }
+
+    /// Raise a user's role for `duration`, after which they are back to their own.
+    ///
+    /// Granting needs `users:manage` on the user and a role above both the
+    /// user's own and the one granted, and lasts at most the configured
+    /// maximum. The stored role is untouched: the grant only counts towards
+    /// the effective role until it expires.
+    pub async fn grant_temporary_role(
+        &self,
+        actor: &User,
+        user_id: Uuid,
+        role: UserRole,
+        duration: std::time::Duration,
+    ) -> AppResult<RoleGrant> {
+        self.read_only.check()?;
+        let user = self.require_user(user_id).await?;
+        let resource = PolicyResource::user(&user);
+        if !self.policies.allows(actor, "users:manage", Some(&resource))
+            || !actor.role.can_manage(&user.role)
+            || !actor.role.can_manage(&role)
+        {
+            return Err(AppError::Forbidden(format!("Cannot grant the {} role", role.as_str())));
+        }
+
+        let mut errors = Vec::new();
+        if role.level() <= user.role.level() {
+            errors.push(format!("Granted role must be above the current {} role", user.role.as_str()));
+        }
+        if duration.is_zero() || duration > self.config.role_grant_max_duration {
+            errors.push(format!(
+                "Duration must be between 1 second and {} hours",
+                self.config.role_grant_max_duration.as_secs() / 3600
+            ));
+        }
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+        let duration = chrono::Duration::from_std(duration)
+            .map_err(|e| AppError::Validation(vec![format!("Invalid duration: {}", e)]))?;
+
+        let grant = RoleGrant::new(user.id, role, Some(actor.id), chrono::Utc::now() + duration);
+        self.role_grants.create(&grant).await?;
+        self.forget_permissions(user.id).await;
+        let details = json!({
+            "from": user.role.as_str(),
+            "to": grant.role.as_str(),
+            "grant_id": grant.id,
+            "expires_at": grant.expires_at,
+        });
+        self.record_audit(user.id, Some(actor.id), AuditAction::RoleChanged, details.clone()).await;
+        let entry = AuditLog::new(Some(actor.id), AuditLogAction::RoleElevated, Some(user.id))
+            .with_before(&user)
+            .with_context(details);
+        self.audit_log.record(entry).await;
+        self.logger.info(&format!(
+            "User {} granted the {} role until {} by {}",
+            user.id,
+            grant.role.as_str(),
+            grant.expires_at,
+            actor.id
+        ));
+        Ok(grant)
+    }
+
+    /// Everything the user may do outright, from their role and the roles temporarily granted to them.
+    ///
+    /// Sets are cached under the fingerprint of the rules in force, so a
+    /// policy reload recomputes them all. Changes to the user's role, status
+    /// or temporary roles drop their entry, and an entry never outlives the
+    /// first temporary role it counts.
+    pub async fn effective_permissions(&self, id: Uuid) -> AppResult<EffectivePermissions> {
+        let key = self.permissions_key(id);
+        match self.cache.get::<EffectivePermissions>(&key).await {
+            Ok(Some(permissions)) => return Ok(permissions),
+            Ok(None) => {}
+            Err(e) => self.logger.warn(&format!("Permission cache read failed: {}", e)),
+        }
+
+        let user = self.require_user(id).await?;
+        let grants = self.role_grants.active_for_user(id).await?;
+        let role = grants
+            .iter()
+            .map(|grant| grant.role.clone())
+            .chain(std::iter::once(user.role.clone()))
+            .max_by_key(UserRole::level)
+            .unwrap_or_else(|| user.role.clone());
+        // Accounts that cannot sign in hold no permissions
+        let (allowed, denied) = if user.can_authenticate() {
+            self.policies.role_permissions(&role)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        let now = chrono::Utc::now();
+        let permissions = EffectivePermissions {
+            user_id: id,
+            role,
+            allowed,
+            denied,
+            expires_at: grants.iter().map(|grant| grant.expires_at).min(),
+            computed_at: now,
+        };
+
+        let ttl = permissions
+            .expires_at
+            .map_or(self.config.permissions_cache_ttl, |expires| {
+                (expires - now).to_std().unwrap_or_default().min(self.config.permissions_cache_ttl)
+            });
+        if !ttl.is_zero() {
+            if let Err(e) = self.cache.set(&key, &permissions, Some(ttl)).await {
+                self.logger.warn(&format!("Permission cache write failed: {}", e));
+            }
+        }
+        Ok(permissions)
+    }
+
+    /// End a temporary role before it expires; allowed to whoever could have granted it
+    pub async fn revoke_role_grant(&self, actor: &User, user_id: Uuid, grant_id: Uuid) -> AppResult<RoleGrant> {
+        self.read_only.check()?;
+        let user = self.require_user(user_id).await?;
+        let resource = PolicyResource::user(&user);
+        if !self.policies.allows(actor, "users:manage", Some(&resource)) || !actor.role.can_manage(&user.role) {
+            return Err(AppError::Forbidden("Cannot revoke this user's roles".to_string()));
+        }
+        let open = self.role_grants.active_for_user(user_id).await?;
+        if open.iter().any(|grant| grant.id == grant_id && !actor.role.can_manage(&grant.role)) {
+            return Err(AppError::Forbidden("Cannot revoke a role above your own".to_string()));
+        }
+        let grant = self
+            .role_grants
+            .end(user_id, grant_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Open role grant {} not found", grant_id)))?;
+        self.record_role_grant_end(&grant, Some(actor.id), "revoked").await;
+        Ok(grant)
+    }
+
+    /// A user's temporary roles, newest first; visible to the user and to those managing their role
+    pub async fn role_grants_for(&self, actor: &User, user_id: Uuid) -> AppResult<Vec<RoleGrant>> {
+        if actor.id != user_id {
+            let user = self.require_user(user_id).await?;
+            if !actor.role.can_manage(&user.role) {
+                return Err(AppError::Forbidden("Cannot view this user's roles".to_string()));
+            }
+        }
+        self.role_grants.for_user(user_id).await
+    }
+
+    /// The highest of the user's own role and the roles they were temporarily granted
+    pub async fn effective_role(&self, user: &User) -> AppResult<UserRole> {
+        Ok(self
+            .role_grants
+            .active_for_user(user.id)
+            .await?
+            .into_iter()
+            .map(|grant| grant.role)
+            .chain(std::iter::once(user.role.clone()))
+            .max_by_key(UserRole::level)
+            .unwrap_or_else(|| user.role.clone()))
+    }
+
+    /// Close up to `limit` temporary roles that have run out, auditing each; returns how many were closed
+    pub async fn end_expired_role_grants(&self, limit: i64) -> AppResult<usize> {
+        // Sweeps wait for the end of maintenance rather than fail every run
+        if self.read_only.is_read_only() {
+            return Ok(0);
+        }
+        let expired = self.role_grants.end_expired(limit).await?;
+        for grant in &expired {
+            self.record_role_grant_end(grant, None, "expired").await;
+        }
+        Ok(expired.len())
+    }
+
+    /// Look up a user by email, normalized the way registration stores it
+    pub async fn get_user_by_email(&self, email: &str) -> AppResult<Option<User>> {
+        let email = self.config.email_policy.normalize(email);
+        self.repository.find_by_email(&email).await
+    }
+
+    /// Create a passwordless account for a verified identity from an external provider.
+    ///
+    /// The username is derived from the provider's handle or the email and
+    /// gets a numeric suffix when taken; names the provider did not share
+    /// are left as placeholders for the user to fill in.
+    pub async fn provision_external_user(&self, identity: &ExternalIdentity, role: UserRole) -> AppResult<User> {
+        let Some(email) = identity.email.clone().filter(|_| identity.email_verified) else {
+            return Err(AppError::Unauthorized("Provider did not report a verified email".to_string()));
+        };
+        let username = self.available_username(identity, &email).await?;
+        let placeholder = || "-".to_string();
+
+        let mut user = self
+            .create_user(CreateUserRequest {
+                email,
+                username,
+                first_name: identity.first_name.clone().filter(|n| !n.is_empty()).unwrap_or_else(placeholder),
+                last_name: identity.last_name.clone().filter(|n| !n.is_empty()).unwrap_or_else(placeholder),
+                password: None,
+                role,
+                region: None,
+            })
+            .await?;
+        user.email_verified = true;
+        let user = self.save(&user).await?;
+        self.logger.info(&format!(
+            "Provisioned user {} from {} identity",
+            user.id, identity.provider
+        ));
+        Ok(user)
+    }
+
+    async fn available_username(&self, identity: &ExternalIdentity, email: &str) -> AppResult<String> {
+        let policy = &self.config.username_policy;
+        let source = identity
+            .username
+            .clone()
+            .unwrap_or_else(|| email.split('@').next().unwrap_or_default().to_string());
+        let mut base: String = source
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || policy.allowed_symbols.contains(*c))
+            .skip_while(|c| !c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+        // Leave room for the suffix that tells apart people with the same handle
+        base.truncate(policy.max_length.saturating_sub(4));
+
+        for attempt in 0..USERNAME_ATTEMPTS {
+            let candidate = if attempt == 0 {
+                base.clone()
+            } else {
+                format!("{}{}", base, rand::thread_rng().gen_range(1000..10000))
+            };
+            if policy.check(&candidate).is_empty() && !self.username_exists(&candidate).await? {
+                return Ok(candidate);
+            }
+        }
+        Err(AppError::Conflict(format!(
+            "No username available for {} identity {}",
+            identity.provider, identity.subject
+        )))
+    }
+
+    pub async fn get_active_users(&self) -> AppResult<Vec<User>> {
+        self.repository
+            .list(&UserFilters::new().with_status(UserStatus::Active))
+            .await
+    }
+
+    /// Change a password after confirming the current one
+    pub async fn set_password(&self, user_id: Uuid, current_password: &str, new_password: &str) -> AppResult<()> {
+        self.read_only.check()?;
+        let user = self.require_user(user_id).await?;
+        let hashing = self.hashing.clone();
+        let (stored, current) = (user.password_hash.clone(), current_password.to_string());
+        if !blocking(move || hashing.matches(&current, &stored)).await? {
+            return Err(AppError::Unauthorized("Current password is incorrect".to_string()));
+        }
+        self.change_password(user, new_password).await
+    }
+
+    /// Replace a password without the current one, for verified reset flows
+    pub async fn reset_password(&self, user_id: Uuid, new_password: &str) -> AppResult<()> {
+        self.read_only.check()?;
+        let user = self.require_user(user_id).await?;
+        self.change_password(user, new_password).await
+    }
+
+    /// Email a single-use password reset link to the account registered under `email`.
+    ///
+    /// Succeeds whether or not such an account exists, so the response does
+    /// not reveal which emails are registered. A new link supersedes any
+    /// earlier one that is still outstanding.
+    pub async fn request_password_reset(&self, tenant: &TenantContext, email: &str) -> AppResult<()> {
+        self.read_only.check()?;
+        let user = match self.get_user_by_email(email).await? {
+            Some(user) if user.status.is_active() && user.deleted_at.is_none() => user,
+            _ => {
+                self.logger.debug("Password reset requested for an unknown or inactive account");
+                return Ok(());
+            }
+        };
+
+        let token = new_link_token();
+        let ttl = self.config.password_reset_ttl;
+        self.resets.issue(user.id, &hash_link_token(&token), link_expiry(ttl)?).await?;
+
+        let link = format!("{}?token={}", self.config.password_reset_url, token);
+        self.notifier.send_password_reset(tenant, &user, &link, ttl).await?;
+        self.logger.info(&format!("Password reset link sent to user {}", user.id));
+        Ok(())
+    }
+
+    /// Set a new password with a token from a reset link, then sign the user out everywhere
+    pub async fn complete_password_reset(&self, token: &str, new_password: &str) -> AppResult<()> {
+        self.read_only.check()?;
+        let invalid = || AppError::Unauthorized("Password reset link is invalid or has expired".to_string());
+        let redemption = self
+            .resets
+            .redeem(&hash_link_token(token))
+            .await?
+            .ok_or_else(invalid)?;
+        let user_id = redemption.user_id;
+        let user = self.require_user(user_id).await?;
+
+        // The token is spent before the password changes, and given back
+        // when the password is rejected so the link can be retried
+        if let Err(e) = self.change_password(user, new_password).await {
+            redemption.release().await?;
+            return Err(e);
+        }
+        redemption.commit().await?;
+        self.sign_out_everywhere(user_id).await?;
+        Ok(())
+    }
+
+    /// Email the user a link confirming their current address; a new link supersedes earlier ones
+    pub async fn send_email_verification(&self, tenant: &TenantContext, user_id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        let user = self.require_user(user_id).await?;
+        if user.email_verified {
+            return Ok(());
+        }
+
+        let token = new_link_token();
+        let ttl = self.config.email_verification_ttl;
+        self.verifications
+            .issue(user.id, &user.email, &hash_link_token(&token), link_expiry(ttl)?)
+            .await?;
+        let link = format!("{}?token={}", self.config.email_verification_url, token);
+        self.notifier.send_email_verification(tenant, &user, &link, ttl).await
+    }
+
+    /// One page of the segment a verification reminder campaign works
+    /// through: active users signed up before `cutoff` who have not verified
+    /// their address. Empty while read-only, since the campaign would change them.
+    pub async fn unverified_users(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+        cursor: Option<&UserCursor>,
+        limit: i64,
+    ) -> AppResult<UserPage> {
+        if self.read_only.is_read_only() {
+            return Ok(UserPage {
+                users: Vec::new(),
+                next_cursor: None,
+            });
+        }
+        let filters = UserFilters::unverified_before(cutoff).with_limit(limit);
+        self.repository.list_page(&filters, cursor).await
+    }
+
+    /// Remind the user to confirm their address with a new link, superseding earlier ones
+    pub async fn send_verification_reminder(
+        &self,
+        tenant: &TenantContext,
+        user: &User,
+        template_key: &str,
+        suspends_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> AppResult<()> {
+        self.read_only.check()?;
+        let token = new_link_token();
+        let ttl = self.config.email_verification_ttl;
+        self.verifications
+            .issue(user.id, &user.email, &hash_link_token(&token), link_expiry(ttl)?)
+            .await?;
+        let link = format!("{}?token={}", self.config.email_verification_url, token);
+        self.notifier
+            .send_verification_reminder(tenant, user, template_key, &link, ttl, suspends_at)
+            .await
+    }
+
+    /// Suspend an account whose address was left unverified past the
+    /// campaign's deadline; None when it was verified or changed status meanwhile
+    pub async fn suspend_unverified(&self, user_id: Uuid) -> AppResult<Option<User>> {
+        self.read_only.check()?;
+        let before = self.require_user(user_id).await?;
+        if before.email_verified || before.status != UserStatus::Active {
+            return Ok(None);
+        }
+        let mut user = before.clone();
+        user.status = UserStatus::Suspended;
+        user.touch();
+        let user = self.save(&user).await?;
+        let context = json!({ "reason": "email_unverified" });
+        self.log_access_change(None, &before, &user, context).await;
+        let details = json!({
+            "from": before.status.as_str(),
+            "to": user.status.as_str(),
+            "reason": "email_unverified",
+        });
+        self.record_audit(user.id, None, AuditAction::StatusChanged, details).await;
+        self.logger
+            .info(&format!("Suspended user {} for leaving their email unverified", user.id));
+        Ok(Some(user))
+    }
+
+    /// `send_email_verification` on the user's request, limited to a few sends per window
+    pub async fn resend_email_verification(&self, tenant: &TenantContext, user_id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        let window = self.config.email_verification_resend_window;
+        let limit = self.config.email_verification_resend_limit;
+        let sent = self
+            .cache
+            .increment(&format!("email_verification_sends:{}", user_id), 1, Some(window))
+            .await?;
+        if sent as u64 > limit {
+            return Err(AppError::QuotaExceeded {
+                limit,
+                resets_at: link_expiry(window)?,
+            });
+        }
+        self.send_email_verification(tenant, user_id).await
+    }
+
+    /// Redeem a verification link, marking the address it was sent to verified
+    pub async fn verify_email(&self, token: &str) -> AppResult<User> {
+        self.read_only.check()?;
+        let invalid = || AppError::Unauthorized("Verification link is invalid or has expired".to_string());
+        let (user_id, email) = self
+            .verifications
+            .consume(&hash_link_token(token))
+            .await?
+            .ok_or_else(invalid)?;
+        // A link sent before an email change must not vouch for the new address
+        let user = self
+            .repository
+            .mark_email_verified(user_id, &email)
+            .await?
+            .ok_or_else(invalid)?;
+        self.record_audit(user.id, Some(user.id), AuditAction::EmailVerified, json!({})).await;
+        self.logger.info(&format!("User {} verified their email", user.id));
+        Ok(user)
+    }
+
+    /// Authenticators and remaining backup codes of a user
+    pub async fn second_factors(&self, user_id: Uuid) -> AppResult<SecondFactorSummary> {
+        self.second_factors.summary(user_id).await
+    }
+
+    /// Start registering a WebAuthn authenticator; users may register several
+    pub async fn begin_authenticator_registration(
+        &self,
+        user_id: Uuid,
+        name: &str,
+    ) -> AppResult<WebAuthnChallenge<CreationChallengeResponse>> {
+        self.read_only.check()?;
+        let user = self.require_user(user_id).await?;
+        self.second_factors.begin_registration(&user, name).await
+    }
+
+    pub async fn finish_authenticator_registration(
+        &self,
+        user_id: Uuid,
+        ceremony_id: Uuid,
+        response: &RegisterPublicKeyCredential,
+    ) -> AppResult<Authenticator> {
+        self.read_only.check()?;
+        let authenticator = self
+            .second_factors
+            .finish_registration(user_id, ceremony_id, response)
+            .await?;
+        // An authenticator is asked for at sign-in from now on, like a confirmed app
+        let mut user = self.require_user(user_id).await?;
+        if !user.preferences.two_factor_enabled {
+            user.preferences.two_factor_enabled = true;
+            self.save(&user).await?;
+        }
+        self.logger.info(&format!(
+            "Registered authenticator {} for user {}",
+            authenticator.id, user_id
+        ));
+        Ok(authenticator)
+    }
+
+    /// Challenge a user who gave the right password to answer with one of their authenticators.
+    ///
+    /// The password is checked, and counts toward the lockout, exactly as
+    /// in `login`, so the challenge reveals nothing to someone without it.
+    /// The answer is then handed to `login` as a `SecondFactorProof::Assertion`.
+    pub async fn begin_login_assertion(
+        &self,
+        email: &str,
+        password: &str,
+        client: &ClientInfo,
+    ) -> AppResult<WebAuthnChallenge<RequestChallengeResponse>> {
+        RequestContext::check("login")?;
+        let (user, _) = self.check_password(email, password, client).await?;
+        self.second_factors.begin_assertion(user.id).await
+    }
+
+    /// Remove an authenticator; two-factor sign-in stays on while the user has an app or another authenticator
+    pub async fn remove_authenticator(&self, user_id: Uuid, authenticator_id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        self.second_factors
+            .remove_authenticator(user_id, authenticator_id)
+            .await?;
+        self.logger.info(&format!(
+            "Removed authenticator {} of user {}",
+            authenticator_id, user_id
+        ));
+        let summary = self.second_factors.summary(user_id).await?;
+        if !summary.totp_enabled && summary.authenticators.is_empty() {
+            let mut user = self.require_user(user_id).await?;
+            if user.preferences.two_factor_enabled {
+                user.preferences.two_factor_enabled = false;
+                self.save(&user).await?;
+                self.logger
+                    .warn(&format!("Disabled two-factor sign-in for user {} with its last authenticator", user_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Issue a fresh set of backup codes, invalidating any previous ones
+    pub async fn generate_backup_codes(&self, user_id: Uuid) -> AppResult<Vec<String>> {
+        self.read_only.check()?;
+        self.require_user(user_id).await?;
+        let codes = self.second_factors.generate_backup_codes(user_id).await?;
+        self.logger
+            .info(&format!("Generated {} backup codes for user {}", codes.len(), user_id));
+        Ok(codes)
+    }
+
+    /// Start setting up an authenticator app; nothing changes for sign-in until it is confirmed
+    pub async fn begin_totp_enrollment(&self, user_id: Uuid) -> AppResult<TotpEnrollment> {
+        self.read_only.check()?;
+        let user = self.require_user(user_id).await?;
+        self.second_factors.begin_totp_enrollment(&user).await
+    }
+
+    /// Turn on two-factor sign-in once the app produces a matching code.
+    ///
+    /// Users without backup codes are issued a set, returned here once, so
+    /// losing the device does not lock them out; otherwise the list is empty.
+    pub async fn confirm_totp_enrollment(&self, user_id: Uuid, code: &str) -> AppResult<Vec<String>> {
+        self.read_only.check()?;
+        let mut user = self.require_user(user_id).await?;
+        if !self.second_factors.confirm_totp(&user, code).await? {
+            return Err(AppError::Unauthorized("Invalid authenticator code".to_string()));
+        }
+        user.preferences.two_factor_enabled = true;
+        self.save(&user).await?;
+        self.logger
+            .info(&format!("Enabled two-factor sign-in for user {}", user_id));
+
+        if self.second_factors.summary(user_id).await?.backup_codes_remaining > 0 {
+            return Ok(Vec::new());
+        }
+        self.second_factors.generate_backup_codes(user_id).await
+    }
+
+    /// Remove the authenticator app; sign-in stops asking for a second factor unless authenticators remain
+    pub async fn disable_totp(&self, user_id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        let mut user = self.require_user(user_id).await?;
+        self.second_factors.remove_totp(user_id).await?;
+        if !self.second_factors.summary(user_id).await?.authenticators.is_empty() {
+            self.logger
+                .info(&format!("Removed the authenticator app of user {}", user_id));
+            return Ok(());
+        }
+        user.preferences.two_factor_enabled = false;
+        self.save(&user).await?;
+        self.logger
+            .warn(&format!("Disabled two-factor sign-in for user {}", user_id));
+        Ok(())
+    }
+
+    /// Accept a backup code in place of an authenticator; each code works once
+    pub async fn redeem_backup_code(&self, user_id: Uuid, code: &str) -> AppResult<()> {
+        if !self.second_factors.redeem_backup_code(user_id, code).await? {
+            return Err(AppError::Unauthorized("Invalid backup code".to_string()));
+        }
+        let remaining = self.second_factors.summary(user_id).await?.backup_codes_remaining;
+        if remaining == 0 {
+            self.logger
+                .warn(&format!("User {} has used their last backup code", user_id));
+        }
+        Ok(())
+    }
+
+    /// Check an email and password, plus a second factor for users who
+    /// enabled two-factor sign-in, and record the sign-in.
+    ///
+    /// A hash in a scheme other than the configured one is replaced while
+    /// the plaintext is at hand, so stored hashes migrate as users sign in.
+    /// Unknown emails and wrong passwords fail alike; wrong passwords and
+    /// wrong second factors count toward the lockout. `second_factor` is a
+    /// code from the user's authenticator app, one of their backup codes, or
+    /// an authenticator's answer to `begin_login_assertion`; it is not asked
+    /// for when `trusted_device` is the cookie of a device the user trusts.
+    pub async fn login(
+        &self,
+        tenant: &TenantContext,
+        email: &str,
+        password: &str,
+        second_factor: Option<&SecondFactorProof>,
+        trusted_device: Option<&str>,
+        client: &ClientInfo,
+    ) -> AppResult<User> {
+        RequestContext::check("login")?;
+        let (mut user, rehashed) = self.check_password(email, password, client).await?;
+        if user.preferences.two_factor_enabled && !self.on_trusted_device(user.id, trusted_device).await {
+            let Some(proof) = second_factor else {
+                return Err(AppError::Unauthorized("Two-factor code required".to_string()));
+            };
+            if !self.check_second_factor(&user, proof).await? {
+                self.record_login_failure(&user.email, Some(user.id), client, LoginFailureReason::WrongSecondFactor)
+                    .await;
+                self.record_failed_login(user).await?;
+                return Err(AppError::Unauthorized("Invalid two-factor code".to_string()));
+            }
+        }
+        if let Some(hash) = rehashed {
+            self.logger.info(&format!(
+                "Migrated password hash of user {} to {}",
+                user.id,
+                self.hashing.algorithm().as_str()
+            ));
+            user.password_hash = hash;
+        }
+        let user = self.complete_login(user).await?;
+        self.record_login_success(tenant, &user, client).await;
+        Ok(user)
+    }
+
+    /// The account behind an email and password, plus the password's new
+    /// hash when it is due a rehash; refused sign-ins are recorded here
+    async fn check_password(
+        &self,
+        email: &str,
+        password: &str,
+        client: &ClientInfo,
+    ) -> AppResult<(User, Option<String>)> {
+        let refused = || AppError::Unauthorized("Invalid email or password".to_string());
+        let email = self.config.email_policy.normalize(email);
+        if self.address_throttled(client).await? {
+            self.record_login_failure(&email, None, client, LoginFailureReason::AddressThrottled)
+                .await;
+            return Err(AppError::RateLimited {
+                retry_after: self.config.lockout_policy.address_window,
+            });
+        }
+        let Some(user) = self.repository.find_by_email(&email).await? else {
+            self.record_login_failure(&email, None, client, LoginFailureReason::UnknownAccount)
+                .await;
+            return Err(refused());
+        };
+        if user.is_locked_out() {
+            self.record_login_failure(&email, Some(user.id), client, LoginFailureReason::LockedOut)
+                .await;
+            return Err(AppError::Unauthorized("Account is locked after too many failed sign-ins".to_string()));
+        }
+
+        let hashing = self.hashing.clone();
+        let (stored, candidate) = (user.password_hash.clone(), password.to_string());
+        let verified = blocking(move || match hashing.verify(&candidate, &stored) {
+            PasswordVerification::Valid { needs_rehash: true } => {
+                hashing.hash(&candidate).map(|hash| (true, Some(hash)))
+            }
+            PasswordVerification::Valid { needs_rehash: false } => Ok((true, None)),
+            PasswordVerification::Invalid => Ok((false, None)),
+        })
+        .await??;
+
+        match verified {
+            (false, _) => {
+                self.record_login_failure(&email, Some(user.id), client, LoginFailureReason::WrongPassword)
+                    .await;
+                self.record_failed_login(user).await?;
+                Err(refused())
+            }
+            (true, rehashed) => Ok((user, rehashed)),
+        }
+    }
+
+    /// Finish a sign-in whose identity an external provider vouched for.
+    ///
+    /// The provider stands in for the password only. The address throttle
+    /// and lockouts refuse the sign-in as they would a password one, and an
+    /// account with two-factor sign-in still answers with its second factor,
+    /// through the returned ticket, unless it comes from a trusted device.
+    /// Attempts are kept in the sign-in history like password sign-ins.
+    pub async fn external_login(
+        &self,
+        tenant: &TenantContext,
+        user: User,
+        trusted_device: Option<&str>,
+        client: &ClientInfo,
+    ) -> AppResult<ExternalLogin> {
+        RequestContext::check("login")?;
+        self.check_external_login(&user, client).await?;
+        if user.preferences.two_factor_enabled && !self.on_trusted_device(user.id, trusted_device).await {
+            let ticket = new_link_token();
+            self.cache
+                .set(
+                    &external_login_key(&ticket),
+                    &user.id,
+                    Some(self.config.second_factor_challenge_ttl),
+                )
+                .await?;
+            return Ok(ExternalLogin::SecondFactorRequired { ticket });
+        }
+        let user = self.complete_login(user).await?;
+        self.record_login_success(tenant, &user, client).await;
+        Ok(ExternalLogin::SignedIn(user))
+    }
+
+    /// Challenge one of the authenticators of the account an external sign-in ticket waits on
+    pub async fn begin_external_assertion(
+        &self,
+        ticket: &str,
+    ) -> AppResult<WebAuthnChallenge<RequestChallengeResponse>> {
+        RequestContext::check("login")?;
+        let user_id: Uuid = self
+            .cache
+            .get(&external_login_key(ticket))
+            .await?
+            .ok_or_else(unknown_external_login)?;
+        self.second_factors.begin_assertion(user_id).await
+    }
+
+    /// Finish an external sign-in that asked for the second factor.
+    ///
+    /// The ticket is spent whatever the answer, and a wrong second factor
+    /// counts toward the lockout exactly as in `login`.
+    pub async fn finish_external_login(
+        &self,
+        tenant: &TenantContext,
+        ticket: &str,
+        second_factor: &SecondFactorProof,
+        client: &ClientInfo,
+    ) -> AppResult<User> {
+        RequestContext::check("login")?;
+        let user_id: Uuid = self
+            .cache
+            .take(&external_login_key(ticket))
+            .await?
+            .ok_or_else(unknown_external_login)?;
+        let user = self.require_user(user_id).await?;
+        self.check_external_login(&user, client).await?;
+        if !self.check_second_factor(&user, second_factor).await? {
+            self.record_login_failure(&user.email, Some(user.id), client, LoginFailureReason::WrongSecondFactor)
+                .await;
+            self.record_failed_login(user).await?;
+            return Err(AppError::Unauthorized("Invalid two-factor code".to_string()));
+        }
+        let user = self.complete_login(user).await?;
+        self.record_login_success(tenant, &user, client).await;
+        Ok(user)
+    }
+
+    /// Refuse a sign-in without a password wherever `check_password` would refuse the right one
+    async fn check_external_login(&self, user: &User, client: &ClientInfo) -> AppResult<()> {
+        if self.address_throttled(client).await? {
+            self.record_login_failure(&user.email, Some(user.id), client, LoginFailureReason::AddressThrottled)
+                .await;
+            return Err(AppError::RateLimited {
+                retry_after: self.config.lockout_policy.address_window,
+            });
+        }
+        if user.is_locked_out() {
+            self.record_login_failure(&user.email, Some(user.id), client, LoginFailureReason::LockedOut)
+                .await;
+            return Err(AppError::Unauthorized("Account is locked after too many failed sign-ins".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Whether the sign-in comes from a device the user trusts; failing to tell asks for the second factor
+    async fn on_trusted_device(&self, user_id: Uuid, token: Option<&str>) -> bool {
+        let (Some(devices), Some(token)) = (&self.trusted_devices, token) else {
+            return false;
+        };
+        let device = match devices.recognize(user_id, token).await {
+            Ok(Some(device)) => device,
+            Ok(None) => return false,
+            Err(e) => {
+                self.logger
+                    .warn(&format!("Failed to check trusted device of user {}: {}", user_id, e));
+                return false;
+            }
+        };
+        if !self.read_only.is_read_only() {
+            if let Err(e) = devices.record_use(device.id).await {
+                self.logger
+                    .warn(&format!("Failed to record use of trusted device {}: {}", device.id, e));
+            }
+        }
+        true
+    }
+
+    /// Count a failed attempt; the one that locks the account is also counted as a lockout
+    async fn record_failed_login(&self, mut user: User) -> AppResult<()> {
+        let policy = &self.config.lockout_policy;
+        let duration = chrono::Duration::from_std(policy.duration)
+            .map_err(|e| AppError::Config(format!("Lockout duration is out of range: {}", e)))?;
+        let before = user.clone();
+        let locked = user.record_failed_login(policy.threshold, duration);
+        let user = self.save(&user).await?;
+        let entry = AuditLog::new(None, AuditLogAction::LoginFailed, Some(user.id))
+            .with_before(&before)
+            .with_after(&user)
+            .with_context(json!({ "locked": locked }));
+        self.audit_log.record(entry).await;
+        if locked {
+            self.metrics.increment_counter("auth.lockouts").await?;
+            if let Err(e) = self.login_analytics.record_lockout().await {
+                self.logger.warn(&format!("Failed to record lockout of user {}: {}", user.id, e));
+            }
+            self.logger.warn(&format!(
+                "User {} locked out after {} failed sign-ins until {}",
+                user.id,
+                user.failed_login_attempts,
+                user.locked_until.map(|until| until.to_rfc3339()).unwrap_or_default()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Add a refused sign-in to the history and the throttling analytics; failing to must not change the answer
+    async fn record_login_failure(
+        &self,
+        email: &str,
+        user_id: Option<Uuid>,
+        client: &ClientInfo,
+        reason: LoginFailureReason,
+    ) {
+        if let Err(e) = self.login_analytics.record_failure(email, user_id, client, reason).await {
+            self.logger
+                .warn(&format!("Failed to record {} sign-in failure: {}", reason.as_str(), e));
+        }
+        // The database is not written to during maintenance, but signing in keeps working
+        if self.read_only.is_read_only() {
+            return;
+        }
+        let attempt = LoginAttempt::failed(user_id, email, reason, client);
+        if let Err(e) = self.login_history.record(&attempt).await {
+            self.logger
+                .warn(&format!("Failed to keep {} sign-in failure in history: {}", reason.as_str(), e));
+        }
+    }
+
+    /// Keep a successful sign-in in the history, and alert the user when it came from a client new to the account
+    async fn record_login_success(&self, tenant: &TenantContext, user: &User, client: &ClientInfo) {
+        if self.read_only.is_read_only() {
+            return;
+        }
+        let familiarity = self.login_history.familiarity(user.id, client).await;
+        if let Err(e) = self
+            .login_history
+            .record(&LoginAttempt::succeeded(user.id, &user.email, client))
+            .await
+        {
+            self.logger
+                .warn(&format!("Failed to keep sign-in of user {} in history: {}", user.id, e));
+        }
+        // The first sign-in is not news, and neither is one from a client seen before
+        let unfamiliar = match familiarity {
+            Ok((signed_in_before, known_client)) => signed_in_before && !known_client,
+            Err(e) => {
+                self.logger
+                    .warn(&format!("Failed to check sign-in history of user {}: {}", user.id, e));
+                false
+            }
+        };
+        if unfamiliar && self.config.new_sign_in_alerts {
+            if let Err(e) = self.notifier.send_new_sign_in(tenant, user, client).await {
+                self.logger
+                    .warn(&format!("Failed to alert user {} of a new sign-in: {}", user.id, e));
+            }
+        }
+    }
+
+    /// Whether the client's address failed too many sign-ins lately, whichever accounts it tried
+    async fn address_throttled(&self, client: &ClientInfo) -> AppResult<bool> {
+        let policy = &self.config.lockout_policy;
+        let Some(address) = client.ip_address.as_deref() else {
+            return Ok(false);
+        };
+        if policy.address_threshold <= 0 {
+            return Ok(false);
+        }
+        let window = chrono::Duration::from_std(policy.address_window)
+            .map_err(|e| AppError::Config(format!("Address lockout window is out of range: {}", e)))?;
+        let failures = self
+            .login_history
+            .failures_from_address(address, chrono::Utc::now() - window)
+            .await?;
+        Ok(failures >= policy.address_threshold)
+    }
+
+    /// A user's sign-in attempts matching `filters`, newest first
+    pub async fn get_login_history(
+        &self,
+        user_id: Uuid,
+        filters: &LoginHistoryFilters,
+    ) -> AppResult<Vec<LoginAttempt>> {
+        let errors = filters.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+        self.require_user(user_id).await?;
+        self.login_history.list_for_user(user_id, filters).await
+    }
+
+    /// Lift a lockout before it expires on its own
+    pub async fn unlock_account(&self, id: Uuid, actor: &User) -> AppResult<User> {
+        self.read_only.check()?;
+        let mut user = self.require_user(id).await?;
+        if !user.is_locked_out() {
+            return Err(AppError::Conflict(format!("User {} is not locked out", id)));
+        }
+        user.reset_failed_attempts();
+        let user = self.save(&user).await?;
+        self.record_audit(id, Some(actor.id), AuditAction::AccountUnlocked, json!({})).await;
+        self.logger.info(&format!("User {} unlocked by {}", id, actor.id));
+        Ok(user)
+    }
+
+    /// Clear lockouts that have run out, returning how many accounts were unlocked.
+    ///
+    /// An expired lockout no longer refuses sign-ins on its own; clearing it
+    /// also resets the failed attempt count and drops the account from the
+    /// locked accounts report.
+    pub async fn unlock_expired_lockouts(&self, limit: i64) -> AppResult<usize> {
+        // Sweeps wait for the end of maintenance rather than fail every run
+        if self.read_only.is_read_only() {
+            return Ok(0);
+        }
+        let expired = self.repository.find_expired_lockouts(limit).await?;
+        let unlocked = expired.len();
+        for mut user in expired {
+            user.reset_failed_attempts();
+            self.save(&user).await?;
+            self.record_audit(user.id, None, AuditAction::AccountUnlocked, json!({ "expired": true })).await;
+        }
+        Ok(unlocked)
+    }
+
+    async fn check_second_factor(&self, user: &User, proof: &SecondFactorProof) -> AppResult<bool> {
+        let code = match proof {
+            SecondFactorProof::Code(code) => code,
+            SecondFactorProof::Assertion { ceremony_id, credential } => {
+                return match self.second_factors.finish_assertion(user.id, *ceremony_id, credential).await {
+                    Ok(authenticator) => {
+                        self.logger.info(&format!(
+                            "User {} signed in with authenticator {}",
+                            user.id, authenticator.id
+                        ));
+                        Ok(true)
+                    }
+                    Err(AppError::Unauthorized(_)) => Ok(false),
+                    Err(e) => Err(e),
+                };
+            }
+        };
+        if self.second_factors.verify_totp(user, code).await? {
+            return Ok(true);
+        }
+        if self.second_factors.redeem_backup_code(user.id, code).await? {
+            self.logger
+                .info(&format!("User {} signed in with a backup code", user.id));
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Finish a sign-in with one of the user's passkeys.
+    ///
+    /// Lockouts and the address throttle apply as to any sign-in, but no
+    /// second factor is asked for: a passkey is already something the user
+    /// holds, unlocked on the device by something they know or are.
+    pub async fn passkey_login(&self, tenant: &TenantContext, user_id: Uuid, client: &ClientInfo) -> AppResult<User> {
+        RequestContext::check("login")?;
+        let user = self.require_user(user_id).await?;
+        self.check_external_login(&user, client).await?;
+        let user = self.complete_login(user).await?;
+        self.record_login_success(tenant, &user, client).await;
+        Ok(user)
+    }
+
+    /// Record a successful sign-in, cancelling any pending account deletion
+    async fn complete_login(&self, mut user: User) -> AppResult<User> {
+        let user_id = user.id;
+        if !user.can_authenticate() {
+            return Err(AppError::Unauthorized(format!("User {} cannot sign in", user_id)));
+        }
+
+        if let Some(cancelled) = self.deletions.cancel(user_id).await? {
+            user.status = cancelled.previous_status.parse().unwrap_or(UserStatus::Active);
+            self.logger.info(&format!(
+                "Cancelled pending deletion of user {} after sign-in",
+                user_id
+            ));
+        }
+
+        user.record_login();
+        let user = self.save(&user).await?;
+        self.record_audit(user_id, Some(user_id), AuditAction::Login, json!({})).await;
+        // Tokens and the session policy follow the effective role; `user` is saved by now
+        let mut user = user;
+        user.role = self.effective_role(&user).await?;
+        Ok(user)
+    }
+
+    /// Open a session for a signed-in user on the device described by `client`.
+    ///
+    /// The session follows the policy of the user's role; when that limits
+    /// concurrent sessions, the least recently active ones beyond the limit
+    /// are ended to make room.
+    pub async fn start_session(&self, user: &User, client: &ClientInfo) -> AppResult<Session> {
+        let policy = self.config.sessions.for_role(&user.role);
+        let invalid = |e: chrono::OutOfRangeError| AppError::Config(format!("Invalid session policy: {}", e));
+        let lifetime = chrono::Duration::from_std(policy.lifetime).map_err(invalid)?;
+        let idle_timeout = policy.idle_timeout.map(chrono::Duration::from_std).transpose().map_err(invalid)?;
+
+        let device = self.sessions.upsert_device(&Device::new(user.id, client)).await?;
+        let session = Session::new(&device, lifetime, idle_timeout);
+        self.sessions.create_session(&session).await?;
+        self.live_sessions.create(&session).await?;
+
+        if let Some(max) = policy.max_concurrent {
+            let ended = self.live_sessions.trim(user.id, max).await?;
+            for stale in &ended {
+                self.sessions.revoke_session(stale.id).await?;
+            }
+            if !ended.is_empty() {
+                self.logger.info(&format!(
+                    "Ended {} sessions of user {} over the limit of {} for {}",
+                    ended.len(),
+                    user.id,
+                    max,
+                    user.role.as_str()
+                ));
+            }
+        }
+        Ok(session)
+    }
+
+    /// Record activity on a session, e.g. when its tokens are refreshed
+    pub async fn refresh_session(&self, session_id: Uuid, ip_address: Option<&str>) -> AppResult<()> {
+        if self.live_sessions.refresh(session_id, ip_address).await?.is_some() {
+            self.sessions.touch_session(session_id, ip_address).await?;
+        }
+        Ok(())
+    }
+
+    /// A page of everything that happened to a user, newest first.
+    ///
+    /// Merges the audit log with the notifications the user was sent. Each
+    /// source is read up to `limit` entries older than `before`, so the
+    /// merged page is complete up to its oldest entry, which is where the
+    /// next page starts.
+    pub async fn activity_timeline(
+        &self,
+        id: Uuid,
+        before: Option<chrono::DateTime<chrono::Utc>>,
+        limit: i64,
+    ) -> AppResult<ActivityPage> {
+        self.require_user(id).await?;
+        let limit = limit.max(1);
+        let (events, notifications) = tokio::try_join!(
+            self.audit.for_user(id, before, limit),
+            self.notifications.list_for_user_before(id, before, limit),
+        )?;
+
+        let mut entries: Vec<ActivityEntry> = events
+            .into_iter()
+            .map(ActivityEntry::from)
+            .chain(notifications.into_iter().map(ActivityEntry::from))
+            .collect();
+        entries.sort_by(|a, b| b.at.cmp(&a.at));
+        entries.truncate(limit as usize);
+        let next_before = if entries.len() == limit as usize {
+            entries.last().map(|entry| entry.at)
+        } else {
+            None
+        };
+        Ok(ActivityPage { entries, next_before })
+    }
+
+    /// Live sessions of a user, most recently active first
+    pub async fn list_sessions(&self, user_id: Uuid) -> AppResult<Vec<Session>> {
+        self.live_sessions.list(user_id).await
+    }
+
+    /// Sign one session out; tokens issued for it stop working on their next use
+    pub async fn revoke_session(&self, user_id: Uuid, session_id: Uuid) -> AppResult<()> {
+        if !self.live_sessions.revoke(user_id, session_id).await? {
+            return Err(AppError::NotFound(format!("Session {} not found", session_id)));
+        }
+        self.sessions.revoke_session(session_id).await?;
+        self.logger
+            .info(&format!("Revoked session {} of user {}", session_id, user_id));
+        Ok(())
+    }
+
+    /// Log out all devices: every session and device of the user is revoked, and no device stays trusted
+    pub async fn sign_out_everywhere(&self, user_id: Uuid) -> AppResult<usize> {
+        self.end_every_session(user_id, Some(user_id), json!({})).await
+    }
+
+    /// Revoke every session, device and trusted device of a user, auditing
+    /// the counts along with `details` of who asked
+    async fn end_every_session(
+        &self,
+        user_id: Uuid,
+        actor_id: Option<Uuid>,
+        mut details: serde_json::Value,
+    ) -> AppResult<usize> {
+        let revoked = self.live_sessions.revoke_all(user_id).await?;
+        let devices = self.sessions.revoke_all_devices(user_id).await?;
+        let trusted = match &self.trusted_devices {
+            Some(trusted_devices) => trusted_devices.revoke_all(user_id).await?,
+            None => 0,
+        };
+        details["sessions"] = json!(revoked);
+        details["devices"] = json!(devices);
+        details["trusted_devices"] = json!(trusted);
+        self.record_audit(user_id, actor_id, AuditAction::SessionsRevoked, details).await;
+        self.logger.info(&format!(
+            "Signed user {} out of {} sessions on {} devices",
+            user_id, revoked, devices
+        ));
+        Ok(revoked)
+    }
+
+    /// A user as seen by an internal service holding `users:read`; users of
+    /// tenants other than the token's are not found
+    pub async fn user_for_service(&self, caller: &ServiceContext, id: Uuid) -> AppResult<User> {
+        caller.require(ServiceScope::UsersRead)?;
+        in_service_tenant(caller, self.require_user(id)).await
+    }
+
+    pub async fn user_by_email_for_service(&self, caller: &ServiceContext, email: &str) -> AppResult<User> {
+        caller.require(ServiceScope::UsersRead)?;
+        in_service_tenant(caller, self.get_user_by_email(email))
+            .await?
+            .ok_or_else(|| AppError::NotFound("No user has this email".to_string()))
+    }
+
+    /// End every session of a user for an internal service holding `sessions:revoke`, such as fraud detection
+    pub async fn sign_out_everywhere_for_service(&self, caller: &ServiceContext, user_id: Uuid) -> AppResult<usize> {
+        caller.require(ServiceScope::SessionsRevoke)?;
+        in_service_tenant(caller, self.require_user(user_id)).await?;
+        let details = json!({ "service_account_id": caller.service_account_id });
+        self.end_every_session(user_id, None, details).await
+    }
+
+    /// The user behind a verified access token, refused once they can no longer sign in
+    pub async fn authenticated_user(&self, context: &AuthContext) -> AppResult<User> {
+        self.signed_in_user(context.user_id, context.session_id).await
+    }
+
+    /// A user who may keep using the credentials of `session_id`: the account
+    /// is active and the session, if any, has been neither revoked nor expired.
+    /// The user carries their effective role, so it is not for saving back.
+    pub async fn signed_in_user(&self, user_id: Uuid, session_id: Option<Uuid>) -> AppResult<User> {
+        let signed_out = || AppError::Unauthorized("Session is no longer valid".to_string());
+        let mut user = self.repository.find_by_id(user_id).await?.ok_or_else(signed_out)?;
+        if !user.status.is_active() || user.deleted_at.is_some() {
+            return Err(signed_out());
+        }
+        if let Some(session_id) = session_id {
+            let session = self.live_sessions.get(session_id).await?.ok_or_else(signed_out)?;
+            if session.user_id != user_id {
+                return Err(signed_out());
+            }
+        }
+        user.role = self.effective_role(&user).await?;
+        Ok(user)
+    }
+
+    /// Devices with an active session, most recently used first
+    pub async fn list_devices(&self, user_id: Uuid) -> AppResult<Vec<Device>> {
+        self.sessions.active_devices(user_id).await
+    }
+
+    /// Devices the user trusts, most recently used first
+    pub async fn list_trusted_devices(&self, user_id: Uuid) -> AppResult<Vec<TrustedDevice>> {
+        self.trusted_devices()?.list(user_id).await
+    }
+
+    /// Trust the device the request comes from, so signing in on it skips
+    /// the second factor. Users with two-factor sign-in enabled confirm
+    /// with a code, so a stolen session cannot trust a device of its own.
+    pub async fn trust_device(
+        &self,
+        user_id: Uuid,
+        request: &TrustDeviceRequest,
+        client: &ClientInfo,
+    ) -> AppResult<IssuedTrustedDevice> {
+        self.read_only.check()?;
+        let devices = self.trusted_devices()?;
+        let user = self.require_user(user_id).await?;
+        let name = request
+            .name
+            .as_deref()
+            .map(device_name)
+            .transpose()
+            .map_err(|e| AppError::Validation(vec![e]))?;
+        if user.preferences.two_factor_enabled {
+            let Some(code) = request.code.as_deref() else {
+                return Err(AppError::Unauthorized("Two-factor code required".to_string()));
+            };
+            if !self.check_second_factor(&user, code).await? {
+                return Err(AppError::Unauthorized("Invalid two-factor code".to_string()));
+            }
+        }
+        let issued = devices.trust(user_id, name, client).await?;
+        self.record_audit(
+            user_id,
+            Some(user_id),
+            AuditAction::DeviceTrusted,
+            json!({ "device_id": issued.device.id, "name": issued.device.name }),
+        )
+        .await;
+        self.logger
+            .info(&format!("User {} trusted device {}", user_id, issued.device.id));
+        Ok(issued)
+    }
+
+    pub async fn rename_trusted_device(&self, user_id: Uuid, device_id: Uuid, name: &str) -> AppResult<TrustedDevice> {
+        self.read_only.check()?;
+        let name = device_name(name).map_err(|e| AppError::Validation(vec![e]))?;
+        self.trusted_devices()?.rename(user_id, device_id, &name).await
+    }
+
+    /// Stop trusting a device; signing in on it asks for the second factor again
+    pub async fn revoke_trusted_device(&self, user_id: Uuid, device_id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        self.trusted_devices()?.revoke(user_id, device_id).await?;
+        self.record_audit(
+            user_id,
+            Some(user_id),
+            AuditAction::DeviceUntrusted,
+            json!({ "device_id": device_id }),
+        )
+        .await;
+        self.logger
+            .info(&format!("User {} stopped trusting device {}", user_id, device_id));
+        Ok(())
+    }
+
+    fn trusted_devices(&self) -> AppResult<&TrustedDevices> {
+        self.trusted_devices
+            .as_deref()
+            .ok_or_else(|| AppError::Forbidden("Trusted devices are not enabled".to_string()))
+    }
+
+    /// Sign a device out by revoking it and every session opened on it
+    pub async fn revoke_device(&self, user_id: Uuid, device_id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        if !self.sessions.revoke_device(user_id, device_id).await? {
+            return Err(AppError::NotFound(format!("Device {} not found", device_id)));
+        }
+        self.live_sessions.revoke_device(user_id, device_id).await?;
+        self.logger
+            .info(&format!("Revoked device {} of user {}", device_id, user_id));
+        Ok(())
+    }
+
+    /// Everything stored about a user, for portability and deletion requests
+    pub async fn export_user_data(&self, user_id: Uuid) -> AppResult<UserDataExport> {
+        let user = self.require_user(user_id).await?;
+        let groups = self.groups.groups_for_user(user_id).await?;
+        let notifications = self
+            .notifications
+            .list_for_user(user_id, EXPORT_NOTIFICATION_LIMIT)
+            .await?;
+
+        Ok(UserDataExport {
+            user,
+            groups,
+            notifications,
+            generated_at: chrono::Utc::now(),
+        })
+    }
+
+    /// Start the deletion grace period for an account.
+    ///
+    /// The account is deactivated straight away and a data export is returned
+    /// so the user can keep a copy. Signing in again before `erase_after`
+    /// cancels the request; after it, the erasure job removes the account.
+    pub async fn request_account_deletion(&self, user_id: Uuid) -> AppResult<AccountDeletionRequest> {
+        self.read_only.check()?;
+        let mut user = self.require_user(user_id).await?;
+        if user.deleted_at.is_some() {
+            return Err(AppError::NotFound(format!("User {} not found", user_id)));
+        }
+
+        let export = self.export_user_data(user_id).await?;
+
+        let now = chrono::Utc::now();
+        let grace = chrono::Duration::from_std(self.config.deletion_grace_period)
+            .map_err(|e| AppError::Config(format!("Invalid deletion grace period: {}", e)))?;
+        let deletion = self
+            .deletions
+            .schedule(&AccountDeletion {
+                user_id,
+                requested_at: now,
+                erase_after: now + grace,
+                previous_status: user.status.as_str().to_string(),
+                cancelled_at: None,
+                erased_at: None,
+            })
+            .await?;
+
+        // Suspended accounts stay suspended; sign-in must not become a way out
+        if user.status == UserStatus::Active {
+            user.status = UserStatus::Inactive;
+            user.touch();
+            self.save(&user).await?;
+        }
+
+        self.logger.info(&format!(
+            "User {} requested deletion; erasure scheduled for {}",
+            user_id, deletion.erase_after
+        ));
+        Ok(AccountDeletionRequest { deletion, export })
+    }
+
+    /// Erase accounts whose deletion grace period has elapsed
+    pub async fn erase_due_accounts(&self, limit: i64) -> AppResult<usize> {
+        // Sweeps wait for the end of maintenance rather than fail every run
+        if self.read_only.is_read_only() {
+            return Ok(0);
+        }
+        let due = self.deletions.due(limit).await?;
+        for deletion in &due {
+            match self.delete_user(deletion.user_id).await {
+                Ok(()) | Err(AppError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+            self.deletions.mark_erased(deletion.user_id).await?;
+        }
+        Ok(due.len())
+    }
+
+    /// Permanently remove a user
+    pub async fn delete_user(&self, id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        let user = self.require_user(id).await?;
+        self.erase(&user, None, json!({})).await?;
+
+        self.logger.info(&format!("Deleted user {}", id));
+        Ok(())
+    }
+
+    /// Bring back a soft-deleted user, active again
+    pub async fn restore_user(&self, id: Uuid, actor: &User) -> AppResult<User> {
+        self.read_only.check()?;
+        let mut user = self.require_user(id).await?;
+        if user.deleted_at.is_none() {
+            return Err(AppError::Conflict(format!("User {} is not deleted", id)));
+        }
+        let before = user.clone();
+        user.restore();
+        let user = self.save(&user).await?;
+        self.log_access_change(Some(actor.id), &before, &user, json!({})).await;
+        self.record_audit(id, Some(actor.id), AuditAction::AccountRestored, json!({})).await;
+        self.logger.info(&format!("User {} restored by {}", id, actor.id));
+        Ok(user)
+    }
+
+    /// Permanently remove a soft-deleted user and everything attached to it.
+    ///
+    /// Only users deleted beforehand can be purged, so an account is never
+    /// lost to a single call. `actor` is None when the purge job acts.
+    pub async fn purge_user(&self, id: Uuid, actor: Option<&User>) -> AppResult<()> {
+        self.read_only.check()?;
+        let user = self.require_user(id).await?;
+        if user.deleted_at.is_none() {
+            return Err(AppError::Conflict(format!("User {} is not deleted", id)));
+        }
+        let actor_id = actor.map(|actor| actor.id);
+        self.erase(&user, actor_id, json!({ "purged": true })).await?;
+
+        self.logger.info(&format!("Purged user {} (by {:?})", id, actor_id));
+        Ok(())
+    }
+
+    /// Purge users soft-deleted longer ago than the configured retention
+    pub async fn purge_deleted_users(&self, limit: i64) -> AppResult<usize> {
+        // Sweeps wait for the end of maintenance rather than fail every run
+        if self.read_only.is_read_only() {
+            return Ok(0);
+        }
+        let Some(retention) = self.config.purge_deleted_after else {
+            return Ok(0);
+        };
+        let retention = chrono::Duration::from_std(retention)
+            .map_err(|e| AppError::Config(format!("Invalid purge retention: {}", e)))?;
+        let due = self
+            .repository
+            .find_deleted_before(chrono::Utc::now() - retention, limit)
+            .await?;
+        for user in &due {
+            match self.purge_user(user.id, None).await {
+                // Restored or already purged since it was found
+                Ok(()) | Err(AppError::NotFound(_)) | Err(AppError::Conflict(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(due.len())
+    }
+
+    /// Remove the account row, whose dependents go with it by foreign key,
+    /// then what is kept elsewhere: live sessions, bulk undo snapshots and
+    /// cached permissions. The search index drops the user on the event.
+    async fn erase(&self, user: &User, actor_id: Option<Uuid>, context: serde_json::Value) -> AppResult<()> {
+        self.repository.delete(user.id).await?;
+        self.live_sessions.revoke_all(user.id).await?;
+        self.bulk.forget_user(user.id).await?;
+        self.forget_permissions(user.id).await;
+        let entry = AuditLog::new(actor_id, AuditLogAction::UserDeleted, Some(user.id))
+            .with_before(user)
+            .with_context(context);
+        self.audit_log.record(entry).await;
+        Ok(())
+    }
+
+    pub async fn create_group(&self, request: CreateGroupRequest) -> AppResult<Group> {
+        self.read_only.check()?;
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        let group = Group::new(request.name.trim().to_string(), request.description, request.created_by);
+        let group = self.groups.create(&group).await?;
+        self.logger.info(&format!("Created group {} ({})", group.id, group.name));
+        Ok(group)
+    }
+
+    pub async fn get_group(&self, group_id: Uuid) -> AppResult<Group> {
+        self.groups
+            .find_by_id(group_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Group {} not found", group_id)))
+    }
+
+    pub async fn delete_group(&self, group_id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        self.groups.delete(group_id).await?;
+        self.logger.info(&format!("Deleted group {}", group_id));
+        Ok(())
+    }
+
+    /// Add a user to a group; adding an existing member is a no-op
+    pub async fn add_to_group(&self, group_id: Uuid, user_id: Uuid, added_by: Option<Uuid>) -> AppResult<()> {
+        self.read_only.check()?;
+        self.get_group(group_id).await?;
+        let user = self
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("User {} not found", user_id)))?;
+        if user.deleted_at.is_some() {
+            return Err(AppError::Validation(vec![format!(
+                "User {} is deleted and cannot join groups",
+                user_id
+            )]));
+        }
+
+        if self.groups.add_member(group_id, user_id, added_by).await? {
+            self.logger.debug(&format!("Added user {} to group {}", user_id, group_id));
+        }
+        Ok(())
+    }
+
+    pub async fn remove_from_group(&self, group_id: Uuid, user_id: Uuid) -> AppResult<()> {
+        self.read_only.check()?;
+        if !self.groups.remove_member(group_id, user_id).await? {
+            return Err(AppError::NotFound(format!(
+                "User {} is not a member of group {}",
+                user_id, group_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// One page of a group's members, ordered by user id; pass the last
+    /// user id of the previous page as `after` to continue
+    pub async fn group_members(
+        &self,
+        group_id: Uuid,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> AppResult<Vec<GroupMembership>> {
+        self.get_group(group_id).await?;
+        self.groups.member_page(group_id, after, limit).await
+    }
+
+    pub async fn groups_for_user(&self, user_id: Uuid) -> AppResult<Vec<Group>> {
+        self.groups.groups_for_user(user_id).await
+    }
+
+    /// Count and sample the users a bulk operation would affect.
+    ///
+    /// Bulk operations act on every match, so `limit` and `offset` in the
+    /// filters only shape the sample.
+    pub async fn preview_bulk(&self, filters: &UserFilters, action: BulkAction) -> AppResult<BulkPreview> {
+        let total = self.repository.count(filters).await?;
+        let sample = self
+            .repository
+            .list(&UserFilters {
+                limit: Some(filters.limit.unwrap_or(BULK_PREVIEW_SAMPLE).min(BULK_PREVIEW_SAMPLE)),
+                ..filters.clone()
+            })
+            .await?;
+
+        Ok(BulkPreview { action, total, sample })
+    }
+
+    /// Apply an action to every user matching `filters`.
+    ///
+    /// The selection is snapshotted before anything changes, so users that
+    /// stop matching mid-run are still processed exactly once. Progress is
+    /// persisted after each batch and can be polled with `bulk_operation`.
+    /// Users the actor cannot manage, including the actor themselves, are skipped.
+    pub async fn execute_bulk(
+        &self,
+        actor_id: Uuid,
+        filters: UserFilters,
+        action: BulkAction,
+    ) -> AppResult<BulkOperation> {
+        self.read_only.check()?;
+        let actor = self.require_bulk_actor(actor_id).await?;
+        if let BulkAction::ChangeRole(role) = &action {
+            if !actor.role.can_manage(role) {
+                return Err(AppError::Forbidden(format!(
+                    "Cannot assign role {} without outranking it",
+                    role.as_str()
+                )));
+            }
+        }
+
+        // Order does not change which users are selected, and cursor pages keep their own.
+        // The request's tenant is written down so the stored selection says what it covered
+        let selection = UserFilters {
+            tenant_id: filters.tenant_id.clone().or_else(|| TenantScope::current().map(|tenant| tenant.tenant_id)),
+            sort: Vec::new(),
+            limit: None,
+            offset: None,
+            ..filters
+        };
+        let mut operation = BulkOperation::new(action, selection.clone(), actor_id);
+        self.bulk.create(&operation).await?;
+
+        let batch = selection.clone().with_limit(BULK_BATCH_SIZE);
+        let mut cursor = None;
+        loop {
+            let page = self.repository.list_page(&batch, cursor.as_ref()).await?;
+            operation.total += self.bulk.record_items(operation.id, &page.users).await? as i32;
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        self.bulk.save_progress(&operation).await?;
+
+        let result = self.apply_bulk(&mut operation, &actor).await;
+        self.finish_bulk(&mut operation, result).await?;
+        Ok(operation)
+    }
+
+    pub async fn bulk_operation(&self, operation_id: Uuid) -> AppResult<BulkOperation> {
+        self.bulk
+            .find(operation_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Bulk operation {} not found", operation_id)))
+    }
+
+    /// Restore every user changed by a completed operation to its prior state
+    pub async fn undo_bulk(&self, actor_id: Uuid, operation_id: Uuid) -> AppResult<BulkOperation> {
+        self.read_only.check()?;
+        self.require_bulk_actor(actor_id).await?;
+        let mut operation = self.bulk_operation(operation_id).await?;
+        if !operation.can_undo() {
+            return Err(AppError::Conflict(format!(
+                "Bulk operation {} can no longer be undone",
+                operation_id
+            )));
+        }
+
+        let mut after = None;
+        loop {
+            let items = self
+                .bulk
+                .applied_items(operation_id, after, BULK_BATCH_SIZE)
+                .await?;
+            let Some(last) = items.last() else {
+                break;
+            };
+            after = Some(last.user_id);
+
+            for item in &items {
+                self.restore_bulk_item(actor_id, operation_id, item).await?;
+            }
+        }
+
+        operation.status = BulkStatus::Undone;
+        operation.undone_at = Some(chrono::Utc::now());
+        self.bulk.save_progress(&operation).await?;
+
+        self.logger
+            .info(&format!("Bulk operation {} undone by {}", operation_id, actor_id));
+        Ok(operation)
+    }
+
+    /// Record how an apply run ended; `Ok(false)` means it was interrupted
+    async fn finish_bulk(&self, operation: &mut BulkOperation, result: AppResult<bool>) -> AppResult<()> {
+        match result {
+            Ok(true) => {
+                let now = chrono::Utc::now();
+                operation.status = BulkStatus::Completed;
+                operation.completed_at = Some(now);
+                operation.undo_until = chrono::Duration::from_std(self.config.bulk_undo_window)
+                    .ok()
+                    .map(|window| now + window);
+            }
+            Ok(false) => operation.status = BulkStatus::Interrupted,
+            Err(e) => {
+                self.logger
+                    .error(&format!("Bulk operation {} failed: {}", operation.id, e));
+                operation.status = BulkStatus::Failed;
+                operation.error = Some(e.to_string());
+            }
+        }
+        self.bulk.save_progress(operation).await?;
+
+        self.logger.info(&format!(
+            "Bulk operation {} by {} {}: {} of {} users changed, {} skipped",
+            operation.id,
+            operation.requested_by,
+            operation.status.as_str(),
+            operation.processed,
+            operation.total,
+            operation.skipped
+        ));
+        Ok(())
+    }
+
+    /// Apply pending items batch by batch; returns false if shutdown stopped it first
+    async fn apply_bulk(&self, operation: &mut BulkOperation, actor: &User) -> AppResult<bool> {
+        loop {
+            // Progress is saved per batch, so stopping here leaves the rest pending
+            if self.shutdown.is_cancelled() {
+                return Ok(false);
+            }
+            RequestContext::check("bulk_operation")?;
+            let items = self.bulk.pending_items(operation.id, BULK_BATCH_SIZE).await?;
+            if items.is_empty() {
+                return Ok(true);
+            }
+
+            for item in &items {
+                let target = match self.repository.find_by_id(item.user_id).await? {
+                    Some(user) if user.id == actor.id => Err("cannot apply to own account"),
+                    Some(user) if !actor.role.can_manage(&user.role) => Err("insufficient role"),
+                    Some(user) => Ok(user),
+                    None => Err("user no longer exists"),
+                };
+
+                match target {
+                    Ok(mut user) => {
+                        let before = user.clone();
+                        match &operation.action {
+                            BulkAction::Suspend => user.status = UserStatus::Suspended,
+                            BulkAction::ChangeRole(role) => user.role = role.clone(),
+                            BulkAction::Delete => user.soft_delete(),
+                        }
+                        user.touch();
+                        let user = self.save(&user).await?;
+                        self.bulk.mark_applied(operation.id, item.user_id).await?;
+                        let context = json!({ "operation_id": operation.id });
+                        self.log_access_change(Some(actor.id), &before, &user, context).await;
+                        let details = json!({ "operation_id": operation.id, "action": operation.action });
+                        self.record_audit(item.user_id, Some(actor.id), AuditAction::BulkOperationApplied, details)
+                            .await;
+                        operation.processed += 1;
+                    }
+                    Err(reason) => {
+                        self.bulk
+                            .mark_skipped(operation.id, item.user_id, reason)
+                            .await?;
+                        operation.skipped += 1;
+                    }
+                }
+            }
+
+            self.bulk.save_progress(operation).await?;
+        }
+    }
+
+    /// Apply the password policy, refuse recently used passwords and store the new hash
+    async fn change_password(&self, mut user: User, new_password: &str) -> AppResult<()> {
+        let policy = &self.config.password_policy;
+        let errors = self.password_problems(new_password).await;
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        let depth = policy.history_depth;
+        let mut previous = Vec::new();
+        if depth > 0 {
+            previous = self.passwords.recent(user.id, depth).await?;
+            // Accounts from before history was kept still have their current password refused
+            if !user.password_hash.is_empty() && !previous.contains(&user.password_hash) {
+                previous.push(user.password_hash.clone());
+            }
+        }
+
+        // Each comparison is a full Argon2 verification, so they run off the async workers
+        let (hashing, candidate) = (self.hashing.clone(), new_password.to_string());
+        let hash = blocking(move || {
+            if previous.iter().any(|stored| hashing.matches(&candidate, stored)) {
+                return Ok(None);
+            }
+            hashing.hash(&candidate).map(Some)
+        })
+        .await??;
+        let Some(hash) = hash else {
+            return Err(AppError::Validation(vec![format!(
+                "Password must not match any of the last {} passwords",
+                depth
+            )]));
+        };
+
+        user.password_hash = hash;
+        user.touch();
+        let user = self.save(&user).await?;
+        if depth > 0 {
+            self.passwords.record(user.id, &user.password_hash, depth).await?;
+        }
+        self.record_audit(user.id, Some(user.id), AuditAction::PasswordChanged, json!({})).await;
+
+        self.logger.info(&format!("Password changed for user {}", user.id));
+        Ok(())
+    }
+
+    async fn restore_bulk_item(&self, actor_id: Uuid, operation_id: Uuid, item: &BulkOperationItem) -> AppResult<()> {
+        let Some(mut user) = self.repository.find_by_id(item.user_id).await? else {
+            return Ok(());
+        };
+        let invalid = |e: String| AppError::Internal(format!("Corrupt bulk operation item: {}", e));
+        let before = user.clone();
+
+        user.role = item.previous_role.parse().map_err(invalid)?;
+        user.status = item.previous_status.parse().map_err(invalid)?;
+        user.deleted_at = item.previous_deleted_at;
+        user.touch();
+        let user = self.save(&user).await?;
+        let context = json!({ "operation_id": operation_id, "undo": true });
+        self.log_access_change(Some(actor_id), &before, &user, context).await;
+        let details = json!({ "operation_id": operation_id });
+        self.record_audit(user.id, Some(actor_id), AuditAction::BulkOperationUndone, details)
+            .await;
+        Ok(())
+    }
+
+    /// Audit failures are logged rather than failing the change they describe
+    async fn record_audit(
+        &self,
+        user_id: Uuid,
+        actor_id: Option<Uuid>,
+        action: AuditAction,
+        details: serde_json::Value,
+    ) {
+        let event = AuditEvent::new(user_id, actor_id, action, details);
+        if let Err(e) = self.audit.record(&event).await {
+            self.logger.warn(&format!(
+                "Failed to record {} for user {}: {}",
+                action.as_str(),
+                user_id,
+                e
+            ));
+        }
+    }
+
+    /// Add role, status and soft deletion changes between `before` and `after` to the compliance log
+    /// Audit a temporary role that stopped counting, with the role the user is left with
+    async fn record_role_grant_end(&self, grant: &RoleGrant, actor_id: Option<Uuid>, reason: &str) {
+        self.forget_permissions(grant.user_id).await;
+        let remaining = match self.repository.find_by_id(grant.user_id).await {
+            Ok(Some(user)) => self.effective_role(&user).await.ok(),
+            _ => None,
+        };
+        let details = json!({
+            "from": grant.role.as_str(),
+            "to": remaining.as_ref().map(UserRole::as_str),
+            "grant_id": grant.id,
+            "reason": reason,
+        });
+        self.record_audit(grant.user_id, actor_id, AuditAction::RoleChanged, details.clone()).await;
+        let entry =
+            AuditLog::new(actor_id, AuditLogAction::RoleElevationEnded, Some(grant.user_id)).with_context(details);
+        self.audit_log.record(entry).await;
+        self.logger.info(&format!(
+            "Temporary {} role of user {} {}",
+            grant.role.as_str(),
+            grant.user_id,
+            reason
+        ));
+    }
+
+    async fn log_access_change(&self, actor_id: Option<Uuid>, before: &User, after: &User, context: serde_json::Value) {
+        let mut actions = Vec::new();
+        if before.role != after.role {
+            actions.push(AuditLogAction::RoleChanged);
+        }
+        if before.status != after.status {
+            actions.push(AuditLogAction::StatusChanged);
+        }
+        if before.deleted_at.is_none() && after.deleted_at.is_some() {
+            actions.push(AuditLogAction::UserDeleted);
+        }
+        if before.deleted_at.is_some() && after.deleted_at.is_none() {
+            actions.push(AuditLogAction::UserRestored);
+        }
+        for action in actions {
+            let entry = AuditLog::new(actor_id, action, Some(after.id))
+                .with_before(before)
+                .with_after(after)
+                .with_context(context.clone());
+            self.audit_log.record(entry).await;
+        }
+    }
+
+    async fn require_bulk_actor(&self, actor_id: Uuid) -> AppResult<User> {
+        let actor = self.require_user(actor_id).await?;
+        if !self.policies.allows(&actor, "users:bulk", None) {
+            return Err(AppError::Forbidden(
+                "Bulk operations require the users:bulk permission".to_string(),
+            ));
+        }
+        Ok(actor)
+    }
+
+    async fn require_user(&self, id: Uuid) -> AppResult<User> {
+        self.repository
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("User {} not found", id)))
+    }
+
+    /// Persist a modified user; the repository publishes the change through the outbox
+    async fn save(&self, user: &User) -> AppResult<User> {
+        let user = self.repository.update(user).await?;
+        self.remember_identity(&user).await;
+        self.forget_permissions(user.id).await;
+        Ok(user)
+    }
+
+    fn permissions_key(&self, user_id: Uuid) -> String {
+        format!("permissions:{}:{}", self.policies.fingerprint(), user_id)
+    }
+
+    /// Drop the user's cached permissions after something they derive from changed.
+    /// A failed eviction leaves the old set until its TTL runs out
+    async fn forget_permissions(&self, user_id: Uuid) {
+        if let Err(e) = self.cache.delete(&self.permissions_key(user_id)).await {
+            self.logger
+                .warn(&format!("Failed to drop cached permissions of user {}: {}", user_id, e));
+        }
+    }
+
+    /// Why a password being chosen is refused, empty if it is acceptable.
+    /// The breach check is skipped when the filter cannot be read, rather than refusing every password
+    async fn password_problems(&self, password: &str) -> Vec<String> {
+        let mut errors = self.config.password_policy.validate(password);
+        match self.breached.contains(password).await {
+            Ok(true) => errors.push("Password has appeared in a data breach; choose another".to_string()),
+            Ok(false) => {}
+            Err(e) => self.logger.warn(&format!("Breached password check failed: {}", e)),
+        }
+        errors
+    }
+
+    /// A filter read failure must never turn into a false "does not exist"
+    async fn filter_might_contain(&self, filter: &BloomFilter, value: &str) -> bool {
+        match filter.might_contain(value).await {
+            Ok(maybe) => maybe,
+            Err(e) => {
+                self.logger.warn(&format!("Identity filter read failed: {}", e));
+                true
+            }
+        }
+    }
+
+    /// A missed insert would cause false negatives, so on failure the filters
+    /// are dropped and checks go to the database until the next rebuild
+    async fn remember_identity(&self, user: &User) {
+        let inserted = match self.emails.insert(&user.email).await {
+            Ok(()) => self.usernames.insert(&user.username).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = inserted {
+            self.logger.error(&format!(
+                "Identity filter write failed for user {}, disabling filters: {}",
+                user.id, e
+            ));
+            let _ = self.emails.clear().await;
+            let _ = self.usernames.clear().await;
+        }
+    }
+}
+
+/// 256 random bits for an emailed single-use link
+fn new_link_token() -> String {
+    let mut token = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut token);
+    URL_SAFE_NO_PAD.encode(token)
+}
+
+fn external_login_key(ticket: &str) -> String {
+    format!("external_login:{}", hash_link_token(ticket))
+}
+
+fn unknown_external_login() -> AppError {
+    AppError::Unauthorized("Unknown or expired sign-in ticket".to_string())
+}
+
+/// Link tokens are 256 random bits, so a fast unsalted hash is enough to keep them out of the database
+fn hash_link_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn link_expiry(ttl: std::time::Duration) -> AppResult<chrono::DateTime<chrono::Utc>> {
+    let ttl = chrono::Duration::from_std(ttl).map_err(|e| AppError::Config(format!("Invalid link lifetime: {}", e)))?;
+    Ok(chrono::Utc::now() + ttl)
+}
+
+/// Run a lookup for an internal service within the tenant its token was
+/// issued for, whichever tenant the request names
+async fn in_service_tenant<T>(caller: &ServiceContext, lookup: impl Future<Output = AppResult<T>>) -> AppResult<T> {
+    TenantScope::scope(TenantContext::new(&caller.tenant_id)?, lookup).await
+}
+
+/// Run CPU-heavy work such as password hashing on the blocking pool
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> AppResult<T> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| AppError::Internal(format!("Blocking task failed: {}", e)))
+}
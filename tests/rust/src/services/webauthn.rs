@@ -0,0 +1,244 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, CredentialID, DiscoverableAuthentication, DiscoverableKey, Passkey,
+    PasskeyRegistration, PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse,
+    ResidentKeyRequirement, Url, Webauthn, WebauthnBuilder, WebauthnError,
+};
+
+use super::cache_service::CacheService;
+use crate::config::AccountConfig;
+use crate::models::{AppError, AppResult, PasskeyCredential, User, WebAuthnChallenge};
+use crate::repositories::PasskeyRepository;
+use crate::utils::new_id;
+
+/// The relying party every WebAuthn ceremony of this service is held for
+pub(crate) fn relying_party(config: &AccountConfig) -> AppResult<Webauthn> {
+    let invalid = |e: String| AppError::Config(format!("Invalid WebAuthn relying party: {}", e));
+    let origin = Url::parse(&config.webauthn_origin).map_err(|e| invalid(e.to_string()))?;
+    WebauthnBuilder::new(&config.webauthn_rp_id, &origin)
+        .and_then(|builder| builder.rp_name(&config.webauthn_rp_name).build())
+        .map_err(|e| invalid(e.to_string()))
+}
+
+/// Ceremony state kept between the begin and finish halves of a passkey exchange
+#[derive(Serialize, Deserialize)]
+enum Ceremony {
+    Registration {
+        user_id: Uuid,
+        name: String,
+        state: PasskeyRegistration,
+    },
+    SignIn {
+        state: DiscoverableAuthentication,
+    },
+}
+
+/// Passkeys as a primary credential, signing users in without a password.
+///
+/// Sign-in is discoverable: the browser offers whichever passkey it holds
+/// for this relying party and the user handle in its answer names the
+/// account, so nothing is asked up front and no account's existence is
+/// revealed. Passkeys are registered as resident credentials for that
+/// reason. User verification is always required, which makes a passkey a
+/// second factor of its own. Like second factors, ceremony state lives in
+/// the cache so the two halves may land on different instances.
+pub struct Passkeys {
+    repository: Arc<PasskeyRepository>,
+    cache: Arc<CacheService>,
+    webauthn: Webauthn,
+    challenge_ttl: Duration,
+}
+
+impl Passkeys {
+    pub fn new(
+        repository: Arc<PasskeyRepository>,
+        cache: Arc<CacheService>,
+        config: &AccountConfig,
+    ) -> AppResult<Self> {
+        Ok(Self {
+            repository,
+            cache,
+            webauthn: relying_party(config)?,
+            challenge_ttl: config.second_factor_challenge_ttl,
+        })
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> AppResult<Vec<PasskeyCredential>> {
+        self.repository.for_user(user_id).await
+    }
+
+    /// Start registering a passkey; users may register one per device, and
+    /// those already registered are excluded
+    pub async fn begin_registration(
+        &self,
+        user: &User,
+        name: &str,
+    ) -> AppResult<WebAuthnChallenge<CreationChallengeResponse>> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::Validation(vec!["Passkey name is required".to_string()]));
+        }
+        let exclude: Vec<CredentialID> = self
+            .passkeys(user.id)
+            .await?
+            .iter()
+            .map(|(_, passkey)| passkey.cred_id().clone())
+            .collect();
+
+        // The user id is the credential's user handle, which is how a
+        // discoverable sign-in finds the account
+        let (mut options, state) = self
+            .webauthn
+            .start_passkey_registration(user.id, &user.email, &user.username, Some(exclude))
+            .map_err(rejected)?;
+        if let Some(selection) = options.public_key.authenticator_selection.as_mut() {
+            selection.resident_key = Some(ResidentKeyRequirement::Required);
+            selection.require_resident_key = true;
+        }
+        let ceremony_id = self
+            .save_ceremony(&Ceremony::Registration {
+                user_id: user.id,
+                name: name.to_string(),
+                state,
+            })
+            .await?;
+        Ok(WebAuthnChallenge { ceremony_id, options })
+    }
+
+    pub async fn finish_registration(
+        &self,
+        user_id: Uuid,
+        ceremony_id: Uuid,
+        response: &RegisterPublicKeyCredential,
+    ) -> AppResult<PasskeyCredential> {
+        let Ceremony::Registration { user_id: owner, name, state } = self.take_ceremony(ceremony_id).await? else {
+            return Err(unknown_ceremony());
+        };
+        if owner != user_id {
+            return Err(unknown_ceremony());
+        }
+
+        let passkey = self
+            .webauthn
+            .finish_passkey_registration(response, &state)
+            .map_err(rejected)?;
+        let credential = PasskeyCredential {
+            id: new_id(),
+            user_id,
+            name,
+            credential_id: passkey.cred_id().to_string(),
+            public_key: encode_passkey(&passkey)?,
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+        self.repository.create(&credential).await?;
+        Ok(credential)
+    }
+
+    /// Challenge the browser for any passkey it holds for this relying party
+    pub async fn begin_sign_in(&self) -> AppResult<WebAuthnChallenge<RequestChallengeResponse>> {
+        let (options, state) = self
+            .webauthn
+            .start_discoverable_authentication()
+            .map_err(rejected)?;
+        let ceremony_id = self.save_ceremony(&Ceremony::SignIn { state }).await?;
+        Ok(WebAuthnChallenge { ceremony_id, options })
+    }
+
+    /// Verify a sign-in assertion, returning the passkey used; whether its
+    /// owner may sign in is left to the caller
+    pub async fn finish_sign_in(
+        &self,
+        ceremony_id: Uuid,
+        response: &PublicKeyCredential,
+    ) -> AppResult<PasskeyCredential> {
+        let Ceremony::SignIn { state } = self.take_ceremony(ceremony_id).await? else {
+            return Err(unknown_ceremony());
+        };
+        let (user_id, _) = self
+            .webauthn
+            .identify_discoverable_authentication(response)
+            .map_err(rejected)?;
+        let passkeys = self.passkeys(user_id).await?;
+        if passkeys.is_empty() {
+            return Err(unknown_passkey());
+        }
+
+        let keys: Vec<DiscoverableKey> = passkeys.iter().map(|(_, passkey)| passkey.into()).collect();
+        let result = self
+            .webauthn
+            .finish_discoverable_authentication(response, state, &keys)
+            .map_err(rejected)?;
+        let (mut credential, mut passkey) = passkeys
+            .into_iter()
+            .find(|(_, passkey)| passkey.cred_id() == result.cred_id())
+            .ok_or_else(unknown_passkey)?;
+
+        passkey.update_credential(&result);
+        credential.public_key = encode_passkey(&passkey)?;
+        credential.last_used_at = Some(Utc::now());
+        self.repository.record_use(credential.id, &credential.public_key).await?;
+        Ok(credential)
+    }
+
+    pub async fn remove(&self, user_id: Uuid, passkey_id: Uuid) -> AppResult<()> {
+        if !self.repository.delete(user_id, passkey_id).await? {
+            return Err(AppError::NotFound(format!("Passkey {} not found", passkey_id)));
+        }
+        Ok(())
+    }
+
+    async fn passkeys(&self, user_id: Uuid) -> AppResult<Vec<(PasskeyCredential, Passkey)>> {
+        self.repository
+            .for_user(user_id)
+            .await?
+            .into_iter()
+            .map(|credential| {
+                let passkey = serde_json::from_value(credential.public_key.clone()).map_err(|e| {
+                    AppError::Internal(format!("Corrupt public key of passkey {}: {}", credential.id, e))
+                })?;
+                Ok((credential, passkey))
+            })
+            .collect()
+    }
+
+    async fn save_ceremony(&self, ceremony: &Ceremony) -> AppResult<Uuid> {
+        let ceremony_id = Uuid::new_v4();
+        self.cache
+            .set(&ceremony_key(ceremony_id), ceremony, Some(self.challenge_ttl))
+            .await?;
+        Ok(ceremony_id)
+    }
+
+    /// Ceremonies are single-use, so a replayed response finds nothing
+    async fn take_ceremony(&self, ceremony_id: Uuid) -> AppResult<Ceremony> {
+        let key = ceremony_key(ceremony_id);
+        let ceremony = self.cache.get(&key).await?.ok_or_else(unknown_ceremony)?;
+        self.cache.delete(&key).await?;
+        Ok(ceremony)
+    }
+}
+
+fn ceremony_key(ceremony_id: Uuid) -> String {
+    format!("passkey:{}", ceremony_id)
+}
+
+fn encode_passkey(passkey: &Passkey) -> AppResult<serde_json::Value> {
+    serde_json::to_value(passkey).map_err(|e| AppError::Internal(format!("Unserializable passkey: {}", e)))
+}
+
+fn unknown_ceremony() -> AppError {
+    AppError::Unauthorized("Unknown or expired passkey challenge".to_string())
+}
+
+fn unknown_passkey() -> AppError {
+    AppError::Unauthorized("Passkey is not registered".to_string())
+}
+
+fn rejected(error: WebauthnError) -> AppError {
+    AppError::Unauthorized(format!("Passkey verification failed: {}", error))
+}
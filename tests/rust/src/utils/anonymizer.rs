@@ -0,0 +1,104 @@
+use fake::faker::internet::en::Username;
+use fake::faker::lorem::en::Sentence;
+use fake::faker::name::en::{FirstName, LastName};
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+/// Deterministic replacement of PII with fake but realistic values.
+///
+/// The same input and salt always produce the same output, so references
+/// across tables (a user's email on their notifications) stay consistent.
+/// Emails and usernames are additionally guaranteed unique within a run.
+pub struct Anonymizer {
+    salt: String,
+    emails: HashMap<String, String>,
+    usernames: HashMap<String, String>,
+    issued_emails: HashSet<String>,
+    issued_usernames: HashSet<String>,
+}
+
+impl Anonymizer {
+    pub fn new(salt: impl Into<String>) -> Self {
+        Self {
+            salt: salt.into(),
+            emails: HashMap::new(),
+            usernames: HashMap::new(),
+            issued_emails: HashSet::new(),
+            issued_usernames: HashSet::new(),
+        }
+    }
+
+    pub fn first_name(&self, original: &str) -> String {
+        FirstName().fake_with_rng(&mut self.rng("first_name", original))
+    }
+
+    pub fn last_name(&self, original: &str) -> String {
+        LastName().fake_with_rng(&mut self.rng("last_name", original))
+    }
+
+    /// Free-form text such as notification bodies
+    pub fn sentence(&self, original: &str) -> String {
+        Sentence(4..12).fake_with_rng(&mut self.rng("sentence", original))
+    }
+
+    pub fn email(&mut self, original: &str) -> String {
+        if let Some(existing) = self.emails.get(original) {
+            return existing.clone();
+        }
+
+        let local: String = Username().fake_with_rng(&mut self.rng("email", original));
+        let base = format!("{}@example.test", local.to_lowercase());
+        let fake = Self::unique(&mut self.issued_emails, base, |base, n| {
+            let (local, domain) = base.split_once('@').unwrap_or((base, "example.test"));
+            format!("{}{}@{}", local, n, domain)
+        });
+
+        self.emails.insert(original.to_string(), fake.clone());
+        fake
+    }
+
+    pub fn username(&mut self, original: &str) -> String {
+        if let Some(existing) = self.usernames.get(original) {
+            return existing.clone();
+        }
+
+        let base: String = Username().fake_with_rng(&mut self.rng("username", original));
+        let fake = Self::unique(&mut self.issued_usernames, base.to_lowercase(), |base, n| {
+            format!("{}_{}", base, n)
+        });
+
+        self.usernames.insert(original.to_string(), fake.clone());
+        fake
+    }
+
+    /// Seed an RNG from the salt, the field kind and the original value
+    fn rng(&self, field: &str, original: &str) -> StdRng {
+        let digest = Sha256::new()
+            .chain_update(self.salt.as_bytes())
+            .chain_update(field.as_bytes())
+            .chain_update(original.as_bytes())
+            .finalize();
+
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&digest);
+        StdRng::from_seed(seed)
+    }
+
+    fn unique(
+        issued: &mut HashSet<String>,
+        base: String,
+        suffixed: impl Fn(&str, u32) -> String,
+    ) -> String {
+        let mut candidate = base.clone();
+        let mut n = 1;
+        while issued.contains(&candidate) {
+            n += 1;
+            candidate = suffixed(&base, n);
+        }
+        issued.insert(candidate.clone());
+        candidate
+    }
+}
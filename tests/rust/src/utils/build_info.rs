@@ -0,0 +1,65 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// What was built, captured by build.rs at compile time
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    pub built_at: DateTime<Utc>,
+    pub dependencies: BTreeMap<&'static str, &'static str>,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        let built_at = env!("BUILD_TIMESTAMP").parse().unwrap_or(0);
+        let dependencies = env!("BUILD_DEPENDENCIES")
+            .split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .collect();
+
+        Self {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: env!("BUILD_GIT_COMMIT"),
+            built_at: Utc.timestamp_opt(built_at, 0).single().unwrap_or_default(),
+            dependencies,
+        }
+    }
+}
+
+/// Build details plus how this process is configured
+#[derive(Debug, Clone, Serialize)]
+pub struct AppInfo {
+    #[serde(flatten)]
+    pub build: BuildInfo,
+    pub profile: String,
+    pub features: Vec<&'static str>,
+    pub started_at: DateTime<Utc>,
+}
+
+impl AppInfo {
+    pub fn new(profile: String, features: Vec<&'static str>) -> Self {
+        Self {
+            build: BuildInfo::current(),
+            profile,
+            features,
+            started_at: Utc::now(),
+        }
+    }
+
+    /// One-line summary logged at startup
+    pub fn banner(&self) -> String {
+        format!(
+            "{} v{} ({}, built {}) profile={} features=[{}]",
+            self.build.name,
+            self.build.version,
+            self.build.git_commit,
+            self.build.built_at.format("%Y-%m-%d %H:%M UTC"),
+            self.profile,
+            self.features.join(", ")
+        )
+    }
+}
@@ -0,0 +1,61 @@
+use chrono::NaiveDate;
+use std::cell::RefCell;
+use std::future::Future;
+
+tokio::task_local! {
+    static USED: RefCell<Vec<&'static DeprecatedField>>;
+}
+
+/// A request field, or a value of one, scheduled for removal.
+///
+/// Declare each as a `static` next to the model it belongs to and call
+/// `Deprecations::record` from wherever the legacy input is accepted,
+/// usually a `deserialize_with` function.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeprecatedField {
+    /// Stable identifier reported in headers and metrics, e.g. `user.role.legacy_value`
+    pub name: &'static str,
+    /// `YYYY-MM-DD` the field was deprecated
+    pub deprecated_on: &'static str,
+    /// `YYYY-MM-DD` after which it may be removed
+    pub sunset: &'static str,
+    /// What clients should send instead
+    pub replacement: &'static str,
+}
+
+impl DeprecatedField {
+    pub fn deprecated_on(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(self.deprecated_on, "%Y-%m-%d").ok()
+    }
+
+    pub fn sunset(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(self.sunset, "%Y-%m-%d").ok()
+    }
+}
+
+/// Deprecated fields the current request has used.
+///
+/// Collected in a task-local installed by the deprecation middleware, so
+/// models can report legacy input while they are deserialized without the
+/// handlers being involved. Outside a request, recording does nothing.
+pub struct Deprecations;
+
+impl Deprecations {
+    pub fn record(field: &'static DeprecatedField) {
+        let _ = USED.try_with(|used| {
+            let mut used = used.borrow_mut();
+            if !used.contains(&field) {
+                used.push(field);
+            }
+        });
+    }
+
+    /// Run `future`, returning what it recorded alongside its output
+    pub async fn scope<F: Future>(future: F) -> (F::Output, Vec<&'static DeprecatedField>) {
+        USED.scope(RefCell::new(Vec::new()), async move {
+            let output = future.await;
+            (output, USED.with(|used| used.take()))
+        })
+        .await
+    }
+}
@@ -0,0 +1,147 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::HashMap;
+
+use crate::config::EncryptionConfig;
+use crate::models::{AppError, AppResult};
+
+/// Key version assumed for ciphertexts written before versioning was introduced
+pub const LEGACY_KEY_VERSION: u32 = 1;
+
+const NONCE_LEN: usize = 12;
+
+/// A ciphertext tagged with the version of the key that produced it.
+///
+/// Stored as `v{version}:{base64(nonce || ciphertext)}`; values without a
+/// version prefix are treated as legacy ciphertexts under key version 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedField {
+    pub key_version: u32,
+    pub payload: String,
+}
+
+impl EncryptedField {
+    /// Parse a stored column value into a versioned ciphertext
+    pub fn parse(stored: &str) -> AppResult<Self> {
+        let versioned = stored
+            .strip_prefix('v')
+            .and_then(|rest| rest.split_once(':'))
+            .and_then(|(version, payload)| version.parse::<u32>().ok().map(|v| (v, payload)));
+
+        match versioned {
+            Some((key_version, payload)) => Ok(Self {
+                key_version,
+                payload: payload.to_string(),
+            }),
+            None if !stored.is_empty() => Ok(Self {
+                key_version: LEGACY_KEY_VERSION,
+                payload: stored.to_string(),
+            }),
+            None => Err(AppError::Encryption("Empty ciphertext".to_string())),
+        }
+    }
+
+    /// Encode for storage in a text column
+    pub fn encode(&self) -> String {
+        format!("v{}:{}", self.key_version, self.payload)
+    }
+}
+
+/// Set of versioned encryption keys with one active key for new writes
+pub struct KeyRing {
+    keys: HashMap<u32, Aes256Gcm>,
+    active_version: u32,
+}
+
+impl KeyRing {
+    /// Build a key ring from configured key material
+    pub fn from_config(config: &EncryptionConfig) -> AppResult<Self> {
+        let mut keys = HashMap::new();
+
+        for (version, encoded) in &config.keys {
+            let bytes = STANDARD.decode(encoded).map_err(|_| {
+                AppError::Config(format!("PII key v{} is not valid base64", version))
+            })?;
+            let cipher = Aes256Gcm::new_from_slice(&bytes).map_err(|_| {
+                AppError::Config(format!("PII key v{} must be 32 bytes", version))
+            })?;
+            keys.insert(*version, cipher);
+        }
+
+        if !keys.contains_key(&config.active_key_version) {
+            return Err(AppError::Config(format!(
+                "Active PII key version {} is not configured",
+                config.active_key_version
+            )));
+        }
+
+        Ok(Self {
+            keys,
+            active_version: config.active_key_version,
+        })
+    }
+
+    /// Version of the key used for new ciphertexts
+    pub fn active_version(&self) -> u32 {
+        self.active_version
+    }
+
+    /// Encrypt a plaintext value under the active key
+    pub fn encrypt(&self, plaintext: &str) -> AppResult<EncryptedField> {
+        let cipher = self.cipher(self.active_version)?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| AppError::Encryption("Failed to encrypt field".to_string()))?;
+
+        let mut combined = nonce.to_vec();
+        combined.extend_from_slice(&ciphertext);
+
+        Ok(EncryptedField {
+            key_version: self.active_version,
+            payload: STANDARD.encode(combined),
+        })
+    }
+
+    /// Decrypt a ciphertext with whichever key version produced it
+    pub fn decrypt(&self, field: &EncryptedField) -> AppResult<String> {
+        let cipher = self.cipher(field.key_version)?;
+        let combined = STANDARD
+            .decode(&field.payload)
+            .map_err(|_| AppError::Encryption("Ciphertext is not valid base64".to_string()))?;
+
+        if combined.len() < NONCE_LEN {
+            return Err(AppError::Encryption("Ciphertext is truncated".to_string()));
+        }
+
+        let (nonce, ciphertext) = combined.split_at(NONCE_LEN);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| AppError::Encryption("Failed to decrypt field".to_string()))?;
+
+        String::from_utf8(plaintext)
+            .map_err(|_| AppError::Encryption("Decrypted field is not UTF-8".to_string()))
+    }
+
+    /// Check whether a ciphertext was produced by a key other than the active one
+    pub fn needs_rotation(&self, field: &EncryptedField) -> bool {
+        field.key_version != self.active_version
+    }
+
+    /// Re-encrypt a ciphertext under the active key, leaving current ones untouched
+    pub fn rotate(&self, field: &EncryptedField) -> AppResult<EncryptedField> {
+        if !self.needs_rotation(field) {
+            return Ok(field.clone());
+        }
+        let plaintext = self.decrypt(field)?;
+        self.encrypt(&plaintext)
+    }
+
+    fn cipher(&self, version: u32) -> AppResult<&Aes256Gcm> {
+        self.keys
+            .get(&version)
+            .ok_or_else(|| AppError::Encryption(format!("Unknown key version {}", version)))
+    }
+}
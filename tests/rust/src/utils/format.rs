@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+
+use crate::models::UserPreferences;
+
+/// Date, time and number conventions of one language
+struct Conventions {
+    date: &'static str,
+    time: &'static str,
+    decimal: char,
+    group: char,
+}
+
+const ENGLISH: Conventions = Conventions {
+    date: "%b %-d, %Y",
+    time: "%-I:%M %p",
+    decimal: '.',
+    group: ',',
+};
+
+/// Conventions by primary language subtag; anything else is formatted as English
+fn conventions(language: &str) -> &'static Conventions {
+    const GERMAN: Conventions = Conventions {
+        date: "%d.%m.%Y",
+        time: "%H:%M",
+        decimal: ',',
+        group: '.',
+    };
+    const FRENCH: Conventions = Conventions {
+        date: "%d/%m/%Y",
+        time: "%H:%M",
+        decimal: ',',
+        group: '\u{202f}',
+    };
+    const SPANISH: Conventions = Conventions {
+        date: "%d/%m/%Y",
+        time: "%H:%M",
+        decimal: ',',
+        group: '.',
+    };
+    const DUTCH: Conventions = Conventions {
+        date: "%d-%m-%Y",
+        time: "%H:%M",
+        decimal: ',',
+        group: '.',
+    };
+    const JAPANESE: Conventions = Conventions {
+        date: "%Y/%m/%d",
+        time: "%H:%M",
+        decimal: '.',
+        group: ',',
+    };
+
+    let primary = language.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+    match primary.as_str() {
+        "de" => &GERMAN,
+        "fr" => &FRENCH,
+        "es" | "it" | "pt" => &SPANISH,
+        "nl" => &DUTCH,
+        "ja" | "zh" => &JAPANESE,
+        _ => &ENGLISH,
+    }
+}
+
+/// Formats dates, times and numbers the way a recipient reads them.
+///
+/// Built from `UserPreferences`: the language picks the date layout and
+/// separators, the IANA timezone (say `Europe/Berlin`) is what times are
+/// shown in. An unknown timezone falls back to UTC rather than failing, so
+/// a stale preference never stops an email from going out.
+pub struct Locale {
+    conventions: &'static Conventions,
+    timezone: Tz,
+}
+
+impl Locale {
+    pub fn new(language: &str, timezone: &str) -> Self {
+        Self {
+            conventions: conventions(language),
+            timezone: timezone.trim().parse().unwrap_or(Tz::UTC),
+        }
+    }
+
+    pub fn for_preferences(preferences: &UserPreferences) -> Self {
+        Self::new(&preferences.language, &preferences.timezone)
+    }
+
+    pub fn date(&self, at: DateTime<Utc>) -> String {
+        at.with_timezone(&self.timezone).format(self.conventions.date).to_string()
+    }
+
+    pub fn time(&self, at: DateTime<Utc>) -> String {
+        at.with_timezone(&self.timezone).format(self.conventions.time).to_string()
+    }
+
+    /// Date and time with the zone abbreviation, e.g. `Oct 14, 2026 3:04 PM CEST`
+    pub fn date_time(&self, at: DateTime<Utc>) -> String {
+        let local = at.with_timezone(&self.timezone);
+        let pattern = format!("{} {} %Z", self.conventions.date, self.conventions.time);
+        local.format(&pattern).to_string()
+    }
+
+    pub fn integer(&self, value: i64) -> String {
+        let digits = value.unsigned_abs().to_string();
+        let grouped = self.group_digits(&digits);
+        if value < 0 {
+            format!("-{}", grouped)
+        } else {
+            grouped
+        }
+    }
+
+    /// `value` rounded to `decimals` places, with grouped thousands
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        let formatted = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+        let mut output = String::with_capacity(formatted.len() + whole.len() / 3 + 1);
+        if value.is_sign_negative() && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            output.push('-');
+        }
+        output.push_str(&self.group_digits(whole));
+        if !fraction.is_empty() {
+            output.push(self.conventions.decimal);
+            output.push_str(fraction);
+        }
+        output
+    }
+
+    fn group_digits(&self, digits: &str) -> String {
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index) % 3 == 0 {
+                grouped.push(self.conventions.group);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+}
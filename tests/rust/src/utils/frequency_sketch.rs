@@ -0,0 +1,127 @@
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Estimated access count of one key
+#[derive(Debug, Clone, Serialize)]
+pub struct HotKey {
+    pub key: String,
+    pub estimated_hits: u64,
+}
+
+/// Count-min sketch with a small top-k table of the heaviest keys.
+///
+/// Memory stays fixed at `width * depth` counters however many distinct
+/// keys are seen; estimates can only overcount. Counters are halved every
+/// `decay_interval` records so the ranking reflects recent traffic rather
+/// than all-time totals.
+pub struct FrequencySketch {
+    counters: Vec<Vec<u32>>,
+    width: usize,
+    top: HashMap<String, u64>,
+    top_capacity: usize,
+    recorded: u64,
+    decay_interval: u64,
+}
+
+impl FrequencySketch {
+    pub fn new(width: usize, depth: usize, top_capacity: usize) -> Self {
+        let width = width.max(16);
+        Self {
+            counters: vec![vec![0; width]; depth.max(1)],
+            width,
+            top: HashMap::with_capacity(top_capacity + 1),
+            top_capacity: top_capacity.max(1),
+            recorded: 0,
+            decay_interval: width as u64 * 10,
+        }
+    }
+
+    /// Count one access and return the key's estimated frequency
+    pub fn record(&mut self, key: &str) -> u64 {
+        let mut estimate = u32::MAX;
+        for row in 0..self.counters.len() {
+            let column = self.column(row, key);
+            let counter = &mut self.counters[row][column];
+            *counter = counter.saturating_add(1);
+            estimate = estimate.min(*counter);
+        }
+        let estimate = estimate as u64;
+        self.track(key, estimate);
+
+        self.recorded += 1;
+        if self.recorded % self.decay_interval == 0 {
+            self.decay();
+        }
+        estimate
+    }
+
+    pub fn estimate(&self, key: &str) -> u64 {
+        (0..self.counters.len())
+            .map(|row| self.counters[row][self.column(row, key)])
+            .min()
+            .unwrap_or(0) as u64
+    }
+
+    /// Heaviest keys first
+    pub fn top(&self, limit: usize) -> Vec<HotKey> {
+        let mut keys: Vec<HotKey> = self
+            .top
+            .iter()
+            .map(|(key, hits)| HotKey {
+                key: key.clone(),
+                estimated_hits: *hits,
+            })
+            .collect();
+        keys.sort_by(|a, b| b.estimated_hits.cmp(&a.estimated_hits).then_with(|| a.key.cmp(&b.key)));
+        keys.truncate(limit);
+        keys
+    }
+
+    /// Total accesses recorded since creation
+    pub fn recorded(&self) -> u64 {
+        self.recorded
+    }
+
+    fn track(&mut self, key: &str, estimate: u64) {
+        if let Some(hits) = self.top.get_mut(key) {
+            *hits = estimate;
+            return;
+        }
+        if self.top.len() < self.top_capacity {
+            self.top.insert(key.to_string(), estimate);
+            return;
+        }
+
+        let coldest = self
+            .top
+            .iter()
+            .min_by_key(|(_, hits)| **hits)
+            .map(|(key, hits)| (key.clone(), *hits));
+        if let Some((coldest_key, coldest_hits)) = coldest {
+            if estimate > coldest_hits {
+                self.top.remove(&coldest_key);
+                self.top.insert(key.to_string(), estimate);
+            }
+        }
+    }
+
+    fn decay(&mut self) {
+        for row in &mut self.counters {
+            for counter in row.iter_mut() {
+                *counter /= 2;
+            }
+        }
+        for hits in self.top.values_mut() {
+            *hits /= 2;
+        }
+    }
+
+    fn column(&self, row: usize, key: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        row.hash(&mut hasher);
+        key.hash(&mut hasher);
+        (hasher.finish() % self.width as u64) as usize
+    }
+}
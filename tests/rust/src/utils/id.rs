@@ -0,0 +1,99 @@
+use rand::RngCore;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::config::{IdConfig, IdStrategy};
+
+/// Largest per-millisecond sequence; once spent, ids borrow the next millisecond
+const MAX_SEQUENCE: u16 = 0x0fff;
+
+static GENERATOR: OnceLock<IdGenerator> = OnceLock::new();
+
+/// Mints ids for new entities.
+///
+/// Every strategy yields a UUID, so ids keep fitting the `UUID` columns and
+/// existing rows keep the random UUIDv4 ids they were created with; nothing
+/// reads meaning into an id beyond what `created_at` tells. UUIDv7 and
+/// Snowflake ids start with a millisecond timestamp followed by a sequence
+/// that counts up within the millisecond, so ids minted by one instance
+/// sort in creation order and inserts append to the primary key index
+/// instead of landing on random pages. Snowflake ids also carry the
+/// instance's node id, keeping them distinct across instances without
+/// relying on the random tail.
+pub struct IdGenerator {
+    strategy: IdStrategy,
+    node_id: u16,
+    /// Millisecond and sequence of the last id minted
+    clock: Mutex<(u64, u16)>,
+}
+
+impl IdGenerator {
+    pub fn new(config: &IdConfig) -> Self {
+        Self {
+            strategy: config.strategy,
+            node_id: config.node_id & 0x03ff,
+            clock: Mutex::new((0, 0)),
+        }
+    }
+
+    /// Make this the generator `new_id` uses; false if one was installed already
+    pub fn install(self) -> bool {
+        GENERATOR.set(self).is_ok()
+    }
+
+    pub fn strategy(&self) -> IdStrategy {
+        self.strategy
+    }
+
+    pub fn generate(&self) -> Uuid {
+        match self.strategy {
+            IdStrategy::UuidV4 => Uuid::new_v4(),
+            IdStrategy::UuidV7 => {
+                let (millis, sequence) = self.tick();
+                let mut bytes = timestamped(millis, 0x7000 | sequence);
+                bytes[8] = 0x80 | (bytes[8] & 0x3f);
+                Uuid::from_bytes(bytes)
+            }
+            IdStrategy::Snowflake => {
+                let (millis, sequence) = self.tick();
+                let mut bytes = timestamped(millis, 0x8000 | sequence);
+                bytes[8] = 0x80 | ((self.node_id >> 8) as u8 & 0x03) | (bytes[8] & 0x3c);
+                bytes[9] = self.node_id as u8;
+                Uuid::from_bytes(bytes)
+            }
+        }
+    }
+
+    /// The millisecond and sequence of the next id; the last is never repeated, even when the clock steps back
+    fn tick(&self) -> (u64, u16) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default();
+        let mut clock = self.clock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (last, sequence) = *clock;
+        *clock = if now > last {
+            (now, 0)
+        } else if sequence < MAX_SEQUENCE {
+            (last, sequence + 1)
+        } else {
+            (last + 1, 0)
+        };
+        *clock
+    }
+}
+
+/// An id for a new entity from the installed generator, or a random UUIDv4 before one is installed
+pub fn new_id() -> Uuid {
+    GENERATOR.get().map_or_else(Uuid::new_v4, IdGenerator::generate)
+}
+
+/// 48-bit big-endian milliseconds, the version and sequence, then random bytes
+fn timestamped(millis: u64, version_and_sequence: u16) -> [u8; 16] {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes[8..]);
+    bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+    bytes[6..8].copy_from_slice(&version_and_sequence.to_be_bytes());
+    bytes
+}
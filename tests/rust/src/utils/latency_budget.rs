@@ -0,0 +1,149 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+tokio::task_local! {
+    static CURRENT: Arc<LatencyBudget>;
+}
+
+/// Where a request spends its time, classified by span name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Auth,
+    Db,
+    Cache,
+    Render,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 4] = [Phase::Auth, Phase::Db, Phase::Cache, Phase::Render];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Auth => "auth",
+            Phase::Db => "db",
+            Phase::Cache => "cache",
+            Phase::Render => "render",
+        }
+    }
+
+    /// Phase a span's time is charged to, if any
+    pub fn of_span(name: &str) -> Option<Phase> {
+        match name {
+            "auth" => Some(Phase::Auth),
+            "db.query" => Some(Phase::Db),
+            "cache.command" => Some(Phase::Cache),
+            "render" => Some(Phase::Render),
+            _ => None,
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Time one request has spent in each phase so far.
+///
+/// Installed in a task-local by the latency middleware and filled in by
+/// `LatencyBudgetLayer` as phase spans close, so repositories and services
+/// need nothing beyond the spans they already emit. Work spawned onto
+/// other tasks is not charged to the request.
+#[derive(Debug)]
+pub struct LatencyBudget {
+    started: Instant,
+    phases: Mutex<[Duration; 4]>,
+}
+
+/// Per-phase totals of a finished request; `other` is handler time outside any phase.
+///
+/// Phases nest: queries made while authorizing count toward both `auth`
+/// and `db`, so the phases can add up to more than `total`.
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyBreakdown {
+    pub total: Duration,
+    pub phases: [(Phase, Duration); 4],
+    pub other: Duration,
+}
+
+impl LatencyBudget {
+    pub fn start() -> Arc<Self> {
+        Arc::new(Self {
+            started: Instant::now(),
+            phases: Mutex::new([Duration::ZERO; 4]),
+        })
+    }
+
+    /// Run `future` with this budget collecting its phase timings
+    pub async fn scope<F: Future>(self: Arc<Self>, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// Charge `elapsed` to the budget of the request the calling task is serving, if any
+    pub fn charge(phase: Phase, elapsed: Duration) {
+        let _ = CURRENT.try_with(|budget| {
+            budget.phases.lock().unwrap_or_else(|e| e.into_inner())[phase.index()] += elapsed;
+        });
+    }
+
+    pub fn breakdown(&self) -> LatencyBreakdown {
+        let total = self.started.elapsed();
+        let spent = *self.phases.lock().unwrap_or_else(|e| e.into_inner());
+        let phases = Phase::ALL.map(|phase| (phase, spent[phase.index()]));
+        let charged: Duration = spent.iter().sum();
+        LatencyBreakdown {
+            total,
+            phases,
+            other: total.saturating_sub(charged),
+        }
+    }
+}
+
+/// Start time of an open phase span
+struct PhaseTiming {
+    phase: Phase,
+    opened: Instant,
+}
+
+/// Tracing layer timing phase spans into the current request's `LatencyBudget`.
+///
+/// Only the outermost span of a phase is timed, so a cache command issued
+/// from inside another cache command is not counted twice.
+pub struct LatencyBudgetLayer;
+
+impl<S> Layer<S> for LatencyBudgetLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(phase) = Phase::of_span(attrs.metadata().name()) else {
+            return;
+        };
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let nested = span
+            .scope()
+            .skip(1)
+            .any(|ancestor| Phase::of_span(ancestor.name()) == Some(phase));
+        if !nested {
+            span.extensions_mut().insert(PhaseTiming {
+                phase,
+                opened: Instant::now(),
+            });
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        if let Some(timing) = span.extensions_mut().remove::<PhaseTiming>() {
+            LatencyBudget::charge(timing.phase, timing.opened.elapsed());
+        }
+    }
+}
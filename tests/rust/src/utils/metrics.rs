@@ -0,0 +1,313 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::shutdown_report::MetricsSnapshot;
+use super::Logger;
+use crate::models::AppResult;
+
+/// Label value every label of a metric collapses to once its cap is reached
+pub const OVERFLOW_LABEL_VALUE: &str = "other";
+
+/// Label name/value pairs qualifying a metric, e.g. `&[("channel", "email")]`
+pub type Labels<'a> = &'a [(&'a str, &'a str)];
+
+/// Counters the alerting gauges are derived from, summed over all their label sets
+const DERIVED_INPUTS: [&str; 4] = ["http.requests", "http.request.errors", "notifications.failed", "auth.lockouts"];
+
+/// In-process metrics registry for counters, gauges and duration samples.
+///
+/// Labelled series are capped per metric: once a metric has seen
+/// `max_label_sets` distinct label combinations, further combinations are
+/// folded into a single series with every label set to `other`, so a label
+/// fed from unbounded input such as user ids cannot explode the registry
+/// or the scraper behind it.
+pub struct Metrics {
+    counters: RwLock<HashMap<String, u64>>,
+    gauges: RwLock<HashMap<String, f64>>,
+    durations: RwLock<HashMap<String, Vec<Duration>>>,
+    cardinality: Mutex<CardinalityGuard>,
+    /// Counter totals at the previous derivation, which the next one takes differences against
+    derived_baseline: Mutex<Option<(Instant, Vec<u64>)>>,
+    /// Requests currently being served
+    in_flight: AtomicUsize,
+    logger: Arc<Logger>,
+}
+
+/// Counts a request as in flight until dropped
+pub struct InFlightRequest<'a> {
+    metrics: &'a Metrics,
+}
+
+impl Drop for InFlightRequest<'_> {
+    fn drop(&mut self) {
+        self.metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    /// Create an empty metrics registry
+    pub fn new(max_label_sets: usize, logger: Arc<Logger>) -> AppResult<Self> {
+        Ok(Self {
+            counters: RwLock::new(HashMap::new()),
+            gauges: RwLock::new(HashMap::new()),
+            durations: RwLock::new(HashMap::new()),
+            cardinality: Mutex::new(CardinalityGuard::new(max_label_sets.max(1))),
+            derived_baseline: Mutex::new(None),
+            in_flight: AtomicUsize::new(0),
+            logger,
+        })
+    }
+
+    /// Increment a named counter by one
+    pub async fn increment_counter(&self, name: &str) -> AppResult<()> {
+        self.add_to_counter(name, 1).await
+    }
+
+    /// Increment a named counter by an arbitrary amount
+    pub async fn add_to_counter(&self, name: &str, value: u64) -> AppResult<()> {
+        self.add_to_labeled_counter(name, &[], value).await
+    }
+
+    /// Set a gauge to an absolute value
+    pub async fn set_gauge(&self, name: &str, value: f64) -> AppResult<()> {
+        self.set_labeled_gauge(name, &[], value).await
+    }
+
+    /// Record a duration sample for a timed operation
+    pub async fn record_duration(&self, name: &str, duration: Duration) -> AppResult<()> {
+        self.record_labeled_duration(name, &[], duration).await
+    }
+
+    pub async fn increment_labeled_counter(&self, name: &str, labels: Labels<'_>) -> AppResult<()> {
+        self.add_to_labeled_counter(name, labels, 1).await
+    }
+
+    pub async fn add_to_labeled_counter(&self, name: &str, labels: Labels<'_>, value: u64) -> AppResult<()> {
+        let series = self.series(name, labels).await;
+        let mut counters = self.counters.write().await;
+        *counters.entry(series).or_insert(0) += value;
+        Ok(())
+    }
+
+    pub async fn set_labeled_gauge(&self, name: &str, labels: Labels<'_>, value: f64) -> AppResult<()> {
+        let series = self.series(name, labels).await;
+        self.gauges.write().await.insert(series, value);
+        Ok(())
+    }
+
+    pub async fn record_labeled_duration(
+        &self,
+        name: &str,
+        labels: Labels<'_>,
+        duration: Duration,
+    ) -> AppResult<()> {
+        let series = self.series(name, labels).await;
+        let mut durations = self.durations.write().await;
+        durations.entry(series).or_default().push(duration);
+        Ok(())
+    }
+
+    /// Current value of a counter, zero if it was never incremented
+    pub async fn counter(&self, name: &str) -> u64 {
+        self.labeled_counter(name, &[]).await
+    }
+
+    pub async fn labeled_counter(&self, name: &str, labels: Labels<'_>) -> u64 {
+        let series = series_key(name, labels);
+        self.counters.read().await.get(&series).copied().unwrap_or(0)
+    }
+
+    /// Current value of a gauge, if it has been set
+    pub async fn gauge(&self, name: &str) -> Option<f64> {
+        self.gauges.read().await.get(name).copied()
+    }
+
+    /// Sum of a counter across every label set it was recorded with
+    pub async fn counter_total(&self, name: &str) -> u64 {
+        let labeled = format!("{}{{", name);
+        self.counters
+            .read()
+            .await
+            .iter()
+            .filter(|(series, _)| series.as_str() == name || series.starts_with(&labeled))
+            .map(|(_, value)| value)
+            .sum()
+    }
+
+    /// Recompute the `alerts.*` gauges over the window since the previous call.
+    ///
+    /// Alerting rules can then fire on plain thresholds instead of rate
+    /// arithmetic over raw counters:
+    /// - `alerts.http_error_rate`: share of requests answered with a 5xx
+    /// - `alerts.notification_backlog`: notifications queued for delivery right now
+    /// - `alerts.dead_letters`: deliveries that failed for good during the window
+    /// - `alerts.lockout_rate`: accounts locked by failed sign-ins, per minute
+    ///
+    /// The first call only records a baseline for the rates.
+    pub async fn refresh_derived(&self) -> AppResult<()> {
+        let mut totals = Vec::with_capacity(DERIVED_INPUTS.len());
+        for name in DERIVED_INPUTS {
+            totals.push(self.counter_total(name).await);
+        }
+        let now = Instant::now();
+        let previous = self
+            .derived_baseline
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace((now, totals.clone()));
+
+        self.set_gauge(
+            "alerts.notification_backlog",
+            self.gauge("notifications.queue_depth").await.unwrap_or(0.0),
+        )
+        .await?;
+        let Some((since, before)) = previous else {
+            return Ok(());
+        };
+
+        let delta: Vec<f64> = totals
+            .iter()
+            .zip(&before)
+            .map(|(now, before)| now.saturating_sub(*before) as f64)
+            .collect();
+        let (requests, errors, dead_letters, lockouts) = (delta[0], delta[1], delta[2], delta[3]);
+        let minutes = (now - since).as_secs_f64() / 60.0;
+
+        self.set_gauge("alerts.http_error_rate", if requests > 0.0 { errors / requests } else { 0.0 })
+            .await?;
+        self.set_gauge("alerts.dead_letters", dead_letters).await?;
+        self.set_gauge("alerts.lockout_rate", if minutes > 0.0 { lockouts / minutes } else { 0.0 })
+            .await?;
+        Ok(())
+    }
+
+    /// Refresh the derived gauges every `interval` until shutdown
+    pub fn spawn_derived(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = self.refresh_derived().await {
+                    self.logger.error(&format!("Failed to refresh derived metrics: {}", e));
+                }
+            }
+        })
+    }
+
+    /// Count a request as in flight for as long as the returned guard lives
+    pub fn track_request(&self) -> InFlightRequest<'_> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightRequest { metrics: self }
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Refresh the derived gauges one last time and return every counter and gauge
+    pub async fn flush(&self) -> AppResult<MetricsSnapshot> {
+        self.refresh_derived().await?;
+        Ok(MetricsSnapshot {
+            counters: self.counters.read().await.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+            gauges: self.gauges.read().await.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+        })
+    }
+
+    /// Metrics whose label sets have overflowed, sorted by name
+    pub fn cardinality_offenders(&self) -> Vec<String> {
+        let guard = self.cardinality.lock().unwrap_or_else(|e| e.into_inner());
+        let mut offenders: Vec<String> = guard.offenders.iter().cloned().collect();
+        offenders.sort();
+        offenders
+    }
+
+    /// Resolve the series a sample is recorded under, folding it into the
+    /// overflow series once the metric is over its cap
+    async fn series(&self, name: &str, labels: Labels<'_>) -> String {
+        if labels.is_empty() {
+            return name.to_string();
+        }
+
+        let series = series_key(name, labels);
+        let admitted = {
+            let mut guard = self.cardinality.lock().unwrap_or_else(|e| e.into_inner());
+            guard.admit(name, &series)
+        };
+        match admitted {
+            Admission::Admitted => series,
+            Admission::Overflow { first } => {
+                if first {
+                    self.logger.warn(&format!(
+                        "Metric {} exceeded its label cardinality cap; new label sets are recorded as '{}' (first dropped: {})",
+                        name, OVERFLOW_LABEL_VALUE, series
+                    ));
+                }
+                let mut counters = self.counters.write().await;
+                *counters
+                    .entry(series_key("metrics.cardinality_overflow", &[("metric", name)]))
+                    .or_insert(0) += 1;
+                let overflow: Vec<(&str, &str)> = labels.iter().map(|(key, _)| (*key, OVERFLOW_LABEL_VALUE)).collect();
+                series_key(name, &overflow)
+            }
+        }
+    }
+}
+
+enum Admission {
+    Admitted,
+    /// `first` is set the first time the metric overflows, so it is logged once
+    Overflow { first: bool },
+}
+
+/// Distinct label sets seen per metric
+struct CardinalityGuard {
+    max_label_sets: usize,
+    series: HashMap<String, HashSet<String>>,
+    offenders: HashSet<String>,
+}
+
+impl CardinalityGuard {
+    fn new(max_label_sets: usize) -> Self {
+        Self {
+            max_label_sets,
+            series: HashMap::new(),
+            offenders: HashSet::new(),
+        }
+    }
+
+    fn admit(&mut self, name: &str, series: &str) -> Admission {
+        let seen = self.series.entry(name.to_string()).or_default();
+        if seen.contains(series) {
+            return Admission::Admitted;
+        }
+        if seen.len() < self.max_label_sets {
+            seen.insert(series.to_string());
+            return Admission::Admitted;
+        }
+        Admission::Overflow {
+            first: self.offenders.insert(name.to_string()),
+        }
+    }
+}
+
+/// `name{a="x",b="y"}` with labels sorted by name, so call-site order does not matter
+fn series_key(name: &str, labels: Labels<'_>) -> String {
+    if labels.is_empty() {
+        return name.to_string();
+    }
+    let mut labels = labels.to_vec();
+    labels.sort_by(|a, b| a.0.cmp(b.0));
+    let rendered: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| format!("{}=\"{}\"", key, value.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    format!("{}{{{}}}", name, rendered.join(","))
+}
@@ -0,0 +1,39 @@
+pub mod logger;
+pub mod metrics;
+pub mod encryption;
+pub mod anonymizer;
+pub mod pdf;
+pub mod build_info;
+pub mod template;
+pub mod frequency_sketch;
+pub mod request_context;
+pub mod tenant_scope;
+pub mod latency_budget;
+pub mod password;
+pub mod signed_url;
+pub mod deprecation;
+pub mod format;
+pub mod shutdown_report;
+pub mod schema;
+pub mod id;
+pub mod trace_sampling;
+
+pub use logger::Logger;
+pub use metrics::{InFlightRequest, Labels, Metrics};
+pub use encryption::{EncryptedField, KeyRing};
+pub use anonymizer::Anonymizer;
+pub use build_info::{AppInfo, BuildInfo};
+pub use frequency_sketch::{FrequencySketch, HotKey};
+pub use request_context::RequestContext;
+pub use tenant_scope::TenantScope;
+pub use password::{
+    Argon2Hasher, BcryptHasher, PasswordAlgorithm, PasswordHasher, PasswordHashing, PasswordVerification, ScryptHasher,
+};
+pub use signed_url::UrlSigner;
+pub use deprecation::{DeprecatedField, Deprecations};
+pub use format::Locale;
+pub use shutdown_report::{MetricsSnapshot, ServiceShutdown, ShutdownReport};
+pub use schema::{describe_constraint, ColumnSpec, IndexSpec, LintFinding, Schema, SchemaDrift, TableSpec};
+pub use id::{new_id, IdGenerator};
+pub use latency_budget::{LatencyBreakdown, LatencyBudget, LatencyBudgetLayer, Phase};
+pub use trace_sampling::{SampledSpans, TraceDecision};
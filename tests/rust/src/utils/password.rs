@@ -0,0 +1,183 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher as PhcHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use scrypt::Scrypt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::models::{AppError, AppResult};
+
+/// Work factor for new bcrypt hashes
+const BCRYPT_COST: u32 = 12;
+
+/// Password hashing schemes understood by the service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordAlgorithm {
+    Argon2,
+    Bcrypt,
+    Scrypt,
+}
+
+impl PasswordAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PasswordAlgorithm::Argon2 => "argon2",
+            PasswordAlgorithm::Bcrypt => "bcrypt",
+            PasswordAlgorithm::Scrypt => "scrypt",
+        }
+    }
+
+    /// Scheme a stored hash was produced with, read from its `$id$` prefix
+    pub fn of_hash(stored: &str) -> Option<Self> {
+        let id = stored.strip_prefix('$')?.split('$').next()?;
+        match id {
+            "argon2id" | "argon2i" | "argon2d" => Some(PasswordAlgorithm::Argon2),
+            "2a" | "2b" | "2x" | "2y" => Some(PasswordAlgorithm::Bcrypt),
+            "scrypt" => Some(PasswordAlgorithm::Scrypt),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for PasswordAlgorithm {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "argon2" => Ok(PasswordAlgorithm::Argon2),
+            "bcrypt" => Ok(PasswordAlgorithm::Bcrypt),
+            "scrypt" => Ok(PasswordAlgorithm::Scrypt),
+            other => Err(format!("Unknown password algorithm: {}", other)),
+        }
+    }
+}
+
+/// One password hashing scheme.
+///
+/// Hashes are self-describing: argon2 and scrypt use PHC strings and bcrypt
+/// its modular crypt format, so the scheme of a stored hash is always
+/// recoverable from the hash itself.
+pub trait PasswordHasher: Send + Sync {
+    fn algorithm(&self) -> PasswordAlgorithm;
+    fn hash(&self, password: &str) -> AppResult<String>;
+    /// Whether `password` matches; malformed hashes never match
+    fn verify(&self, password: &str, stored: &str) -> bool;
+}
+
+pub struct Argon2Hasher;
+
+impl PasswordHasher for Argon2Hasher {
+    fn algorithm(&self) -> PasswordAlgorithm {
+        PasswordAlgorithm::Argon2
+    }
+
+    fn hash(&self, password: &str) -> AppResult<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))
+    }
+
+    fn verify(&self, password: &str, stored: &str) -> bool {
+        PasswordHash::new(stored)
+            .map(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+            .unwrap_or(false)
+    }
+}
+
+pub struct BcryptHasher;
+
+impl PasswordHasher for BcryptHasher {
+    fn algorithm(&self) -> PasswordAlgorithm {
+        PasswordAlgorithm::Bcrypt
+    }
+
+    fn hash(&self, password: &str) -> AppResult<String> {
+        bcrypt::hash(password, BCRYPT_COST).map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))
+    }
+
+    fn verify(&self, password: &str, stored: &str) -> bool {
+        bcrypt::verify(password, stored).unwrap_or(false)
+    }
+}
+
+pub struct ScryptHasher;
+
+impl PasswordHasher for ScryptHasher {
+    fn algorithm(&self) -> PasswordAlgorithm {
+        PasswordAlgorithm::Scrypt
+    }
+
+    fn hash(&self, password: &str) -> AppResult<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        Scrypt
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))
+    }
+
+    fn verify(&self, password: &str, stored: &str) -> bool {
+        PasswordHash::new(stored)
+            .map(|hash| Scrypt.verify_password(password.as_bytes(), &hash).is_ok())
+            .unwrap_or(false)
+    }
+}
+
+/// Outcome of checking a password against a stored hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordVerification {
+    Invalid,
+    /// `needs_rehash` is set when the hash uses a scheme other than the configured one
+    Valid { needs_rehash: bool },
+}
+
+/// Hashes new passwords with the configured scheme and verifies stored
+/// hashes of any supported scheme, so the configured scheme can change
+/// while older hashes are migrated as their owners sign in.
+#[derive(Clone)]
+pub struct PasswordHashing {
+    current: Arc<dyn PasswordHasher>,
+}
+
+impl PasswordHashing {
+    pub fn new(algorithm: PasswordAlgorithm) -> Self {
+        Self {
+            current: hasher_for(algorithm),
+        }
+    }
+
+    pub fn algorithm(&self) -> PasswordAlgorithm {
+        self.current.algorithm()
+    }
+
+    pub fn hash(&self, password: &str) -> AppResult<String> {
+        self.current.hash(password)
+    }
+
+    pub fn verify(&self, password: &str, stored: &str) -> PasswordVerification {
+        let Some(algorithm) = PasswordAlgorithm::of_hash(stored) else {
+            return PasswordVerification::Invalid;
+        };
+        if hasher_for(algorithm).verify(password, stored) {
+            PasswordVerification::Valid {
+                needs_rehash: algorithm != self.current.algorithm(),
+            }
+        } else {
+            PasswordVerification::Invalid
+        }
+    }
+
+    /// `verify` without the rehash hint
+    pub fn matches(&self, password: &str, stored: &str) -> bool {
+        matches!(self.verify(password, stored), PasswordVerification::Valid { .. })
+    }
+}
+
+fn hasher_for(algorithm: PasswordAlgorithm) -> Arc<dyn PasswordHasher> {
+    match algorithm {
+        PasswordAlgorithm::Argon2 => Arc::new(Argon2Hasher),
+        PasswordAlgorithm::Bcrypt => Arc::new(BcryptHasher),
+        PasswordAlgorithm::Scrypt => Arc::new(ScryptHasher),
+    }
+}
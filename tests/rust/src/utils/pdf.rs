@@ -0,0 +1,91 @@
+//! Minimal PDF writer for plain-text documents such as reports.
+//!
+//! Produces PDF 1.4 files using the built-in Helvetica font, one line of
+//! text per entry, paginated on US Letter pages. Good enough for tabular
+//! summaries without pulling in a full layout engine.
+
+const PAGE_WIDTH: u32 = 612;
+const PAGE_HEIGHT: u32 = 792;
+const MARGIN: u32 = 50;
+const FONT_SIZE: u32 = 10;
+const LINE_HEIGHT: u32 = 14;
+
+/// Render a title and lines of text into a PDF document
+pub fn render_text(title: &str, lines: &[String]) -> Vec<u8> {
+    let lines_per_page = ((PAGE_HEIGHT - 2 * MARGIN) / LINE_HEIGHT) as usize - 2;
+    let pages: Vec<&[String]> = if lines.is_empty() {
+        vec![&[]]
+    } else {
+        lines.chunks(lines_per_page).collect()
+    };
+
+    // Objects: 1 catalog, 2 page tree, 3 font, then a page and content stream per page
+    let mut objects: Vec<String> = Vec::new();
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 4 + i * 2).collect();
+
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+    objects.push(format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" "),
+        pages.len()
+    ));
+    objects.push("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string());
+
+    for (index, page_lines) in pages.iter().enumerate() {
+        let mut stream = format!(
+            "BT /F1 {} Tf {} TL {} {} Td",
+            FONT_SIZE + 4,
+            LINE_HEIGHT,
+            MARGIN,
+            PAGE_HEIGHT - MARGIN
+        );
+        stream.push_str(&format!(" ({}) Tj /F1 {} Tf T* T*", escape(title), FONT_SIZE));
+        for line in page_lines.iter() {
+            stream.push_str(&format!(" ({}) Tj T*", escape(line)));
+        }
+        stream.push_str(&format!(" ({}) Tj ET", escape(&format!("Page {} of {}", index + 1, pages.len()))));
+
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            page_ids[index] + 1
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}\nendstream", stream.len(), stream));
+    }
+
+    let mut output = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(output.len());
+        output.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", index + 1, object).as_bytes());
+    }
+
+    let xref_offset = output.len();
+    output.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        output.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    output.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        )
+        .as_bytes(),
+    );
+
+    output
+}
+
+/// Escape text for a PDF literal string, dropping characters Helvetica cannot show
+fn escape(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_ascii() && !c.is_ascii_control())
+        .flat_map(|c| match c {
+            '(' | ')' | '\\' => vec!['\\', c],
+            _ => vec![c],
+        })
+        .collect()
+}
@@ -0,0 +1,68 @@
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::models::{AppError, AppResult};
+
+tokio::task_local! {
+    static CURRENT: RequestContext;
+}
+
+/// Per-request execution context, installed by middleware for the duration
+/// of a request.
+///
+/// It lives in a task-local rather than being passed through every call,
+/// so services and repositories can check the deadline without changing
+/// their signatures. Work spawned onto other tasks, such as notification
+/// dispatch, is deliberately outside the request and never sees it.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestContext {
+    pub deadline: Instant,
+}
+
+impl RequestContext {
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    /// Context of the request the calling task is serving, if any
+    pub fn current() -> Option<RequestContext> {
+        CURRENT.try_with(|context| *context).ok()
+    }
+
+    /// Run `future` with this context installed
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    /// Fail once the current request's deadline has passed; always succeeds outside a request
+    pub fn check(operation: &str) -> AppResult<()> {
+        match Self::current() {
+            Some(context) if context.remaining().is_zero() => Err(deadline_exceeded(operation)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Run `future`, abandoning it when the current request's deadline passes.
+    ///
+    /// Dropping an in-flight query releases its pool connection, so a slow
+    /// query for a client that already gave up stops holding one.
+    pub async fn bounded<T>(operation: &str, future: impl Future<Output = AppResult<T>>) -> AppResult<T> {
+        match Self::current() {
+            Some(context) => tokio::time::timeout_at(context.deadline, future)
+                .await
+                .map_err(|_| deadline_exceeded(operation))?,
+            None => future.await,
+        }
+    }
+}
+
+fn deadline_exceeded(operation: &str) -> AppError {
+    AppError::DeadlineExceeded(operation.to_string())
+}
@@ -0,0 +1,739 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Column types as Postgres reports them, from the spellings migrations use
+fn normalize_type(raw: &str) -> String {
+    if let Some(element) = raw.strip_suffix("[]") {
+        return format!("{}[]", normalize_type(element));
+    }
+    if let Some(length) = raw.strip_prefix("varchar").filter(|rest| rest.starts_with('(')) {
+        return format!("character varying{}", length);
+    }
+    match raw {
+        "timestamptz" | "timestamp with time zone" => "timestamp with time zone",
+        "timestamp" | "timestamp without time zone" => "timestamp without time zone",
+        "bigint" | "int8" | "bigserial" | "serial8" => "bigint",
+        "integer" | "int" | "int4" | "serial" | "serial4" => "integer",
+        "smallint" | "int2" | "smallserial" => "smallint",
+        "boolean" | "bool" => "boolean",
+        "double precision" | "float8" => "double precision",
+        "real" | "float4" => "real",
+        "varchar" => "character varying",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Words that end a column's type in its definition
+const COLUMN_KEYWORDS: [&str; 10] = [
+    "not", "null", "default", "primary", "references", "unique", "check", "generated", "constraint", "collate",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnSpec {
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+impl fmt::Display for ColumnSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nullability = if self.nullable { "null" } else { "not null" };
+        write!(f, "{} {}", self.data_type, nullability)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexSpec {
+    pub table: String,
+    pub unique: bool,
+    pub method: String,
+    /// Indexed columns or expressions, as written between the parentheses
+    pub columns: String,
+    pub partial: bool,
+}
+
+impl IndexSpec {
+    /// Postgres prints expressions back with its own spacing and parentheses
+    fn column_key(&self) -> String {
+        self.columns
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '(' && *c != ')')
+            .collect()
+    }
+}
+
+impl PartialEq for IndexSpec {
+    fn eq(&self, other: &Self) -> bool {
+        self.table == other.table
+            && self.unique == other.unique
+            && self.method == other.method
+            && self.partial == other.partial
+            && self.column_key() == other.column_key()
+    }
+}
+
+impl fmt::Display for IndexSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unique = if self.unique { "unique " } else { "" };
+        let partial = if self.partial { " where ..." } else { "" };
+        write!(f, "{}index on {} using {} ({}){}", unique, self.table, self.method, self.columns, partial)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TableSpec {
+    pub columns: BTreeMap<String, ColumnSpec>,
+    /// Described by kind and columns rather than name, e.g. `foreign key (user_id) references users (id)`
+    pub constraints: Vec<String>,
+    /// Created by a migration, so every column and constraint of it is known;
+    /// tables migrations only alter may have more than they describe
+    pub complete: bool,
+}
+
+/// Tables and indexes of one database schema
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    pub tables: BTreeMap<String, TableSpec>,
+    pub indexes: BTreeMap<String, IndexSpec>,
+}
+
+/// A migration statement that is unsafe to re-run or could not be checked
+#[derive(Debug, Clone)]
+pub struct LintFinding {
+    pub migration: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.migration, self.message)
+    }
+}
+
+/// One difference between the schema migrations build and a live one
+#[derive(Debug, Clone)]
+pub struct SchemaDrift {
+    /// e.g. `column users.region`
+    pub object: String,
+    pub expected: Option<String>,
+    pub found: Option<String>,
+}
+
+impl fmt::Display for SchemaDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.expected, &self.found) {
+            (Some(expected), None) => write!(f, "missing {}: expected {}", self.object, expected),
+            (None, Some(found)) => write!(f, "unexpected {}: {}", self.object, found),
+            (Some(expected), Some(found)) => {
+                write!(f, "{} differs: expected {}, found {}", self.object, expected, found)
+            }
+            (None, None) => write!(f, "{}", self.object),
+        }
+    }
+}
+
+/// How a constraint is described on both sides of a comparison
+pub fn describe_constraint(kind: char, columns: &[String], references: Option<(&str, &[String])>) -> String {
+    let columns = columns.join(", ");
+    match (kind, references) {
+        ('p', _) => format!("primary key ({})", columns),
+        ('u', _) => format!("unique ({})", columns),
+        ('f', Some((table, referenced))) => {
+            format!("foreign key ({}) references {} ({})", columns, table, referenced.join(", "))
+        }
+        _ => "check".to_string(),
+    }
+}
+
+impl Schema {
+    /// The schema `migrations` build when applied in order, each given by
+    /// name and SQL, with lint findings for their statements.
+    ///
+    /// Understands the DDL this repository's migrations use; anything else
+    /// is reported as a finding instead of silently ignored.
+    pub fn from_migrations<'a>(migrations: impl IntoIterator<Item = (&'a str, &'a str)>) -> (Self, Vec<LintFinding>) {
+        let mut builder = Builder::default();
+        for (name, sql) in migrations {
+            builder.migration = name.to_string();
+            for statement in statements(sql) {
+                builder.apply(&tokens(&statement));
+            }
+        }
+        (builder.schema, builder.findings)
+    }
+
+    /// The index a `pg_get_indexdef` definition describes, with its name
+    pub fn index_from_definition(definition: &str) -> Option<(String, IndexSpec)> {
+        let statement = statements(definition).into_iter().next()?;
+        parse_index(&tokens(&statement))
+    }
+
+    /// Everything `live` lacks, has in addition or has differently.
+    ///
+    /// Columns, constraints and indexes beyond the ones migrations describe
+    /// are only reported for tables a migration created, since the base
+    /// tables migrations alter predate them.
+    pub fn diff(&self, live: &Schema) -> Vec<SchemaDrift> {
+        let mut drift = Vec::new();
+        for (name, table) in &self.tables {
+            let Some(found) = live.tables.get(name) else {
+                drift.push(SchemaDrift {
+                    object: format!("table {}", name),
+                    expected: Some(format!("{} columns", table.columns.len())),
+                    found: None,
+                });
+                continue;
+            };
+            for (column, spec) in &table.columns {
+                let object = format!("column {}.{}", name, column);
+                match found.columns.get(column) {
+                    None => drift.push(SchemaDrift {
+                        object,
+                        expected: Some(spec.to_string()),
+                        found: None,
+                    }),
+                    Some(found) if found != spec => drift.push(SchemaDrift {
+                        object,
+                        expected: Some(spec.to_string()),
+                        found: Some(found.to_string()),
+                    }),
+                    Some(_) => {}
+                }
+            }
+            for constraint in missing_from(&table.constraints, &found.constraints) {
+                drift.push(SchemaDrift {
+                    object: format!("constraint on {}", name),
+                    expected: Some(constraint),
+                    found: None,
+                });
+            }
+            if !table.complete {
+                continue;
+            }
+            for (column, spec) in found.columns.iter().filter(|(column, _)| !table.columns.contains_key(*column)) {
+                drift.push(SchemaDrift {
+                    object: format!("column {}.{}", name, column),
+                    expected: None,
+                    found: Some(spec.to_string()),
+                });
+            }
+            for constraint in missing_from(&found.constraints, &table.constraints) {
+                drift.push(SchemaDrift {
+                    object: format!("constraint on {}", name),
+                    expected: None,
+                    found: Some(constraint),
+                });
+            }
+        }
+        for name in live.tables.keys().filter(|name| !self.tables.contains_key(*name)) {
+            drift.push(SchemaDrift {
+                object: format!("table {}", name),
+                expected: None,
+                found: Some("not created by any migration".to_string()),
+            });
+        }
+
+        for (name, index) in &self.indexes {
+            let object = format!("index {}", name);
+            match live.indexes.get(name) {
+                None => drift.push(SchemaDrift {
+                    object,
+                    expected: Some(index.to_string()),
+                    found: None,
+                }),
+                Some(found) if found != index => drift.push(SchemaDrift {
+                    object,
+                    expected: Some(index.to_string()),
+                    found: Some(found.to_string()),
+                }),
+                Some(_) => {}
+            }
+        }
+        let created_by_migrations = |table: &str| self.tables.get(table).is_some_and(|table| table.complete);
+        for (name, index) in &live.indexes {
+            if !self.indexes.contains_key(name) && created_by_migrations(&index.table) {
+                drift.push(SchemaDrift {
+                    object: format!("index {}", name),
+                    expected: None,
+                    found: Some(index.to_string()),
+                });
+            }
+        }
+        drift
+    }
+}
+
+/// Entries of `expected` not in `found`, counting duplicates
+fn missing_from(expected: &[String], found: &[String]) -> Vec<String> {
+    let mut remaining = found.to_vec();
+    expected
+        .iter()
+        .filter(|entry| match remaining.iter().position(|candidate| candidate == *entry) {
+            Some(position) => {
+                remaining.swap_remove(position);
+                false
+            }
+            None => true,
+        })
+        .cloned()
+        .collect()
+}
+
+#[derive(Default)]
+struct Builder {
+    schema: Schema,
+    findings: Vec<LintFinding>,
+    migration: String,
+}
+
+impl Builder {
+    fn lint(&mut self, message: String) {
+        self.findings.push(LintFinding {
+            migration: self.migration.clone(),
+            message,
+        });
+    }
+
+    fn not_understood(&mut self, statement: &[String]) {
+        let summary: Vec<&str> = statement.iter().take(6).map(String::as_str).collect();
+        self.lint(format!("statement not understood, its effect is not checked: {} ...", summary.join(" ")));
+    }
+
+    fn apply(&mut self, statement: &[String]) {
+        let word = |index: usize| statement.get(index).map(String::as_str).unwrap_or_default();
+        match (word(0), word(1)) {
+            ("create", "table") => self.create_table(statement),
+            ("create", "index") | ("create", "unique") => self.create_index(statement),
+            ("alter", "table") => self.alter_table(statement),
+            ("drop", "table") => self.drop_table(statement),
+            ("drop", "index") => self.drop_index(statement),
+            // Data changes and permissions leave the structure alone
+            ("insert" | "update" | "delete" | "comment" | "grant" | "revoke", _) => {}
+            _ => self.not_understood(statement),
+        }
+    }
+
+    fn create_table(&mut self, statement: &[String]) {
+        let (if_not_exists, position) = flag(statement, 2, &["if", "not", "exists"]);
+        if !if_not_exists {
+            self.lint("CREATE TABLE without IF NOT EXISTS fails when re-run".to_string());
+        }
+        let (Some(name), Some((body, _))) = (statement.get(position), group(statement, position + 1)) else {
+            return self.not_understood(statement);
+        };
+        let name = identifier(name);
+        if if_not_exists && self.schema.tables.get(&name).is_some_and(|table| table.complete) {
+            return;
+        }
+
+        let mut table = TableSpec {
+            complete: true,
+            ..TableSpec::default()
+        };
+        let mut key_columns = Vec::new();
+        for item in split_top_level(body) {
+            let item = if item.first().map(String::as_str) == Some("constraint") {
+                item.get(2..).unwrap_or_default()
+            } else {
+                item
+            };
+            match item.first().map(String::as_str) {
+                Some("primary" | "unique" | "foreign" | "check") => match table_constraint(item) {
+                    Some((constraint, primary_key)) => {
+                        key_columns.extend(primary_key);
+                        table.constraints.push(constraint);
+                    }
+                    None => self.not_understood(item),
+                },
+                Some("exclude" | "like") => self.not_understood(item),
+                Some(_) => {
+                    let (column, spec, constraints) = parse_column(item);
+                    table.columns.insert(column, spec);
+                    table.constraints.extend(constraints);
+                }
+                None => {}
+            }
+        }
+        for column in key_columns {
+            if let Some(spec) = table.columns.get_mut(&column) {
+                spec.nullable = false;
+            }
+        }
+        self.schema.tables.insert(name, table);
+    }
+
+    fn create_index(&mut self, statement: &[String]) {
+        let Some((name, index)) = parse_index(statement) else {
+            return self.not_understood(statement);
+        };
+        let (_, position) = flag(statement, 1, &["unique"]);
+        let (_, position) = flag(statement, position + 1, &["concurrently"]);
+        let (if_not_exists, _) = flag(statement, position, &["if", "not", "exists"]);
+        if !if_not_exists {
+            self.lint(format!("CREATE INDEX {} without IF NOT EXISTS fails when re-run", name));
+        } else if self.schema.indexes.contains_key(&name) {
+            return;
+        }
+        self.schema.indexes.insert(name, index);
+    }
+
+    fn alter_table(&mut self, statement: &[String]) {
+        let (_, position) = flag(statement, 2, &["if", "exists"]);
+        let (_, position) = flag(statement, position, &["only"]);
+        let Some(name) = statement.get(position) else {
+            return self.not_understood(statement);
+        };
+        let name = identifier(name);
+        for action in split_top_level(&statement[position + 1..]) {
+            self.alter_action(&name, action);
+        }
+    }
+
+    fn alter_action(&mut self, table_name: &str, action: &[String]) {
+        let word = |index: usize| action.get(index).map(String::as_str).unwrap_or_default();
+        match (word(0), word(1)) {
+            ("add", "constraint" | "primary" | "unique" | "foreign" | "check") => {
+                let constraint = if word(1) == "constraint" {
+                    action.get(3..).unwrap_or_default()
+                } else {
+                    &action[1..]
+                };
+                let Some((constraint, primary_key)) = table_constraint(constraint) else {
+                    return self.not_understood(action);
+                };
+                let table = self.table(table_name);
+                for column in primary_key {
+                    if let Some(spec) = table.columns.get_mut(&column) {
+                        spec.nullable = false;
+                    }
+                }
+                table.constraints.push(constraint);
+            }
+            ("add", _) => {
+                let (_, position) = flag(action, 1, &["column"]);
+                let (if_not_exists, position) = flag(action, position, &["if", "not", "exists"]);
+                let definition = &action[position..];
+                let (column, spec, constraints) = parse_column(definition);
+                if !if_not_exists {
+                    self.lint(format!("ADD COLUMN {}.{} without IF NOT EXISTS fails when re-run", table_name, column));
+                }
+                let generated = definition.iter().any(|token| token == "default" || token == "generated");
+                if !spec.nullable && !generated {
+                    self.lint(format!(
+                        "ADD COLUMN {}.{} is NOT NULL without a DEFAULT and fails on tables with rows",
+                        table_name, column
+                    ));
+                }
+                let table = self.table(table_name);
+                if if_not_exists && table.columns.contains_key(&column) {
+                    return;
+                }
+                table.columns.insert(column, spec);
+                table.constraints.extend(constraints);
+            }
+            ("drop", "constraint") => self.not_understood(action),
+            ("drop", _) => {
+                let (_, position) = flag(action, 1, &["column"]);
+                let (if_exists, position) = flag(action, position, &["if", "exists"]);
+                let Some(column) = action.get(position).map(|column| identifier(column)) else {
+                    return self.not_understood(action);
+                };
+                if !if_exists {
+                    self.lint(format!("DROP COLUMN {}.{} without IF EXISTS fails when re-run", table_name, column));
+                }
+                self.table(table_name).columns.remove(&column);
+            }
+            ("alter", _) => {
+                let (_, position) = flag(action, 1, &["column"]);
+                let Some(column) = action.get(position).map(|column| identifier(column)) else {
+                    return self.not_understood(action);
+                };
+                let change: Vec<&str> = action[position + 1..].iter().map(String::as_str).collect();
+                let Some(spec) = self.table(table_name).columns.get_mut(&column) else {
+                    return self.not_understood(action);
+                };
+                match change.as_slice() {
+                    ["set", "not", "null"] => spec.nullable = false,
+                    ["drop", "not", "null"] => spec.nullable = true,
+                    ["set", "default", ..] | ["drop", "default"] => {}
+                    ["type", data_type @ ..] | ["set", "data", "type", data_type @ ..] => {
+                        let end = data_type.iter().position(|token| *token == "using").unwrap_or(data_type.len());
+                        let data_type: Vec<String> = data_type[..end].iter().map(|token| token.to_string()).collect();
+                        spec.data_type = normalize_type(&render(&data_type));
+                    }
+                    _ => self.not_understood(action),
+                }
+            }
+            _ => self.not_understood(action),
+        }
+    }
+
+    fn drop_table(&mut self, statement: &[String]) {
+        let (if_exists, position) = flag(statement, 2, &["if", "exists"]);
+        if !if_exists {
+            self.lint("DROP TABLE without IF EXISTS fails when re-run".to_string());
+        }
+        for name in split_top_level(&statement[position..]).into_iter().filter_map(|item| item.first()) {
+            let name = identifier(name);
+            self.schema.tables.remove(&name);
+            self.schema.indexes.retain(|_, index| index.table != name);
+        }
+    }
+
+    fn drop_index(&mut self, statement: &[String]) {
+        let (_, position) = flag(statement, 2, &["concurrently"]);
+        let (if_exists, position) = flag(statement, position, &["if", "exists"]);
+        if !if_exists {
+            self.lint("DROP INDEX without IF EXISTS fails when re-run".to_string());
+        }
+        for name in split_top_level(&statement[position..]).into_iter().filter_map(|item| item.first()) {
+            self.schema.indexes.remove(&identifier(name));
+        }
+    }
+
+    /// A table migrations alter; created as incomplete when no migration created it
+    fn table(&mut self, name: &str) -> &mut TableSpec {
+        self.schema.tables.entry(name.to_string()).or_default()
+    }
+}
+
+fn parse_index(statement: &[String]) -> Option<(String, IndexSpec)> {
+    let (unique, position) = flag(statement, 1, &["unique"]);
+    if statement.get(position).map(String::as_str) != Some("index") {
+        return None;
+    }
+    let (_, position) = flag(statement, position + 1, &["concurrently"]);
+    let (_, position) = flag(statement, position, &["if", "not", "exists"]);
+    let name = identifier(statement.get(position)?);
+    if statement.get(position + 1).map(String::as_str) != Some("on") {
+        return None;
+    }
+    let (_, position) = flag(statement, position + 2, &["only"]);
+    let table = identifier(statement.get(position)?);
+    let (method, position) = match statement.get(position + 1).map(String::as_str) {
+        Some("using") => (statement.get(position + 2)?.clone(), position + 3),
+        _ => ("btree".to_string(), position + 1),
+    };
+    let (columns, after) = group(statement, position)?;
+    let index = IndexSpec {
+        table,
+        unique,
+        method,
+        columns: render(columns),
+        partial: statement[after..].iter().any(|token| token == "where"),
+    };
+    Some((name, index))
+}
+
+/// A column definition's name, type and nullability, and the constraints it declares inline
+fn parse_column(definition: &[String]) -> (String, ColumnSpec, Vec<String>) {
+    let name = definition.first().map(|name| identifier(name)).unwrap_or_default();
+    let type_end = definition
+        .iter()
+        .skip(1)
+        .position(|token| COLUMN_KEYWORDS.contains(&token.as_str()))
+        .map_or(definition.len(), |position| position + 1);
+    let data_type = normalize_type(&render(definition.get(1..type_end).unwrap_or_default()));
+
+    let mut nullable = true;
+    let mut constraints = Vec::new();
+    let column = std::slice::from_ref(&name);
+    let mut position = type_end;
+    while let Some(token) = definition.get(position) {
+        let next = definition.get(position + 1).map(String::as_str);
+        match (token.as_str(), next) {
+            ("not", Some("null")) => nullable = false,
+            ("primary", Some("key")) => {
+                nullable = false;
+                constraints.push(describe_constraint('p', column, None));
+            }
+            ("unique", _) => constraints.push(describe_constraint('u', column, None)),
+            ("check", _) => constraints.push(describe_constraint('c', column, None)),
+            ("references", Some(table)) => {
+                let table = identifier(table);
+                // A reference without columns points at the primary key, which is `id` throughout
+                let referenced = group(definition, position + 2)
+                    .map(|(columns, _)| column_names(columns))
+                    .unwrap_or_else(|| vec!["id".to_string()]);
+                constraints.push(describe_constraint('f', column, Some((&table, &referenced))));
+            }
+            ("(", _) => {
+                if let Some((_, after)) = group(definition, position) {
+                    position = after;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        position += 1;
+    }
+    (name, ColumnSpec { data_type, nullable }, constraints)
+}
+
+/// A table-level constraint, and the columns it puts a primary key on
+fn table_constraint(item: &[String]) -> Option<(String, Vec<String>)> {
+    match item.first()?.as_str() {
+        "primary" => {
+            let (columns, _) = group(item, 2)?;
+            let columns = column_names(columns);
+            Some((describe_constraint('p', &columns, None), columns))
+        }
+        "unique" => {
+            let (columns, _) = group(item, 1)?;
+            Some((describe_constraint('u', &column_names(columns), None), Vec::new()))
+        }
+        "foreign" => {
+            let (columns, after) = group(item, 2)?;
+            if item.get(after).map(String::as_str) != Some("references") {
+                return None;
+            }
+            let table = identifier(item.get(after + 1)?);
+            let referenced = group(item, after + 2)
+                .map(|(referenced, _)| column_names(referenced))
+                .unwrap_or_else(|| vec!["id".to_string()]);
+            Some((describe_constraint('f', &column_names(columns), Some((&table, &referenced))), Vec::new()))
+        }
+        "check" => Some((describe_constraint('c', &[], None), Vec::new())),
+        _ => None,
+    }
+}
+
+fn column_names(columns: &[String]) -> Vec<String> {
+    split_top_level(columns)
+        .into_iter()
+        .filter_map(|column| column.first().map(|name| identifier(name)))
+        .collect()
+}
+
+/// Whether `words` follow at `position`, and the position after them if they do
+fn flag(statement: &[String], position: usize, words: &[&str]) -> (bool, usize) {
+    let present = statement
+        .get(position..position + words.len())
+        .is_some_and(|found| found.iter().zip(words).all(|(found, word)| found == word));
+    if present {
+        (true, position + words.len())
+    } else {
+        (false, position)
+    }
+}
+
+/// The tokens inside the parentheses opening at `open`, and the position after the closing one
+fn group(tokens: &[String], open: usize) -> Option<(&[String], usize)> {
+    if tokens.get(open).map(String::as_str) != Some("(") {
+        return None;
+    }
+    let mut depth = 0;
+    for (position, token) in tokens.iter().enumerate().skip(open) {
+        match token.as_str() {
+            "(" => depth += 1,
+            ")" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((&tokens[open + 1..position], position + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split at commas outside parentheses
+fn split_top_level(tokens: &[String]) -> Vec<&[String]> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (position, token) in tokens.iter().enumerate() {
+        match token.as_str() {
+            "(" => depth += 1,
+            ")" => depth -= 1,
+            "," if depth == 0 => {
+                items.push(&tokens[start..position]);
+                start = position + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < tokens.len() {
+        items.push(&tokens[start..]);
+    }
+    items
+}
+
+fn render(tokens: &[String]) -> String {
+    tokens
+        .join(" ")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(" ,", ",")
+        .replace(" (", "(")
+}
+
+/// Unquoted and without the default schema, the way names are compared
+fn identifier(token: &str) -> String {
+    let name = token.trim_matches('"');
+    name.strip_prefix("public.").unwrap_or(name).to_string()
+}
+
+/// Statements of a script, lowercased, with comments dropped and string
+/// literals emptied so nothing inside them is mistaken for SQL
+fn statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = sql.chars().peekable();
+    let mut in_literal = false;
+    while let Some(c) = chars.next() {
+        if in_literal {
+            if c == '\'' {
+                in_literal = false;
+                current.push('\'');
+            }
+            continue;
+        }
+        match c {
+            '\'' => {
+                in_literal = true;
+                current.push('\'');
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                current.push(' ');
+            }
+            ';' => statements.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    statements.push(current);
+    statements
+        .into_iter()
+        .map(|statement| statement.trim().to_lowercase())
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
+/// Words and punctuation of a statement, with `::type` casts dropped since
+/// Postgres adds them when it prints definitions back
+fn tokens(statement: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in statement.chars() {
+        match c {
+            '(' | ')' | ',' => {
+                tokens.push(std::mem::take(&mut current));
+                tokens.push(c.to_string());
+            }
+            c if c.is_whitespace() => tokens.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    tokens.push(current);
+    tokens
+        .into_iter()
+        .map(|token| token.split("::").next().unwrap_or_default().to_string())
+        .filter(|token| !token.is_empty())
+        .collect()
+}
@@ -0,0 +1,88 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// How one service's shutdown went
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceShutdown {
+    pub service: &'static str,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Final counters and gauges, flushed to the log as the process exits
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetricsSnapshot {
+    pub counters: BTreeMap<String, u64>,
+    pub gauges: BTreeMap<String, f64>,
+}
+
+/// What happened between the shutdown signal and exit, for post-deploy diagnostics.
+///
+/// Logged once as a single JSON line, so a deploy that stopped slowly or
+/// dropped work can be told apart from a clean one without trawling the
+/// log lines leading up to it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShutdownReport {
+    pub total_ms: u64,
+    /// Requests being served when the signal arrived
+    pub in_flight_at_signal: usize,
+    /// Of those, the ones still unfinished once the grace period ran out
+    pub in_flight_abandoned: usize,
+    /// Background jobs that stopped at a checkpoint within the grace period
+    pub jobs_checkpointed: usize,
+    /// Background jobs aborted after the grace period or that panicked
+    pub jobs_aborted: usize,
+    pub services: Vec<ServiceShutdown>,
+    pub errors: Vec<String>,
+    pub metrics: MetricsSnapshot,
+    #[serde(skip)]
+    started: Instant,
+}
+
+impl ShutdownReport {
+    pub fn start(in_flight_at_signal: usize) -> Self {
+        Self {
+            total_ms: 0,
+            in_flight_at_signal,
+            in_flight_abandoned: 0,
+            jobs_checkpointed: 0,
+            jobs_aborted: 0,
+            services: Vec::new(),
+            errors: Vec::new(),
+            metrics: MetricsSnapshot::default(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Record how long a service took to stop and whether it failed
+    pub fn service<E: std::fmt::Display>(&mut self, service: &'static str, took: Duration, result: Result<(), E>) {
+        let error = result.err().map(|e| e.to_string());
+        if let Some(error) = &error {
+            self.errors.push(format!("{}: {}", service, error));
+        }
+        self.services.push(ServiceShutdown {
+            service,
+            duration_ms: took.as_millis() as u64,
+            error,
+        });
+    }
+
+    pub fn error(&mut self, error: String) {
+        self.errors.push(error);
+    }
+
+    /// Stop the clock; call once everything has shut down
+    pub fn finish(&mut self, metrics: MetricsSnapshot) {
+        self.total_ms = self.started.elapsed().as_millis() as u64;
+        self.metrics = metrics;
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty() && self.jobs_aborted == 0 && self.in_flight_abandoned == 0
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|e| format!("{{\"error\":\"unserializable report: {}\"}}", e))
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::models::{AppError, AppResult};
+
+/// Substitute `{{name}}` placeholders with values from `params`.
+///
+/// Every placeholder must have a value; the error lists all missing names
+/// at once so a template author sees the whole problem in one preview.
+pub fn render(template: &str, params: &HashMap<String, String>) -> AppResult<String> {
+    render_with(template, params, |value| value.to_string())
+}
+
+/// Like `render`, but HTML-escapes substituted values
+pub fn render_html(template: &str, params: &HashMap<String, String>) -> AppResult<String> {
+    render_with(template, params, escape_html)
+}
+
+/// Placeholder names used in a template, in order of first appearance
+pub fn placeholders(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + end].trim().to_string();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    names
+}
+
+fn render_with(
+    template: &str,
+    params: &HashMap<String, String>,
+    encode: impl Fn(&str) -> String,
+) -> AppResult<String> {
+    let missing: Vec<String> = placeholders(template)
+        .into_iter()
+        .filter(|name| !params.contains_key(name))
+        .map(|name| format!("Missing template parameter: {}", name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::Validation(missing));
+    }
+
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + end].trim();
+        output.push_str(&encode(&params[name]));
+        rest = &rest[start + 2 + end + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,28 @@
+use std::future::Future;
+
+use crate::models::TenantContext;
+
+tokio::task_local! {
+    static CURRENT: TenantContext;
+}
+
+/// The tenant the request being served acts for, installed by the tenant
+/// middleware for the duration of the request.
+///
+/// Like `RequestContext` it lives in a task-local, so the user repository
+/// can confine listings to the tenant's users without every caller passing
+/// the tenant down. Work spawned onto other tasks is outside the request and
+/// sees no tenant.
+pub struct TenantScope;
+
+impl TenantScope {
+    /// Tenant of the request the calling task is serving, if any
+    pub fn current() -> Option<TenantContext> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Run `future` on behalf of `tenant`
+    pub async fn scope<F: Future>(tenant: TenantContext, future: F) -> F::Output {
+        CURRENT.scope(tenant, future).await
+    }
+}
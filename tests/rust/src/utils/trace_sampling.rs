@@ -0,0 +1,66 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::subscriber::Interest;
+use tracing::Metadata;
+use tracing_subscriber::layer::{Context, Filter};
+
+tokio::task_local! {
+    static CURRENT: Arc<TraceDecision>;
+}
+
+/// Whether the spans of the request the calling task serves are kept.
+///
+/// Made by the sampling middleware before the request's spans are created
+/// and installed in a task-local for `SampledSpans` to read. A decision can
+/// only be raised, never lowered, so spans already kept stay whole.
+#[derive(Debug)]
+pub struct TraceDecision {
+    sampled: AtomicBool,
+}
+
+impl TraceDecision {
+    pub fn new(sampled: bool) -> Arc<Self> {
+        Arc::new(Self {
+            sampled: AtomicBool::new(sampled),
+        })
+    }
+
+    pub fn is_sampled(&self) -> bool {
+        self.sampled.load(Ordering::Relaxed)
+    }
+
+    /// Keep the request's spans from here on
+    pub fn sample(&self) {
+        self.sampled.store(true, Ordering::Relaxed);
+    }
+
+    /// Run `future` with this decision applying to the spans it creates
+    pub async fn scope<F: Future>(self: Arc<Self>, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// The decision of the calling task; spans outside any request, such as those of jobs, are always kept
+    pub fn current_sampled() -> bool {
+        CURRENT.try_with(|decision| decision.is_sampled()).unwrap_or(true)
+    }
+}
+
+/// Per-layer filter dropping the spans of unsampled requests.
+///
+/// Events always pass, so log lines are written whether or not their
+/// request was sampled; they just lose the span context. Meant for the
+/// layers traces are written or exported by, not for `LatencyBudgetLayer`,
+/// which times every request.
+pub struct SampledSpans;
+
+impl<S> Filter<S> for SampledSpans {
+    fn enabled(&self, metadata: &Metadata<'_>, _: &Context<'_, S>) -> bool {
+        !metadata.is_span() || TraceDecision::current_sampled()
+    }
+
+    /// The answer depends on the request, so it is asked again for every span
+    fn callsite_enabled(&self, _: &'static Metadata<'static>) -> Interest {
+        Interest::sometimes()
+    }
+}
@@ -0,0 +1,106 @@
+//! Checks that need a real PostgreSQL database.
+//!
+//! Each test migrates the database named by `TEST_DATABASE_URL` and passes
+//! without doing anything when it is unset.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crawler_test_rust::config::EncryptionConfig;
+use crawler_test_rust::repositories::contract_tests;
+use crawler_test_rust::repositories::{
+    OutboxRepository, PasswordResetRepository, PostgresUserRepository, UserRepository,
+};
+use crawler_test_rust::utils::KeyRing;
+use crawler_test_rust::{Database, User};
+
+async fn database() -> Option<Arc<Database>> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let database = Database::connect(&url).await.expect("TEST_DATABASE_URL is reachable");
+    database.migrate().await.expect("migrations apply");
+    Some(Arc::new(database))
+}
+
+fn users(database: &Arc<Database>) -> Arc<PostgresUserRepository> {
+    let key_ring = KeyRing::from_config(&EncryptionConfig {
+        keys: vec![(1, STANDARD.encode([7u8; 32]))],
+        active_key_version: 1,
+        rotation_batch_size: 100,
+        rotation_interval: std::time::Duration::from_secs(3600),
+    })
+    .expect("test key is valid");
+    Arc::new(PostgresUserRepository::new(
+        database.clone(),
+        Arc::new(key_ring),
+        Arc::new(OutboxRepository::new(database.clone())),
+    ))
+}
+
+async fn reset_user(repository: &PostgresUserRepository) -> User {
+    let suffix = Uuid::new_v4().simple().to_string();
+    let user = User::new(
+        format!("reset.{}@example.com", suffix),
+        format!("reset_{}", &suffix[..16]),
+        "Reset".to_string(),
+        "Test".to_string(),
+        "reset-test-hash".to_string(),
+    );
+    repository.create(&user).await.expect("user is created")
+}
+
+#[tokio::test]
+async fn postgres_user_repository_conforms() {
+    let Some(database) = database().await else {
+        return;
+    };
+    contract_tests::assert_user_repository_contract(users(&database)).await;
+}
+
+#[tokio::test]
+async fn a_released_reset_token_can_be_redeemed_again() {
+    let Some(database) = database().await else {
+        return;
+    };
+    let repository = users(&database);
+    let user = reset_user(&repository).await;
+    let resets = PasswordResetRepository::new(database);
+    let token_hash = Uuid::new_v4().simple().to_string();
+    resets.issue(user.id, &token_hash, Utc::now() + Duration::hours(1)).await.unwrap();
+
+    let first = resets.redeem(&token_hash).await.unwrap().expect("a fresh token redeems");
+    first.release().await.unwrap();
+    let again = resets.redeem(&token_hash).await.unwrap().expect("a released token redeems");
+    assert_eq!(again.user_id, user.id);
+    again.release().await.unwrap();
+
+    repository.delete(user.id).await.unwrap();
+}
+
+#[tokio::test]
+async fn a_reset_token_is_spent_once_however_many_redeem_it() {
+    let Some(database) = database().await else {
+        return;
+    };
+    let repository = users(&database);
+    let user = reset_user(&repository).await;
+    let resets = Arc::new(PasswordResetRepository::new(database));
+    let token_hash = Uuid::new_v4().simple().to_string();
+    resets.issue(user.id, &token_hash, Utc::now() + Duration::hours(1)).await.unwrap();
+
+    let first = resets.redeem(&token_hash).await.unwrap().expect("a fresh token redeems");
+    // The second use waits on the first one's row lock, then finds the token spent
+    let second = tokio::spawn({
+        let resets = resets.clone();
+        let token_hash = token_hash.clone();
+        async move { resets.redeem(&token_hash).await.map(|redemption| redemption.is_some()) }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    first.commit().await.unwrap();
+    assert!(!second.await.unwrap().unwrap(), "a spent token redeemed twice");
+    assert!(resets.redeem(&token_hash).await.unwrap().is_none());
+
+    repository.delete(user.id).await.unwrap();
+}